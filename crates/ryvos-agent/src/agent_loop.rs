@@ -1,3 +1,5 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -6,10 +8,12 @@ use futures::StreamExt;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
-use ryvos_core::config::AppConfig;
+use ryvos_core::clock::{Clock, SystemClock};
+use ryvos_core::config::{AppConfig, ModelConfig, RepeatedAnswerAction};
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::event::EventBus;
 use ryvos_core::goal::Goal;
+use ryvos_core::overrides::{ConfigOverrides, SamplingOverride};
 use ryvos_core::traits::{LlmClient, SessionStore};
 use ryvos_core::types::*;
 use ryvos_memory::CostStore;
@@ -18,14 +22,19 @@ use ryvos_tools::ToolRegistry;
 use crate::checkpoint::CheckpointStore;
 use crate::context;
 use crate::gate::SecurityGate;
+use crate::guardian::repeats_earlier_answer;
 use crate::guardian::GuardianAction;
 use crate::healing::{reflexion_hint_with_history, FailureJournal, FailureRecord};
 use crate::intelligence::{
-    compact_tool_output, expire_protected_messages, is_flush_complete, memory_flush_prompt,
-    prune_to_budget, reflexion_hint, summarize_and_prune, FailureTracker,
+    compact_tool_output, estimate_context_tokens, expire_protected_messages, is_flush_complete,
+    memory_flush_prompt, pinned_notes_message, plan_message, prime_plan, prune_to_budget,
+    reflexion_hint, scratchpad_message, summarize_and_prune, CallUsage, FailureTracker,
+    PrunePolicy, PRIME_MIN_PROMPT_CHARS,
 };
-use crate::judge::Judge;
+use crate::judge::{Judge, Judgment};
+use crate::metrics::RuntimeMetrics;
 use crate::output_validator::OutputCleaner;
+use crate::session::SessionManager;
 
 /// Accumulator for streaming tool call deltas.
 ///
@@ -40,6 +49,154 @@ struct ToolCallAccumulator {
     input_json: String,
 }
 
+/// Turns in a row with invalid tool input before the run gives up.
+const MAX_INVALID_INPUT_TURNS: usize = 3;
+
+/// Operator hints kept per session while it waits for its next turn.
+const MAX_SESSION_HINTS: usize = 32;
+
+/// Share of the context limit at which a session with compaction off is
+/// warned that it is running out of room.
+const NEAR_CONTEXT_LIMIT_PCT: usize = 90;
+
+/// Phrases providers use when a request does not fit the context window.
+const CONTEXT_OVERFLOW_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context length",
+    "context window",
+    "prompt is too long",
+    "input is too long",
+    "too many tokens",
+    "exceeds the maximum number of tokens",
+];
+
+/// Whether the provider rejected the request for not fitting the model's
+/// context window.
+fn is_context_overflow(e: &RyvosError) -> bool {
+    match e {
+        RyvosError::LlmRequest(msg) | RyvosError::LlmStream(msg) => {
+            let msg = msg.to_ascii_lowercase();
+            CONTEXT_OVERFLOW_MARKERS.iter().any(|m| msg.contains(m))
+        }
+        _ => false,
+    }
+}
+
+/// Tokens the model's context window leaves for input, when its limits are
+/// known.
+fn context_limit(model: &ModelConfig) -> Option<usize> {
+    ryvos_core::models::model_limits(&model.model_id).map(|l| {
+        l.context_window
+            .saturating_sub(model.max_output_tokens() as usize)
+    })
+}
+
+/// What is wrong with a streamed response that is worth retrying: no text
+/// and no tool calls, or tool input that does not parse as JSON. Input cut
+/// off by the token limit is not retried here, since the same request would
+/// be cut off again; the call gets an error result from `parse_tool_input`.
+fn malformed_response(
+    text: &str,
+    tool_calls: &[ToolCallAccumulator],
+    truncated: bool,
+) -> Option<String> {
+    if text.trim().is_empty() && tool_calls.is_empty() {
+        return Some("empty response".to_string());
+    }
+    if truncated {
+        return None;
+    }
+    tool_calls
+        .iter()
+        .find(|tc| {
+            !tc.input_json.trim().is_empty()
+                && serde_json::from_str::<serde_json::Value>(&tc.input_json).is_err()
+        })
+        .map(|tc| format!("malformed JSON input for tool '{}'", tc.name))
+}
+
+/// Parse a streamed tool input. Empty input is a call without arguments.
+/// Invalid JSON is an error for the model instead of a call with null
+/// input; `truncated` says the response stopped at the token limit.
+fn parse_tool_input(
+    tc: &ToolCallAccumulator,
+    truncated: bool,
+) -> std::result::Result<serde_json::Value, String> {
+    if tc.input_json.trim().is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_str(&tc.input_json).map_err(|e| {
+        if truncated {
+            format!(
+                "Your input for '{}' was cut off at the output token limit after {} \
+                 characters, so the tool was not run. Call it again with complete JSON \
+                 input; if the input is large, split the work across smaller calls.",
+                tc.name,
+                tc.input_json.len()
+            )
+        } else {
+            format!(
+                "Your input for '{}' is not valid JSON ({}), so the tool was not run. \
+                 Call it again with valid JSON input.",
+                tc.name, e
+            )
+        }
+    })
+}
+
+/// Republish a sub-agent's events on the parent bus until `done` fires,
+/// then drain whatever the sub-agent published before finishing.
+async fn forward_sub_agent_events(
+    mut rx: tokio::sync::broadcast::Receiver<AgentEvent>,
+    parent: Arc<EventBus>,
+    session_id: SessionId,
+    depth: u32,
+    mut done: tokio::sync::oneshot::Receiver<()>,
+) {
+    use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+    let wrap = |event| AgentEvent::SubAgent {
+        session_id: session_id.clone(),
+        depth,
+        event: Box::new(event),
+    };
+    loop {
+        tokio::select! {
+            biased;
+            received = rx.recv() => match received {
+                Ok(event) => parent.publish(wrap(event)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Sub-agent event forwarder lagged");
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = &mut done => break,
+        }
+    }
+    loop {
+        match rx.try_recv() {
+            Ok(event) => parent.publish(wrap(event)),
+            Err(TryRecvError::Lagged(_)) => continue,
+            Err(_) => return,
+        }
+    }
+}
+
+/// Count the usage of a call made outside the turn loop like a turn's:
+/// in the metrics, and on the bus for the Guardian's budgets.
+fn publish_call_usage(event_bus: &EventBus, metrics: &RuntimeMetrics, usage: CallUsage) {
+    if usage == CallUsage::default() {
+        return;
+    }
+    metrics.record_usage(usage.input_tokens, usage.output_tokens);
+    event_bus.publish(AgentEvent::UsageUpdate {
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        thinking_tokens: usage.thinking_tokens,
+    });
+}
+
 /// The agent runtime: Ryvos's core execution engine.
 ///
 /// Runs a ReAct (Reason + Act) loop where the LLM alternates between
@@ -74,26 +231,56 @@ struct ToolCallAccumulator {
 /// - Guardian sends `CancelRun`: the CancellationToken fires.
 pub struct AgentRuntime {
     config: AppConfig,
-    llm: Arc<dyn LlmClient>,
+    /// Model config and client used for new runs; swapped by `set_model`.
+    model: std::sync::RwLock<ActiveModel>,
     tools: Arc<tokio::sync::RwLock<ToolRegistry>>,
     gate: Option<Arc<SecurityGate>>,
     store: Arc<dyn SessionStore>,
     event_bus: Arc<EventBus>,
     cancel: CancellationToken,
     journal: Option<Arc<FailureJournal>>,
-    guardian_hints: Option<Arc<tokio::sync::Mutex<tokio::sync::mpsc::Receiver<GuardianAction>>>>,
+    /// Hint channel drained at the start of every turn. The sender half is
+    /// shared with the Guardian; operator hints go through `session_hints`.
+    hint_tx: tokio::sync::mpsc::Sender<GuardianAction>,
+    guardian_hints: Arc<tokio::sync::Mutex<tokio::sync::mpsc::Receiver<GuardianAction>>>,
     checkpoint_store: Option<Arc<CheckpointStore>>,
     cost_store: Option<Arc<CostStore>>,
     /// Captured CLI session ID from the last MessageId delta (for session resumption).
     last_message_id: Arc<std::sync::Mutex<Option<String>>>,
     /// Override CLI session ID for the next run (set before calling run()).
     cli_session_override: Arc<std::sync::Mutex<Option<String>>>,
+    /// Sampling for the next run of a session, by session ID.
+    run_sampling: Arc<std::sync::Mutex<HashMap<String, SamplingOverride>>>,
+    /// Operator hints waiting for a session's next turn, by session ID.
+    session_hints: Arc<std::sync::Mutex<HashMap<String, VecDeque<String>>>>,
+    /// Client for `agent.prime_model`, created on first use.
+    prime_llm: std::sync::OnceLock<Arc<dyn LlmClient>>,
+    /// Session summary updates still running after their run, by session ID.
+    summary_updates: Arc<std::sync::Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
     /// Self-reference for sub-agent spawning (set after Arc wrapping).
     pub spawner: Arc<tokio::sync::Mutex<Option<Arc<dyn ryvos_core::types::AgentSpawner>>>>,
     /// OpenViking client for hierarchical memory (set after Arc wrapping if auto-started).
     pub viking_client: Arc<tokio::sync::Mutex<Option<Arc<ryvos_memory::VikingClient>>>>,
     /// Safety memory for self-learning lessons (injected into context each run).
     safety_memory: Option<Arc<crate::safety_memory::SafetyMemory>>,
+    /// Nesting depth of this runtime (0 = top-level, sub-agents count up).
+    depth: u32,
+    /// Per-session state; supplies each session's working directory.
+    sessions: Arc<SessionManager>,
+    /// Per-session changes the agent made to its own config.
+    config_overrides: Arc<ConfigOverrides>,
+    /// Time source for `inject_datetime`.
+    clock: Arc<dyn Clock>,
+    /// Run, tool and token counters for `/status`.
+    metrics: Arc<RuntimeMetrics>,
+    /// Tool definitions with the registry epoch they were built at.
+    tool_defs: std::sync::Mutex<Option<(u64, Arc<Vec<ToolDefinition>>)>>,
+}
+
+/// The model a runtime sends new turns to.
+struct ActiveModel {
+    config: ModelConfig,
+    llm: Arc<dyn LlmClient>,
 }
 
 impl AgentRuntime {
@@ -104,23 +291,41 @@ impl AgentRuntime {
         store: Arc<dyn SessionStore>,
         event_bus: Arc<EventBus>,
     ) -> Self {
+        let (hint_tx, hint_rx) = tokio::sync::mpsc::channel(32);
+        let sessions =
+            Arc::new(SessionManager::new().with_working_dir_roots(config.working_dir_roots()));
+        let config_overrides = Arc::new(ConfigOverrides::new(&config));
         Self {
+            model: std::sync::RwLock::new(ActiveModel {
+                config: config.model.clone(),
+                llm: llm.into(),
+            }),
             config,
-            llm: llm.into(),
             tools,
             gate: None,
             store,
             event_bus,
             cancel: CancellationToken::new(),
             journal: None,
-            guardian_hints: None,
+            hint_tx,
+            guardian_hints: Arc::new(tokio::sync::Mutex::new(hint_rx)),
             checkpoint_store: None,
             cost_store: None,
             last_message_id: Arc::new(std::sync::Mutex::new(None)),
             cli_session_override: Arc::new(std::sync::Mutex::new(None)),
+            run_sampling: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            session_hints: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            prime_llm: std::sync::OnceLock::new(),
+            summary_updates: Arc::new(std::sync::Mutex::new(HashMap::new())),
             spawner: Arc::new(tokio::sync::Mutex::new(None)),
             viking_client: Arc::new(tokio::sync::Mutex::new(None)),
             safety_memory: None,
+            depth: 0,
+            sessions,
+            config_overrides,
+            clock: Arc::new(SystemClock),
+            metrics: Arc::new(RuntimeMetrics::new()),
+            tool_defs: std::sync::Mutex::new(None),
         }
     }
 
@@ -133,23 +338,41 @@ impl AgentRuntime {
         event_bus: Arc<EventBus>,
     ) -> Self {
         let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())); // unused when gate is present
+        let (hint_tx, hint_rx) = tokio::sync::mpsc::channel(32);
+        let sessions =
+            Arc::new(SessionManager::new().with_working_dir_roots(config.working_dir_roots()));
+        let config_overrides = Arc::new(ConfigOverrides::new(&config));
         Self {
+            model: std::sync::RwLock::new(ActiveModel {
+                config: config.model.clone(),
+                llm,
+            }),
             config,
-            llm,
             tools,
             gate: Some(gate),
             store,
             event_bus,
             cancel: CancellationToken::new(),
             journal: None,
-            guardian_hints: None,
+            hint_tx,
+            guardian_hints: Arc::new(tokio::sync::Mutex::new(hint_rx)),
             checkpoint_store: None,
             cost_store: None,
             last_message_id: Arc::new(std::sync::Mutex::new(None)),
             cli_session_override: Arc::new(std::sync::Mutex::new(None)),
+            run_sampling: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            session_hints: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            prime_llm: std::sync::OnceLock::new(),
+            summary_updates: Arc::new(std::sync::Mutex::new(HashMap::new())),
             spawner: Arc::new(tokio::sync::Mutex::new(None)),
             viking_client: Arc::new(tokio::sync::Mutex::new(None)),
             safety_memory: None,
+            depth: 0,
+            sessions,
+            config_overrides,
+            clock: Arc::new(SystemClock),
+            metrics: Arc::new(RuntimeMetrics::new()),
+            tool_defs: std::sync::Mutex::new(None),
         }
     }
 
@@ -158,14 +381,33 @@ impl AgentRuntime {
         self.safety_memory = Some(memory);
     }
 
+    /// Set the time source for the date and time put in the system prompt.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
     /// Set the failure journal for self-healing pattern tracking.
     pub fn set_journal(&mut self, journal: Arc<FailureJournal>) {
         self.journal = Some(journal);
     }
 
-    /// Set the Guardian hint receiver for receiving corrective actions.
-    pub fn set_guardian_hints(&mut self, rx: tokio::sync::mpsc::Receiver<GuardianAction>) {
-        self.guardian_hints = Some(Arc::new(tokio::sync::Mutex::new(rx)));
+    /// Get a sender into the runtime's hint channel.
+    /// Hints sent here are injected as user messages at the start of the next
+    /// turn of whichever run drains them; use [`Self::queue_hint`] to target a session.
+    pub fn hint_sender(&self) -> tokio::sync::mpsc::Sender<GuardianAction> {
+        self.hint_tx.clone()
+    }
+
+    /// Queue `hint` for `session_id`. It is injected as a user message at
+    /// the start of that session's next turn, and never reaches another
+    /// session's run. The oldest hints are dropped past [`MAX_SESSION_HINTS`].
+    pub fn queue_hint(&self, session_id: &SessionId, hint: String) {
+        let mut pending = self.session_hints.lock().unwrap();
+        let queue = pending.entry(session_id.0.clone()).or_default();
+        if queue.len() >= MAX_SESSION_HINTS {
+            queue.pop_front();
+        }
+        queue.push_back(hint);
     }
 
     /// Set the checkpoint store for save/resume support.
@@ -178,6 +420,55 @@ impl AgentRuntime {
         self.cost_store = Some(store);
     }
 
+    /// Share a session manager with the channels and gateway, so working
+    /// directories they set apply to this runtime's runs.
+    pub fn set_session_manager(&mut self, sessions: Arc<SessionManager>) {
+        self.sessions = sessions;
+    }
+
+    /// The session manager supplying per-session working directories.
+    pub fn session_manager(&self) -> &Arc<SessionManager> {
+        &self.sessions
+    }
+
+    /// Share the overrides the `config_set` tool writes, so the agent's
+    /// changes to its own config apply to this runtime's runs.
+    pub fn set_config_overrides(&mut self, overrides: Arc<ConfigOverrides>) {
+        self.config_overrides = overrides;
+    }
+
+    /// The per-session config overrides applied at the start of each run.
+    pub fn config_overrides(&self) -> &Arc<ConfigOverrides> {
+        &self.config_overrides
+    }
+
+    /// The store session history is kept in.
+    pub fn store(&self) -> &Arc<dyn SessionStore> {
+        &self.store
+    }
+
+    /// The registry tool calls are made from: the gate's when there is one.
+    pub fn tools(&self) -> &Arc<tokio::sync::RwLock<ToolRegistry>> {
+        match self.gate {
+            Some(ref gate) => gate.tools_lock(),
+            None => &self.tools,
+        }
+    }
+
+    /// Counters of the runs, tool calls and tokens since startup.
+    pub fn metrics(&self) -> &Arc<RuntimeMetrics> {
+        &self.metrics
+    }
+
+    /// Directory tool calls in `session_id` run in: the session's working
+    /// directory if set, else the process directory.
+    fn working_dir(&self, session_id: &SessionId, workspace: &Path) -> PathBuf {
+        self.sessions
+            .working_dir(session_id)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| workspace.to_path_buf())
+    }
+
     /// Set the OpenViking client for hierarchical memory tools.
     /// Can be called after Arc wrapping (uses interior mutability).
     pub async fn set_viking_client(&self, client: Arc<ryvos_memory::VikingClient>) {
@@ -189,22 +480,281 @@ impl AgentRuntime {
         *self.cli_session_override.lock().unwrap() = id;
     }
 
+    /// Use `sampling` for the next run in `session_id` only. The model
+    /// config, and later runs, are unaffected.
+    pub fn set_run_sampling(&self, session_id: &SessionId, sampling: SamplingOverride) {
+        let mut pending = self.run_sampling.lock().unwrap();
+        if sampling.is_empty() {
+            pending.remove(&session_id.0);
+        } else {
+            pending.insert(session_id.0.clone(), sampling);
+        }
+    }
+
     /// Get the last captured CLI session ID (from MessageId delta).
     pub fn last_message_id(&self) -> Option<String> {
         self.last_message_id.lock().unwrap().clone()
     }
 
+    /// Compact `session_id`'s context at the start of its next run,
+    /// summarizing or pruning everything but the most recent messages
+    /// regardless of the token budget. Runs of other sessions leave the
+    /// request alone.
+    pub fn request_compaction(&self, session_id: &SessionId) {
+        self.sessions.request_compaction(session_id);
+    }
+
+    /// Turn compaction off or back on for `session_id`, recording it in the
+    /// session store so it still holds after a restart.
+    pub async fn set_no_compact(&self, session_id: &SessionId, no_compact: bool) -> Result<()> {
+        self.sessions.set_no_compact(session_id, no_compact);
+        self.store.save_no_compact(session_id, no_compact).await
+    }
+
+    /// Whether compaction is off for `session_id`, in this process or as
+    /// recorded by an earlier one.
+    pub async fn no_compact(&self, session_id: &SessionId) -> Result<bool> {
+        if self.sessions.no_compact(session_id) {
+            return Ok(true);
+        }
+        let stored = self.store.load_no_compact(session_id).await?;
+        if stored {
+            self.sessions.set_no_compact(session_id, true);
+        }
+        Ok(stored)
+    }
+
+    /// Warn that a session with compaction off is close to the model's
+    /// context window, or to the budget for a model whose window is unknown.
+    fn warn_near_context_limit(
+        &self,
+        session_id: &SessionId,
+        messages: &[ChatMessage],
+        model: &ModelConfig,
+        budget: usize,
+    ) {
+        let limit = context_limit(model).unwrap_or(budget);
+        let used: usize = messages
+            .iter()
+            .map(crate::intelligence::estimate_message_tokens)
+            .sum();
+        if used * 100 < limit * NEAR_CONTEXT_LIMIT_PCT {
+            return;
+        }
+        warn!(
+            used,
+            limit, "Context is near the model limit and compaction is off"
+        );
+        self.event_bus.publish(AgentEvent::ContextNearLimit {
+            session_id: session_id.clone(),
+            context_tokens: used as u64,
+            limit_tokens: limit as u64,
+        });
+    }
+
+    /// Prune hard after the provider rejected a request as too long: to half
+    /// the estimated context, or half the model's window if that is smaller,
+    /// since the estimate fell short. Returns how many messages went.
+    async fn emergency_prune(
+        &self,
+        messages: &mut Vec<ChatMessage>,
+        model: &ModelConfig,
+        policy: &PrunePolicy,
+        llm: &dyn LlmClient,
+    ) -> usize {
+        let used: usize = messages
+            .iter()
+            .map(crate::intelligence::estimate_message_tokens)
+            .sum();
+        let target = context_limit(model).unwrap_or(usize::MAX).min(used) / 2;
+        if self.config.agent.enable_summarization {
+            match summarize_and_prune(messages, target, policy, llm, model).await {
+                Ok((pruned, usage)) => {
+                    publish_call_usage(&self.event_bus, &self.metrics, usage);
+                    return pruned;
+                }
+                Err(e) => warn!(error = %e, "Summarization failed, pruning instead"),
+            }
+        }
+        prune_to_budget(messages, target, policy)
+    }
+
+    /// Run `session_id` without tools: the model is sent no tool
+    /// definitions and any tool call it makes anyway is rejected. The
+    /// session's history is unaffected, as are other sessions.
+    pub fn set_no_tools(&self, session_id: &SessionId, no_tools: bool) {
+        self.sessions.set_no_tools(session_id, no_tools);
+    }
+
+    /// Whether `session_id`'s runs are currently made without tools.
+    pub fn no_tools(&self, session_id: &SessionId) -> bool {
+        self.sessions.no_tools(session_id)
+    }
+
     /// Get a cancellation token for this runtime.
     pub fn cancel_token(&self) -> CancellationToken {
         self.cancel.clone()
     }
 
-    /// Get tool definitions (from gate if present, else from registry).
-    async fn tool_definitions(&self) -> Vec<ToolDefinition> {
-        if let Some(ref gate) = self.gate {
-            gate.definitions().await
+    /// The model config and client used for the next run.
+    pub fn active_model(&self) -> (ModelConfig, Arc<dyn LlmClient>) {
+        let active = self.model.read().unwrap();
+        (active.config.clone(), active.llm.clone())
+    }
+
+    /// The model config used for the next run.
+    pub fn model_config(&self) -> ModelConfig {
+        self.model.read().unwrap().config.clone()
+    }
+
+    /// The `fallback_models` from the config this runtime was built with.
+    pub fn fallback_models(&self) -> &[ModelConfig] {
+        &self.config.fallback_models
+    }
+
+    /// The LLM client used for the next run.
+    pub fn llm(&self) -> Arc<dyn LlmClient> {
+        self.model.read().unwrap().llm.clone()
+    }
+
+    /// Count the usage of a call made on the active model outside any run,
+    /// such as an MCP server's sampling request, like a turn's.
+    pub fn record_call_usage(&self, usage: CallUsage) {
+        publish_call_usage(&self.event_bus, &self.metrics, usage);
+    }
+
+    /// Replace the model and client used for subsequent runs.
+    /// A run already in progress keeps the model it started with.
+    pub fn set_model(&self, config: ModelConfig, llm: Arc<dyn LlmClient>) {
+        *self.model.write().unwrap() = ActiveModel { config, llm };
+    }
+
+    /// Switch to `model_id` on `provider` for subsequent runs.
+    ///
+    /// Credentials carry over when the provider is unchanged; otherwise they
+    /// come from a matching `fallback_models` entry, if any. Preset defaults
+    /// are applied before `build` creates the client. Session history is
+    /// untouched, so the conversation continues on the new model.
+    pub fn switch_model<F>(&self, provider: &str, model_id: &str, build: F) -> Result<ModelConfig>
+    where
+        F: FnOnce(&ModelConfig) -> Arc<dyn LlmClient>,
+    {
+        if !ryvos_llm::is_known_provider(provider) {
+            return Err(RyvosError::Config(format!(
+                "unknown provider '{}'",
+                provider
+            )));
+        }
+
+        let current = self.model_config();
+        let mut model = if current.provider == provider {
+            current
+        } else if let Some(fb) = self
+            .config
+            .fallback_models
+            .iter()
+            .find(|fb| fb.provider == provider)
+        {
+            fb.clone()
         } else {
-            self.tools.read().await.definitions()
+            ModelConfig {
+                provider: provider.to_string(),
+                api_key: None,
+                api_keys: vec![],
+                base_url: None,
+                extra_headers: Default::default(),
+                azure_resource: None,
+                azure_deployment: None,
+                azure_api_version: None,
+                aws_region: None,
+                cli_session_id: None,
+                ..current
+            }
+        };
+        model.model_id = model_id.to_string();
+        ryvos_llm::apply_preset_defaults(&mut model);
+
+        let llm = build(&model);
+        self.set_model(model.clone(), llm);
+        Ok(model)
+    }
+
+    /// Build the runtime for a sub-agent at `depth`, gated by
+    /// `security.sub_agent_policy`. It shares this runtime's cancellation
+    /// token and routes nested spawns back through this runtime's spawner.
+    ///
+    /// The sub-agent publishes on a bus of its own so its run boundaries
+    /// don't end ours; [`run_sub_agent`](Self::run_sub_agent) forwards them.
+    /// Approvals and blocks still go through our gate and bus directly.
+    pub async fn sub_agent(&self, depth: u32) -> AgentRuntime {
+        let policy = self.config.security.sub_agent_policy();
+        let gate = match self.gate {
+            Some(ref gate) => gate.with_policy(policy),
+            None => SecurityGate::new(
+                policy,
+                self.tools.clone(),
+                Arc::new(crate::approval::ApprovalBroker::new(self.event_bus.clone())),
+                self.event_bus.clone(),
+            ),
+        };
+        let (model, llm) = self.active_model();
+        let mut sub = AgentRuntime::new_with_gate(
+            AppConfig {
+                model,
+                ..self.config.clone()
+            },
+            llm,
+            Arc::new(gate),
+            self.store.clone(),
+            Arc::new(EventBus::default()),
+        );
+        sub.depth = depth;
+        sub.cancel = self.cancel.clone();
+        sub.journal = self.journal.clone();
+        sub.cost_store = self.cost_store.clone();
+        sub.safety_memory = self.safety_memory.clone();
+        sub.viking_client = self.viking_client.clone();
+        sub.sessions = self.sessions.clone();
+        *sub.spawner.lock().await = self.spawner.lock().await.clone();
+        sub
+    }
+
+    /// Run `prompt` in a fresh sub-agent session at `depth`, publishing
+    /// its events on our bus as they happen, wrapped in
+    /// [`AgentEvent::SubAgent`]. Returns the sub-agent's final text.
+    pub async fn run_sub_agent(&self, prompt: &str, depth: u32) -> Result<String> {
+        let sub = self.sub_agent(depth).await;
+        let session_id = SessionId::new();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        let forwarder = tokio::spawn(forward_sub_agent_events(
+            sub.event_bus.subscribe(),
+            self.event_bus.clone(),
+            session_id.clone(),
+            depth,
+            done_rx,
+        ));
+        let result = sub.run(&session_id, prompt).await;
+        let _ = done_tx.send(());
+        let _ = forwarder.await;
+        result
+    }
+
+    /// Get tool definitions (from gate if present, else from registry).
+    /// Reused until the registry's epoch changes.
+    async fn tool_definitions(&self, session_id: &SessionId) -> Arc<Vec<ToolDefinition>> {
+        if self.no_tools(session_id) {
+            return Arc::new(Vec::new());
+        }
+        let registry = self.tools().read().await;
+        let epoch = registry.epoch();
+        let mut cached = self.tool_defs.lock().unwrap();
+        match cached.as_ref() {
+            Some((cached_epoch, defs)) if *cached_epoch == epoch => defs.clone(),
+            _ => {
+                let defs = Arc::new(registry.definitions());
+                *cached = Some((epoch, defs.clone()));
+                defs
+            }
         }
     }
 
@@ -215,18 +765,58 @@ impl AgentRuntime {
         input: serde_json::Value,
         ctx: ToolContext,
     ) -> Result<ToolResult> {
-        if let Some(ref gate) = self.gate {
+        if self.no_tools(&ctx.session_id) {
+            Err(RyvosError::ToolNotFound(name.to_string()))
+        } else if let Some(ref gate) = self.gate {
             gate.execute(name, input, ctx).await
         } else {
             self.tools.read().await.execute(name, input, ctx).await
         }
     }
 
+    /// Result for a call to a tool that is not registered, e.g. because its
+    /// MCP server disconnected mid-run. Lists the tools that can be used
+    /// instead, so the model can adapt.
+    async fn unavailable_tool(&self, session_id: &SessionId, name: &str) -> ToolResult {
+        let mut names: Vec<String> = self
+            .tool_definitions(session_id)
+            .await
+            .iter()
+            .map(|def| def.name.clone())
+            .collect();
+        names.sort();
+        let available = if names.is_empty() {
+            "No tools are available right now.".to_string()
+        } else {
+            format!("Tools available now: {}.", names.join(", "))
+        };
+        ToolResult::error(format!(
+            "Tool '{}' is no longer available; it may have been removed or its MCP \
+             server disconnected. {} Use one of these instead, or continue without it.",
+            name, available
+        ))
+    }
+
     /// Run the agent loop for a given session and user message.
     pub async fn run(&self, session_id: &SessionId, user_message: &str) -> Result<String> {
         self.run_with_goal(session_id, user_message, None).await
     }
 
+    /// `run`, with `metadata` on the user message in the session history:
+    /// the channel and sender it came from and any attachments.
+    pub async fn run_with_metadata(
+        &self,
+        session_id: &SessionId,
+        user_message: &str,
+        metadata: MessageMetadata,
+    ) -> Result<String> {
+        let sampling = self.take_run_sampling(session_id);
+        let result = self
+            .run_turns(session_id, user_message, None, Some(metadata), sampling)
+            .await;
+        self.finish_run(session_id, user_message, result).await
+    }
+
     /// Run the agent loop with an optional goal.
     /// If a goal is provided, the agent evaluates output against it and retries if not met.
     /// When Director orchestration is enabled AND a goal is provided, delegates to Director.
@@ -235,20 +825,180 @@ impl AgentRuntime {
         session_id: &SessionId,
         user_message: &str,
         goal: Option<&Goal>,
+    ) -> Result<String> {
+        // Taken first, so a run that fails early cannot pass it on
+        let sampling = self.take_run_sampling(session_id);
+        let result = self
+            .run_sampled(session_id, user_message, goal, sampling)
+            .await;
+        self.finish_run(session_id, user_message, result).await
+    }
+
+    /// Record a finished run and start folding it into the session summary.
+    async fn finish_run(
+        &self,
+        session_id: &SessionId,
+        user_message: &str,
+        result: Result<String>,
+    ) -> Result<String> {
+        self.metrics.record_run(result.is_ok());
+        let reply = result?;
+        if self.config.agent.session_summary && self.depth == 0 {
+            self.update_session_summary(session_id, user_message, &reply);
+        }
+        Ok(reply)
+    }
+
+    /// `run_with_goal` without updating the session summary, for runs in
+    /// throwaway sessions such as Director graph nodes.
+    pub(crate) async fn run_without_summary(
+        &self,
+        session_id: &SessionId,
+        user_message: &str,
+        goal: Option<&Goal>,
+    ) -> Result<String> {
+        self.run_sampled(session_id, user_message, goal, None).await
+    }
+
+    /// `run_without_summary` with the run's sampling override, if any.
+    async fn run_sampled(
+        &self,
+        session_id: &SessionId,
+        user_message: &str,
+        goal: Option<&Goal>,
+        sampling: Option<SamplingOverride>,
     ) -> Result<String> {
         // Director delegation: if enabled and a goal is provided, use Director orchestration
         if let (Some(goal), Some(director_cfg)) = (goal, self.config.agent.director.as_ref()) {
             if director_cfg.enabled {
-                return self.run_with_director(session_id, user_message, goal).await;
+                return self
+                    .run_with_director(session_id, user_message, goal, sampling)
+                    .await;
+            }
+        }
+        self.run_turns(session_id, user_message, goal, None, sampling)
+            .await
+    }
+
+    /// Remove the sampling override pending for the session's next run.
+    fn take_run_sampling(&self, session_id: &SessionId) -> Option<SamplingOverride> {
+        self.run_sampling.lock().unwrap().remove(&session_id.0)
+    }
+
+    /// The `prime` planning pass, on `prime_model` if one is configured.
+    async fn prime(
+        &self,
+        user_message: &str,
+        model: &ModelConfig,
+        llm: &Arc<dyn LlmClient>,
+    ) -> Option<String> {
+        let (plan, usage) = match self.config.agent.prime_model {
+            Some(ref prime_model) => {
+                let prime_llm = self
+                    .prime_llm
+                    .get_or_init(|| Arc::from(ryvos_llm::create_client(prime_model)));
+                prime_plan(user_message, prime_llm.as_ref(), prime_model).await
+            }
+            None => prime_plan(user_message, llm.as_ref(), model).await,
+        };
+        publish_call_usage(&self.event_bus, &self.metrics, usage);
+        plan
+    }
+
+    /// Fold a finished run into its session's rolling summary in the
+    /// background, so the reply is not held up. Updates of one session run
+    /// in order, and the next run's context waits for them. Failures are
+    /// logged and leave the previous summary in place.
+    fn update_session_summary(&self, session_id: &SessionId, user_message: &str, reply: &str) {
+        let store = self.store.clone();
+        let event_bus = self.event_bus.clone();
+        let metrics = self.metrics.clone();
+        let (model, llm) = self.active_model();
+        let session = session_id.clone();
+        let (user_message, reply) = (user_message.to_string(), reply.to_string());
+
+        let mut updates = self.summary_updates.lock().unwrap();
+        let previous_update = updates.remove(&session_id.0);
+        let update = tokio::spawn(async move {
+            if let Some(previous_update) = previous_update {
+                let _ = previous_update.await;
+            }
+            let previous = match store.load_summary(&session).await {
+                Ok(previous) => previous,
+                Err(e) => {
+                    warn!(error = %e, "Failed to load session summary");
+                    return;
+                }
+            };
+            let (summary, usage) = crate::intelligence::update_session_summary(
+                previous.as_deref(),
+                &user_message,
+                &reply,
+                llm.as_ref(),
+                &model,
+            )
+            .await;
+            publish_call_usage(&event_bus, &metrics, usage);
+            let Some(summary) = summary else {
+                debug!("Session summary not updated");
+                return;
+            };
+            if let Err(e) = store.save_summary(&session, &summary).await {
+                warn!(error = %e, "Failed to save session summary");
             }
+        });
+        updates.insert(session_id.0.clone(), update);
+    }
+
+    /// Wait for the session's summary updates to finish.
+    async fn settle_session_summary(&self, session_id: &SessionId) {
+        let update = self.summary_updates.lock().unwrap().remove(&session_id.0);
+        if let Some(update) = update {
+            let _ = update.await;
         }
+    }
+
+    /// Wait for every pending session summary update, e.g. before the
+    /// process exits.
+    pub async fn settle_session_summaries(&self) {
+        let updates: Vec<_> = self
+            .summary_updates
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, update)| update)
+            .collect();
+        for update in updates {
+            let _ = update.await;
+        }
+    }
 
+    /// The turn loop of a run that is not handed to the Director.
+    async fn run_turns(
+        &self,
+        session_id: &SessionId,
+        user_message: &str,
+        goal: Option<&Goal>,
+        metadata: Option<MessageMetadata>,
+        sampling: Option<SamplingOverride>,
+    ) -> Result<String> {
         let start = Instant::now();
-        let max_turns = self.config.agent.max_turns;
-        let max_duration = Duration::from_secs(self.config.agent.max_duration_secs);
+        let overrides = self.config_overrides.for_session(session_id);
+        let max_turns = overrides.max_turns.unwrap_or(self.config.agent.max_turns);
+        let max_duration_secs = overrides
+            .max_duration_secs
+            .unwrap_or(self.config.agent.max_duration_secs);
+        let max_duration = Duration::from_secs(max_duration_secs);
 
-        // Apply CLI session ID override to model config for --resume
-        let mut model_config = self.config.model.clone();
+        // Snapshot the active model so a `/model` switch mid-run only takes
+        // effect on the next run. Apply CLI session ID override for --resume.
+        let (mut base_model, mut llm) = self.active_model();
+        overrides.apply_model(&mut base_model);
+        if let Some(sampling) = sampling {
+            debug!(?sampling, "Applying sampling override for this run");
+            sampling.apply(&mut base_model);
+        }
+        let mut model_config = base_model.clone();
         if let Some(cli_id) = self.cli_session_override.lock().unwrap().take() {
             info!(cli_session = %cli_id, "Applying CLI session override for --resume");
             model_config.cli_session_id = Some(cli_id);
@@ -278,10 +1028,22 @@ impl AgentRuntime {
             daily_log_days: ctx_config.daily_log_days,
             ..Default::default()
         };
+        if self.config.agent.inject_datetime {
+            extended.current_time = self.config.agent.zone().describe(self.clock.now());
+        }
+        if self.config.agent.session_summary {
+            self.settle_session_summary(session_id).await;
+            match self.store.load_summary(session_id).await {
+                Ok(summary) => extended.session_summary = summary.unwrap_or_default(),
+                Err(e) => warn!(error = %e, "Failed to load session summary"),
+            }
+        }
         if let Some(ref vc) = *self.viking_client.lock().await {
             let query_hint = user_message;
-            let mut policy = ryvos_memory::viking::ContextLevelPolicy::default();
-            policy.max_l0_entries = ctx_config.viking_max_l0;
+            let policy = ryvos_memory::viking::ContextLevelPolicy {
+                max_l0_entries: ctx_config.viking_max_l0,
+                ..Default::default()
+            };
             let viking_ctx = ryvos_memory::viking::load_viking_context_filtered(
                 vc,
                 query_hint,
@@ -300,21 +1062,12 @@ impl AgentRuntime {
 
         // Load safety lessons from past experience (self-learning pipeline)
         if let Some(ref sm) = self.safety_memory {
-            let tool_names: Vec<String> = if let Some(ref gate) = self.gate {
-                gate.definitions()
-                    .await
-                    .iter()
-                    .map(|t| t.name.clone())
-                    .collect()
-            } else {
-                self.tools
-                    .read()
-                    .await
-                    .definitions()
-                    .iter()
-                    .map(|t| t.name.clone())
-                    .collect()
-            };
+            let tool_names: Vec<String> = self
+                .tool_definitions(session_id)
+                .await
+                .iter()
+                .map(|t| t.name.clone())
+                .collect();
             let max_lessons = ctx_config.max_safety_lessons;
             let safety_ctx = sm.format_for_context(&tool_names, max_lessons).await;
             if !safety_ctx.is_empty() {
@@ -346,16 +1099,16 @@ impl AgentRuntime {
 
         // Record run start in cost store
         if let Some(ref cost_store) = self.cost_store {
-            let billing_type = if self.config.model.provider == "claude-code"
-                || self.config.model.provider == "claude-cli"
-                || self.config.model.provider == "claude-sub"
+            let billing_type = if base_model.provider == "claude-code"
+                || base_model.provider == "claude-cli"
+                || base_model.provider == "claude-sub"
             {
                 ryvos_llm::providers::claude_code::ClaudeCodeClient::detect_billing_type(
-                    &self.config.model,
+                    &base_model,
                 )
-            } else if self.config.model.provider == "copilot"
-                || self.config.model.provider == "github-copilot"
-                || self.config.model.provider == "copilot-cli"
+            } else if base_model.provider == "copilot"
+                || base_model.provider == "github-copilot"
+                || base_model.provider == "copilot-cli"
             {
                 BillingType::Subscription
             } else {
@@ -364,32 +1117,42 @@ impl AgentRuntime {
             if let Err(e) = cost_store.record_run(
                 &run_id,
                 &session_id.0,
-                &self.config.model.model_id,
-                &self.config.model.provider,
+                &base_model.model_id,
+                &base_model.provider,
                 billing_type,
             ) {
                 warn!(error = %e, "Failed to record run start");
             }
         }
 
-        // Load history
+        // Load history, after any notes pinned to the session
         let mut messages = vec![system_msg];
+        messages.extend(pinned_notes_message(&self.sessions.pins(session_id)));
         let history = self.store.load_history(session_id, 100).await?;
         messages.extend(history);
 
         // Append user message
-        let user_msg = ChatMessage::user(user_message);
+        let mut user_msg = ChatMessage::user(user_message);
+        user_msg.metadata = metadata;
         self.store
             .append_messages(session_id, std::slice::from_ref(&user_msg))
             .await?;
         messages.push(user_msg);
 
         // Prune context to fit token budget (with summarization if enabled)
-        let budget = self.config.agent.max_context_tokens;
+        let budget = self.config.agent.context_budget(&model_config);
+
+        // A session with compaction off keeps its whole history
+        let no_compact = self.no_compact(session_id).await?;
+
+        // A requested compaction prunes down to the tail whatever the usage,
+        // even in a session with compaction off
+        let forced = self.sessions.take_compaction_request(session_id);
 
         // Memory flush before compaction: if tokens > 85% budget, run a mini-turn
         // to let the agent persist durable info before we prune.
-        let flush_disabled = self.config.agent.disable_memory_flush.unwrap_or(false);
+        let flush_disabled =
+            (no_compact && !forced) || self.config.agent.disable_memory_flush.unwrap_or(false);
         if !flush_disabled {
             let total_tokens: usize = messages
                 .iter()
@@ -404,20 +1167,21 @@ impl AgentRuntime {
                 messages.push(memory_flush_prompt());
 
                 // Run one mini-turn to let agent call memory tools
-                let flush_tool_defs = self.tool_definitions().await;
+                let flush_tool_defs = self.tool_definitions(session_id).await;
                 let flush_vc = self.viking_client.lock().await.clone();
                 let flush_ctx = ToolContext {
                     session_id: session_id.clone(),
-                    working_dir: std::env::current_dir().unwrap_or_else(|_| workspace.clone()),
+                    working_dir: self.working_dir(session_id, &workspace),
                     store: Some(self.store.clone()),
                     agent_spawner: None,
                     sandbox_config: self.config.agent.sandbox.clone(),
                     config_path: None,
                     viking_client: flush_vc
                         .map(|c| Arc::new(c) as Arc<dyn std::any::Any + Send + Sync>),
+                    agent_depth: self.depth,
+                    event_bus: Some(self.event_bus.clone()),
                 };
-                if let Ok(mut stream) = self
-                    .llm
+                if let Ok(mut stream) = llm
                     .chat_stream(&model_config, messages.clone(), &flush_tool_defs)
                     .await
                 {
@@ -467,36 +1231,72 @@ impl AgentRuntime {
             }
         }
 
-        if self.config.agent.enable_summarization {
-            let pruned =
-                summarize_and_prune(&mut messages, budget, 6, &*self.llm, &model_config).await?;
+        let compact_budget = if forced {
+            info!("Forcing context compaction");
+            0
+        } else {
+            budget
+        };
+        let prune_policy = PrunePolicy::from_config(&self.config.agent.context);
+        let pruned = if no_compact && !forced {
+            self.warn_near_context_limit(session_id, &messages, &model_config, budget);
+            0
+        } else if self.config.agent.enable_summarization {
+            let (pruned, usage) = summarize_and_prune(
+                &mut messages,
+                compact_budget,
+                &prune_policy,
+                &*llm,
+                &model_config,
+            )
+            .await?;
+            publish_call_usage(&self.event_bus, &self.metrics, usage);
             if pruned > 0 {
                 info!(
                     pruned,
                     "Summarized and pruned messages to fit context budget"
                 );
             }
+            pruned
         } else {
             // Expire protected messages past their TTL before pruning
             let protected_ttl = self.config.agent.context.protected_ttl;
             expire_protected_messages(&mut messages, 0, protected_ttl);
-            let pruned = prune_to_budget(&mut messages, budget, 6);
+            let pruned = prune_to_budget(&mut messages, compact_budget, &prune_policy);
             if pruned > 0 {
                 info!(pruned, "Pruned messages to fit context budget");
             }
+            pruned
+        };
+        if pruned > 0 && self.config.agent.context.scratchpad_on_compaction {
+            let entries = self.store.load_scratchpad(session_id).await?;
+            if let Some(msg) = scratchpad_message(&entries) {
+                messages.insert(1, msg);
+            }
+        }
+
+        if self.config.agent.prime
+            && self.depth == 0
+            && user_message.trim().chars().count() >= PRIME_MIN_PROMPT_CHARS
+        {
+            if let Some(plan) = self.prime(user_message, &base_model, &llm).await {
+                debug!(len = plan.len(), "Prime plan added to context");
+                messages.push(plan_message(&plan));
+            }
         }
 
-        let tool_defs = self.tool_definitions().await;
-        let max_output_tokens = self.config.agent.max_tool_output_tokens;
+        let tool_defs = self.tool_definitions(session_id).await;
         let vc = self.viking_client.lock().await.clone();
         let tool_ctx = ToolContext {
             session_id: session_id.clone(),
-            working_dir: std::env::current_dir().unwrap_or_else(|_| workspace.clone()),
+            working_dir: self.working_dir(session_id, &workspace),
             store: Some(self.store.clone()),
             agent_spawner: self.spawner.lock().await.clone(),
             sandbox_config: self.config.agent.sandbox.clone(),
             config_path: None,
             viking_client: vc.map(|c| Arc::new(c) as Arc<dyn std::any::Any + Send + Sync>),
+            agent_depth: self.depth,
+            event_bus: Some(self.event_bus.clone()),
         };
 
         let mut total_input_tokens = 0u64;
@@ -504,23 +1304,63 @@ impl AgentRuntime {
         #[allow(unused_assignments)]
         let mut final_text = String::new();
         let mut failure_tracker = FailureTracker::default();
+        // Set after a malformed response is retried; cleared by a good one
+        let mut retried_malformed = false;
+        // Consecutive turns with tool input that was not run for being invalid
+        let mut invalid_input_turns = 0;
+        // Final answers so far, to catch the model repeating itself
+        let mut final_answers: Vec<String> = Vec::new();
+        let mut fell_back = false;
+        // A request rejected for its context length, to prune for and retry
+        // once at the top of the next turn
+        let mut retried_overflow = false;
+
+        // Snapshot `messages` so the run can be resumed from this point
+        let save_checkpoint = |turn: usize, messages: &[ChatMessage], input: u64, output: u64| {
+            let Some(ref cp_store) = self.checkpoint_store else {
+                return;
+            };
+            if let Ok(json) = CheckpointStore::serialize_messages(messages) {
+                let cp = crate::checkpoint::Checkpoint {
+                    session_id: session_id.0.clone(),
+                    run_id: run_id.clone(),
+                    turn,
+                    messages_json: json,
+                    total_input_tokens: input,
+                    total_output_tokens: output,
+                    timestamp: Utc::now(),
+                };
+                if let Err(e) = cp_store.save(&cp) {
+                    warn!(error = %e, "Failed to save checkpoint");
+                }
+            }
+        };
+        // On cancellation, keep what the completed turns built. A partly
+        // streamed reply is not in `messages` yet, so it is left out.
+        let cancelled = |turn: usize, messages: &[ChatMessage], input: u64, output: u64| {
+            save_checkpoint(turn, messages, input, output);
+            RyvosError::Cancelled
+        };
 
         for turn in 0..max_turns {
             // Check cancellation
             if self.cancel.is_cancelled() {
-                return Err(RyvosError::Cancelled);
+                return Err(cancelled(
+                    turn,
+                    &messages,
+                    total_input_tokens,
+                    total_output_tokens,
+                ));
             }
 
             // Check timeout
             if start.elapsed() > max_duration {
-                return Err(RyvosError::MaxDurationExceeded(
-                    self.config.agent.max_duration_secs,
-                ));
+                return Err(RyvosError::MaxDurationExceeded(max_duration_secs));
             }
 
-            // Drain Guardian hints (non-blocking)
-            if let Some(ref hints_rx) = self.guardian_hints {
-                let mut rx = hints_rx.lock().await;
+            // Drain Guardian and operator hints (non-blocking)
+            {
+                let mut rx = self.guardian_hints.lock().await;
                 while let Ok(action) = rx.try_recv() {
                     match action {
                         GuardianAction::InjectHint(hint) => {
@@ -528,20 +1368,53 @@ impl AgentRuntime {
                             messages.push(ChatMessage::user(&hint));
                         }
                         GuardianAction::CancelRun(_) => {
-                            return Err(RyvosError::Cancelled);
+                            return Err(cancelled(
+                                turn,
+                                &messages,
+                                total_input_tokens,
+                                total_output_tokens,
+                            ));
                         }
                     }
                 }
             }
+            let queued = self.session_hints.lock().unwrap().remove(&session_id.0);
+            for hint in queued.into_iter().flatten() {
+                debug!(hint = %hint, "Operator hint injected");
+                messages.push(ChatMessage::user(&hint));
+            }
 
             debug!(turn, "Starting agent turn");
 
-            // Stream from LLM
-            let stream_result = tokio::select! {
-                result = self.llm.chat_stream(&model_config, messages.clone(), &tool_defs) => result,
-                _ = self.cancel.cancelled() => return Err(RyvosError::Cancelled),
+            // Stream from LLM. A request too long for the model is pruned
+            // hard and sent again within this turn, once per run.
+            let stream_result = loop {
+                let result = tokio::select! {
+                    result = llm.chat_stream(&model_config, messages.clone(), &tool_defs) => result,
+                    _ = self.cancel.cancelled() => {
+                        return Err(cancelled(
+                            turn,
+                            &messages,
+                            total_input_tokens,
+                            total_output_tokens,
+                        ));
+                    }
+                };
+                match result {
+                    Err(e) if !retried_overflow && is_context_overflow(&e) => {
+                        warn!(turn, error = %e, "Context length exceeded, pruning to retry");
+                        retried_overflow = true;
+                        let pruned = self
+                            .emergency_prune(&mut messages, &model_config, &prune_policy, &*llm)
+                            .await;
+                        if pruned == 0 {
+                            break Err(e);
+                        }
+                        info!(pruned, "Pruned messages after a context length error");
+                    }
+                    result => break result,
+                }
             };
-
             let mut stream = stream_result?;
 
             // Accumulate response
@@ -552,7 +1425,12 @@ impl AgentRuntime {
 
             while let Some(delta) = stream.next().await {
                 if self.cancel.is_cancelled() {
-                    return Err(RyvosError::Cancelled);
+                    return Err(cancelled(
+                        turn,
+                        &messages,
+                        total_input_tokens,
+                        total_output_tokens,
+                    ));
                 }
 
                 match delta? {
@@ -582,12 +1460,15 @@ impl AgentRuntime {
                     StreamDelta::Usage {
                         input_tokens,
                         output_tokens,
+                        thinking_tokens,
                     } => {
                         total_input_tokens += input_tokens;
                         total_output_tokens += output_tokens;
+                        self.metrics.record_usage(input_tokens, output_tokens);
                         self.event_bus.publish(AgentEvent::UsageUpdate {
                             input_tokens,
                             output_tokens,
+                            thinking_tokens,
                         });
                     }
                     StreamDelta::MessageId(id) => {
@@ -606,6 +1487,7 @@ impl AgentRuntime {
                             input = %input_summary.chars().take(80).collect::<String>(),
                             "CLI tool executed (audit logged)"
                         );
+                        self.metrics.record_tool_call(false);
                         self.event_bus.publish(AgentEvent::ToolStart {
                             name: tool_name.clone(),
                             input: serde_json::json!({ "summary": &input_summary }),
@@ -771,6 +1653,8 @@ impl AgentRuntime {
                     }
                 }
             }
+            // Release the borrow of `llm` so a fallback can replace it
+            drop(stream);
 
             // Thinking-only fallback: if the model produced reasoning but no
             // visible content (common with Qwen 3.5, DeepSeek-R1 via OpenAI-compat),
@@ -779,6 +1663,44 @@ impl AgentRuntime {
                 text_content = thinking_content.clone();
             }
 
+            // Retry an empty or garbled response once, without keeping it
+            let truncated = matches!(stop_reason, Some(StopReason::MaxTokens));
+            if self.config.agent.retry_empty_response {
+                if let Some(problem) = malformed_response(&text_content, &tool_calls, truncated) {
+                    if retried_malformed {
+                        return Err(RyvosError::LlmParse(format!(
+                            "model returned {} again after a retry",
+                            problem
+                        )));
+                    }
+                    warn!(turn, problem = %problem, "Retrying malformed LLM response");
+                    retried_malformed = true;
+                    messages.push(ChatMessage::user(format!(
+                        "Your last response was unusable ({}). Please respond again: \
+                         reply with text, or call a tool with valid JSON input.",
+                        problem
+                    )));
+                    continue;
+                }
+                retried_malformed = false;
+            }
+
+            // Run at most `max_tool_calls_per_turn` calls. The rest are left out
+            // of the assistant message too, so no tool use lacks a result.
+            let mut dropped_calls = Vec::new();
+            if let Some(limit) = self.config.agent.max_tool_calls_per_turn {
+                let limit = limit.max(1);
+                if tool_calls.len() > limit {
+                    dropped_calls = tool_calls.split_off(limit);
+                    warn!(
+                        turn,
+                        limit,
+                        dropped = dropped_calls.len(),
+                        "Dropping tool calls over max_tool_calls_per_turn"
+                    );
+                }
+            }
+
             // Build the assistant message
             let mut content_blocks = Vec::new();
             if !thinking_content.is_empty() {
@@ -792,8 +1714,10 @@ impl AgentRuntime {
                 });
             }
             for tc in &tool_calls {
-                let input: serde_json::Value =
-                    serde_json::from_str(&tc.input_json).unwrap_or(serde_json::Value::Null);
+                // Providers reject a null input, so an empty or unparseable
+                // one is recorded as `{}`; its tool result says what went wrong
+                let input = serde_json::from_str(&tc.input_json)
+                    .unwrap_or_else(|_| serde_json::Value::Object(Default::default()));
                 content_blocks.push(ContentBlock::ToolUse {
                     id: tc.id.clone(),
                     name: tc.name.clone(),
@@ -820,15 +1744,66 @@ impl AgentRuntime {
             match stop_reason {
                 Some(StopReason::EndTurn) | Some(StopReason::StopSequence) | None => {
                     if is_final_response {
-                        // Apply heuristic output repair
+                        // Apply heuristic output repair, then the configured formatting
                         let repaired = OutputCleaner::heuristic_repair(&text_content);
-                        final_text = repaired;
+                        final_text = OutputCleaner::post_process(
+                            &repaired,
+                            &self.config.agent.output.processors,
+                        );
+
+                        let mut escalated = false;
+                        if self.config.agent.guardian.enabled
+                            && repeats_earlier_answer(&final_text, &final_answers)
+                        {
+                            let action = self.config.agent.guardian.repeated_answer_action;
+                            warn!(%action, "Final answer repeats an earlier one");
+                            self.event_bus.publish(AgentEvent::GuardianRepeatedAnswer {
+                                session_id: session_id.clone(),
+                                repeats: final_answers.len() + 1,
+                                action,
+                            });
+                            let fallback = self.config.fallback_models.first();
+                            match (action, fallback) {
+                                (RepeatedAnswerAction::Stop, _) => {
+                                    return Err(cancelled(
+                                        turn,
+                                        &messages,
+                                        total_input_tokens,
+                                        total_output_tokens,
+                                    ));
+                                }
+                                (RepeatedAnswerAction::Fallback, Some(fb)) if !fell_back => {
+                                    info!(model = %fb.model_id, "Switching to fallback model");
+                                    llm = Arc::from(ryvos_llm::create_client(fb));
+                                    model_config = fb.clone();
+                                    fell_back = true;
+                                }
+                                _ => {
+                                    let reason = "The agent repeated an earlier answer".to_string();
+                                    warn!(reason = %reason, "Escalating — returning output as-is");
+                                    self.event_bus.publish(AgentEvent::JudgeVerdict {
+                                        session_id: session_id.clone(),
+                                        verdict: Verdict::Escalate { reason },
+                                    });
+                                    escalated = true;
+                                }
+                            }
+                        }
+                        final_answers.push(final_text.clone());
 
                         // Judge evaluation (if goal provided)
-                        if let Some(goal) = goal {
-                            let judge = Judge::new(self.llm.clone(), self.config.model.clone());
+                        if let Some(goal) = goal.filter(|_| !escalated) {
+                            // Workspace checks run where tools ran
+                            let work_dir = self.working_dir(session_id, &workspace);
+                            let judge = Judge::new(llm.clone(), base_model.clone()).with_workspace(
+                                work_dir,
+                                self.config.agent.goal_check_commands.clone(),
+                            );
                             match judge.evaluate(&final_text, &messages, goal).await {
-                                Ok(verdict) => {
+                                Ok(Judgment {
+                                    verdict,
+                                    workspace_results,
+                                }) => {
                                     self.event_bus.publish(AgentEvent::JudgeVerdict {
                                         session_id: session_id.clone(),
                                         verdict: verdict.clone(),
@@ -836,7 +1811,10 @@ impl AgentRuntime {
                                     match &verdict {
                                         Verdict::Accept { confidence } => {
                                             // Also publish GoalEvaluated for backward compat
-                                            let results = goal.evaluate_deterministic(&final_text);
+                                            // Workspace checks are not run again
+                                            let mut results =
+                                                goal.evaluate_deterministic(&final_text);
+                                            results.extend(workspace_results);
                                             let eval = goal.compute_evaluation(results, vec![]);
                                             self.event_bus.publish(AgentEvent::GoalEvaluated {
                                                 session_id: session_id.clone(),
@@ -880,12 +1858,13 @@ impl AgentRuntime {
                             total_turns: turn + 1,
                             input_tokens: total_input_tokens,
                             output_tokens: total_output_tokens,
+                            context_tokens: estimate_context_tokens(&messages),
                         });
                         // Record completion in cost store
                         if let Some(ref cost_store) = self.cost_store {
                             let cost = ryvos_memory::estimate_cost_cents(
-                                &self.config.model.model_id,
-                                &self.config.model.provider,
+                                &base_model.model_id,
+                                &base_model.provider,
                                 BillingType::Api,
                                 total_input_tokens,
                                 total_output_tokens,
@@ -914,12 +1893,13 @@ impl AgentRuntime {
                             total_turns: turn + 1,
                             input_tokens: total_input_tokens,
                             output_tokens: total_output_tokens,
+                            context_tokens: estimate_context_tokens(&messages),
                         });
                         // Record completion in cost store
                         if let Some(ref cost_store) = self.cost_store {
                             let cost = ryvos_memory::estimate_cost_cents(
-                                &self.config.model.model_id,
-                                &self.config.model.provider,
+                                &base_model.model_id,
+                                &base_model.provider,
                                 BillingType::Api,
                                 total_input_tokens,
                                 total_output_tokens,
@@ -979,93 +1959,207 @@ impl AgentRuntime {
                 })
                 .collect();
 
-            let tool_exec_start = Instant::now();
-
-            // Execute tool calls
-            // Publish all ToolStart events first (preserves ordering for TUI/gateway)
-            let parsed_inputs: Vec<serde_json::Value> = tool_calls
+            // Execute tool calls; invalid input gets an error result instead
+            let parsed_inputs: Vec<_> = tool_calls
                 .iter()
-                .map(|tc| serde_json::from_str(&tc.input_json).unwrap_or(serde_json::Value::Null))
+                .map(|tc| parse_tool_input(tc, truncated))
                 .collect();
-
+            if parsed_inputs.iter().any(|input| input.is_err()) {
+                invalid_input_turns += 1;
+                warn!(turn, truncated, "Tool input is not valid JSON");
+                if invalid_input_turns >= MAX_INVALID_INPUT_TURNS {
+                    return Err(RyvosError::LlmParse(format!(
+                        "model sent tool input that is not valid JSON {} turns in a row",
+                        invalid_input_turns
+                    )));
+                }
+            } else {
+                invalid_input_turns = 0;
+            }
+
+            // Publish all ToolStart events first (preserves ordering for TUI/gateway)
             for (tc, input) in tool_calls.iter().zip(parsed_inputs.iter()) {
                 self.event_bus.publish(AgentEvent::ToolStart {
                     name: tc.name.clone(),
-                    input: input.clone(),
+                    input: input.clone().unwrap_or_default(),
                 });
             }
 
-            // Collect (name, id, result) tuples — parallel or serial
+            // Collect (name, id, result, ms the call ran) tuples — parallel or
+            // serial. Calls that never ran have no time.
             // Note: when gate is present, parallel execution still works because
             // SecurityGate.execute() is &self (shared ref). For approval-requiring
-            // tools, each call awaits independently.
-            let tool_results: Vec<(String, String, ToolResult)> =
-                if self.config.agent.parallel_tools && tool_calls.len() > 1 {
-                    // Parallel execution
-                    let futs: Vec<_> = tool_calls
-                        .iter()
-                        .zip(parsed_inputs.into_iter())
-                        .map(|(tc, input)| {
-                            let gate = self.gate.clone();
-                            let tools = Arc::clone(&self.tools);
-                            let ctx = tool_ctx.clone();
-                            let name = tc.name.clone();
-                            let id = tc.id.clone();
-                            async move {
-                                let result = if let Some(gate) = gate {
-                                    gate.execute(&name, input, ctx).await
-                                } else {
-                                    tools.read().await.execute(&name, input, ctx).await
-                                };
-                                let tool_result = match result {
-                                    Ok(r) => r,
-                                    Err(e) => {
-                                        error!(tool = %name, error = %e, "Tool execution failed");
-                                        ToolResult::error(e.to_string())
-                                    }
-                                };
-                                (name, id, tool_result)
-                            }
-                        })
-                        .collect();
-                    futures::future::join_all(futs).await
+            // tools, each call awaits independently, unless `group_approvals`
+            // asks about them together.
+            let grouping_gate = match self.gate {
+                Some(ref gate) if gate.policy().group_approvals && tool_calls.len() > 1 => {
+                    Some(gate)
+                }
+                _ => None,
+            };
+            let tool_results: Vec<(String, String, ToolResult, Option<u64>)> = if self
+                .no_tools(session_id)
+            {
+                // Nothing runs in no-tools mode, however the calls would be batched
+                let mut rejected = Vec::with_capacity(tool_calls.len());
+                for tc in &tool_calls {
+                    warn!(tool = %tc.name, "Model called a tool in no-tools mode");
+                    let result = self.unavailable_tool(session_id, &tc.name).await;
+                    rejected.push((tc.name.clone(), tc.id.clone(), result, None));
+                }
+                rejected
+            } else if let Some(gate) = grouping_gate {
+                // One approval request for the turn, decided before any call runs
+                let parallel = if self.config.agent.parallel_tools {
+                    self.config.agent.max_parallel_tools
                 } else {
-                    // Serial execution
-                    let mut results = Vec::with_capacity(tool_calls.len());
-                    for (tc, input) in tool_calls.iter().zip(parsed_inputs.into_iter()) {
-                        let result = self.execute_tool(&tc.name, input, tool_ctx.clone()).await;
-                        let tool_result = match result {
-                            Ok(r) => r,
-                            Err(e) => {
-                                error!(tool = %tc.name, error = %e, "Tool execution failed");
-                                ToolResult::error(e.to_string())
-                            }
-                        };
-                        results.push((tc.name.clone(), tc.id.clone(), tool_result));
-                    }
-                    results
+                    1
                 };
+                // Calls with unparseable input never reach the gate
+                let mut runnable = Vec::new();
+                let mut input_errors = Vec::with_capacity(tool_calls.len());
+                for (tc, input) in tool_calls.iter().zip(parsed_inputs) {
+                    match input {
+                        Ok(input) => {
+                            runnable.push((tc.name.clone(), input));
+                            input_errors.push(None);
+                        }
+                        Err(e) => input_errors.push(Some(e)),
+                    }
+                }
+                let mut results = gate
+                    .execute_group(runnable, tool_ctx.clone(), parallel)
+                    .await
+                    .into_iter();
+                let mut tool_results = Vec::with_capacity(tool_calls.len());
+                for (tc, input_error) in tool_calls.iter().zip(input_errors) {
+                    let (result, ran) = match input_error {
+                        None => results.next().unwrap_or_else(|| {
+                            let e = RyvosError::ToolExecution {
+                                tool: tc.name.clone(),
+                                message: "no result".into(),
+                            };
+                            (Err(e), None)
+                        }),
+                        Some(e) => (Ok(ToolResult::error(e)), None),
+                    };
+                    let tool_result = match result {
+                        Ok(r) => r,
+                        Err(RyvosError::ToolNotFound(_)) => {
+                            warn!(tool = %tc.name, "Model called an unknown tool");
+                            self.unavailable_tool(session_id, &tc.name).await
+                        }
+                        Err(e) => {
+                            error!(tool = %tc.name, error = %e, "Tool execution failed");
+                            ToolResult::error(e.to_string())
+                        }
+                    };
+                    let elapsed_ms = ran.map(|d| d.as_millis() as u64);
+                    tool_results.push((tc.name.clone(), tc.id.clone(), tool_result, elapsed_ms));
+                }
+                tool_results
+            } else if self.config.agent.parallel_tools
+                && self.config.agent.max_parallel_tools > 1
+                && tool_calls.len() > 1
+            {
+                // Parallel execution, at most `max_parallel_tools` at a time.
+                // join_all keeps results in call order.
+                let permits = Arc::new(tokio::sync::Semaphore::new(
+                    self.config.agent.max_parallel_tools,
+                ));
+                let futs: Vec<_> = tool_calls
+                    .iter()
+                    .zip(parsed_inputs)
+                    .map(|(tc, input)| {
+                        let gate = self.gate.clone();
+                        let tools = Arc::clone(&self.tools);
+                        let ctx = tool_ctx.clone();
+                        let name = tc.name.clone();
+                        let id = tc.id.clone();
+                        let permits = permits.clone();
+                        async move {
+                            let input = match input {
+                                Ok(input) => input,
+                                Err(e) => return (name, id, ToolResult::error(e), None),
+                            };
+                            let _permit = permits.acquire_owned().await.ok();
+                            let started = Instant::now();
+                            let result = if let Some(gate) = gate {
+                                gate.execute(&name, input, ctx).await
+                            } else {
+                                tools.read().await.execute(&name, input, ctx).await
+                            };
+                            let elapsed_ms = started.elapsed().as_millis() as u64;
+                            let tool_result = match result {
+                                Ok(r) => r,
+                                Err(RyvosError::ToolNotFound(_)) => {
+                                    warn!(tool = %name, "Model called an unknown tool");
+                                    self.unavailable_tool(session_id, &name).await
+                                }
+                                Err(e) => {
+                                    error!(tool = %name, error = %e, "Tool execution failed");
+                                    ToolResult::error(e.to_string())
+                                }
+                            };
+                            (name, id, tool_result, Some(elapsed_ms))
+                        }
+                    })
+                    .collect();
+                futures::future::join_all(futs).await
+            } else {
+                // Serial execution
+                let mut results = Vec::with_capacity(tool_calls.len());
+                for (tc, input) in tool_calls.iter().zip(parsed_inputs) {
+                    let started = Instant::now();
+                    let (result, ran) = match input {
+                        Ok(input) => (
+                            self.execute_tool(&tc.name, input, tool_ctx.clone()).await,
+                            true,
+                        ),
+                        Err(e) => (Ok(ToolResult::error(e)), false),
+                    };
+                    let elapsed_ms = ran.then(|| started.elapsed().as_millis() as u64);
+                    let tool_result = match result {
+                        Ok(r) => r,
+                        Err(RyvosError::ToolNotFound(_)) => {
+                            warn!(tool = %tc.name, "Model called an unknown tool");
+                            self.unavailable_tool(session_id, &tc.name).await
+                        }
+                        Err(e) => {
+                            error!(tool = %tc.name, error = %e, "Tool execution failed");
+                            ToolResult::error(e.to_string())
+                        }
+                    };
+                    results.push((tc.name.clone(), tc.id.clone(), tool_result, elapsed_ms));
+                }
+                results
+            };
 
             // Process results: compact output, track failures, build content blocks
             let threshold = self.config.agent.reflexion_failure_threshold;
             let mut tool_result_blocks = Vec::new();
 
-            let tool_exec_elapsed_ms = tool_exec_start.elapsed().as_millis() as u64;
-            for (idx, (_name, _id, tool_result)) in tool_results.iter().enumerate() {
+            for (idx, (_name, _id, tool_result, elapsed_ms)) in tool_results.iter().enumerate() {
                 // Backfill decision outcome
                 if let (Some(ref journal), Some(dec_id)) = (&self.journal, decision_ids.get(idx)) {
                     let outcome = DecisionOutcome {
                         tokens_used: 0, // not tracked per-tool
-                        latency_ms: tool_exec_elapsed_ms,
+                        latency_ms: elapsed_ms.unwrap_or(0),
                         succeeded: !tool_result.is_error,
                     };
                     journal.update_decision_outcome(dec_id, &outcome).ok();
                 }
             }
 
-            for (name, id, tool_result) in tool_results {
+            for (name, id, tool_result, elapsed_ms) in tool_results {
+                self.metrics.record_tool_call(tool_result.is_error);
+                if let (Some(ref journal), Some(elapsed_ms)) = (&self.journal, elapsed_ms) {
+                    journal
+                        .record_latency(&session_id.0, &name, elapsed_ms)
+                        .ok();
+                }
                 let compacted_content =
-                    compact_tool_output(&tool_result.content, max_output_tokens);
+                    compact_tool_output(&tool_result.content, self.config.tool_output_limit(&name));
 
                 let compacted_result = ToolResult {
                     content: compacted_content.clone(),
@@ -1130,6 +2224,21 @@ impl AgentRuntime {
                 });
             }
 
+            // Say which calls were dropped alongside the results, so the
+            // note is stored and pruned with them
+            if !dropped_calls.is_empty() {
+                let names: Vec<&str> = dropped_calls.iter().map(|tc| tc.name.as_str()).collect();
+                tool_result_blocks.push(ContentBlock::Text {
+                    text: format!(
+                        "Only the first {} tool calls of your last response were run. These \
+                         calls were dropped and not run: {}. Request them again if you still \
+                         need them.",
+                        tool_calls.len(),
+                        names.join(", ")
+                    ),
+                });
+            }
+
             // Add tool results as a user message
             let results_msg = ChatMessage {
                 role: Role::User,
@@ -1147,43 +2256,51 @@ impl AgentRuntime {
                 .await?;
             messages.push(results_msg);
 
-            // Expire protected messages past their TTL, then re-prune
-            let protected_ttl = self.config.agent.context.protected_ttl;
-            expire_protected_messages(&mut messages, turn, protected_ttl);
-            let pruned = prune_to_budget(&mut messages, budget, 6);
-            if pruned > 0 {
-                debug!(pruned, "Re-pruned messages after tool execution");
+            // Expire protected messages past their TTL, then re-prune,
+            // unless compaction is off for the session
+            if !no_compact {
+                let protected_ttl = self.config.agent.context.protected_ttl;
+                expire_protected_messages(&mut messages, turn, protected_ttl);
+                let pruned = prune_to_budget(&mut messages, budget, &prune_policy);
+                if pruned > 0 {
+                    debug!(pruned, "Re-pruned messages after tool execution");
+                }
             }
 
             // Save checkpoint after each turn
-            if let Some(ref cp_store) = self.checkpoint_store {
-                if let Ok(json) = CheckpointStore::serialize_messages(&messages) {
-                    let cp = crate::checkpoint::Checkpoint {
-                        session_id: session_id.0.clone(),
-                        run_id: run_id.clone(),
-                        turn,
-                        messages_json: json,
-                        total_input_tokens,
-                        total_output_tokens,
-                        timestamp: Utc::now(),
-                    };
-                    if let Err(e) = cp_store.save(&cp) {
-                        warn!(error = %e, "Failed to save checkpoint");
-                    }
-                }
-            }
+            save_checkpoint(turn, &messages, total_input_tokens, total_output_tokens);
 
-            #[allow(unused_assignments)]
-            {
+            // Kept as the partial answer if the turn limit is hit
+            if !text_content.trim().is_empty() {
                 final_text = text_content;
             }
         }
 
-        // Record error in cost store
+        // Out of turns: a run that wrote something returns it, cut short
+        let truncated = !final_text.trim().is_empty();
+        if truncated {
+            warn!(
+                max_turns,
+                "Turn limit reached; returning the partial answer"
+            );
+            self.event_bus.publish(AgentEvent::TurnLimitReached {
+                session_id: session_id.clone(),
+                max_turns,
+            });
+            self.event_bus.publish(AgentEvent::RunComplete {
+                session_id: session_id.clone(),
+                total_turns: max_turns,
+                input_tokens: total_input_tokens,
+                output_tokens: total_output_tokens,
+                context_tokens: estimate_context_tokens(&messages),
+            });
+        }
+
+        // Record the outcome in cost store
         if let Some(ref cost_store) = self.cost_store {
             let cost = ryvos_memory::estimate_cost_cents(
-                &self.config.model.model_id,
-                &self.config.model.provider,
+                &base_model.model_id,
+                &base_model.provider,
                 BillingType::Api,
                 total_input_tokens,
                 total_output_tokens,
@@ -1195,12 +2312,19 @@ impl AgentRuntime {
                 total_output_tokens,
                 max_turns as u64,
                 cost,
-                "error",
+                if truncated { "truncated" } else { "error" },
             ) {
-                warn!(error = %e, "Failed to record run error");
+                warn!(error = %e, "Failed to record run end");
             }
         }
 
+        if truncated {
+            let repaired = OutputCleaner::heuristic_repair(&final_text);
+            return Ok(OutputCleaner::post_process(
+                &repaired,
+                &self.config.agent.output.processors,
+            ));
+        }
         Err(RyvosError::MaxTurnsExceeded(max_turns))
     }
 
@@ -1210,6 +2334,7 @@ impl AgentRuntime {
         session_id: &'a SessionId,
         user_message: &'a str,
         goal: &'a Goal,
+        sampling: Option<SamplingOverride>,
     ) -> futures::future::BoxFuture<'a, Result<String>> {
         Box::pin(async move {
             use ryvos_core::goal::GoalObject;
@@ -1221,13 +2346,16 @@ impl AgentRuntime {
                 .as_ref()
                 .expect("director config checked before call");
 
-            let director_model = director_cfg
+            let mut director_model = director_cfg
                 .model
                 .clone()
-                .unwrap_or_else(|| self.config.model.clone());
+                .unwrap_or_else(|| self.model_config());
+            if let Some(sampling) = sampling {
+                sampling.apply(&mut director_model);
+            }
 
             let director = crate::director::Director::new(
-                self.llm.clone(),
+                self.llm(),
                 director_model,
                 self.event_bus.clone(),
                 director_cfg.max_evolution_cycles,
@@ -1247,11 +2375,21 @@ impl AgentRuntime {
 
             let result = director.run(&mut goal_obj, self, session_id).await?;
 
+            // Nodes run in sessions of their own, so this session's context
+            // is the history its next turn loads
+            let context_tokens = match self.store.load_history(session_id, 100).await {
+                Ok(history) => estimate_context_tokens(&history),
+                Err(e) => {
+                    warn!(error = %e, "Failed to load history for the context estimate");
+                    0
+                }
+            };
             self.event_bus.publish(AgentEvent::RunComplete {
                 session_id: session_id.clone(),
                 total_turns: result.total_nodes_executed,
                 input_tokens: 0,
                 output_tokens: 0,
+                context_tokens,
             });
 
             if result.succeeded {
@@ -1263,3 +2401,1887 @@ impl AgentRuntime {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use ryvos_core::config::{OutputProcessor, SubAgentPolicyConfig};
+    use ryvos_core::security::SecurityTier;
+    use ryvos_core::traits::Tool;
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient, MockTool};
+
+    /// Tool that pushes a hint into the runtime while the run is in flight.
+    struct HintingTool {
+        hints: tokio::sync::mpsc::Sender<GuardianAction>,
+    }
+
+    impl Tool for HintingTool {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn description(&self) -> &str {
+            "Sends an operator hint"
+        }
+
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _input: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> BoxFuture<'_, Result<ToolResult>> {
+            Box::pin(async move {
+                self.hints
+                    .send(GuardianAction::InjectHint("check the README first".into()))
+                    .await
+                    .unwrap();
+                Ok(ToolResult::success("ok"))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn injected_hint_reaches_next_turn() {
+        let llm = MockLlmClient::new()
+            .with_tool_call("probe", "{}")
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools.clone(),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        tools.write().await.register(HintingTool {
+            hints: runtime.hint_sender(),
+        });
+
+        let response = runtime.run(&SessionId::new(), "hello").await.unwrap();
+        assert_eq!(response, "done");
+        assert_eq!(llm.call_count(), 2);
+
+        let first: Vec<String> = llm.call_messages(0).iter().map(|m| m.text()).collect();
+        assert!(!first.iter().any(|t| t.contains("check the README")));
+        let last = llm.call_messages(1).last().cloned().unwrap();
+        assert_eq!(last.role, Role::User);
+        assert_eq!(last.text(), "check the README first");
+    }
+
+    #[tokio::test]
+    async fn queued_hint_reaches_only_its_session() {
+        let llm = MockLlmClient::new()
+            .with_text_response("one")
+            .with_text_response("two");
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let (alpha, beta) = (SessionId::new(), SessionId::new());
+        runtime.queue_hint(&beta, "look at beta.rs".to_string());
+
+        runtime.run(&alpha, "hello").await.unwrap();
+        let seen: Vec<String> = llm.call_messages(0).iter().map(|m| m.text()).collect();
+        assert!(!seen.iter().any(|t| t.contains("beta.rs")));
+
+        runtime.run(&beta, "hello").await.unwrap();
+        let last = llm.call_messages(1).last().cloned().unwrap();
+        assert_eq!(last.role, Role::User);
+        assert_eq!(last.text(), "look at beta.rs");
+    }
+
+    #[tokio::test]
+    async fn sessions_resolve_relative_paths_in_their_own_working_dir() {
+        let root = std::env::temp_dir().join(format!("ryvos_session_cwd_{}", uuid::Uuid::new_v4()));
+        for project in ["alpha", "beta"] {
+            std::fs::create_dir_all(root.join(project)).unwrap();
+        }
+        let mut config = test_config();
+        config.agent.working_dir_roots = vec![root.display().to_string()];
+        let write = |content: &str| {
+            serde_json::json!({"file_path": "notes.txt", "content": content}).to_string()
+        };
+        let llm = MockLlmClient::new()
+            .with_tool_call("write", &write("alpha notes"))
+            .with_text_response("done")
+            .with_tool_call("write", &write("beta notes"))
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools
+            .write()
+            .await
+            .register(ryvos_tools::builtin::write::WriteTool::default());
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+
+        let (alpha, beta) = (SessionId::new(), SessionId::new());
+        let sessions = runtime.session_manager();
+        sessions
+            .set_working_dir(&alpha, root.join("alpha").to_str().unwrap())
+            .unwrap();
+        sessions
+            .set_working_dir(&beta, root.join("beta").to_str().unwrap())
+            .unwrap();
+        assert!(sessions.set_working_dir(&alpha, "/").is_err());
+
+        runtime.run(&alpha, "write alpha notes").await.unwrap();
+        runtime.run(&beta, "write beta notes").await.unwrap();
+        let read = |project: &str| std::fs::read_to_string(root.join(project).join("notes.txt"));
+        assert_eq!(read("alpha").unwrap(), "alpha notes");
+        assert_eq!(read("beta").unwrap(), "beta notes");
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[tokio::test]
+    async fn pinned_notes_are_sent_after_the_system_prompt() {
+        let llm = MockLlmClient::new()
+            .with_text_response("ok")
+            .with_text_response("ok");
+        let runtime = runtime_with_llm(test_config(), &llm);
+        let session = SessionId::new();
+        runtime
+            .session_manager()
+            .pin(&session, "the repo uses pnpm");
+
+        runtime.run(&session, "install deps").await.unwrap();
+        runtime.run(&session, "and again").await.unwrap();
+        for call in 0..2 {
+            let sent = llm.call_messages(call);
+            assert!(sent[1].is_pinned());
+            assert!(sent[1].text().contains("the repo uses pnpm"));
+            assert_eq!(sent.iter().filter(|m| m.is_pinned()).count(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn session_summary_carries_into_the_next_run() {
+        let llm = MockLlmClient::new()
+            .with_text_response("Cargo.toml looks fine")
+            .with_text_response("User is reviewing the ferris crate manifest.")
+            .with_text_response("Bumped to 1.2")
+            .with_text_response("User reviewed the ferris manifest and bumped it to 1.2.");
+        let mut config = test_config();
+        config.agent.session_summary = true;
+        let store = Arc::new(InMemorySessionStore::new());
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store.clone(),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+
+        let reply = runtime.run(&session, "check Cargo.toml").await.unwrap();
+        assert_eq!(reply, "Cargo.toml looks fine");
+        assert!(!llm.call_messages(0)[0].text().contains("Session Summary"));
+        runtime.settle_session_summary(&session).await;
+        let summary = store.load_summary(&session).await.unwrap();
+        assert_eq!(
+            summary.as_deref(),
+            Some("User is reviewing the ferris crate manifest.")
+        );
+
+        runtime.run(&session, "bump the version").await.unwrap();
+        runtime.settle_session_summary(&session).await;
+        let system = llm.call_messages(2)[0].text();
+        assert!(system.contains("# Session Summary So Far\n\nUser is reviewing the ferris"));
+        // The update sees the old summary and the latest exchange
+        let update = llm.call_messages(3)[0].text();
+        assert!(update.contains("User is reviewing the ferris crate manifest."));
+        assert!(update.contains("bump the version") && update.contains("Bumped to 1.2"));
+        let summary = store.load_summary(&session).await.unwrap();
+        assert_eq!(
+            summary.as_deref(),
+            Some("User reviewed the ferris manifest and bumped it to 1.2.")
+        );
+        assert_eq!(llm.call_count(), 4);
+        // The summary calls are billed like the runs' own
+        assert_eq!(runtime.metrics().snapshot().input_tokens, 400);
+    }
+
+    #[tokio::test]
+    async fn system_prompt_starts_with_injected_time() {
+        use chrono::TimeZone;
+        let llm = MockLlmClient::new()
+            .with_text_response("ok")
+            .with_text_response("ok");
+        let mut config = test_config();
+        config.agent.timezone = Some("Europe/Berlin".into());
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 5, 0).unwrap();
+        let mut runtime = runtime_with_llm(config.clone(), &llm);
+        runtime.set_clock(Arc::new(ryvos_core::clock::FixedClock(now)));
+
+        runtime.run(&SessionId::new(), "hi").await.unwrap();
+        assert!(!llm.call_messages(0)[0]
+            .text()
+            .contains("Current date and time"));
+
+        config.agent.inject_datetime = true;
+        let mut runtime = runtime_with_llm(config, &llm);
+        runtime.set_clock(Arc::new(ryvos_core::clock::FixedClock(now)));
+        runtime.run(&SessionId::new(), "hi").await.unwrap();
+        let system = llm.call_messages(1)[0].text();
+        assert!(system
+            .starts_with("Current date and time: Friday, 2026-10-16 14:05 +02:00 (Europe/Berlin)"));
+    }
+
+    fn runtime_with_llm(config: AppConfig, llm: &MockLlmClient) -> AgentRuntime {
+        AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        )
+    }
+
+    fn empty_response() -> Vec<StreamDelta> {
+        vec![StreamDelta::Stop(StopReason::EndTurn)]
+    }
+
+    #[tokio::test]
+    async fn output_processors_format_the_final_answer() {
+        let mut config = test_config();
+        config.agent.output.processors = vec![
+            OutputProcessor::StripMarkdown,
+            OutputProcessor::Template {
+                template: "[ops] {output}".to_string(),
+            },
+        ];
+        let llm = MockLlmClient::new().with_text_response("**All** services are up.");
+        let runtime = runtime_with_llm(config, &llm);
+        let answer = runtime.run(&SessionId::new(), "status?").await.unwrap();
+        assert_eq!(answer, "[ops] All services are up.");
+    }
+
+    #[tokio::test]
+    async fn empty_response_is_retried_once() {
+        let llm = MockLlmClient::new()
+            .with_response(empty_response())
+            .with_text_response("second try");
+        let runtime = runtime_with_llm(test_config(), &llm);
+
+        let answer = runtime.run(&SessionId::new(), "hello").await.unwrap();
+        assert_eq!(answer, "second try");
+        assert_eq!(llm.call_count(), 2);
+        let nudge = llm.call_messages(1).last().unwrap().text();
+        assert!(nudge.contains("empty response"), "{}", nudge);
+    }
+
+    #[tokio::test]
+    async fn second_empty_response_ends_the_run() {
+        let llm = MockLlmClient::new()
+            .with_response(empty_response())
+            .with_response(empty_response())
+            .with_text_response("never reached");
+        let runtime = runtime_with_llm(test_config(), &llm);
+
+        let err = runtime.run(&SessionId::new(), "hello").await.unwrap_err();
+        assert!(matches!(err, RyvosError::LlmParse(_)));
+        assert!(err.to_string().contains("empty response again"), "{}", err);
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn malformed_tool_input_is_retried_unless_disabled() {
+        let garbled = || {
+            MockLlmClient::new()
+                .with_tool_call("read", r#"{"file_path": "notes"#)
+                .with_text_response("recovered")
+                .with_text_response("after tool error")
+        };
+
+        let llm = garbled();
+        let runtime = runtime_with_llm(test_config(), &llm);
+        let answer = runtime.run(&SessionId::new(), "read notes").await.unwrap();
+        assert_eq!(answer, "recovered");
+        assert!(llm
+            .call_messages(1)
+            .last()
+            .unwrap()
+            .text()
+            .contains("'read'"));
+
+        let llm = garbled();
+        let mut config = test_config();
+        config.agent.retry_empty_response = false;
+        let runtime = runtime_with_llm(config, &llm);
+        runtime.run(&SessionId::new(), "read notes").await.unwrap();
+        // The call went ahead and failed as a tool error instead
+        assert_eq!(llm.call_count(), 2);
+        assert!(llm
+            .call_messages(1)
+            .last()
+            .unwrap()
+            .content
+            .iter()
+            .any(|b| matches!(b, ContentBlock::ToolResult { is_error: true, .. })));
+    }
+
+    #[tokio::test]
+    async fn tool_output_limits_override_the_default_cap() {
+        let output: String = (0..400).map(|i| format!("result line {}\n", i)).collect();
+        let llm = MockLlmClient::new()
+            .with_tool_call("search", "{}")
+            .with_tool_call("list", "{}")
+            .with_tool_call("ping", "{}")
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        for name in ["search", "list", "ping"] {
+            tools
+                .write()
+                .await
+                .register(MockTool::new(name).with_result(ToolResult::success(output.clone())));
+        }
+        let mut config = test_config();
+        config.agent.max_tool_output_tokens = 200;
+        config.tools.output_limits = [("search".to_string(), 1000), ("ping".to_string(), 20)]
+            .into_iter()
+            .collect();
+        let store = Arc::new(InMemorySessionStore::new());
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            store.clone(),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+        runtime.run(&session, "look around").await.unwrap();
+
+        let sent: Vec<String> = llm
+            .call_messages(3)
+            .iter()
+            .flat_map(|m| m.content.iter())
+            .filter_map(|b| match b {
+                ContentBlock::ToolResult { content, .. } => Some(content.clone()),
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<String> = [1000, 200, 20]
+            .iter()
+            .map(|&cap| compact_tool_output(&output, cap))
+            .collect();
+        assert_eq!(sent, expected);
+        assert!(sent[0].len() > sent[1].len() && sent[1].len() > sent[2].len());
+
+        // The stored results are the capped ones
+        let history = store.load_history(&session, 100).await.unwrap();
+        let stored: Vec<&String> = history
+            .iter()
+            .flat_map(|m| m.content.iter())
+            .filter_map(|b| match b {
+                ContentBlock::ToolResult { content, .. } => Some(content),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(stored.len(), 3);
+        assert!(!stored.iter().any(|c| c.contains("result line 399")));
+    }
+
+    /// A `write` call whose input stops mid-string at the token limit.
+    fn truncated_write() -> Vec<StreamDelta> {
+        vec![
+            StreamDelta::ToolUseStart {
+                index: 0,
+                id: "tool_write".into(),
+                name: "write".into(),
+            },
+            StreamDelta::ToolInputDelta {
+                index: 0,
+                delta: r#"{"file_path": "big.txt", "content": "line one\nline tw"#.into(),
+            },
+            StreamDelta::Stop(StopReason::MaxTokens),
+        ]
+    }
+
+    #[tokio::test]
+    async fn truncated_tool_input_asks_the_model_to_reemit() {
+        let llm = MockLlmClient::new()
+            .with_response(truncated_write())
+            .with_tool_call(
+                "write",
+                r#"{"file_path": "big.txt", "content": "line one"}"#,
+            )
+            .with_text_response("written");
+        let write = MockTool::new("write");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(write.clone());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+
+        let answer = runtime.run(&SessionId::new(), "write it").await.unwrap();
+        assert_eq!(answer, "written");
+        // Only the re-emitted call ran, never one with null input
+        assert_eq!(write.invocation_count(), 1);
+        assert_eq!(write.invocation_input(0)["content"], "line one");
+
+        let messages = llm.call_messages(1);
+        let (call, result) = match &messages[messages.len() - 2..] {
+            [call, result] => (&call.content, &result.content),
+            _ => unreachable!(),
+        };
+        // The cut-off call is recorded with an empty object, never null
+        match &call[..] {
+            [ContentBlock::ToolUse { name, input, .. }] => {
+                assert_eq!(name, "write");
+                assert_eq!(input, &serde_json::json!({}));
+            }
+            other => panic!("expected one tool use, got {:?}", other),
+        }
+        match &result[..] {
+            [ContentBlock::ToolResult {
+                content, is_error, ..
+            }] => {
+                assert!(*is_error);
+                assert!(
+                    content.contains("cut off at the output token limit"),
+                    "{}",
+                    content
+                );
+            }
+            other => panic!("expected one tool result, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_truncated_tool_input_ends_the_run() {
+        let llm = MockLlmClient::new()
+            .with_response(truncated_write())
+            .with_response(truncated_write())
+            .with_response(truncated_write())
+            .with_text_response("never reached");
+        let write = MockTool::new("write");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(write.clone());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+
+        let err = runtime
+            .run(&SessionId::new(), "write it")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RyvosError::LlmParse(_)), "{}", err);
+        assert_eq!(llm.call_count(), MAX_INVALID_INPUT_TURNS);
+        assert_eq!(write.invocation_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn metrics_count_runs_tools_and_tokens() {
+        let llm = MockLlmClient::new()
+            .with_tool_call("read", "{}")
+            .with_text_response("done");
+        let read = MockTool::new("read");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(read.clone());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+
+        runtime.run(&SessionId::new(), "look").await.unwrap();
+        // The mock has no responses left, so this run fails
+        assert!(runtime.run(&SessionId::new(), "again").await.is_err());
+
+        let snapshot = runtime.metrics().snapshot();
+        assert_eq!((snapshot.runs, snapshot.run_errors), (2, 1));
+        assert_eq!((snapshot.tool_calls, snapshot.tool_errors), (1, 0));
+        assert_eq!((snapshot.input_tokens, snapshot.output_tokens), (200, 100));
+    }
+
+    #[tokio::test]
+    async fn tool_calls_over_the_turn_limit_are_dropped() {
+        let mut calls = Vec::new();
+        for index in 0..5 {
+            calls.push(StreamDelta::ToolUseStart {
+                index,
+                id: format!("call_{}", index),
+                name: if index < 3 { "read" } else { "grep" }.into(),
+            });
+            calls.push(StreamDelta::ToolInputDelta {
+                index,
+                delta: "{}".into(),
+            });
+        }
+        calls.push(StreamDelta::Stop(StopReason::ToolUse));
+        let llm = MockLlmClient::new()
+            .with_response(calls)
+            .with_text_response("done");
+        let (read, grep) = (MockTool::new("read"), MockTool::new("grep"));
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(read.clone());
+        tools.write().await.register(grep.clone());
+        let mut config = test_config();
+        config.agent.max_tool_calls_per_turn = Some(2);
+        let store = Arc::new(InMemorySessionStore::new());
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            store.clone(),
+            Arc::new(EventBus::default()),
+        );
+
+        let session = SessionId::new();
+        runtime.run(&session, "look").await.unwrap();
+        assert_eq!(read.invocation_count(), 2);
+        assert_eq!(grep.invocation_count(), 0);
+
+        let sent = llm.call_messages(1);
+        let uses: Vec<String> = sent
+            .iter()
+            .flat_map(|m| m.tool_uses())
+            .map(|(id, _, _)| id.to_string())
+            .collect();
+        let results: Vec<String> = sent
+            .iter()
+            .flat_map(|m| m.content.iter())
+            .filter_map(|b| match b {
+                ContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(uses, ["call_0", "call_1"]);
+        assert_eq!(results, uses);
+        let note = sent.last().unwrap().text();
+        assert!(note.contains("first 2 tool calls"), "{}", note);
+        assert!(note.contains("read, grep, grep"), "{}", note);
+        // The note travels with the results, into the store as well
+        let history = store.load_history(&session, 100).await.unwrap();
+        let stored = history
+            .iter()
+            .find(|m| {
+                m.content
+                    .iter()
+                    .any(|b| matches!(b, ContentBlock::ToolResult { .. }))
+            })
+            .unwrap();
+        assert!(stored.text().contains("read, grep, grep"));
+    }
+
+    #[tokio::test]
+    async fn removed_tool_gets_the_current_tool_list() {
+        let call = |index: usize, name: &str| {
+            [
+                StreamDelta::ToolUseStart {
+                    index,
+                    id: format!("call_{}", index),
+                    name: name.into(),
+                },
+                StreamDelta::ToolInputDelta {
+                    index,
+                    delta: "{}".into(),
+                },
+            ]
+        };
+        // Two calls run in parallel, then one runs serially
+        let mut both: Vec<StreamDelta> = call(0, "github_search").into();
+        both.extend(call(1, "read"));
+        both.push(StreamDelta::Stop(StopReason::ToolUse));
+        let llm = MockLlmClient::new()
+            .with_response(both)
+            .with_tool_call("github_search", "{}")
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        for name in ["read", "grep", "github_search"] {
+            tools.write().await.register(MockTool::new(name));
+        }
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools.clone(),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        // The MCP server behind github_search went away
+        tools.write().await.unregister("github_search");
+
+        runtime
+            .run(&SessionId::new(), "find the issue")
+            .await
+            .unwrap();
+        let results: Vec<(String, bool)> = llm
+            .call_messages(2)
+            .iter()
+            .flat_map(|m| m.content.iter())
+            .filter_map(|b| match b {
+                ContentBlock::ToolResult {
+                    content, is_error, ..
+                } => Some((content.clone(), *is_error)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(results.len(), 3);
+        for index in [0, 2] {
+            let (content, is_error) = &results[index];
+            assert!(is_error);
+            assert!(
+                content.contains("'github_search' is no longer available"),
+                "{}",
+                content
+            );
+            assert!(
+                content.contains("Tools available now: grep, read."),
+                "{}",
+                content
+            );
+        }
+        assert_eq!(results[1], ("mock output".to_string(), false));
+    }
+
+    #[tokio::test]
+    async fn sampling_override_applies_to_one_run() {
+        let llm = MockLlmClient::new()
+            .with_text_response("creative")
+            .with_text_response("back to normal");
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let before = runtime.model_config();
+        let session = SessionId::new();
+
+        let sampling = SamplingOverride::new(Some(1.4), Some(0.85)).unwrap();
+        runtime.set_run_sampling(&session, sampling);
+        runtime.run(&session, "write a poem").await.unwrap();
+        assert_eq!(llm.call_config(0).temperature, 1.4);
+        assert_eq!(llm.call_config(0).top_p, Some(0.85));
+
+        // The config is untouched and the next run samples as before
+        assert_eq!(runtime.model_config().temperature, before.temperature);
+        assert_eq!(runtime.model_config().top_p, before.top_p);
+        runtime.run(&session, "now a list").await.unwrap();
+        assert_eq!(llm.call_config(1).temperature, before.temperature);
+        assert_eq!(llm.call_config(1).top_p, before.top_p);
+    }
+
+    #[tokio::test]
+    async fn sampling_override_ends_with_a_director_run() {
+        let llm = MockLlmClient::new();
+        let mut config = test_config();
+        config.agent.director = Some(ryvos_core::config::DirectorConfig {
+            enabled: true,
+            max_evolution_cycles: 0,
+            failure_threshold: 3,
+            model: None,
+        });
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+
+        let sampling = SamplingOverride::new(Some(1.4), None).unwrap();
+        runtime.set_run_sampling(&session, sampling);
+        // The mock has no responses, so the run fails
+        let _ = runtime
+            .run_with_goal(&session, "plan a trip", Some(&unmet_goal()))
+            .await;
+        assert_eq!(llm.call_config(0).temperature, 1.4);
+        assert!(runtime.run_sampling.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn no_tools_mode_offers_no_tools() {
+        let llm = MockLlmClient::new()
+            .with_text_response("with tools")
+            .with_tool_call("write", r#"{"file_path":"x.txt","content":"x"}"#)
+            .with_text_response("plain answer");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools
+            .write()
+            .await
+            .register(ryvos_tools::builtin::write::WriteTool::default());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+
+        runtime.run(&session, "hello").await.unwrap();
+        assert_eq!(llm.call_tool_names(0), vec!["write".to_string()]);
+
+        runtime.set_no_tools(&session, true);
+        let answer = runtime.run(&session, "just chat").await.unwrap();
+        assert_eq!(answer, "plain answer");
+        assert!(llm.call_tool_names(1).is_empty());
+        assert!(llm.call_tool_names(2).is_empty());
+        // A tool requested anyway is rejected, not run
+        let results = llm.call_messages(2);
+        assert!(results
+            .last()
+            .unwrap()
+            .content
+            .iter()
+            .any(|b| matches!(b, ContentBlock::ToolResult { is_error: true, .. })));
+        // The session keeps its history across the switch
+        assert!(results.iter().any(|m| m.text() == "hello"));
+    }
+
+    #[tokio::test]
+    async fn no_tools_mode_rejects_every_call_in_a_multi_call_turn() {
+        let mut calls = Vec::new();
+        for (index, name) in ["read", "grep"].into_iter().enumerate() {
+            calls.push(StreamDelta::ToolUseStart {
+                index,
+                id: format!("call_{}", index),
+                name: name.into(),
+            });
+            calls.push(StreamDelta::ToolInputDelta {
+                index,
+                delta: "{}".into(),
+            });
+        }
+        calls.push(StreamDelta::Stop(StopReason::ToolUse));
+        let llm = MockLlmClient::new()
+            .with_response(calls)
+            .with_text_response("done");
+        let (read, grep) = (MockTool::new("read"), MockTool::new("grep"));
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(read.clone());
+        tools.write().await.register(grep.clone());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+
+        runtime.set_no_tools(&session, true);
+        runtime.run(&session, "look").await.unwrap();
+        assert_eq!(read.invocation_count(), 0);
+        assert_eq!(grep.invocation_count(), 0);
+        let rejected = llm
+            .call_messages(1)
+            .last()
+            .unwrap()
+            .content
+            .iter()
+            .filter(|b| matches!(b, ContentBlock::ToolResult { is_error: true, .. }))
+            .count();
+        assert_eq!(rejected, 2);
+        // Other sessions on the runtime keep their tools
+        assert!(!runtime.no_tools(&SessionId::new()));
+    }
+
+    #[tokio::test]
+    async fn spawned_sub_agent_runs_under_restricted_policy() {
+        let mut config = test_config();
+        config.security.sub_agent_policy = Some(SubAgentPolicyConfig {
+            auto_approve_up_to: SecurityTier::T0,
+            deny_above: Some(SecurityTier::T1),
+        });
+        // Parent spawns a sub-agent, which tries a T3 tool, then both finish
+        let llm = MockLlmClient::new()
+            .with_tool_call("spawn_agent", r#"{"prompt": "deploy the site"}"#)
+            .with_tool_call("deploy", "{}")
+            .with_text_response("deploy was refused")
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools
+            .write()
+            .await
+            .register(ryvos_tools::builtin::spawn_agent::SpawnAgentTool);
+        tools
+            .write()
+            .await
+            .register(MockTool::new("deploy").with_tier(SecurityTier::T3));
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let runtime = Arc::new(AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            event_bus,
+        ));
+        *runtime.spawner.lock().await = Some(runtime.clone());
+
+        let response = runtime.run(&SessionId::new(), "ship it").await.unwrap();
+        assert_eq!(response, "done");
+        assert_eq!(llm.call_count(), 4);
+
+        let mut blocked = vec![];
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::ToolBlocked { name, tier, .. } = event {
+                blocked.push((name, tier));
+            }
+        }
+        assert_eq!(blocked, vec![("deploy".to_string(), SecurityTier::T3)]);
+
+        // The sub-agent's output is returned to the parent as the tool result
+        let parent_view = format!("{:?}", llm.call_messages(3));
+        assert!(parent_view.contains("deploy was refused"));
+    }
+
+    #[tokio::test]
+    async fn sub_agent_events_reach_parent_bus_tagged() {
+        let llm = MockLlmClient::new()
+            .with_tool_call("spawn_agent", r#"{"prompt": "look it up"}"#)
+            .with_tool_call("lookup", "{}")
+            .with_text_response("found it")
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools
+            .write()
+            .await
+            .register(ryvos_tools::builtin::spawn_agent::SpawnAgentTool);
+        tools.write().await.register(MockTool::new("lookup"));
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            Arc::new(InMemorySessionStore::new()),
+            event_bus,
+        ));
+        *runtime.spawner.lock().await = Some(runtime.clone());
+
+        let parent = SessionId::new();
+        let response = runtime.run(&parent, "find it").await.unwrap();
+        assert_eq!(response, "done");
+
+        let (mut sub_sessions, mut nested, mut completions) = (vec![], vec![], 0);
+        let mut parent_text = String::new();
+        while let Ok(event) = events.try_recv() {
+            match event {
+                AgentEvent::SubAgent {
+                    session_id,
+                    depth,
+                    event,
+                } => {
+                    assert_eq!(depth, 1);
+                    sub_sessions.push(session_id);
+                    nested.push(*event);
+                }
+                AgentEvent::TextDelta(text) => parent_text.push_str(&text),
+                AgentEvent::RunComplete { session_id, .. } => {
+                    assert_eq!(session_id, parent);
+                    completions += 1;
+                }
+                _ => {}
+            }
+        }
+        // Only the parent's own run ends on the shared bus
+        assert_eq!(completions, 1);
+        assert_eq!(parent_text, "done");
+
+        let sub = sub_sessions[0].clone();
+        assert_ne!(sub, parent);
+        assert!(sub_sessions.iter().all(|s| *s == sub));
+        assert!(matches!(&nested[0], AgentEvent::RunStarted { session_id } if *session_id == sub));
+        assert!(nested
+            .iter()
+            .any(|e| matches!(e, AgentEvent::ToolStart { name, .. } if name == "lookup")));
+        let sub_text: String = nested
+            .iter()
+            .filter_map(|e| match e {
+                AgentEvent::TextDelta(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sub_text, "found it");
+        assert!(matches!(
+            nested.last(),
+            Some(AgentEvent::RunComplete { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn switch_model_uses_new_client_and_keeps_history() {
+        let first = MockLlmClient::new().with_text_response("cheap answer");
+        let second = MockLlmClient::new().with_text_response("strong answer");
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(first.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+        runtime.run(&session, "first question").await.unwrap();
+
+        let built = std::sync::Mutex::new(Vec::new());
+        let model = runtime
+            .switch_model("groq", "llama-3.3-70b", |model| {
+                built.lock().unwrap().push(model.clone());
+                Arc::new(second.clone()) as Arc<dyn LlmClient>
+            })
+            .unwrap();
+        assert_eq!(built.lock().unwrap().len(), 1);
+        assert_eq!(model.provider, "groq");
+        assert_eq!(runtime.model_config().model_id, "llama-3.3-70b");
+        // Preset defaults applied; the old provider's key is not carried over
+        assert!(model.base_url.is_some());
+        assert!(model.api_key.is_none());
+
+        let answer = runtime.run(&session, "second question").await.unwrap();
+        assert_eq!(answer, "strong answer");
+        assert_eq!(first.call_count(), 1);
+        assert_eq!(second.call_count(), 1);
+        let seen = format!("{:?}", second.call_messages(0));
+        assert!(seen.contains("first question"));
+        assert!(seen.contains("cheap answer"));
+    }
+
+    #[tokio::test]
+    async fn switch_model_rejects_unknown_provider() {
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let result = runtime.switch_model("nope", "model", |_| -> Arc<dyn LlmClient> {
+            panic!("client must not be built for an unknown provider")
+        });
+        assert!(matches!(result, Err(RyvosError::Config(_))));
+        assert_eq!(runtime.model_config().model_id, "test-model");
+    }
+
+    async fn seeded_store(turns: usize) -> (Arc<InMemorySessionStore>, SessionId) {
+        let store = Arc::new(InMemorySessionStore::new());
+        let session = SessionId::new();
+        let mut history = Vec::new();
+        for i in 0..turns {
+            history.push(ChatMessage::user(format!("question {}", i)));
+            history.push(ChatMessage::assistant_text(format!("answer {}", i)));
+        }
+        store.append_messages(&session, &history).await.unwrap();
+        (store, session)
+    }
+
+    #[tokio::test]
+    async fn requested_compaction_summarizes_under_budget() {
+        let (store, session) = seeded_store(5).await;
+        let llm = MockLlmClient::new()
+            .with_text_response("no compaction yet")
+            .with_text_response("the user asked five questions")
+            .with_text_response("compacted");
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            Arc::new(EventBus::default()),
+        );
+
+        // Well under max_context_tokens, so nothing is summarized
+        runtime.run(&session, "first").await.unwrap();
+        assert_eq!(llm.call_count(), 1);
+        assert_eq!(llm.call_messages(0).len(), 12);
+
+        runtime.request_compaction(&session);
+        assert_eq!(runtime.run(&session, "second").await.unwrap(), "compacted");
+        assert_eq!(llm.call_count(), 3);
+        assert!(llm.call_messages(1)[0]
+            .text()
+            .starts_with("Summarize the following conversation"));
+        let turn = llm.call_messages(2);
+        assert_eq!(turn.len(), 8);
+        assert!(turn[1].text().contains("the user asked five questions"));
+        assert_eq!(turn.last().unwrap().text(), "second");
+    }
+
+    #[tokio::test]
+    async fn compaction_shrinks_context_tokens_but_not_billed_tokens() {
+        let (store, session) = seeded_store(5).await;
+        let llm = MockLlmClient::new()
+            .with_text_response("no compaction yet")
+            .with_text_response("the user asked five questions")
+            .with_text_response("compacted");
+        let event_bus = Arc::new(EventBus::default());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            event_bus.clone(),
+        );
+        let mut rx = event_bus.subscribe();
+        let mut usage = || {
+            while let Ok(event) = rx.try_recv() {
+                if let AgentEvent::RunComplete {
+                    input_tokens,
+                    output_tokens,
+                    context_tokens,
+                    ..
+                } = event
+                {
+                    return (input_tokens + output_tokens, context_tokens);
+                }
+            }
+            panic!("no RunComplete published");
+        };
+
+        runtime.run(&session, "first").await.unwrap();
+        let (billed_before, context_before) = usage();
+        runtime.request_compaction(&session);
+        runtime.run(&session, "second").await.unwrap();
+        let (billed_after, context_after) = usage();
+
+        assert!(context_after < context_before);
+        // Every call the mock answers bills 100 in and 50 out
+        assert_eq!(billed_before, 150);
+        assert_eq!(billed_after, 150);
+    }
+
+    #[tokio::test]
+    async fn scratchpad_survives_compaction() {
+        let (store, session) = seeded_store(5).await;
+        let llm = MockLlmClient::new()
+            .with_tool_call(
+                "scratchpad",
+                r#"{"action": "set", "key": "plan", "value": "migrate the schema first"}"#,
+            )
+            .with_text_response("noted")
+            .with_text_response("the user asked five questions")
+            .with_text_response("compacted");
+        let mut tools = ToolRegistry::new();
+        tools.register(ryvos_tools::builtin::scratchpad::ScratchpadTool);
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            store.clone(),
+            Arc::new(EventBus::default()),
+        );
+
+        runtime.run(&session, "make a plan").await.unwrap();
+        let later: Vec<ChatMessage> = (0..4)
+            .flat_map(|i| {
+                [
+                    ChatMessage::user(format!("later {}", i)),
+                    ChatMessage::assistant_text("ok"),
+                ]
+            })
+            .collect();
+        store.append_messages(&session, &later).await.unwrap();
+        runtime.request_compaction(&session);
+        runtime.run(&session, "go on").await.unwrap();
+
+        // The tool call and its result were summarized away, the entry was not
+        let turn = llm.call_messages(3);
+        assert!(turn.iter().all(|m| m.text() != "make a plan"));
+        let pad = turn
+            .iter()
+            .find(|m| m.text().starts_with("[Scratchpad]"))
+            .unwrap();
+        assert!(pad.text().contains("## plan\nmigrate the schema first"));
+    }
+
+    #[tokio::test]
+    async fn requested_compaction_prunes_once_without_summarization() {
+        let (store, session) = seeded_store(5).await;
+        let mut config = test_config();
+        config.agent.enable_summarization = false;
+        let llm = MockLlmClient::new()
+            .with_text_response("other")
+            .with_text_response("one")
+            .with_text_response("two");
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            Arc::new(EventBus::default()),
+        );
+
+        // A run of another session in between does not take the request
+        runtime.request_compaction(&session);
+        runtime.run(&SessionId::new(), "elsewhere").await.unwrap();
+        runtime.run(&session, "first").await.unwrap();
+        // System prompt plus the six most recent messages
+        assert_eq!(llm.call_messages(1).len(), 7);
+
+        // The request is consumed by the run it applied to
+        runtime.run(&session, "second").await.unwrap();
+        assert_eq!(llm.call_messages(2).len(), 14);
+    }
+
+    #[tokio::test]
+    async fn config_overrides_apply_to_the_session_only() {
+        let llm = MockLlmClient::new()
+            .with_tool_call("echo", r#"{"text": "hi"}"#)
+            .with_tool_call("echo", r#"{"text": "again"}"#)
+            .with_text_response("done");
+        let mut tools = ToolRegistry::new();
+        tools.register(MockTool::new("echo"));
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let (limited, other) = (SessionId::new(), SessionId::new());
+        runtime
+            .config_overrides()
+            .set(&limited, "agent.max_turns", &serde_json::json!(1))
+            .unwrap();
+
+        let err = runtime.run(&limited, "loop").await.unwrap_err();
+        assert!(matches!(err, RyvosError::MaxTurnsExceeded(1)));
+        assert_eq!(runtime.run(&other, "loop").await.unwrap(), "done");
+    }
+
+    #[tokio::test]
+    async fn turn_limit_returns_the_partial_answer() {
+        let mut config = test_config();
+        config.agent.max_turns = 2;
+        // Text on the first turn only; the second is a bare tool call
+        let llm = MockLlmClient::new()
+            .with_response(vec![
+                StreamDelta::TextDelta("Two of the three files check out.".into()),
+                StreamDelta::ToolUseStart {
+                    index: 0,
+                    id: "call_1".into(),
+                    name: "echo".into(),
+                },
+                StreamDelta::ToolInputDelta {
+                    index: 0,
+                    delta: r#"{"text": "third"}"#.into(),
+                },
+                StreamDelta::Stop(StopReason::ToolUse),
+            ])
+            .with_tool_call("echo", r#"{"text": "again"}"#);
+        let mut tools = ToolRegistry::new();
+        tools.register(MockTool::new("echo"));
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            Arc::new(InMemorySessionStore::new()),
+            event_bus,
+        );
+        let session = SessionId::new();
+
+        let response = runtime.run(&session, "check the files").await.unwrap();
+        assert_eq!(response, "Two of the three files check out.");
+
+        let mut ends = vec![];
+        while let Ok(event) = events.try_recv() {
+            match event {
+                AgentEvent::TurnLimitReached {
+                    session_id,
+                    max_turns,
+                } => {
+                    assert_eq!(session_id, session);
+                    ends.push(format!("limit {}", max_turns));
+                }
+                AgentEvent::RunComplete { total_turns, .. } => {
+                    ends.push(format!("complete {}", total_turns))
+                }
+                AgentEvent::RunError { .. } => ends.push("error".to_string()),
+                _ => {}
+            }
+        }
+        assert_eq!(ends, vec!["limit 2", "complete 2"]);
+    }
+
+    const CONTEXT_LENGTH_ERROR: &str =
+        "HTTP 400: {\"error\": {\"message\": \"This model's maximum \
+         context length is 8192 tokens\", \"code\": \"context_length_exceeded\"}}";
+
+    #[tokio::test]
+    async fn context_length_error_prunes_and_retries_the_turn() {
+        let (store, session) = seeded_store(5).await;
+        let llm = MockLlmClient::new()
+            .with_error(CONTEXT_LENGTH_ERROR)
+            .with_text_response("the user asked five questions")
+            .with_text_response("recovered");
+        // The retry happens within the turn, so one turn is enough
+        let mut config = test_config();
+        config.agent.max_turns = 1;
+        let event_bus = Arc::new(EventBus::default());
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            event_bus.clone(),
+        );
+        let mut rx = event_bus.subscribe();
+
+        assert_eq!(runtime.run(&session, "next").await.unwrap(), "recovered");
+        assert_eq!(llm.call_count(), 3);
+        // The summary call is counted like the turn's
+        let mut published = 0;
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::UsageUpdate {
+                input_tokens,
+                output_tokens,
+                ..
+            } = event
+            {
+                published += input_tokens + output_tokens;
+            }
+        }
+        assert_eq!(published, 300);
+        assert_eq!(llm.call_messages(0).len(), 12);
+        assert!(llm.call_messages(1)[0]
+            .text()
+            .starts_with("Summarize the following conversation"));
+        let retried = llm.call_messages(2);
+        assert!(retried.len() < 12, "{} messages", retried.len());
+        assert!(retried[1].text().contains("the user asked five questions"));
+        assert_eq!(retried.last().unwrap().text(), "next");
+    }
+
+    #[tokio::test]
+    async fn context_length_error_is_retried_only_once() {
+        let (store, session) = seeded_store(5).await;
+        let llm = MockLlmClient::new()
+            .with_error(CONTEXT_LENGTH_ERROR)
+            .with_text_response("the user asked five questions")
+            .with_error("HTTP 400: prompt is too long: 9000 tokens > 8192 maximum");
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            Arc::new(EventBus::default()),
+        );
+        let err = runtime.run(&session, "next").await.unwrap_err();
+        assert!(is_context_overflow(&err), "{}", err);
+        assert_eq!(llm.call_count(), 3);
+
+        // Gemini's wording
+        assert!(is_context_overflow(&RyvosError::LlmRequest(
+            "HTTP 400: The input token count (1200000) exceeds the maximum number of \
+             tokens allowed (1048576)."
+                .into()
+        )));
+
+        // Other request errors are not retried at all
+        let llm = MockLlmClient::new().with_error("HTTP 401: invalid api key");
+        let runtime = runtime_with_llm(test_config(), &llm);
+        assert!(runtime.run(&SessionId::new(), "hi").await.is_err());
+        assert_eq!(llm.call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn no_compact_sessions_keep_history_above_the_budget() {
+        let (store, session) = seeded_store(5).await;
+        let mut config = test_config();
+        config.agent.max_context_tokens = Some(40);
+        let llm = MockLlmClient::new()
+            .with_text_response("kept")
+            .with_text_response("nothing to save")
+            .with_text_response("the user asked five questions")
+            .with_text_response("compacted");
+        let event_bus = Arc::new(EventBus::default());
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            event_bus.clone(),
+        );
+        let mut rx = event_bus.subscribe();
+
+        runtime.set_no_compact(&session, true).await.unwrap();
+        runtime.run(&session, "first").await.unwrap();
+        // Nothing flushed, summarized or pruned: one call with everything
+        assert_eq!(llm.call_count(), 1);
+        assert_eq!(llm.call_messages(0).len(), 12);
+        let mut warned = None;
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::ContextNearLimit {
+                context_tokens,
+                limit_tokens,
+                ..
+            } = event
+            {
+                warned = Some((context_tokens, limit_tokens));
+            }
+        }
+        let (used, limit) = warned.expect("no ContextNearLimit published");
+        assert_eq!(limit, 40);
+        assert!(used > limit);
+
+        // Back on, the same history is flushed, summarized and pruned
+        runtime.set_no_compact(&session, false).await.unwrap();
+        assert_eq!(runtime.run(&session, "second").await.unwrap(), "compacted");
+        assert_eq!(llm.call_count(), 4);
+        assert!(llm.call_messages(3).len() < 14);
+    }
+
+    #[tokio::test]
+    async fn no_compact_survives_a_new_runtime_and_tool_calls() {
+        let (store, session) = seeded_store(5).await;
+        let mut config = test_config();
+        config.agent.max_context_tokens = Some(40);
+        AgentRuntime::new(
+            config.clone(),
+            Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store.clone(),
+            Arc::new(EventBus::default()),
+        )
+        .set_no_compact(&session, true)
+        .await
+        .unwrap();
+
+        // A later process only has the stored setting
+        let llm = MockLlmClient::new()
+            .with_tool_call("lookup", "{}")
+            .with_text_response("done");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(MockTool::new("lookup"));
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools,
+            store,
+            Arc::new(EventBus::default()),
+        );
+        assert_eq!(runtime.run(&session, "first").await.unwrap(), "done");
+
+        // The turn after the tool call still carries the whole history
+        assert_eq!(llm.call_count(), 2);
+        assert_eq!(llm.call_messages(0).len(), 12);
+        assert_eq!(llm.call_messages(1).len(), 14);
+    }
+
+    #[tokio::test]
+    async fn requested_compaction_flushes_memory_with_compaction_off() {
+        let (store, session) = seeded_store(5).await;
+        let mut config = test_config();
+        config.agent.max_context_tokens = Some(40);
+        let llm = MockLlmClient::new()
+            .with_text_response("nothing to save")
+            .with_text_response("the user asked five questions")
+            .with_text_response("compacted");
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store,
+            Arc::new(EventBus::default()),
+        );
+        runtime.set_no_compact(&session, true).await.unwrap();
+
+        runtime.request_compaction(&session);
+        assert_eq!(runtime.run(&session, "second").await.unwrap(), "compacted");
+        assert_eq!(llm.call_count(), 3);
+        assert!(llm
+            .call_messages(0)
+            .last()
+            .unwrap()
+            .text()
+            .contains("Before compaction, persist"));
+        assert!(llm.call_messages(2).len() < 12);
+    }
+
+    /// Tool that records how many of its calls are in flight at once.
+    struct ConcurrencyProbe {
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Tool for ConcurrencyProbe {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn description(&self) -> &str {
+            "Sleeps briefly while counting concurrent calls"
+        }
+
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            input: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> BoxFuture<'_, Result<ToolResult>> {
+            use std::sync::atomic::Ordering;
+            Box::pin(async move {
+                let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                Ok(ToolResult::success(format!("probe {}", input["n"])))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_tools_respect_max_parallel_tools() {
+        let mut deltas = Vec::new();
+        for n in 0..5 {
+            deltas.push(StreamDelta::ToolUseStart {
+                index: n,
+                id: format!("call_{}", n),
+                name: "probe".into(),
+            });
+            deltas.push(StreamDelta::ToolInputDelta {
+                index: n,
+                delta: format!("{{\"n\": {}}}", n),
+            });
+        }
+        deltas.push(StreamDelta::Stop(StopReason::ToolUse));
+        let llm = MockLlmClient::new()
+            .with_response(deltas)
+            .with_text_response("all probed");
+
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut tools = ToolRegistry::new();
+        tools.register(ConcurrencyProbe {
+            running: running.clone(),
+            peak: peak.clone(),
+        });
+        let mut config = test_config();
+        config.agent.max_parallel_tools = 2;
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+
+        let answer = runtime.run(&SessionId::new(), "probe").await.unwrap();
+        assert_eq!(answer, "all probed");
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // Results come back in call order
+        let results = format!("{:?}", llm.call_messages(1).last().unwrap());
+        let positions: Vec<_> = (0..5)
+            .map(|n| results.find(&format!("probe {}", n)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    /// Tool that sleeps for a fixed time.
+    struct Sleeper {
+        name: &'static str,
+        ms: u64,
+    }
+
+    impl Tool for Sleeper {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Sleeps"
+        }
+
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _input: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> BoxFuture<'_, Result<ToolResult>> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(self.ms)).await;
+                Ok(ToolResult::success("slept"))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_calls_are_timed_one_by_one() {
+        let mut deltas = Vec::new();
+        for (n, name) in ["fast", "slow"].into_iter().enumerate() {
+            deltas.push(StreamDelta::ToolUseStart {
+                index: n,
+                id: format!("call_{}", n),
+                name: name.into(),
+            });
+            deltas.push(StreamDelta::ToolInputDelta {
+                index: n,
+                delta: "{}".into(),
+            });
+        }
+        deltas.push(StreamDelta::Stop(StopReason::ToolUse));
+        let llm = MockLlmClient::new()
+            .with_response(deltas)
+            .with_text_response("done");
+
+        let mut tools = ToolRegistry::new();
+        tools.register(Sleeper {
+            name: "fast",
+            ms: 5,
+        });
+        tools.register(Sleeper {
+            name: "slow",
+            ms: 300,
+        });
+        let dir = std::env::temp_dir().join(format!("ryvos_latency_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journal = Arc::new(FailureJournal::open(&dir.join("healing.db")).unwrap());
+        let mut runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        runtime.set_journal(journal.clone());
+        let since = chrono::Utc::now() - chrono::Duration::minutes(1);
+
+        runtime.run(&SessionId::new(), "sleep").await.unwrap();
+        let latency = journal.tool_latency(since).unwrap();
+        assert!(latency["fast"].max_ms < 200, "{:?}", latency["fast"]);
+        assert!(latency["slow"].max_ms >= 300, "{:?}", latency["slow"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// Tool that counts how often its input schema is built.
+    struct SchemaCounter {
+        builds: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Tool for SchemaCounter {
+        fn name(&self) -> &str {
+            "counted"
+        }
+
+        fn description(&self) -> &str {
+            "Counts schema builds"
+        }
+
+        fn input_schema(&self) -> serde_json::Value {
+            self.builds
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _input: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> BoxFuture<'_, Result<ToolResult>> {
+            Box::pin(async { Ok(ToolResult::success("ok")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_definitions_are_rebuilt_only_after_registry_changes() {
+        use std::sync::atomic::Ordering;
+        let builds = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        tools.write().await.register(SchemaCounter {
+            builds: builds.clone(),
+        });
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>,
+            tools.clone(),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        let session = SessionId::new();
+
+        runtime.tool_definitions(&session).await;
+        runtime.tool_definitions(&session).await;
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        tools.write().await.register(MockTool::new("other"));
+        assert_eq!(runtime.tool_definitions(&session).await.len(), 2);
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+
+        tools.write().await.unregister("other");
+        assert_eq!(runtime.tool_definitions(&session).await.len(), 1);
+        runtime.tool_definitions(&session).await;
+        assert_eq!(builds.load(Ordering::SeqCst), 3);
+    }
+
+    /// Tool that cancels the run it is called from.
+    struct CancellingTool {
+        cancel: CancellationToken,
+    }
+
+    impl Tool for CancellingTool {
+        fn name(&self) -> &str {
+            "stop"
+        }
+
+        fn description(&self) -> &str {
+            "Cancels the run"
+        }
+
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _input: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> BoxFuture<'_, Result<ToolResult>> {
+            self.cancel.cancel();
+            Box::pin(async { Ok(ToolResult::success("partial results saved")) })
+        }
+    }
+
+    fn temp_checkpoint_store() -> Arc<CheckpointStore> {
+        let dir = std::env::temp_dir().join(format!("ryvos_cp_cancel_{}", uuid::Uuid::new_v4()));
+        Arc::new(CheckpointStore::open(&dir.join("checkpoints.db")).unwrap())
+    }
+
+    #[tokio::test]
+    async fn cancelled_run_leaves_checkpoint_of_completed_turns() {
+        let llm = MockLlmClient::new()
+            .with_tool_call("stop", "{}")
+            .with_text_response("never requested");
+        let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::new()));
+        let mut runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            tools.clone(),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        );
+        tools.write().await.register(CancellingTool {
+            cancel: runtime.cancel_token(),
+        });
+        let cp_store = temp_checkpoint_store();
+        runtime.set_checkpoint_store(cp_store.clone());
+
+        let session_id = SessionId::new();
+        let err = runtime.run(&session_id, "collect the logs").await;
+        assert!(matches!(err, Err(RyvosError::Cancelled)));
+        assert_eq!(llm.call_count(), 1);
+
+        // Interrupted at the start of turn 1, holding turn 0's tool result
+        let cp = cp_store.load_latest(&session_id.0).unwrap().unwrap();
+        assert_eq!(cp.turn, 1);
+        let messages = CheckpointStore::deserialize_messages(&cp.messages_json).unwrap();
+        assert!(messages
+            .iter()
+            .any(|m| m.text().contains("collect the logs")));
+        assert!(cp.messages_json.contains("partial results saved"));
+    }
+
+    #[tokio::test]
+    async fn run_cancelled_before_first_turn_still_checkpoints_prompt() {
+        let llm = MockLlmClient::new().with_text_response("never requested");
+        let mut runtime = runtime_with_llm(test_config(), &llm);
+        let cp_store = temp_checkpoint_store();
+        runtime.set_checkpoint_store(cp_store.clone());
+        runtime.cancel_token().cancel();
+
+        let session_id = SessionId::new();
+        let err = runtime.run(&session_id, "collect the logs").await;
+        assert!(matches!(err, Err(RyvosError::Cancelled)));
+        assert_eq!(llm.call_count(), 0);
+
+        let cp = cp_store.load_latest(&session_id.0).unwrap().unwrap();
+        assert_eq!(cp.turn, 0);
+        assert!(cp.messages_json.contains("collect the logs"));
+    }
+
+    #[tokio::test]
+    async fn prime_adds_plan_before_first_turn() {
+        let llm = MockLlmClient::new()
+            .with_text_response("1. Read src/lib.rs\n2. Rename the function")
+            .with_text_response("Renamed");
+        let mut config = test_config();
+        config.agent.prime = true;
+        let runtime = runtime_with_llm(config, &llm);
+        let prompt =
+            "Rename parse_config to load_config in src/lib.rs and update every caller in the crate";
+
+        let reply = runtime.run(&SessionId::new(), prompt).await.unwrap();
+        assert_eq!(reply, "Renamed");
+        assert_eq!(llm.call_count(), 2);
+        assert!(llm.call_messages(0)[0].text().contains(prompt));
+
+        let first_turn = llm.call_messages(1);
+        let plan = first_turn.last().unwrap();
+        assert!(plan.text().starts_with("[Plan] Drafted before starting."));
+        assert!(plan
+            .text()
+            .ends_with("1. Read src/lib.rs\n2. Rename the function"));
+        assert_eq!(first_turn[first_turn.len() - 2].text(), prompt);
+        // The planning call is billed like a turn
+        assert_eq!(runtime.metrics().snapshot().input_tokens, 200);
+    }
+
+    #[tokio::test]
+    async fn prime_is_skipped_for_short_prompts() {
+        let llm = MockLlmClient::new().with_text_response("Hi!");
+        let mut config = test_config();
+        config.agent.prime = true;
+        let runtime = runtime_with_llm(config, &llm);
+
+        runtime.run(&SessionId::new(), "hello").await.unwrap();
+        assert_eq!(llm.call_count(), 1);
+        assert!(!llm
+            .call_messages(0)
+            .iter()
+            .any(|m| m.text().starts_with("[Plan]")));
+    }
+
+    /// Goal the mock replies below never meet, so the judge keeps retrying.
+    fn unmet_goal() -> Goal {
+        serde_json::from_value(serde_json::json!({
+            "description": "Report the result",
+            "success_criteria": [{
+                "id": "done",
+                "criterion_type": { "type": "output_contains", "pattern": "DONE" },
+                "description": "Says DONE"
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn goal_shell_checks_run_once_per_evaluation() {
+        let log = std::env::temp_dir().join(format!("ryvos_checks_{}.log", uuid::Uuid::new_v4()));
+        let command = format!("echo run >> {}", log.display());
+        let goal: Goal = serde_json::from_value(serde_json::json!({
+            "description": "Leave the checks passing",
+            "success_criteria": [{
+                "id": "checks",
+                "criterion_type": { "type": "shell_exit_code", "command": command },
+                "description": "Checks pass"
+            }]
+        }))
+        .unwrap();
+        let mut config = test_config();
+        config.agent.goal_check_commands = vec![command];
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(MockLlmClient::new().with_text_response("done")) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            event_bus,
+        );
+
+        runtime
+            .run_with_goal(&SessionId::new(), "check", Some(&goal))
+            .await
+            .unwrap();
+        let runs = std::fs::read_to_string(&log).unwrap();
+        let _ = std::fs::remove_file(&log);
+        assert_eq!(runs.lines().count(), 1);
+        // The accepted run reports the check it was judged on
+        let mut evaluated = None;
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::GoalEvaluated { evaluation, .. } = event {
+                evaluated = Some(evaluation);
+            }
+        }
+        let evaluation = evaluated.expect("GoalEvaluated published");
+        assert!(evaluation.passed);
+        assert_eq!(evaluation.criteria_results.len(), 1);
+    }
+
+    /// Run against `unmet_goal` with a model that gives the same answer
+    /// twice, returning the result and the repeated-answer actions seen.
+    async fn run_repeating(
+        action: RepeatedAnswerAction,
+    ) -> (Result<String>, Vec<RepeatedAnswerAction>, MockLlmClient) {
+        let llm = MockLlmClient::new()
+            .with_text_response("I could not find the report. Please check the path.")
+            .with_text_response("I could not find the report, please check the path!")
+            .with_text_response("never requested");
+        let mut config = test_config();
+        config.agent.guardian.repeated_answer_action = action;
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let runtime = AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            event_bus,
+        );
+
+        let result = runtime
+            .run_with_goal(&SessionId::new(), "find the report", Some(&unmet_goal()))
+            .await;
+        let mut actions = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::GuardianRepeatedAnswer {
+                repeats, action, ..
+            } = event
+            {
+                assert_eq!(repeats, 2);
+                actions.push(action);
+            }
+        }
+        (result, actions, llm)
+    }
+
+    #[tokio::test]
+    async fn repeated_answer_escalates_instead_of_retrying() {
+        let (result, actions, llm) = run_repeating(RepeatedAnswerAction::Escalate).await;
+        assert_eq!(
+            result.unwrap(),
+            "I could not find the report, please check the path!"
+        );
+        assert_eq!(actions, [RepeatedAnswerAction::Escalate]);
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn repeated_answer_can_stop_the_run() {
+        let (result, actions, llm) = run_repeating(RepeatedAnswerAction::Stop).await;
+        assert!(matches!(result, Err(RyvosError::Cancelled)));
+        assert_eq!(actions, [RepeatedAnswerAction::Stop]);
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn fallback_without_fallback_models_escalates() {
+        let (result, actions, llm) = run_repeating(RepeatedAnswerAction::Fallback).await;
+        assert!(result.is_ok());
+        assert_eq!(actions, [RepeatedAnswerAction::Fallback]);
+        assert_eq!(llm.call_count(), 2);
+    }
+}
@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::sync::{oneshot, Mutex};
+use tracing::warn;
 
 use ryvos_core::event::EventBus;
-use ryvos_core::security::{ApprovalDecision, ApprovalRequest};
+use ryvos_core::security::{
+    ApprovalDecision, ApprovalRequest, ApprovalTimeoutAction, SecurityPolicy,
+};
 use ryvos_core::types::AgentEvent;
 
 /// Manages pending approval requests with oneshot channels.
@@ -34,12 +38,95 @@ impl ApprovalBroker {
         rx
     }
 
+    /// Ask for a decision and wait up to the policy's `approval_timeout_secs`.
+    /// A request nobody answers is settled by `approval_timeout_action`, and
+    /// an `ApprovalTimedOut` event reports the action taken. One that needs
+    /// a typed confirmation, or any request in safe mode, is never allowed
+    /// by a timeout.
+    pub async fn decide(&self, req: ApprovalRequest, policy: &SecurityPolicy) -> ApprovalDecision {
+        let timeout = Duration::from_secs(policy.approval_timeout_secs);
+        let id = req.id.clone();
+        let needs_confirm = req.confirm_word.is_some();
+        let mut rx = self.request(req).await;
+        if let Some(decision) = wait(&mut rx, timeout).await {
+            return decision;
+        }
+
+        let action = match policy.approval_timeout_action {
+            ApprovalTimeoutAction::Allow if needs_confirm || policy.safe_mode => {
+                ApprovalTimeoutAction::Deny
+            }
+            action => action,
+        };
+        if action == ApprovalTimeoutAction::Escalate {
+            let request = self.pending.lock().await.get(&id).map(|(r, _)| r.clone());
+            if let Some(request) = request {
+                warn!(request_id = %id, tool = %request.tool_name, "Approval timed out, escalating");
+                self.event_bus.publish(AgentEvent::ApprovalTimedOut {
+                    request,
+                    action,
+                    target_channel: policy.approval_escalation_channel.clone(),
+                });
+                if let Some(decision) = wait(&mut rx, timeout).await {
+                    return decision;
+                }
+            }
+            let reason = format!(
+                "no answer within {}s, including after escalation",
+                policy.approval_timeout_secs
+            );
+            return self
+                .expire(&id, &mut rx, ApprovalTimeoutAction::Deny, reason)
+                .await;
+        }
+
+        let reason = format!("no answer within {}s", policy.approval_timeout_secs);
+        self.expire(&id, &mut rx, action, reason).await
+    }
+
+    /// Drop a timed-out request and settle it with `action` (allow or deny).
+    /// If a response won the race, that response stands.
+    async fn expire(
+        &self,
+        id: &str,
+        rx: &mut oneshot::Receiver<ApprovalDecision>,
+        action: ApprovalTimeoutAction,
+        reason: String,
+    ) -> ApprovalDecision {
+        let Some((request, _tx)) = self.pending.lock().await.remove(id) else {
+            if let Ok(decision) = rx.try_recv() {
+                return decision;
+            }
+            return ApprovalDecision::Denied { reason };
+        };
+        warn!(request_id = %id, tool = %request.tool_name, %action, "Approval timed out");
+        self.event_bus.publish(AgentEvent::ApprovalTimedOut {
+            request,
+            action,
+            target_channel: None,
+        });
+        match action {
+            ApprovalTimeoutAction::Allow => ApprovalDecision::Approved,
+            _ => ApprovalDecision::Denied { reason },
+        }
+    }
+
     /// Respond to a pending approval (called by REPL/WebSocket/Telegram).
-    /// Returns true if the request was found and resolved.
+    /// Returns true if the request was found and resolved. An approval of a
+    /// request with a `confirm_word` that does not carry it is a denial.
     pub async fn respond(&self, request_id: &str, decision: ApprovalDecision) -> bool {
         let entry = self.pending.lock().await.remove(request_id);
-        if let Some((_req, tx)) = entry {
-            let approved = matches!(decision, ApprovalDecision::Approved);
+        if let Some((req, tx)) = entry {
+            let decision = match req.confirm_word {
+                Some(ref word) if decision.is_approved() && !decision.confirms(word) => {
+                    warn!(request_id, tool = %req.tool_name, "Approval without the confirmation word");
+                    ApprovalDecision::Denied {
+                        reason: format!("approved without typing {}", word),
+                    }
+                }
+                _ => decision,
+            };
+            let approved = decision.is_approved();
             self.event_bus.publish(AgentEvent::ApprovalResolved {
                 request_id: request_id.to_string(),
                 approved,
@@ -62,6 +149,26 @@ impl ApprovalBroker {
             .collect()
     }
 
+    /// The word a pending request needs typed to be approved, if any.
+    pub async fn confirm_word(&self, request_id: &str) -> Option<String> {
+        self.pending
+            .lock()
+            .await
+            .get(request_id)
+            .and_then(|(req, _)| req.confirm_word.clone())
+    }
+
+    /// How many calls a pending grouped request asks about; `None` for a
+    /// request about a single call.
+    pub async fn group_size(&self, request_id: &str) -> Option<usize> {
+        self.pending
+            .lock()
+            .await
+            .get(request_id)
+            .map(|(req, _)| req.calls.len())
+            .filter(|&n| n > 0)
+    }
+
     /// Find a pending request by prefix match on the ID.
     pub async fn find_by_prefix(&self, prefix: &str) -> Option<String> {
         let pending = self.pending.lock().await;
@@ -74,6 +181,14 @@ impl ApprovalBroker {
     }
 }
 
+/// Wait for a response; `None` on timeout or if the request was dropped.
+async fn wait(
+    rx: &mut oneshot::Receiver<ApprovalDecision>,
+    timeout: Duration,
+) -> Option<ApprovalDecision> {
+    tokio::time::timeout(timeout, rx).await.ok()?.ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,8 +201,11 @@ mod tests {
             tool_name: "bash".to_string(),
             tier: SecurityTier::T2,
             input_summary: "ls -la".to_string(),
+            input_detail: None,
+            confirm_word: None,
             session_id: "test-session".to_string(),
             timestamp: Utc::now(),
+            calls: vec![],
         }
     }
 
@@ -127,6 +245,33 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn approval_without_confirm_word_is_a_denial() {
+        let event_bus = Arc::new(EventBus::default());
+        let broker = ApprovalBroker::new(event_bus);
+        let confirmed = |id: &str| ApprovalRequest {
+            confirm_word: Some("CONFIRM".to_string()),
+            ..test_request(id)
+        };
+
+        let rx = broker.request(confirmed("req-c1")).await;
+        assert_eq!(
+            broker.confirm_word("req-c1").await.as_deref(),
+            Some("CONFIRM")
+        );
+        assert!(broker.respond("req-c1", ApprovalDecision::Approved).await);
+        assert!(matches!(rx.await.unwrap(), ApprovalDecision::Denied { .. }));
+
+        let rx = broker.request(confirmed("req-c2")).await;
+        let typed = ApprovalDecision::approve_with(Some("CONFIRM"));
+        assert!(broker.respond("req-c2", typed).await);
+        assert!(rx.await.unwrap().confirms("CONFIRM"));
+
+        let policy = timing_out(0, ApprovalTimeoutAction::Allow);
+        let decision = broker.decide(confirmed("req-c3"), &policy).await;
+        assert!(matches!(decision, ApprovalDecision::Denied { .. }));
+    }
+
     #[tokio::test]
     async fn respond_unknown_id() {
         let event_bus = Arc::new(EventBus::default());
@@ -162,4 +307,106 @@ mod tests {
         let pending = broker.pending_requests().await;
         assert_eq!(pending.len(), 2);
     }
+
+    fn timing_out(secs: u64, action: ApprovalTimeoutAction) -> SecurityPolicy {
+        SecurityPolicy {
+            approval_timeout_secs: secs,
+            approval_timeout_action: action,
+            approval_escalation_channel: Some("telegram".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout_allow_approves() {
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let broker = ApprovalBroker::new(event_bus);
+
+        let policy = timing_out(0, ApprovalTimeoutAction::Allow);
+        let decision = broker.decide(test_request("req-t1"), &policy).await;
+        assert!(matches!(decision, ApprovalDecision::Approved));
+        assert!(broker.pending_requests().await.is_empty());
+
+        assert!(matches!(
+            events.recv().await,
+            Ok(AgentEvent::ApprovalRequested { .. })
+        ));
+        match events.recv().await {
+            Ok(AgentEvent::ApprovalTimedOut {
+                request, action, ..
+            }) => {
+                assert_eq!(request.id, "req-t1");
+                assert_eq!(action, ApprovalTimeoutAction::Allow);
+            }
+            other => panic!("expected ApprovalTimedOut, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout_deny_denies() {
+        let event_bus = Arc::new(EventBus::default());
+        let broker = ApprovalBroker::new(event_bus);
+
+        let policy = timing_out(0, ApprovalTimeoutAction::Deny);
+        match broker.decide(test_request("req-t2"), &policy).await {
+            ApprovalDecision::Denied { reason } => assert_eq!(reason, "no answer within 0s"),
+            other => panic!("expected Denied, got {:?}", other),
+        }
+        assert!(broker.pending_requests().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn timeout_escalate_reposts_then_denies() {
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let broker = ApprovalBroker::new(event_bus);
+
+        let policy = timing_out(0, ApprovalTimeoutAction::Escalate);
+        let decision = broker.decide(test_request("req-t3"), &policy).await;
+        assert!(matches!(decision, ApprovalDecision::Denied { .. }));
+
+        let mut actions = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::ApprovalTimedOut {
+                action,
+                target_channel,
+                ..
+            } = event
+            {
+                actions.push((action, target_channel));
+            }
+        }
+        assert_eq!(
+            actions,
+            [
+                (
+                    ApprovalTimeoutAction::Escalate,
+                    Some("telegram".to_string())
+                ),
+                (ApprovalTimeoutAction::Deny, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn escalated_request_can_still_be_answered() {
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let broker = Arc::new(ApprovalBroker::new(event_bus));
+
+        // Stands in for the admin answering on the escalation channel
+        let admin = broker.clone();
+        tokio::spawn(async move {
+            while let Ok(event) = events.recv().await {
+                if let AgentEvent::ApprovalTimedOut { request, .. } = event {
+                    admin.respond(&request.id, ApprovalDecision::Approved).await;
+                }
+            }
+        });
+
+        let policy = timing_out(1, ApprovalTimeoutAction::Escalate);
+        let decision = broker.decide(test_request("req-t4"), &policy).await;
+        assert!(matches!(decision, ApprovalDecision::Approved));
+    }
 }
@@ -0,0 +1,261 @@
+//! `ryvos bench`: the agent loop's own overhead, without a provider.
+//!
+//! Runs are scripted with a [`ReplayClient`]: every turn but the last calls
+//! a tool that does nothing, and the last one answers. No time goes to the
+//! network or a model, so what is measured is the loop itself: building
+//! the context, storing messages, publishing events and dispatching tools.
+//! Pruning and tool dispatch are also timed on their own. Allocations are
+//! not counted.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use serde::Serialize;
+
+use ryvos_core::config::AppConfig;
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::event::EventBus;
+use ryvos_core::traits::{LlmClient, SessionStore, Tool};
+use ryvos_core::types::*;
+use ryvos_llm::ReplayClient;
+use ryvos_memory::SqliteStore;
+use ryvos_tools::ToolRegistry;
+
+use crate::intelligence::{estimate_message_tokens, prune_to_budget, PrunePolicy};
+use crate::AgentRuntime;
+
+/// Messages in the history the pruning benchmark cuts in half.
+const PRUNE_HISTORY: usize = 400;
+/// Tool calls the dispatch benchmark makes.
+const DISPATCH_CALLS: usize = 1000;
+
+/// Timings from one benchmark.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub runs: usize,
+    /// Turns across all runs.
+    pub turns: usize,
+    /// Wall time of all runs.
+    pub total_ms: f64,
+    /// Mean loop overhead per turn.
+    pub turn_us: f64,
+    pub turns_per_sec: f64,
+    /// Mean time to prune a history of `prune_messages` to half its tokens.
+    pub prune_us: f64,
+    pub prune_messages: usize,
+    /// Mean time for `ToolRegistry::execute` to dispatch a no-op tool.
+    pub tool_dispatch_us: f64,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Agent loop:     {} runs, {} turns in {:.1} ms",
+            self.runs, self.turns, self.total_ms
+        )?;
+        writeln!(
+            f,
+            "Per turn:       {:.1} µs ({:.0} turns/s)",
+            self.turn_us, self.turns_per_sec
+        )?;
+        writeln!(
+            f,
+            "Pruning:        {:.1} µs for {} messages",
+            self.prune_us, self.prune_messages
+        )?;
+        write!(
+            f,
+            "Tool dispatch:  {:.2} µs per call",
+            self.tool_dispatch_us
+        )
+    }
+}
+
+/// Returns "ok" without doing anything.
+struct NoopTool;
+
+impl Tool for NoopTool {
+    fn name(&self) -> &str {
+        "bench_noop"
+    }
+
+    fn description(&self) -> &str {
+        "Does nothing."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object", "properties": {}})
+    }
+
+    fn execute(
+        &self,
+        _input: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async { Ok(ToolResult::success("ok")) })
+    }
+}
+
+/// Responses for `runs` runs of `turns` turns each.
+fn transcript(runs: usize, turns: usize) -> Vec<Vec<StreamDelta>> {
+    let usage = StreamDelta::Usage {
+        input_tokens: 1000,
+        output_tokens: 50,
+        thinking_tokens: 0,
+    };
+    let mut responses = Vec::new();
+    for run in 0..runs {
+        for turn in 0..turns - 1 {
+            responses.push(vec![
+                StreamDelta::ToolUseStart {
+                    index: 0,
+                    id: format!("call_{}_{}", run, turn),
+                    name: "bench_noop".to_string(),
+                },
+                StreamDelta::ToolInputDelta {
+                    index: 0,
+                    delta: "{}".to_string(),
+                },
+                usage.clone(),
+                StreamDelta::Stop(StopReason::ToolUse),
+            ]);
+        }
+        responses.push(vec![
+            StreamDelta::TextDelta(format!("Run {} done.", run)),
+            usage.clone(),
+            StreamDelta::Stop(StopReason::EndTurn),
+        ]);
+    }
+    responses
+}
+
+fn workspace() -> PathBuf {
+    std::env::temp_dir().join("ryvos-bench")
+}
+
+fn mean_us(total: Duration, count: usize) -> f64 {
+    total.as_secs_f64() * 1e6 / count.max(1) as f64
+}
+
+/// Run `runs` scripted runs of `turns` turns each, then the pruning and
+/// tool dispatch benchmarks. Pruning is measured `runs` times as well.
+pub async fn run_bench(runs: usize, turns: usize) -> Result<BenchReport> {
+    if runs == 0 || turns == 0 {
+        return Err(RyvosError::Config(
+            "bench needs at least one run of one turn".to_string(),
+        ));
+    }
+    let config: AppConfig = serde_json::from_value(serde_json::json!({
+        "model": { "provider": "replay", "model_id": "bench" },
+        "agent": {
+            "max_turns": turns,
+            "workspace": workspace().to_string_lossy(),
+        },
+    }))?;
+
+    let mut registry = ToolRegistry::new();
+    registry.register(NoopTool);
+    let tools = Arc::new(tokio::sync::RwLock::new(registry));
+    let store: Arc<dyn SessionStore> = Arc::new(SqliteStore::in_memory()?);
+    let llm: Arc<dyn LlmClient> = Arc::new(ReplayClient::new(transcript(runs, turns)));
+    let runtime = AgentRuntime::new(
+        config.clone(),
+        llm,
+        tools.clone(),
+        store,
+        Arc::new(EventBus::default()),
+    );
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        runtime.run(&SessionId::new(), "Run the benchmark.").await?;
+    }
+    let total = start.elapsed();
+    let total_turns = runs * turns;
+
+    // Pruning: a long history down to half its tokens
+    let mut history = vec![ChatMessage {
+        role: Role::System,
+        content: vec![ContentBlock::Text {
+            text: "You are a benchmark.".to_string(),
+        }],
+        timestamp: None,
+        metadata: None,
+    }];
+    for i in 0..PRUNE_HISTORY / 2 {
+        history.push(ChatMessage::user(format!(
+            "Question {}: {}",
+            i,
+            "lorem ipsum ".repeat(20)
+        )));
+        history.push(ChatMessage::assistant_text(format!(
+            "Answer {}: {}",
+            i,
+            "dolor sit amet ".repeat(20)
+        )));
+    }
+    let budget = history.iter().map(estimate_message_tokens).sum::<usize>() / 2;
+    let policy = PrunePolicy::from_config(&config.agent.context);
+    let mut prune_time = Duration::ZERO;
+    for _ in 0..runs {
+        let mut messages = history.clone();
+        let started = Instant::now();
+        prune_to_budget(&mut messages, budget, &policy);
+        prune_time += started.elapsed();
+    }
+
+    // Tool dispatch through the registry, as the loop does it
+    let ctx = ToolContext {
+        session_id: SessionId::new(),
+        working_dir: workspace(),
+        store: None,
+        agent_spawner: None,
+        sandbox_config: None,
+        config_path: None,
+        viking_client: None,
+        agent_depth: 0,
+        event_bus: None,
+    };
+    let registry = tools.read().await;
+    let started = Instant::now();
+    for _ in 0..DISPATCH_CALLS {
+        registry
+            .execute("bench_noop", serde_json::json!({}), ctx.clone())
+            .await?;
+    }
+    let dispatch_time = started.elapsed();
+
+    Ok(BenchReport {
+        runs,
+        turns: total_turns,
+        total_ms: total.as_secs_f64() * 1e3,
+        turn_us: mean_us(total, total_turns),
+        turns_per_sec: total_turns as f64 / total.as_secs_f64().max(f64::EPSILON),
+        prune_us: mean_us(prune_time, runs),
+        prune_messages: history.len(),
+        tool_dispatch_us: mean_us(dispatch_time, DISPATCH_CALLS),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scripted_bench_run_reports_timings() {
+        let report = run_bench(2, 3).await.unwrap();
+        assert_eq!(report.runs, 2);
+        assert_eq!(report.turns, 6);
+        assert!(report.total_ms > 0.0);
+        assert!(report.turn_us > 0.0 && report.turns_per_sec > 0.0);
+        assert!(report.prune_us > 0.0);
+        assert_eq!(report.prune_messages, PRUNE_HISTORY + 1);
+        assert!(report.tool_dispatch_us > 0.0);
+        assert!(report.to_string().contains("6 turns"));
+
+        assert!(run_bench(1, 0).await.is_err());
+    }
+}
@@ -22,6 +22,14 @@ impl ContextBuilder {
         Self { parts: Vec::new() }
     }
 
+    /// Add the current date and time. Empty adds nothing.
+    pub fn with_current_time(mut self, now: &str) -> Self {
+        if !now.is_empty() {
+            self.parts.push(format!("Current date and time: {}", now));
+        }
+        self
+    }
+
     /// Add a base system prompt.
     pub fn with_base_prompt(mut self, prompt: &str) -> Self {
         self.parts.push(prompt.to_string());
@@ -101,11 +109,9 @@ impl ContextBuilder {
     ) -> Self {
         match mode {
             "never" => return self,
-            "relevant" => {
-                if !query_hint_is_temporal(query_hint.unwrap_or("")) {
-                    debug!("Skipping daily logs — query not temporal");
-                    return self;
-                }
+            "relevant" if !query_hint_is_temporal(query_hint.unwrap_or("")) => {
+                debug!("Skipping daily logs — query not temporal");
+                return self;
             }
             _ => {} // "always" or unknown → load
         }
@@ -146,13 +152,11 @@ impl ContextBuilder {
         self
     }
 
-    /// Layer 2b (Narrative): Inject a conversation summary from a prior compaction.
+    /// Layer 2b (Narrative): Inject the rolling summary of this session.
     pub fn with_summary(mut self, summary: &str) -> Self {
         if !summary.is_empty() {
-            self.parts.push(format!(
-                "# Previous Conversation Summary\n\n{}",
-                summary.trim()
-            ));
+            self.parts
+                .push(format!("# Session Summary So Far\n\n{}", summary.trim()));
         }
         self
     }
@@ -246,6 +250,13 @@ When something goes wrong — a tool fails, the user corrects you, or you notice
 3. If the user says "that was wrong", "don't do that", or expresses frustration — that's a correction. Record it immediately.
 4. The goal: never make the same mistake twice. Your lessons persist across sessions and restarts.
 
+## Structured Results
+When a reply carries tabular or key/value data (disk usage, a list of hosts, build results), put it in a fenced code block tagged `ryvos-result` holding JSON, instead of a markdown table:
+```ryvos-result
+{"title": "Disk usage", "fields": [{"name": "Host", "value": "web-1"}], "columns": ["Mount", "Used"], "rows": [["/", "71%"], ["/var", "38%"]]}
+```
+Every key is optional. Channels that can render it natively (Slack blocks, Discord embeds) do; everywhere else it is shown as markdown. Keep prose outside the block.
+
 ## Safety Constitution
 
 You are a capable, trusted agent. You have access to all tools with no restrictions.
@@ -317,6 +328,10 @@ pub struct ExtendedContext {
     pub daily_log_mode: String,
     /// Number of daily log days to load.
     pub daily_log_days: usize,
+    /// Current date and time, put first when `inject_datetime` is on.
+    pub current_time: String,
+    /// Rolling summary of the session when `session_summary` is on.
+    pub session_summary: String,
 }
 
 /// Build the default context for an agent run using the three-layer onion model.
@@ -327,9 +342,11 @@ pub fn build_default_context(
     workspace: &Path,
     system_prompt_override: Option<&str>,
 ) -> ChatMessage {
-    let mut ext = ExtendedContext::default();
-    ext.daily_log_mode = "always".to_string();
-    ext.daily_log_days = 3;
+    let ext = ExtendedContext {
+        daily_log_mode: "always".to_string(),
+        daily_log_days: 3,
+        ..Default::default()
+    };
     build_default_context_extended(workspace, system_prompt_override, &ext)
 }
 
@@ -356,11 +373,13 @@ pub fn build_default_context_extended(
     };
 
     let mut builder = ContextBuilder::new()
+        .with_current_time(&extended.current_time)
         .with_base_prompt(DEFAULT_SYSTEM_PROMPT)
         // Layer 1: Identity
         .with_identity_layer(workspace)
         // Layer 2: Narrative
         .with_narrative_layer(workspace)
+        .with_summary(&extended.session_summary)
         // Layer 2b: Daily logs (conditional on mode + query)
         .with_daily_logs(workspace, log_days, log_mode, hint)
         // Layer 2.5: Recall (Viking sustained context)
@@ -385,9 +404,11 @@ pub fn build_goal_context(
     system_prompt_override: Option<&str>,
     goal: Option<&Goal>,
 ) -> ChatMessage {
-    let mut ext = ExtendedContext::default();
-    ext.daily_log_mode = "always".to_string();
-    ext.daily_log_days = 3;
+    let ext = ExtendedContext {
+        daily_log_mode: "always".to_string(),
+        daily_log_days: 3,
+        ..Default::default()
+    };
     build_goal_context_extended(workspace, system_prompt_override, goal, &ext)
 }
 
@@ -415,11 +436,13 @@ pub fn build_goal_context_extended(
     };
 
     let mut builder = ContextBuilder::new()
+        .with_current_time(&extended.current_time)
         .with_base_prompt(DEFAULT_SYSTEM_PROMPT)
         // Layer 1: Identity
         .with_identity_layer(workspace)
         // Layer 2: Narrative
         .with_narrative_layer(workspace)
+        .with_summary(&extended.session_summary)
         // Layer 2b: Daily logs (conditional on mode + query)
         .with_daily_logs(workspace, log_days, log_mode, hint)
         // Layer 2.5: Recall (Viking sustained context)
@@ -0,0 +1,232 @@
+//! Reporting over the decision audit trail.
+//!
+//! Turns [`Decision`]s loaded from the [`FailureJournal`](crate::FailureJournal)
+//! into the text and JSON printed by `ryvos decisions`, with per-option
+//! aggregates over the decisions whose outcome has been backfilled.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use ryvos_core::types::Decision;
+
+/// Aggregate outcomes for one chosen option.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OptionStats {
+    pub chosen_option: String,
+    /// Decisions that picked this option.
+    pub decisions: usize,
+    /// How many of those have a recorded outcome.
+    pub with_outcome: usize,
+    pub succeeded: usize,
+    /// Mean latency over decisions with an outcome.
+    pub avg_latency_ms: Option<f64>,
+    /// `succeeded / with_outcome`, from 0.0 to 1.0.
+    pub success_rate: Option<f64>,
+}
+
+/// Per-option aggregates, most frequently chosen first.
+pub fn option_stats(decisions: &[Decision]) -> Vec<OptionStats> {
+    let mut by_option: BTreeMap<&str, (usize, usize, usize, u64)> = BTreeMap::new();
+    for d in decisions {
+        let entry = by_option.entry(d.chosen_option.as_str()).or_default();
+        entry.0 += 1;
+        if let Some(ref outcome) = d.outcome {
+            entry.1 += 1;
+            entry.2 += usize::from(outcome.succeeded);
+            entry.3 += outcome.latency_ms;
+        }
+    }
+
+    let mut stats: Vec<OptionStats> = by_option
+        .into_iter()
+        .map(|(option, (count, with_outcome, succeeded, latency))| {
+            let measured = (with_outcome > 0).then_some(with_outcome as f64);
+            OptionStats {
+                chosen_option: option.to_string(),
+                decisions: count,
+                with_outcome,
+                succeeded,
+                avg_latency_ms: measured.map(|n| latency as f64 / n),
+                success_rate: measured.map(|n| succeeded as f64 / n),
+            }
+        })
+        .collect();
+    // Stable sort keeps ties in name order
+    stats.sort_by_key(|s| std::cmp::Reverse(s.decisions));
+    stats
+}
+
+/// Human-readable report: one block per decision, then per-option stats.
+/// `total` is the number of decisions in the journal.
+pub fn format_decisions(decisions: &[Decision], total: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Agent Decisions ({} total, showing {}):",
+        total,
+        decisions.len()
+    );
+    if decisions.is_empty() {
+        let _ = writeln!(out, "  No decisions recorded yet.");
+        return out;
+    }
+
+    for d in decisions {
+        let _ = writeln!(out, "  ──────────────────────────────────────");
+        let _ = writeln!(out, "  Decision:  {}", d.description);
+        let _ = writeln!(out, "  Chosen:    {}", d.chosen_option);
+        if !d.alternatives.is_empty() {
+            let alts: Vec<String> = d
+                .alternatives
+                .iter()
+                .map(|a| match a.confidence {
+                    Some(c) => format!("{} ({:.2})", a.name, c),
+                    None => a.name.clone(),
+                })
+                .collect();
+            let _ = writeln!(out, "  Alternatives: {}", alts.join(", "));
+        }
+        let outcome = match d.outcome {
+            Some(ref o) => format!(
+                "{} in {} ms ({} tokens)",
+                if o.succeeded { "succeeded" } else { "failed" },
+                o.latency_ms,
+                o.tokens_used
+            ),
+            None => "pending".to_string(),
+        };
+        let _ = writeln!(out, "  Outcome:   {}", outcome);
+        let _ = writeln!(
+            out,
+            "  Session:   {}  Turn: {}",
+            &d.session_id[..8.min(d.session_id.len())],
+            d.turn
+        );
+        let _ = writeln!(out, "  Time:      {}", d.timestamp.format("%Y-%m-%d %H:%M"));
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "By chosen option:");
+    for s in option_stats(decisions) {
+        let rate = match s.success_rate {
+            Some(r) => format!("{:.0}% ({}/{})", r * 100.0, s.succeeded, s.with_outcome),
+            None => "n/a".to_string(),
+        };
+        let latency = match s.avg_latency_ms {
+            Some(ms) => format!("{:.0} ms", ms),
+            None => "n/a".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "  {:<20} {:>4} chosen  success {:<14} avg latency {}",
+            s.chosen_option, s.decisions, rate, latency
+        );
+    }
+    out
+}
+
+/// JSON export: `{ "total", "decisions": [...], "stats": [...] }`.
+pub fn decisions_json(decisions: &[Decision], total: usize) -> serde_json::Value {
+    serde_json::json!({
+        "total": total,
+        "decisions": decisions,
+        "stats": option_stats(decisions),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FailureJournal;
+    use chrono::Utc;
+    use ryvos_core::types::{DecisionOption, DecisionOutcome};
+
+    fn decision(id: &str, session: &str, chosen: &str, outcome: Option<(u64, bool)>) -> Decision {
+        Decision {
+            id: id.into(),
+            timestamp: Utc::now(),
+            session_id: session.into(),
+            turn: 1,
+            description: format!("Pick a tool for {}", id),
+            chosen_option: chosen.into(),
+            alternatives: vec![DecisionOption {
+                name: "bash".into(),
+                confidence: Some(0.25),
+            }],
+            outcome: outcome.map(|(latency_ms, succeeded)| DecisionOutcome {
+                tokens_used: 100,
+                latency_ms,
+                succeeded,
+            }),
+        }
+    }
+
+    fn seeded_journal() -> FailureJournal {
+        let dir =
+            std::env::temp_dir().join(format!("ryvos_decisions_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journal = FailureJournal::open(&dir.join("healing.db")).unwrap();
+        for d in [
+            decision("d1", "session-a1", "read", Some((40, true))),
+            decision("d2", "session-a1", "read", Some((60, false))),
+            decision("d3", "session-a1", "grep", Some((10, true))),
+            decision("d4", "session-a1", "read", None),
+            decision("d5", "session-b2", "write", Some((5, true))),
+        ] {
+            journal.record_decision(&d).unwrap();
+        }
+        journal
+    }
+
+    #[test]
+    fn stats_aggregate_latency_and_success_per_option() {
+        let journal = seeded_journal();
+        let decisions = journal.search_decisions(Some("session-a"), 50).unwrap();
+        assert_eq!(decisions.len(), 4);
+
+        let stats = option_stats(&decisions);
+        assert_eq!(
+            stats[0],
+            OptionStats {
+                chosen_option: "read".into(),
+                decisions: 3,
+                with_outcome: 2,
+                succeeded: 1,
+                avg_latency_ms: Some(50.0),
+                success_rate: Some(0.5),
+            }
+        );
+        assert_eq!(stats[1].chosen_option, "grep");
+        assert_eq!(stats[1].success_rate, Some(1.0));
+    }
+
+    #[test]
+    fn report_prints_decisions_and_aggregates() {
+        let journal = seeded_journal();
+        let decisions = journal.search_decisions(Some("session-a"), 50).unwrap();
+        let text = format_decisions(&decisions, journal.count_decisions().unwrap());
+
+        assert!(text.starts_with("Agent Decisions (5 total, showing 4):"));
+        assert!(text.contains("Decision:  Pick a tool for d1"));
+        assert!(text.contains("Alternatives: bash (0.25)"));
+        assert!(text.contains("Outcome:   failed in 60 ms (100 tokens)"));
+        assert!(text.contains("Outcome:   pending"));
+        assert!(!text.contains("write"));
+        assert!(text.contains("50% (1/2)"));
+        assert!(text.contains("avg latency 50 ms"));
+
+        let json = decisions_json(&decisions, 5);
+        assert_eq!(json["decisions"].as_array().unwrap().len(), 4);
+        assert_eq!(json["stats"][0]["chosen_option"], "read");
+        assert_eq!(json["stats"][0]["avg_latency_ms"], 50.0);
+    }
+
+    #[test]
+    fn report_without_decisions() {
+        let text = format_decisions(&[], 0);
+        assert!(text.contains("No decisions recorded yet."));
+        assert!(option_stats(&[]).is_empty());
+    }
+}
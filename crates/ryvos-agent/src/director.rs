@@ -262,7 +262,7 @@ Keep the graph minimal (2-5 nodes). Respond with ONLY the JSON object."#,
         // use Judge for full evaluation
         let judge = Judge::new(self.llm.clone(), self.config.clone());
         match judge.evaluate(output, &[], &goal_obj.goal).await {
-            Ok(verdict) => matches!(verdict, ryvos_core::types::Verdict::Accept { .. }),
+            Ok(judgment) => matches!(judgment.verdict, ryvos_core::types::Verdict::Accept { .. }),
             Err(_) => false,
         }
     }
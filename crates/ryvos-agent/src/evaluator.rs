@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use futures::StreamExt;
@@ -109,18 +110,41 @@ Evaluate whether the task was completed successfully. Respond with ONLY valid JS
 pub struct GoalEvaluator {
     llm: Arc<dyn LlmClient>,
     config: ModelConfig,
+    workspace: Option<PathBuf>,
+    allowed_commands: Vec<String>,
 }
 
 impl GoalEvaluator {
     pub fn new(llm: Arc<dyn LlmClient>, config: ModelConfig) -> Self {
-        Self { llm, config }
+        Self {
+            llm,
+            config,
+            workspace: None,
+            allowed_commands: vec![],
+        }
+    }
+
+    /// Enable workspace criteria (file and shell checks) against `workspace`.
+    /// Only commands in `allowed_commands` may be run by shell checks.
+    pub fn with_workspace(mut self, workspace: PathBuf, allowed_commands: Vec<String>) -> Self {
+        self.workspace = Some(workspace);
+        self.allowed_commands = allowed_commands;
+        self
     }
 
     /// Evaluate agent output against a goal.
-    /// First evaluates deterministic criteria, then uses LLM for LlmJudge criteria.
+    /// First evaluates deterministic criteria, then workspace criteria (if a
+    /// workspace is set), then uses LLM for LlmJudge criteria.
     pub async fn evaluate(&self, output: &str, goal: &Goal) -> Result<GoalEvaluation, String> {
         let mut results = goal.evaluate_deterministic(output);
 
+        if let Some(ref workspace) = self.workspace {
+            results.extend(
+                goal.evaluate_workspace(workspace, &self.allowed_commands)
+                    .await,
+            );
+        }
+
         // Evaluate LlmJudge criteria
         for criterion in &goal.success_criteria {
             if let CriterionType::LlmJudge { prompt } = &criterion.criterion_type {
@@ -114,11 +114,9 @@ impl GraphExecutor {
             // Execute node
             let node_start = Instant::now();
             let session = SessionId::new();
-            let result = if let Some(ref goal) = node.goal {
-                runtime.run_with_goal(&session, &prompt, Some(goal)).await
-            } else {
-                runtime.run(&session, &prompt).await
-            };
+            let result = runtime
+                .run_without_summary(&session, &prompt, node.goal.as_ref())
+                .await;
 
             let elapsed_ms = node_start.elapsed().as_millis() as u64;
             let (output, succeeded) = match result {
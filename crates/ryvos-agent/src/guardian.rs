@@ -13,11 +13,17 @@
 //!    120s) during an active run. Injects a hint suggesting alternative actions.
 //!
 //! 3. **Token budget**: Warns at 80% of the configured token budget, cancels
-//!    the run at 100%.
+//!    the run at 100%. `cost_budget_usd` does the same for the run's cost,
+//!    priced from the model's rates; models without pricing are not checked.
 //!
 //! 4. **Dollar budget**: Reads monthly spend from CostStore, warns at
 //!    `warn_pct` of `monthly_budget_cents`, hard-stops at `hard_stop_pct`.
 //!
+//! Repeated final answers are caught in the agent loop itself, since the
+//! action has to land before the judge's next retry:
+//! [`repeats_earlier_answer`] decides, and `repeated_answer_action` picks
+//! escalate, fallback or stop.
+//!
 //! Actions are sent through an `mpsc` channel to the agent loop, which
 //! processes them between turns: `InjectHint` adds a user message,
 //! `CancelRun` fires the CancellationToken.
@@ -61,26 +67,41 @@ pub struct Guardian {
     hint_tx: mpsc::Sender<GuardianAction>,
     cost_store: Option<Arc<CostStore>>,
     budget_config: Option<BudgetConfig>,
+    /// Model whose rates price `cost_budget_usd`.
+    model_id: Option<String>,
 }
 
 impl Guardian {
     /// Create a new Guardian and its action receiver.
-    /// The receiver should be passed to `AgentRuntime::set_guardian_hints()`.
     pub fn new(
         config: GuardianConfig,
         event_bus: Arc<EventBus>,
         cancel: CancellationToken,
     ) -> (Self, mpsc::Receiver<GuardianAction>) {
         let (hint_tx, hint_rx) = mpsc::channel(32);
-        let guardian = Self {
+        (
+            Self::with_hint_sender(config, event_bus, cancel, hint_tx),
+            hint_rx,
+        )
+    }
+
+    /// Create a Guardian that sends its actions into an existing channel,
+    /// typically `AgentRuntime::hint_sender()`.
+    pub fn with_hint_sender(
+        config: GuardianConfig,
+        event_bus: Arc<EventBus>,
+        cancel: CancellationToken,
+        hint_tx: mpsc::Sender<GuardianAction>,
+    ) -> Self {
+        Self {
             config,
             event_bus,
             cancel,
             hint_tx,
             cost_store: None,
             budget_config: None,
-        };
-        (guardian, hint_rx)
+            model_id: None,
+        }
     }
 
     /// Set the cost store and budget config for dollar-based budget enforcement.
@@ -89,6 +110,11 @@ impl Guardian {
         self.budget_config = Some(budget_config);
     }
 
+    /// Set the model used to price runs against `cost_budget_usd`.
+    pub fn set_model(&mut self, model_id: impl Into<String>) {
+        self.model_id = Some(model_id.into());
+    }
+
     /// Run the Guardian event loop. Spawned as a tokio task.
     pub async fn run(self, session_id: SessionId) {
         let mut rx = self.event_bus.subscribe();
@@ -134,6 +160,29 @@ impl Guardian {
             .map(|b| b.pricing.clone())
             .unwrap_or_default();
 
+        // Per-run cost budget, only when the model's pricing is known
+        let cost_budget = self.config.cost_budget_usd.filter(|b| *b > 0.0);
+        let cost_rates = match (cost_budget, self.model_id.as_deref()) {
+            (Some(_), Some(model)) => {
+                let rates = ryvos_memory::model_pricing(model, &pricing_overrides);
+                if rates.is_none() {
+                    warn!(
+                        model,
+                        "Guardian: no pricing for model, cost budget not enforced"
+                    );
+                }
+                rates
+            }
+            (Some(_), None) => {
+                warn!("Guardian: no model set, cost budget not enforced");
+                None
+            }
+            _ => None,
+        };
+        let (mut run_input, mut run_output) = (0u64, 0u64);
+        let mut cost_warned = false;
+        let mut cost_stopped = false;
+
         info!("Guardian watchdog started");
 
         loop {
@@ -142,6 +191,7 @@ impl Guardian {
             tokio::select! {
                 event = rx.recv() => {
                     let Ok(event) = event else { break };
+                    let Some(event) = parent_view(event) else { continue };
                     match event {
                         AgentEvent::RunStarted { .. } => {
                             run_active = true;
@@ -196,11 +246,15 @@ impl Guardian {
                                 }
                             }
                         }
-                        AgentEvent::ToolEnd { .. } | AgentEvent::TurnComplete { .. } => {
+                        AgentEvent::ToolProgress { .. }
+                        | AgentEvent::ToolEnd { .. }
+                        | AgentEvent::TurnComplete { .. } => {
                             last_progress = Instant::now();
                         }
-                        AgentEvent::UsageUpdate { input_tokens, output_tokens } => {
+                        AgentEvent::UsageUpdate { input_tokens, output_tokens, .. } => {
                             total_tokens += input_tokens + output_tokens;
+                            run_input += input_tokens;
+                            run_output += output_tokens;
 
                             if token_budget > 0 && !hard_stopped {
                                 let warn_threshold = token_budget * warn_pct / 100;
@@ -216,6 +270,8 @@ impl Guardian {
                                         session_id: session_id.clone(),
                                         used_tokens: total_tokens,
                                         budget_tokens: token_budget,
+                                        used_usd: None,
+                                        budget_usd: None,
                                         is_hard_stop: false,
                                     });
                                     let hint = format!(
@@ -241,6 +297,8 @@ impl Guardian {
                                         session_id: session_id.clone(),
                                         used_tokens: total_tokens,
                                         budget_tokens: token_budget,
+                                        used_usd: None,
+                                        budget_usd: None,
                                         is_hard_stop: true,
                                     });
                                     let reason = format!(
@@ -252,6 +310,58 @@ impl Guardian {
                                 }
                             }
 
+                            // Per-run cost budget
+                            if let (Some(budget_usd), Some((input_rate, output_rate))) =
+                                (cost_budget, cost_rates)
+                            {
+                                let used_usd = (run_input * input_rate + run_output * output_rate)
+                                    as f64
+                                    / 100_000_000.0;
+                                let alert = |is_hard_stop| AgentEvent::GuardianBudgetAlert {
+                                    session_id: session_id.clone(),
+                                    used_tokens: total_tokens,
+                                    budget_tokens: token_budget,
+                                    used_usd: Some(used_usd),
+                                    budget_usd: Some(budget_usd),
+                                    is_hard_stop,
+                                };
+
+                                if !cost_warned
+                                    && !cost_stopped
+                                    && used_usd >= budget_usd * warn_pct as f64 / 100.0
+                                {
+                                    cost_warned = true;
+                                    warn!(used_usd, budget_usd, "Guardian: cost budget warning");
+                                    self.event_bus.publish(alert(false));
+                                    let hint = format!(
+                                        "[Guardian] Cost budget warning: ${:.2} / ${:.2} this run. \
+                                         Please wrap up your current task efficiently.",
+                                        used_usd, budget_usd
+                                    );
+                                    self.event_bus.publish(AgentEvent::GuardianHint {
+                                        session_id: session_id.clone(),
+                                        message: hint.clone(),
+                                    });
+                                    let _ = self.hint_tx.send(GuardianAction::InjectHint(hint)).await;
+                                }
+
+                                if !cost_stopped && used_usd >= budget_usd {
+                                    cost_stopped = true;
+                                    warn!(
+                                        used_usd,
+                                        budget_usd,
+                                        "Guardian: cost budget exceeded — cancelling run"
+                                    );
+                                    self.event_bus.publish(alert(true));
+                                    let reason = format!(
+                                        "Cost budget exceeded: ${:.2} / ${:.2}",
+                                        used_usd, budget_usd
+                                    );
+                                    let _ = self.hint_tx.send(GuardianAction::CancelRun(reason)).await;
+                                    self.cancel.cancel();
+                                }
+                            }
+
                             // Dollar budget enforcement
                             if budget_cents > 0 && !dollar_stopped {
                                 if let Some(ref cost_store) = self.cost_store {
@@ -337,6 +447,9 @@ impl Guardian {
                             total_tokens = 0;
                             warned = false;
                             hard_stopped = false;
+                            (run_input, run_output) = (0, 0);
+                            cost_warned = false;
+                            cost_stopped = false;
                             // Don't reset dollar_warned/dollar_stopped — monthly budget persists
                         }
                         _ => {}
@@ -376,6 +489,45 @@ impl Guardian {
     }
 }
 
+/// Share of distinct words two final answers must have in common to count
+/// as the same answer.
+const REPEATED_ANSWER_SIMILARITY: f64 = 0.9;
+
+/// Whether `answer` is near-identical to one of the run's `previous` final
+/// answers: the same words, ignoring case, punctuation and order, with a
+/// few allowed to differ.
+pub fn repeats_earlier_answer(answer: &str, previous: &[String]) -> bool {
+    fn words(text: &str) -> std::collections::HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+    let answer = words(answer);
+    previous.iter().any(|earlier| {
+        let earlier = words(earlier);
+        let union = answer.union(&earlier).count();
+        union == 0
+            || answer.intersection(&earlier).count() as f64 / union as f64
+                >= REPEATED_ANSWER_SIMILARITY
+    })
+}
+
+/// How the watchdog sees `event`. A sub-agent's usage and tool activity
+/// count toward the parent's run, so they are unwrapped at any depth; its
+/// other events, such as its own `RunComplete`, are ignored.
+fn parent_view(event: AgentEvent) -> Option<AgentEvent> {
+    let AgentEvent::SubAgent { event, .. } = event else {
+        return Some(event);
+    };
+    match parent_view(*event)? {
+        inner @ (AgentEvent::UsageUpdate { .. }
+        | AgentEvent::ToolStart { .. }
+        | AgentEvent::ToolProgress { .. }) => Some(inner),
+        _ => None,
+    }
+}
+
 /// Normalize a JSON value into a canonical fingerprint for doom loop detection.
 /// Sorts object keys recursively, strips whitespace, takes first 300 chars.
 /// This catches LLM retry patterns where it varies whitespace or argument order.
@@ -404,6 +556,24 @@ fn normalize_fingerprint(input: &serde_json::Value) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn near_identical_answers_count_as_repeats() {
+        let previous = vec!["I could not find the config file. Please check the path.".to_string()];
+        assert!(repeats_earlier_answer(
+            "i could not find the CONFIG file.  please check the path",
+            &previous
+        ));
+        assert!(repeats_earlier_answer(
+            "I could not find the config file. Please check the path again.",
+            &previous
+        ));
+        assert!(!repeats_earlier_answer(
+            "The config file is at ~/.ryvos/config.toml and sets max_turns to 25.",
+            &previous
+        ));
+        assert!(!repeats_earlier_answer("anything", &[]));
+    }
+
     #[test]
     fn fingerprint_normalizes_key_order() {
         let a = serde_json::json!({"b": 1, "a": 2});
@@ -428,6 +598,8 @@ mod tests {
             stall_timeout_secs: 300, // long timeout to avoid interference
             token_budget: 0,
             token_warn_pct: 80,
+            cost_budget_usd: None,
+            ..Default::default()
         };
 
         let (guardian, mut hint_rx) = Guardian::new(config, event_bus.clone(), cancel.clone());
@@ -474,6 +646,8 @@ mod tests {
             stall_timeout_secs: 300,
             token_budget: 0,
             token_warn_pct: 80,
+            cost_budget_usd: None,
+            ..Default::default()
         };
 
         let (guardian, mut hint_rx) = Guardian::new(config, event_bus.clone(), cancel.clone());
@@ -520,6 +694,8 @@ mod tests {
             stall_timeout_secs: 1, // 1 second for fast test
             token_budget: 0,
             token_warn_pct: 80,
+            cost_budget_usd: None,
+            ..Default::default()
         };
 
         let (guardian, mut hint_rx) = Guardian::new(config, event_bus.clone(), cancel.clone());
@@ -561,6 +737,8 @@ mod tests {
             stall_timeout_secs: 1, // 1 second
             token_budget: 0,
             token_warn_pct: 80,
+            cost_budget_usd: None,
+            ..Default::default()
         };
 
         let (guardian, mut hint_rx) = Guardian::new(config, event_bus.clone(), cancel.clone());
@@ -580,6 +758,219 @@ mod tests {
         handle.await.ok();
     }
 
+    fn cost_guardian(
+        model: &str,
+        event_bus: &Arc<EventBus>,
+        cancel: &CancellationToken,
+    ) -> (Guardian, mpsc::Receiver<GuardianAction>) {
+        let config = GuardianConfig {
+            cost_budget_usd: Some(0.05),
+            ..Default::default()
+        };
+        let (mut guardian, hint_rx) = Guardian::new(config, event_bus.clone(), cancel.clone());
+        guardian.set_model(model);
+        (guardian, hint_rx)
+    }
+
+    #[tokio::test]
+    async fn cost_budget_stops_run() {
+        let event_bus = Arc::new(EventBus::default());
+        let cancel = CancellationToken::new();
+        let (guardian, mut hint_rx) = cost_guardian("claude-sonnet-4", &event_bus, &cancel);
+        let mut events = event_bus.subscribe();
+        let handle = tokio::spawn(guardian.run(SessionId::new()));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // $3 / $15 per Mtok: 10k in + 1k out = $0.045, past the 80% warning
+        event_bus.publish(AgentEvent::UsageUpdate {
+            input_tokens: 10_000,
+            output_tokens: 1_000,
+            thinking_tokens: 0,
+        });
+        let hint = tokio::time::timeout(std::time::Duration::from_secs(2), hint_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(hint, GuardianAction::InjectHint(ref m) if m.contains("$0.04 / $0.05")));
+        assert!(!cancel.is_cancelled());
+
+        event_bus.publish(AgentEvent::UsageUpdate {
+            input_tokens: 2_000,
+            output_tokens: 0,
+            thinking_tokens: 0,
+        });
+        let stop = tokio::time::timeout(std::time::Duration::from_secs(2), hint_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            matches!(stop, GuardianAction::CancelRun(ref m) if m.starts_with("Cost budget exceeded"))
+        );
+        handle.await.ok();
+        assert!(cancel.is_cancelled());
+
+        let mut alerts = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::GuardianBudgetAlert {
+                used_usd,
+                budget_usd,
+                is_hard_stop,
+                ..
+            } = event
+            {
+                assert_eq!(budget_usd, Some(0.05));
+                alerts.push((used_usd.unwrap(), is_hard_stop));
+            }
+        }
+        assert_eq!(alerts.len(), 2);
+        assert!(!alerts[0].1 && alerts[1].1);
+        assert!((alerts[1].0 - 0.051).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn cost_budget_ignores_unpriced_model() {
+        let event_bus = Arc::new(EventBus::default());
+        let cancel = CancellationToken::new();
+        let (guardian, mut hint_rx) = cost_guardian("local-llama-70b", &event_bus, &cancel);
+        let handle = tokio::spawn(guardian.run(SessionId::new()));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        event_bus.publish(AgentEvent::UsageUpdate {
+            input_tokens: 5_000_000,
+            output_tokens: 1_000_000,
+            thinking_tokens: 0,
+        });
+        let result =
+            tokio::time::timeout(std::time::Duration::from_millis(200), hint_rx.recv()).await;
+        assert!(
+            result.is_err(),
+            "unpriced model should not trip the cost budget"
+        );
+        assert!(!cancel.is_cancelled());
+
+        cancel.cancel();
+        handle.await.ok();
+    }
+
+    fn from_sub_agent(event: AgentEvent) -> AgentEvent {
+        AgentEvent::SubAgent {
+            session_id: SessionId::from_string("sub"),
+            depth: 1,
+            event: Box::new(event),
+        }
+    }
+
+    /// A started Guardian with a 1s stall timeout and the given token budget.
+    async fn sub_agent_guardian(
+        token_budget: u64,
+    ) -> (
+        Arc<EventBus>,
+        CancellationToken,
+        mpsc::Receiver<GuardianAction>,
+        tokio::task::JoinHandle<()>,
+    ) {
+        let event_bus = Arc::new(EventBus::default());
+        let cancel = CancellationToken::new();
+        let config = GuardianConfig {
+            doom_loop_threshold: 3,
+            stall_timeout_secs: 1,
+            token_budget,
+            ..Default::default()
+        };
+        let (guardian, hint_rx) = Guardian::new(config, event_bus.clone(), cancel.clone());
+        let handle = tokio::spawn(guardian.run(SessionId::new()));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        (event_bus, cancel, hint_rx, handle)
+    }
+
+    #[tokio::test]
+    async fn sub_agent_usage_counts_toward_token_budget() {
+        let (event_bus, cancel, mut hint_rx, handle) = sub_agent_guardian(1_000).await;
+        event_bus.publish(from_sub_agent(AgentEvent::UsageUpdate {
+            input_tokens: 900,
+            output_tokens: 200,
+            thinking_tokens: 0,
+        }));
+
+        let mut actions = Vec::new();
+        while let Ok(Some(action)) =
+            tokio::time::timeout(std::time::Duration::from_millis(500), hint_rx.recv()).await
+        {
+            actions.push(action);
+        }
+        assert!(actions
+            .iter()
+            .any(|a| matches!(a, GuardianAction::CancelRun(m) if m.contains("1100/1000"))));
+        assert!(cancel.is_cancelled());
+        handle.await.ok();
+    }
+
+    #[tokio::test]
+    async fn sub_agent_tool_calls_are_checked_for_doom_loops() {
+        let (event_bus, cancel, mut hint_rx, handle) = sub_agent_guardian(0).await;
+        let input = serde_json::json!({"command": "echo hello"});
+        for _ in 0..3 {
+            event_bus.publish(from_sub_agent(AgentEvent::ToolStart {
+                name: "bash".to_string(),
+                input: input.clone(),
+            }));
+        }
+        let action = tokio::time::timeout(std::time::Duration::from_secs(2), hint_rx.recv())
+            .await
+            .expect("timeout waiting for hint")
+            .expect("channel closed");
+        assert!(matches!(action, GuardianAction::InjectHint(ref m) if m.contains("3 times")));
+        cancel.cancel();
+        handle.await.ok();
+    }
+
+    #[tokio::test]
+    async fn sub_agent_progress_keeps_run_from_stalling() {
+        let (event_bus, cancel, mut hint_rx, handle) = sub_agent_guardian(0).await;
+        event_bus.publish(AgentEvent::RunStarted {
+            session_id: SessionId::new(),
+        });
+        for _ in 0..6 {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            event_bus.publish(from_sub_agent(AgentEvent::ToolProgress {
+                name: "bash".to_string(),
+                chunk: "building...\n".to_string(),
+            }));
+        }
+        assert!(
+            hint_rx.try_recv().is_err(),
+            "sub-agent progress is progress"
+        );
+        cancel.cancel();
+        handle.await.ok();
+    }
+
+    #[tokio::test]
+    async fn sub_agent_run_complete_keeps_parent_tracking() {
+        let (event_bus, cancel, mut hint_rx, handle) = sub_agent_guardian(0).await;
+        let call = || AgentEvent::ToolStart {
+            name: "bash".to_string(),
+            input: serde_json::json!({"command": "ls"}),
+        };
+        event_bus.publish(call());
+        event_bus.publish(call());
+        event_bus.publish(from_sub_agent(AgentEvent::RunComplete {
+            session_id: SessionId::from_string("sub"),
+            total_turns: 1,
+            input_tokens: 0,
+            output_tokens: 0,
+            context_tokens: 0,
+        }));
+        event_bus.publish(call());
+        let action = tokio::time::timeout(std::time::Duration::from_secs(2), hint_rx.recv())
+            .await
+            .expect("the sub-agent's RunComplete reset the parent's calls")
+            .expect("channel closed");
+        assert!(matches!(action, GuardianAction::InjectHint(ref m) if m.contains("3 times")));
+        cancel.cancel();
+        handle.await.ok();
+    }
+
     #[test]
     fn guardian_config_defaults() {
         let config = GuardianConfig::default();
@@ -588,5 +979,6 @@ mod tests {
         assert_eq!(config.stall_timeout_secs, 120);
         assert_eq!(config.token_budget, 0);
         assert_eq!(config.token_warn_pct, 80);
+        assert_eq!(config.cost_budget_usd, None);
     }
 }
@@ -15,17 +15,22 @@
 //!   Outcomes are linked back to decisions for learning.
 //!
 //! - **Health tracking**: Success and failure journals feed into per-tool
-//!   health scores displayed by `ryvos health`.
+//!   health scores displayed by `ryvos health`, alongside per-tool latency
+//!   percentiles from the latency journal.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::sync::Mutex;
 
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection};
+use serde::Serialize;
 
 use ryvos_core::types::{Decision, DecisionOutcome};
 
+/// How long latency samples are kept.
+const LATENCY_RETENTION_DAYS: i64 = 30;
+
 /// A record of a tool failure for pattern analysis.
 #[derive(Debug, Clone)]
 pub struct FailureRecord {
@@ -37,6 +42,66 @@ pub struct FailureRecord {
     pub turn: usize,
 }
 
+/// Latency percentiles for one tool's calls, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LatencySummary {
+    pub calls: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencySummary {
+    /// Nearest-rank percentiles of `samples`; `None` when there are none.
+    pub fn from_samples(mut samples: Vec<u64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            calls: samples.len(),
+            p50_ms: rank(50),
+            p95_ms: rank(95),
+            max_ms: samples[samples.len() - 1],
+        })
+    }
+}
+
+/// Success rate, in percent, below which a tool counts as degraded.
+pub const DEGRADED_BELOW_PCT: u32 = 90;
+
+/// One tool's line in `ryvos health`: call outcomes and, when recorded,
+/// latency percentiles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolHealth {
+    pub successes: usize,
+    pub failures: usize,
+    /// Success rate in whole percent; 100 for a tool with no calls.
+    pub pct: u32,
+    pub degraded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencySummary>,
+}
+
+impl ToolHealth {
+    fn new(successes: usize, failures: usize, latency: Option<LatencySummary>) -> Self {
+        let total = successes + failures;
+        let pct = if total > 0 {
+            (successes as f64 / total as f64 * 100.0) as u32
+        } else {
+            100
+        };
+        Self {
+            successes,
+            failures,
+            pct,
+            degraded: pct < DEGRADED_BELOW_PCT,
+            latency,
+        }
+    }
+}
+
 /// Persistent journal of tool failures for self-healing pattern detection.
 pub struct FailureJournal {
     conn: Mutex<Connection>,
@@ -80,6 +145,20 @@ impl FailureJournal {
              CREATE INDEX IF NOT EXISTS idx_sj_tool
                  ON success_journal(tool_name, timestamp);
 
+             CREATE TABLE IF NOT EXISTS latency_journal (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 timestamp TEXT NOT NULL,
+                 session_id TEXT NOT NULL,
+                 tool_name TEXT NOT NULL,
+                 latency_ms INTEGER NOT NULL
+             );
+
+             CREATE INDEX IF NOT EXISTS idx_lj_tool
+                 ON latency_journal(tool_name, timestamp);
+
+             CREATE INDEX IF NOT EXISTS idx_lj_time
+                 ON latency_journal(timestamp);
+
              CREATE TABLE IF NOT EXISTS decisions (
                  id TEXT PRIMARY KEY,
                  timestamp TEXT NOT NULL,
@@ -131,6 +210,35 @@ impl FailureJournal {
         Ok(())
     }
 
+    /// Record how long one call to a tool took, whether or not it failed,
+    /// and drop samples older than `LATENCY_RETENTION_DAYS`.
+    pub fn record_latency(
+        &self,
+        session_id: &str,
+        tool_name: &str,
+        latency_ms: u64,
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO latency_journal (timestamp, session_id, tool_name, latency_ms)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                Utc::now().to_rfc3339(),
+                session_id,
+                tool_name,
+                latency_ms as i64
+            ],
+        )
+        .map_err(|e| format!("Failed to record latency: {}", e))?;
+        let cutoff = Utc::now() - chrono::Duration::days(LATENCY_RETENTION_DAYS);
+        conn.execute(
+            "DELETE FROM latency_journal WHERE timestamp < ?1",
+            params![cutoff.to_rfc3339()],
+        )
+        .map_err(|e| format!("Failed to prune latency: {}", e))?;
+        Ok(())
+    }
+
     /// Find past failure patterns for a specific tool.
     pub fn find_patterns(&self, tool: &str, limit: usize) -> Result<Vec<FailureRecord>, String> {
         let conn = self.conn.lock().map_err(|e| e.to_string())?;
@@ -224,7 +332,7 @@ impl FailureJournal {
 
         let rows = stmt
             .query_map(params![session_id, limit as i64], |row| {
-                let ts_str: String = row.get(0)?;
+                let ts_str: String = row.get(1)?;
                 let alts_str: String = row.get(6)?;
                 let outcome_str: Option<String> = row.get(7)?;
 
@@ -306,6 +414,74 @@ impl FailureJournal {
 
         Ok(health)
     }
+
+    /// Health of every tool used since a given time, sorted by name.
+    pub fn health_report(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<BTreeMap<String, ToolHealth>, String> {
+        let mut latency = self.tool_latency(since)?;
+        Ok(self
+            .tool_health(since)?
+            .into_iter()
+            .map(|(tool, (successes, failures))| {
+                let health = ToolHealth::new(successes, failures, latency.remove(&tool));
+                (tool, health)
+            })
+            .collect())
+    }
+
+    /// Latency percentiles per tool for calls since a given time.
+    pub fn tool_latency(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<HashMap<String, LatencySummary>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT tool_name, latency_ms FROM latency_journal
+                 WHERE timestamp >= ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![since.to_rfc3339()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut samples: HashMap<String, Vec<u64>> = HashMap::new();
+        for row in rows {
+            let (tool, latency_ms) = row.map_err(|e| e.to_string())?;
+            samples.entry(tool).or_default().push(latency_ms);
+        }
+        Ok(samples
+            .into_iter()
+            .filter_map(|(tool, ms)| LatencySummary::from_samples(ms).map(|s| (tool, s)))
+            .collect())
+    }
+}
+
+/// Columns read by [`decision_from_row`], in its order.
+const DECISION_COLUMNS: &str = "id, timestamp, session_id, turn, description, chosen_option, \
+                                alternatives_json, outcome_json";
+
+/// The decision in a row selected with [`DECISION_COLUMNS`].
+fn decision_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Decision> {
+    let alts_json: String = row.get(6)?;
+    let outcome_json: Option<String> = row.get(7)?;
+    Ok(Decision {
+        id: row.get(0)?,
+        timestamp: row
+            .get::<_, String>(1)?
+            .parse()
+            .unwrap_or_else(|_| Utc::now()),
+        session_id: row.get(2)?,
+        turn: row.get(3)?,
+        description: row.get(4)?,
+        chosen_option: row.get(5)?,
+        alternatives: serde_json::from_str(&alts_json).unwrap_or_default(),
+        outcome: outcome_json.and_then(|j| serde_json::from_str(&j).ok()),
+    })
 }
 
 /// Generate a pattern-aware reflexion hint using past failure history.
@@ -356,37 +532,42 @@ fn truncate_str(s: &str, max: usize) -> &str {
 
 impl FailureJournal {
     /// List decisions, paginated, ordered by timestamp DESC.
-    pub fn list_decisions(
+    pub fn list_decisions(&self, limit: usize, offset: usize) -> Result<Vec<Decision>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM decisions ORDER BY timestamp DESC LIMIT ?1 OFFSET ?2",
+                DECISION_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let decisions = stmt
+            .query_map(rusqlite::params![limit, offset], decision_from_row)
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(decisions)
+    }
+
+    /// Most recent decisions, newest first, optionally limited to sessions
+    /// whose id starts with `session_prefix`.
+    pub fn search_decisions(
         &self,
+        session_prefix: Option<&str>,
         limit: usize,
-        offset: usize,
     ) -> Result<Vec<Decision>, String> {
+        let Some(prefix) = session_prefix else {
+            return self.list_decisions(limit, 0);
+        };
         let conn = self.conn.lock().map_err(|e| e.to_string())?;
         let mut stmt = conn
-            .prepare(
-                "SELECT id, timestamp, session_id, turn, description, chosen_option, \
-                 alternatives_json, outcome_json \
-                 FROM decisions ORDER BY timestamp DESC LIMIT ?1 OFFSET ?2",
-            )
+            .prepare(&format!(
+                "SELECT {} FROM decisions WHERE substr(session_id, 1, length(?1)) = ?1 \
+                 ORDER BY timestamp DESC LIMIT ?2",
+                DECISION_COLUMNS
+            ))
             .map_err(|e| e.to_string())?;
         let decisions = stmt
-            .query_map(rusqlite::params![limit, offset], |row| {
-                let alts_json: String = row.get(6)?;
-                let outcome_json: Option<String> = row.get(7)?;
-                Ok(Decision {
-                    id: row.get(0)?,
-                    timestamp: row
-                        .get::<_, String>(1)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                    session_id: row.get(2)?,
-                    turn: row.get(3)?,
-                    description: row.get(4)?,
-                    chosen_option: row.get(5)?,
-                    alternatives: serde_json::from_str(&alts_json).unwrap_or_default(),
-                    outcome: outcome_json.and_then(|j| serde_json::from_str(&j).ok()),
-                })
-            })
+            .query_map(rusqlite::params![prefix, limit], decision_from_row)
             .map_err(|e| e.to_string())?
             .filter_map(|r| r.ok())
             .collect();
@@ -535,6 +716,105 @@ mod tests {
         assert_eq!(*failures, 1);
     }
 
+    #[test]
+    fn health_report_serializes_journal_data() {
+        let journal = temp_journal();
+        let since = Utc::now() - chrono::Duration::hours(1);
+
+        for _ in 0..9 {
+            journal.record_success("sess1", "read").unwrap();
+        }
+        journal.record_success("sess1", "bash").unwrap();
+        for _ in 0..2 {
+            journal
+                .record(FailureRecord {
+                    timestamp: Utc::now(),
+                    session_id: "sess1".into(),
+                    tool_name: "bash".into(),
+                    error: "exit 1".into(),
+                    input_summary: "make".into(),
+                    turn: 0,
+                })
+                .unwrap();
+        }
+        for ms in [30, 10, 20] {
+            journal.record_latency("sess1", "read", ms).unwrap();
+        }
+
+        let report = journal.health_report(since).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string_pretty(&report).unwrap()).unwrap();
+
+        let health = journal.tool_health(since).unwrap();
+        let latency = journal.tool_latency(since).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), health.len());
+        for (tool, (successes, failures)) in &health {
+            assert_eq!(json[tool]["successes"], *successes);
+            assert_eq!(json[tool]["failures"], *failures);
+        }
+        assert_eq!(json["read"]["pct"], 100);
+        assert_eq!(json["read"]["degraded"], false);
+        assert_eq!(json["read"]["latency"]["p50_ms"], latency["read"].p50_ms);
+        assert_eq!(json["read"]["latency"]["max_ms"], 30);
+        assert_eq!(json["bash"]["pct"], 33);
+        assert_eq!(json["bash"]["degraded"], true);
+        assert!(json["bash"].get("latency").is_none());
+    }
+
+    #[test]
+    fn tool_latency_percentiles() {
+        let journal = temp_journal();
+        let since = Utc::now() - chrono::Duration::hours(1);
+
+        // 1..=20 ms in shuffled order, plus one slow outlier
+        for ms in [
+            7, 3, 15, 1, 20, 9, 12, 4, 18, 6, 2, 11, 16, 5, 19, 8, 13, 10, 17, 14, 900,
+        ] {
+            journal.record_latency("sess1", "bash", ms).unwrap();
+        }
+        journal.record_latency("sess1", "read", 40).unwrap();
+
+        let latency = journal.tool_latency(since).unwrap();
+        assert_eq!(
+            latency["bash"],
+            LatencySummary {
+                calls: 21,
+                p50_ms: 11,
+                p95_ms: 20,
+                max_ms: 900,
+            }
+        );
+        assert_eq!(latency["read"].p50_ms, 40);
+        assert_eq!(latency["read"].p95_ms, 40);
+
+        let later = journal.tool_latency(Utc::now() + chrono::Duration::hours(1));
+        assert!(later.unwrap().is_empty());
+        assert_eq!(LatencySummary::from_samples(vec![]), None);
+    }
+
+    #[test]
+    fn old_latency_samples_are_pruned() {
+        let journal = temp_journal();
+        let old = Utc::now() - chrono::Duration::days(LATENCY_RETENTION_DAYS + 1);
+        journal
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO latency_journal (timestamp, session_id, tool_name, latency_ms)
+                 VALUES (?1, 'sess1', 'bash', 5000)",
+                params![old.to_rfc3339()],
+            )
+            .unwrap();
+
+        journal.record_latency("sess1", "bash", 12).unwrap();
+        let latency = journal
+            .tool_latency(old - chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(latency["bash"].calls, 1);
+        assert_eq!(latency["bash"].max_ms, 12);
+    }
+
     #[test]
     fn test_decision_record_roundtrip() {
         let journal = temp_journal();
@@ -7,6 +7,7 @@ use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 use ryvos_core::config::HeartbeatConfig;
+use ryvos_core::error::Result;
 use ryvos_core::event::EventBus;
 use ryvos_core::types::{AgentEvent, SessionId};
 
@@ -33,6 +34,19 @@ const ACK_PATTERNS: &[&str] = &[
     "all clear",
 ];
 
+/// What a single heartbeat check produced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeartbeatOutcome {
+    /// The agent acknowledged; only a `HeartbeatOk` event was published.
+    Ok { response: String },
+    /// The response was published as a `HeartbeatAlert` for `target_channel`
+    /// (`None` = all channels).
+    Alert {
+        message: String,
+        target_channel: Option<String>,
+    },
+}
+
 /// Periodic proactive agent check.
 ///
 /// Fires at a configurable interval, reads `HEARTBEAT.md` from the workspace
@@ -101,100 +115,114 @@ impl Heartbeat {
                 continue;
             }
 
-            let now = Utc::now();
-            let session_id =
-                SessionId::from_string(&format!("heartbeat:{}", now.format("%Y%m%d-%H%M%S")));
-
-            self.event_bus
-                .publish(AgentEvent::HeartbeatFired { timestamp: now });
-
-            let mut prompt = self.build_prompt();
-
-            // Inject safety retrospective: recent non-Harmless audit entries
-            if let Some(ref trail) = self.audit_trail {
-                if let Ok(entries) = trail.recent_entries("", 50).await {
-                    let flagged: Vec<_> = entries
-                        .iter()
-                        .filter(|e| {
-                            !matches!(e.outcome, crate::safety_memory::SafetyOutcome::Harmless)
-                        })
-                        .collect();
-                    if !flagged.is_empty() {
-                        prompt.push_str("\n\n## Safety Retrospective\n\n");
-                        prompt.push_str(
-                            "The following recent actions had non-harmless safety outcomes. \
-                             Evaluate whether corrective lessons should be recorded via viking_write \
-                             to viking://agent/lessons/:\n\n",
-                        );
-                        for entry in flagged.iter().take(10) {
-                            prompt.push_str(&format!(
-                                "- **{}** `{}`: {:?}\n",
-                                entry.tool_name,
-                                entry.input_summary.chars().take(80).collect::<String>(),
-                                entry.outcome
-                            ));
-                        }
+            // Failures are logged by run_once; the next tick tries again
+            let _ = self.run_once().await;
+        }
+    }
+
+    /// Fire a single heartbeat check now, ignoring active hours.
+    ///
+    /// Runs the heartbeat prompt through the agent and publishes the result
+    /// like a scheduled check: `HeartbeatOk` for an ack, `HeartbeatAlert`
+    /// addressed to the configured target channel otherwise.
+    pub async fn run_once(&self) -> Result<HeartbeatOutcome> {
+        let now = Utc::now();
+        let session_id =
+            SessionId::from_string(&format!("heartbeat:{}", now.format("%Y%m%d-%H%M%S")));
+
+        self.event_bus
+            .publish(AgentEvent::HeartbeatFired { timestamp: now });
+
+        let mut prompt = self.build_prompt();
+
+        // Inject safety retrospective: recent non-Harmless audit entries
+        if let Some(ref trail) = self.audit_trail {
+            if let Ok(entries) = trail.recent_entries("", 50).await {
+                let flagged: Vec<_> = entries
+                    .iter()
+                    .filter(|e| !matches!(e.outcome, crate::safety_memory::SafetyOutcome::Harmless))
+                    .collect();
+                if !flagged.is_empty() {
+                    prompt.push_str("\n\n## Safety Retrospective\n\n");
+                    prompt.push_str(
+                        "The following recent actions had non-harmless safety outcomes. \
+                         Evaluate whether corrective lessons should be recorded via viking_write \
+                         to viking://agent/lessons/:\n\n",
+                    );
+                    for entry in flagged.iter().take(10) {
+                        prompt.push_str(&format!(
+                            "- **{}** `{}`: {:?}\n",
+                            entry.tool_name,
+                            entry.input_summary.chars().take(80).collect::<String>(),
+                            entry.outcome
+                        ));
                     }
                 }
             }
+        }
 
-            let session_key = "heartbeat:default";
+        let session_key = "heartbeat:default";
 
-            info!(session = %session_id, "Heartbeat firing");
+        info!(session = %session_id, "Heartbeat firing");
 
-            // Look up CLI session ID for resumption
-            if let Some(ref meta_store) = self.session_meta {
-                if let Ok(Some(meta)) = meta_store.get(session_key) {
-                    if let Some(cli_id) = meta.cli_session_id {
-                        info!(cli_session = %cli_id, "Resuming CLI session");
-                        self.runtime.set_cli_session_id(Some(cli_id));
-                    }
+        // Look up CLI session ID for resumption
+        if let Some(ref meta_store) = self.session_meta {
+            if let Ok(Some(meta)) = meta_store.get(session_key) {
+                if let Some(cli_id) = meta.cli_session_id {
+                    info!(cli_session = %cli_id, "Resuming CLI session");
+                    self.runtime.set_cli_session_id(Some(cli_id));
                 }
             }
+        }
 
-            match self.runtime.run(&session_id, &prompt).await {
-                Ok(response) => {
-                    // Capture and persist new CLI session ID
-                    if let Some(ref meta_store) = self.session_meta {
-                        if let Some(new_cli_id) = self.runtime.last_message_id() {
-                            meta_store
-                                .get_or_create(session_key, &session_id.0, "heartbeat")
-                                .ok();
-                            if let Err(e) = meta_store.set_cli_session_id(session_key, &new_cli_id)
-                            {
-                                warn!(error = %e, "Failed to persist CLI session ID");
-                            }
-                        }
-                    }
-
-                    match evaluate_response(&response, self.config.ack_max_chars) {
-                        HeartbeatResult::Ok => {
-                            info!(session = %session_id, chars = response.len(), "Heartbeat OK (suppressed)");
-                            self.event_bus.publish(AgentEvent::HeartbeatOk {
-                                session_id,
-                                response_chars: response.len(),
-                            });
-                        }
-                        HeartbeatResult::Alert => {
-                            warn!(session = %session_id, "Heartbeat alert");
-                            self.event_bus.publish(AgentEvent::HeartbeatAlert {
-                                session_id,
-                                message: response,
-                                target_channel: self.config.target_channel.clone(),
-                            });
-                        }
-                    }
+        let response = match self.runtime.run(&session_id, &prompt).await {
+            Ok(response) => response,
+            Err(e) => {
+                error!(session = %session_id, error = %e, "Heartbeat run failed");
+                // Clear CLI session ID on failure (graceful fallback)
+                if let Some(ref meta_store) = self.session_meta {
+                    meta_store.clear_cli_session_id(session_key).ok();
                 }
-                Err(e) => {
-                    error!(session = %session_id, error = %e, "Heartbeat run failed");
-                    // Clear CLI session ID on failure (graceful fallback)
-                    if let Some(ref meta_store) = self.session_meta {
-                        meta_store.clear_cli_session_id(session_key).ok();
-                    }
-                    self.runtime.set_cli_session_id(None);
+                self.runtime.set_cli_session_id(None);
+                return Err(e);
+            }
+        };
+
+        // Capture and persist new CLI session ID
+        if let Some(ref meta_store) = self.session_meta {
+            if let Some(new_cli_id) = self.runtime.last_message_id() {
+                meta_store
+                    .get_or_create(session_key, &session_id.0, "heartbeat")
+                    .ok();
+                if let Err(e) = meta_store.set_cli_session_id(session_key, &new_cli_id) {
+                    warn!(error = %e, "Failed to persist CLI session ID");
                 }
             }
         }
+
+        match evaluate_response(&response, self.config.ack_max_chars) {
+            HeartbeatResult::Ok => {
+                info!(session = %session_id, chars = response.len(), "Heartbeat OK (suppressed)");
+                self.event_bus.publish(AgentEvent::HeartbeatOk {
+                    session_id,
+                    response_chars: response.len(),
+                });
+                Ok(HeartbeatOutcome::Ok { response })
+            }
+            HeartbeatResult::Alert => {
+                warn!(session = %session_id, "Heartbeat alert");
+                let target_channel = self.config.target_channel.clone();
+                self.event_bus.publish(AgentEvent::HeartbeatAlert {
+                    session_id,
+                    message: response.clone(),
+                    target_channel: target_channel.clone(),
+                });
+                Ok(HeartbeatOutcome::Alert {
+                    message: response,
+                    target_channel,
+                })
+            }
+        }
     }
 
     /// Build the prompt by reading HEARTBEAT.md (if it exists) and appending
@@ -293,6 +321,98 @@ fn evaluate_response(response: &str, ack_max_chars: usize) -> HeartbeatResult {
 mod tests {
     use super::*;
     use chrono::TimeZone;
+    use ryvos_core::config::ActiveHoursConfig;
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient};
+    use ryvos_tools::ToolRegistry;
+
+    /// A heartbeat whose active-hours window is empty, so only an explicit
+    /// `run_once` can fire it.
+    fn heartbeat(llm: &MockLlmClient, bus: &Arc<EventBus>) -> (Heartbeat, PathBuf) {
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            bus.clone(),
+        ));
+        let workspace =
+            std::env::temp_dir().join(format!("ryvos_heartbeat_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let config = HeartbeatConfig {
+            target_channel: Some("telegram".into()),
+            active_hours: Some(ActiveHoursConfig {
+                start_hour: 0,
+                end_hour: 0,
+                utc_offset_hours: 0,
+            }),
+            ..Default::default()
+        };
+        let heartbeat = Heartbeat::new(
+            config,
+            runtime,
+            bus.clone(),
+            CancellationToken::new(),
+            workspace.clone(),
+        );
+        assert!(!heartbeat.is_within_active_hours());
+        (heartbeat, workspace)
+    }
+
+    #[tokio::test]
+    async fn run_once_routes_alert_to_target_channel() {
+        let llm = MockLlmClient::new().with_text_response("Disk usage is at 95%.");
+        let bus = Arc::new(EventBus::default());
+        let mut rx = bus.subscribe();
+        let (heartbeat, workspace) = heartbeat(&llm, &bus);
+
+        let outcome = heartbeat.run_once().await.unwrap();
+        assert_eq!(
+            outcome,
+            HeartbeatOutcome::Alert {
+                message: "Disk usage is at 95%.".into(),
+                target_channel: Some("telegram".into()),
+            }
+        );
+        assert_eq!(llm.call_count(), 1);
+        let prompt = llm.call_messages(0).last().unwrap().text();
+        assert!(prompt.ends_with(DEFAULT_PROMPT));
+
+        let mut alert = None;
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::HeartbeatAlert { target_channel, .. } = event {
+                alert = Some(target_channel);
+            }
+        }
+        assert_eq!(alert, Some(Some("telegram".to_string())));
+        std::fs::remove_dir_all(&workspace).ok();
+    }
+
+    #[tokio::test]
+    async fn run_once_suppresses_ack() {
+        let llm = MockLlmClient::new().with_text_response("HEARTBEAT_OK");
+        let bus = Arc::new(EventBus::default());
+        let mut rx = bus.subscribe();
+        let (heartbeat, workspace) = heartbeat(&llm, &bus);
+
+        let outcome = heartbeat.run_once().await.unwrap();
+        assert_eq!(
+            outcome,
+            HeartbeatOutcome::Ok {
+                response: "HEARTBEAT_OK".into()
+            }
+        );
+        let mut events = vec![];
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, AgentEvent::HeartbeatOk { .. })));
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, AgentEvent::HeartbeatAlert { .. })));
+        std::fs::remove_dir_all(&workspace).ok();
+    }
 
     #[test]
     fn test_active_hours_normal_window() {
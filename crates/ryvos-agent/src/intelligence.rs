@@ -5,8 +5,11 @@
 //!
 //! - **Counting tokens** accurately via tiktoken (cl100k_base BPE tokenizer).
 //! - **Pruning**: Removing oldest non-protected messages when over budget.
+//!   Pinned messages are never pruned or summarized.
 //! - **Summarizing**: Asking the LLM to summarize a batch of old messages
 //!   into a single compact message, preserving key information.
+//! - **Session summary**: Folding each run into a rolling per-session
+//!   summary that outlives pruning.
 //! - **Memory flush**: Before pruning, giving the agent a chance to write
 //!   important info to durable storage (memory_write, daily_log_write).
 //! - **Tool output compaction**: Truncating large tool outputs at newline
@@ -20,7 +23,7 @@ use std::sync::OnceLock;
 use futures::StreamExt;
 use tiktoken_rs::CoreBPE;
 
-use ryvos_core::config::ModelConfig;
+use ryvos_core::config::{ContextConfig, ModelConfig};
 use ryvos_core::error::Result;
 use ryvos_core::traits::LlmClient;
 use ryvos_core::types::{ChatMessage, ContentBlock, Role, StreamDelta};
@@ -44,32 +47,113 @@ pub fn estimate_message_tokens(msg: &ChatMessage) -> usize {
     estimate_tokens(&content_str) + 4
 }
 
-/// Remove oldest non-system, non-protected messages from the middle until
-/// total tokens fit within `budget`. Never removes index 0 (system) or the
-/// last `min_tail` messages. Protected messages (metadata.protected == true)
-/// are never removed. Returns the number of messages removed.
-pub fn prune_to_budget(messages: &mut Vec<ChatMessage>, budget: usize, min_tail: usize) -> usize {
-    let mut removed = 0;
+/// Estimated tokens the conversation occupies in the context window.
+pub fn estimate_context_tokens(messages: &[ChatMessage]) -> u64 {
+    messages.iter().map(estimate_message_tokens).sum::<usize>() as u64
+}
 
-    loop {
-        let total: usize = messages.iter().map(estimate_message_tokens).sum();
-        if total <= budget {
-            break;
+/// How pruning and summarization choose what to keep; built from
+/// `[agent.context]`.
+#[derive(Debug, Clone)]
+pub struct PrunePolicy {
+    /// Messages at the end never removed.
+    pub min_tail: usize,
+    /// Most recent user turns never removed, on top of `min_tail`.
+    pub protected_turns: usize,
+    /// Honour the protected flag on tool-result messages.
+    pub protect_tool_results: bool,
+    /// Fraction of the budget an over-budget context is reduced to.
+    pub target_ratio: f64,
+}
+
+impl Default for PrunePolicy {
+    fn default() -> Self {
+        Self {
+            min_tail: 6,
+            protected_turns: 1,
+            protect_tool_results: true,
+            target_ratio: 1.0,
         }
+    }
+}
+
+impl PrunePolicy {
+    pub fn from_config(config: &ContextConfig) -> Self {
+        Self {
+            protected_turns: config.protected_turns,
+            protect_tool_results: config.protect_tool_results,
+            target_ratio: config.summary_target_ratio,
+            ..Default::default()
+        }
+    }
+
+    /// Token count compaction aims for once `budget` is exceeded.
+    fn target(&self, budget: usize) -> usize {
+        (budget as f64 * self.target_ratio.clamp(0.0, 1.0)) as usize
+    }
+
+    /// Number of trailing messages that must survive: `min_tail`, widened
+    /// to reach back to the start of the last `protected_turns` user turns.
+    fn tail_len(&self, messages: &[ChatMessage]) -> usize {
+        let turn_starts: Vec<usize> = (1..messages.len())
+            .filter(|&i| is_turn_start(&messages[i]))
+            .collect();
+        let turn_tail = match self.protected_turns {
+            0 => 0,
+            n if n > turn_starts.len() => messages.len().saturating_sub(1),
+            n => messages.len() - turn_starts[turn_starts.len() - n],
+        };
+        self.min_tail.max(turn_tail)
+    }
 
+    /// Whether `msg` must stay even outside the tail.
+    fn keeps(&self, msg: &ChatMessage) -> bool {
+        msg.is_pinned()
+            || (msg.is_protected() && (self.protect_tool_results || !is_tool_result(msg)))
+    }
+}
+
+/// A user message that starts a turn, as opposed to one carrying tool results.
+fn is_turn_start(msg: &ChatMessage) -> bool {
+    msg.role == Role::User && !is_tool_result(msg)
+}
+
+fn is_tool_result(msg: &ChatMessage) -> bool {
+    msg.content
+        .iter()
+        .any(|b| matches!(b, ContentBlock::ToolResult { .. }))
+}
+
+/// Once total tokens exceed `budget`, remove the oldest messages that are
+/// not the system prompt (index 0), not in the policy's tail and not kept
+/// as protected or pinned, until the total fits the policy's target.
+/// Returns the number of messages removed.
+pub fn prune_to_budget(
+    messages: &mut Vec<ChatMessage>,
+    budget: usize,
+    policy: &PrunePolicy,
+) -> usize {
+    let mut total: usize = messages.iter().map(estimate_message_tokens).sum();
+    if total <= budget {
+        return 0;
+    }
+    let target = policy.target(budget);
+    let tail = policy.tail_len(messages);
+
+    let mut removed = 0;
+    while total > target {
         let len = messages.len();
-        if len <= 1 + min_tail {
+        if len <= 1 + tail {
             break;
         }
+        let tail_start = len - tail;
 
-        let tail_start = len - min_tail;
-
-        // Find the first removable message: not system (idx 0), not in tail, not protected
-        let remove_idx = (1..tail_start).find(|&idx| !messages[idx].is_protected());
+        // Find the first removable message: not system (idx 0), not in tail, not kept
+        let remove_idx = (1..tail_start).find(|&idx| !policy.keeps(&messages[idx]));
 
         match remove_idx {
             Some(idx) => {
-                messages.remove(idx);
+                total -= estimate_message_tokens(&messages.remove(idx));
                 removed += 1;
             }
             None => break, // All remaining messages are protected
@@ -79,26 +163,29 @@ pub fn prune_to_budget(messages: &mut Vec<ChatMessage>, budget: usize, min_tail:
     removed
 }
 
-/// Summarize old messages before pruning to preserve context.
+/// Summarize old messages before pruning to preserve context. Returns how
+/// many messages went, with the usage of the summary call for the caller to
+/// publish.
 ///
 /// Phase-aware: groups messages by their phase tag before summarizing.
-/// Protected messages are kept as-is. Messages within a phase are never
+/// Protected and pinned messages are kept as-is. Messages within a phase are never
 /// split — the entire phase group is summarized together.
 pub async fn summarize_and_prune(
     messages: &mut Vec<ChatMessage>,
     budget: usize,
-    min_tail: usize,
+    policy: &PrunePolicy,
     llm: &dyn LlmClient,
     config: &ModelConfig,
-) -> Result<usize> {
+) -> Result<(usize, CallUsage)> {
     let total: usize = messages.iter().map(estimate_message_tokens).sum();
     if total <= budget {
-        return Ok(0);
+        return Ok((0, CallUsage::default()));
     }
 
     let len = messages.len();
+    let min_tail = policy.tail_len(messages);
     if len <= 1 + min_tail {
-        return Ok(0);
+        return Ok((0, CallUsage::default()));
     }
 
     let summarize_end = len - min_tail;
@@ -107,11 +194,14 @@ pub async fn summarize_and_prune(
     // Protected messages are kept as-is.
     let to_summarize: Vec<&ChatMessage> = messages[1..summarize_end]
         .iter()
-        .filter(|m| !m.is_protected())
+        .filter(|m| !policy.keeps(m))
         .collect();
 
     if to_summarize.is_empty() {
-        return Ok(prune_to_budget(messages, budget, min_tail));
+        return Ok((
+            prune_to_budget(messages, budget, policy),
+            CallUsage::default(),
+        ));
     }
 
     // Group by phase for the summarization prompt
@@ -141,65 +231,137 @@ pub async fn summarize_and_prune(
         }
     }
 
-    let summary_msgs = vec![ChatMessage::user(format!(
+    let prompt = format!(
         "Summarize the following conversation concisely, preserving key facts, \
          decisions, code snippets, and file paths. If phases are marked, \
          preserve the phase structure in your summary. Output only the summary.\n\n{}",
         conversation_text
-    ))];
-
-    let stream_result = llm.chat_stream(config, summary_msgs, &[]).await;
+    );
+    let (summary_text, usage) = complete(prompt, llm, config).await;
+    let Some(summary_text) = summary_text else {
+        return Ok((prune_to_budget(messages, budget, policy), usage));
+    };
 
-    match stream_result {
-        Ok(mut stream) => {
-            let mut summary_text = String::new();
-            while let Some(delta) = stream.next().await {
-                if let Ok(StreamDelta::TextDelta(text)) = delta {
-                    summary_text.push_str(&text);
-                }
-            }
+    let summary_msg = ChatMessage {
+        role: Role::User,
+        content: vec![ContentBlock::Text {
+            text: format!("[Conversation Summary]\n{}", summary_text),
+        }],
+        timestamp: Some(chrono::Utc::now()),
+        metadata: Some(ryvos_core::types::MessageMetadata {
+            protected: true,
+            ..Default::default()
+        }),
+    };
 
-            if summary_text.is_empty() {
-                return Ok(prune_to_budget(messages, budget, min_tail));
-            }
+    // Remove all non-protected messages from the summarizable range.
+    // Keep protected messages in place.
+    let mut removed = 0;
+    let mut i = 1;
+    while i < messages.len() - min_tail.min(messages.len().saturating_sub(1)) {
+        if i >= summarize_end - removed {
+            break;
+        }
+        if !policy.keeps(&messages[i]) {
+            messages.remove(i);
+            removed += 1;
+        } else {
+            i += 1;
+        }
+    }
 
-            let summary_msg = ChatMessage {
-                role: Role::User,
-                content: vec![ContentBlock::Text {
-                    text: format!("[Conversation Summary]\n{}", summary_text),
-                }],
-                timestamp: Some(chrono::Utc::now()),
-                metadata: Some(ryvos_core::types::MessageMetadata {
-                    protected: true,
-                    ..Default::default()
-                }),
-            };
+    // Insert summary after system message (index 1)
+    messages.insert(1, summary_msg);
 
-            // Remove all non-protected messages from the summarizable range.
-            // Keep protected messages in place.
-            let mut removed = 0;
-            let mut i = 1;
-            while i < messages.len() - min_tail.min(messages.len().saturating_sub(1)) {
-                if i >= summarize_end - removed {
-                    break;
-                }
-                if !messages[i].is_protected() {
-                    messages.remove(i);
-                    removed += 1;
-                } else {
-                    i += 1;
-                }
-            }
+    // If still over budget, fall back to pruning
+    let remaining = prune_to_budget(messages, budget, policy);
+    Ok((removed + remaining, usage))
+}
 
-            // Insert summary after system message (index 1)
-            messages.insert(1, summary_msg);
+/// Tokens used by a call made outside the turn loop, such as a summary
+/// update, for the caller to publish like a turn's usage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub thinking_tokens: u64,
+}
 
-            // If still over budget, fall back to pruning
-            let remaining = prune_to_budget(messages, budget, min_tail);
-            Ok(removed + remaining)
+/// Send a single user message and collect the reply's text and usage. The
+/// text is `None` when the call fails or returns nothing.
+async fn complete(
+    prompt: String,
+    llm: &dyn LlmClient,
+    config: &ModelConfig,
+) -> (Option<String>, CallUsage) {
+    let mut usage = CallUsage::default();
+    let Ok(mut stream) = llm
+        .chat_stream(config, vec![ChatMessage::user(prompt)], &[])
+        .await
+    else {
+        return (None, usage);
+    };
+    let mut text = String::new();
+    while let Some(delta) = stream.next().await {
+        match delta {
+            Ok(StreamDelta::TextDelta(delta)) => text.push_str(&delta),
+            Ok(StreamDelta::Usage {
+                input_tokens,
+                output_tokens,
+                thinking_tokens,
+            }) => {
+                usage.input_tokens += input_tokens;
+                usage.output_tokens += output_tokens;
+                usage.thinking_tokens += thinking_tokens;
+            }
+            _ => {}
         }
-        Err(_) => Ok(prune_to_budget(messages, budget, min_tail)),
     }
+    let text = text.trim();
+    ((!text.is_empty()).then(|| text.to_string()), usage)
+}
+
+/// Fold one run (the user's message and the final reply) into the rolling
+/// summary of its session, with the call's usage. The summary is `None`
+/// when the LLM call fails or returns nothing, so the previous one is kept.
+pub async fn update_session_summary(
+    previous: Option<&str>,
+    user_message: &str,
+    reply: &str,
+    llm: &dyn LlmClient,
+    config: &ModelConfig,
+) -> (Option<String>, CallUsage) {
+    let previous = previous.unwrap_or("(none yet)");
+    let prompt = format!(
+        "Update the running summary of a conversation with its latest exchange. \
+         Keep the user's goals, key facts, decisions, file paths and open tasks; \
+         drop what is no longer relevant. Stay under 300 words. Output only the \
+         summary.\n\n# Current summary\n{}\n\n# Latest exchange\nUser: {}\n\nAssistant: {}",
+        previous.trim(),
+        user_message.trim(),
+        reply.trim()
+    );
+    complete(prompt, llm, config).await
+}
+
+/// Prompts shorter than this (in characters) skip the `prime` pass.
+pub const PRIME_MIN_PROMPT_CHARS: usize = 80;
+
+/// Draft a short plan for a request before the main loop starts, with the
+/// call's usage. The plan is `None` when the LLM call fails or returns
+/// nothing, so the run goes ahead without one.
+pub async fn prime_plan(
+    user_message: &str,
+    llm: &dyn LlmClient,
+    config: &ModelConfig,
+) -> (Option<String>, CallUsage) {
+    let prompt = format!(
+        "Draft a short plan for the request below: at most five numbered steps, \
+         naming the files, commands or tools each step needs. Do not carry it \
+         out. Output only the plan.\n\n# Request\n{}",
+        user_message.trim()
+    );
+    complete(prompt, llm, config).await
 }
 
 /// Truncate tool output to fit within `max_tokens` using BPE token counting.
@@ -303,6 +465,62 @@ pub fn memory_flush_prompt() -> ChatMessage {
     }
 }
 
+/// The pinned notes of a session as one pinned message, placed after the
+/// system prompt on every run. `None` when nothing is pinned.
+pub fn pinned_notes_message(notes: &[String]) -> Option<ChatMessage> {
+    if notes.is_empty() {
+        return None;
+    }
+    let list: Vec<String> = notes.iter().map(|n| format!("- {}", n)).collect();
+    Some(
+        ChatMessage::user(format!(
+            "[Pinned notes] The user pinned these; they stay true for the whole session:\n{}",
+            list.join("\n")
+        ))
+        .with_metadata(ryvos_core::types::MessageMetadata {
+            pinned: true,
+            ..Default::default()
+        }),
+    )
+}
+
+/// Context message carrying a session's scratchpad entries, re-added after
+/// a compaction. `None` when the scratchpad is empty.
+pub fn scratchpad_message(
+    entries: &std::collections::BTreeMap<String, String>,
+) -> Option<ChatMessage> {
+    if entries.is_empty() {
+        return None;
+    }
+    let sections: Vec<String> = entries
+        .iter()
+        .map(|(key, value)| format!("## {}\n{}", key, value))
+        .collect();
+    Some(
+        ChatMessage::user(format!(
+            "[Scratchpad] Your working notes for this session, kept across compaction:\n\n{}",
+            sections.join("\n\n")
+        ))
+        .with_metadata(ryvos_core::types::MessageMetadata {
+            protected: true,
+            ..Default::default()
+        }),
+    )
+}
+
+/// Context message carrying the plan drafted by the `prime` pass.
+pub fn plan_message(plan: &str) -> ChatMessage {
+    ChatMessage::user(format!(
+        "[Plan] Drafted before starting. Follow it where it helps and change \
+         course if it turns out wrong:\n\n{}",
+        plan
+    ))
+    .with_metadata(ryvos_core::types::MessageMetadata {
+        protected: true,
+        ..Default::default()
+    })
+}
+
 /// Check if a response text indicates the flush is complete.
 pub fn is_flush_complete(text: &str) -> bool {
     text.contains("FLUSH_COMPLETE")
@@ -332,6 +550,33 @@ impl FailureTracker {
 mod tests {
     use super::*;
 
+    fn tail(min_tail: usize) -> PrunePolicy {
+        PrunePolicy {
+            min_tail,
+            ..Default::default()
+        }
+    }
+
+    /// System prompt followed by `turns` question/answer pairs.
+    fn conversation(turns: usize) -> Vec<ChatMessage> {
+        let mut messages = vec![ChatMessage {
+            role: Role::System,
+            content: vec![ContentBlock::Text {
+                text: "system".to_string(),
+            }],
+            timestamp: None,
+            metadata: None,
+        }];
+        for i in 0..turns {
+            messages.push(ChatMessage::user(format!("question {} about the build", i)));
+            messages.push(ChatMessage::assistant_text(format!(
+                "answer {} with some detail",
+                i
+            )));
+        }
+        messages
+    }
+
     #[test]
     fn test_estimate_tokens_empty() {
         assert_eq!(estimate_tokens(""), 0);
@@ -395,7 +640,7 @@ mod tests {
 
         let original_len = messages.len();
         // Use a small budget to force pruning
-        let removed = prune_to_budget(&mut messages, 100, 3);
+        let removed = prune_to_budget(&mut messages, 100, &tail(3));
         assert!(removed > 0);
         assert!(messages.len() < original_len);
         // System message should still be first
@@ -430,7 +675,7 @@ mod tests {
         }
 
         let original_len = messages.len();
-        let removed = prune_to_budget(&mut messages, 100, 3);
+        let removed = prune_to_budget(&mut messages, 100, &tail(3));
         assert!(removed > 0);
         assert!(messages.len() < original_len);
         // System message still first
@@ -439,6 +684,145 @@ mod tests {
         assert!(messages.iter().any(|m| m.is_protected()));
     }
 
+    #[test]
+    fn protected_turns_decide_what_survives() {
+        let surviving = |protected_turns: usize| {
+            let mut messages = conversation(5);
+            let policy = PrunePolicy {
+                min_tail: 2,
+                protected_turns,
+                ..Default::default()
+            };
+            prune_to_budget(&mut messages, 10, &policy);
+            messages.iter().map(|m| m.text()).collect::<Vec<_>>()
+        };
+
+        let one = surviving(1);
+        assert_eq!(one.len(), 3);
+        assert!(one[1].starts_with("question 4"));
+
+        let three = surviving(3);
+        assert_eq!(three.len(), 7);
+        assert!(three[1].starts_with("question 2"));
+        assert!(three[6].starts_with("answer 4"));
+    }
+
+    #[test]
+    fn tool_results_lose_protection_when_disabled() {
+        let protected = ryvos_core::types::MessageMetadata {
+            protected: true,
+            ..Default::default()
+        };
+        let build = || {
+            let mut messages = conversation(1);
+            messages.insert(
+                2,
+                ChatMessage::tool_result("t1", "cargo build output", false)
+                    .with_metadata(protected.clone()),
+            );
+            messages.insert(
+                3,
+                ChatMessage::user("pinned note").with_metadata(protected.clone()),
+            );
+            messages.extend(conversation(3).into_iter().skip(1));
+            messages
+        };
+        let has_tool_result = |messages: &[ChatMessage]| messages.iter().any(is_tool_result);
+
+        let mut kept = build();
+        prune_to_budget(&mut kept, 10, &tail(2));
+        assert!(has_tool_result(&kept));
+
+        let mut dropped = build();
+        let policy = PrunePolicy {
+            min_tail: 2,
+            protect_tool_results: false,
+            ..Default::default()
+        };
+        prune_to_budget(&mut dropped, 10, &policy);
+        assert!(!has_tool_result(&dropped));
+        assert!(dropped.iter().any(|m| m.text() == "pinned note"));
+    }
+
+    #[test]
+    fn pinned_messages_survive_a_tiny_budget() {
+        let notes = vec!["deploys go through staging first".to_string()];
+        let mut messages = conversation(6);
+        messages.insert(1, pinned_notes_message(&notes).unwrap());
+        let mut fact = ChatMessage::user("the API lives in services/api");
+        fact.metadata = Some(ryvos_core::types::MessageMetadata {
+            pinned: true,
+            // Pins outlive protected_ttl and protect_tool_results
+            created_at_turn: Some(0),
+            ..Default::default()
+        });
+        messages.insert(5, fact);
+        expire_protected_messages(&mut messages, 100, 1);
+
+        let policy = PrunePolicy {
+            min_tail: 0,
+            protected_turns: 0,
+            protect_tool_results: false,
+            ..Default::default()
+        };
+        let removed = prune_to_budget(&mut messages, 1, &policy);
+        assert_eq!(removed, 12);
+        let texts: Vec<String> = messages.iter().map(|m| m.text()).collect();
+        assert_eq!(texts.len(), 3);
+        assert_eq!(texts[0], "system");
+        assert!(texts[1].contains("deploys go through staging first"));
+        assert_eq!(texts[2], "the API lives in services/api");
+        assert!(pinned_notes_message(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn summarization_keeps_pinned_messages_verbatim() {
+        use ryvos_test_utils::{test_config, MockLlmClient};
+
+        let mut messages = conversation(6);
+        let pinned = pinned_notes_message(&["never touch prod.env".to_string()]).unwrap();
+        messages.insert(3, pinned.clone());
+        let llm = MockLlmClient::new().with_text_response("they talked about the build");
+
+        let policy = tail(2);
+        summarize_and_prune(&mut messages, 1, &policy, &llm, &test_config().model)
+            .await
+            .unwrap();
+
+        let summary_prompt = llm.call_messages(0)[0].text();
+        assert!(!summary_prompt.contains("never touch prod.env"));
+        assert!(summary_prompt.contains("question 0"));
+        let texts: Vec<String> = messages.iter().map(|m| m.text()).collect();
+        assert!(texts.contains(&pinned.text()));
+        assert!(!texts.iter().any(|t| t.starts_with("question 0")));
+    }
+
+    #[test]
+    fn target_ratio_prunes_below_budget() {
+        let messages = conversation(10);
+        let total: usize = messages.iter().map(estimate_message_tokens).sum();
+        let budget = total - 1;
+
+        let mut full = messages.clone();
+        let removed_full = prune_to_budget(&mut full, budget, &tail(2));
+        let mut half = messages.clone();
+        let policy = PrunePolicy {
+            min_tail: 2,
+            target_ratio: 0.5,
+            ..Default::default()
+        };
+        let removed_half = prune_to_budget(&mut half, budget, &policy);
+
+        assert_eq!(removed_full, 1);
+        assert!(removed_half > removed_full);
+        let remaining: usize = half.iter().map(estimate_message_tokens).sum();
+        assert!(remaining <= budget / 2);
+
+        // Within budget nothing is pruned, whatever the ratio
+        let mut untouched = messages.clone();
+        assert_eq!(prune_to_budget(&mut untouched, total, &policy), 0);
+    }
+
     #[test]
     fn test_failure_tracker() {
         let mut tracker = FailureTracker::default();
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use futures::StreamExt;
@@ -5,41 +6,67 @@ use serde::Deserialize;
 use tracing::warn;
 
 use ryvos_core::config::ModelConfig;
-use ryvos_core::goal::{CriterionType, Goal};
+use ryvos_core::goal::{CriterionResult, CriterionType, Goal};
 use ryvos_core::traits::LlmClient;
 use ryvos_core::types::{ChatMessage, StreamDelta, Verdict};
 
 /// Two-level Judge system.
 ///
-/// Level 0 (fast): Deterministic checks — OutputContains, OutputEquals.
+/// Level 0 (fast): Deterministic checks — OutputContains, OutputEquals, and
+///   workspace checks (file and shell) when a workspace is set.
 /// Level 2 (slow): LLM ConversationJudge — evaluates the full conversation
 ///   against the goal's success criteria and returns a structured Verdict.
 pub struct Judge {
     llm: Arc<dyn LlmClient>,
     config: ModelConfig,
+    workspace: Option<PathBuf>,
+    allowed_commands: Vec<String>,
+}
+
+/// A verdict and the workspace check results it was based on.
+#[derive(Debug, Clone)]
+pub struct Judgment {
+    pub verdict: Verdict,
+    /// Results of the goal's workspace criteria. Empty when the LLM judged,
+    /// since the checks are then not run.
+    pub workspace_results: Vec<CriterionResult>,
 }
 
 impl Judge {
     pub fn new(llm: Arc<dyn LlmClient>, config: ModelConfig) -> Self {
-        Self { llm, config }
+        Self {
+            llm,
+            config,
+            workspace: None,
+            allowed_commands: vec![],
+        }
+    }
+
+    /// Enable workspace criteria (file and shell checks) against `workspace`.
+    /// Only commands in `allowed_commands` may be run by shell checks.
+    pub fn with_workspace(mut self, workspace: PathBuf, allowed_commands: Vec<String>) -> Self {
+        self.workspace = Some(workspace);
+        self.allowed_commands = allowed_commands;
+        self
     }
 
     /// Level 0: Fast deterministic check.
     ///
-    /// Returns `Some(Verdict)` if all deterministic criteria can be evaluated
-    /// (i.e., the goal has no LlmJudge criteria). Returns `None` if LLM
-    /// evaluation is needed.
-    pub fn fast_check(output: &str, goal: &Goal) -> Option<Verdict> {
-        let has_llm_criteria = goal
-            .success_criteria
-            .iter()
-            .any(|c| matches!(c.criterion_type, CriterionType::LlmJudge { .. }));
-
-        if has_llm_criteria {
+    /// `workspace_results` are the goal's already-run workspace checks; they
+    /// are scored alongside the output criteria. Returns `Some(Verdict)` if
+    /// all deterministic criteria can be evaluated (i.e., the goal has no
+    /// LlmJudge criteria). Returns `None` if LLM evaluation is needed.
+    pub fn fast_check(
+        output: &str,
+        goal: &Goal,
+        workspace_results: Vec<CriterionResult>,
+    ) -> Option<Verdict> {
+        if needs_llm(goal) {
             return None; // Need LLM evaluation
         }
 
-        let results = goal.evaluate_deterministic(output);
+        let mut results = goal.evaluate_deterministic(output);
+        results.extend(workspace_results);
         let eval = goal.compute_evaluation(results, vec![]);
 
         if eval.passed {
@@ -136,22 +163,48 @@ Evaluate the agent's output against the goal and criteria. Respond with ONLY val
     }
 
     /// Combined evaluation: try fast check first, fall back to LLM.
+    ///
+    /// Workspace checks run at most once, and only on the fast path; their
+    /// results come back with the verdict for reuse.
     pub async fn evaluate(
         &self,
         output: &str,
         conversation: &[ChatMessage],
         goal: &Goal,
-    ) -> Result<Verdict, String> {
+    ) -> Result<Judgment, String> {
         // Level 0: fast check
-        if let Some(verdict) = Self::fast_check(output, goal) {
-            return Ok(verdict);
+        if !needs_llm(goal) {
+            let workspace_results = match self.workspace {
+                Some(ref workspace) => {
+                    goal.evaluate_workspace(workspace, &self.allowed_commands)
+                        .await
+                }
+                None => vec![],
+            };
+            if let Some(verdict) = Self::fast_check(output, goal, workspace_results.clone()) {
+                return Ok(Judgment {
+                    verdict,
+                    workspace_results,
+                });
+            }
         }
 
         // Level 2: LLM judge
-        self.llm_judge(conversation, goal).await
+        let verdict = self.llm_judge(conversation, goal).await?;
+        Ok(Judgment {
+            verdict,
+            workspace_results: vec![],
+        })
     }
 }
 
+/// Whether `goal` has criteria only the LLM judge can evaluate.
+fn needs_llm(goal: &Goal) -> bool {
+    goal.success_criteria
+        .iter()
+        .any(|c| matches!(c.criterion_type, CriterionType::LlmJudge { .. }))
+}
+
 /// Response from the LLM judge.
 #[derive(Deserialize)]
 struct JudgeResponse {
@@ -252,7 +305,7 @@ mod tests {
     #[test]
     fn test_fast_check_accept() {
         let goal = make_goal(vec![contains_criterion("c1", "hello")], 0.9);
-        let verdict = Judge::fast_check("hello world", &goal);
+        let verdict = Judge::fast_check("hello world", &goal, vec![]);
         assert!(verdict.is_some());
         match verdict.unwrap() {
             Verdict::Accept { confidence } => {
@@ -265,7 +318,7 @@ mod tests {
     #[test]
     fn test_fast_check_retry() {
         let goal = make_goal(vec![contains_criterion("c1", "goodbye")], 0.9);
-        let verdict = Judge::fast_check("hello world", &goal);
+        let verdict = Judge::fast_check("hello world", &goal, vec![]);
         assert!(verdict.is_some());
         match verdict.unwrap() {
             Verdict::Retry { reason, hint } => {
@@ -291,7 +344,75 @@ mod tests {
         ];
         let goal = make_goal(criteria, 0.5);
         // Returns None because LLM evaluation is needed
-        assert!(Judge::fast_check("hello", &goal).is_none());
+        assert!(Judge::fast_check("hello", &goal, vec![]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_accepts_file_exists_once_file_exists() {
+        let dir = std::env::temp_dir().join(format!("ryvos_judge_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let goal = make_goal(
+            vec![SuccessCriterion {
+                id: "f1".to_string(),
+                criterion_type: CriterionType::FileExists {
+                    path: "out.txt".to_string(),
+                },
+                weight: 1.0,
+                description: "out.txt exists".to_string(),
+            }],
+            0.9,
+        );
+        let llm: Arc<dyn LlmClient> = Arc::new(ryvos_test_utils::MockLlmClient::new());
+        let judge = Judge::new(llm, ryvos_test_utils::test_config().model)
+            .with_workspace(dir.clone(), vec![]);
+
+        let before = judge.evaluate("done", &[], &goal).await.unwrap();
+        assert!(matches!(before.verdict, Verdict::Retry { .. }));
+
+        std::fs::write(dir.join("out.txt"), "x").unwrap();
+        let after = judge.evaluate("done", &[], &goal).await.unwrap();
+        assert!(matches!(after.verdict, Verdict::Accept { .. }));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_skips_workspace_checks_for_llm_judged_goals() {
+        let log = std::env::temp_dir().join(format!("ryvos_judge_{}.log", uuid::Uuid::new_v4()));
+        let command = format!("echo run >> {}", log.display());
+        let goal = make_goal(
+            vec![
+                SuccessCriterion {
+                    id: "s1".to_string(),
+                    criterion_type: CriterionType::ShellExitCode {
+                        command: command.clone(),
+                        expected_code: 0,
+                    },
+                    weight: 1.0,
+                    description: "checks pass".to_string(),
+                },
+                SuccessCriterion {
+                    id: "l1".to_string(),
+                    criterion_type: CriterionType::LlmJudge {
+                        prompt: "Is it good?".to_string(),
+                    },
+                    weight: 1.0,
+                    description: "llm judge".to_string(),
+                },
+            ],
+            0.5,
+        );
+        let llm: Arc<dyn LlmClient> = Arc::new(
+            ryvos_test_utils::MockLlmClient::new()
+                .with_text_response(r#"{"verdict": "accept", "confidence": 0.9}"#),
+        );
+        let judge = Judge::new(llm, ryvos_test_utils::test_config().model)
+            .with_workspace(std::env::temp_dir(), vec![command]);
+
+        let judgment = judge.evaluate("done", &[], &goal).await.unwrap();
+        assert!(matches!(judgment.verdict, Verdict::Accept { .. }));
+        assert!(judgment.workspace_results.is_empty());
+        assert!(!log.exists());
     }
 
     #[test]
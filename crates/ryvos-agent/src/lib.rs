@@ -20,9 +20,12 @@
 
 pub mod agent_loop;
 pub mod approval;
+pub mod approval_rules;
 pub mod audit;
+pub mod bench;
 pub mod checkpoint;
 pub mod context;
+pub mod decision_report;
 pub mod director;
 pub mod evaluator;
 pub mod gate;
@@ -32,6 +35,7 @@ pub mod healing;
 pub mod heartbeat;
 pub mod intelligence;
 pub mod judge;
+pub mod metrics;
 pub mod orchestrator;
 pub mod output_validator;
 pub mod prime;
@@ -39,10 +43,13 @@ pub mod run_log;
 pub mod safety_memory;
 pub mod scheduler;
 pub mod session;
+pub mod session_export;
 
 pub use agent_loop::AgentRuntime;
 pub use approval::ApprovalBroker;
+pub use approval_rules::ApprovalRuleStore;
 pub use audit::AuditTrail;
+pub use bench::{run_bench, BenchReport};
 pub use checkpoint::CheckpointStore;
 pub use director::Director;
 pub use evaluator::GoalEvaluator;
@@ -51,13 +58,14 @@ pub use graph::{
     Edge, EdgeCondition, ExecutionResult, GraphExecutor, HandoffContext, Node, NodeResult,
 };
 pub use guardian::{Guardian, GuardianAction};
-pub use healing::FailureJournal;
-pub use heartbeat::Heartbeat;
-pub use judge::Judge;
+pub use healing::{FailureJournal, LatencySummary, ToolHealth};
+pub use heartbeat::{Heartbeat, HeartbeatOutcome};
+pub use judge::{Judge, Judgment};
+pub use metrics::{MetricsSnapshot, RuntimeMetrics};
 pub use orchestrator::{AgentCapability, MultiAgentOrchestrator, OrchestratorBuilder};
 pub use output_validator::{OutputCleaner, OutputValidator};
 pub use prime::PrimeOrchestrator;
-pub use run_log::RunLogger;
+pub use run_log::{LogFilter, LogTail, RunLogger};
 pub use safety_memory::SafetyMemory;
 pub use scheduler::CronScheduler;
 pub use session::SessionManager;
@@ -73,4 +81,8 @@ impl AgentSpawner for AgentRuntime {
             self.run(&sub_session, &prompt).await
         })
     }
+
+    fn spawn_sub_agent(&self, prompt: String, depth: u32) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move { self.run_sub_agent(&prompt, depth).await })
+    }
 }
@@ -0,0 +1,85 @@
+//! In-process activity counters for an [`AgentRuntime`](crate::AgentRuntime).
+//!
+//! Plain relaxed atomics, bumped from the run loop and read by the REPL's
+//! `/status` and the gateway's `GET /api/status`. They count from process
+//! start and are not persisted; the cost store keeps the durable history.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use serde::Serialize;
+
+#[derive(Debug)]
+pub struct RuntimeMetrics {
+    started: Instant,
+    runs: AtomicU64,
+    run_errors: AtomicU64,
+    tool_calls: AtomicU64,
+    tool_errors: AtomicU64,
+    input_tokens: AtomicU64,
+    output_tokens: AtomicU64,
+}
+
+/// Point-in-time copy of [`RuntimeMetrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    pub uptime_secs: u64,
+    pub runs: u64,
+    pub run_errors: u64,
+    pub tool_calls: u64,
+    pub tool_errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl RuntimeMetrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            runs: AtomicU64::new(0),
+            run_errors: AtomicU64::new(0),
+            tool_calls: AtomicU64::new(0),
+            tool_errors: AtomicU64::new(0),
+            input_tokens: AtomicU64::new(0),
+            output_tokens: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record_run(&self, ok: bool) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.run_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_tool_call(&self, is_error: bool) {
+        self.tool_calls.fetch_add(1, Ordering::Relaxed);
+        if is_error {
+            self.tool_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_usage(&self, input_tokens: u64, output_tokens: u64) {
+        self.input_tokens.fetch_add(input_tokens, Ordering::Relaxed);
+        self.output_tokens
+            .fetch_add(output_tokens, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            uptime_secs: self.started.elapsed().as_secs(),
+            runs: self.runs.load(Ordering::Relaxed),
+            run_errors: self.run_errors.load(Ordering::Relaxed),
+            tool_calls: self.tool_calls.load(Ordering::Relaxed),
+            tool_errors: self.tool_errors.load(Ordering::Relaxed),
+            input_tokens: self.input_tokens.load(Ordering::Relaxed),
+            output_tokens: self.output_tokens.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for RuntimeMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use futures::future::BoxFuture;
 use tracing::{debug, info, warn};
 
-use ryvos_core::config::AppConfig;
+use ryvos_core::config::{AppConfig, OrchestratorAgentConfig, OrchestratorConfig};
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::event::EventBus;
 use ryvos_core::goal::Goal;
@@ -50,6 +50,19 @@ impl AgentCapability {
         }
     }
 
+    /// Build a capability from an `[orchestrator.agents.<id>]` entry.
+    pub fn from_config(agent_id: &str, config: &OrchestratorAgentConfig) -> Self {
+        Self {
+            agent_id: agent_id.to_string(),
+            name: config.name.clone().unwrap_or_else(|| agent_id.to_string()),
+            tools: config.tools.clone(),
+            specializations: config.capabilities.clone(),
+            policy: config.security.as_ref().map(|s| s.to_policy()),
+            goal: None,
+            model: config.model.clone(),
+        }
+    }
+
     /// Set a model override for this agent.
     pub fn with_model(mut self, model: ryvos_core::config::ModelConfig) -> Self {
         self.model = Some(model);
@@ -132,6 +145,38 @@ pub struct AgentDispatchResult {
     pub succeeded: bool,
 }
 
+/// A request from one agent to pass the task on to another.
+#[derive(Debug, Clone, PartialEq)]
+struct Handoff {
+    /// Agent the task is handed to.
+    to: String,
+    /// What the receiving agent should do.
+    instructions: String,
+}
+
+/// Split a trailing `HANDOFF <agent_id>: <instructions>` line off an agent's
+/// output. Returns the remaining output and the handoff, if any.
+fn parse_handoff(output: &str) -> (String, Option<Handoff>) {
+    let trimmed = output.trim_end();
+    let (body, last) = match trimmed.rsplit_once('\n') {
+        Some((body, last)) => (body, last),
+        None => ("", trimmed),
+    };
+    let handoff = last
+        .trim()
+        .strip_prefix("HANDOFF ")
+        .and_then(|rest| rest.split_once(':'))
+        .map(|(to, instructions)| Handoff {
+            to: to.trim().to_string(),
+            instructions: instructions.trim().to_string(),
+        })
+        .filter(|h| !h.to.is_empty());
+    match handoff {
+        Some(h) => (body.trim_end().to_string(), Some(h)),
+        None => (output.to_string(), None),
+    }
+}
+
 /// Shared infrastructure for building agent runtimes.
 pub struct OrchestratorBuilder {
     pub config: AppConfig,
@@ -152,6 +197,7 @@ pub struct MultiAgentOrchestrator {
     agents: HashMap<String, AgentCapability>,
     default_policy: SecurityPolicy,
     builder: OrchestratorBuilder,
+    max_handoffs: usize,
 }
 
 impl MultiAgentOrchestrator {
@@ -161,9 +207,24 @@ impl MultiAgentOrchestrator {
             agents: HashMap::new(),
             default_policy,
             builder,
+            max_handoffs: OrchestratorConfig::default().max_handoffs,
         }
     }
 
+    /// Create an orchestrator with the agents from an `[orchestrator]` section.
+    pub fn from_config(
+        config: &OrchestratorConfig,
+        default_policy: SecurityPolicy,
+        builder: OrchestratorBuilder,
+    ) -> Self {
+        let mut orchestrator = Self::new(default_policy, builder);
+        orchestrator.max_handoffs = config.max_handoffs;
+        for (agent_id, agent) in &config.agents {
+            orchestrator.register(AgentCapability::from_config(agent_id, agent));
+        }
+        orchestrator
+    }
+
     /// Register an agent with its capabilities.
     pub fn register(&mut self, capability: AgentCapability) {
         self.agents.insert(capability.agent_id.clone(), capability);
@@ -180,15 +241,95 @@ impl MultiAgentOrchestrator {
     }
 
     /// Find the best agent for a given task.
+    /// Ties go to the agent id that sorts first.
     /// Returns None if no agents are registered.
     pub fn route(&self, task: &str, required_tools: &[String]) -> Option<&AgentCapability> {
         self.agents
             .values()
             .map(|a| (a, a.match_score(task, required_tools)))
-            .max_by(|(_, sa), (_, sb)| sa.partial_cmp(sb).unwrap_or(std::cmp::Ordering::Equal))
+            .max_by(|(a, sa), (b, sb)| {
+                sa.partial_cmp(sb)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| b.agent_id.cmp(&a.agent_id))
+            })
             .map(|(a, _)| a)
     }
 
+    /// Route a task to the best-matching agent and follow its handoffs.
+    ///
+    /// An agent hands off by ending its reply with a line
+    /// `HANDOFF <agent_id>: <instructions>`. The receiving agent gets the
+    /// original task, the previous agent's output, and the instructions.
+    /// Returns one result per agent run, in order.
+    pub async fn orchestrate(&self, task: &str) -> Result<Vec<AgentDispatchResult>> {
+        let mut agent = self
+            .route(task, &[])
+            .ok_or_else(|| RyvosError::Config("No agents registered".into()))?;
+        info!(agent_id = %agent.agent_id, name = %agent.name, "Routing task to agent");
+
+        let mut prompt = self.orchestration_prompt(agent, task);
+        let mut results = Vec::new();
+        loop {
+            let mut result = self.run_agent(agent, &prompt).await?;
+            let (output, handoff) = parse_handoff(&result.output);
+            result.output = output;
+            let from = result.agent_id.clone();
+            let context = result.output.clone();
+            let succeeded = result.succeeded;
+            results.push(result);
+
+            let Some(handoff) = handoff.filter(|_| succeeded) else {
+                break;
+            };
+            if results.len() > self.max_handoffs {
+                warn!(max = self.max_handoffs, "Handoff limit reached, stopping");
+                break;
+            }
+            let Some(next) = self.agents.get(&handoff.to) else {
+                warn!(from = %from, to = %handoff.to, "Handoff to unknown agent ignored");
+                break;
+            };
+
+            info!(from = %from, to = %next.agent_id, "Handing off task");
+            let received = format!(
+                "Handoff from {}:\n---\n{}\n---\n\nOriginal task: {}\n\nYour task: {}",
+                from, context, task, handoff.instructions
+            );
+            prompt = self.orchestration_prompt(next, &received);
+            agent = next;
+        }
+
+        Ok(results)
+    }
+
+    /// Append the handoff instructions and the other agents to a prompt.
+    fn orchestration_prompt(&self, agent: &AgentCapability, task: &str) -> String {
+        let mut others: Vec<_> = self
+            .agents
+            .values()
+            .filter(|a| a.agent_id != agent.agent_id)
+            .collect();
+        if others.is_empty() {
+            return task.to_string();
+        }
+        others.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+
+        let mut prompt = format!("{}\n\nOther agents can continue this task:\n", task);
+        for other in others {
+            prompt.push_str(&format!(
+                "- {} ({}): {}\n",
+                other.agent_id,
+                other.name,
+                other.specializations.join(", ")
+            ));
+        }
+        prompt.push_str(
+            "If one of them should take over, end your reply with a line \
+             `HANDOFF <agent_id>: <what they should do>`.",
+        );
+        prompt
+    }
+
     /// Dispatch a task to the best-matching agent.
     pub async fn dispatch(&self, task: &str) -> Result<AgentDispatchResult> {
         let agent = self
@@ -327,9 +468,17 @@ impl MultiAgentOrchestrator {
             .clone()
             .unwrap_or_else(|| self.default_policy.clone());
 
+        // Restrict the registry to the agent's tools, if it lists any
+        let tools = if capability.tools.is_empty() {
+            self.builder.tools.clone()
+        } else {
+            let subset = self.builder.tools.read().await.subset(&capability.tools);
+            Arc::new(tokio::sync::RwLock::new(subset))
+        };
+
         let gate = Arc::new(SecurityGate::new(
             policy,
-            self.builder.tools.clone(),
+            tools,
             self.builder.broker.clone(),
             self.builder.event_bus.clone(),
         ));
@@ -479,4 +628,107 @@ mod tests {
         assert!(best.is_some());
         assert_eq!(best.unwrap().agent_id, "coder");
     }
+
+    fn orchestrator(
+        llm: &ryvos_test_utils::MockLlmClient,
+        max_handoffs: usize,
+    ) -> MultiAgentOrchestrator {
+        let agent = |name: Option<&str>, capabilities: &[&str]| OrchestratorAgentConfig {
+            name: name.map(String::from),
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        let config = OrchestratorConfig {
+            agents: HashMap::from([
+                (
+                    "coder".into(),
+                    agent(Some("Code Agent"), &["code", "debug"]),
+                ),
+                ("writer".into(), agent(None, &["writing", "docs"])),
+            ]),
+            max_handoffs,
+        };
+        let event_bus = Arc::new(EventBus::default());
+        let builder = OrchestratorBuilder {
+            config: ryvos_test_utils::test_config(),
+            llm: Arc::new(llm.clone()),
+            tools: Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store: Arc::new(ryvos_test_utils::InMemorySessionStore::new()),
+            event_bus: event_bus.clone(),
+            broker: Arc::new(ApprovalBroker::new(event_bus)),
+        };
+        MultiAgentOrchestrator::from_config(&config, SecurityPolicy::default(), builder)
+    }
+
+    #[tokio::test]
+    async fn orchestrate_routes_to_matching_capability() {
+        let llm = ryvos_test_utils::MockLlmClient::new().with_text_response("fixed");
+        let orchestrator = orchestrator(&llm, 3);
+
+        let results = orchestrator
+            .orchestrate("debug the failing code")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].agent_id, "coder");
+        assert_eq!(results[0].output, "fixed");
+
+        // The routed agent is told who it can hand off to
+        let prompt = llm.call_messages(0).last().unwrap().text();
+        assert!(prompt.contains("- writer (writer): writing, docs"));
+        assert!(!prompt.contains("- coder"));
+    }
+
+    #[tokio::test]
+    async fn handoff_passes_context_to_next_agent() {
+        let llm = ryvos_test_utils::MockLlmClient::new()
+            .with_text_response("Found the bug in parse().\nHANDOFF writer: write release notes")
+            .with_text_response("Notes written");
+        let orchestrator = orchestrator(&llm, 3);
+
+        let results = orchestrator
+            .orchestrate("debug the parser code")
+            .await
+            .unwrap();
+        let agents: Vec<_> = results.iter().map(|r| r.agent_id.as_str()).collect();
+        assert_eq!(agents, vec!["coder", "writer"]);
+        assert_eq!(results[0].output, "Found the bug in parse().");
+        assert_eq!(results[1].output, "Notes written");
+
+        let received = llm.call_messages(1).last().unwrap().text();
+        assert!(received.contains("Handoff from coder"));
+        assert!(received.contains("Found the bug in parse()."));
+        assert!(received.contains("Original task: debug the parser code"));
+        assert!(received.contains("Your task: write release notes"));
+    }
+
+    #[tokio::test]
+    async fn handoffs_stop_at_limit() {
+        let llm = ryvos_test_utils::MockLlmClient::new()
+            .with_text_response("a\nHANDOFF writer: continue")
+            .with_text_response("b\nHANDOFF coder: continue")
+            .with_text_response("c\nHANDOFF writer: continue");
+        let orchestrator = orchestrator(&llm, 1);
+
+        let results = orchestrator.orchestrate("debug code").await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[test]
+    fn parse_handoff_splits_trailing_directive() {
+        let (body, handoff) = parse_handoff("done\nHANDOFF writer: polish it\n");
+        assert_eq!(body, "done");
+        assert_eq!(
+            handoff,
+            Some(Handoff {
+                to: "writer".into(),
+                instructions: "polish it".into(),
+            })
+        );
+
+        let (body, handoff) = parse_handoff("no HANDOFF here");
+        assert_eq!(body, "no HANDOFF here");
+        assert!(handoff.is_none());
+    }
 }
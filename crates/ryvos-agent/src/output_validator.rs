@@ -3,7 +3,7 @@ use std::sync::Arc;
 use futures::StreamExt;
 use tracing::{debug, warn};
 
-use ryvos_core::config::ModelConfig;
+use ryvos_core::config::{ModelConfig, OutputProcessor};
 use ryvos_core::traits::LlmClient;
 use ryvos_core::types::{ChatMessage, StreamDelta};
 
@@ -127,6 +127,17 @@ impl OutputCleaner {
         result
     }
 
+    /// Apply `[agent.output] processors` to a repaired answer, in order.
+    pub fn post_process(output: &str, processors: &[OutputProcessor]) -> String {
+        processors
+            .iter()
+            .fold(output.to_string(), |text, processor| match processor {
+                OutputProcessor::StripMarkdown => strip_markdown(&text),
+                OutputProcessor::Wrap { width } => wrap_lines(&text, *width),
+                OutputProcessor::Template { template } => template.replace("{output}", &text),
+            })
+    }
+
     /// Ask the LLM to fix malformed output.
     pub async fn llm_repair(&self, output: &str, issues: &[String]) -> Result<String, String> {
         let llm = self
@@ -204,6 +215,171 @@ fn strip_code_fences(text: &str) -> String {
     trimmed.to_string()
 }
 
+/// Remove markdown syntax line by line: fences and rules are dropped,
+/// heading and quote markers removed, bullets normalized to `- `, and
+/// emphasis, inline code and links reduced to their text. Lines inside a
+/// fenced code block are kept as they are.
+fn strip_markdown(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let body = line.trim_start();
+        if body.starts_with("```") || body.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            lines.push(line.to_string());
+            continue;
+        }
+        let indent = &line[..line.len() - body.len()];
+        let bare: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+        if bare.len() >= 3 && ["-", "*", "_"].iter().any(|r| bare == r.repeat(bare.len())) {
+            continue;
+        }
+        let mut body = body;
+        let unhashed = body.trim_start_matches('#');
+        if unhashed.len() < body.len() && (unhashed.is_empty() || unhashed.starts_with(' ')) {
+            body = unhashed.trim_start();
+        }
+        while let Some(rest) = body.strip_prefix('>') {
+            body = rest.trim_start();
+        }
+        let body = match body.strip_prefix("* ").or_else(|| body.strip_prefix("+ ")) {
+            Some(rest) => format!("- {}", rest),
+            None => body.to_string(),
+        };
+        lines.push(format!("{}{}", indent, strip_inline_markdown(&body)));
+    }
+    lines.join("\n")
+}
+
+/// Drop emphasis and code markers, and turn `[text](url)` into
+/// `text (url)` and `![alt](url)` into `alt`. Inline code keeps its text
+/// as written.
+fn strip_inline_markdown(text: &str) -> String {
+    let text = if text.matches('`').count().is_multiple_of(2) {
+        // Odd pieces are inside backticks
+        text.split('`')
+            .enumerate()
+            .map(|(i, piece)| {
+                if i.is_multiple_of(2) {
+                    strip_emphasis(piece)
+                } else {
+                    piece.to_string()
+                }
+            })
+            .collect()
+    } else {
+        strip_emphasis(text).replace('`', "")
+    };
+    let mut out = String::new();
+    let mut rest = text.as_str();
+    while let Some(open) = rest.find('[') {
+        let image = rest[..open].ends_with('!');
+        let link = rest[open + 1..].find("](").and_then(|close| {
+            let label = &rest[open + 1..open + 1 + close];
+            let after = &rest[open + close + 3..];
+            after
+                .find(')')
+                .map(|end| (label, &after[..end], &after[end + 1..]))
+        });
+        let Some((label, url, after)) = link else {
+            out.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        };
+        if image {
+            out.push_str(&rest[..open - 1]);
+            out.push_str(label);
+        } else {
+            out.push_str(&rest[..open]);
+            out.push_str(&format!("{} ({})", label, url));
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Drop paired `*` and `_` emphasis delimiters, one to three long. A
+/// delimiter opens before text and closes after it; `_` inside a word,
+/// as in `snake_case`, is not a delimiter.
+fn strip_emphasis(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let run_at = |i: usize| chars[i..].iter().take_while(|&&c| c == chars[i]).count();
+    let mut keep = vec![true; chars.len()];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if !keep[i] || (c != '*' && c != '_') {
+            i += 1;
+            continue;
+        }
+        let run = run_at(i);
+        let before = i.checked_sub(1).map(|j| chars[j]);
+        let after = chars.get(i + run);
+        let opens = run <= 3
+            && after.is_some_and(|a| !a.is_whitespace())
+            && !(c == '_' && before.is_some_and(char::is_alphanumeric));
+        if opens {
+            let mut j = i + run;
+            while j < chars.len() {
+                if chars[j] != c || !keep[j] {
+                    j += 1;
+                    continue;
+                }
+                let close = run_at(j);
+                let after = chars.get(j + close);
+                if close == run
+                    && !chars[j - 1].is_whitespace()
+                    && !(c == '_' && after.is_some_and(|a| a.is_alphanumeric()))
+                {
+                    keep[i..i + run].fill(false);
+                    keep[j..j + run].fill(false);
+                    break;
+                }
+                j += close;
+            }
+        }
+        i += run;
+    }
+    chars
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(c, keep)| keep.then_some(c))
+        .collect()
+}
+
+/// Wrap lines longer than `width` characters at spaces, keeping each
+/// line's indentation. Words longer than `width` get a line of their own.
+fn wrap_lines(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    for line in text.lines() {
+        if line.chars().count() <= width {
+            out.push(line.to_string());
+            continue;
+        }
+        let body = line.trim_start();
+        let indent = &line[..line.len() - body.len()];
+        let mut current = String::new();
+        for word in body.split_whitespace() {
+            let len = indent.chars().count() + current.chars().count();
+            if !current.is_empty() && len + 1 + word.chars().count() > width {
+                out.push(format!("{}{}", indent, current));
+                current.clear();
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        out.push(format!("{}{}", indent, current));
+    }
+    out.join("\n")
+}
+
 /// Balance JSON braces/brackets by appending missing closers.
 fn balance_braces(text: &str) -> String {
     let mut brace_depth: i32 = 0;
@@ -297,6 +473,42 @@ mod tests {
         assert_eq!(result, input);
     }
 
+    fn processors(chain: serde_json::Value) -> Vec<OutputProcessor> {
+        serde_json::from_value(chain).unwrap()
+    }
+
+    #[test]
+    fn wrap_then_template_formats_the_answer() {
+        let chain = processors(serde_json::json!([
+            { "type": "wrap", "width": 20 },
+            { "type": "template", "template": "Answer:\n{output}\n-- ryvos" },
+        ]));
+        let answer =
+            "The deploy finished in four minutes with no errors.\n  indented and also long enough";
+        assert_eq!(
+            OutputCleaner::post_process(answer, &chain),
+            "Answer:\nThe deploy finished\nin four minutes with\nno errors.\n  indented and also\n  long enough\n-- ryvos"
+        );
+        assert!(OutputCleaner::post_process(answer, &[]) == answer);
+    }
+
+    #[test]
+    fn strip_markdown_keeps_the_text() {
+        let chain = processors(serde_json::json!([{ "type": "strip_markdown" }]));
+        let answer = "## Summary\n\n> **Done**: see [the log](http://x/log) and `ryvos logs`.\n\n---\n* one\n```\nls -la\n```\n![chart](c.png) snake_case";
+        assert_eq!(
+            OutputCleaner::post_process(answer, &chain),
+            "Summary\n\nDone: see the log (http://x/log) and ryvos logs.\n\n- one\nls -la\nchart snake_case"
+        );
+
+        // Single emphasis goes; code, fenced or inline, stays as written
+        let answer = "*Really* _done_, ***twice***: 2 * 3 in `my_*glob*`\n```sh\n# list **all** files\nls -la *.rs\n```\n# After";
+        assert_eq!(
+            OutputCleaner::post_process(answer, &chain),
+            "Really done, twice: 2 * 3 in my_*glob*\n# list **all** files\nls -la *.rs\nAfter"
+        );
+    }
+
     #[test]
     fn test_validator_required_keys() {
         let validator = OutputValidator {
@@ -1,7 +1,9 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use tokio::io::AsyncWriteExt;
 use tokio_util::sync::CancellationToken;
@@ -144,6 +146,7 @@ impl RunLogger {
                 total_turns,
                 input_tokens,
                 output_tokens,
+                context_tokens,
                 ..
             } => Some(LogEntry {
                 timestamp: ts,
@@ -154,8 +157,16 @@ impl RunLogger {
                     "total_turns": total_turns,
                     "input_tokens": input_tokens,
                     "output_tokens": output_tokens,
+                    "context_tokens": context_tokens,
                 })),
             }),
+            AgentEvent::TurnLimitReached { max_turns, .. } => Some(LogEntry {
+                timestamp: ts,
+                session_id: session_id.to_string(),
+                event_type: "turn_limit_reached".to_string(),
+                turn: Some(*max_turns),
+                detail: Some(serde_json::json!({ "max_turns": max_turns })),
+            }),
             AgentEvent::RunError { error } => Some(LogEntry {
                 timestamp: ts,
                 session_id: session_id.to_string(),
@@ -175,6 +186,7 @@ impl RunLogger {
             AgentEvent::UsageUpdate {
                 input_tokens,
                 output_tokens,
+                thinking_tokens,
             } if self.level >= 2 => Some(LogEntry {
                 timestamp: ts,
                 session_id: session_id.to_string(),
@@ -183,6 +195,7 @@ impl RunLogger {
                 detail: Some(serde_json::json!({
                     "input_tokens": input_tokens,
                     "output_tokens": output_tokens,
+                    "thinking_tokens": thinking_tokens,
                 })),
             }),
             AgentEvent::GoalEvaluated { evaluation, .. } if self.level >= 2 => Some(LogEntry {
@@ -229,6 +242,23 @@ impl RunLogger {
                     "reason": reason,
                 })),
             }),
+            AgentEvent::PromptInjectionDetected {
+                name,
+                label,
+                fragment,
+                in_output,
+            } => Some(LogEntry {
+                timestamp: ts,
+                session_id: session_id.to_string(),
+                event_type: "prompt_injection".to_string(),
+                turn: None,
+                detail: Some(serde_json::json!({
+                    "tool": name,
+                    "label": label,
+                    "fragment": fragment,
+                    "in_output": in_output,
+                })),
+            }),
             AgentEvent::DecisionMade { decision } if self.level >= 3 => Some(LogEntry {
                 timestamp: ts,
                 session_id: session_id.to_string(),
@@ -289,9 +319,23 @@ impl RunLogger {
                     "consecutive_calls": consecutive_calls,
                 })),
             }),
+            AgentEvent::GuardianRepeatedAnswer {
+                repeats, action, ..
+            } if self.level >= 2 => Some(LogEntry {
+                timestamp: ts,
+                session_id: session_id.to_string(),
+                event_type: "guardian_repeated_answer".to_string(),
+                turn: None,
+                detail: Some(serde_json::json!({
+                    "repeats": repeats,
+                    "action": action.to_string(),
+                })),
+            }),
             AgentEvent::GuardianBudgetAlert {
                 used_tokens,
                 budget_tokens,
+                used_usd,
+                budget_usd,
                 is_hard_stop,
                 ..
             } if self.level >= 2 => Some(LogEntry {
@@ -302,16 +346,149 @@ impl RunLogger {
                 detail: Some(serde_json::json!({
                     "used_tokens": used_tokens,
                     "budget_tokens": budget_tokens,
+                    "used_usd": used_usd,
+                    "budget_usd": budget_usd,
                     "is_hard_stop": is_hard_stop,
                 })),
             }),
 
+            // Sub-agent activity goes in the parent's log under its own session
+            AgentEvent::SubAgent {
+                session_id, event, ..
+            } => self.event_to_entry(&session_id.0, event),
+
             // Everything else: not logged (TextDelta, ThinkingDelta, etc.)
             _ => None,
         }
     }
 }
 
+/// The verbosity level `event_type` is logged at: 1 for the run summary,
+/// 2 for per-turn events, 3 for per-step events.
+pub fn event_level(event_type: &str) -> u8 {
+    match event_type {
+        "run_started" | "run_complete" | "run_error" | "prompt_injection" => 1,
+        "tool_start" | "tool_end" | "tool_blocked" | "decision_made" => 3,
+        _ => 2,
+    }
+}
+
+/// Which log entries `ryvos logs` shows.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Only entries for this session (sub-agent entries carry their own).
+    pub session: Option<String>,
+    /// Only entries logged at this verbosity level or below.
+    pub level: Option<u8>,
+    /// Only entries written at or after this time.
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &serde_json::Value) -> bool {
+        if let Some(ref session) = self.session {
+            if entry["session_id"].as_str() != Some(session.as_str()) {
+                return false;
+            }
+        }
+        if let Some(level) = self.level {
+            if event_level(entry["event_type"].as_str().unwrap_or_default()) > level {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            let written = entry["timestamp"]
+                .as_str()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+            match written {
+                Some(t) if t >= since => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Reads the JSONL files under a log directory incrementally.
+///
+/// Each [`poll`](Self::poll) returns the entries appended since the last
+/// one, across every session directory, including files created in the
+/// meantime (each run gets a new file). A file that shrank was replaced
+/// or truncated and is read again from the start; a last line still being
+/// written is left for the next poll.
+pub struct LogTail {
+    log_dir: PathBuf,
+    filter: LogFilter,
+    offsets: HashMap<PathBuf, u64>,
+}
+
+impl LogTail {
+    pub fn new(log_dir: PathBuf, filter: LogFilter) -> Self {
+        Self {
+            log_dir,
+            filter,
+            offsets: HashMap::new(),
+        }
+    }
+
+    /// Matching entries written since the last poll, oldest file first.
+    pub fn poll(&mut self) -> std::io::Result<Vec<serde_json::Value>> {
+        let mut entries = Vec::new();
+        for path in log_files(&self.log_dir)? {
+            let offset = self.offsets.get(&path).copied().unwrap_or(0);
+            let (lines, next) = read_complete_lines(&path, offset)?;
+            self.offsets.insert(path, next);
+            entries.extend(
+                lines
+                    .iter()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .filter(|entry| self.filter.matches(entry)),
+            );
+        }
+        Ok(entries)
+    }
+}
+
+/// Every `*.jsonl` file one level below `log_dir`, ordered by file name
+/// (the run's start time) and then by session directory.
+fn log_files(log_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let sessions = match std::fs::read_dir(log_dir) {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(e),
+    };
+    for session in sessions.flatten() {
+        let Ok(dir) = std::fs::read_dir(session.path()) else {
+            continue;
+        };
+        files.extend(
+            dir.flatten()
+                .map(|f| f.path())
+                .filter(|p| p.extension().is_some_and(|e| e == "jsonl")),
+        );
+    }
+    files.sort_by(|a, b| (a.file_name(), a).cmp(&(b.file_name(), b)));
+    Ok(files)
+}
+
+/// The whole lines in `path` from byte `offset` on, and the offset after
+/// the last of them.
+fn read_complete_lines(path: &Path, offset: u64) -> std::io::Result<(Vec<String>, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let offset = if len < offset { 0 } else { offset };
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let lines = String::from_utf8_lossy(&buf[..complete])
+        .lines()
+        .map(String::from)
+        .collect();
+    Ok((lines, offset + complete as u64))
+}
+
 /// Truncate a JSON value for logging.
 fn truncate_json(value: &serde_json::Value, max_len: usize) -> String {
     let s = value.to_string();
@@ -372,6 +549,106 @@ mod tests {
         assert!(json.contains("input_tokens"));
     }
 
+    fn write_log(dir: &Path, session: &str, file: &str, lines: &[(&str, &str)]) -> PathBuf {
+        let session_dir = dir.join(session);
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let path = session_dir.join(file);
+        let mut text = String::new();
+        for (session_id, event_type) in lines {
+            text.push_str(&format!(
+                "{{\"timestamp\":\"2026-02-24T12:00:00Z\",\"session_id\":\"{}\",\"event_type\":\"{}\"}}\n",
+                session_id, event_type
+            ));
+        }
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn temp_log_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ryvos_logs_{}", uuid::Uuid::new_v4()))
+    }
+
+    fn kinds(entries: &[serde_json::Value]) -> Vec<&str> {
+        entries
+            .iter()
+            .map(|e| e["event_type"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn tail_filters_by_session_and_level() {
+        let dir = temp_log_dir();
+        write_log(
+            &dir,
+            "a",
+            "20260224_120000.jsonl",
+            &[
+                ("a", "run_started"),
+                ("a", "tool_start"),
+                ("sub", "tool_end"),
+                ("a", "run_complete"),
+            ],
+        );
+        write_log(&dir, "b", "20260224_120100.jsonl", &[("b", "run_started")]);
+
+        let filter = LogFilter {
+            session: Some("a".into()),
+            ..Default::default()
+        };
+        let entries = LogTail::new(dir.clone(), filter).poll().unwrap();
+        assert_eq!(
+            kinds(&entries),
+            ["run_started", "tool_start", "run_complete"]
+        );
+
+        let filter = LogFilter {
+            session: Some("a".into()),
+            level: Some(1),
+            ..Default::default()
+        };
+        let entries = LogTail::new(dir.clone(), filter).poll().unwrap();
+        assert_eq!(kinds(&entries), ["run_started", "run_complete"]);
+
+        let filter = LogFilter {
+            since: Some(Utc::now()),
+            ..Default::default()
+        };
+        assert!(LogTail::new(dir.clone(), filter).poll().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn tail_picks_up_appended_lines_and_new_files() {
+        use std::io::Write;
+
+        let dir = temp_log_dir();
+        let path = write_log(&dir, "a", "20260224_120000.jsonl", &[("a", "run_started")]);
+        let mut tail = LogTail::new(dir.clone(), LogFilter::default());
+        assert_eq!(kinds(&tail.poll().unwrap()), ["run_started"]);
+        assert!(tail.poll().unwrap().is_empty());
+
+        // A line still being written waits for its newline
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(br#"{"timestamp":"2026-02-24T12:00:01Z","session_id":"a","#)
+            .unwrap();
+        assert!(tail.poll().unwrap().is_empty());
+        file.write_all(b"\"event_type\":\"turn_complete\"}\n")
+            .unwrap();
+        assert_eq!(kinds(&tail.poll().unwrap()), ["turn_complete"]);
+
+        // The next run writes a new file
+        write_log(&dir, "a", "20260224_130000.jsonl", &[("a", "run_error")]);
+        assert_eq!(kinds(&tail.poll().unwrap()), ["run_error"]);
+
+        // A truncated or replaced file is read from the start
+        write_log(&dir, "a", "20260224_120000.jsonl", &[("a", "run_complete")]);
+        assert_eq!(kinds(&tail.poll().unwrap()), ["run_complete"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_truncate_json() {
         let value = serde_json::json!({"key": "a very long string that should be truncated"});
@@ -2,12 +2,13 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use cron::Schedule;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 use ryvos_core::config::CronConfig;
+use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::event::EventBus;
 use ryvos_core::goal::{CriterionType, Goal, SuccessCriterion};
 use ryvos_core::types::{AgentEvent, SessionId};
@@ -18,9 +19,9 @@ struct CronJob {
     name: String,
     schedule: Schedule,
     prompt: String,
-    #[allow(dead_code)]
     channel: Option<String>,
     goal: Option<String>,
+    working_dir: Option<String>,
 }
 
 /// Runs scheduled agent tasks based on cron expressions.
@@ -49,6 +50,7 @@ impl CronScheduler {
                         prompt: job_config.prompt.clone(),
                         channel: job_config.channel.clone(),
                         goal: job_config.goal.clone(),
+                        working_dir: job_config.working_dir.clone(),
                     });
                     info!(name = %job_config.name, schedule = %job_config.schedule, "Cron job registered");
                 }
@@ -106,49 +108,8 @@ impl CronScheduler {
 
                 tokio::select! {
                     _ = tokio::time::sleep(delay) => {
-                        info!(job = %job.name, "Firing cron job");
-
-                        self.event_bus.publish(AgentEvent::CronFired {
-                            job_id: job.name.clone(),
-                            prompt: job.prompt.clone(),
-                        });
-
-                        let session_id = SessionId::from_string(&format!("cron:{}", job.name));
-
-                        // Use Director orchestration when goal is configured
-                        let run_result = if let Some(ref goal_desc) = job.goal {
-                            let goal = Goal {
-                                description: goal_desc.clone(),
-                                success_criteria: vec![SuccessCriterion {
-                                    id: "llm_judge".into(),
-                                    criterion_type: CriterionType::LlmJudge {
-                                        prompt: format!("Did the agent achieve this goal: {}?", goal_desc),
-                                    },
-                                    weight: 1.0,
-                                    description: "Goal achievement".into(),
-                                }],
-                                constraints: vec![],
-                                success_threshold: 0.7,
-                                version: 0,
-                                metrics: Default::default(),
-                            };
-                            info!(job = %job.name, "Cron job using Director orchestration");
-                            self.runtime.run_with_goal(&session_id, &job.prompt, Some(&goal)).await
-                        } else {
-                            self.runtime.run(&session_id, &job.prompt).await
-                        };
-
-                        match run_result {
-                            Ok(response) => {
-                                info!(job = %job.name, "Cron job completed");
-                                self.event_bus.publish(AgentEvent::CronJobComplete {
-                                    name: job.name.clone(),
-                                    response,
-                                    channel: job.channel.clone(),
-                                });
-                            }
-                            Err(e) => error!(job = %job.name, error = %e, "Cron job failed"),
-                        }
+                        // Failures are logged by fire; the job runs again next time
+                        let _ = self.fire(job).await;
                     }
                     _ = self.cancel.cancelled() => {
                         info!("Cron scheduler shutting down");
@@ -162,4 +123,184 @@ impl CronScheduler {
             }
         }
     }
+
+    /// Run the job called `name` immediately, outside its schedule, and
+    /// route its response to the job's channel like a scheduled fire.
+    pub async fn run_job(&self, name: &str) -> Result<String> {
+        let Some(job) = self.jobs.iter().find(|j| j.name == name) else {
+            let known: Vec<&str> = self.jobs.iter().map(|j| j.name.as_str()).collect();
+            return Err(RyvosError::Config(format!(
+                "Unknown cron job '{}' (configured: {})",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )));
+        };
+        self.fire(job).await
+    }
+
+    /// Run one job and publish its result as `CronJobComplete`.
+    async fn fire(&self, job: &CronJob) -> Result<String> {
+        info!(job = %job.name, "Firing cron job");
+
+        self.event_bus.publish(AgentEvent::CronFired {
+            job_id: job.name.clone(),
+            prompt: job.prompt.clone(),
+        });
+
+        let session_id = SessionId::from_string(&format!("cron:{}", job.name));
+        if let Some(ref dir) = job.working_dir {
+            // Cleared first so a relative path resolves the same every fire
+            let sessions = self.runtime.session_manager();
+            sessions.clear_working_dir(&session_id);
+            if let Err(e) = sessions.set_working_dir(&session_id, dir) {
+                error!(job = %job.name, error = %e, "Cron job working directory rejected");
+                return Err(e);
+            }
+        }
+
+        // Use Director orchestration when goal is configured
+        let run_result = if let Some(ref goal_desc) = job.goal {
+            let goal = Goal {
+                description: goal_desc.clone(),
+                success_criteria: vec![SuccessCriterion {
+                    id: "llm_judge".into(),
+                    criterion_type: CriterionType::LlmJudge {
+                        prompt: format!("Did the agent achieve this goal: {}?", goal_desc),
+                    },
+                    weight: 1.0,
+                    description: "Goal achievement".into(),
+                }],
+                constraints: vec![],
+                success_threshold: 0.7,
+                version: 0,
+                metrics: Default::default(),
+            };
+            info!(job = %job.name, "Cron job using Director orchestration");
+            self.runtime
+                .run_with_goal(&session_id, &job.prompt, Some(&goal))
+                .await
+        } else {
+            self.runtime.run(&session_id, &job.prompt).await
+        };
+
+        match run_result {
+            Ok(response) => {
+                info!(job = %job.name, "Cron job completed");
+                self.event_bus.publish(AgentEvent::CronJobComplete {
+                    name: job.name.clone(),
+                    response: response.clone(),
+                    channel: job.channel.clone(),
+                });
+                Ok(response)
+            }
+            Err(e) => {
+                error!(job = %job.name, error = %e, "Cron job failed");
+                Err(e)
+            }
+        }
+    }
+}
+
+/// First time `schedule` fires strictly after `after`, or `None` if it
+/// never fires again. Errors on an invalid cron expression.
+pub fn next_fire_after(
+    schedule: &str,
+    after: DateTime<Utc>,
+) -> std::result::Result<Option<DateTime<Utc>>, String> {
+    let schedule = Schedule::from_str(schedule).map_err(|e| e.to_string())?;
+    Ok(schedule.after(&after).next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ryvos_core::config::CronJobConfig;
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient};
+    use ryvos_tools::ToolRegistry;
+
+    fn job(name: &str, prompt: &str, channel: Option<&str>) -> CronJobConfig {
+        CronJobConfig {
+            name: name.into(),
+            schedule: "0 0 9 * * *".into(),
+            prompt: prompt.into(),
+            channel: channel.map(String::from),
+            goal: None,
+            working_dir: None,
+        }
+    }
+
+    fn scheduler(llm: &MockLlmClient, bus: &Arc<EventBus>) -> CronScheduler {
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            bus.clone(),
+        ));
+        let config = CronConfig {
+            jobs: vec![
+                job("digest", "Summarize today's inbox", Some("telegram")),
+                job("cleanup", "Clean up /tmp", None),
+            ],
+        };
+        CronScheduler::new(&config, runtime, bus.clone(), CancellationToken::new())
+    }
+
+    #[tokio::test]
+    async fn run_job_executes_named_prompt_and_routes_to_channel() {
+        let llm = MockLlmClient::new().with_text_response("3 new messages");
+        let bus = Arc::new(EventBus::default());
+        let mut rx = bus.subscribe();
+        let scheduler = scheduler(&llm, &bus);
+
+        let response = scheduler.run_job("digest").await.unwrap();
+        assert_eq!(response, "3 new messages");
+        assert_eq!(llm.call_count(), 1);
+        let prompt = llm.call_messages(0).last().unwrap().text();
+        assert_eq!(prompt, "Summarize today's inbox");
+
+        let mut routed = None;
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::CronJobComplete { name, channel, .. } = event {
+                routed = Some((name, channel));
+            }
+        }
+        assert_eq!(
+            routed,
+            Some(("digest".to_string(), Some("telegram".to_string())))
+        );
+    }
+
+    #[tokio::test]
+    async fn run_job_rejects_unknown_name() {
+        let llm = MockLlmClient::new();
+        let bus = Arc::new(EventBus::default());
+        let err = scheduler(&llm, &bus)
+            .run_job("nightly")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Unknown cron job 'nightly'"));
+        assert!(err.contains("digest, cleanup"));
+        assert_eq!(llm.call_count(), 0);
+    }
+
+    #[test]
+    fn next_fire_after_valid_expression() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+        assert_eq!(
+            next_fire_after("0 30 9 * * *", now).unwrap(),
+            Some(Utc.with_ymd_and_hms(2026, 3, 2, 9, 30, 0).unwrap())
+        );
+        assert_eq!(
+            next_fire_after("0 */15 * * * *", now).unwrap(),
+            Some(Utc.with_ymd_and_hms(2026, 3, 1, 10, 15, 0).unwrap())
+        );
+        assert!(next_fire_after("not a schedule", now).is_err());
+    }
 }
@@ -1,10 +1,36 @@
+use ryvos_core::config::expand_home;
+use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::types::SessionId;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use tracing::warn;
+
+/// File in the workspace holding the session the last CLI invocation used.
+pub const LAST_SESSION_FILE: &str = "last_session";
+
+/// `--session` value that continues the last CLI session.
+pub const SESSION_LAST: &str = "last";
+
+/// `--session` value that starts a fresh session, whatever the default.
+pub const SESSION_NEW: &str = "new";
 
 /// Simple session manager tracking active sessions.
 pub struct SessionManager {
     sessions: Mutex<HashMap<String, SessionInfo>>,
+    /// Working directory per session ID, for sessions that changed it.
+    working_dirs: Mutex<HashMap<String, PathBuf>>,
+    /// Notes pinned per session ID, re-sent verbatim on every run.
+    pins: Mutex<HashMap<String, Vec<String>>>,
+    /// Session IDs whose context is never compacted.
+    no_compact: Mutex<HashSet<String>>,
+    /// Session IDs whose next run compacts regardless of the budget.
+    compact_requested: Mutex<HashSet<String>>,
+    /// Session IDs whose runs offer the model no tools.
+    no_tools: Mutex<HashSet<String>>,
+    /// Canonical directories a working directory must lie within.
+    /// Empty means unrestricted.
+    working_dir_roots: Vec<PathBuf>,
 }
 
 pub struct SessionInfo {
@@ -22,9 +48,25 @@ impl SessionManager {
     pub fn new() -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
+            working_dirs: Mutex::new(HashMap::new()),
+            pins: Mutex::new(HashMap::new()),
+            no_compact: Mutex::new(HashSet::new()),
+            compact_requested: Mutex::new(HashSet::new()),
+            no_tools: Mutex::new(HashSet::new()),
+            working_dir_roots: vec![],
         }
     }
 
+    /// Restrict working directories to `roots` (see
+    /// `AppConfig::working_dir_roots`). Roots that do not exist are ignored.
+    pub fn with_working_dir_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.working_dir_roots = roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect();
+        self
+    }
+
     /// Get or create a session for a given key (e.g., "telegram:user:12345").
     pub fn get_or_create(&self, key: &str, channel: &str) -> SessionId {
         let mut sessions = self.sessions.lock().unwrap();
@@ -50,6 +92,12 @@ impl SessionManager {
         session_id
     }
 
+    /// The session ID of a key, without creating a session.
+    pub fn session_id(&self, key: &str) -> Option<SessionId> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(key).map(|info| info.session_id.clone())
+    }
+
     /// Set the CLI session ID for a session.
     pub fn set_cli_session_id(&self, key: &str, cli_session_id: &str) {
         let mut sessions = self.sessions.lock().unwrap();
@@ -111,6 +159,138 @@ impl SessionManager {
     pub fn list(&self) -> Vec<String> {
         self.sessions.lock().unwrap().keys().cloned().collect()
     }
+
+    /// Change the working directory tool calls in `session_id` run in.
+    /// A relative `dir` resolves against the session's current working
+    /// directory. The directory must exist and lie within the allowed
+    /// roots. Returns the canonical path.
+    pub fn set_working_dir(&self, session_id: &SessionId, dir: &str) -> Result<PathBuf> {
+        let requested = expand_home(dir);
+        let base = self
+            .working_dir(session_id)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let path = base.join(&requested).canonicalize().map_err(|e| {
+            RyvosError::Config(format!(
+                "Working directory {} not found: {}",
+                requested.display(),
+                e
+            ))
+        })?;
+        if !path.is_dir() {
+            return Err(RyvosError::Config(format!(
+                "{} is not a directory",
+                path.display()
+            )));
+        }
+        if !self.working_dir_roots.is_empty()
+            && !self.working_dir_roots.iter().any(|r| path.starts_with(r))
+        {
+            return Err(RyvosError::SecurityViolation(format!(
+                "{} is outside the allowed working directories ({})",
+                path.display(),
+                self.working_dir_roots
+                    .iter()
+                    .map(|r| r.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        self.working_dirs
+            .lock()
+            .unwrap()
+            .insert(session_id.0.clone(), path.clone());
+        Ok(path)
+    }
+
+    /// The working directory set for `session_id`, if any.
+    pub fn working_dir(&self, session_id: &SessionId) -> Option<PathBuf> {
+        self.working_dirs
+            .lock()
+            .unwrap()
+            .get(&session_id.0)
+            .cloned()
+    }
+
+    /// Return `session_id` to the default working directory.
+    pub fn clear_working_dir(&self, session_id: &SessionId) {
+        self.working_dirs.lock().unwrap().remove(&session_id.0);
+    }
+
+    /// Pin a note to `session_id`; it survives every compaction. Returns
+    /// its 1-based position in [`pins`](Self::pins).
+    pub fn pin(&self, session_id: &SessionId, note: &str) -> usize {
+        let mut pins = self.pins.lock().unwrap();
+        let notes = pins.entry(session_id.0.clone()).or_default();
+        notes.push(note.to_string());
+        notes.len()
+    }
+
+    /// Remove the pinned note at 1-based `position`, returning it.
+    pub fn unpin(&self, session_id: &SessionId, position: usize) -> Option<String> {
+        let mut pins = self.pins.lock().unwrap();
+        let notes = pins.get_mut(&session_id.0)?;
+        if position == 0 || position > notes.len() {
+            return None;
+        }
+        Some(notes.remove(position - 1))
+    }
+
+    /// The notes pinned to `session_id`, oldest first.
+    pub fn pins(&self, session_id: &SessionId) -> Vec<String> {
+        self.pins
+            .lock()
+            .unwrap()
+            .get(&session_id.0)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Turn compaction off or back on for `session_id`. While it is off,
+    /// runs skip the memory flush and never prune or summarize the
+    /// history, so the session can outgrow the model's context window.
+    pub fn set_no_compact(&self, session_id: &SessionId, no_compact: bool) {
+        let mut sessions = self.no_compact.lock().unwrap();
+        if no_compact {
+            sessions.insert(session_id.0.clone());
+        } else {
+            sessions.remove(&session_id.0);
+        }
+    }
+
+    /// Whether compaction is off for `session_id`.
+    pub fn no_compact(&self, session_id: &SessionId) -> bool {
+        self.no_compact.lock().unwrap().contains(&session_id.0)
+    }
+
+    /// Turn tools off or back on for `session_id`'s runs.
+    pub fn set_no_tools(&self, session_id: &SessionId, no_tools: bool) {
+        let mut sessions = self.no_tools.lock().unwrap();
+        if no_tools {
+            sessions.insert(session_id.0.clone());
+        } else {
+            sessions.remove(&session_id.0);
+        }
+    }
+
+    /// Whether `session_id`'s runs are made without tools.
+    pub fn no_tools(&self, session_id: &SessionId) -> bool {
+        self.no_tools.lock().unwrap().contains(&session_id.0)
+    }
+
+    /// Compact `session_id`'s context on its next run (`/compact`).
+    pub fn request_compaction(&self, session_id: &SessionId) {
+        self.compact_requested
+            .lock()
+            .unwrap()
+            .insert(session_id.0.clone());
+    }
+
+    /// Whether a compaction was requested for `session_id`, clearing the
+    /// request.
+    pub fn take_compaction_request(&self, session_id: &SessionId) -> bool {
+        self.compact_requested.lock().unwrap().remove(&session_id.0)
+    }
 }
 
 impl Default for SessionManager {
@@ -118,3 +298,186 @@ impl Default for SessionManager {
         Self::new()
     }
 }
+
+/// The session for a CLI invocation: `requested` (`--session`), else
+/// `default` (`[agent] default_session`), else a fresh one. `last` is the
+/// session recorded in `workspace` by [`remember_cli_session`], or a fresh
+/// one if none was; `new` is always fresh.
+pub fn resolve_cli_session(
+    requested: Option<&str>,
+    default: Option<&str>,
+    workspace: &Path,
+) -> SessionId {
+    match requested.or(default).map(str::trim) {
+        None | Some("") | Some(SESSION_NEW) => SessionId::new(),
+        Some(SESSION_LAST) => last_cli_session(workspace).unwrap_or_default(),
+        Some(id) => SessionId::from_string(id),
+    }
+}
+
+/// The session a CLI run or REPL works in, resolved as by
+/// [`resolve_cli_session`] and recorded as the one `last` resolves to.
+/// Failing to record it is logged, not fatal.
+pub fn enter_cli_session(
+    requested: Option<&str>,
+    default: Option<&str>,
+    workspace: &Path,
+) -> SessionId {
+    let session = resolve_cli_session(requested, default, workspace);
+    if let Err(e) = remember_cli_session(workspace, &session) {
+        warn!(error = %e, "Failed to record the last session");
+    }
+    session
+}
+
+/// The session the last CLI invocation in `workspace` used, if any.
+pub fn last_cli_session(workspace: &Path) -> Option<SessionId> {
+    let id = std::fs::read_to_string(workspace.join(LAST_SESSION_FILE)).ok()?;
+    let id = id.trim();
+    (!id.is_empty()).then(|| SessionId::from_string(id))
+}
+
+/// Record `session` as the one `--session last` resolves to.
+pub fn remember_cli_session(workspace: &Path, session: &SessionId) -> Result<()> {
+    std::fs::create_dir_all(workspace)?;
+    std::fs::write(
+        workspace.join(LAST_SESSION_FILE),
+        format!("{}\n", session.0),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ryvos_cwd_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("project/src")).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn working_dir_resolves_relative_to_current() {
+        let root = temp_root();
+        let mgr = SessionManager::new().with_working_dir_roots(vec![root.clone()]);
+        let sid = SessionId::new();
+        assert_eq!(mgr.working_dir(&sid), None);
+
+        let project = mgr
+            .set_working_dir(&sid, root.join("project").to_str().unwrap())
+            .unwrap();
+        assert_eq!(project, root.join("project"));
+        assert_eq!(
+            mgr.set_working_dir(&sid, "src").unwrap(),
+            root.join("project/src")
+        );
+        assert_eq!(mgr.set_working_dir(&sid, "..").unwrap(), project);
+
+        mgr.clear_working_dir(&sid);
+        assert_eq!(mgr.working_dir(&sid), None);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn pins_are_per_session_and_numbered_from_one() {
+        let mgr = SessionManager::new();
+        let (a, b) = (SessionId::new(), SessionId::new());
+        assert_eq!(mgr.pin(&a, "use pnpm"), 1);
+        assert_eq!(mgr.pin(&a, "staging first"), 2);
+        assert!(mgr.pins(&b).is_empty());
+
+        assert_eq!(mgr.unpin(&a, 0), None);
+        assert_eq!(mgr.unpin(&a, 3), None);
+        assert_eq!(mgr.unpin(&a, 1).as_deref(), Some("use pnpm"));
+        assert_eq!(mgr.pins(&a), vec!["staging first".to_string()]);
+        assert_eq!(mgr.unpin(&b, 1), None);
+    }
+
+    #[test]
+    fn working_dir_must_exist_within_roots() {
+        let root = temp_root();
+        let mgr = SessionManager::new().with_working_dir_roots(vec![root.join("project")]);
+        let sid = SessionId::new();
+
+        let err = mgr
+            .set_working_dir(&sid, root.join("missing").to_str().unwrap())
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        let err = mgr
+            .set_working_dir(&sid, root.to_str().unwrap())
+            .unwrap_err();
+        assert!(matches!(err, RyvosError::SecurityViolation(_)));
+        assert_eq!(mgr.working_dir(&sid), None);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn cli_session_resolves_last_and_new() {
+        let root = temp_root();
+        // Nothing recorded yet: `last` starts fresh
+        let first = resolve_cli_session(Some("last"), None, &root);
+        remember_cli_session(&root, &first).unwrap();
+
+        assert_eq!(resolve_cli_session(Some("last"), None, &root), first);
+        assert_eq!(resolve_cli_session(None, Some("last"), &root), first);
+        assert_ne!(resolve_cli_session(Some("new"), Some("last"), &root), first);
+        assert_ne!(resolve_cli_session(None, None, &root), first);
+        assert_eq!(
+            resolve_cli_session(None, Some("notes"), &root),
+            SessionId::from_string("notes")
+        );
+        // An explicit id wins over the default
+        assert_eq!(
+            resolve_cli_session(Some("other"), Some("last"), &root),
+            SessionId::from_string("other")
+        );
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[tokio::test]
+    async fn runs_on_the_default_session_share_history() {
+        use crate::AgentRuntime;
+        use ryvos_core::event::EventBus;
+        use ryvos_core::traits::LlmClient;
+        use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient};
+        use std::sync::Arc;
+
+        let root = temp_root();
+        let store = Arc::new(InMemorySessionStore::new());
+        let llm = MockLlmClient::new()
+            .with_text_response("the answer is 42")
+            .with_text_response("still 42");
+
+        // Two CLI invocations on the `last` default
+        for prompt in ["what is the answer?", "repeat it"] {
+            let session = enter_cli_session(None, Some(SESSION_LAST), &root);
+            let runtime = AgentRuntime::new(
+                test_config(),
+                Arc::new(llm.clone()) as Arc<dyn LlmClient>,
+                Arc::new(tokio::sync::RwLock::new(ryvos_tools::ToolRegistry::new())),
+                store.clone(),
+                Arc::new(EventBus::default()),
+            );
+            runtime.run(&session, prompt).await.unwrap();
+        }
+
+        let seen: Vec<String> = llm.call_messages(1).iter().map(|m| m.text()).collect();
+        assert!(seen.contains(&"what is the answer?".to_string()));
+        assert!(seen.contains(&"the answer is 42".to_string()));
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn entered_session_is_the_next_last() {
+        let root = temp_root();
+        let first = enter_cli_session(None, None, &root);
+        assert_eq!(last_cli_session(&root), Some(first.clone()));
+
+        // Switching, e.g. with `/session notes` in the REPL, moves `last`
+        let notes = enter_cli_session(Some("notes"), None, &root);
+        assert_eq!(notes, SessionId::from_string("notes"));
+        assert_eq!(enter_cli_session(Some(SESSION_LAST), None, &root), notes);
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,272 @@
+//! Writing a session's transcript to a file.
+//!
+//! Backs the REPL's `/export [format] [path]`: the session's full history
+//! is loaded from the [`SessionStore`] and rendered as Markdown for reading
+//! or as JSON (the stored [`ChatMessage`]s) for tooling.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::traits::SessionStore;
+use ryvos_core::types::{ChatMessage, ContentBlock, MessageMetadata, Role, SessionId};
+
+/// Enough to load every message a session has.
+const WHOLE_SESSION: usize = i64::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+
+    /// Format implied by a file name; anything but `.json` is Markdown.
+    fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Markdown,
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = RyvosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            other => Err(RyvosError::Config(format!(
+                "unknown export format '{}' (use markdown or json)",
+                other
+            ))),
+        }
+    }
+}
+
+/// What `/export` was asked for: a format, a path, both or neither. A
+/// lone argument is a format if it names one, otherwise a path whose
+/// extension picks the format. Without a path the file goes to
+/// `<workspace>/exports/session-<id>-<timestamp>.<ext>`.
+pub fn resolve_target(
+    args: &[&str],
+    session_id: &SessionId,
+    workspace: &Path,
+    now: DateTime<Utc>,
+) -> Result<(ExportFormat, PathBuf)> {
+    let (format, path) = match args {
+        [] => (None, None),
+        [one] => match one.parse::<ExportFormat>() {
+            Ok(format) => (Some(format), None),
+            Err(_) => (None, Some(PathBuf::from(one))),
+        },
+        [format, path] => (Some(format.parse()?), Some(PathBuf::from(path))),
+        _ => {
+            return Err(RyvosError::Config(
+                "usage: /export [markdown|json] [path]".to_string(),
+            ))
+        }
+    };
+    let format = format
+        .or_else(|| path.as_deref().map(ExportFormat::for_path))
+        .unwrap_or(ExportFormat::Markdown);
+    let path = path.unwrap_or_else(|| {
+        let short: String = session_id.0.chars().take(8).collect();
+        workspace.join("exports").join(format!(
+            "session-{}-{}.{}",
+            short,
+            now.format("%Y%m%d-%H%M%S"),
+            format.extension()
+        ))
+    });
+    Ok((format, path))
+}
+
+/// Write the whole of `session_id` to `path`, creating its directory.
+/// Returns the number of messages written.
+pub async fn export_session(
+    store: &dyn SessionStore,
+    session_id: &SessionId,
+    format: ExportFormat,
+    path: &Path,
+) -> Result<usize> {
+    let messages = store.load_history(session_id, WHOLE_SESSION).await?;
+    let text = match format {
+        ExportFormat::Markdown => render_markdown(session_id, &messages),
+        ExportFormat::Json => render_json(session_id, &messages)?,
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(messages.len())
+}
+
+pub fn render_json(session_id: &SessionId, messages: &[ChatMessage]) -> Result<String> {
+    let doc = serde_json::json!({
+        "session_id": session_id.0,
+        "exported_at": Utc::now().to_rfc3339(),
+        "messages": messages,
+    });
+    Ok(serde_json::to_string_pretty(&doc)?)
+}
+
+/// Markdown transcript: a heading per message with the channel and sender
+/// it came from, tool calls and results as fenced blocks. Thinking blocks
+/// are left out.
+pub fn render_markdown(session_id: &SessionId, messages: &[ChatMessage]) -> String {
+    let mut out = format!("# Session {}\n", session_id);
+    for msg in messages {
+        let role = match msg.role {
+            Role::System => "System",
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool",
+        };
+        let _ = write!(out, "\n## {}", role);
+        if let Some(ts) = msg.timestamp {
+            let _ = write!(out, " ({})", ts.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+        out.push('\n');
+        if let Some(source) = msg.metadata.as_ref().and_then(MessageMetadata::source) {
+            render_source(&mut out, &source);
+        }
+
+        for block in &msg.content {
+            match block {
+                ContentBlock::Text { text } => {
+                    let _ = write!(out, "\n{}\n", text.trim_end());
+                }
+                ContentBlock::ToolUse { name, input, .. } => {
+                    let input = serde_json::to_string_pretty(input).unwrap_or_default();
+                    let _ = write!(out, "\nTool call `{}`:\n\n```json\n{}\n```\n", name, input);
+                }
+                ContentBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let label = if *is_error {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    let _ = write!(out, "\n{}:\n\n```\n{}\n```\n", label, content.trim_end());
+                }
+                ContentBlock::Thinking { .. } => {}
+            }
+        }
+    }
+    out
+}
+
+/// "_via telegram from 4242_" and a list of the attachments.
+fn render_source(out: &mut String, source: &MessageMetadata) {
+    let mut via = Vec::new();
+    if let Some(ref channel) = source.source_channel {
+        via.push(format!("via {}", channel));
+    }
+    if let Some(ref sender) = source.sender {
+        via.push(format!("from {}", sender));
+    }
+    if !via.is_empty() {
+        let _ = write!(out, "\n_{}_\n", via.join(" "));
+    }
+    if !source.attachments.is_empty() {
+        out.push_str("\nAttachments:\n\n");
+        for file in &source.attachments {
+            let _ = writeln!(out, "- {} `{}`", file.kind, file.reference);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ryvos_test_utils::InMemorySessionStore;
+
+    async fn session() -> (InMemorySessionStore, SessionId) {
+        let store = InMemorySessionStore::new();
+        let sid = SessionId::from_string("3f2a9c1e-0000-4000-8000-000000000000");
+        let call = ChatMessage {
+            role: Role::Assistant,
+            content: vec![ContentBlock::ToolUse {
+                id: "t1".to_string(),
+                name: "read".to_string(),
+                input: serde_json::json!({"file_path": "Cargo.toml"}),
+            }],
+            timestamp: None,
+            metadata: None,
+        };
+        let messages = [
+            ChatMessage::user("What does the workspace contain?"),
+            call,
+            ChatMessage::tool_result("t1", "[workspace]", false),
+            ChatMessage::assistant_text("Eleven crates."),
+        ];
+        store.append_messages(&sid, &messages).await.unwrap();
+        (store, sid)
+    }
+
+    #[test]
+    fn arguments_pick_format_and_path() {
+        let sid = SessionId::from_string("3f2a9c1e-0000");
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap();
+        let ws = Path::new("/ws");
+
+        let (format, path) = resolve_target(&[], &sid, ws, now).unwrap();
+        assert_eq!(format, ExportFormat::Markdown);
+        assert_eq!(
+            path,
+            Path::new("/ws/exports/session-3f2a9c1e-20261016-093000.md")
+        );
+        let (format, path) = resolve_target(&["json"], &sid, ws, now).unwrap();
+        assert_eq!(format, ExportFormat::Json);
+        assert_eq!(path.extension().unwrap(), "json");
+        let (format, path) = resolve_target(&["out/chat.json"], &sid, ws, now).unwrap();
+        assert_eq!((format, path), (ExportFormat::Json, "out/chat.json".into()));
+        let (format, _) = resolve_target(&["md", "chat.json"], &sid, ws, now).unwrap();
+        assert_eq!(format, ExportFormat::Markdown);
+        assert!(resolve_target(&["yaml", "chat.yaml"], &sid, ws, now).is_err());
+    }
+
+    #[tokio::test]
+    async fn export_writes_every_message() {
+        let (store, sid) = session().await;
+        let dir = std::env::temp_dir().join(format!("ryvos_export_{}", uuid::Uuid::new_v4()));
+
+        let (format, path) = resolve_target(&["markdown"], &sid, &dir, Utc::now()).unwrap();
+        let written = export_session(&store, &sid, format, &path).await.unwrap();
+        assert_eq!(written, 4);
+        let md = std::fs::read_to_string(&path).unwrap();
+        assert!(md.starts_with("# Session 3f2a9c1e-"));
+        assert!(md.contains(" UTC)\n\nWhat does the workspace contain?"));
+        assert!(md.contains("Tool call `read`:"));
+        assert!(md.contains("\"file_path\": \"Cargo.toml\""));
+        assert!(md.contains("Tool result:\n\n```\n[workspace]\n```"));
+        assert!(md.contains("## Assistant ("));
+        assert!(md.contains("\n\nEleven crates.\n"));
+
+        let path = dir.join("chat.json");
+        let path_arg = path.to_str().unwrap();
+        let (format, path) = resolve_target(&[path_arg], &sid, &dir, Utc::now()).unwrap();
+        export_session(&store, &sid, format, &path).await.unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["session_id"], sid.0);
+        let messages: Vec<ChatMessage> = serde_json::from_value(json["messages"].clone()).unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3].text(), "Eleven crates.");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use futures::future::BoxFuture;
 use tokio::sync::{mpsc, Mutex};
@@ -8,24 +9,35 @@ use tracing::{error, info, warn};
 use ryvos_agent::{ApprovalBroker, SessionManager};
 use ryvos_core::config::{DiscordConfig, DmPolicy};
 use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::event::EventBus;
 use ryvos_core::security::{ApprovalDecision, ApprovalRequest};
 use ryvos_core::traits::ChannelAdapter;
-use ryvos_core::types::{MessageContent, MessageEnvelope, SessionId};
+use ryvos_core::types::{Attachment, AttachmentKind, MessageContent, MessageEnvelope, SessionId};
 
 use serenity::all::{
-    ButtonStyle, Context, CreateActionRow, CreateButton, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateMessage, EventHandler, GatewayIntents, Interaction,
-    Ready,
+    ButtonStyle, ConnectionStage, Context, CreateActionRow, CreateButton, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, EventHandler,
+    GatewayIntents, Interaction, Ready, ShardStageUpdateEvent,
 };
+use serenity::gateway::GatewayError;
+use serenity::http::StatusCode;
 use serenity::model::channel::Message;
 use serenity::model::id::ChannelId;
 use serenity::prelude::TypeMapKey;
 use serenity::Client;
 
-use crate::util::split_message;
+use crate::rich::{self, Segment, StructuredResult};
+use crate::util::{split_message, Backoff, ConnectionReporter, RECONNECT_MAX_DELAY};
 
 const DISCORD_MAX_LEN: usize = 2000;
 
+/// Discord's per-message and per-embed limits.
+const DISCORD_MAX_EMBEDS: usize = 10;
+const DISCORD_MAX_FIELDS: usize = 25;
+const DISCORD_DESCRIPTION_MAX_LEN: usize = 4096;
+/// Characters allowed across all the embeds of one message.
+const DISCORD_EMBEDS_TOTAL_MAX_LEN: usize = 6000;
+
 /// Typed keys for serenity's TypeMap.
 struct EnvelopeSender;
 impl TypeMapKey for EnvelopeSender {
@@ -56,7 +68,11 @@ impl TypeMapKey for ApprovalBrokerKey {
     type Value = Arc<ApprovalBroker>;
 }
 
-struct Handler;
+/// Serenity event handler. The shard runner heartbeats the gateway and
+/// reconnects on its own; stage changes are reported through `status`.
+struct Handler {
+    status: ConnectionReporter,
+}
 
 #[serenity::async_trait]
 impl EventHandler for Handler {
@@ -104,12 +120,23 @@ impl EventHandler for Handler {
             .insert(session_id.0.clone(), msg.channel_id);
 
         let envelope = MessageEnvelope {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: msg.id.to_string(),
             session_id,
             session_key: key.clone(),
             channel: "discord".into(),
             sender: msg.author.id.to_string(),
             text: msg.content.clone(),
+            attachments: msg
+                .attachments
+                .iter()
+                .map(|file| Attachment {
+                    kind: file
+                        .content_type
+                        .as_deref()
+                        .map_or(AttachmentKind::File, AttachmentKind::from_mime),
+                    reference: file.url.clone(),
+                })
+                .collect(),
             timestamp: chrono::Utc::now(),
         };
 
@@ -120,11 +147,22 @@ impl EventHandler for Handler {
 
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!(user = %ready.user.name, "Discord bot connected");
+        self.status.connected();
         // Store HTTP client for send()
         let mut data = ctx.data.write().await;
         data.insert::<HttpKey>(Arc::clone(&ctx.http));
     }
 
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        match event.new {
+            ConnectionStage::Connected => self.status.connected(),
+            ConnectionStage::Disconnected | ConnectionStage::Resuming => self
+                .status
+                .disconnected(format!("shard {} {:?}", event.shard_id, event.new)),
+            _ => {}
+        }
+    }
+
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         if let Interaction::Component(component) = interaction {
             let custom_id = component.data.custom_id.as_str();
@@ -144,6 +182,11 @@ impl EventHandler for Handler {
             };
             drop(data);
 
+            let confirm_word = if action == "approve" {
+                broker.confirm_word(&request_id).await
+            } else {
+                None
+            };
             let decision = if action == "approve" {
                 ApprovalDecision::Approved
             } else {
@@ -152,14 +195,18 @@ impl EventHandler for Handler {
                 }
             };
 
-            let resolved = broker.respond(&request_id, decision).await;
+            // A destructive command is only approved by typing the word
+            let resolved = confirm_word.is_none() && broker.respond(&request_id, decision).await;
 
             let label = if action == "approve" {
                 "Approved"
             } else {
                 "Denied"
             };
-            let response_text = if resolved {
+            let response_text = if let Some(word) = confirm_word {
+                let short_id = &request_id[..8.min(request_id.len())];
+                format!("Send `/approve {} {}` to run this.", short_id, word)
+            } else if resolved {
                 format!("{} successfully.", label)
             } else {
                 "Request expired or not found.".to_string()
@@ -190,6 +237,7 @@ pub struct DiscordAdapter {
     shard_manager: Arc<Mutex<Option<Arc<serenity::gateway::ShardManager>>>>,
     /// Approval broker for HITL.
     broker: Arc<Mutex<Option<Arc<ApprovalBroker>>>>,
+    status: ConnectionReporter,
 }
 
 impl DiscordAdapter {
@@ -201,6 +249,7 @@ impl DiscordAdapter {
             http: Arc::new(Mutex::new(None)),
             shard_manager: Arc::new(Mutex::new(None)),
             broker: Arc::new(Mutex::new(None)),
+            status: ConnectionReporter::new("discord", None),
         }
     }
 
@@ -208,6 +257,11 @@ impl DiscordAdapter {
     pub fn set_broker(&mut self, broker: Arc<ApprovalBroker>) {
         self.broker = Arc::new(Mutex::new(Some(broker)));
     }
+
+    /// Publish connection changes on the event bus.
+    pub fn set_event_bus(&mut self, event_bus: Arc<EventBus>) {
+        self.status = ConnectionReporter::new("discord", Some(event_bus));
+    }
 }
 
 impl ChannelAdapter for DiscordAdapter {
@@ -216,50 +270,72 @@ impl ChannelAdapter for DiscordAdapter {
     }
 
     fn start(&self, tx: mpsc::Sender<MessageEnvelope>) -> BoxFuture<'_, Result<()>> {
-        let token = self.config.bot_token.clone();
-        let session_mgr = self.session_mgr.clone();
-        let channel_map = self.channel_map.clone();
         let http_slot = self.http.clone();
         let shard_slot = self.shard_manager.clone();
-        let broker_slot = self.broker.clone();
+        let status = self.status.clone();
 
         Box::pin(async move {
-            let intents = GatewayIntents::GUILD_MESSAGES
-                | GatewayIntents::DIRECT_MESSAGES
-                | GatewayIntents::MESSAGE_CONTENT;
-
-            let mut client = Client::builder(&token, intents)
-                .event_handler(Handler)
-                .await
-                .map_err(|e| RyvosError::Channel {
-                    channel: "discord".into(),
-                    message: e.to_string(),
-                })?;
-
-            // Inject shared state into serenity's TypeMap
-            {
-                let mut data = client.data.write().await;
-                data.insert::<EnvelopeSender>(tx);
-                data.insert::<SessionMgrKey>(session_mgr);
-                data.insert::<ChannelMapKey>(channel_map);
-                data.insert::<DmPolicyKey>(self.config.dm_policy.clone());
-                data.insert::<AllowedUsersKey>(self.config.allowed_users.clone());
-                if let Some(broker) = broker_slot.lock().await.clone() {
-                    data.insert::<ApprovalBrokerKey>(broker);
-                }
-            }
+            let parts = ClientParts {
+                token: self.config.bot_token.clone(),
+                tx,
+                session_mgr: self.session_mgr.clone(),
+                channel_map: self.channel_map.clone(),
+                dm_policy: self.config.dm_policy.clone(),
+                allowed_users: self.config.allowed_users.clone(),
+                broker: self.broker.lock().await.clone(),
+                status: status.clone(),
+            };
 
-            // Store shard manager for shutdown
-            *shard_slot.lock().await = Some(client.shard_manager.clone());
+            let client = parts.build().await.map_err(|e| RyvosError::Channel {
+                channel: "discord".into(),
+                message: e.to_string(),
+            })?;
 
-            // Store HTTP for send()
+            // Store shard manager for shutdown and HTTP for send()
+            *shard_slot.lock().await = Some(client.shard_manager.clone());
             *http_slot.lock().await = Some(Arc::clone(&client.http));
 
             info!("Discord adapter starting");
 
+            // start() returns Ok after a shutdown. A client whose start()
+            // failed has already shut its shards down and cannot be started
+            // again, so every retry builds a fresh one.
             tokio::spawn(async move {
-                if let Err(e) = client.start().await {
-                    error!(error = %e, "Discord client error");
+                let mut backoff = Backoff::default();
+                let mut client = Some(client);
+                loop {
+                    if let Some(mut running) = client.take() {
+                        let started = Instant::now();
+                        let Err(e) = running.start().await else {
+                            return;
+                        };
+                        if started.elapsed() >= RECONNECT_MAX_DELAY {
+                            backoff.reset();
+                        }
+                        if gives_up(&status, &e) {
+                            return;
+                        }
+                    }
+                    tokio::time::sleep(backoff.next_delay()).await;
+
+                    // Hold the slot while rebuilding so a stop() cannot slip
+                    // in between; an empty slot means stop() already ran
+                    let mut shard_guard = shard_slot.lock().await;
+                    if shard_guard.is_none() {
+                        return;
+                    }
+                    match parts.build().await {
+                        Ok(fresh) => {
+                            *shard_guard = Some(fresh.shard_manager.clone());
+                            *http_slot.lock().await = Some(Arc::clone(&fresh.http));
+                            client = Some(fresh);
+                        }
+                        Err(e) => {
+                            if gives_up(&status, &e) {
+                                return;
+                            }
+                        }
+                    }
                 }
             });
 
@@ -276,9 +352,7 @@ impl ChannelAdapter for DiscordAdapter {
         let channel_map = self.channel_map.clone();
         let http_slot = self.http.clone();
         let request_id = request.id.clone();
-        let tool_name = request.tool_name.clone();
-        let tier = request.tier;
-        let input_summary = request.input_summary.clone();
+        let text = approval_text(request);
 
         Box::pin(async move {
             let channel_id = {
@@ -297,11 +371,6 @@ impl ChannelAdapter for DiscordAdapter {
                 None => return Ok(false),
             };
 
-            let text = format!(
-                "🔐 **Approval Required**\n\nTool: `{}`\nTier: {}\nAction: *{}*",
-                tool_name, tier, input_summary
-            );
-
             let approve_btn = CreateButton::new(format!("approve:{}", request_id))
                 .label("Approve")
                 .style(ButtonStyle::Success);
@@ -329,6 +398,7 @@ impl ChannelAdapter for DiscordAdapter {
         let content = content.clone();
         let channel_map = self.channel_map.clone();
         let http_slot = self.http.clone();
+        let rich_formatting = self.config.rich_formatting;
 
         Box::pin(async move {
             let text = match &content {
@@ -356,10 +426,47 @@ impl ChannelAdapter for DiscordAdapter {
                 message: "Bot not started".into(),
             })?;
 
-            let chunks = split_message(&text, DISCORD_MAX_LEN);
-            for chunk in chunks {
+            let rich = if rich_formatting {
+                result_message(&text)
+            } else {
+                None
+            };
+            let Some(messages) = rich else {
+                for chunk in split_message(&rich::to_markdown(&text), DISCORD_MAX_LEN) {
+                    channel_id
+                        .say(http, &chunk)
+                        .await
+                        .map_err(|e| RyvosError::Channel {
+                            channel: "discord".into(),
+                            message: e.to_string(),
+                        })?;
+                }
+                return Ok(());
+            };
+
+            for (prose, embeds) in messages {
+                // Embeds ride on the last chunk of the prose before them
+                let mut chunks = if prose.is_empty() {
+                    vec![]
+                } else {
+                    split_message(&prose, DISCORD_MAX_LEN)
+                };
+                let last = chunks.pop().unwrap_or_default();
+                for chunk in chunks {
+                    channel_id
+                        .say(http, &chunk)
+                        .await
+                        .map_err(|e| RyvosError::Channel {
+                            channel: "discord".into(),
+                            message: e.to_string(),
+                        })?;
+                }
+                let mut message = CreateMessage::new().content(last);
+                if !embeds.is_empty() {
+                    message = message.embeds(embeds);
+                }
                 channel_id
-                    .say(http, &chunk)
+                    .send_message(http, message)
                     .await
                     .map_err(|e| RyvosError::Channel {
                         channel: "discord".into(),
@@ -371,6 +478,10 @@ impl ChannelAdapter for DiscordAdapter {
         })
     }
 
+    fn renders_structured_results(&self) -> bool {
+        self.config.rich_formatting
+    }
+
     fn broadcast(&self, content: &MessageContent) -> BoxFuture<'_, Result<()>> {
         let content = content.clone();
         let channel_map = self.channel_map.clone();
@@ -420,3 +531,271 @@ impl ChannelAdapter for DiscordAdapter {
         })
     }
 }
+
+/// Everything needed to build a serenity client, kept so the reconnect
+/// loop can build a new one after a failed start.
+struct ClientParts {
+    token: String,
+    tx: mpsc::Sender<MessageEnvelope>,
+    session_mgr: Arc<SessionManager>,
+    channel_map: Arc<Mutex<HashMap<String, ChannelId>>>,
+    dm_policy: DmPolicy,
+    allowed_users: Vec<u64>,
+    broker: Option<Arc<ApprovalBroker>>,
+    status: ConnectionReporter,
+}
+
+impl ClientParts {
+    async fn build(&self) -> serenity::Result<Client> {
+        let intents = GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::DIRECT_MESSAGES
+            | GatewayIntents::MESSAGE_CONTENT;
+
+        let client = Client::builder(&self.token, intents)
+            .event_handler(Handler {
+                status: self.status.clone(),
+            })
+            .await?;
+
+        // Inject shared state into serenity's TypeMap
+        {
+            let mut data = client.data.write().await;
+            data.insert::<EnvelopeSender>(self.tx.clone());
+            data.insert::<SessionMgrKey>(self.session_mgr.clone());
+            data.insert::<ChannelMapKey>(self.channel_map.clone());
+            data.insert::<DmPolicyKey>(self.dm_policy.clone());
+            data.insert::<AllowedUsersKey>(self.allowed_users.clone());
+            if let Some(broker) = &self.broker {
+                data.insert::<ApprovalBrokerKey>(broker.clone());
+            }
+        }
+
+        Ok(client)
+    }
+}
+
+/// Log a client failure and report whether retrying is pointless.
+fn gives_up(status: &ConnectionReporter, e: &serenity::Error) -> bool {
+    error!(error = %e, "Discord client error");
+    status.disconnected(e.to_string());
+    let fatal = is_fatal(e);
+    if fatal {
+        error!("Discord rejected the bot token or intents, not reconnecting");
+    }
+    fatal
+}
+
+/// Errors that come back the same on every attempt: a bad token or
+/// intents the bot is not allowed to use.
+fn is_fatal(e: &serenity::Error) -> bool {
+    match e {
+        serenity::Error::Gateway(
+            GatewayError::InvalidAuthentication
+            | GatewayError::NoAuthentication
+            | GatewayError::InvalidGatewayIntents
+            | GatewayError::DisallowedGatewayIntents,
+        ) => true,
+        serenity::Error::Http(e) => e.status_code() == Some(StatusCode::UNAUTHORIZED),
+        _ => false,
+    }
+}
+
+/// Approval prompt, with the redacted arguments in a code block when the
+/// policy attaches them. Detail is capped well under Discord's 2000-char
+/// message limit.
+fn approval_text(request: &ApprovalRequest) -> String {
+    let mut text = format!(
+        "🔐 **Approval Required**\n\nTool: `{}`\nTier: {}\nAction: *{}*",
+        request.tool_name, request.tier, request.input_summary
+    );
+    if let Some(ref detail) = request.input_detail {
+        // A zero-width space keeps a fence inside the input from closing ours
+        let detail = detail.replace("```", "`\u{200b}``");
+        text.push_str(&format!("\n```json\n{}\n```", detail));
+    }
+    text
+}
+
+/// The messages to send for a reply, in order: each carries the prose
+/// before it and an embed for each structured result that follows, so
+/// text after a result goes in a later message. A message starts early
+/// when another embed would break Discord's per-message limits. `None`
+/// when there are no results or one exceeds Discord's embed limits, so
+/// the caller falls back to markdown.
+fn result_message(text: &str) -> Option<Vec<(String, Vec<CreateEmbed>)>> {
+    let mut messages = Vec::new();
+    let mut prose = Vec::new();
+    let mut embeds = Vec::new();
+    let mut embeds_len = 0;
+    let mut any_results = false;
+    for segment in rich::segments(text) {
+        match segment {
+            Segment::Text(t) => {
+                if !embeds.is_empty() {
+                    messages.push((prose.join("\n\n"), std::mem::take(&mut embeds)));
+                    prose.clear();
+                    embeds_len = 0;
+                }
+                prose.push(t);
+            }
+            Segment::Result(result) => {
+                let (embed, len) = result_embed(&result)?;
+                if embeds.len() == DISCORD_MAX_EMBEDS
+                    || embeds_len + len > DISCORD_EMBEDS_TOTAL_MAX_LEN
+                {
+                    messages.push((prose.join("\n\n"), std::mem::take(&mut embeds)));
+                    prose.clear();
+                    embeds_len = 0;
+                }
+                embeds.push(embed);
+                embeds_len += len;
+                any_results = true;
+            }
+        }
+    }
+    if !any_results {
+        return None;
+    }
+    if !prose.is_empty() || !embeds.is_empty() {
+        messages.push((prose.join("\n\n"), embeds));
+    }
+    Some(messages)
+}
+
+/// An embed with the result's title, its fields inline and its table as a
+/// code block in the description, and the characters it counts toward
+/// Discord's per-message total. `None` if it cannot fit in one message.
+fn result_embed(result: &StructuredResult) -> Option<(CreateEmbed, usize)> {
+    if result.fields.len() > DISCORD_MAX_FIELDS {
+        return None;
+    }
+    let mut embed = CreateEmbed::new();
+    let mut len = 0;
+    if let Some(title) = &result.title {
+        let title = truncate(title, 256);
+        len += title.chars().count();
+        embed = embed.title(title);
+    }
+    if let Some(table) = result.table_text() {
+        let table = table.replace("```", "`\u{200b}``");
+        let description = format!("```\n{}\n```", table);
+        let description_len = description.chars().count();
+        if description_len > DISCORD_DESCRIPTION_MAX_LEN {
+            return None;
+        }
+        len += description_len;
+        embed = embed.description(description);
+    }
+    for (name, value) in &result.fields {
+        // Discord rejects empty field names and values
+        let name = non_empty(truncate(name, 256));
+        let value = non_empty(truncate(value, 1024));
+        len += name.chars().count() + value.chars().count();
+        embed = embed.field(name, value, true);
+    }
+    if len > DISCORD_EMBEDS_TOTAL_MAX_LEN {
+        return None;
+    }
+    Some((embed, len))
+}
+
+/// `text`, or a zero-width space where Discord needs something visible.
+fn non_empty(text: String) -> String {
+    if text.trim().is_empty() {
+        "\u{200b}".to_string()
+    } else {
+        text
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::security::SecurityTier;
+
+    #[test]
+    fn approval_detail_in_code_block() {
+        let mut request = ApprovalRequest {
+            id: "req-1".into(),
+            tool_name: "bash".into(),
+            tier: SecurityTier::T3,
+            input_summary: "echo".into(),
+            session_id: "discord:1".into(),
+            timestamp: chrono::Utc::now(),
+            input_detail: None,
+            confirm_word: None,
+            calls: vec![],
+        };
+        assert!(!approval_text(&request).contains("```"));
+
+        request.input_detail = Some("{\n  \"command\": \"echo ```\"\n}".into());
+        let text = approval_text(&request);
+        assert!(text.ends_with("```json\n{\n  \"command\": \"echo `\u{200b}``\"\n}\n```"));
+    }
+
+    #[test]
+    fn structured_result_becomes_embed() {
+        let reply = "Usage:\n```ryvos-result\n{\"title\": \"Disk\", \"fields\": [{\"name\": \"Host\", \"value\": \"web-1\"}], \"columns\": [\"Mount\", \"Used\"], \"rows\": [[\"/\", \"71%\"]]}\n```\nDone.";
+        let messages = result_message(reply).unwrap();
+        assert_eq!(messages.len(), 2);
+        let (prose, embeds) = &messages[0];
+        assert_eq!(prose, "Usage:");
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(messages[1].0, "Done.");
+        assert!(messages[1].1.is_empty());
+
+        let json = serde_json::to_value(&embeds[0]).unwrap();
+        assert_eq!(json["title"], "Disk");
+        assert_eq!(
+            json["description"],
+            "```\nMount  Used\n-----  ----\n/      71%\n```"
+        );
+        assert_eq!(
+            json["fields"],
+            serde_json::json!([{ "name": "Host", "value": "web-1", "inline": true }])
+        );
+
+        assert!(result_message("no results here").is_none());
+    }
+
+    #[test]
+    fn result_embeds_respect_discord_limits() {
+        let result = |fields: &str| {
+            format!(
+                "```ryvos-result\n{{\"title\": \"T\", \"fields\": [{}]}}\n```\n",
+                fields
+            )
+        };
+        let (_, embeds) = &result_message(&result(r#"{"name": "", "value": "x"}"#)).unwrap()[0];
+        let json = serde_json::to_value(&embeds[0]).unwrap();
+        assert_eq!(json["fields"][0]["name"], "\u{200b}");
+
+        // Four embeds of ~2000 characters need two messages
+        let big = format!(r#"{{"name": "n", "value": "{}"}}"#, "v".repeat(1000));
+        let one = result(&[big.as_str(), big.as_str()].join(", "));
+        let messages = result_message(&one.repeat(4)).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].1.len(), 2);
+        assert_eq!(messages[1].1.len(), 2);
+    }
+
+    #[test]
+    fn auth_failures_stop_the_reconnect_loop() {
+        assert!(is_fatal(&serenity::Error::Gateway(
+            GatewayError::InvalidAuthentication
+        )));
+        assert!(is_fatal(&serenity::Error::Gateway(
+            GatewayError::DisallowedGatewayIntents
+        )));
+        assert!(!is_fatal(&serenity::Error::Gateway(
+            GatewayError::HeartbeatFailed
+        )));
+        assert!(!is_fatal(&serenity::Error::Gateway(
+            GatewayError::ReconnectFailure
+        )));
+    }
+}
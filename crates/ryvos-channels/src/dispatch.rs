@@ -4,31 +4,158 @@
 //! and the agent. It:
 //!
 //! 1. Receives [`MessageEnvelope`]s from channel adapters via an mpsc channel.
+//!    Redeliveries of an already-seen `(channel, envelope id)` within the
+//!    dedupe window are dropped.
 //! 2. Checks for special commands (`/approve`, `/deny`) and routes them to
 //!    the [`ApprovalBroker`] for human-in-the-loop decisions.
-//! 3. For regular messages, spawns a tokio task that calls `runtime.run()`,
+//! 3. For regular messages, spawns a tokio task that calls
+//!    `runtime.run_with_metadata()`, recording the channel and sender.
+//!    Runs for one session never overlap: a message arriving while its
+//!    session is busy is queued (and optionally coalesced with other queued
+//!    messages) until the current run finishes. The task
 //!    manages session resume for CLI providers, and sends the response back
-//!    through the originating adapter.
+//!    through the originating adapter. Adapters that can edit messages get a
+//!    placeholder right away that is edited as the response streams in.
 //! 4. Subscribes to the EventBus and forwards heartbeat alerts and cron job
-//!    results to the appropriate channel adapters.
+//!    results to the appropriate channel adapters, and logs adapters'
+//!    connection changes.
 //! 5. Fires lifecycle hooks (on_start, on_session_start, on_message,
 //!    on_response, on_session_end) at each stage.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 
 use ryvos_agent::{AgentRuntime, ApprovalBroker};
 use ryvos_core::config::HooksConfig;
 use ryvos_core::event::EventBus;
-use ryvos_core::security::ApprovalDecision;
+use ryvos_core::hooks::HookEvent;
+use ryvos_core::security::{ApprovalDecision, ApprovalTimeoutAction};
 use ryvos_core::traits::ChannelAdapter;
 use ryvos_core::types::{AgentEvent, MessageContent, MessageEnvelope};
 use ryvos_memory::SessionMetaStore;
 
+use crate::rich;
+use crate::stream::StreamingReply;
+
+/// Default window in which a redelivered envelope id is dropped.
+const DEFAULT_DEDUPE_WINDOW: Duration = Duration::from_secs(300);
+
+/// Default minimum time between edits of a streaming reply.
+const DEFAULT_STREAM_EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// How often a reply collector that lagged checks whether the run has ended.
+const LAGGED_POLL: Duration = Duration::from_millis(250);
+
+/// Recently seen `(channel, envelope id)` pairs.
+struct SeenMessages {
+    window: Duration,
+    seen: HashMap<(String, String), Instant>,
+}
+
+impl SeenMessages {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Record an envelope. Returns `false` if the same id already arrived on
+    /// this channel within the window.
+    fn first_delivery(&mut self, channel: &str, id: &str) -> bool {
+        if self.window.is_zero() {
+            return true;
+        }
+        let window = self.window;
+        self.seen.retain(|_, at| at.elapsed() < window);
+        self.seen
+            .insert((channel.to_string(), id.to_string()), Instant::now())
+            .is_none()
+    }
+}
+
+/// Notice sent when a message has to wait for its session's current run.
+const QUEUED_NOTICE: &str = "Still working on your previous message; I'll get to this one next.";
+
+/// Appended to an answer cut short by the run's turn limit.
+fn turn_limit_notice(max_turns: usize) -> String {
+    format!(
+        "\n\n(Stopped at the limit of {} turns; this answer may be incomplete.)",
+        max_turns
+    )
+}
+
+/// Messages waiting for a busy session. A session has an entry exactly
+/// while a worker is running messages for it.
+#[derive(Default)]
+struct SessionQueues {
+    busy: std::sync::Mutex<HashMap<String, VecDeque<MessageEnvelope>>>,
+}
+
+impl SessionQueues {
+    /// Claim the envelope's session for a new worker. Returns `false` and
+    /// queues the envelope if a worker already owns the session.
+    fn claim_or_queue(&self, env: &MessageEnvelope) -> bool {
+        let mut busy = self.busy.lock().unwrap();
+        match busy.get_mut(&env.session_id.0) {
+            Some(queue) => {
+                queue.push_back(env.clone());
+                false
+            }
+            None => {
+                busy.insert(env.session_id.0.clone(), VecDeque::new());
+                true
+            }
+        }
+    }
+
+    /// The next envelope for `session`, or `None` after releasing the
+    /// session. With `coalesce`, every queued message is merged into one.
+    fn next(&self, session: &str, coalesce: bool) -> Option<MessageEnvelope> {
+        let mut busy = self.busy.lock().unwrap();
+        let queue = busy.get_mut(session)?;
+        let next = if coalesce {
+            let mut merged = queue.pop_front()?;
+            for later in queue.drain(..) {
+                merged.text = format!("{}\n\n{}", merged.text, later.text);
+                merged.attachments.extend(later.attachments);
+                merged.id = later.id;
+                merged.timestamp = later.timestamp;
+            }
+            Some(merged)
+        } else {
+            queue.pop_front()
+        };
+        if next.is_none() {
+            busy.remove(session);
+        }
+        next
+    }
+}
+
+/// A worker's hold on a claimed session. Dropping it while still held,
+/// as when a run panics, releases the session and drops its queue, so the
+/// session does not stay busy forever.
+struct SessionClaim {
+    queues: Arc<SessionQueues>,
+    session: String,
+    held: bool,
+}
+
+impl Drop for SessionClaim {
+    fn drop(&mut self) {
+        if self.held {
+            warn!(session = %self.session, "Session worker ended early, releasing session");
+            self.queues.busy.lock().unwrap().remove(&self.session);
+        }
+    }
+}
+
 /// Dispatches incoming channel messages to the agent runtime
 /// and routes responses back to the originating adapter.
 pub struct ChannelDispatcher {
@@ -39,6 +166,19 @@ pub struct ChannelDispatcher {
     hooks: Option<HooksConfig>,
     broker: Option<Arc<ApprovalBroker>>,
     session_meta: Option<Arc<SessionMetaStore>>,
+    dedupe_window: Duration,
+    stream_edit_interval: Option<Duration>,
+    coalesce_queued: bool,
+}
+
+/// Everything a spawned message task needs from the dispatcher.
+#[derive(Clone)]
+struct MessageContext {
+    runtime: Arc<AgentRuntime>,
+    event_bus: Arc<EventBus>,
+    hooks: Option<HooksConfig>,
+    session_meta: Option<Arc<SessionMetaStore>>,
+    stream_edit_interval: Option<Duration>,
 }
 
 impl ChannelDispatcher {
@@ -55,9 +195,30 @@ impl ChannelDispatcher {
             hooks: None,
             broker: None,
             session_meta: None,
+            dedupe_window: DEFAULT_DEDUPE_WINDOW,
+            stream_edit_interval: Some(DEFAULT_STREAM_EDIT_INTERVAL),
+            coalesce_queued: false,
         }
     }
 
+    /// Merge all messages queued behind a busy session into a single run
+    /// instead of running them one by one.
+    pub fn set_coalesce_queued(&mut self, coalesce: bool) {
+        self.coalesce_queued = coalesce;
+    }
+
+    /// Set the minimum time between edits of a streaming reply. `None`
+    /// sends each reply once, after the run completes.
+    pub fn set_stream_edit_interval(&mut self, interval: Option<Duration>) {
+        self.stream_edit_interval = interval;
+    }
+
+    /// Set how long a delivered envelope id is remembered for dropping
+    /// redeliveries. Zero disables deduplication.
+    pub fn set_dedupe_window(&mut self, window: Duration) {
+        self.dedupe_window = window;
+    }
+
     /// Add a channel adapter.
     pub fn add_adapter(&mut self, adapter: Arc<dyn ChannelAdapter>) {
         self.adapters.insert(adapter.name().to_string(), adapter);
@@ -95,10 +256,11 @@ impl ChannelDispatcher {
 
         // Fire on_start hook
         if let Some(ref hooks) = self.hooks {
-            ryvos_core::hooks::run_hooks(&hooks.on_start, &[]).await;
+            let event = HookEvent::new("start", hooks.payload, &[]);
+            ryvos_core::hooks::run_hooks(&hooks.on_start, &event).await;
         }
 
-        // Spawn a heartbeat event router task
+        // Spawn a router for heartbeat, cron and escalated-approval events
         {
             let mut hb_rx = self.event_bus.subscribe();
             let adapters = self.adapters.clone();
@@ -119,18 +281,31 @@ impl ChannelDispatcher {
                                     let msg = format!("[Cron: {}] {}", name, response);
                                     (MessageContent::Text(msg), channel)
                                 }
+                                Ok(AgentEvent::ApprovalTimedOut {
+                                    request,
+                                    action: ApprovalTimeoutAction::Escalate,
+                                    target_channel,
+                                }) => {
+                                    let short_id = &request.id[..8.min(request.id.len())];
+                                    let msg = format!(
+                                        "[APPROVAL ESCALATED] {} ({}) in session {} got no answer: \"{}\"\nReply /approve {} or /deny {}",
+                                        request.tool_name, request.tier, request.session_id,
+                                        request.input_summary, short_id, short_id,
+                                    );
+                                    (MessageContent::Text(msg), target_channel)
+                                }
+                                Ok(AgentEvent::ChannelConnected { channel }) => {
+                                    info!(channel = %channel, "Channel connected");
+                                    continue;
+                                }
+                                Ok(AgentEvent::ChannelDisconnected { channel, reason }) => {
+                                    warn!(channel = %channel, reason = %reason, "Channel disconnected, adapter is reconnecting");
+                                    continue;
+                                }
                                 _ => continue,
                             };
 
-                            if let Some(ref channel) = target_channel {
-                                if let Some(adapter) = adapters.get(channel) {
-                                    adapter.broadcast(&content).await.ok();
-                                }
-                            } else {
-                                for adapter in adapters.values() {
-                                    adapter.broadcast(&content).await.ok();
-                                }
-                            }
+                            route_notice(&adapters, &content, target_channel.as_deref()).await;
                         }
                     }
                 }
@@ -139,6 +314,16 @@ impl ChannelDispatcher {
 
         info!(count = self.adapters.len(), "Channel dispatcher running");
 
+        let mut seen = SeenMessages::new(self.dedupe_window);
+        let ctx = MessageContext {
+            runtime: self.runtime.clone(),
+            event_bus: self.event_bus.clone(),
+            hooks: self.hooks.clone(),
+            session_meta: self.session_meta.clone(),
+            stream_edit_interval: self.stream_edit_interval,
+        };
+        let queues = Arc::new(SessionQueues::default());
+
         loop {
             tokio::select! {
                 _ = self.cancel.cancelled() => {
@@ -148,6 +333,11 @@ impl ChannelDispatcher {
                 envelope = rx.recv() => {
                     match envelope {
                         Some(env) => {
+                            if !seen.first_delivery(&env.channel, &env.id) {
+                                debug!(channel = %env.channel, id = %env.id, "Dropping redelivered message");
+                                continue;
+                            }
+
                             // Intercept /approve and /deny text commands
                             if let Some(ref broker) = self.broker {
                                 if env.text.starts_with("/approve ") || env.text.starts_with("/deny ") {
@@ -159,14 +349,22 @@ impl ChannelDispatcher {
 
                             let adapter = self.adapters.get(&env.channel).cloned();
                             if let Some(adapter) = adapter {
-                                let runtime = self.runtime.clone();
-                                let event_bus = self.event_bus.clone();
-                                let hooks = self.hooks.clone();
-                                let broker = self.broker.clone();
-                                let session_meta = self.session_meta.clone();
-                                tokio::spawn(run_channel_message(
-                                    runtime, event_bus, adapter, env, hooks, broker, session_meta,
-                                ));
+                                if queues.claim_or_queue(&env) {
+                                    tokio::spawn(run_session(
+                                        ctx.clone(),
+                                        queues.clone(),
+                                        self.coalesce_queued,
+                                        adapter,
+                                        env,
+                                    ));
+                                } else {
+                                    debug!(session = %env.session_id, "Session busy, queuing message");
+                                    let session = env.session_id.clone();
+                                    tokio::spawn(async move {
+                                        let notice = MessageContent::Text(QUEUED_NOTICE.into());
+                                        adapter.send(&session, &notice).await.ok();
+                                    });
+                                }
                             } else {
                                 error!(channel = %env.channel, "No adapter for channel");
                             }
@@ -192,6 +390,29 @@ impl ChannelDispatcher {
     }
 }
 
+/// Broadcast a system notice (heartbeat alert, cron result, escalation) on
+/// `target_channel`, or on every adapter when there is none. Returns the
+/// outcome per channel it was sent on; an unknown target sends nothing.
+pub async fn route_notice(
+    adapters: &HashMap<String, Arc<dyn ChannelAdapter>>,
+    content: &MessageContent,
+    target_channel: Option<&str>,
+) -> Vec<(String, ryvos_core::error::Result<()>)> {
+    let targets: Vec<(&String, &Arc<dyn ChannelAdapter>)> = match target_channel {
+        Some(channel) => adapters.get_key_value(channel).into_iter().collect(),
+        None => adapters.iter().collect(),
+    };
+    let mut outcomes = Vec::new();
+    for (name, adapter) in targets {
+        let result = adapter.broadcast(content).await;
+        if let Err(ref e) = result {
+            warn!(channel = %name, error = %e, "Failed to deliver notice");
+        }
+        outcomes.push((name.clone(), result));
+    }
+    outcomes
+}
+
 /// Handle /approve or /deny text commands from a channel.
 async fn handle_approval_command(
     broker: &ApprovalBroker,
@@ -205,7 +426,7 @@ async fn handle_approval_command(
         None => {
             if let Some(adapter) = adapter {
                 let usage = if is_approve {
-                    "Usage: /approve <id-prefix>"
+                    "Usage: /approve <id-prefix> [CONFIRM | call numbers]"
                 } else {
                     "Usage: /deny <id-prefix> [reason]"
                 };
@@ -233,7 +454,7 @@ async fn handle_approval_command(
     };
 
     let decision = if is_approve {
-        ApprovalDecision::Approved
+        ApprovalDecision::from_approve_args(parts.get(2..).unwrap_or_default())
     } else {
         let reason = if parts.len() > 2 {
             parts[2..].join(" ")
@@ -243,6 +464,24 @@ async fn handle_approval_command(
         ApprovalDecision::Denied { reason }
     };
 
+    if is_approve {
+        if let Some(word) = broker.confirm_word(&full_id).await {
+            if !decision.confirms(&word) {
+                if let Some(adapter) = adapter {
+                    let msg = format!(
+                        "This runs a destructive command. Send /approve {} {} to run it.",
+                        prefix, word
+                    );
+                    adapter
+                        .send(&envelope.session_id, &MessageContent::Text(msg))
+                        .await
+                        .ok();
+                }
+                return;
+            }
+        }
+    }
+
     let label = if is_approve { "Approved" } else { "Denied" };
     let short_id = &full_id[..8.min(full_id.len())];
 
@@ -263,17 +502,43 @@ async fn handle_approval_command(
     }
 }
 
+/// Run `envelope` and then every message queued behind it for the same
+/// session, one run at a time, before releasing the session.
+async fn run_session(
+    ctx: MessageContext,
+    queues: Arc<SessionQueues>,
+    coalesce: bool,
+    adapter: Arc<dyn ChannelAdapter>,
+    envelope: MessageEnvelope,
+) {
+    let mut claim = SessionClaim {
+        queues,
+        session: envelope.session_id.0.clone(),
+        held: true,
+    };
+    let mut next = Some(envelope);
+    while let Some(envelope) = next {
+        run_channel_message(ctx.clone(), adapter.clone(), envelope).await;
+        next = claim.queues.next(&claim.session, coalesce);
+    }
+    // `next` released the session once its queue ran dry
+    claim.held = false;
+}
+
 /// Handle a single channel message: run the agent, capture response via
 /// EventBus, and send the collected text back through the adapter.
 async fn run_channel_message(
-    runtime: Arc<AgentRuntime>,
-    event_bus: Arc<EventBus>,
+    ctx: MessageContext,
     adapter: Arc<dyn ChannelAdapter>,
     envelope: MessageEnvelope,
-    hooks: Option<HooksConfig>,
-    _broker: Option<Arc<ApprovalBroker>>,
-    session_meta: Option<Arc<SessionMetaStore>>,
 ) {
+    let MessageContext {
+        runtime,
+        event_bus,
+        hooks,
+        session_meta,
+        stream_edit_interval,
+    } = ctx;
     let session_id = envelope.session_id.clone();
 
     info!(
@@ -285,20 +550,20 @@ async fn run_channel_message(
 
     // Fire on_session_start hook
     if let Some(ref hooks) = hooks {
-        ryvos_core::hooks::run_hooks(&hooks.on_session_start, &[("RYVOS_SESSION", &session_id.0)])
-            .await;
+        let env = [("RYVOS_SESSION", session_id.0.as_str())];
+        let event = HookEvent::new("session_start", hooks.payload, &env);
+        ryvos_core::hooks::run_hooks(&hooks.on_session_start, &event).await;
     }
 
     // Fire on_message hook
     if let Some(ref hooks) = hooks {
-        ryvos_core::hooks::run_hooks(
-            &hooks.on_message,
-            &[
-                ("RYVOS_SESSION", &session_id.0),
-                ("RYVOS_TEXT", &envelope.text),
-            ],
-        )
-        .await;
+        let env = [
+            ("RYVOS_SESSION", session_id.0.as_str()),
+            ("RYVOS_TEXT", envelope.text.as_str()),
+        ];
+        let event = HookEvent::new("message", hooks.payload, &env)
+            .with("channel", envelope.channel.clone().into());
+        ryvos_core::hooks::run_hooks(&hooks.on_message, &event).await;
     }
 
     // Subscribe to events BEFORE running so we capture all deltas
@@ -317,6 +582,7 @@ async fn run_channel_message(
         .as_ref()
         .map(|h| h.on_tool_error.clone())
         .unwrap_or_default();
+    let hook_payload = hooks.as_ref().map(|h| h.payload).unwrap_or_default();
     let session_id_str = session_id.0.clone();
 
     // Resume previous CLI session if available
@@ -336,13 +602,22 @@ async fn run_channel_message(
             .ok();
     }
 
+    // Post a placeholder to edit as text streams in, where supported
+    let mut live = match stream_edit_interval {
+        Some(interval) => {
+            StreamingReply::start(adapter.clone(), session_id.clone(), interval).await
+        }
+        None => None,
+    };
+
     // Run the agent in a background task, publishing RunError on failure
     let rt = runtime.clone();
     let sid = session_id.clone();
-    let text = envelope.text.clone();
+    let text = envelope.prompt();
+    let metadata = envelope.metadata();
     let eb = event_bus.clone();
     let run_handle = tokio::spawn(async move {
-        let result = rt.run(&sid, &text).await;
+        let result = rt.run_with_metadata(&sid, &text, metadata).await;
         if let Err(ref e) = result {
             eb.publish(AgentEvent::RunError {
                 error: e.to_string(),
@@ -353,60 +628,78 @@ async fn run_channel_message(
 
     // Collect text deltas from the event stream
     let mut response_text = String::new();
+    let rich_results = adapter.renders_structured_results();
+    let mut turn_limit = None;
+    let mut lagged = false;
     loop {
-        match event_rx.recv().await {
+        let event = if lagged {
+            // RunComplete may have been among the skipped events
+            match tokio::time::timeout(LAGGED_POLL, event_rx.recv()).await {
+                Ok(event) => event,
+                Err(_) if run_handle.is_finished() => break,
+                Err(_) => continue,
+            }
+        } else {
+            event_rx.recv().await
+        };
+        match event {
             Ok(AgentEvent::TextDelta(delta)) => {
                 response_text.push_str(&delta);
-            }
-            Ok(AgentEvent::ToolStart { ref name, .. }) => {
-                if !on_tool_call_cmds.is_empty() {
-                    let cmds = on_tool_call_cmds.clone();
-                    let sid = session_id_str.clone();
-                    let tool = name.clone();
-                    tokio::spawn(async move {
-                        ryvos_core::hooks::run_hooks(
-                            &cmds,
-                            &[("RYVOS_SESSION", &sid), ("RYVOS_TOOL", &tool)],
-                        )
-                        .await;
-                    });
+                if let Some(ref mut live) = live {
+                    if rich_results {
+                        live.update(&response_text).await;
+                    } else {
+                        live.update(&rich::to_markdown(&response_text)).await;
+                    }
                 }
             }
-            Ok(AgentEvent::TurnComplete { turn }) => {
-                if !on_turn_complete_cmds.is_empty() {
-                    let cmds = on_turn_complete_cmds.clone();
-                    let sid = session_id_str.clone();
-                    let turn_str = turn.to_string();
-                    tokio::spawn(async move {
-                        ryvos_core::hooks::run_hooks(
-                            &cmds,
-                            &[("RYVOS_SESSION", &sid), ("RYVOS_TURN", &turn_str)],
-                        )
-                        .await;
-                    });
-                }
+            Ok(AgentEvent::ToolStart { name, input }) if !on_tool_call_cmds.is_empty() => {
+                let cmds = on_tool_call_cmds.clone();
+                let sid = session_id_str.clone();
+                tokio::spawn(async move {
+                    let env = [
+                        ("RYVOS_SESSION", sid.as_str()),
+                        ("RYVOS_TOOL", name.as_str()),
+                    ];
+                    let event =
+                        HookEvent::new("tool_call", hook_payload, &env).with("input", input);
+                    ryvos_core::hooks::run_hooks(&cmds, &event).await;
+                });
+            }
+            Ok(AgentEvent::TurnComplete { turn }) if !on_turn_complete_cmds.is_empty() => {
+                let cmds = on_turn_complete_cmds.clone();
+                let sid = session_id_str.clone();
+                let turn_str = turn.to_string();
+                tokio::spawn(async move {
+                    let env = [("RYVOS_SESSION", sid.as_str()), ("RYVOS_TURN", &turn_str)];
+                    let event = HookEvent::new("turn_complete", hook_payload, &env)
+                        .with("turn", turn.into());
+                    ryvos_core::hooks::run_hooks(&cmds, &event).await;
+                });
             }
             Ok(AgentEvent::ToolEnd {
                 ref name,
                 ref result,
-            }) if result.is_error => {
-                if !on_tool_error_cmds.is_empty() {
-                    let cmds = on_tool_error_cmds.clone();
-                    let sid = session_id_str.clone();
-                    let tool = name.clone();
-                    let error = result.content.clone();
-                    tokio::spawn(async move {
-                        ryvos_core::hooks::run_hooks(
-                            &cmds,
-                            &[
-                                ("RYVOS_SESSION", &sid),
-                                ("RYVOS_TOOL", &tool),
-                                ("RYVOS_ERROR", &error),
-                            ],
-                        )
-                        .await;
-                    });
-                }
+            }) if result.is_error && !on_tool_error_cmds.is_empty() => {
+                let cmds = on_tool_error_cmds.clone();
+                let sid = session_id_str.clone();
+                let tool = name.clone();
+                let error = result.content.clone();
+                tokio::spawn(async move {
+                    let env = [
+                        ("RYVOS_SESSION", sid.as_str()),
+                        ("RYVOS_TOOL", &tool),
+                        ("RYVOS_ERROR", &error),
+                    ];
+                    let event = HookEvent::new("tool_error", hook_payload, &env);
+                    ryvos_core::hooks::run_hooks(&cmds, &event).await;
+                });
+            }
+            Ok(AgentEvent::TurnLimitReached {
+                session_id: ref limited_sid,
+                max_turns,
+            }) if limited_sid.0 == session_id.0 => {
+                turn_limit = Some(max_turns);
             }
             Ok(AgentEvent::RunComplete {
                 session_id: ref completed_sid,
@@ -427,10 +720,16 @@ async fn run_channel_message(
                     .unwrap_or(false);
                 if !sent {
                     let short_id = &request.id[..8.min(request.id.len())];
-                    let text = format!(
+                    let mut text = format!(
                         "[APPROVAL] {} ({}): \"{}\"\nReply /approve {} or /deny {}",
                         request.tool_name, request.tier, request.input_summary, short_id, short_id,
                     );
+                    if !request.calls.is_empty() {
+                        text.push_str(&format!(
+                            "\nTo run only some calls, add their numbers: /approve {} 1 3",
+                            short_id
+                        ));
+                    }
                     adapter
                         .send(&session_id, &MessageContent::Text(text))
                         .await
@@ -448,14 +747,26 @@ async fn run_channel_message(
                     .await
                     .ok();
             }
-            Err(_) => break,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(skipped, "Reply collector lagged behind the event bus");
+                lagged = true;
+                if run_handle.is_finished() {
+                    break;
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
             _ => {}
         }
     }
 
     // Wait for the run task to finish
-    if let Err(e) = run_handle.await {
-        error!(error = %e, "Agent task panicked");
+    match run_handle.await {
+        // Skipped deltas left holes in the collected text; the run's own
+        // answer is whole
+        Ok(Ok(answer)) if lagged => response_text = answer,
+        Ok(Err(e)) if lagged => response_text = format!("Error: {}", e),
+        Ok(_) => {}
+        Err(e) => error!(error = %e, "Agent task panicked"),
     }
 
     // Persist CLI session ID for next message (--resume)
@@ -470,11 +781,23 @@ async fn run_channel_message(
 
     // Fire on_response hook
     if let Some(ref hooks) = hooks {
-        ryvos_core::hooks::run_hooks(&hooks.on_response, &[("RYVOS_SESSION", &session_id.0)]).await;
+        let env = [("RYVOS_SESSION", session_id.0.as_str())];
+        let event = HookEvent::new("response", hooks.payload, &env);
+        ryvos_core::hooks::run_hooks(&hooks.on_response, &event).await;
     }
 
     // Send the collected response back through the adapter
-    if !response_text.is_empty() {
+    if let Some(max_turns) = turn_limit {
+        response_text.push_str(&turn_limit_notice(max_turns));
+    }
+    if !rich_results {
+        response_text = rich::to_markdown(&response_text);
+    }
+    if let Some(live) = live {
+        if let Err(e) = live.finish(&response_text).await {
+            error!(error = %e, "Failed to send response to channel");
+        }
+    } else if !response_text.is_empty() {
         let content = MessageContent::Text(response_text);
         if let Err(e) = adapter.send(&session_id, &content).await {
             error!(error = %e, "Failed to send response to channel");
@@ -483,7 +806,609 @@ async fn run_channel_message(
 
     // Fire on_session_end hook
     if let Some(ref hooks) = hooks {
-        ryvos_core::hooks::run_hooks(&hooks.on_session_end, &[("RYVOS_SESSION", &session_id.0)])
-            .await;
+        let env = [("RYVOS_SESSION", session_id.0.as_str())];
+        let event = HookEvent::new("session_end", hooks.payload, &env);
+        ryvos_core::hooks::run_hooks(&hooks.on_session_end, &event).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use ryvos_core::traits::SessionStore;
+    use ryvos_core::types::{SessionId, StopReason, StreamDelta};
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient};
+    use ryvos_tools::ToolRegistry;
+
+    /// Adapter that delivers a fixed list of envelopes when started.
+    struct ReplayAdapter {
+        inbound: Vec<MessageEnvelope>,
+    }
+
+    impl ChannelAdapter for ReplayAdapter {
+        fn name(&self) -> &str {
+            "replay"
+        }
+
+        fn start(
+            &self,
+            tx: mpsc::Sender<MessageEnvelope>,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            Box::pin(async move {
+                for env in &self.inbound {
+                    tx.send(env.clone()).await.unwrap();
+                }
+                Ok(())
+            })
+        }
+
+        fn send(
+            &self,
+            _session: &SessionId,
+            _content: &MessageContent,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn broadcast(
+            &self,
+            _content: &MessageContent,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn stop(&self) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn envelope(id: &str, text: &str) -> MessageEnvelope {
+        MessageEnvelope {
+            id: id.into(),
+            session_id: SessionId::from_string(&format!("session-{}", id)),
+            session_key: format!("replay:{}", id),
+            channel: "replay".into(),
+            sender: "user".into(),
+            text: text.into(),
+            attachments: vec![],
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn redelivered_envelope_is_dropped() {
+        let llm = MockLlmClient::new()
+            .with_text_response("first")
+            .with_text_response("second")
+            .with_text_response("unexpected");
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        ));
+        let mut dispatcher = ChannelDispatcher::new(
+            runtime,
+            Arc::new(EventBus::default()),
+            CancellationToken::new(),
+        );
+        dispatcher.add_adapter(Arc::new(ReplayAdapter {
+            inbound: vec![
+                envelope("m1", "hello"),
+                envelope("m1", "hello"),
+                envelope("m2", "again"),
+            ],
+        }));
+
+        // The dispatcher returns once the adapter's sender is dropped
+        dispatcher.run().await.unwrap();
+        for _ in 0..50 {
+            if llm.call_count() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[test]
+    fn seen_ids_expire_after_window() {
+        let mut seen = SeenMessages::new(Duration::from_millis(20));
+        assert!(seen.first_delivery("telegram", "1:100"));
+        assert!(!seen.first_delivery("telegram", "1:100"));
+        // Same id on another channel is a different message
+        assert!(seen.first_delivery("discord", "1:100"));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(seen.first_delivery("telegram", "1:100"));
+    }
+
+    #[test]
+    fn zero_window_disables_dedupe() {
+        let mut seen = SeenMessages::new(Duration::ZERO);
+        assert!(seen.first_delivery("slack", "C1:1700000000.000100"));
+        assert!(seen.first_delivery("slack", "C1:1700000000.000100"));
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Call {
+        Send(String),
+        SendEditable(String),
+        Edit(String, String),
+    }
+
+    /// Adapter that delivers one envelope and records what the dispatcher
+    /// sends back, optionally supporting message edits.
+    struct RecordingAdapter {
+        inbound: Vec<MessageEnvelope>,
+        editable: bool,
+        calls: Arc<std::sync::Mutex<Vec<Call>>>,
+    }
+
+    fn text_of(content: &MessageContent) -> String {
+        match content {
+            MessageContent::Text(t) => t.clone(),
+            MessageContent::Streaming { delta, .. } => delta.clone(),
+        }
+    }
+
+    impl ChannelAdapter for RecordingAdapter {
+        fn name(&self) -> &str {
+            "replay"
+        }
+
+        fn start(
+            &self,
+            tx: mpsc::Sender<MessageEnvelope>,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            Box::pin(async move {
+                for env in &self.inbound {
+                    tx.send(env.clone()).await.unwrap();
+                }
+                Ok(())
+            })
+        }
+
+        fn send(
+            &self,
+            _session: &SessionId,
+            content: &MessageContent,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(Call::Send(text_of(content)));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn send_editable(
+            &self,
+            _session: &SessionId,
+            content: &MessageContent,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<Option<String>>> {
+            let editable = self.editable;
+            if editable {
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push(Call::SendEditable(text_of(content)));
+            }
+            Box::pin(async move { Ok(editable.then(|| "msg-1".to_string())) })
+        }
+
+        fn edit(
+            &self,
+            _session: &SessionId,
+            message_id: &str,
+            content: &MessageContent,
+        ) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(Call::Edit(message_id.to_string(), text_of(content)));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn stop(&self) -> BoxFuture<'_, ryvos_core::error::Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    /// Dispatch one message whose reply streams as three deltas, and return
+    /// the adapter calls once the final reply has been delivered.
+    async fn dispatch_streamed_reply(editable: bool) -> Vec<Call> {
+        let llm = MockLlmClient::new().with_response(vec![
+            StreamDelta::TextDelta("Hel".into()),
+            StreamDelta::TextDelta("lo ".into()),
+            StreamDelta::TextDelta("world".into()),
+            StreamDelta::Stop(StopReason::EndTurn),
+        ]);
+        let bus = Arc::new(EventBus::default());
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            bus.clone(),
+        ));
+        let mut dispatcher = ChannelDispatcher::new(runtime, bus, CancellationToken::new());
+        dispatcher.set_stream_edit_interval(Some(Duration::ZERO));
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        dispatcher.add_adapter(Arc::new(RecordingAdapter {
+            inbound: vec![envelope("m1", "hello")],
+            editable,
+            calls: calls.clone(),
+        }));
+
+        dispatcher.run().await.unwrap();
+        for _ in 0..100 {
+            let delivered = calls.lock().unwrap().iter().any(|c| match c {
+                Call::Send(t) | Call::Edit(_, t) => t == "Hello world",
+                Call::SendEditable(_) => false,
+            });
+            if delivered {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let calls = calls.lock().unwrap().clone();
+        calls
+    }
+
+    #[tokio::test]
+    async fn editable_adapter_gets_placeholder_then_edits() {
+        let calls = dispatch_streamed_reply(true).await;
+        assert_eq!(
+            calls,
+            vec![
+                Call::SendEditable("…".into()),
+                Call::Edit("msg-1".into(), "Hel".into()),
+                Call::Edit("msg-1".into(), "Hello ".into()),
+                Call::Edit("msg-1".into(), "Hello world".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn non_editable_adapter_gets_single_reply() {
+        let calls = dispatch_streamed_reply(false).await;
+        assert_eq!(calls, vec![Call::Send("Hello world".into())]);
+    }
+
+    #[tokio::test]
+    async fn structured_results_reach_plain_adapters_as_markdown() {
+        let reply =
+            "```ryvos-result\n{\"fields\": [{\"name\": \"Status\", \"value\": \"ok\"}]}\n```";
+        let llm = MockLlmClient::new().with_text_response(reply);
+        let bus = Arc::new(EventBus::default());
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            bus.clone(),
+        ));
+        let mut dispatcher = ChannelDispatcher::new(runtime, bus, CancellationToken::new());
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        dispatcher.add_adapter(Arc::new(RecordingAdapter {
+            inbound: vec![envelope("m1", "status?")],
+            editable: false,
+            calls: calls.clone(),
+        }));
+
+        dispatcher.run().await.unwrap();
+        for _ in 0..100 {
+            if !calls.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            calls.lock().unwrap().clone(),
+            vec![Call::Send("**Status:** ok".into())]
+        );
+    }
+
+    /// Envelope `id` in the shared session `chat`.
+    fn chat_envelope(id: &str, text: &str) -> MessageEnvelope {
+        MessageEnvelope {
+            session_id: SessionId::from_string("chat"),
+            session_key: "replay:chat".into(),
+            ..envelope(id, text)
+        }
+    }
+
+    /// Dispatch `inbound` (all for session `chat`) and wait until the LLM
+    /// has been called `runs` times and every reply has been sent.
+    async fn dispatch_to_one_session(
+        inbound: Vec<MessageEnvelope>,
+        runs: usize,
+        coalesce: bool,
+    ) -> (MockLlmClient, Arc<InMemorySessionStore>, Vec<Call>) {
+        let store = Arc::new(InMemorySessionStore::new());
+        let (llm, calls) = dispatch_into(store.clone(), inbound, runs, coalesce).await;
+        (llm, store, calls)
+    }
+
+    /// `dispatch_to_one_session`, persisting to `store`.
+    async fn dispatch_into(
+        store: Arc<dyn SessionStore>,
+        inbound: Vec<MessageEnvelope>,
+        runs: usize,
+        coalesce: bool,
+    ) -> (MockLlmClient, Vec<Call>) {
+        let mut llm = MockLlmClient::new();
+        for i in 1..=runs {
+            llm = llm.with_text_response(&format!("reply {}", i));
+        }
+        let bus = Arc::new(EventBus::default());
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store.clone(),
+            bus.clone(),
+        ));
+        let mut dispatcher = ChannelDispatcher::new(runtime, bus, CancellationToken::new());
+        dispatcher.set_coalesce_queued(coalesce);
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        dispatcher.add_adapter(Arc::new(RecordingAdapter {
+            inbound,
+            editable: false,
+            calls: calls.clone(),
+        }));
+
+        dispatcher.run().await.unwrap();
+        let last_reply = Call::Send(format!("reply {}", runs));
+        for _ in 0..200 {
+            if calls.lock().unwrap().contains(&last_reply) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let calls = calls.lock().unwrap().clone();
+        (llm, calls)
+    }
+
+    #[tokio::test]
+    async fn busy_session_runs_messages_in_order() {
+        let (llm, store, calls) = dispatch_to_one_session(
+            vec![chat_envelope("m1", "first"), chat_envelope("m2", "second")],
+            2,
+            false,
+        )
+        .await;
+
+        assert_eq!(
+            calls,
+            vec![
+                Call::Send(QUEUED_NOTICE.into()),
+                Call::Send("reply 1".into()),
+                Call::Send("reply 2".into()),
+            ]
+        );
+        assert_eq!(llm.call_count(), 2);
+        // The second run started only after the first had been persisted
+        let seen: Vec<String> = llm.call_messages(1).iter().map(|m| m.text()).collect();
+        assert!(seen.contains(&"reply 1".to_string()));
+
+        let history = store
+            .load_history(&SessionId::from_string("chat"), 100)
+            .await
+            .unwrap();
+        let texts: Vec<String> = history.iter().map(|m| m.text()).collect();
+        assert_eq!(texts, vec!["first", "reply 1", "second", "reply 2"]);
+    }
+
+    #[tokio::test]
+    async fn notice_goes_to_target_channel_or_everywhere() {
+        let telegram = Arc::new(ryvos_test_utils::MockChannelAdapter::new("telegram"));
+        let slack = Arc::new(ryvos_test_utils::MockChannelAdapter::new("slack"));
+        let adapters: HashMap<String, Arc<dyn ChannelAdapter>> = [
+            (
+                "telegram".to_string(),
+                telegram.clone() as Arc<dyn ChannelAdapter>,
+            ),
+            (
+                "slack".to_string(),
+                slack.clone() as Arc<dyn ChannelAdapter>,
+            ),
+        ]
+        .into();
+        let content = MessageContent::Text("[Heartbeat Alert] disk full".into());
+
+        let sent = route_notice(&adapters, &content, Some("slack")).await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "slack");
+        assert_eq!(slack.broadcast_messages().len(), 1);
+        assert!(telegram.broadcast_messages().is_empty());
+
+        assert!(route_notice(&adapters, &content, Some("irc"))
+            .await
+            .is_empty());
+        assert_eq!(route_notice(&adapters, &content, None).await.len(), 2);
+        assert_eq!(telegram.broadcast_messages().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn panicked_worker_releases_its_session() {
+        let queues = Arc::new(SessionQueues::default());
+        let env = envelope("m1", "first");
+        assert!(queues.claim_or_queue(&env));
+        assert!(!queues.claim_or_queue(&env));
+
+        let claim = SessionClaim {
+            queues: queues.clone(),
+            session: env.session_id.0.clone(),
+            held: true,
+        };
+        let worker = tokio::spawn(async move {
+            let _claim = claim;
+            panic!("run failed");
+        });
+        assert!(worker.await.is_err());
+        assert!(queues.claim_or_queue(&env));
+    }
+
+    #[tokio::test]
+    async fn queued_messages_coalesce_into_one_run() {
+        let (llm, _store, calls) = dispatch_to_one_session(
+            vec![
+                chat_envelope("m1", "first"),
+                chat_envelope("m2", "second"),
+                chat_envelope("m3", "third"),
+            ],
+            2,
+            true,
+        )
+        .await;
+
+        assert_eq!(llm.call_count(), 2);
+        let last = llm.call_messages(1).last().unwrap().text();
+        assert_eq!(last, "second\n\nthird");
+        let notices = calls
+            .iter()
+            .filter(|c| **c == Call::Send(QUEUED_NOTICE.into()))
+            .count();
+        assert_eq!(notices, 2);
+    }
+
+    #[tokio::test]
+    async fn lagged_collector_sends_the_whole_answer() {
+        let deltas: Vec<StreamDelta> = (0..20)
+            .map(|i| StreamDelta::TextDelta(format!("{} ", i)))
+            .chain([StreamDelta::Stop(StopReason::EndTurn)])
+            .collect();
+        let llm = MockLlmClient::new().with_response(deltas);
+        let bus = Arc::new(EventBus::new(4));
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            bus.clone(),
+        ));
+        let mut dispatcher = ChannelDispatcher::new(runtime, bus, CancellationToken::new());
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        dispatcher.add_adapter(Arc::new(RecordingAdapter {
+            inbound: vec![envelope("m1", "count")],
+            editable: false,
+            calls: calls.clone(),
+        }));
+
+        dispatcher.run().await.unwrap();
+        for _ in 0..200 {
+            if !calls.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let expected: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        assert_eq!(*calls.lock().unwrap(), vec![Call::Send(expected.join(" "))]);
+    }
+
+    #[tokio::test]
+    async fn turn_limit_answer_is_sent_with_a_notice() {
+        let mut config = test_config();
+        config.agent.max_turns = 1;
+        let llm = MockLlmClient::new().with_response(vec![
+            StreamDelta::TextDelta("Halfway there.".into()),
+            StreamDelta::ToolUseStart {
+                index: 0,
+                id: "call_1".into(),
+                name: "echo".into(),
+            },
+            StreamDelta::ToolInputDelta {
+                index: 0,
+                delta: "{}".into(),
+            },
+            StreamDelta::Stop(StopReason::ToolUse),
+        ]);
+        let mut tools = ToolRegistry::new();
+        tools.register(ryvos_test_utils::MockTool::new("echo"));
+        let bus = Arc::new(EventBus::default());
+        let runtime = Arc::new(AgentRuntime::new(
+            config,
+            Arc::new(llm) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            Arc::new(InMemorySessionStore::new()),
+            bus.clone(),
+        ));
+        let mut dispatcher = ChannelDispatcher::new(runtime, bus, CancellationToken::new());
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        dispatcher.add_adapter(Arc::new(RecordingAdapter {
+            inbound: vec![envelope("m1", "do it all")],
+            editable: false,
+            calls: calls.clone(),
+        }));
+
+        dispatcher.run().await.unwrap();
+        for _ in 0..100 {
+            if !calls.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let expected = format!("Halfway there.{}", turn_limit_notice(1));
+        assert_eq!(*calls.lock().unwrap(), vec![Call::Send(expected)]);
+    }
+
+    #[tokio::test]
+    async fn channel_source_is_kept_through_storage_and_export() {
+        use ryvos_agent::session_export::{render_json, render_markdown};
+        use ryvos_core::types::{Attachment, AttachmentKind};
+
+        let store = Arc::new(ryvos_memory::SqliteStore::in_memory().unwrap());
+        let photo = Attachment {
+            kind: AttachmentKind::Image,
+            reference: "AgACAgQ".to_string(),
+        };
+        let inbound = MessageEnvelope {
+            sender: "4242".into(),
+            attachments: vec![photo.clone()],
+            ..chat_envelope("m1", "What is in this photo?")
+        };
+        dispatch_into(store.clone(), vec![inbound], 1, false).await;
+
+        let sid = SessionId::from_string("chat");
+        let history = store.load_history(&sid, 100).await.unwrap();
+        let source = history[0].metadata.as_ref().unwrap();
+        assert_eq!(source.source_channel.as_deref(), Some("replay"));
+        assert_eq!(source.sender.as_deref(), Some("4242"));
+        assert_eq!(source.attachments, vec![photo]);
+        assert!(history[1].metadata.is_none());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json(&sid, &history).unwrap()).unwrap();
+        let exported = &json["messages"][0]["metadata"];
+        assert_eq!(exported["source_channel"], "replay");
+        assert_eq!(exported["attachments"][0]["type"], "image");
+        let md = render_markdown(&sid, &history);
+        assert!(md.contains("\n_via replay from 4242_\n"), "{}", md);
+        assert!(md.contains("- image `AgACAgQ`"));
+    }
+
+    #[tokio::test]
+    async fn attachment_without_text_is_run_on_its_listing() {
+        use ryvos_core::types::{Attachment, AttachmentKind};
+
+        let store = Arc::new(ryvos_memory::SqliteStore::in_memory().unwrap());
+        let inbound = MessageEnvelope {
+            attachments: vec![Attachment {
+                kind: AttachmentKind::Image,
+                reference: "AgACAgQ".to_string(),
+            }],
+            ..chat_envelope("m1", "")
+        };
+        dispatch_into(store.clone(), vec![inbound], 1, false).await;
+
+        let history = store
+            .load_history(&SessionId::from_string("chat"), 100)
+            .await
+            .unwrap();
+        assert_eq!(history[0].text(), "[image attached: AgACAgQ]");
     }
 }
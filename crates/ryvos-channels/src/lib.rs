@@ -9,6 +9,10 @@
 //! - **Discord**: Serenity event handler, per-guild sessions.
 //! - **WhatsApp**: Cloud API webhooks, interactive buttons.
 //!
+//! Replies may carry structured results (see [`rich`]); Slack and Discord
+//! render them as Block Kit sections and embeds when `rich_formatting` is
+//! on, and every other adapter receives them as markdown.
+//!
 //! The [`ChannelDispatcher`] routes incoming messages to the agent runtime
 //! and delivers responses back through the originating adapter. It also
 //! handles `/approve` and `/deny` commands, lifecycle hooks, and session
@@ -17,13 +21,15 @@
 pub mod discord;
 pub mod dispatch;
 pub mod pairing;
+pub mod rich;
 pub mod slack;
+mod stream;
 pub mod telegram;
 pub mod util;
 pub mod whatsapp;
 
 pub use discord::DiscordAdapter;
-pub use dispatch::ChannelDispatcher;
+pub use dispatch::{route_notice, ChannelDispatcher};
 pub use pairing::PairingManager;
 pub use slack::SlackAdapter;
 pub use telegram::TelegramAdapter;
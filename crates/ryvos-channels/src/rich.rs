@@ -0,0 +1,249 @@
+//! Structured results in agent replies.
+//!
+//! A reply can carry tables and key/value data as a fenced code block
+//! tagged `ryvos-result` holding JSON:
+//!
+//! ````text
+//! ```ryvos-result
+//! {"title": "Disk usage",
+//!  "fields": [{"name": "Host", "value": "web-1"}],
+//!  "columns": ["Mount", "Used"],
+//!  "rows": [["/", "71%"], ["/var", "38%"]]}
+//! ```
+//! ````
+//!
+//! Adapters with `rich_formatting` enabled render these natively (Slack
+//! Block Kit sections, Discord embeds). Everywhere else the dispatcher
+//! replaces them with plain markdown via [`to_markdown`]. Blocks that do
+//! not parse are left as written.
+
+use serde::Deserialize;
+
+/// Info string of the fenced block that marks a structured result.
+pub const RESULT_MARKER: &str = "ryvos-result";
+
+/// A table and/or key/value pairs the agent wants shown as data.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructuredResult {
+    pub title: Option<String>,
+    pub fields: Vec<(String, String)>,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A piece of a reply: prose, or a parsed structured result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Result(StructuredResult),
+}
+
+#[derive(Deserialize)]
+struct RawResult {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    fields: Vec<RawField>,
+    #[serde(default)]
+    columns: Vec<String>,
+    #[serde(default)]
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+#[derive(Deserialize)]
+struct RawField {
+    name: String,
+    value: serde_json::Value,
+}
+
+impl StructuredResult {
+    /// Parse the JSON body of a `ryvos-result` block. Cells and field
+    /// values may be any JSON scalar.
+    pub fn parse(json: &str) -> Option<Self> {
+        let raw: RawResult = serde_json::from_str(json).ok()?;
+        let result = Self {
+            title: raw.title.filter(|t| !t.trim().is_empty()),
+            fields: raw
+                .fields
+                .into_iter()
+                .map(|f| (f.name, cell(f.value)))
+                .collect(),
+            columns: raw.columns,
+            rows: raw
+                .rows
+                .into_iter()
+                .map(|row| row.into_iter().map(cell).collect())
+                .collect(),
+        };
+        (result != Self::default()).then_some(result)
+    }
+
+    /// The table as monospaced, column-aligned text, or `None` when the
+    /// result has no rows.
+    pub fn table_text(&self) -> Option<String> {
+        if self.rows.is_empty() {
+            return None;
+        }
+        let width = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain([self.columns.len()])
+            .max()
+            .unwrap_or(0);
+        let mut widths = vec![0; width];
+        for row in std::iter::once(&self.columns).chain(&self.rows) {
+            for (i, c) in row.iter().enumerate() {
+                widths[i] = widths[i].max(c.chars().count());
+            }
+        }
+        let line = |row: &[String]| {
+            let cells: Vec<String> = (0..width)
+                .map(|i| {
+                    let c = row.get(i).map(String::as_str).unwrap_or("");
+                    format!("{:w$}", c, w = widths[i])
+                })
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        };
+        let mut lines = Vec::new();
+        if !self.columns.is_empty() {
+            lines.push(line(&self.columns));
+            let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+            lines.push(rule.join("  "));
+        }
+        lines.extend(self.rows.iter().map(|r| line(r)));
+        Some(lines.join("\n"))
+    }
+
+    /// Plain markdown: a bold title, one `**name:** value` line per field
+    /// and the table in a code block.
+    pub fn markdown(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(title) = &self.title {
+            parts.push(format!("**{}**", title));
+        }
+        if !self.fields.is_empty() {
+            let fields: Vec<String> = self
+                .fields
+                .iter()
+                .map(|(name, value)| format!("**{}:** {}", name, value))
+                .collect();
+            parts.push(fields.join("\n"));
+        }
+        if let Some(table) = self.table_text() {
+            parts.push(format!("```\n{}\n```", table));
+        }
+        parts.join("\n\n")
+    }
+}
+
+/// Split `text` into prose and structured results, in order. Prose
+/// segments are trimmed and empty ones dropped.
+pub fn segments(text: &str) -> Vec<Segment> {
+    let open = format!("```{}", RESULT_MARKER);
+    let mut out = Vec::new();
+    let mut prose = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        // The tag must end its line, so "```ryvos-results" is not a marker
+        let Some(body) = after.strip_prefix('\n').or(after.strip_prefix("\r\n")) else {
+            prose.push_str(&rest[..start + open.len()]);
+            rest = after;
+            continue;
+        };
+        let Some(end) = body.find("```") else {
+            break;
+        };
+        let close = rest.len() - body.len() + end + 3;
+        match StructuredResult::parse(&body[..end]) {
+            Some(result) => {
+                prose.push_str(&rest[..start]);
+                push_text(&mut out, std::mem::take(&mut prose));
+                out.push(Segment::Result(result));
+            }
+            None => prose.push_str(&rest[..close]),
+        }
+        rest = &rest[close..];
+    }
+    prose.push_str(rest);
+    push_text(&mut out, prose);
+    out
+}
+
+/// Whether `text` holds at least one parseable structured result.
+pub fn has_results(text: &str) -> bool {
+    text.contains(RESULT_MARKER)
+        && segments(text)
+            .iter()
+            .any(|s| matches!(s, Segment::Result(_)))
+}
+
+/// `text` with every structured result replaced by its markdown form.
+pub fn to_markdown(text: &str) -> String {
+    if !has_results(text) {
+        return text.to_string();
+    }
+    let parts: Vec<String> = segments(text)
+        .into_iter()
+        .map(|s| match s {
+            Segment::Text(t) => t,
+            Segment::Result(r) => r.markdown(),
+        })
+        .collect();
+    parts.join("\n\n")
+}
+
+fn push_text(out: &mut Vec<Segment>, text: String) {
+    let text = text.trim();
+    if !text.is_empty() {
+        out.push(Segment::Text(text.to_string()));
+    }
+}
+
+fn cell(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLY: &str = "Here you go:\n```ryvos-result\n{\"title\": \"Disk\", \"fields\": [{\"name\": \"Host\", \"value\": \"web-1\"}, {\"name\": \"Mounts\", \"value\": 2}], \"columns\": [\"Mount\", \"Used\"], \"rows\": [[\"/\", \"71%\"], [\"/var\", 38]]}\n```\nAll healthy.";
+
+    #[test]
+    fn segments_split_prose_and_results() {
+        let segs = segments(REPLY);
+        assert_eq!(segs.len(), 3);
+        assert_eq!(segs[0], Segment::Text("Here you go:".into()));
+        let Segment::Result(r) = &segs[1] else {
+            panic!("expected a result");
+        };
+        assert_eq!(r.title.as_deref(), Some("Disk"));
+        assert_eq!(r.fields[1], ("Mounts".to_string(), "2".to_string()));
+        assert_eq!(r.rows[1], vec!["/var".to_string(), "38".to_string()]);
+        assert_eq!(segs[2], Segment::Text("All healthy.".into()));
+    }
+
+    #[test]
+    fn markdown_fallback_aligns_table() {
+        let md = to_markdown(REPLY);
+        assert_eq!(
+            md,
+            "Here you go:\n\n**Disk**\n\n**Host:** web-1\n**Mounts:** 2\n\n```\nMount  Used\n-----  ----\n/      71%\n/var   38\n```\n\nAll healthy."
+        );
+    }
+
+    #[test]
+    fn invalid_or_plain_blocks_are_left_alone() {
+        let text = "```ryvos-result\nnot json\n```\nand ```rust\nfn main() {}\n```";
+        assert!(!has_results(text));
+        assert_eq!(to_markdown(text), text);
+        assert_eq!(segments(text), vec![Segment::Text(text.into())]);
+    }
+}
@@ -10,14 +10,24 @@ use tracing::{debug, error, info, warn};
 use ryvos_agent::{ApprovalBroker, SessionManager};
 use ryvos_core::config::{DmPolicy, SlackConfig};
 use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::event::EventBus;
 use ryvos_core::security::{ApprovalDecision, ApprovalRequest};
 use ryvos_core::traits::ChannelAdapter;
-use ryvos_core::types::{MessageContent, MessageEnvelope, SessionId};
+use ryvos_core::types::{Attachment, AttachmentKind, MessageContent, MessageEnvelope, SessionId};
 
-use crate::util::split_message;
+use crate::rich::{self, Segment, StructuredResult};
+use crate::util::{split_message, Backoff, ConnectionReporter, HEALTH_PING_INTERVAL};
 
 const SLACK_MAX_LEN: usize = 4000;
 
+/// Longest text Slack accepts in one section block.
+const SLACK_SECTION_MAX_LEN: usize = 3000;
+
+/// Most blocks Slack accepts in one message.
+const SLACK_MAX_BLOCKS: usize = 50;
+
+const SLACK_API_BASE: &str = "https://slack.com/api";
+
 /// Slack channel adapter using Socket Mode (WebSocket) for receiving
 /// and Web API for sending messages.
 pub struct SlackAdapter {
@@ -26,9 +36,12 @@ pub struct SlackAdapter {
     /// Maps session_id -> channel_id for routing responses back.
     channel_map: Arc<Mutex<HashMap<String, String>>>,
     http: reqwest::Client,
+    /// Web API base URL (overridden in tests).
+    api_base: String,
     shutdown_tx: Arc<Mutex<Option<tokio::sync::oneshot::Sender<()>>>>,
     /// Approval broker for HITL.
     broker: Arc<Mutex<Option<Arc<ApprovalBroker>>>>,
+    status: ConnectionReporter,
 }
 
 impl SlackAdapter {
@@ -38,8 +51,10 @@ impl SlackAdapter {
             session_mgr,
             channel_map: Arc::new(Mutex::new(HashMap::new())),
             http: reqwest::Client::new(),
+            api_base: SLACK_API_BASE.to_string(),
             shutdown_tx: Arc::new(Mutex::new(None)),
             broker: Arc::new(Mutex::new(None)),
+            status: ConnectionReporter::new("slack", None),
         }
     }
 
@@ -48,10 +63,15 @@ impl SlackAdapter {
         self.broker = Arc::new(Mutex::new(Some(broker)));
     }
 
+    /// Publish connection changes on the event bus.
+    pub fn set_event_bus(&mut self, event_bus: Arc<EventBus>) {
+        self.status = ConnectionReporter::new("slack", Some(event_bus));
+    }
+
     /// Request a Socket Mode WebSocket URL from Slack.
-    async fn get_ws_url(http: &reqwest::Client, app_token: &str) -> Result<String> {
+    async fn get_ws_url(http: &reqwest::Client, api_base: &str, app_token: &str) -> Result<String> {
         let resp = http
-            .post("https://slack.com/api/apps.connections.open")
+            .post(format!("{}/apps.connections.open", api_base))
             .bearer_auth(app_token)
             .header("Content-Type", "application/x-www-form-urlencoded")
             .send()
@@ -85,44 +105,142 @@ impl SlackAdapter {
             })
     }
 
-    /// Send a message via Slack Web API.
-    async fn post_message(
+    /// Call a Slack Web API method, failing unless the response is `ok`.
+    async fn api_call(
         http: &reqwest::Client,
+        api_base: &str,
         bot_token: &str,
-        channel: &str,
-        text: &str,
-    ) -> Result<()> {
+        method: &str,
+        body: serde_json::Value,
+    ) -> Result<serde_json::Value> {
         let resp = http
-            .post("https://slack.com/api/chat.postMessage")
+            .post(format!("{}/{}", api_base, method))
             .bearer_auth(bot_token)
-            .json(&serde_json::json!({
-                "channel": channel,
-                "text": text,
-            }))
+            .json(&body)
             .send()
             .await
             .map_err(|e| RyvosError::Channel {
                 channel: "slack".into(),
-                message: format!("chat.postMessage failed: {e}"),
+                message: format!("{method} failed: {e}"),
             })?;
 
         let body: serde_json::Value = resp.json().await.map_err(|e| RyvosError::Channel {
             channel: "slack".into(),
-            message: format!("Invalid postMessage response: {e}"),
+            message: format!("Invalid {method} response: {e}"),
         })?;
 
         if !body["ok"].as_bool().unwrap_or(false) {
             return Err(RyvosError::Channel {
                 channel: "slack".into(),
                 message: format!(
-                    "chat.postMessage error: {}",
+                    "{method} error: {}",
                     body["error"].as_str().unwrap_or("unknown")
                 ),
             });
         }
 
+        Ok(body)
+    }
+
+    /// Send a message via Slack Web API, returning its `ts`.
+    async fn post_message(
+        http: &reqwest::Client,
+        api_base: &str,
+        bot_token: &str,
+        channel: &str,
+        text: &str,
+    ) -> Result<String> {
+        let body = serde_json::json!({
+            "channel": channel,
+            "text": text,
+        });
+        let resp = Self::api_call(http, api_base, bot_token, "chat.postMessage", body).await?;
+        Ok(resp["ts"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Send Block Kit `blocks` with `text` as the notification fallback.
+    async fn post_blocks(
+        http: &reqwest::Client,
+        api_base: &str,
+        bot_token: &str,
+        channel: &str,
+        text: &str,
+        blocks: Vec<serde_json::Value>,
+    ) -> Result<String> {
+        let body = serde_json::json!({
+            "channel": channel,
+            "text": fallback_text(text),
+            "blocks": blocks,
+        });
+        let resp = Self::api_call(http, api_base, bot_token, "chat.postMessage", body).await?;
+        Ok(resp["ts"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Replace the message `ts` with Block Kit `blocks`.
+    async fn update_blocks(
+        &self,
+        channel: &str,
+        ts: &str,
+        text: &str,
+        blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let body = serde_json::json!({
+            "channel": channel,
+            "ts": ts,
+            "text": fallback_text(text),
+            "blocks": blocks,
+        });
+        Self::api_call(
+            &self.http,
+            &self.api_base,
+            &self.config.bot_token,
+            "chat.update",
+            body,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Block Kit for `text` when rich formatting is on and it holds
+    /// structured results.
+    fn rich_blocks(&self, text: &str) -> Option<Vec<serde_json::Value>> {
+        if self.config.rich_formatting {
+            result_blocks(text)
+        } else {
+            None
+        }
+    }
+
+    /// Replace the text of the message `ts` via `chat.update`.
+    async fn update_message(
+        http: &reqwest::Client,
+        api_base: &str,
+        bot_token: &str,
+        channel: &str,
+        ts: &str,
+        text: &str,
+    ) -> Result<()> {
+        let body = serde_json::json!({
+            "channel": channel,
+            "ts": ts,
+            "text": text,
+        });
+        Self::api_call(http, api_base, bot_token, "chat.update", body).await?;
         Ok(())
     }
+
+    /// The Slack channel mapped to `session_key`.
+    async fn channel_for(&self, session_key: &str) -> Result<String> {
+        self.channel_map
+            .lock()
+            .await
+            .get(session_key)
+            .cloned()
+            .ok_or_else(|| RyvosError::Channel {
+                channel: "slack".into(),
+                message: format!("No channel mapped for session {}", session_key),
+            })
+    }
 }
 
 impl ChannelAdapter for SlackAdapter {
@@ -139,10 +257,11 @@ impl ChannelAdapter for SlackAdapter {
         let channel_map = self.channel_map.clone();
         let http = self.http.clone();
         let bot_token = self.config.bot_token.clone();
-        let request_id = request.id.clone();
         let tool_name = request.tool_name.clone();
         let tier = request.tier;
         let input_summary = request.input_summary.clone();
+        let blocks = approval_blocks(request);
+        let api_base = self.api_base.clone();
 
         Box::pin(async move {
             let channel_id = {
@@ -155,38 +274,8 @@ impl ChannelAdapter for SlackAdapter {
                 None => return Ok(false),
             };
 
-            let blocks = serde_json::json!([
-                {
-                    "type": "section",
-                    "text": {
-                        "type": "mrkdwn",
-                        "text": format!(
-                            ":lock: *Approval Required*\n\nTool: `{}`\nTier: {}\nAction: _{}_",
-                            tool_name, tier, input_summary
-                        )
-                    }
-                },
-                {
-                    "type": "actions",
-                    "elements": [
-                        {
-                            "type": "button",
-                            "text": { "type": "plain_text", "text": "Approve" },
-                            "style": "primary",
-                            "action_id": format!("approve:{}", request_id)
-                        },
-                        {
-                            "type": "button",
-                            "text": { "type": "plain_text", "text": "Deny" },
-                            "style": "danger",
-                            "action_id": format!("deny:{}", request_id)
-                        }
-                    ]
-                }
-            ]);
-
             let resp = http
-                .post("https://slack.com/api/chat.postMessage")
+                .post(format!("{}/chat.postMessage", api_base))
                 .bearer_auth(&bot_token)
                 .json(&serde_json::json!({
                     "channel": channel_id,
@@ -222,8 +311,10 @@ impl ChannelAdapter for SlackAdapter {
         let session_mgr = self.session_mgr.clone();
         let channel_map = self.channel_map.clone();
         let http = self.http.clone();
+        let api_base = self.api_base.clone();
         let shutdown_tx_arc = self.shutdown_tx.clone();
         let broker_arc = self.broker.clone();
+        let status = self.status.clone();
 
         Box::pin(async move {
             let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
@@ -232,47 +323,61 @@ impl ChannelAdapter for SlackAdapter {
             info!("Slack adapter starting (Socket Mode)");
 
             tokio::spawn(async move {
+                let mut backoff = Backoff::default();
                 loop {
-                    // Get WebSocket URL
-                    let ws_url = match Self::get_ws_url(&http, &config.app_token).await {
-                        Ok(url) => url,
-                        Err(e) => {
-                            error!(error = %e, "Failed to get Slack WebSocket URL");
-                            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                            continue;
-                        }
-                    };
-
-                    // Connect WebSocket
-                    let ws_stream = match tokio_tungstenite::connect_async(&ws_url).await {
+                    // Get WebSocket URL, then connect
+                    let connected =
+                        match Self::get_ws_url(&http, &api_base, &config.app_token).await {
+                            Ok(ws_url) => tokio_tungstenite::connect_async(&ws_url)
+                                .await
+                                .map_err(|e| format!("WebSocket connect failed: {}", e)),
+                            Err(e) => Err(format!("no WebSocket URL: {}", e)),
+                        };
+                    let ws_stream = match connected {
                         Ok((stream, _)) => stream,
-                        Err(e) => {
-                            error!(error = %e, "Failed to connect Slack WebSocket");
-                            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                            continue;
+                        Err(reason) => {
+                            error!(error = %reason, "Failed to connect Slack Socket Mode");
+                            status.disconnected(reason);
+                            let delay = backoff.next_delay();
+                            tokio::select! {
+                                _ = &mut stop_rx => return,
+                                _ = tokio::time::sleep(delay) => continue,
+                            }
                         }
                     };
 
                     info!("Slack Socket Mode connected");
+                    status.connected();
+                    backoff.reset();
                     let (mut ws_tx, mut ws_rx) = ws_stream.split();
 
-                    loop {
+                    // Any frame from Slack shows the socket is alive; a ping
+                    // that gets nothing back by the next tick means it is not
+                    let mut health = tokio::time::interval(HEALTH_PING_INTERVAL);
+                    health.tick().await;
+                    let mut awaiting_pong = false;
+
+                    let reason = loop {
                         tokio::select! {
                             _ = &mut stop_rx => {
                                 info!("Slack adapter received shutdown signal");
                                 return;
                             }
+                            _ = health.tick() => {
+                                if awaiting_pong {
+                                    break "no reply to health ping".to_string();
+                                }
+                                if ws_tx.send(WsMessage::Ping(Vec::new().into())).await.is_err() {
+                                    break "health ping failed".to_string();
+                                }
+                                awaiting_pong = true;
+                            }
                             msg = ws_rx.next() => {
+                                awaiting_pong = false;
                                 let msg = match msg {
                                     Some(Ok(m)) => m,
-                                    Some(Err(e)) => {
-                                        warn!(error = %e, "Slack WebSocket error, reconnecting");
-                                        break; // Reconnect
-                                    }
-                                    None => {
-                                        warn!("Slack WebSocket closed, reconnecting");
-                                        break; // Reconnect
-                                    }
+                                    Some(Err(e)) => break format!("WebSocket error: {}", e),
+                                    None => break "WebSocket closed".to_string(),
                                 };
 
                                 let text = match msg {
@@ -281,10 +386,7 @@ impl ChannelAdapter for SlackAdapter {
                                         let _ = ws_tx.send(WsMessage::Pong(data)).await;
                                         continue;
                                     }
-                                    WsMessage::Close(_) => {
-                                        warn!("Slack WebSocket close frame, reconnecting");
-                                        break;
-                                    }
+                                    WsMessage::Close(_) => break "close frame".to_string(),
                                     _ => continue,
                                 };
 
@@ -319,9 +421,12 @@ impl ChannelAdapter for SlackAdapter {
                                             continue;
                                         }
 
-                                        // Skip bot messages and subtypes (edits, joins, etc.)
+                                        // Skip bot messages and subtypes (edits, joins, etc.),
+                                        // but not messages with files
                                         if event.get("bot_id").is_some()
-                                            || event.get("subtype").is_some()
+                                            || event["subtype"]
+                                                .as_str()
+                                                .is_some_and(|s| s != "file_share")
                                         {
                                             continue;
                                         }
@@ -338,8 +443,9 @@ impl ChannelAdapter for SlackAdapter {
                                             .as_str()
                                             .unwrap_or("")
                                             .to_string();
+                                        let attachments = files(event);
 
-                                        if msg_text.is_empty() {
+                                        if msg_text.is_empty() && attachments.is_empty() {
                                             continue;
                                         }
 
@@ -367,6 +473,12 @@ impl ChannelAdapter for SlackAdapter {
                                         let session_id =
                                             session_mgr.get_or_create(&key, "slack");
 
+                                        // Message ts is unique per channel
+                                        let id = match event["ts"].as_str() {
+                                            Some(ts) => format!("{}:{}", channel_id, ts),
+                                            None => uuid::Uuid::new_v4().to_string(),
+                                        };
+
                                         // Map session -> channel for response routing
                                         channel_map
                                             .lock()
@@ -374,12 +486,13 @@ impl ChannelAdapter for SlackAdapter {
                                             .insert(session_id.0.clone(), channel_id);
 
                                         let msg_envelope = MessageEnvelope {
-                                            id: uuid::Uuid::new_v4().to_string(),
+                                            id,
                                             session_id,
                                             session_key: key.clone(),
                                             channel: "slack".into(),
                                             sender: user_id,
                                             text: msg_text,
+                                            attachments,
                                             timestamp: chrono::Utc::now(),
                                         };
 
@@ -401,6 +514,30 @@ impl ChannelAdapter for SlackAdapter {
                                                             Some((a, id)) if a == "approve" || a == "deny" => (a, id),
                                                             _ => continue,
                                                         };
+                                                        // A destructive command is only approved
+                                                        // by typing the word, not by a button
+                                                        if act == "approve" {
+                                                            if let Some(word) = broker.confirm_word(request_id).await {
+                                                                let short_id = &request_id[..8.min(request_id.len())];
+                                                                let body = serde_json::json!({
+                                                                    "channel": payload["channel"]["id"],
+                                                                    "user": payload["user"]["id"],
+                                                                    "text": format!("Send /approve {} {} to run this", short_id, word),
+                                                                });
+                                                                if let Err(e) = Self::api_call(
+                                                                    &http,
+                                                                    &api_base,
+                                                                    &config.bot_token,
+                                                                    "chat.postEphemeral",
+                                                                    body,
+                                                                )
+                                                                .await
+                                                                {
+                                                                    warn!(error = %e, "Failed to post the confirmation hint");
+                                                                }
+                                                                continue;
+                                                            }
+                                                        }
                                                         let decision = if act == "approve" {
                                                             ApprovalDecision::Approved
                                                         } else {
@@ -422,7 +559,7 @@ impl ChannelAdapter for SlackAdapter {
                                     }
                                     "disconnect" => {
                                         info!("Slack requested disconnect, reconnecting");
-                                        break;
+                                        break "Slack requested a reconnect".to_string();
                                     }
                                     _ => {
                                         debug!(envelope_type, "Unhandled Slack envelope type");
@@ -430,10 +567,15 @@ impl ChannelAdapter for SlackAdapter {
                                 }
                             }
                         }
-                    }
+                    };
 
-                    // Brief delay before reconnect
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    warn!(reason = %reason, "Slack WebSocket dropped, reconnecting");
+                    status.disconnected(reason);
+                    let delay = backoff.next_delay();
+                    tokio::select! {
+                        _ = &mut stop_rx => return,
+                        _ = tokio::time::sleep(delay) => {}
+                    }
                 }
             });
 
@@ -446,7 +588,12 @@ impl ChannelAdapter for SlackAdapter {
         let content = content.clone();
         let channel_map = self.channel_map.clone();
         let http = self.http.clone();
+        let api_base = self.api_base.clone();
         let bot_token = self.config.bot_token.clone();
+        let blocks = match &content {
+            MessageContent::Text(t) => self.rich_blocks(t),
+            MessageContent::Streaming { .. } => None,
+        };
 
         Box::pin(async move {
             let text = match &content {
@@ -468,19 +615,91 @@ impl ChannelAdapter for SlackAdapter {
                 message: format!("No channel mapped for session {}", session_key),
             })?;
 
-            let chunks = split_message(&text, SLACK_MAX_LEN);
+            if let Some(blocks) = blocks {
+                Self::post_blocks(&http, &api_base, &bot_token, &channel_id, &text, blocks).await?;
+                return Ok(());
+            }
+
+            let chunks = split_message(&rich::to_markdown(&text), SLACK_MAX_LEN);
             for chunk in chunks {
-                Self::post_message(&http, &bot_token, &channel_id, &chunk).await?;
+                Self::post_message(&http, &api_base, &bot_token, &channel_id, &chunk).await?;
             }
 
             Ok(())
         })
     }
 
+    fn send_editable(
+        &self,
+        session: &SessionId,
+        content: &MessageContent,
+    ) -> BoxFuture<'_, Result<Option<String>>> {
+        let session_key = session.0.clone();
+        let text = match content {
+            MessageContent::Text(t) => t.clone(),
+            MessageContent::Streaming { delta, .. } => delta.clone(),
+        };
+
+        Box::pin(async move {
+            let channel_id = self.channel_for(&session_key).await?;
+            let first = split_message(&text, SLACK_MAX_LEN)
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            let ts = Self::post_message(
+                &self.http,
+                &self.api_base,
+                &self.config.bot_token,
+                &channel_id,
+                &first,
+            )
+            .await?;
+            Ok(Some(ts))
+        })
+    }
+
+    fn edit(
+        &self,
+        session: &SessionId,
+        message_id: &str,
+        content: &MessageContent,
+    ) -> BoxFuture<'_, Result<()>> {
+        let session_key = session.0.clone();
+        let ts = message_id.to_string();
+        let text = match content {
+            MessageContent::Text(t) => t.clone(),
+            MessageContent::Streaming { delta, .. } => delta.clone(),
+        };
+
+        Box::pin(async move {
+            let channel_id = self.channel_for(&session_key).await?;
+            if let Some(blocks) = self.rich_blocks(&text) {
+                return self.update_blocks(&channel_id, &ts, &text, blocks).await;
+            }
+            let token = &self.config.bot_token;
+            // Overflow beyond one message is posted after the edited one
+            let text = rich::to_markdown(&text);
+            let mut chunks = split_message(&text, SLACK_MAX_LEN).into_iter();
+            if let Some(first) = chunks.next() {
+                Self::update_message(&self.http, &self.api_base, token, &channel_id, &ts, &first)
+                    .await?;
+            }
+            for chunk in chunks {
+                Self::post_message(&self.http, &self.api_base, token, &channel_id, &chunk).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn renders_structured_results(&self) -> bool {
+        self.config.rich_formatting
+    }
+
     fn broadcast(&self, content: &MessageContent) -> BoxFuture<'_, Result<()>> {
         let content = content.clone();
         let channel_map = self.channel_map.clone();
         let http = self.http.clone();
+        let api_base = self.api_base.clone();
         let bot_token = self.config.bot_token.clone();
 
         Box::pin(async move {
@@ -500,7 +719,8 @@ impl ChannelAdapter for SlackAdapter {
             let chunks = split_message(&text, SLACK_MAX_LEN);
             for channel_id in channels {
                 for chunk in &chunks {
-                    if let Err(e) = Self::post_message(&http, &bot_token, &channel_id, chunk).await
+                    if let Err(e) =
+                        Self::post_message(&http, &api_base, &bot_token, &channel_id, chunk).await
                     {
                         warn!(channel = %channel_id, error = %e, "Failed to broadcast to Slack channel");
                     }
@@ -522,3 +742,349 @@ impl ChannelAdapter for SlackAdapter {
         })
     }
 }
+
+/// Files shared with a message event, by their private URL.
+fn files(event: &serde_json::Value) -> Vec<Attachment> {
+    event["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|file| {
+            Some(Attachment {
+                kind: file["mimetype"]
+                    .as_str()
+                    .map_or(AttachmentKind::File, AttachmentKind::from_mime),
+                reference: file["url_private"].as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Block Kit blocks for an approval prompt: the summary, the redacted
+/// arguments when the policy attaches them, and Approve/Deny buttons.
+fn approval_blocks(request: &ApprovalRequest) -> serde_json::Value {
+    let mut blocks = vec![serde_json::json!({
+        "type": "section",
+        "text": {
+            "type": "mrkdwn",
+            "text": format!(
+                ":lock: *Approval Required*\n\nTool: `{}`\nTier: {}\nAction: _{}_",
+                request.tool_name, request.tier, request.input_summary
+            )
+        }
+    })];
+    if let Some(ref detail) = request.input_detail {
+        let escaped = escape_mrkdwn(detail);
+        blocks.push(serde_json::json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("```{}```", escaped) }
+        }));
+    }
+    blocks.push(serde_json::json!({
+        "type": "actions",
+        "elements": [
+            {
+                "type": "button",
+                "text": { "type": "plain_text", "text": "Approve" },
+                "style": "primary",
+                "action_id": format!("approve:{}", request.id)
+            },
+            {
+                "type": "button",
+                "text": { "type": "plain_text", "text": "Deny" },
+                "style": "danger",
+                "action_id": format!("deny:{}", request.id)
+            }
+        ]
+    }));
+    serde_json::Value::Array(blocks)
+}
+
+/// Block Kit sections for a reply holding structured results: prose as
+/// mrkdwn sections, each result as a header, field sections and a table in
+/// a code block. `None` when there are no results or the reply needs more
+/// blocks than Slack allows, so the caller falls back to markdown.
+fn result_blocks(text: &str) -> Option<Vec<serde_json::Value>> {
+    let segments = rich::segments(text);
+    if !segments.iter().any(|s| matches!(s, Segment::Result(_))) {
+        return None;
+    }
+    let mut blocks = Vec::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(prose) => {
+                for chunk in split_message(&prose, SLACK_SECTION_MAX_LEN) {
+                    blocks.push(mrkdwn_section(chunk));
+                }
+            }
+            Segment::Result(result) => blocks.extend(structured_blocks(&result)),
+        }
+    }
+    (blocks.len() <= SLACK_MAX_BLOCKS).then_some(blocks)
+}
+
+fn structured_blocks(result: &StructuredResult) -> Vec<serde_json::Value> {
+    let mut blocks = Vec::new();
+    if let Some(title) = &result.title {
+        // Header text is plain_text and capped at 150 characters
+        let title: String = title.chars().take(150).collect();
+        blocks.push(serde_json::json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": title }
+        }));
+    }
+    // A section holds at most ten fields
+    for fields in result.fields.chunks(10) {
+        let fields: Vec<serde_json::Value> = fields
+            .iter()
+            .map(|(name, value)| {
+                serde_json::json!({
+                    "type": "mrkdwn",
+                    "text": format!("*{}*\n{}", escape_mrkdwn(name), escape_mrkdwn(value)),
+                })
+            })
+            .collect();
+        blocks.push(serde_json::json!({ "type": "section", "fields": fields }));
+    }
+    if let Some(table) = result.table_text() {
+        for chunk in split_message(&escape_mrkdwn(&table), SLACK_SECTION_MAX_LEN - 8) {
+            blocks.push(mrkdwn_section(format!("```\n{}\n```", chunk)));
+        }
+    }
+    blocks
+}
+
+fn mrkdwn_section(text: String) -> serde_json::Value {
+    serde_json::json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": text }
+    })
+}
+
+/// mrkdwn only needs these three escaped, even inside code blocks.
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The notification text sent alongside blocks, cut to one message.
+fn fallback_text(text: &str) -> String {
+    split_message(&rich::to_markdown(text), SLACK_MAX_LEN)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Fake Slack Web API: answers every call with `ok` and a fresh `ts`,
+    /// recording `(method, json body)` pairs.
+    async fn fake_slack() -> (
+        String,
+        Arc<std::sync::Mutex<Vec<(String, serde_json::Value)>>>,
+    ) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/api", listener.local_addr().unwrap());
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    let body_start = loop {
+                        let n = sock.read(&mut chunk).await.unwrap();
+                        buf.extend_from_slice(&chunk[..n]);
+                        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break pos + 4;
+                        }
+                    };
+                    let head = String::from_utf8_lossy(&buf[..body_start]).to_string();
+                    let len: usize = head
+                        .to_lowercase()
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length: "))
+                        .map(|v| v.trim().parse().unwrap())
+                        .unwrap_or(0);
+                    while buf.len() < body_start + len {
+                        let n = sock.read(&mut chunk).await.unwrap();
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+                    let method = head
+                        .split_whitespace()
+                        .nth(1)
+                        .and_then(|path| path.rsplit('/').next())
+                        .unwrap_or_default()
+                        .to_string();
+                    let body = serde_json::from_slice(&buf[body_start..body_start + len]).unwrap();
+                    let ts = {
+                        let mut calls = recorded.lock().unwrap();
+                        calls.push((method, body));
+                        format!("1700000000.{:06}", calls.len())
+                    };
+                    let reply = serde_json::json!({"ok": true, "ts": ts}).to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        reply.len(),
+                        reply
+                    );
+                    sock.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        (base, calls)
+    }
+
+    async fn adapter_for(base: String) -> (SlackAdapter, SessionId) {
+        let mut adapter = SlackAdapter::new(
+            SlackConfig {
+                bot_token: "xoxb-test".into(),
+                app_token: "xapp-test".into(),
+                dm_policy: DmPolicy::default(),
+                allowed_users: vec![],
+                rich_formatting: false,
+            },
+            Arc::new(SessionManager::new()),
+        );
+        adapter.api_base = base;
+        let session = SessionId::from_string("slack-session");
+        adapter
+            .channel_map
+            .lock()
+            .await
+            .insert(session.0.clone(), "C123".into());
+        (adapter, session)
+    }
+
+    #[tokio::test]
+    async fn streaming_reply_posts_then_updates() {
+        let (base, calls) = fake_slack().await;
+        let (adapter, session) = adapter_for(base).await;
+
+        let ts = adapter
+            .send_editable(&session, &MessageContent::Text("…".into()))
+            .await
+            .unwrap()
+            .unwrap();
+        for text in ["Hello", "Hello, world"] {
+            adapter
+                .edit(&session, &ts, &MessageContent::Text(text.into()))
+                .await
+                .unwrap();
+        }
+
+        let calls = calls.lock().unwrap().clone();
+        let methods: Vec<_> = calls.iter().map(|(m, _)| m.as_str()).collect();
+        assert_eq!(
+            methods,
+            vec!["chat.postMessage", "chat.update", "chat.update"]
+        );
+        assert_eq!(calls[0].1["channel"], "C123");
+        assert_eq!(calls[2].1["ts"], ts.as_str());
+        assert_eq!(calls[2].1["text"], "Hello, world");
+    }
+
+    #[tokio::test]
+    async fn final_edit_overflow_is_posted_separately() {
+        let (base, calls) = fake_slack().await;
+        let (adapter, session) = adapter_for(base).await;
+
+        let long = "word ".repeat(1000);
+        adapter
+            .edit(&session, "1.1", &MessageContent::Text(long))
+            .await
+            .unwrap();
+
+        let methods: Vec<_> = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(m, _)| m.clone())
+            .collect();
+        assert_eq!(methods, vec!["chat.update", "chat.postMessage"]);
+    }
+
+    const RESULT_REPLY: &str = "Usage:\n```ryvos-result\n{\"title\": \"Disk\", \"fields\": [{\"name\": \"Host\", \"value\": \"web<1>\"}], \"columns\": [\"Mount\", \"Used\"], \"rows\": [[\"/\", \"71%\"]]}\n```";
+
+    #[test]
+    fn structured_result_becomes_block_kit() {
+        let blocks = result_blocks(RESULT_REPLY).unwrap();
+        assert_eq!(
+            serde_json::Value::Array(blocks),
+            serde_json::json!([
+                { "type": "section", "text": { "type": "mrkdwn", "text": "Usage:" } },
+                { "type": "header", "text": { "type": "plain_text", "text": "Disk" } },
+                {
+                    "type": "section",
+                    "fields": [{ "type": "mrkdwn", "text": "*Host*\nweb&lt;1&gt;" }]
+                },
+                {
+                    "type": "section",
+                    "text": {
+                        "type": "mrkdwn",
+                        "text": "```\nMount  Used\n-----  ----\n/      71%\n```"
+                    }
+                }
+            ])
+        );
+        assert!(result_blocks("no results here").is_none());
+    }
+
+    #[tokio::test]
+    async fn structured_results_are_markdown_unless_enabled() {
+        let (base, calls) = fake_slack().await;
+        let (mut adapter, session) = adapter_for(base).await;
+        let content = MessageContent::Text(RESULT_REPLY.into());
+
+        adapter.send(&session, &content).await.unwrap();
+        adapter.config.rich_formatting = true;
+        adapter.send(&session, &content).await.unwrap();
+
+        let calls = calls.lock().unwrap().clone();
+        assert!(calls[0].1.get("blocks").is_none());
+        assert!(calls[0].1["text"]
+            .as_str()
+            .unwrap()
+            .contains("**Host:** web<1>"));
+        assert_eq!(calls[1].1["blocks"].as_array().unwrap().len(), 4);
+        assert_eq!(calls[1].1["text"], calls[0].1["text"]);
+    }
+
+    #[tokio::test]
+    async fn approval_shows_redacted_arguments() {
+        let (base, calls) = fake_slack().await;
+        let (adapter, session) = adapter_for(base).await;
+        let mut request = ApprovalRequest {
+            id: "req-1".into(),
+            tool_name: "bash".into(),
+            tier: ryvos_core::security::SecurityTier::T3,
+            input_summary: "curl <host>".into(),
+            session_id: session.0.clone(),
+            timestamp: chrono::Utc::now(),
+            input_detail: None,
+            confirm_word: None,
+            calls: vec![],
+        };
+
+        assert!(adapter.send_approval(&session, &request).await.unwrap());
+        request.input_detail = Some("{\n  \"token\": \"***\",\n  \"url\": \"a<b\"\n}".into());
+        assert!(adapter.send_approval(&session, &request).await.unwrap());
+
+        let calls = calls.lock().unwrap().clone();
+        let plain = calls[0].1["blocks"].as_array().unwrap();
+        assert_eq!(plain.len(), 2);
+        let detailed = calls[1].1["blocks"].as_array().unwrap();
+        assert_eq!(detailed.len(), 3);
+        let detail = detailed[1]["text"]["text"].as_str().unwrap();
+        assert!(detail.starts_with("```{"));
+        assert!(detail.contains("\"token\": \"***\""));
+        assert!(detail.contains("a&lt;b"));
+        assert_eq!(detailed[2]["elements"][0]["action_id"], "approve:req-1");
+    }
+}
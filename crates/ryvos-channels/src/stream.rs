@@ -0,0 +1,240 @@
+//! Live replies for platforms that can edit messages.
+//!
+//! A [`StreamingReply`] posts a placeholder as soon as a run starts, then
+//! edits it with the accumulated text as `TextDelta`s arrive, no more often
+//! than the configured interval so edit rate limits are respected.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use ryvos_core::error::Result;
+use ryvos_core::traits::ChannelAdapter;
+use ryvos_core::types::{MessageContent, SessionId};
+
+use crate::util::split_message;
+
+/// Text shown until the first delta arrives.
+const PLACEHOLDER: &str = "…";
+
+/// Interim edits show at most this many characters, well under every
+/// platform's message limit. The final edit carries the full text.
+const PREVIEW_MAX_CHARS: usize = 3000;
+
+/// A reply message that is edited in place while the agent streams.
+pub(crate) struct StreamingReply {
+    adapter: Arc<dyn ChannelAdapter>,
+    session: SessionId,
+    message_id: String,
+    interval: Duration,
+    last_edit: Instant,
+    shown: String,
+}
+
+impl StreamingReply {
+    /// Post the placeholder. Returns `None` when the adapter cannot edit
+    /// messages (or the placeholder failed), in which case the caller sends
+    /// the reply once at the end.
+    pub(crate) async fn start(
+        adapter: Arc<dyn ChannelAdapter>,
+        session: SessionId,
+        interval: Duration,
+    ) -> Option<Self> {
+        let placeholder = MessageContent::Text(PLACEHOLDER.to_string());
+        let message_id = match adapter.send_editable(&session, &placeholder).await {
+            Ok(Some(id)) => id,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!(channel = %adapter.name(), error = %e, "Failed to post reply placeholder");
+                return None;
+            }
+        };
+        Some(Self {
+            adapter,
+            session,
+            message_id,
+            interval,
+            last_edit: Instant::now(),
+            shown: PLACEHOLDER.to_string(),
+        })
+    }
+
+    /// Show `text` so far, unless the last edit was too recent.
+    pub(crate) async fn update(&mut self, text: &str) {
+        if self.last_edit.elapsed() < self.interval {
+            return;
+        }
+        let preview = preview(text);
+        if preview.is_empty() || preview == self.shown {
+            return;
+        }
+        self.last_edit = Instant::now();
+        match self.edit(&preview).await {
+            Ok(()) => self.shown = preview,
+            // A rate-limited edit is retried with newer text on a later delta
+            Err(e) => debug!(error = %e, "Skipped streaming edit"),
+        }
+    }
+
+    /// Replace the message with the complete reply. Text past the first
+    /// `PREVIEW_MAX_CHARS` goes in follow-up messages, so an edit never
+    /// overflows the platform limit. If the edit fails the reply is sent as
+    /// a new message so it is never lost; text already posted is not resent.
+    pub(crate) async fn finish(self, text: &str) -> Result<()> {
+        let text = if text.is_empty() {
+            "(no response)"
+        } else {
+            text
+        };
+        if text == self.shown {
+            return Ok(());
+        }
+        let first = split_message(text, PREVIEW_MAX_CHARS)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if first != self.shown {
+            if let Err(e) = self.edit(&first).await {
+                warn!(error = %e, "Final reply edit failed, sending as a new message");
+                let content = MessageContent::Text(text.to_string());
+                return self.adapter.send(&self.session, &content).await;
+            }
+        }
+        let rest = text[first.len()..].trim_start();
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let content = MessageContent::Text(rest.to_string());
+        self.adapter.send(&self.session, &content).await
+    }
+
+    async fn edit(&self, text: &str) -> Result<()> {
+        let content = MessageContent::Text(text.to_string());
+        self.adapter
+            .edit(&self.session, &self.message_id, &content)
+            .await
+    }
+}
+
+/// `text` cut to `PREVIEW_MAX_CHARS`, marked as truncated.
+fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_MAX_CHARS) {
+        Some((cut, _)) => format!("{} …", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use ryvos_core::types::MessageEnvelope;
+    use tokio::sync::mpsc;
+
+    /// Adapter recording sends and edits; edits fail when `fail_edits`.
+    #[derive(Default)]
+    struct EditAdapter {
+        fail_edits: bool,
+        calls: std::sync::Mutex<Vec<(&'static str, String)>>,
+    }
+
+    impl EditAdapter {
+        fn record(&self, kind: &'static str, content: &MessageContent) {
+            if let MessageContent::Text(text) = content {
+                self.calls.lock().unwrap().push((kind, text.clone()));
+            }
+        }
+    }
+
+    impl ChannelAdapter for EditAdapter {
+        fn name(&self) -> &str {
+            "edit"
+        }
+
+        fn start(&self, _tx: mpsc::Sender<MessageEnvelope>) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn send(
+            &self,
+            _session: &SessionId,
+            content: &MessageContent,
+        ) -> BoxFuture<'_, Result<()>> {
+            self.record("send", content);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn send_editable(
+            &self,
+            _session: &SessionId,
+            content: &MessageContent,
+        ) -> BoxFuture<'_, Result<Option<String>>> {
+            self.record("post", content);
+            Box::pin(async { Ok(Some("msg-1".to_string())) })
+        }
+
+        fn edit(
+            &self,
+            _session: &SessionId,
+            _message_id: &str,
+            content: &MessageContent,
+        ) -> BoxFuture<'_, Result<()>> {
+            self.record("edit", content);
+            let fail = self.fail_edits;
+            Box::pin(async move {
+                if fail {
+                    Err(ryvos_core::error::RyvosError::Channel {
+                        channel: "edit".to_string(),
+                        message: "rejected".to_string(),
+                    })
+                } else {
+                    Ok(())
+                }
+            })
+        }
+
+        fn stop(&self) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    async fn finish_with(adapter: Arc<EditAdapter>, text: &str) -> Vec<(&'static str, String)> {
+        let reply = StreamingReply::start(adapter.clone(), SessionId::new(), Duration::ZERO)
+            .await
+            .unwrap();
+        reply.finish(text).await.unwrap();
+        let calls = adapter.calls.lock().unwrap().clone();
+        calls
+    }
+
+    #[tokio::test]
+    async fn long_reply_overflows_into_follow_ups_once() {
+        let first = "a".repeat(PREVIEW_MAX_CHARS - 10);
+        let text = format!("{} {}", first, "b".repeat(20));
+        let calls = finish_with(Arc::new(EditAdapter::default()), &text).await;
+        assert_eq!(calls[1..], [("edit", first), ("send", "b".repeat(20))]);
+    }
+
+    #[tokio::test]
+    async fn failed_final_edit_sends_reply_once() {
+        let adapter = Arc::new(EditAdapter {
+            fail_edits: true,
+            ..Default::default()
+        });
+        let text = format!("{} {}", "a".repeat(PREVIEW_MAX_CHARS), "b".repeat(20));
+        let calls = finish_with(adapter, &text).await;
+        let sent: Vec<_> = calls.iter().filter(|(kind, _)| *kind == "send").collect();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1, text);
+    }
+
+    #[test]
+    fn preview_truncates_on_char_boundary() {
+        assert_eq!(preview("short"), "short");
+        let long = "é".repeat(PREVIEW_MAX_CHARS + 5);
+        let shown = preview(&long);
+        assert!(shown.ends_with(" …"));
+        assert_eq!(shown.chars().count(), PREVIEW_MAX_CHARS + 2);
+    }
+}
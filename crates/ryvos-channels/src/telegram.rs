@@ -8,15 +8,16 @@ use tracing::{debug, error, info, warn};
 use ryvos_agent::{ApprovalBroker, SessionManager};
 use ryvos_core::config::{DmPolicy, TelegramConfig};
 use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::event::EventBus;
 use ryvos_core::security::{ApprovalDecision, ApprovalRequest};
 use ryvos_core::traits::ChannelAdapter;
-use ryvos_core::types::{MessageContent, MessageEnvelope, SessionId};
+use ryvos_core::types::{Attachment, AttachmentKind, MessageContent, MessageEnvelope, SessionId};
 
 use teloxide::prelude::*;
 use teloxide::respond;
-use teloxide::types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup};
+use teloxide::types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup, MessageId};
 
-use crate::util::split_message;
+use crate::util::{split_message, Backoff, ConnectionReporter, HEALTH_PING_INTERVAL};
 
 const TELEGRAM_MAX_LEN: usize = 4096;
 
@@ -32,6 +33,7 @@ pub struct TelegramAdapter {
     shutdown_tx: Arc<Mutex<Option<tokio::sync::oneshot::Sender<()>>>>,
     /// Approval broker for HITL.
     broker: Arc<Mutex<Option<Arc<ApprovalBroker>>>>,
+    status: ConnectionReporter,
 }
 
 impl TelegramAdapter {
@@ -43,6 +45,7 @@ impl TelegramAdapter {
             bot: Arc::new(Mutex::new(None)),
             shutdown_tx: Arc::new(Mutex::new(None)),
             broker: Arc::new(Mutex::new(None)),
+            status: ConnectionReporter::new("telegram", None),
         }
     }
 
@@ -51,6 +54,32 @@ impl TelegramAdapter {
         // Use blocking-safe approach: store directly since we're called before start()
         self.broker = Arc::new(Mutex::new(Some(broker)));
     }
+
+    /// Publish connection changes on the event bus.
+    pub fn set_event_bus(&mut self, event_bus: Arc<EventBus>) {
+        self.status = ConnectionReporter::new("telegram", Some(event_bus));
+    }
+}
+
+/// Check the Bot API with `getMe` every [`HEALTH_PING_INTERVAL`], and with
+/// backoff while it is unreachable. Long polling retries on its own; this
+/// only reports whether it can currently succeed.
+async fn health_ping(bot: Bot, status: ConnectionReporter) {
+    let mut backoff = Backoff::default();
+    loop {
+        let delay = match bot.get_me().await {
+            Ok(_) => {
+                status.connected();
+                backoff.reset();
+                HEALTH_PING_INTERVAL
+            }
+            Err(e) => {
+                status.disconnected(e.to_string());
+                backoff.next_delay()
+            }
+        };
+        tokio::time::sleep(delay).await;
+    }
 }
 
 impl ChannelAdapter for TelegramAdapter {
@@ -67,6 +96,7 @@ impl ChannelAdapter for TelegramAdapter {
         let bot_arc = self.bot.clone();
         let shutdown_tx_arc = self.shutdown_tx.clone();
         let broker_arc = self.broker.clone();
+        let status = self.status.clone();
 
         Box::pin(async move {
             // Validate bot token by calling get_me
@@ -135,8 +165,10 @@ impl ChannelAdapter for TelegramAdapter {
                                 DmPolicy::Open => {}
                             }
 
-                            let text = msg.text().unwrap_or("").to_string();
-                            if text.is_empty() {
+                            // A file's caption stands in for the text
+                            let text = msg.text().or(msg.caption()).unwrap_or("").to_string();
+                            let attachments = attachments(&msg);
+                            if text.is_empty() && attachments.is_empty() {
                                 return respond(());
                             }
 
@@ -147,12 +179,13 @@ impl ChannelAdapter for TelegramAdapter {
                             cm.lock().await.insert(session_id.0.clone(), msg.chat.id);
 
                             let envelope = MessageEnvelope {
-                                id: uuid::Uuid::new_v4().to_string(),
+                                id: format!("{}:{}", msg.chat.id.0, msg.id.0),
                                 session_id,
                                 session_key: key.clone(),
                                 channel: "telegram".into(),
                                 sender: user.id.0.to_string(),
                                 text,
+                                attachments,
                                 timestamp: chrono::Utc::now(),
                             };
 
@@ -192,6 +225,20 @@ impl ChannelAdapter for TelegramAdapter {
                             };
                             drop(broker_guard);
 
+                            if action == "approve" {
+                                if let Some(word) = broker.confirm_word(request_id).await {
+                                    let short_id = &request_id[..8.min(request_id.len())];
+                                    let text =
+                                        format!("Send /approve {} {} to run this", short_id, word);
+                                    if let Err(e) =
+                                        bot.answer_callback_query(&cq.id).text(text).await
+                                    {
+                                        warn!(error = %e, "Failed to answer callback query");
+                                    }
+                                    return respond(());
+                                }
+                            }
+
                             let decision = if action == "approve" {
                                 ApprovalDecision::Approved
                             } else {
@@ -244,6 +291,7 @@ impl ChannelAdapter for TelegramAdapter {
                     .branch(message_handler)
                     .branch(callback_handler);
 
+                let health = health_ping(bot.clone(), status);
                 let mut dispatcher =
                     teloxide::dispatching::Dispatcher::builder(bot, handler).build();
 
@@ -252,6 +300,7 @@ impl ChannelAdapter for TelegramAdapter {
                     _ = dispatcher.dispatch() => {
                         info!("Telegram dispatcher exited");
                     }
+                    _ = health => {}
                     _ = &mut stop_rx => {
                         info!("Telegram adapter received shutdown signal");
                         if let Ok(f) = dispatcher.shutdown_token().shutdown() {
@@ -274,9 +323,8 @@ impl ChannelAdapter for TelegramAdapter {
         let chat_map = self.chat_map.clone();
         let bot_arc = self.bot.clone();
         let request_id = request.id.clone();
-        let tool_name = request.tool_name.clone();
-        let tier = request.tier;
-        let input_summary = request.input_summary.clone();
+        let text = approval_markdown(request);
+        let plain = approval_plain(request);
 
         Box::pin(async move {
             let chat_id = {
@@ -295,11 +343,6 @@ impl ChannelAdapter for TelegramAdapter {
                 None => return Ok(false),
             };
 
-            let text = format!(
-                "🔐 *Approval Required*\n\nTool: `{}`\nTier: {}\nAction: _{}_",
-                tool_name, tier, input_summary
-            );
-
             let keyboard = InlineKeyboardMarkup::new(vec![vec![
                 InlineKeyboardButton::callback("✅ Approve", format!("approve:{}", request_id)),
                 InlineKeyboardButton::callback("❌ Deny", format!("deny:{}", request_id)),
@@ -314,8 +357,6 @@ impl ChannelAdapter for TelegramAdapter {
                 Ok(_) => Ok(true),
                 Err(e) => {
                     // Fall back — try without markdown in case of parse errors
-                    let plain =
-                        format!("[APPROVAL] {} ({}): \"{}\"", tool_name, tier, input_summary);
                     let keyboard2 = InlineKeyboardMarkup::new(vec![vec![
                         InlineKeyboardButton::callback(
                             "Approve",
@@ -342,6 +383,7 @@ impl ChannelAdapter for TelegramAdapter {
     fn broadcast(&self, content: &MessageContent) -> BoxFuture<'_, Result<()>> {
         let content = content.clone();
         let bot_arc = self.bot.clone();
+        let bot_token = self.config.bot_token.clone();
         let allowed_users = self.config.allowed_users.clone();
 
         Box::pin(async move {
@@ -353,11 +395,12 @@ impl ChannelAdapter for TelegramAdapter {
                 return Ok(());
             }
 
-            let bot_guard = bot_arc.lock().await;
-            let bot = bot_guard.as_ref().ok_or_else(|| RyvosError::Channel {
-                channel: "telegram".into(),
-                message: "Bot not started".into(),
-            })?;
+            // Allowed users are known up front, so a broadcast needs no
+            // running bot (e.g. `ryvos heartbeat run`)
+            let bot = match bot_arc.lock().await.as_ref() {
+                Some(bot) => bot.clone(),
+                None => Bot::new(&bot_token),
+            };
 
             let chunks = split_message(&text, TELEGRAM_MAX_LEN);
             for user_id in &allowed_users {
@@ -418,6 +461,65 @@ impl ChannelAdapter for TelegramAdapter {
         })
     }
 
+    fn send_editable(
+        &self,
+        session: &SessionId,
+        content: &MessageContent,
+    ) -> BoxFuture<'_, Result<Option<String>>> {
+        let session_key = session.0.clone();
+        let text = content_text(content);
+        let chat_map = self.chat_map.clone();
+        let bot_arc = self.bot.clone();
+
+        Box::pin(async move {
+            let (bot, chat_id) = bot_and_chat(&bot_arc, &chat_map, &session_key).await?;
+            let first = split_message(&text, TELEGRAM_MAX_LEN)
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            let sent = bot
+                .send_message(chat_id, &first)
+                .await
+                .map_err(channel_error)?;
+            Ok(Some(sent.id.0.to_string()))
+        })
+    }
+
+    fn edit(
+        &self,
+        session: &SessionId,
+        message_id: &str,
+        content: &MessageContent,
+    ) -> BoxFuture<'_, Result<()>> {
+        let session_key = session.0.clone();
+        let message_id = message_id.to_string();
+        let text = content_text(content);
+        let chat_map = self.chat_map.clone();
+        let bot_arc = self.bot.clone();
+
+        Box::pin(async move {
+            let id = message_id
+                .parse::<i32>()
+                .map(MessageId)
+                .map_err(|_| channel_error(format!("Invalid message id {}", message_id)))?;
+            let (bot, chat_id) = bot_and_chat(&bot_arc, &chat_map, &session_key).await?;
+
+            // editMessageText rejects over-long text; overflow goes in new messages
+            let mut chunks = split_message(&text, TELEGRAM_MAX_LEN).into_iter();
+            if let Some(first) = chunks.next() {
+                bot.edit_message_text(chat_id, id, first)
+                    .await
+                    .map_err(channel_error)?;
+            }
+            for chunk in chunks {
+                bot.send_message(chat_id, &chunk)
+                    .await
+                    .map_err(channel_error)?;
+            }
+            Ok(())
+        })
+    }
+
     fn stop(&self) -> BoxFuture<'_, Result<()>> {
         let shutdown_tx = self.shutdown_tx.clone();
 
@@ -430,3 +532,145 @@ impl ChannelAdapter for TelegramAdapter {
         })
     }
 }
+
+/// Files sent with `msg`, by Telegram file ID. Of a photo's sizes, only
+/// the largest is kept.
+fn attachments(msg: &Message) -> Vec<Attachment> {
+    let mut files = Vec::new();
+    let mut add = |kind, reference: &str| {
+        files.push(Attachment {
+            kind,
+            reference: reference.to_string(),
+        })
+    };
+    if let Some(size) = msg.photo().and_then(|sizes| sizes.last()) {
+        add(AttachmentKind::Image, &size.file.id);
+    }
+    if let Some(doc) = msg.document() {
+        let kind = doc.mime_type.as_ref().map_or(AttachmentKind::File, |mime| {
+            AttachmentKind::from_mime(mime.essence_str())
+        });
+        add(kind, &doc.file.id);
+    }
+    if let Some(audio) = msg.audio() {
+        add(AttachmentKind::Audio, &audio.file.id);
+    }
+    if let Some(voice) = msg.voice() {
+        add(AttachmentKind::Audio, &voice.file.id);
+    }
+    if let Some(video) = msg.video() {
+        add(AttachmentKind::Video, &video.file.id);
+    }
+    files
+}
+
+fn content_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::Text(t) => t.clone(),
+        MessageContent::Streaming { delta, .. } => delta.clone(),
+    }
+}
+
+fn channel_error(e: impl std::fmt::Display) -> RyvosError {
+    RyvosError::Channel {
+        channel: "telegram".into(),
+        message: e.to_string(),
+    }
+}
+
+/// The started bot and the chat mapped to `session_key`.
+async fn bot_and_chat(
+    bot: &Mutex<Option<Bot>>,
+    chat_map: &Mutex<HashMap<String, ChatId>>,
+    session_key: &str,
+) -> Result<(Bot, ChatId)> {
+    let chat_id = chat_map
+        .lock()
+        .await
+        .get(session_key)
+        .copied()
+        .ok_or_else(|| channel_error(format!("No chat mapped for session {}", session_key)))?;
+    let bot = bot
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| channel_error("Bot not started"))?;
+    Ok((bot, chat_id))
+}
+
+/// MarkdownV2 approval prompt. Redacted arguments, when attached, go in an
+/// expandable blockquote so long inputs stay collapsed in the chat.
+fn approval_markdown(request: &ApprovalRequest) -> String {
+    let mut text = format!(
+        "🔐 *Approval Required*\n\nTool: `{}`\nTier: {}\nAction: _{}_",
+        escape_markdown(&request.tool_name),
+        request.tier,
+        escape_markdown(&request.input_summary)
+    );
+    if let Some(ref detail) = request.input_detail {
+        let quoted: Vec<String> = detail
+            .lines()
+            .map(|line| format!(">{}", escape_markdown(line)))
+            .collect();
+        text.push_str("\n\n**");
+        text.push_str(&quoted.join("\n"));
+        text.push_str("||");
+    }
+    text
+}
+
+/// Plain-text approval prompt, used when Telegram rejects the markdown.
+fn approval_plain(request: &ApprovalRequest) -> String {
+    let mut text = format!(
+        "[APPROVAL] {} ({}): \"{}\"",
+        request.tool_name, request.tier, request.input_summary
+    );
+    if let Some(ref detail) = request.input_detail {
+        text.push_str("\n\n");
+        text.push_str(detail);
+    }
+    text
+}
+
+/// Escape MarkdownV2 special characters, including the backslash that
+/// teloxide's helper leaves alone.
+fn escape_markdown(text: &str) -> String {
+    teloxide::utils::markdown::escape(&text.replace('\\', "\\\\"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::security::SecurityTier;
+
+    fn request(detail: Option<&str>) -> ApprovalRequest {
+        ApprovalRequest {
+            id: "req-1".into(),
+            tool_name: "write_file".into(),
+            tier: SecurityTier::T2,
+            input_summary: "notes.md".into(),
+            session_id: "telegram:1".into(),
+            timestamp: chrono::Utc::now(),
+            input_detail: detail.map(String::from),
+            confirm_word: None,
+            calls: vec![],
+        }
+    }
+
+    #[test]
+    fn approval_detail_is_an_escaped_expandable_quote() {
+        let text = approval_markdown(&request(Some("{\n  \"path\": \"notes.md\"\n}")));
+        assert!(text.contains("Action: _notes\\.md_"));
+        assert!(text.ends_with("\n\n**>\\{\n>  \"path\": \"notes\\.md\"\n>\\}||"));
+
+        let plain = approval_plain(&request(Some("{}")));
+        assert!(plain.ends_with("\"notes.md\"\n\n{}"));
+    }
+
+    #[test]
+    fn approval_without_detail_has_no_quote() {
+        let text = approval_markdown(&request(None));
+        assert!(!text.contains("**>"));
+        assert!(!approval_plain(&request(None)).contains('\n'));
+    }
+}
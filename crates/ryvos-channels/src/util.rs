@@ -1,64 +1,242 @@
-/// Split a message into chunks that fit within `max_len`.
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{info, warn};
+use unicode_segmentation::UnicodeSegmentation;
+
+use ryvos_core::event::EventBus;
+use ryvos_core::types::AgentEvent;
+
+/// First delay before reconnecting a dropped platform connection.
+pub const RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between reconnect attempts.
+pub const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How often adapters check that their platform connection is alive.
+pub const HEALTH_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reconnect delays: doubling from an initial delay up to a cap, each
+/// jittered by ±20% so adapters that lost the network together do not
+/// retry in lockstep.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(RECONNECT_INITIAL_DELAY, RECONNECT_MAX_DELAY)
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            attempt: 0,
+        }
+    }
+
+    /// The delay before the next attempt. Call once per failed attempt.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay_for(self.attempt, rand::random::<f64>());
+        self.attempt = self.attempt.saturating_add(1);
+        delay
+    }
+
+    /// Start over from the initial delay, after a connection succeeds.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// The delay for `attempt` (0-based) with `jitter` in `[0, 1)`, which
+    /// scales the doubled delay by 0.8x to 1.2x. Never above the cap.
+    fn delay_for(&self, attempt: u32, jitter: f64) -> Duration {
+        let doubled = self
+            .initial
+            .saturating_mul(2u32.saturating_pow(attempt.min(31)))
+            .min(self.max);
+        doubled.mul_f64(0.8 + jitter * 0.4).min(self.max)
+    }
+}
+
+const STATE_UNKNOWN: u8 = 0;
+const STATE_CONNECTED: u8 = 1;
+const STATE_DISCONNECTED: u8 = 2;
+
+/// Reports an adapter's connection state as `ChannelConnected` and
+/// `ChannelDisconnected` events, which the dispatcher logs; without an
+/// event bus it logs them itself. Only changes are reported, so a loop may
+/// report the same state repeatedly.
+#[derive(Clone)]
+pub struct ConnectionReporter {
+    channel: &'static str,
+    event_bus: Option<Arc<EventBus>>,
+    state: Arc<AtomicU8>,
+}
+
+impl ConnectionReporter {
+    /// A reporter that publishes on `event_bus`, or only logs without one.
+    pub fn new(channel: &'static str, event_bus: Option<Arc<EventBus>>) -> Self {
+        Self {
+            channel,
+            event_bus,
+            state: Arc::new(AtomicU8::new(STATE_UNKNOWN)),
+        }
+    }
+
+    pub fn connected(&self) {
+        if self.state.swap(STATE_CONNECTED, Ordering::SeqCst) == STATE_CONNECTED {
+            return;
+        }
+        match self.event_bus {
+            Some(ref bus) => bus.publish(AgentEvent::ChannelConnected {
+                channel: self.channel.to_string(),
+            }),
+            None => info!(channel = self.channel, "Channel connected"),
+        }
+    }
+
+    pub fn disconnected(&self, reason: impl Into<String>) {
+        if self.state.swap(STATE_DISCONNECTED, Ordering::SeqCst) == STATE_DISCONNECTED {
+            return;
+        }
+        let reason = reason.into();
+        match self.event_bus {
+            Some(ref bus) => bus.publish(AgentEvent::ChannelDisconnected {
+                channel: self.channel.to_string(),
+                reason,
+            }),
+            None => warn!(channel = self.channel, reason = %reason, "Channel disconnected"),
+        }
+    }
+}
+
+/// Split a message into chunks that fit within `max_len` bytes.
 ///
-/// Splits on newline boundaries when possible, falling back to
-/// hard splits at `max_len` if a single line exceeds the limit.
+/// Each chunk breaks at the last newline before the limit, else the last
+/// whitespace, else the last word boundary (which keeps CJK text readable),
+/// else the last grapheme boundary. The newline or space at a break is
+/// dropped. Chunks never split a codepoint or, unless a single grapheme is
+/// larger than `max_len`, a grapheme cluster.
 pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
     if text.is_empty() {
         return vec![String::new()];
     }
-    if text.len() <= max_len {
-        return vec![text.to_string()];
-    }
 
     let mut chunks = Vec::new();
-    let mut current = String::new();
-
-    for line in text.split('\n') {
-        // If adding this line (plus newline) would exceed the limit
-        let needed = if current.is_empty() {
-            line.len()
-        } else {
-            current.len() + 1 + line.len()
-        };
-
-        if needed > max_len {
-            // Flush current chunk if non-empty
-            if !current.is_empty() {
-                chunks.push(std::mem::take(&mut current));
-            }
+    let mut rest = text;
+    while rest.len() > max_len {
+        let (at, separator_len) = break_point(rest, max_len);
+        chunks.push(rest[..at].to_string());
+        rest = &rest[at + separator_len..];
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
 
-            // If the line itself exceeds max_len, hard-split it
-            if line.len() > max_len {
-                let mut remaining = line;
-                while remaining.len() > max_len {
-                    chunks.push(remaining[..max_len].to_string());
-                    remaining = &remaining[max_len..];
-                }
-                if !remaining.is_empty() {
-                    current = remaining.to_string();
-                }
-            } else {
-                current = line.to_string();
-            }
-        } else if current.is_empty() {
-            current = line.to_string();
-        } else {
-            current.push('\n');
-            current.push_str(line);
-        }
+    chunks
+}
+
+/// Where to end the next chunk of `text`: the byte offset (never above
+/// `max_len` unless one codepoint is wider than that) and the length of
+/// the separator to drop there.
+fn break_point(text: &str, max_len: usize) -> (usize, usize) {
+    // Last boundary in (0, max_len] from a list of offsets
+    fn last_fit(offsets: impl Iterator<Item = usize>, max_len: usize) -> Option<usize> {
+        offsets
+            .take_while(|&i| i <= max_len)
+            .filter(|&i| i > 0)
+            .last()
     }
 
-    if !current.is_empty() {
-        chunks.push(current);
+    let mut newline = None;
+    let mut space = None;
+    for (i, c) in text.char_indices().take_while(|&(i, _)| i <= max_len) {
+        if i == 0 {
+            continue;
+        }
+        if c == '\n' {
+            newline = Some((i, 1));
+        } else if c.is_whitespace() {
+            space = Some((i, c.len_utf8()));
+        }
+    }
+    if let Some(found) = newline.or(space) {
+        return found;
     }
 
-    chunks
+    let boundary = last_fit(text.split_word_bound_indices().map(|(i, _)| i), max_len)
+        .or_else(|| last_fit(text.grapheme_indices(true).map(|(i, _)| i), max_len))
+        .or_else(|| last_fit(text.char_indices().map(|(i, _)| i), max_len))
+        // A limit smaller than one codepoint: emit it whole to make progress
+        .unwrap_or_else(|| text.chars().next().map_or(text.len(), char::len_utf8));
+    (boundary, 0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        let schedule: Vec<u64> = (0..10)
+            .map(|attempt| backoff.delay_for(attempt, 0.5).as_secs())
+            .collect();
+        assert_eq!(schedule, [1, 2, 4, 8, 16, 32, 60, 60, 60, 60]);
+        assert!(schedule.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(backoff.delay_for(u32::MAX, 0.5), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn backoff_jitter_stays_within_twenty_percent_and_the_cap() {
+        let backoff = Backoff::new(Duration::from_secs(10), Duration::from_secs(60));
+        assert_eq!(backoff.delay_for(0, 0.0), Duration::from_secs(8));
+        let high = backoff.delay_for(0, 0.999);
+        assert!(high > Duration::from_millis(11_990) && high < Duration::from_secs(12));
+        assert_eq!(backoff.delay_for(3, 0.999), Duration::from_secs(60));
+
+        let mut live = Backoff::default();
+        let delays: Vec<Duration> = (0..8).map(|_| live.next_delay()).collect();
+        for (attempt, delay) in delays.iter().enumerate() {
+            let base = live.delay_for(attempt as u32, 0.5);
+            assert!(*delay >= base.mul_f64(0.8) && *delay <= RECONNECT_MAX_DELAY);
+        }
+        live.reset();
+        assert!(live.next_delay() < Duration::from_millis(1200));
+    }
+
+    #[test]
+    fn reporter_publishes_only_state_changes() {
+        let bus = Arc::new(EventBus::default());
+        let mut rx = bus.subscribe();
+        let reporter = ConnectionReporter::new("slack", Some(bus));
+
+        reporter.connected();
+        reporter.connected();
+        reporter.disconnected("socket closed");
+        reporter.disconnected("still closed");
+        reporter.connected();
+
+        let mut seen = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            seen.push(match event {
+                AgentEvent::ChannelConnected { channel } => format!("up {}", channel),
+                AgentEvent::ChannelDisconnected { channel, reason } => {
+                    format!("down {} ({})", channel, reason)
+                }
+                other => panic!("unexpected {:?}", other),
+            });
+        }
+        assert_eq!(seen, ["up slack", "down slack (socket closed)", "up slack"]);
+    }
+
     #[test]
     fn short_message_unchanged() {
         let result = split_message("hello", 100);
@@ -159,4 +337,81 @@ mod tests {
             assert!(chunk.len() <= 10);
         }
     }
+
+    /// Every chunk fits and the chunks concatenate back to `text` minus the
+    /// dropped separators.
+    fn assert_valid_split(text: &str, max_len: usize) -> Vec<String> {
+        let chunks = split_message(text, max_len);
+        for chunk in &chunks {
+            assert!(
+                chunk.len() <= max_len,
+                "chunk {:?} exceeds {}",
+                chunk,
+                max_len
+            );
+        }
+        let strip = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        assert_eq!(strip(&chunks.concat()), strip(text));
+        chunks
+    }
+
+    #[test]
+    fn emoji_never_split_mid_codepoint() {
+        // 4-byte codepoints with a limit that is not a multiple of 4
+        let text = "\u{1F600}".repeat(10);
+        let chunks = assert_valid_split(&text, 10);
+        assert!(chunks.iter().all(|c| c.chars().count() == 2));
+    }
+
+    #[test]
+    fn grapheme_clusters_stay_together() {
+        // Family emoji: 5 codepoints joined by ZWJ, 18 bytes, one grapheme
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let text = family.repeat(3);
+        let chunks = assert_valid_split(&text, 20);
+        assert_eq!(chunks, vec![family; 3]);
+    }
+
+    #[test]
+    fn cjk_splits_between_characters() {
+        // Each ideograph is 3 bytes; no whitespace to break on
+        let text = "你好世界你好世界";
+        let chunks = assert_valid_split(text, 10);
+        assert_eq!(chunks, vec!["你好世", "界你好", "世界"]);
+    }
+
+    #[test]
+    fn breaks_at_word_boundaries() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let chunks = assert_valid_split(text, 12);
+        assert_eq!(
+            chunks,
+            vec!["the quick", "brown fox", "jumps over", "the lazy dog"]
+        );
+    }
+
+    #[test]
+    fn prefers_newline_over_space() {
+        let text = "first line\nsecond line here";
+        let chunks = assert_valid_split(text, 20);
+        assert_eq!(chunks, vec!["first line", "second line here"]);
+    }
+
+    #[test]
+    fn mixed_multibyte_words_are_not_cut() {
+        let text = "café naïve 日本語 résumé \u{1F680}rocket";
+        let words: Vec<&str> = text.split_whitespace().collect();
+        for max_len in 11..text.len() {
+            for chunk in assert_valid_split(text, max_len) {
+                for word in chunk.split_whitespace() {
+                    assert!(
+                        words.contains(&word),
+                        "word {:?} was cut at {}",
+                        word,
+                        max_len
+                    );
+                }
+            }
+        }
+    }
 }
@@ -10,7 +10,7 @@ use ryvos_core::config::{DmPolicy, WhatsAppConfig};
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::security::ApprovalRequest;
 use ryvos_core::traits::ChannelAdapter;
-use ryvos_core::types::{MessageContent, MessageEnvelope, SessionId};
+use ryvos_core::types::{Attachment, AttachmentKind, MessageContent, MessageEnvelope, SessionId};
 
 use crate::util::split_message;
 
@@ -380,13 +380,18 @@ impl WhatsAppWebhookHandle {
                                 continue;
                             }
                         }
+                        // A file's caption stands in for the text
+                        "image" | "document" | "audio" | "video" => {
+                            msg[msg_type]["caption"].as_str().unwrap_or("").to_string()
+                        }
                         _ => {
                             debug!(msg_type, "Unsupported WhatsApp message type");
                             continue;
                         }
                     };
 
-                    if text.is_empty() {
+                    let attachments: Vec<_> = media_attachment(msg).into_iter().collect();
+                    if text.is_empty() && attachments.is_empty() {
                         continue;
                     }
 
@@ -400,12 +405,16 @@ impl WhatsAppWebhookHandle {
                         .insert(session_id.0.clone(), from.clone());
 
                     let envelope = MessageEnvelope {
-                        id: uuid::Uuid::new_v4().to_string(),
+                        id: msg["id"]
+                            .as_str()
+                            .map(String::from)
+                            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
                         session_id,
                         session_key: key.clone(),
                         channel: "whatsapp".into(),
                         sender: from,
                         text,
+                        attachments,
                         timestamp: chrono::Utc::now(),
                     };
 
@@ -417,3 +426,22 @@ impl WhatsAppWebhookHandle {
         }
     }
 }
+
+/// The media file of an image, document, audio or video message, by
+/// WhatsApp media ID.
+fn media_attachment(msg: &serde_json::Value) -> Option<Attachment> {
+    let msg_type = msg["type"].as_str()?;
+    let kind = match msg_type {
+        "image" => AttachmentKind::Image,
+        "audio" => AttachmentKind::Audio,
+        "video" => AttachmentKind::Video,
+        "document" => msg["document"]["mime_type"]
+            .as_str()
+            .map_or(AttachmentKind::File, AttachmentKind::from_mime),
+        _ => return None,
+    };
+    Some(Attachment {
+        kind,
+        reference: msg[msg_type]["id"].as_str()?.to_string(),
+    })
+}
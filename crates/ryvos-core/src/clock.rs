@@ -0,0 +1,113 @@
+//! Wall-clock time and timezones.
+//!
+//! Code that tells the agent what time it is reads it from a [`Clock`], so
+//! tests can pin "now" with a [`FixedClock`].
+
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+/// Source of the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock stopped at one instant.
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// A timezone: the system's local zone or an IANA name such as
+/// `Europe/Berlin` (`UTC` included).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+impl Zone {
+    /// Format `time` in this zone with a strftime `format`. `None` when the
+    /// format has an invalid specifier.
+    pub fn format(&self, time: DateTime<Utc>, format: &str) -> Option<String> {
+        let mut out = String::new();
+        let written = match self {
+            Zone::Local => write!(out, "{}", time.with_timezone(&Local).format(format)),
+            Zone::Named(tz) => write!(out, "{}", time.with_timezone(tz).format(format)),
+        };
+        written.ok().map(|_| out)
+    }
+
+    /// One line for the system prompt, e.g.
+    /// `Friday, 2026-10-16 14:05 +02:00 (Europe/Berlin)`.
+    pub fn describe(&self, time: DateTime<Utc>) -> String {
+        let when = self
+            .format(time, "%A, %Y-%m-%d %H:%M %:z")
+            .unwrap_or_default();
+        format!("{} ({})", when, self)
+    }
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") {
+            return Ok(Zone::Named(Tz::UTC));
+        }
+        s.parse::<Tz>().map(Zone::Named).map_err(|_| {
+            format!(
+                "unknown timezone '{}' (use an IANA name such as Europe/Berlin, UTC, or local)",
+                s
+            )
+        })
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Local => f.write_str("local time"),
+            Zone::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn named_zones_format_in_their_offset() {
+        let t = Utc.with_ymd_and_hms(2026, 10, 16, 12, 5, 0).unwrap();
+        let berlin: Zone = "Europe/Berlin".parse().unwrap();
+        assert_eq!(
+            berlin.describe(t),
+            "Friday, 2026-10-16 14:05 +02:00 (Europe/Berlin)"
+        );
+        let utc: Zone = "utc".parse().unwrap();
+        assert_eq!(utc.format(t, "%H:%M").as_deref(), Some("12:05"));
+        assert_eq!("local".parse::<Zone>(), Ok(Zone::Local));
+        assert!("Mars/Olympus".parse::<Zone>().is_err());
+        assert_eq!(utc.format(t, "%Q"), None);
+    }
+}
@@ -58,9 +58,13 @@ pub enum RyvosError {
     Gateway(String),
 
     // Security errors
-    /// **Deprecated:** Tools are no longer blocked by policy. Kept for compat.
-    #[error("Tool blocked by security policy: {tool} (tier {tier})")]
-    ToolBlocked { tool: String, tier: String },
+    /// A tool call matched a configured dangerous pattern.
+    #[error("Tool blocked by security policy: {tool} (tier {tier}): {reason}")]
+    ToolBlocked {
+        tool: String,
+        tier: String,
+        reason: String,
+    },
 
     /// User explicitly denied a soft checkpoint.
     #[error("Approval denied for tool {tool}: {reason}")]
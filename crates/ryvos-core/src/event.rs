@@ -116,13 +116,17 @@ fn extract_session_id(event: &AgentEvent) -> Option<&str> {
     match event {
         AgentEvent::RunStarted { session_id } => Some(&session_id.0),
         AgentEvent::RunComplete { session_id, .. } => Some(&session_id.0),
+        AgentEvent::TurnLimitReached { session_id, .. } => Some(&session_id.0),
         AgentEvent::GoalEvaluated { session_id, .. } => Some(&session_id.0),
         AgentEvent::JudgeVerdict { session_id, .. } => Some(&session_id.0),
         AgentEvent::GuardianStall { session_id, .. } => Some(&session_id.0),
         AgentEvent::GuardianDoomLoop { session_id, .. } => Some(&session_id.0),
         AgentEvent::GuardianBudgetAlert { session_id, .. } => Some(&session_id.0),
+        AgentEvent::GuardianRepeatedAnswer { session_id, .. } => Some(&session_id.0),
         AgentEvent::GuardianHint { session_id, .. } => Some(&session_id.0),
+        AgentEvent::ContextNearLimit { session_id, .. } => Some(&session_id.0),
         AgentEvent::ApprovalRequested { request } => Some(&request.session_id),
+        AgentEvent::ApprovalTimedOut { request, .. } => Some(&request.session_id),
         AgentEvent::HeartbeatOk { session_id, .. } => Some(&session_id.0),
         AgentEvent::HeartbeatAlert { session_id, .. } => Some(&session_id.0),
         AgentEvent::BudgetWarning { session_id, .. } => Some(&session_id.0),
@@ -131,6 +135,8 @@ fn extract_session_id(event: &AgentEvent) -> Option<&str> {
         AgentEvent::NodeComplete { session_id, .. } => Some(&session_id.0),
         AgentEvent::EvolutionTriggered { session_id, .. } => Some(&session_id.0),
         AgentEvent::SemanticFailureCaptured { session_id, .. } => Some(&session_id.0),
+        // Sub-agent activity belongs to the run that spawned it, so it
+        // passes the parent's session filter like untagged events do
         _ => None,
     }
 }
@@ -141,19 +147,25 @@ fn event_type_name(event: &AgentEvent) -> &'static str {
         AgentEvent::RunStarted { .. } => "RunStarted",
         AgentEvent::TextDelta(_) => "TextDelta",
         AgentEvent::ToolStart { .. } => "ToolStart",
+        AgentEvent::ToolProgress { .. } => "ToolProgress",
         AgentEvent::ToolEnd { .. } => "ToolEnd",
         AgentEvent::TurnComplete { .. } => "TurnComplete",
         AgentEvent::RunComplete { .. } => "RunComplete",
+        AgentEvent::TurnLimitReached { .. } => "TurnLimitReached",
         AgentEvent::RunError { .. } => "RunError",
         AgentEvent::CronFired { .. } => "CronFired",
         AgentEvent::ApprovalRequested { .. } => "ApprovalRequested",
         AgentEvent::ApprovalResolved { .. } => "ApprovalResolved",
+        AgentEvent::ApprovalTimedOut { .. } => "ApprovalTimedOut",
         AgentEvent::ToolBlocked { .. } => "ToolBlocked",
+        AgentEvent::PromptInjectionDetected { .. } => "PromptInjectionDetected",
         AgentEvent::GuardianStall { .. } => "GuardianStall",
         AgentEvent::GuardianDoomLoop { .. } => "GuardianDoomLoop",
         AgentEvent::GuardianBudgetAlert { .. } => "GuardianBudgetAlert",
+        AgentEvent::GuardianRepeatedAnswer { .. } => "GuardianRepeatedAnswer",
         AgentEvent::GuardianHint { .. } => "GuardianHint",
         AgentEvent::UsageUpdate { .. } => "UsageUpdate",
+        AgentEvent::ContextNearLimit { .. } => "ContextNearLimit",
         AgentEvent::GoalEvaluated { .. } => "GoalEvaluated",
         AgentEvent::DecisionMade { .. } => "DecisionMade",
         AgentEvent::JudgeVerdict { .. } => "JudgeVerdict",
@@ -167,6 +179,9 @@ fn event_type_name(event: &AgentEvent) -> &'static str {
         AgentEvent::NodeComplete { .. } => "NodeComplete",
         AgentEvent::EvolutionTriggered { .. } => "EvolutionTriggered",
         AgentEvent::SemanticFailureCaptured { .. } => "SemanticFailureCaptured",
+        AgentEvent::ChannelConnected { .. } => "ChannelConnected",
+        AgentEvent::ChannelDisconnected { .. } => "ChannelDisconnected",
+        AgentEvent::SubAgent { .. } => "SubAgent",
     }
 }
 
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+/// How long a `shell_exit_code` goal check may run before it is killed.
+pub const SHELL_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// A goal that defines what success looks like for an agent run.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Goal {
@@ -61,6 +65,46 @@ pub enum CriterionType {
     LlmJudge { prompt: String },
     /// A named custom criterion (evaluated externally).
     Custom { name: String },
+    /// Check that a path exists in the workspace after the run.
+    FileExists { path: String },
+    /// Check that a workspace file contains a pattern.
+    FileContains {
+        path: String,
+        pattern: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// Count workspace paths matching a glob and check the count is within bounds.
+    PathGlobCount {
+        pattern: String,
+        #[serde(default = "default_min_count")]
+        min: usize,
+        #[serde(default)]
+        max: Option<usize>,
+    },
+    /// Run an allowlisted shell command in the workspace and check its exit code.
+    ShellExitCode {
+        command: String,
+        #[serde(default)]
+        expected_code: i32,
+    },
+}
+
+fn default_min_count() -> usize {
+    1
+}
+
+impl CriterionType {
+    /// Whether this criterion inspects the workspace rather than the output text.
+    pub fn is_workspace_check(&self) -> bool {
+        matches!(
+            self,
+            CriterionType::FileExists { .. }
+                | CriterionType::FileContains { .. }
+                | CriterionType::PathGlobCount { .. }
+                | CriterionType::ShellExitCode { .. }
+        )
+    }
 }
 
 /// A constraint on the agent execution.
@@ -204,11 +248,46 @@ impl Goal {
                         },
                     })
                 }
-                CriterionType::LlmJudge { .. } | CriterionType::Custom { .. } => None,
+                CriterionType::LlmJudge { .. }
+                | CriterionType::Custom { .. }
+                | CriterionType::FileExists { .. }
+                | CriterionType::FileContains { .. }
+                | CriterionType::PathGlobCount { .. }
+                | CriterionType::ShellExitCode { .. } => None,
             })
             .collect()
     }
 
+    /// Evaluate workspace criteria (FileExists, FileContains, PathGlobCount,
+    /// ShellExitCode) against `workspace` after a run.
+    ///
+    /// File checks are read-only and confined to the workspace. Shell checks
+    /// only run when the command string appears verbatim in `allowed_commands`;
+    /// anything else fails without being executed. A shell check still
+    /// running after [`SHELL_CHECK_TIMEOUT`] is killed and fails.
+    pub async fn evaluate_workspace(
+        &self,
+        workspace: &Path,
+        allowed_commands: &[String],
+    ) -> Vec<CriterionResult> {
+        let mut results = Vec::new();
+        for c in self
+            .success_criteria
+            .iter()
+            .filter(|c| c.criterion_type.is_workspace_check())
+        {
+            let (passed, reasoning) =
+                check_workspace_criterion(&c.criterion_type, workspace, allowed_commands).await;
+            results.push(CriterionResult {
+                criterion_id: c.id.clone(),
+                score: if passed { 1.0 } else { 0.0 },
+                passed,
+                reasoning,
+            });
+        }
+        results
+    }
+
     /// Compute the overall evaluation from a complete set of criterion results.
     pub fn compute_evaluation(
         &self,
@@ -253,6 +332,124 @@ impl Goal {
     }
 }
 
+/// Resolve a criterion path inside the workspace, rejecting absolute paths
+/// and `..` components so checks cannot escape it.
+fn resolve_in_workspace(workspace: &Path, path: &str) -> Option<PathBuf> {
+    let rel = Path::new(path);
+    if rel
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    Some(workspace.join(rel))
+}
+
+async fn check_workspace_criterion(
+    criterion: &CriterionType,
+    workspace: &Path,
+    allowed_commands: &[String],
+) -> (bool, String) {
+    match criterion {
+        CriterionType::FileExists { path } => match resolve_in_workspace(workspace, path) {
+            Some(full) if tokio::fs::try_exists(&full).await.unwrap_or(false) => {
+                (true, format!("'{}' exists", path))
+            }
+            Some(_) => (false, format!("'{}' does not exist", path)),
+            None => (false, format!("'{}' is outside the workspace", path)),
+        },
+        CriterionType::FileContains {
+            path,
+            pattern,
+            case_sensitive,
+        } => {
+            let Some(full) = resolve_in_workspace(workspace, path) else {
+                return (false, format!("'{}' is outside the workspace", path));
+            };
+            match tokio::fs::read_to_string(&full).await {
+                Ok(content) => {
+                    let found = if *case_sensitive {
+                        content.contains(pattern.as_str())
+                    } else {
+                        content.to_lowercase().contains(&pattern.to_lowercase())
+                    };
+                    if found {
+                        (true, format!("'{}' contains '{}'", path, pattern))
+                    } else {
+                        (false, format!("'{}' does not contain '{}'", path, pattern))
+                    }
+                }
+                Err(e) => (false, format!("Cannot read '{}': {}", path, e)),
+            }
+        }
+        CriterionType::PathGlobCount { pattern, min, max } => {
+            // Escape the workspace so brackets or stars in its path match literally
+            let escaped = glob::Pattern::escape(&workspace.to_string_lossy());
+            let Some(full) = resolve_in_workspace(Path::new(&escaped), pattern) else {
+                return (false, format!("'{}' is outside the workspace", pattern));
+            };
+            let paths = match glob::glob(&full.to_string_lossy()) {
+                Ok(paths) => paths,
+                Err(e) => return (false, format!("Invalid glob '{}': {}", pattern, e)),
+            };
+            let count = paths.filter_map(|p| p.ok()).count();
+            let in_range = count >= *min && !matches!(max, Some(m) if count > *m);
+            let bounds = match max {
+                Some(m) => format!("{}..={}", min, m),
+                None => format!(">= {}", min),
+            };
+            (
+                in_range,
+                format!(
+                    "'{}' matched {} paths (expected {})",
+                    pattern, count, bounds
+                ),
+            )
+        }
+        CriterionType::ShellExitCode {
+            command,
+            expected_code,
+        } => {
+            if !allowed_commands.iter().any(|c| c == command) {
+                return (
+                    false,
+                    format!("Command '{}' is not in the goal check allowlist", command),
+                );
+            }
+            let status = tokio::process::Command::new("sh")
+                .args(["-c", command])
+                .current_dir(workspace)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .status();
+            match tokio::time::timeout(SHELL_CHECK_TIMEOUT, status).await {
+                Err(_) => (
+                    false,
+                    format!(
+                        "'{}' did not finish within {}s",
+                        command,
+                        SHELL_CHECK_TIMEOUT.as_secs()
+                    ),
+                ),
+                Ok(Ok(s)) => {
+                    let code = s.code().unwrap_or(-1);
+                    (
+                        code == *expected_code,
+                        format!(
+                            "'{}' exited with {} (expected {})",
+                            command, code, expected_code
+                        ),
+                    )
+                }
+                Ok(Err(e)) => (false, format!("Failed to run '{}': {}", command, e)),
+            }
+        }
+        _ => (false, "Not a workspace criterion".to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -465,4 +662,185 @@ mod tests {
         assert_eq!(parsed.version, 3);
         assert_eq!(parsed.metrics.get("latency_ms"), Some(&150.0));
     }
+
+    fn workspace_criterion(id: &str, criterion_type: CriterionType) -> SuccessCriterion {
+        SuccessCriterion {
+            id: id.to_string(),
+            criterion_type,
+            weight: 1.0,
+            description: id.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_exists_check() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("report.md"), "# Report").unwrap();
+
+        let goal = make_goal(
+            vec![
+                workspace_criterion(
+                    "present",
+                    CriterionType::FileExists {
+                        path: "report.md".to_string(),
+                    },
+                ),
+                workspace_criterion(
+                    "missing",
+                    CriterionType::FileExists {
+                        path: "missing.md".to_string(),
+                    },
+                ),
+            ],
+            0.9,
+        );
+
+        let results = goal.evaluate_workspace(dir.path(), &[]).await;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].passed);
+        assert!(!results[1].passed);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_checks_contribute_to_score() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("out.txt"), "done").unwrap();
+
+        let goal = make_goal(
+            vec![
+                contains_criterion("c1", "finished", 1.0),
+                workspace_criterion(
+                    "c2",
+                    CriterionType::FileExists {
+                        path: "out.txt".to_string(),
+                    },
+                ),
+            ],
+            0.5,
+        );
+
+        let mut results = goal.evaluate_deterministic("nothing to see");
+        results.extend(goal.evaluate_workspace(dir.path(), &[]).await);
+        let eval = goal.compute_evaluation(results, vec![]);
+        assert!((eval.overall_score - 0.5).abs() < 0.001);
+        assert!(eval.passed);
+
+        std::fs::remove_file(dir.path().join("out.txt")).unwrap();
+        let mut results = goal.evaluate_deterministic("nothing to see");
+        results.extend(goal.evaluate_workspace(dir.path(), &[]).await);
+        let eval = goal.compute_evaluation(results, vec![]);
+        assert!(eval.overall_score.abs() < 0.001);
+        assert!(!eval.passed);
+    }
+
+    #[tokio::test]
+    async fn test_file_contains_and_glob_count() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("b.rs"), "fn helper() {}").unwrap();
+
+        let goal = make_goal(
+            vec![
+                workspace_criterion(
+                    "contains",
+                    CriterionType::FileContains {
+                        path: "a.rs".to_string(),
+                        pattern: "MAIN".to_string(),
+                        case_sensitive: false,
+                    },
+                ),
+                workspace_criterion(
+                    "count",
+                    CriterionType::PathGlobCount {
+                        pattern: "*.rs".to_string(),
+                        min: 2,
+                        max: Some(2),
+                    },
+                ),
+            ],
+            0.9,
+        );
+
+        let results = goal.evaluate_workspace(dir.path(), &[]).await;
+        assert!(results.iter().all(|r| r.passed));
+    }
+
+    #[tokio::test]
+    async fn test_glob_count_escapes_the_workspace_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("build [x]");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("a.log"), "").unwrap();
+
+        let goal = make_goal(
+            vec![workspace_criterion(
+                "logs",
+                CriterionType::PathGlobCount {
+                    pattern: "*.log".to_string(),
+                    min: 1,
+                    max: None,
+                },
+            )],
+            0.9,
+        );
+
+        let results = goal.evaluate_workspace(&workspace, &[]).await;
+        assert!(results[0].passed, "{}", results[0].reasoning);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_paths_cannot_escape() {
+        let dir = tempfile::tempdir().unwrap();
+        let goal = make_goal(
+            vec![workspace_criterion(
+                "escape",
+                CriterionType::FileExists {
+                    path: "../".to_string(),
+                },
+            )],
+            0.9,
+        );
+
+        let results = goal.evaluate_workspace(dir.path(), &[]).await;
+        assert!(!results[0].passed);
+        assert!(results[0].reasoning.contains("outside the workspace"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_exit_code_requires_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let goal = make_goal(
+            vec![workspace_criterion(
+                "shell",
+                CriterionType::ShellExitCode {
+                    command: "true".to_string(),
+                    expected_code: 0,
+                },
+            )],
+            0.9,
+        );
+
+        let results = goal.evaluate_workspace(dir.path(), &[]).await;
+        assert!(!results[0].passed);
+        assert!(results[0].reasoning.contains("allowlist"));
+
+        let results = goal
+            .evaluate_workspace(dir.path(), &["true".to_string()])
+            .await;
+        assert!(results[0].passed);
+    }
+
+    #[test]
+    fn test_workspace_criteria_skipped_in_deterministic() {
+        let goal = make_goal(
+            vec![workspace_criterion(
+                "f",
+                CriterionType::FileExists {
+                    path: "x".to_string(),
+                },
+            )],
+            0.9,
+        );
+        assert!(goal.evaluate_deterministic("x").is_empty());
+    }
 }
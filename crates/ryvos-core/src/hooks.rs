@@ -1,20 +1,191 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
 use tracing::warn;
 
-/// Execute hook commands with environment variables.
-/// Fire-and-forget: errors are logged, not propagated.
-pub async fn run_hooks(commands: &[String], env_vars: &[(&str, &str)]) {
-    for cmd in commands {
-        let mut command = tokio::process::Command::new("sh");
+use crate::config::HookPayload;
+use crate::security::env_mask;
+
+/// One firing of a hook list: the event, its `RYVOS_*` env vars and any
+/// structured fields the env vars cannot carry (e.g. full tool input).
+pub struct HookEvent<'a> {
+    pub event: &'a str,
+    pub payload: HookPayload,
+    pub env: &'a [(&'a str, &'a str)],
+    pub data: Map<String, Value>,
+}
+
+impl<'a> HookEvent<'a> {
+    pub fn new(event: &'a str, payload: HookPayload, env: &'a [(&'a str, &'a str)]) -> Self {
+        Self {
+            event,
+            payload,
+            env,
+            data: Map::new(),
+        }
+    }
+
+    /// Add a field to the JSON payload, replacing the env-derived one of
+    /// the same name.
+    pub fn with(mut self, key: &str, value: Value) -> Self {
+        self.data.insert(key.to_string(), value);
+        self
+    }
+
+    /// The stdin payload: `event`, `timestamp`, each env var under its
+    /// lowercased name without `RYVOS_`, then the extra fields.
+    pub fn to_json(&self) -> Value {
+        let mut obj = Map::new();
+        obj.insert("event".into(), self.event.into());
+        obj.insert("timestamp".into(), chrono::Utc::now().to_rfc3339().into());
+        for (key, val) in self.env {
+            let key = key.strip_prefix("RYVOS_").unwrap_or(key).to_lowercase();
+            obj.insert(key, (*val).into());
+        }
+        obj.extend(self.data.clone());
+        Value::Object(obj)
+    }
+
+    /// Spawn `cmd` with this event's env vars and/or stdin payload.
+    fn spawn(&self, cmd: &str) -> std::io::Result<tokio::process::Child> {
+        let mut command = Command::new("sh");
         command.args(["-c", cmd]);
-        for (key, val) in env_vars {
-            command.env(key, val);
+        if self.payload.env() {
+            for (key, val) in self.env {
+                command.env(key, val);
+            }
+        }
+        let stdin = if self.payload.json() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        command
+            .stdin(stdin)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+    }
+
+    /// Run `cmd` to completion, writing the JSON payload to its stdin.
+    async fn run(&self, cmd: &str) -> std::io::Result<std::process::ExitStatus> {
+        let mut child = self.spawn(cmd)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let mut json = self.to_json().to_string();
+            json.push('\n');
+            // A hook that exits without reading stdin is not an error
+            let _ = stdin.write_all(json.as_bytes()).await;
         }
-        command.stdout(std::process::Stdio::null());
-        command.stderr(std::process::Stdio::null());
-        match command.status().await {
-            Ok(s) if !s.success() => warn!(hook = %cmd, code = s.code(), "Hook exited non-zero"),
-            Err(e) => warn!(hook = %cmd, error = %e, "Hook failed to execute"),
+        child.wait().await
+    }
+}
+
+/// Execute hook commands for `event`.
+/// Fire-and-forget: errors are logged, not propagated.
+pub async fn run_hooks(commands: &[String], event: &HookEvent<'_>) {
+    for cmd in commands {
+        match event.run(cmd).await {
+            Ok(s) if !s.success() => {
+                warn!(hook = %env_mask().mask(cmd), code = s.code(), "Hook exited non-zero")
+            }
+            Err(e) => warn!(hook = %env_mask().mask(cmd), error = %e, "Hook failed to execute"),
             _ => {}
         }
     }
 }
+
+/// Verdict of the `on_tool_approval` hooks on one tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookApproval {
+    /// Every hook exited 0.
+    Approved,
+    /// A hook exited non-zero; the reason names it.
+    Denied(String),
+    /// No hooks, or one could not be run or timed out: leave it to a human.
+    Undecided,
+}
+
+/// Run approval hooks in order and read their exit codes. The first
+/// non-zero exit denies; hooks after it are not run.
+pub async fn run_approval_hooks(
+    commands: &[String],
+    event: &HookEvent<'_>,
+    timeout: Duration,
+) -> HookApproval {
+    let mut undecided = commands.is_empty();
+    for cmd in commands {
+        match tokio::time::timeout(timeout, event.run(cmd)).await {
+            Ok(Ok(s)) if s.success() => {}
+            Ok(Ok(s)) => {
+                let code = s
+                    .code()
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "signal".to_string());
+                return HookApproval::Denied(format!(
+                    "denied by approval hook `{}` (exit {})",
+                    env_mask().mask(cmd),
+                    code
+                ));
+            }
+            Ok(Err(e)) => {
+                warn!(hook = %env_mask().mask(cmd), error = %e, "Approval hook failed to execute");
+                undecided = true;
+            }
+            Err(_) => {
+                warn!(hook = %env_mask().mask(cmd), "Approval hook timed out");
+                undecided = true;
+            }
+        }
+    }
+    if undecided {
+        HookApproval::Undecided
+    } else {
+        HookApproval::Approved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn out_file() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ryvos_hook_{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn tool_call_hook_reads_json_on_stdin() {
+        let out = out_file();
+        let cmd = format!("cat > {}; echo \"$RYVOS_TOOL\" >> {0}", out.display());
+        let env = [("RYVOS_SESSION", "s1"), ("RYVOS_TOOL", "bash")];
+        let input = serde_json::json!({"command": "cargo test", "timeout": 60});
+        let event =
+            HookEvent::new("tool_call", HookPayload::Json, &env).with("input", input.clone());
+        run_hooks(&[cmd], &event).await;
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        let (json, env_line) = written.split_once('\n').unwrap();
+        let payload: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(payload["event"], "tool_call");
+        assert_eq!(payload["session"], "s1");
+        assert_eq!(payload["tool"], "bash");
+        assert_eq!(payload["input"], input);
+        assert!(payload["timestamp"].as_str().unwrap().starts_with("20"));
+        // json alone leaves the env vars out
+        assert_eq!(env_line, "\n");
+        std::fs::remove_file(&out).ok();
+    }
+
+    #[tokio::test]
+    async fn env_payload_sends_nothing_on_stdin() {
+        let out = out_file();
+        let cmd = format!("cat > {}; echo \"$RYVOS_TOOL\" >> {0}", out.display());
+        let env = [("RYVOS_TOOL", "bash")];
+        run_hooks(&[cmd], &HookEvent::new("tool_call", HookPayload::Env, &env)).await;
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "bash\n");
+        std::fs::remove_file(&out).ok();
+    }
+}
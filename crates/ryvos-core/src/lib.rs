@@ -9,15 +9,20 @@
 //! - **Config**: [`AppConfig`] and all nested configuration structs, parsed from
 //!   TOML with `${ENV_VAR}` expansion.
 //! - **Events**: [`EventBus`] for pub/sub communication between components.
+//! - **Messages**: Translated CLI and REPL strings, selected by locale.
 //! - **Goals**: Weighted success criteria with deterministic and LLM-based evaluation.
 //! - **Security**: Deprecated tier-based security (kept for compat), plus
 //!   `tool_has_side_effects()` and `summarize_input()` used by the safety pipeline.
 
+pub mod clock;
 pub mod config;
 pub mod error;
 pub mod event;
 pub mod goal;
 pub mod hooks;
+pub mod messages;
+pub mod models;
+pub mod overrides;
 pub mod security;
 pub mod traits;
 pub mod types;
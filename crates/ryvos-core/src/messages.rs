@@ -0,0 +1,466 @@
+//! Translated user-facing strings for the CLI and REPL.
+//!
+//! Each locale is a table of key → string. The locale comes from
+//! `[agent] locale`, falling back to `LANG`, and is chosen once at startup
+//! with [`set_locale`]. A key missing from the chosen locale's table is
+//! looked up in English, so a partial translation never shows a blank.
+//! Templates use `{}` placeholders, filled in order by [`format`].
+
+use std::fmt::{self, Display};
+use std::sync::OnceLock;
+
+/// A language with its own message table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    De,
+    Fr,
+}
+
+impl Locale {
+    /// Parse a language tag such as `es`, `de-DE` or `fr_FR.UTF-8`.
+    /// `C` and `POSIX` mean English; unsupported languages give `None`.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let lang = tag
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match lang.as_str() {
+            "en" | "c" | "posix" => Some(Self::En),
+            "es" => Some(Self::Es),
+            "de" => Some(Self::De),
+            "fr" => Some(Self::Fr),
+            _ => None,
+        }
+    }
+
+    /// The configured locale if it is supported, otherwise `LANG`,
+    /// otherwise English.
+    pub fn resolve(configured: Option<&str>) -> Self {
+        configured
+            .and_then(Self::parse)
+            .or_else(|| std::env::var("LANG").ok().as_deref().and_then(Self::parse))
+            .unwrap_or_default()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Es => "es",
+            Self::De => "de",
+            Self::Fr => "fr",
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::En => EN,
+            Self::Es => ES,
+            Self::De => DE,
+            Self::Fr => FR,
+        }
+    }
+
+    /// The string for `key`, in English when this locale lacks it, or the
+    /// key itself when no table has it.
+    pub fn get(self, key: &'static str) -> &'static str {
+        find(self.table(), key)
+            .or_else(|| find(EN, key))
+            .unwrap_or(key)
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn find(table: &'static [(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+static CURRENT: OnceLock<Locale> = OnceLock::new();
+
+/// Choose the process-wide locale. Only the first call has an effect.
+pub fn set_locale(locale: Locale) {
+    let _ = CURRENT.set(locale);
+}
+
+/// The process-wide locale, English until [`set_locale`] is called.
+pub fn locale() -> Locale {
+    CURRENT.get().copied().unwrap_or_default()
+}
+
+/// The string for `key` in the process-wide locale.
+pub fn t(key: &'static str) -> &'static str {
+    locale().get(key)
+}
+
+/// The template for `key` with each `{}` replaced by the next argument.
+pub fn format(key: &'static str, args: &[&dyn Display]) -> String {
+    fill(t(key), args)
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+const EN: &[(&str, &str)] = &[
+    ("repl.hint", "Type /help for commands, /quit to exit."),
+    ("repl.security", "Security: auto-approve up to {}"),
+    (
+        "repl.unknown_command",
+        "Unknown command: {}. Type /help for available commands.",
+    ),
+    ("help.title", "Commands:"),
+    ("help.quit", "Exit"),
+    ("help.clear", "Reset session context"),
+    ("help.session", "Show the session ID, or switch session"),
+    ("help.status", "Show agent status"),
+    ("help.usage", "Show token usage"),
+    ("help.tokens", "Show usage against a session token budget"),
+    ("help.tools", "List available tools"),
+    (
+        "help.think",
+        "Set thinking level (off/low/medium/high/<budget>)",
+    ),
+    ("help.model", "Switch model for the next turns"),
+    ("help.cd", "Show or change the session's working directory"),
+    ("help.compact", "Force context compaction"),
+    (
+        "help.nocompact",
+        "Toggle automatic compaction for this session",
+    ),
+    ("help.export", "Save this session's transcript"),
+    (
+        "help.pin",
+        "Keep a note in context through every compaction",
+    ),
+    ("help.unpin", "Remove pinned note n"),
+    ("help.pins", "List pinned notes"),
+    ("help.notools", "Toggle plain chat without tools"),
+    ("help.temp", "Set temperature and top_p for the next message only"),
+    (
+        "help.security",
+        "Show security policy and pending approvals",
+    ),
+    ("help.approve", "Approve a pending tool call"),
+    ("help.deny", "Deny a pending tool call"),
+    ("help.mcp", "Show MCP status"),
+    ("help.mcp_list", "List configured servers"),
+    ("help.mcp_connect", "Connect to a server"),
+    ("help.mcp_disconnect", "Disconnect from a server"),
+    ("help.mcp_resources", "List MCP resources"),
+    ("help.mcp_prompts", "List MCP prompts"),
+    ("help.mcp_tools", "List MCP tools"),
+    ("help.mcp_logs", "Show recent server log messages"),
+    ("help.prompts", "List all MCP prompts"),
+    ("help.soul", "Personalize your agent"),
+    ("status.session", "Session: {}"),
+    ("status.model", "Model: {} ({})"),
+    ("status.thinking", "Thinking: {}"),
+    ("status.tools", "Tools: {}"),
+    ("status.mcp_connected", "MCP: {} server(s) connected ({})"),
+    ("status.mcp_no_servers", "MCP: no servers connected"),
+    ("status.mcp_not_configured", "MCP: none configured"),
+    (
+        "status.activity",
+        "Activity: {} runs ({} failed), {} tool calls ({} failed), {} in / {} out tokens",
+    ),
+    ("usage.billed", "Billed tokens  -- Input: {}, Output: {} (cumulative)"),
+    (
+        "usage.context",
+        "Context tokens -- {} (current conversation, after compaction)",
+    ),
+    ("approval.prompt", "Allow?"),
+    ("approval.approved", "Approved: {}"),
+    ("approval.denied", "Denied: {}"),
+    (
+        "approval.not_found",
+        "Request not found (may have timed out).",
+    ),
+    ("approval.no_match", "No pending request matching '{}'."),
+    (
+        "approval.confirm_prompt",
+        "Type {} to run it (anything else denies)",
+    ),
+    (
+        "approval.confirm_needed",
+        "This runs a destructive command. Use /approve {} {} to run it.",
+    ),
+    (
+        "approval.calls_needed",
+        "This asks about several calls. Use /approve {} to run them all, or add their numbers (/approve {} 1 3).",
+    ),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("repl.hint", "Escribe /help para ver los comandos, /quit para salir."),
+    ("repl.security", "Seguridad: aprobación automática hasta {}"),
+    (
+        "repl.unknown_command",
+        "Comando desconocido: {}. Escribe /help para ver los comandos.",
+    ),
+    ("help.title", "Comandos:"),
+    ("help.quit", "Salir"),
+    ("help.clear", "Reiniciar el contexto de la sesión"),
+    ("help.session", "Mostrar el ID de sesión o cambiar de sesión"),
+    ("help.status", "Mostrar el estado del agente"),
+    ("help.usage", "Mostrar el uso de tokens"),
+    ("help.tokens", "Mostrar el uso frente al presupuesto de tokens"),
+    ("help.tools", "Listar las herramientas disponibles"),
+    ("help.think", "Nivel de razonamiento (off/low/medium/high/<presupuesto>)"),
+    ("help.model", "Cambiar el modelo para los próximos turnos"),
+    ("help.cd", "Mostrar o cambiar el directorio de trabajo"),
+    ("help.compact", "Forzar la compactación del contexto"),
+    (
+        "help.nocompact",
+        "Alternar la compactación automática en esta sesión",
+    ),
+    ("help.export", "Guardar la transcripción de la sesión"),
+    ("help.pin", "Mantener una nota en el contexto tras cada compactación"),
+    ("help.unpin", "Quitar la nota fijada n"),
+    ("help.pins", "Listar las notas fijadas"),
+    ("help.notools", "Alternar el chat sin herramientas"),
+    ("help.temp", "Fijar temperature y top_p solo para el próximo mensaje"),
+    ("help.security", "Mostrar la política de seguridad y las aprobaciones pendientes"),
+    ("help.approve", "Aprobar una llamada pendiente"),
+    ("help.deny", "Denegar una llamada pendiente"),
+    ("help.mcp", "Mostrar el estado de MCP"),
+    ("help.mcp_list", "Listar los servidores configurados"),
+    ("help.mcp_connect", "Conectar a un servidor"),
+    ("help.mcp_disconnect", "Desconectar de un servidor"),
+    ("help.mcp_resources", "Listar los recursos MCP"),
+    ("help.mcp_prompts", "Listar los prompts MCP"),
+    ("help.mcp_tools", "Listar las herramientas MCP"),
+    ("help.mcp_logs", "Mostrar los mensajes de registro recientes"),
+    ("help.prompts", "Listar todos los prompts MCP"),
+    ("help.soul", "Personalizar tu agente"),
+    ("status.session", "Sesión: {}"),
+    ("status.model", "Modelo: {} ({})"),
+    ("status.thinking", "Razonamiento: {}"),
+    ("status.tools", "Herramientas: {}"),
+    ("status.mcp_connected", "MCP: {} servidor(es) conectado(s) ({})"),
+    ("status.mcp_no_servers", "MCP: ningún servidor conectado"),
+    ("status.mcp_not_configured", "MCP: sin configurar"),
+    (
+        "status.activity",
+        "Actividad: {} ejecuciones ({} fallidas), {} llamadas ({} fallidas), {} tokens de entrada / {} de salida",
+    ),
+    (
+        "usage.billed",
+        "Tokens facturados -- Entrada: {}, Salida: {} (acumulado)",
+    ),
+    (
+        "usage.context",
+        "Tokens de contexto -- {} (conversación actual, tras la compactación)",
+    ),
+    ("approval.prompt", "¿Permitir?"),
+    ("approval.approved", "Aprobada: {}"),
+    ("approval.denied", "Denegada: {}"),
+    ("approval.not_found", "Solicitud no encontrada (puede haber expirado)."),
+    ("approval.no_match", "Ninguna solicitud pendiente coincide con '{}'."),
+    (
+        "approval.confirm_prompt",
+        "Escribe {} para ejecutarlo (cualquier otra cosa lo deniega)",
+    ),
+    (
+        "approval.confirm_needed",
+        "Esto ejecuta un comando destructivo. Usa /approve {} {} para ejecutarlo.",
+    ),
+    (
+        "approval.calls_needed",
+        "Esta solicitud incluye varias llamadas. Usa /approve {} para ejecutarlas todas, o indica sus números (/approve {} 1 3).",
+    ),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("repl.hint", "/help zeigt die Befehle, /quit beendet."),
+    ("repl.security", "Sicherheit: automatisch genehmigt bis {}"),
+    (
+        "repl.unknown_command",
+        "Unbekannter Befehl: {}. /help zeigt die verfügbaren Befehle.",
+    ),
+    ("help.title", "Befehle:"),
+    ("help.quit", "Beenden"),
+    ("help.clear", "Sitzungskontext zurücksetzen"),
+    ("help.session", "Sitzungs-ID anzeigen oder Sitzung wechseln"),
+    ("help.status", "Agentenstatus anzeigen"),
+    ("help.usage", "Token-Verbrauch anzeigen"),
+    ("help.tokens", "Verbrauch gegenüber dem Token-Budget anzeigen"),
+    ("help.tools", "Verfügbare Werkzeuge auflisten"),
+    ("help.think", "Denkstufe setzen (off/low/medium/high/<Budget>)"),
+    ("help.model", "Modell für die nächsten Runden wechseln"),
+    ("help.cd", "Arbeitsverzeichnis der Sitzung anzeigen oder ändern"),
+    ("help.compact", "Kontextverdichtung erzwingen"),
+    (
+        "help.nocompact",
+        "Automatische Verdichtung für diese Sitzung umschalten",
+    ),
+    ("help.export", "Transkript der Sitzung speichern"),
+    ("help.pin", "Notiz über jede Verdichtung hinweg im Kontext halten"),
+    ("help.unpin", "Angeheftete Notiz n entfernen"),
+    ("help.pins", "Angeheftete Notizen auflisten"),
+    ("help.notools", "Chat ohne Werkzeuge umschalten"),
+    ("help.temp", "temperature und top_p nur für die nächste Nachricht setzen"),
+    ("help.security", "Sicherheitsrichtlinie und offene Freigaben anzeigen"),
+    ("help.approve", "Offenen Werkzeugaufruf genehmigen"),
+    ("help.deny", "Offenen Werkzeugaufruf ablehnen"),
+    ("help.mcp", "MCP-Status anzeigen"),
+    ("help.mcp_list", "Konfigurierte Server auflisten"),
+    ("help.mcp_connect", "Mit einem Server verbinden"),
+    ("help.mcp_disconnect", "Verbindung zu einem Server trennen"),
+    ("help.mcp_resources", "MCP-Ressourcen auflisten"),
+    ("help.mcp_prompts", "MCP-Prompts auflisten"),
+    ("help.mcp_tools", "MCP-Werkzeuge auflisten"),
+    ("help.mcp_logs", "Aktuelle Server-Logmeldungen anzeigen"),
+    ("help.prompts", "Alle MCP-Prompts auflisten"),
+    ("help.soul", "Agenten personalisieren"),
+    ("status.session", "Sitzung: {}"),
+    ("status.model", "Modell: {} ({})"),
+    ("status.thinking", "Denkstufe: {}"),
+    ("status.tools", "Werkzeuge: {}"),
+    ("status.mcp_connected", "MCP: {} Server verbunden ({})"),
+    ("status.mcp_no_servers", "MCP: keine Server verbunden"),
+    ("status.mcp_not_configured", "MCP: nicht konfiguriert"),
+    (
+        "status.activity",
+        "Aktivität: {} Läufe ({} fehlgeschlagen), {} Werkzeugaufrufe ({} fehlgeschlagen), {} Tokens ein / {} aus",
+    ),
+    (
+        "usage.billed",
+        "Abgerechnete Tokens -- Eingabe: {}, Ausgabe: {} (kumuliert)",
+    ),
+    (
+        "usage.context",
+        "Kontext-Tokens -- {} (aktuelle Unterhaltung, nach der Kompaktierung)",
+    ),
+    ("approval.prompt", "Zulassen?"),
+    ("approval.approved", "Genehmigt: {}"),
+    ("approval.denied", "Abgelehnt: {}"),
+    ("approval.not_found", "Anfrage nicht gefunden (evtl. abgelaufen)."),
+    ("approval.no_match", "Keine offene Anfrage passt zu '{}'."),
+    (
+        "approval.confirm_prompt",
+        "{} eingeben, um es auszuführen (alles andere lehnt ab)",
+    ),
+    (
+        "approval.confirm_needed",
+        "Dies führt einen destruktiven Befehl aus. Mit /approve {} {} ausführen.",
+    ),
+    (
+        "approval.calls_needed",
+        "Diese Anfrage umfasst mehrere Aufrufe. Mit /approve {} alle ausführen oder ihre Nummern angeben (/approve {} 1 3).",
+    ),
+];
+
+/// Partial: command descriptions in `/help` fall back to English.
+const FR: &[(&str, &str)] = &[
+    (
+        "repl.hint",
+        "Tapez /help pour les commandes, /quit pour quitter.",
+    ),
+    (
+        "repl.security",
+        "Sécurité : approbation automatique jusqu'à {}",
+    ),
+    (
+        "repl.unknown_command",
+        "Commande inconnue : {}. Tapez /help pour la liste des commandes.",
+    ),
+    ("help.title", "Commandes :"),
+    ("status.session", "Session : {}"),
+    ("status.model", "Modèle : {} ({})"),
+    ("status.thinking", "Réflexion : {}"),
+    ("status.tools", "Outils : {}"),
+    ("approval.prompt", "Autoriser ?"),
+    ("approval.approved", "Approuvée : {}"),
+    ("approval.denied", "Refusée : {}"),
+    (
+        "approval.not_found",
+        "Demande introuvable (elle a peut-être expiré).",
+    ),
+    (
+        "approval.no_match",
+        "Aucune demande en attente ne correspond à '{}'.",
+    ),
+    (
+        "approval.confirm_prompt",
+        "Tapez {} pour l'exécuter (toute autre saisie refuse)",
+    ),
+    (
+        "approval.confirm_needed",
+        "Ceci exécute une commande destructive. Utilisez /approve {} {} pour l'exécuter.",
+    ),
+    (
+        "approval.calls_needed",
+        "Cette demande porte sur plusieurs appels. Utilisez /approve {} pour tous les exécuter, ou indiquez leurs numéros (/approve {} 1 3).",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_switch_translates_present_keys_and_falls_back_to_english() {
+        assert_eq!(Locale::En.get("approval.prompt"), "Allow?");
+        assert_eq!(Locale::Es.get("approval.prompt"), "¿Permitir?");
+        assert_eq!(Locale::De.get("help.quit"), "Beenden");
+
+        // French has no /help descriptions yet
+        assert_eq!(Locale::Fr.get("approval.prompt"), "Autoriser ?");
+        assert_eq!(Locale::Fr.get("help.quit"), "Exit");
+        assert_eq!(Locale::Fr.get("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn every_translated_key_exists_in_english() {
+        for locale in [Locale::Es, Locale::De, Locale::Fr] {
+            for (key, _) in locale.table() {
+                assert!(
+                    find(EN, key).is_some(),
+                    "{} has unknown key {}",
+                    locale,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tags_parse_from_config_and_lang_forms() {
+        assert_eq!(Locale::parse("es"), Some(Locale::Es));
+        assert_eq!(Locale::parse("de-DE"), Some(Locale::De));
+        assert_eq!(Locale::parse("fr_FR.UTF-8"), Some(Locale::Fr));
+        assert_eq!(Locale::parse("C"), Some(Locale::En));
+        assert_eq!(Locale::parse("ja_JP"), None);
+        assert_eq!(Locale::resolve(Some("de")), Locale::De);
+    }
+
+    #[test]
+    fn placeholders_fill_in_order() {
+        let template = Locale::Es.get("status.model");
+        assert_eq!(
+            fill(template, &[&"gpt-4o", &"openai"]),
+            "Modelo: gpt-4o (openai)"
+        );
+        assert_eq!(fill("{} of {}", &[&1]), "1 of ");
+    }
+}
@@ -0,0 +1,92 @@
+//! Context window and output limits of known models.
+//!
+//! Used to default `[model] max_tokens` and `[agent] max_context_tokens`
+//! when the config leaves them out. Model IDs are matched by prefix, so
+//! dated releases (`claude-sonnet-4-20250514`) and router names
+//! (`anthropic/claude-sonnet-4`) resolve to their family.
+
+/// Limits published for a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelLimits {
+    /// Prompt plus output, in tokens.
+    pub context_window: usize,
+    /// Most tokens the model will generate in one response.
+    pub max_output_tokens: u32,
+}
+
+/// Output cap for models not in the table.
+pub const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 8192;
+
+/// Context budget for models not in the table.
+pub const DEFAULT_CONTEXT_BUDGET: usize = 80_000;
+
+/// `(model ID prefix, context window, max output tokens)`. A prefix must
+/// come before any shorter prefix it extends.
+const KNOWN_MODELS: &[(&str, usize, u32)] = &[
+    // Anthropic
+    ("claude-opus-4-5", 200_000, 64_000),
+    ("claude-opus-4", 200_000, 32_000),
+    ("claude-sonnet-4", 200_000, 64_000),
+    ("claude-haiku-4", 200_000, 64_000),
+    ("claude-3-7-sonnet", 200_000, 64_000),
+    ("claude-3-5-sonnet", 200_000, 8_192),
+    ("claude-3-5-haiku", 200_000, 8_192),
+    ("claude-3", 200_000, 4_096),
+    // OpenAI
+    ("gpt-4.1", 1_047_576, 32_768),
+    ("gpt-4o", 128_000, 16_384),
+    ("gpt-4-turbo", 128_000, 4_096),
+    ("o1-mini", 128_000, 65_536),
+    ("o1", 200_000, 100_000),
+    ("o3", 200_000, 100_000),
+    ("o4-mini", 200_000, 100_000),
+    // Google
+    ("gemini-2.5", 1_048_576, 65_536),
+    ("gemini-2.0-flash", 1_048_576, 8_192),
+    ("gemini-1.5-pro", 2_097_152, 8_192),
+    ("gemini-1.5-flash", 1_048_576, 8_192),
+    // Open-weight models as served by the OpenAI-compatible presets
+    ("deepseek-chat", 64_000, 8_192),
+    ("deepseek-reasoner", 64_000, 8_192),
+    ("llama-3.3", 128_000, 8_192),
+    ("llama-3.1", 128_000, 8_192),
+];
+
+/// Limits for `model_id`, or `None` for a model not in the table.
+pub fn model_limits(model_id: &str) -> Option<ModelLimits> {
+    let id = model_id.to_ascii_lowercase();
+    let id = id.rsplit('/').next().unwrap_or(&id);
+    KNOWN_MODELS
+        .iter()
+        .find(|(prefix, _, _)| id.starts_with(prefix))
+        .map(|&(_, context_window, max_output_tokens)| ModelLimits {
+            context_window,
+            max_output_tokens,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_match_releases_and_router_names() {
+        let sonnet = model_limits("claude-sonnet-4-20250514").unwrap();
+        assert_eq!(sonnet.context_window, 200_000);
+        assert_eq!(sonnet.max_output_tokens, 64_000);
+        assert_eq!(model_limits("anthropic/Claude-Sonnet-4"), Some(sonnet));
+
+        assert_eq!(model_limits("o1-mini").unwrap().max_output_tokens, 65_536);
+        assert_eq!(
+            model_limits("o1-preview").unwrap().max_output_tokens,
+            100_000
+        );
+        assert_eq!(
+            model_limits("claude-3-haiku-20240307")
+                .unwrap()
+                .max_output_tokens,
+            4_096
+        );
+        assert_eq!(model_limits("my-finetune"), None);
+    }
+}
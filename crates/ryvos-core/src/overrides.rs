@@ -0,0 +1,295 @@
+//! Config the agent may change about itself while it runs.
+//!
+//! The `config_get` and `config_set` tools read and write a session's
+//! [`SessionOverrides`] through the shared [`ConfigOverrides`]; the agent
+//! loop applies them on top of [`AppConfig`] at the start of each run. Only
+//! the keys in [`MUTABLE_KEYS`] can be changed, each within bounds, and
+//! security settings and credentials cannot even be read.
+//!
+//! A [`SamplingOverride`] is narrower still: the sampling a person asked for
+//! one run (`ryvos run --temperature`, `/temp`, or a gateway request).
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::config::{AppConfig, ModelConfig};
+use crate::error::{Result, RyvosError};
+use crate::types::{SessionId, ThinkingLevel};
+
+/// Keys the agent may read and set, with what they control.
+pub const MUTABLE_KEYS: &[(&str, &str)] = &[
+    ("agent.max_turns", "Turns per run (1-500)"),
+    ("agent.max_duration_secs", "Seconds per run (10-86400)"),
+    (
+        "model.thinking",
+        "Thinking level: off, low, medium, high or {\"budget_tokens\": N}",
+    ),
+    ("model.temperature", "Sampling temperature (0.0-2.0)"),
+];
+
+/// Key fragments that mark a credential, refused wherever they appear.
+const CREDENTIAL_MARKERS: &[&str] = &["api_key", "token", "password", "secret"];
+
+/// One session's changes to the [`MUTABLE_KEYS`]; `None` keeps the config.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionOverrides {
+    pub max_turns: Option<usize>,
+    pub max_duration_secs: Option<u64>,
+    pub thinking: Option<ThinkingLevel>,
+    pub temperature: Option<f32>,
+}
+
+impl SessionOverrides {
+    /// Apply the model keys to `model`.
+    pub fn apply_model(&self, model: &mut ModelConfig) {
+        if let Some(ref thinking) = self.thinking {
+            model.thinking = thinking.clone();
+        }
+        if let Some(temperature) = self.temperature {
+            model.temperature = temperature;
+        }
+    }
+}
+
+/// Sampling for a single run over the model's config; `None` keeps the
+/// config's value.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SamplingOverride {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl SamplingOverride {
+    /// Validate the values: temperature from 0.0 to 2.0, top_p from 0.0
+    /// to 1.0.
+    pub fn new(temperature: Option<f32>, top_p: Option<f32>) -> Result<Self> {
+        if let Some(t) = temperature.filter(|t| !(0.0..=2.0).contains(t)) {
+            return Err(RyvosError::Config(format!(
+                "invalid temperature {}: expected a number from 0.0 to 2.0",
+                t
+            )));
+        }
+        if let Some(p) = top_p.filter(|p| !(0.0..=1.0).contains(p)) {
+            return Err(RyvosError::Config(format!(
+                "invalid top_p {}: expected a number from 0.0 to 1.0",
+                p
+            )));
+        }
+        Ok(Self { temperature, top_p })
+    }
+
+    /// Whether this changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.top_p.is_none()
+    }
+
+    /// Apply to `model`. Providers still leave out what a model rejects,
+    /// such as temperature while thinking.
+    pub fn apply(&self, model: &mut ModelConfig) {
+        if let Some(temperature) = self.temperature {
+            model.temperature = temperature;
+        }
+        if let Some(top_p) = self.top_p {
+            model.top_p = Some(top_p);
+        }
+    }
+}
+
+/// Refuse keys outside [`MUTABLE_KEYS`], naming security settings and
+/// credentials as off limits.
+pub fn check_key(key: &str) -> Result<()> {
+    let lower = key.to_ascii_lowercase();
+    if lower == "security"
+        || lower.starts_with("security.")
+        || CREDENTIAL_MARKERS.iter().any(|m| lower.contains(m))
+    {
+        return Err(RyvosError::SecurityViolation(format!(
+            "'{}' is off limits: security settings and credentials cannot be read or changed by the agent",
+            key
+        )));
+    }
+    if !MUTABLE_KEYS.iter().any(|(k, _)| *k == key) {
+        return Err(RyvosError::Config(format!(
+            "'{}' cannot be changed at run time (allowed: {})",
+            key,
+            MUTABLE_KEYS
+                .iter()
+                .map(|(k, _)| *k)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Per-session overrides over the config the runtime was started with.
+pub struct ConfigOverrides {
+    base: AppConfig,
+    sessions: Mutex<HashMap<String, SessionOverrides>>,
+}
+
+impl ConfigOverrides {
+    pub fn new(base: &AppConfig) -> Self {
+        Self {
+            base: base.clone(),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The overrides set for `session_id` so far.
+    pub fn for_session(&self, session_id: &SessionId) -> SessionOverrides {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(&session_id.0)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The value `key` has in `session_id`: its override, else the config's.
+    pub fn get(&self, session_id: &SessionId, key: &str) -> Result<Value> {
+        check_key(key)?;
+        let overrides = self.for_session(session_id);
+        let agent = &self.base.agent;
+        let model = &self.base.model;
+        Ok(match key {
+            "agent.max_turns" => overrides.max_turns.unwrap_or(agent.max_turns).into(),
+            "agent.max_duration_secs" => overrides
+                .max_duration_secs
+                .unwrap_or(agent.max_duration_secs)
+                .into(),
+            "model.thinking" => {
+                serde_json::to_value(overrides.thinking.as_ref().unwrap_or(&model.thinking))?
+            }
+            _ => {
+                // f32 to f64 without the float noise (0.7, not 0.699999988)
+                let t = overrides.temperature.unwrap_or(model.temperature) as f64;
+                Value::from((t * 1000.0).round() / 1000.0)
+            }
+        })
+    }
+
+    /// Validate `value` for `key` and set it for `session_id`.
+    pub fn set(&self, session_id: &SessionId, key: &str, value: &Value) -> Result<()> {
+        check_key(key)?;
+        let invalid = |expected: &str| {
+            RyvosError::Config(format!(
+                "invalid value {} for '{}': {}",
+                value, key, expected
+            ))
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        let overrides = sessions.entry(session_id.0.clone()).or_default();
+        match key {
+            "agent.max_turns" => {
+                let n = value
+                    .as_u64()
+                    .filter(|n| (1..=500).contains(n))
+                    .ok_or_else(|| invalid("expected an integer from 1 to 500"))?;
+                overrides.max_turns = Some(n as usize);
+            }
+            "agent.max_duration_secs" => {
+                let secs = value
+                    .as_u64()
+                    .filter(|s| (10..=86_400).contains(s))
+                    .ok_or_else(|| invalid("expected seconds from 10 to 86400"))?;
+                overrides.max_duration_secs = Some(secs);
+            }
+            "model.thinking" => {
+                let level =
+                    serde_json::from_value(value.clone()).map_err(|e| invalid(&e.to_string()))?;
+                overrides.thinking = Some(level);
+            }
+            _ => {
+                let t = value
+                    .as_f64()
+                    .filter(|t| (0.0..=2.0).contains(t))
+                    .ok_or_else(|| invalid("expected a number from 0.0 to 2.0"))?;
+                overrides.temperature = Some(t as f32);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> AppConfig {
+        toml::from_str("[model]\nmodel_id = \"claude-sonnet-4-20250514\"\n").unwrap()
+    }
+
+    #[test]
+    fn overrides_are_per_session_and_fall_back_to_the_config() {
+        let config = config();
+        let overrides = ConfigOverrides::new(&config);
+        let (a, b) = (SessionId::new(), SessionId::new());
+
+        overrides.set(&a, "agent.max_turns", &json!(5)).unwrap();
+        overrides.set(&a, "model.thinking", &json!("high")).unwrap();
+        assert_eq!(overrides.get(&a, "agent.max_turns").unwrap(), json!(5));
+        assert_eq!(overrides.get(&b, "agent.max_turns").unwrap(), json!(25));
+        assert_eq!(overrides.get(&a, "model.thinking").unwrap(), json!("high"));
+
+        let mut model = config.model.clone();
+        overrides.for_session(&a).apply_model(&mut model);
+        assert_eq!(model.thinking, ThinkingLevel::High);
+    }
+
+    #[test]
+    fn values_are_validated() {
+        let overrides = ConfigOverrides::new(&config());
+        let sid = SessionId::new();
+        for (key, value) in [
+            ("agent.max_turns", json!(0)),
+            ("agent.max_turns", json!("ten")),
+            ("agent.max_duration_secs", json!(1)),
+            ("model.temperature", json!(3.5)),
+            ("model.thinking", json!("extreme")),
+        ] {
+            assert!(
+                overrides.set(&sid, key, &value).is_err(),
+                "{} {}",
+                key,
+                value
+            );
+        }
+        assert_eq!(overrides.for_session(&sid), SessionOverrides::default());
+    }
+
+    #[test]
+    fn sampling_override_is_validated_and_applied() {
+        assert!(SamplingOverride::new(Some(2.5), None).is_err());
+        assert!(SamplingOverride::new(None, Some(1.5)).is_err());
+        assert!(SamplingOverride::default().is_empty());
+
+        let sampling = SamplingOverride::new(Some(1.1), Some(0.9)).unwrap();
+        let mut model = config().model;
+        sampling.apply(&mut model);
+        assert_eq!(model.temperature, 1.1);
+        assert_eq!(model.top_p, Some(0.9));
+    }
+
+    #[test]
+    fn security_and_credentials_are_refused() {
+        for key in [
+            "model.api_key",
+            "security",
+            "security.deny_above",
+            "gateway.token",
+        ] {
+            assert!(matches!(
+                check_key(key),
+                Err(RyvosError::SecurityViolation(_))
+            ));
+        }
+        assert!(matches!(
+            check_key("agent.workspace"),
+            Err(RyvosError::Config(_))
+        ));
+    }
+}
@@ -3,7 +3,7 @@ use futures::stream::BoxStream;
 use tokio::sync::mpsc;
 
 use crate::config::ModelConfig;
-use crate::error::Result;
+use crate::error::{Result, RyvosError};
 use crate::types::*;
 
 /// LLM client — multi-provider streaming.
@@ -49,6 +49,23 @@ pub trait Tool: Send + Sync + 'static {
     fn tier(&self) -> crate::security::SecurityTier {
         crate::security::SecurityTier::T1
     }
+
+    /// The input approvers, rules and the audit trail should see for a
+    /// call, where it says less than what the tool will do, such as a call
+    /// naming only an id (default: `None`, the input as given).
+    fn describe_input(
+        &self,
+        _input: &serde_json::Value,
+        _ctx: &ToolContext,
+    ) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Release what the tool holds for a session that has ended, such as
+    /// processes it started (default: nothing).
+    fn end_session(&self, _session_id: &SessionId) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
 }
 
 /// Channel adapter — multi-platform messaging.
@@ -62,6 +79,36 @@ pub trait ChannelAdapter: Send + Sync + 'static {
     /// Send a message to a session.
     fn send(&self, session: &SessionId, content: &MessageContent) -> BoxFuture<'_, Result<()>>;
 
+    /// Send a message that can later be replaced with [`edit`](Self::edit).
+    /// Returns the platform message id, or `None` without sending anything
+    /// when the platform cannot edit messages.
+    fn send_editable(
+        &self,
+        session: &SessionId,
+        content: &MessageContent,
+    ) -> BoxFuture<'_, Result<Option<String>>> {
+        let _ = (session, content);
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Replace the text of a message sent with `send_editable`. Text beyond
+    /// the platform's length limit is sent as follow-up messages.
+    fn edit(
+        &self,
+        session: &SessionId,
+        message_id: &str,
+        content: &MessageContent,
+    ) -> BoxFuture<'_, Result<()>> {
+        let _ = (session, message_id, content);
+        let channel = self.name().to_string();
+        Box::pin(async move {
+            Err(RyvosError::Channel {
+                channel,
+                message: "message editing not supported".into(),
+            })
+        })
+    }
+
     /// Send an approval request with platform-native interactive UI (buttons).
     /// Returns true if the adapter handled it with rich UI, false to fall back to text.
     fn send_approval(
@@ -73,6 +120,12 @@ pub trait ChannelAdapter: Send + Sync + 'static {
         Box::pin(async { Ok(false) })
     }
 
+    /// Whether `send` and `edit` render structured result blocks natively.
+    /// When false the dispatcher replaces them with markdown first.
+    fn renders_structured_results(&self) -> bool {
+        false
+    }
+
     /// Broadcast a message to all known users (e.g., heartbeat alerts).
     /// Falls back to no-op by default.
     fn broadcast(&self, content: &MessageContent) -> BoxFuture<'_, Result<()>> {
@@ -98,4 +151,47 @@ pub trait SessionStore: Send + Sync + 'static {
 
     /// Full-text search across all sessions.
     fn search(&self, query: &str, limit: usize) -> BoxFuture<'_, Result<Vec<SearchResult>>>;
+
+    /// List the sessions with stored history, most recently active first.
+    fn list_sessions(&self, _limit: usize) -> BoxFuture<'_, Result<Vec<StoredSession>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Load the rolling summary of a session, if one was saved.
+    fn load_summary(&self, _sid: &SessionId) -> BoxFuture<'_, Result<Option<String>>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Replace the rolling summary of a session.
+    fn save_summary(&self, _sid: &SessionId, _summary: &str) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Load the scratchpad entries of a session, by key.
+    fn load_scratchpad(
+        &self,
+        _sid: &SessionId,
+    ) -> BoxFuture<'_, Result<std::collections::BTreeMap<String, String>>> {
+        Box::pin(async { Ok(Default::default()) })
+    }
+
+    /// Set one scratchpad entry of a session, replacing any previous value.
+    fn set_scratchpad(
+        &self,
+        _sid: &SessionId,
+        _key: &str,
+        _value: &str,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Whether compaction was turned off for a session (`/nocompact`).
+    fn load_no_compact(&self, _sid: &SessionId) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(async { Ok(false) })
+    }
+
+    /// Record whether compaction is off for a session.
+    fn save_no_compact(&self, _sid: &SessionId, _no_compact: bool) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
 }
@@ -6,8 +6,9 @@ use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::config::RepeatedAnswerAction;
 use crate::goal::GoalEvaluation;
-use crate::security::{ApprovalRequest, SecurityTier};
+use crate::security::{ApprovalRequest, ApprovalTimeoutAction, SecurityTier};
 
 /// Unique session identifier.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -79,12 +80,84 @@ pub struct MessageMetadata {
     /// If true, this message is never pruned during context compaction.
     #[serde(default)]
     pub protected: bool,
+    /// Kept verbatim through every compaction: never pruned, summarized,
+    /// or expired by `protected_ttl`. Set on notes added with `/pin`.
+    #[serde(default)]
+    pub pinned: bool,
     /// Keys from tool output that should be preserved in summaries.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub output_keys: Vec<String>,
     /// Turn number when this message was created (for TTL-based expiry).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub created_at_turn: Option<usize>,
+    /// Channel the message arrived on ("telegram", "api", ...).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_channel: Option<String>,
+    /// Who sent it, as that channel identifies them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Files sent with it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+impl MessageMetadata {
+    /// Only where the message came from, or `None` if that is unknown.
+    /// This is the part stores persist: the compaction fields only mean
+    /// something to the run that set them.
+    pub fn source(&self) -> Option<MessageMetadata> {
+        if self.source_channel.is_none() && self.sender.is_none() && self.attachments.is_empty() {
+            return None;
+        }
+        Some(MessageMetadata {
+            source_channel: self.source_channel.clone(),
+            sender: self.sender.clone(),
+            attachments: self.attachments.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+/// A file sent with a message, kept by reference: the platform's file ID
+/// or URL. The file itself is not downloaded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(rename = "type")]
+    pub kind: AttachmentKind,
+    pub reference: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Image,
+    Audio,
+    Video,
+    File,
+}
+
+impl AttachmentKind {
+    /// The kind a MIME type names; anything but image, audio and video is
+    /// a `File`.
+    pub fn from_mime(mime: &str) -> Self {
+        match mime.split('/').next().unwrap_or_default() {
+            "image" => Self::Image,
+            "audio" => Self::Audio,
+            "video" => Self::Video,
+            _ => Self::File,
+        }
+    }
+}
+
+impl std::fmt::Display for AttachmentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Image => write!(f, "image"),
+            Self::Audio => write!(f, "audio"),
+            Self::Video => write!(f, "video"),
+            Self::File => write!(f, "file"),
+        }
+    }
 }
 
 /// A chat message in the conversation.
@@ -146,6 +219,11 @@ impl ChatMessage {
         self.metadata.as_ref().is_some_and(|m| m.protected)
     }
 
+    /// Check if this message is pinned (kept verbatim forever).
+    pub fn is_pinned(&self) -> bool {
+        self.metadata.as_ref().is_some_and(|m| m.pinned)
+    }
+
     /// Get the phase tag, if any.
     pub fn phase(&self) -> Option<&str> {
         self.metadata.as_ref().and_then(|m| m.phase.as_deref())
@@ -187,7 +265,8 @@ pub enum StopReason {
 }
 
 /// A streaming delta from the LLM.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum StreamDelta {
     /// A chunk of text content.
     TextDelta(String),
@@ -212,6 +291,9 @@ pub enum StreamDelta {
     Usage {
         input_tokens: u64,
         output_tokens: u64,
+        /// Part of `output_tokens` spent on reasoning, when the provider
+        /// reports it separately (0 otherwise).
+        thinking_tokens: u64,
     },
 
     /// Message ID from the API.
@@ -277,6 +359,11 @@ pub struct ToolContext {
     pub config_path: Option<std::path::PathBuf>,
     /// OpenViking client for hierarchical memory tools (None if not configured).
     pub viking_client: Option<Arc<dyn std::any::Any + Send + Sync>>,
+    /// Nesting depth of the agent making the call (0 = top-level agent).
+    pub agent_depth: u32,
+    /// Bus for `ToolProgress` events while a tool runs (None outside an
+    /// agent run).
+    pub event_bus: Option<Arc<crate::event::EventBus>>,
 }
 
 impl std::fmt::Debug for ToolContext {
@@ -289,6 +376,8 @@ impl std::fmt::Debug for ToolContext {
             .field("sandbox_config", &self.sandbox_config)
             .field("config_path", &self.config_path)
             .field("viking_client", &self.viking_client.is_some())
+            .field("agent_depth", &self.agent_depth)
+            .field("event_bus", &self.event_bus.is_some())
             .finish()
     }
 }
@@ -296,6 +385,17 @@ impl std::fmt::Debug for ToolContext {
 /// Trait for spawning sub-agents without circular dependencies.
 pub trait AgentSpawner: Send + Sync + 'static {
     fn spawn(&self, prompt: String) -> BoxFuture<'_, crate::error::Result<String>>;
+
+    /// Spawn a sub-agent running at `depth` under the sub-agent security
+    /// policy. Spawners without a notion of depth fall back to `spawn`.
+    fn spawn_sub_agent(
+        &self,
+        prompt: String,
+        depth: u32,
+    ) -> BoxFuture<'_, crate::error::Result<String>> {
+        let _ = depth;
+        self.spawn(prompt)
+    }
 }
 
 /// An incoming message from any channel.
@@ -307,9 +407,37 @@ pub struct MessageEnvelope {
     pub channel: String,
     pub sender: String,
     pub text: String,
+    /// Files sent with the message.
+    pub attachments: Vec<Attachment>,
     pub timestamp: DateTime<Utc>,
 }
 
+impl MessageEnvelope {
+    /// Metadata recording where the message came from, for its place in
+    /// the session history.
+    pub fn metadata(&self) -> MessageMetadata {
+        MessageMetadata {
+            source_channel: Some(self.channel.clone()),
+            sender: Some(self.sender.clone()),
+            attachments: self.attachments.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// The text to run the agent on. A message with only attachments
+    /// lists them instead, since a model rejects an empty message.
+    pub fn prompt(&self) -> String {
+        if !self.text.trim().is_empty() {
+            return self.text.clone();
+        }
+        self.attachments
+            .iter()
+            .map(|file| format!("[{} attached: {}]", file.kind, file.reference))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 /// Content for outgoing messages.
 #[derive(Debug, Clone)]
 pub enum MessageContent {
@@ -327,6 +455,14 @@ pub struct SearchResult {
     pub rank: f64,
 }
 
+/// A session with stored history, as listed by the session store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSession {
+    pub session_id: String,
+    pub message_count: u64,
+    pub last_active: DateTime<Utc>,
+}
+
 /// Verdict from the Judge system.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Verdict {
@@ -436,6 +572,8 @@ pub enum AgentEvent {
         name: String,
         input: serde_json::Value,
     },
+    /// Incremental output from a running tool (e.g. a command's stdout).
+    ToolProgress { name: String, chunk: String },
     /// Tool execution completed.
     ToolEnd { name: String, result: ToolResult },
     /// Agent turn completed.
@@ -444,8 +582,19 @@ pub enum AgentEvent {
     RunComplete {
         session_id: SessionId,
         total_turns: usize,
+        /// Input tokens billed across every turn of the run.
         input_tokens: u64,
+        /// Output tokens billed across every turn of the run.
         output_tokens: u64,
+        /// Estimated size of the conversation as it now stands, after any
+        /// compaction; what the next turn starts from.
+        context_tokens: u64,
+    },
+    /// A run stopped at its turn limit. It still returns the last text the
+    /// model wrote, which is likely incomplete; `RunComplete` follows.
+    TurnLimitReached {
+        session_id: SessionId,
+        max_turns: usize,
     },
     /// Agent run failed.
     RunError { error: String },
@@ -461,12 +610,28 @@ pub enum AgentEvent {
     ApprovalRequested { request: ApprovalRequest },
     /// Approval resolved (approved or denied).
     ApprovalResolved { request_id: String, approved: bool },
+    /// Nobody answered an approval request within the timeout; `action` is
+    /// what the broker did about it. An escalation is re-posted to
+    /// `target_channel` (None = every channel).
+    ApprovalTimedOut {
+        request: ApprovalRequest,
+        action: ApprovalTimeoutAction,
+        target_channel: Option<String>,
+    },
     /// Tool blocked by security policy.
     ToolBlocked {
         name: String,
         tier: SecurityTier,
         reason: String,
     },
+    /// Prompt-injection marker found in a tool's arguments, or in its
+    /// output when `in_output` is set.
+    PromptInjectionDetected {
+        name: String,
+        label: String,
+        fragment: String,
+        in_output: bool,
+    },
     /// Guardian detected a stall (no progress for N seconds).
     GuardianStall {
         session_id: SessionId,
@@ -484,8 +649,18 @@ pub enum AgentEvent {
         session_id: SessionId,
         used_tokens: u64,
         budget_tokens: u64,
+        /// Run cost and cost budget in US dollars, set for cost alerts.
+        used_usd: Option<f64>,
+        budget_usd: Option<f64>,
         is_hard_stop: bool,
     },
+    /// A run's final answer repeated an earlier one; `action` is what the
+    /// agent loop did about it.
+    GuardianRepeatedAnswer {
+        session_id: SessionId,
+        repeats: usize,
+        action: RepeatedAnswerAction,
+    },
     /// Guardian injected a corrective hint.
     GuardianHint {
         session_id: SessionId,
@@ -495,6 +670,15 @@ pub enum AgentEvent {
     UsageUpdate {
         input_tokens: u64,
         output_tokens: u64,
+        /// Reasoning tokens, already counted in `output_tokens`.
+        thinking_tokens: u64,
+    },
+    /// A run in a session with compaction off started with its context
+    /// close to the model's limit.
+    ContextNearLimit {
+        session_id: SessionId,
+        context_tokens: u64,
+        limit_tokens: u64,
     },
     /// Goal evaluation completed.
     GoalEvaluated {
@@ -561,20 +745,84 @@ pub enum AgentEvent {
         category: String,
         diagnosis: String,
     },
+    /// A channel adapter connected to its platform, at start or after
+    /// reconnecting.
+    ChannelConnected { channel: String },
+    /// A channel adapter lost its platform connection and is reconnecting.
+    ChannelDisconnected { channel: String, reason: String },
+    /// An event from a sub-agent spawned by this run, tagged with the
+    /// sub-agent's own session. Nested sub-agents wrap again.
+    SubAgent {
+        session_id: SessionId,
+        depth: u32,
+        event: Box<AgentEvent>,
+    },
 }
 
 /// Thinking level for extended thinking / reasoning tokens.
+///
+/// Configured either as a named preset (`thinking = "medium"`) or as an
+/// explicit budget (`thinking = { budget_tokens = 4096 }`).
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "ThinkingRepr", into = "ThinkingRepr")]
 pub enum ThinkingLevel {
     #[default]
     Off,
     Low,
     Medium,
     High,
+    /// Explicit thinking-token budget.
+    Custom(u32),
+}
+
+/// On-disk form of [`ThinkingLevel`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ThinkingRepr {
+    Preset(String),
+    Budget { budget_tokens: u32 },
+}
+
+impl TryFrom<ThinkingRepr> for ThinkingLevel {
+    type Error = String;
+
+    fn try_from(repr: ThinkingRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ThinkingRepr::Budget { budget_tokens } => Ok(Self::Custom(budget_tokens)),
+            ThinkingRepr::Preset(name) => match name.as_str() {
+                "off" => Ok(Self::Off),
+                "low" => Ok(Self::Low),
+                "medium" => Ok(Self::Medium),
+                "high" => Ok(Self::High),
+                other => Err(format!(
+                    "unknown thinking level '{}' (expected off/low/medium/high or {{ budget_tokens = N }})",
+                    other
+                )),
+            },
+        }
+    }
+}
+
+impl From<ThinkingLevel> for ThinkingRepr {
+    fn from(level: ThinkingLevel) -> Self {
+        let name = match level {
+            ThinkingLevel::Off => "off",
+            ThinkingLevel::Low => "low",
+            ThinkingLevel::Medium => "medium",
+            ThinkingLevel::High => "high",
+            ThinkingLevel::Custom(budget_tokens) => return Self::Budget { budget_tokens },
+        };
+        Self::Preset(name.to_string())
+    }
 }
 
 impl ThinkingLevel {
+    /// Whether any thinking tokens should be requested. A custom budget of
+    /// zero is the same as `Off`.
+    pub fn is_enabled(&self) -> bool {
+        self.budget_tokens() > 0
+    }
+
     /// Budget tokens for Anthropic extended thinking.
     pub fn budget_tokens(&self) -> u32 {
         match self {
@@ -582,16 +830,21 @@ impl ThinkingLevel {
             Self::Low => 4096,
             Self::Medium => 10240,
             Self::High => 32768,
+            Self::Custom(budget) => *budget,
         }
     }
 
     /// Reasoning effort string for OpenAI o-series models.
+    /// A custom budget maps to the smallest preset that covers it.
     pub fn reasoning_effort(&self) -> &str {
         match self {
-            Self::Off => "none",
+            Self::Off | Self::Custom(0) => "none",
             Self::Low => "low",
             Self::Medium => "medium",
             Self::High => "high",
+            Self::Custom(budget) if *budget <= Self::Low.budget_tokens() => "low",
+            Self::Custom(budget) if *budget <= Self::Medium.budget_tokens() => "medium",
+            Self::Custom(_) => "high",
         }
     }
 }
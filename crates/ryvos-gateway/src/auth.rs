@@ -8,7 +8,14 @@ pub struct AuthResult {
     pub role: ApiKeyRole,
 }
 
-/// Full validation: Bearer header -> api_keys -> legacy token -> legacy password -> anonymous.
+/// Full validation of the credentials presented with a request.
+///
+/// Precedence is `api_keys` > legacy `token` > legacy `password`: a Bearer
+/// header or `?token=` is matched against the API keys first, then the legacy
+/// token; the legacy password, given as `?password=` or a Bearer header, is
+/// only consulted when no legacy token is configured.
+/// Presenting several different credentials at once is rejected instead of
+/// letting one of them silently win. Every secret comparison is constant-time.
 ///
 /// Returns `Some(AuthResult)` on success, `None` on auth failure.
 pub fn validate_auth(
@@ -17,44 +24,31 @@ pub fn validate_auth(
     query_token: Option<&str>,
     query_password: Option<&str>,
 ) -> Option<AuthResult> {
-    // 1. Check bearer against api_keys, then legacy token
-    if let Some(bearer_val) = bearer {
-        for ak in &config.api_keys {
-            if ak.key == bearer_val {
-                return Some(AuthResult {
-                    name: ak.name.clone(),
-                    role: ak.role.clone(),
-                });
-            }
+    let supplied: Vec<&str> = [bearer, query_token, query_password]
+        .into_iter()
+        .flatten()
+        .collect();
+    if supplied.windows(2).any(|pair| pair[0] != pair[1]) {
+        return None; // Conflicting credentials
+    }
+
+    // 1. Bearer header or query token: api_keys, then legacy token
+    if let Some(secret) = bearer.or(query_token) {
+        if let Some(result) = match_secret(config, secret) {
+            return Some(result);
         }
-        // Check legacy token
-        if config.token.as_deref() == Some(bearer_val) {
-            return Some(AuthResult {
-                name: "legacy-token".into(),
-                role: ApiKeyRole::Admin,
-            });
+        if bearer.is_some() {
+            // A Bearer that is neither a key nor the token may be the password
+            return match_password(config, bearer);
         }
-        return None; // Bearer provided but no match
     }
 
-    // 2. Legacy query-string auth
-    if let Some(expected) = &config.token {
-        if query_token == Some(expected.as_str()) {
-            return Some(AuthResult {
-                name: "legacy-token".into(),
-                role: ApiKeyRole::Admin,
-            });
-        }
+    // 2. Legacy password auth
+    if config.token.is_some() {
         return None;
     }
-    if let Some(expected) = &config.password {
-        if query_password == Some(expected.as_str()) {
-            return Some(AuthResult {
-                name: "legacy-password".into(),
-                role: ApiKeyRole::Admin,
-            });
-        }
-        return None;
+    if config.password.is_some() {
+        return match_password(config, query_password);
     }
 
     // 3. No auth configured = full admin access (self-hosted single-user mode).
@@ -70,6 +64,59 @@ pub fn validate_auth(
     }
 }
 
+/// Match a presented secret against the API keys, then the legacy token.
+/// Every key is compared so the time taken doesn't reveal which one matched.
+fn match_secret(config: &GatewayConfig, secret: &str) -> Option<AuthResult> {
+    let mut matched = None;
+    for ak in &config.api_keys {
+        if constant_time_eq(&ak.key, secret) && matched.is_none() {
+            matched = Some(AuthResult {
+                name: ak.name.clone(),
+                role: ak.role.clone(),
+            });
+        }
+    }
+    if matched.is_some() {
+        return matched;
+    }
+    config
+        .token
+        .as_deref()
+        .filter(|expected| constant_time_eq(expected, secret))
+        .map(|_| AuthResult {
+            name: "legacy-token".into(),
+            role: ApiKeyRole::Admin,
+        })
+}
+
+/// Match a presented secret against the legacy password, which only counts
+/// when no legacy token is configured.
+fn match_password(config: &GatewayConfig, given: Option<&str>) -> Option<AuthResult> {
+    let expected = config
+        .password
+        .as_deref()
+        .filter(|_| config.token.is_none())?;
+    given
+        .filter(|given| constant_time_eq(expected, given))
+        .map(|_| AuthResult {
+            name: "legacy-password".into(),
+            role: ApiKeyRole::Admin,
+        })
+}
+
+/// Compare two secrets without short-circuiting: the time taken depends only
+/// on their lengths, never on where the first differing byte is.
+pub fn constant_time_eq(expected: &str, given: &str) -> bool {
+    let (a, b) = (expected.as_bytes(), given.as_bytes());
+    let mut diff = u8::from(a.len() != b.len());
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
 /// Extract token from the query string (?token=...).
 pub fn extract_token_from_query(query: &str) -> Option<&str> {
     for pair in query.split('&') {
@@ -90,17 +137,121 @@ pub fn extract_password_from_query(query: &str) -> Option<&str> {
     None
 }
 
-/// Check if a role has at least viewer-level access.
-pub fn has_viewer_access(role: &ApiKeyRole) -> bool {
-    matches!(
-        role,
-        ApiKeyRole::Viewer | ApiKeyRole::Operator | ApiKeyRole::Admin
-    )
+/// Minimum role for each authenticated HTTP route, keyed by method and the
+/// route pattern as registered on the router.
+///
+/// Enforced by `middleware::require_role`. A route that is neither listed here
+/// nor in [`PUBLIC_ROUTES`] is refused, so new routes must declare a role.
+pub const ROUTE_ROLES: &[(&str, &str, ApiKeyRole)] = &[
+    ("GET", "/ws", ApiKeyRole::Viewer),
+    ("GET", "/api/sessions", ApiKeyRole::Viewer),
+    ("GET", "/api/sessions/{id}/history", ApiKeyRole::Viewer),
+    ("POST", "/api/sessions/{id}/messages", ApiKeyRole::Operator),
+    ("GET", "/api/metrics", ApiKeyRole::Viewer),
+    ("GET", "/api/status", ApiKeyRole::Viewer),
+    ("GET", "/api/runs", ApiKeyRole::Viewer),
+    ("GET", "/api/costs", ApiKeyRole::Viewer),
+    ("GET", "/api/audit", ApiKeyRole::Viewer),
+    ("GET", "/api/audit/stats", ApiKeyRole::Viewer),
+    ("GET", "/api/viking/list", ApiKeyRole::Viewer),
+    ("GET", "/api/viking/read", ApiKeyRole::Viewer),
+    ("GET", "/api/viking/search", ApiKeyRole::Viewer),
+    ("GET", "/api/config", ApiKeyRole::Admin),
+    ("PUT", "/api/config", ApiKeyRole::Admin),
+    ("GET", "/api/channels", ApiKeyRole::Viewer),
+    ("GET", "/api/approvals", ApiKeyRole::Viewer),
+    ("POST", "/api/approvals/{id}/approve", ApiKeyRole::Operator),
+    ("POST", "/api/approvals/{id}/deny", ApiKeyRole::Operator),
+    ("GET", "/api/cron", ApiKeyRole::Viewer),
+    ("POST", "/api/cron", ApiKeyRole::Operator),
+    ("DELETE", "/api/cron/{name}", ApiKeyRole::Operator),
+    ("GET", "/api/budget", ApiKeyRole::Viewer),
+    ("PUT", "/api/budget", ApiKeyRole::Operator),
+    ("GET", "/api/model", ApiKeyRole::Viewer),
+    ("PUT", "/api/model", ApiKeyRole::Operator),
+    ("GET", "/api/models/available", ApiKeyRole::Viewer),
+    ("GET", "/api/integrations", ApiKeyRole::Viewer),
+    (
+        "POST",
+        "/api/integrations/{app}/connect",
+        ApiKeyRole::Operator,
+    ),
+    ("DELETE", "/api/integrations/{app}", ApiKeyRole::Operator),
+    ("GET", "/api/tools", ApiKeyRole::Viewer),
+    ("GET", "/api/skills", ApiKeyRole::Viewer),
+    ("GET", "/api/heartbeat/history", ApiKeyRole::Viewer),
+    ("GET", "/api/safety/lessons", ApiKeyRole::Viewer),
+    ("GET", "/api/decisions", ApiKeyRole::Viewer),
+    ("GET", "/api/failures", ApiKeyRole::Viewer),
+    ("POST", "/api/goals/run", ApiKeyRole::Operator),
+    ("GET", "/api/goals/history", ApiKeyRole::Viewer),
+    ("GET", "/v1/models", ApiKeyRole::Viewer),
+    ("POST", "/v1/embeddings", ApiKeyRole::Operator),
+];
+
+/// Routes that skip gateway auth, by method and route pattern: they are
+/// either open or verify their own credentials (webhook token, WhatsApp
+/// verify token, OAuth state).
+pub const PUBLIC_ROUTES: &[(&str, &str)] = &[
+    ("GET", "/"),
+    ("GET", "/assets/{*path}"),
+    ("GET", "/openapi.json"),
+    ("GET", "/api/health"),
+    ("POST", "/api/hooks/wake"),
+    ("GET", "/api/whatsapp/webhook"),
+    ("POST", "/api/whatsapp/webhook"),
+    ("GET", "/api/integrations/callback"),
+];
+
+/// Minimum role for each WebSocket RPC method. Unknown methods need Viewer
+/// so the connection can still answer them with an error.
+pub const WS_METHOD_ROLES: &[(&str, ApiKeyRole)] = &[
+    ("agent.send", ApiKeyRole::Operator),
+    ("agent.cancel", ApiKeyRole::Operator),
+    ("agent.hint", ApiKeyRole::Operator),
+    ("session.list", ApiKeyRole::Viewer),
+    ("session.history", ApiKeyRole::Viewer),
+    ("session.subscribe", ApiKeyRole::Operator),
+    ("session.resume", ApiKeyRole::Viewer),
+    ("session.working_dir", ApiKeyRole::Operator),
+    ("approval.respond", ApiKeyRole::Operator),
+];
+
+/// Look up the minimum role for an HTTP route. `None` means undeclared.
+pub fn route_role(method: &str, path: &str) -> Option<ApiKeyRole> {
+    ROUTE_ROLES
+        .iter()
+        .find(|(m, p, _)| *m == method && *p == path)
+        .map(|(_, _, role)| role.clone())
 }
 
-/// Check if a role has at least operator-level access.
-pub fn has_operator_access(role: &ApiKeyRole) -> bool {
-    matches!(role, ApiKeyRole::Operator | ApiKeyRole::Admin)
+/// Whether an HTTP route skips gateway auth. Another method on a public
+/// path still needs a declared role.
+pub fn is_public_route(method: &str, path: &str) -> bool {
+    PUBLIC_ROUTES
+        .iter()
+        .any(|(m, p)| *m == method && *p == path)
+}
+
+/// Look up the minimum role for a WebSocket RPC method.
+pub fn ws_method_role(method: &str) -> ApiKeyRole {
+    WS_METHOD_ROLES
+        .iter()
+        .find(|(m, _)| *m == method)
+        .map(|(_, role)| role.clone())
+        .unwrap_or(ApiKeyRole::Viewer)
+}
+
+/// Check if `role` is at least as privileged as `required`.
+pub fn has_role(role: &ApiKeyRole, required: &ApiKeyRole) -> bool {
+    fn rank(role: &ApiKeyRole) -> u8 {
+        match role {
+            ApiKeyRole::Viewer => 0,
+            ApiKeyRole::Operator => 1,
+            ApiKeyRole::Admin => 2,
+        }
+    }
+    rank(role) >= rank(required)
 }
 
 #[cfg(test)]
@@ -119,6 +270,8 @@ mod tests {
             password: password.map(|s| s.to_string()),
             api_keys,
             webhooks: None,
+            cors: None,
+            ..Default::default()
         }
     }
 
@@ -150,6 +303,8 @@ mod tests {
         assert!(validate_auth(&config, None, None, None).is_none());
         assert!(validate_auth(&config, None, None, Some("wrong")).is_none());
         assert!(validate_auth(&config, None, None, Some("pass123")).is_some());
+        assert!(validate_auth(&config, Some("pass123"), None, None).is_some());
+        assert!(validate_auth(&config, Some("wrong"), None, None).is_none());
         // Token param is irrelevant when only password is configured
         assert!(validate_auth(&config, None, Some("pass123"), None).is_none());
     }
@@ -159,6 +314,7 @@ mod tests {
         let config = gateway(Some("tok"), Some("pass"), vec![]);
         assert!(validate_auth(&config, None, Some("tok"), None).is_some());
         assert!(validate_auth(&config, None, None, Some("pass")).is_none());
+        assert!(validate_auth(&config, Some("pass"), None, None).is_none());
         assert!(validate_auth(&config, None, Some("pass"), Some("tok")).is_none());
     }
 
@@ -211,12 +367,12 @@ mod tests {
 
         let viewer = validate_auth(&config, Some("rk_view"), None, None).unwrap();
         assert_eq!(viewer.role, ApiKeyRole::Viewer);
-        assert!(has_viewer_access(&viewer.role));
-        assert!(!has_operator_access(&viewer.role));
+        assert!(has_role(&viewer.role, &ApiKeyRole::Viewer));
+        assert!(!has_role(&viewer.role, &ApiKeyRole::Operator));
 
         let admin = validate_auth(&config, Some("rk_admin"), None, None).unwrap();
         assert_eq!(admin.role, ApiKeyRole::Admin);
-        assert!(has_operator_access(&admin.role));
+        assert!(has_role(&admin.role, &ApiKeyRole::Operator));
     }
 
     #[test]
@@ -235,4 +391,66 @@ mod tests {
         );
         assert_eq!(extract_password_from_query("foo=bar"), None);
     }
+
+    #[test]
+    fn test_query_token_matches_api_keys() {
+        let keys = vec![ApiKeyConfig {
+            name: "web-ui".to_string(),
+            key: "rk_ws".to_string(),
+            role: ApiKeyRole::Viewer,
+        }];
+        let config = gateway(Some("legacy"), None, keys);
+        let result = validate_auth(&config, None, Some("rk_ws"), None).unwrap();
+        assert_eq!(result.name, "web-ui");
+        assert_eq!(result.role, ApiKeyRole::Viewer);
+        assert!(validate_auth(&config, None, Some("rk_wrong"), None).is_none());
+    }
+
+    #[test]
+    fn test_api_keys_take_precedence_over_token() {
+        // A secret configured both as an API key and the legacy token resolves to the key
+        let keys = vec![ApiKeyConfig {
+            name: "viewer".to_string(),
+            key: "shared".to_string(),
+            role: ApiKeyRole::Viewer,
+        }];
+        let config = gateway(Some("shared"), Some("pass"), keys);
+        let result = validate_auth(&config, Some("shared"), None, None).unwrap();
+        assert_eq!(result.role, ApiKeyRole::Viewer);
+        assert!(validate_auth(&config, None, None, Some("pass")).is_none());
+    }
+
+    #[test]
+    fn test_conflicting_credentials_rejected() {
+        let config = gateway(Some("tok"), None, vec![]);
+        assert!(validate_auth(&config, Some("tok"), Some("other"), None).is_none());
+        assert!(validate_auth(&config, Some("tok"), None, Some("other")).is_none());
+        // The same secret sent twice is not a conflict
+        assert!(validate_auth(&config, Some("tok"), Some("tok"), None).is_some());
+
+        let config = gateway(None, None, vec![]);
+        assert!(validate_auth(&config, None, Some("a"), Some("b")).is_none());
+    }
+
+    #[test]
+    fn public_routes_match_method_and_path() {
+        assert!(is_public_route("GET", "/api/health"));
+        assert!(is_public_route("POST", "/api/hooks/wake"));
+        assert!(!is_public_route("POST", "/api/health"));
+        assert!(!is_public_route("DELETE", "/api/hooks/wake"));
+        assert!(!is_public_route("GET", "/api/sessions"));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(constant_time_eq("", ""));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "Secret"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(!constant_time_eq("secret", "secre"));
+        assert!(!constant_time_eq("secret", ""));
+        // Trailing NULs must not pad a prefix into a match
+        assert!(!constant_time_eq("abc\0", "abc"));
+    }
 }
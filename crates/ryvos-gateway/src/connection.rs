@@ -2,47 +2,68 @@
 //!
 //! Each WebSocket connection gets:
 //!
-//! - **Event stream**: A background task subscribes to the EventBus and
-//!   converts ~23 AgentEvent types into ServerEvent frames, forwarding them
-//!   to the client in real time. Auto-subscribes to system events (heartbeat,
-//!   cron, budget, guardian alerts).
+//! - **Event stream**: A background task subscribes to the gateway's
+//!   sequence-numbered event log (see `replay`), which converts ~23 AgentEvent
+//!   types into ServerEvent frames, and forwards them to the client in real
+//!   time. Auto-subscribes to system events (heartbeat, cron, budget,
+//!   guardian alerts).
+//!
+//! - **Resume**: The first frame carries a `resume_token`. After a dropped
+//!   socket, `session.resume` with that token and the last `seq` received
+//!   replays the missed frames for a session.
+//!
+//! - **Slow clients**: A client that falls `event_buffer` frames behind is
+//!   sent a `resync` frame and the frames it missed from the replay buffer,
+//!   or closed with code 1013 under `slow_client = "disconnect"`.
 //!
 //! - **Lane queue**: A per-session FIFO queue (buffer size 32) that serializes
 //!   incoming RPC requests to prevent concurrent mutations on the same session.
+//!   `agent.send` runs then wait for a slot in the gateway's priority lanes
+//!   (see `lane::LaneScheduler`); the lane follows the connection's role and
+//!   an optional `lane` param.
 //!
 //! - **RPC methods**: `agent.send` (send message), `agent.cancel` (cancel run),
-//!   `session.list`, `session.history`, `approval.respond` (approve/deny).
+//!   `agent.hint` (inject a hint into a session's next turn, operator only),
+//!   `session.list`, `session.history`, `session.subscribe` (watch a
+//!   session read-only, operator only), `session.resume` (replay missed
+//!   events),
+//!   `approval.respond` (approve/deny).
+//!
+//! Frames over `max_ws_frame_bytes` close the socket with code 1009.
 //!
 //! The WebSocket protocol uses JSON frames:
 //! - Client sends: `{ "type": "request", "id": "...", "method": "...", "params": {...} }`
 //! - Server responds: `{ "type": "response", "id": "...", "result": {...} }`
-//! - Server pushes: `{ "type": "event", "session_id": "...", "event": {...} }`
+//! - Server pushes: `{ "type": "event", "session_id": "...", "seq": 1, "event": {...} }`
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use axum::extract::ws::{Message, WebSocket};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
 use futures::{SinkExt, StreamExt};
 use tokio::sync::Mutex;
 use tracing::{debug, warn};
 
 use ryvos_agent::{AgentRuntime, ApprovalBroker, SessionManager};
-use ryvos_core::event::EventBus;
+use ryvos_core::config::{ApiKeyRole, SlowClientPolicy};
+use ryvos_core::overrides::SamplingOverride;
 use ryvos_core::security::ApprovalDecision;
 use ryvos_core::traits::SessionStore;
-use ryvos_core::types::{AgentEvent, SessionId};
+use ryvos_core::types::{AgentEvent, MessageMetadata, SessionId};
 
-use crate::lane::LaneQueue;
-use crate::protocol::{ClientFrame, ServerEvent, ServerResponse};
+use crate::auth::{self, AuthResult};
+use crate::lane::{Lane, LaneQueue, LaneScheduler};
+use crate::protocol::{history_entry, ClientFrame, ServerEvent, ServerResponse};
+use crate::replay::EventLog;
+use crate::state::AppState;
 
 /// Handle a single WebSocket connection (axum WebSocket).
-pub async fn handle_connection(
-    ws: WebSocket,
-    runtime: Arc<AgentRuntime>,
-    event_bus: Arc<EventBus>,
-    store: Arc<dyn SessionStore>,
-    session_mgr: Arc<SessionManager>,
-    broker: Arc<ApprovalBroker>,
-) {
+pub async fn handle_connection(ws: WebSocket, state: Arc<AppState>, auth: AuthResult) {
+    let AuthResult {
+        name: key_name,
+        role,
+    } = auth;
+    let event_log = state.event_log.clone();
     let (ws_tx, mut ws_rx) = ws.split();
     let ws_tx = Arc::new(Mutex::new(ws_tx));
 
@@ -50,296 +71,76 @@ pub async fn handle_connection(
     // Auto-subscribe to "*" so system events (heartbeat, cron, budget) are always forwarded
     let subscribed_sessions: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec!["*".to_string()]));
 
-    // Subscribe to event bus and forward events
-    let mut event_rx = event_bus.subscribe();
+    // Hand the client a resume token before any events flow
+    let resume_token = event_log.issue_token(subscribed_sessions.clone());
+    let hello = ServerEvent::new("system".to_string(), "connected")
+        .with_data(serde_json::json!({ "resume_token": resume_token }));
+    if let Ok(json) = serde_json::to_string(&hello) {
+        let _ = ws_tx.lock().await.send(Message::Text(json.into())).await;
+    }
+
+    // Forward sequenced frames from the gateway event log. Frames recorded
+    // before this point are history, not news.
+    let (mut event_rx, heads) = event_log.subscribe_with_heads();
+    // Highest seq per session already delivered, live or by a resume replay
+    let replay_floor: Arc<Mutex<HashMap<String, u64>>> = Arc::new(Mutex::new(heads.clone()));
     let event_ws_tx = ws_tx.clone();
-    let event_subs = subscribed_sessions.clone();
-    let event_task =
-        tokio::spawn(async move {
-            while let Ok(event) = event_rx.recv().await {
-                let server_event =
-                    match &event {
-                        AgentEvent::TextDelta(text) => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(ServerEvent::new(sid, "text_delta").with_text(text.clone()))
-                        }
-                        AgentEvent::ToolStart { name, input } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(
-                                ServerEvent::new(sid, "tool_start")
-                                    .with_tool(name.clone())
-                                    .with_data(input.clone()),
-                            )
-                        }
-                        AgentEvent::ToolEnd { name, result } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(
-                                ServerEvent::new(sid, "tool_end")
-                                    .with_tool(name.clone())
-                                    .with_data(serde_json::json!({
-                                        "content": result.content,
-                                        "is_error": result.is_error,
-                                    })),
-                            )
-                        }
-                        AgentEvent::RunStarted { session_id } => {
-                            Some(ServerEvent::new(session_id.to_string(), "run_started"))
-                        }
-                        AgentEvent::RunComplete {
-                            session_id,
-                            total_turns,
-                            input_tokens,
-                            output_tokens,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "run_complete").with_data(
-                                serde_json::json!({
-                                    "total_turns": total_turns,
-                                    "input_tokens": input_tokens,
-                                    "output_tokens": output_tokens,
-                                }),
-                            ),
-                        ),
-                        AgentEvent::RunError { error } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(
-                                ServerEvent::new(sid, "run_error")
-                                    .with_data(serde_json::json!({ "error": error })),
-                            )
-                        }
-                        AgentEvent::ApprovalRequested { request } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(ServerEvent::new(sid, "approval_requested").with_data(
-                                serde_json::json!({
-                                    "id": request.id,
-                                    "tool_name": request.tool_name,
-                                    "tier": request.tier.to_string(),
-                                    "input_summary": request.input_summary,
-                                    "session_id": request.session_id,
-                                }),
-                            ))
-                        }
-                        AgentEvent::ToolBlocked { name, tier, reason } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(
-                                ServerEvent::new(sid, "tool_blocked")
-                                    .with_tool(name.clone())
-                                    .with_data(serde_json::json!({
-                                        "tier": tier.to_string(),
-                                        "reason": reason,
-                                    })),
-                            )
-                        }
-                        AgentEvent::UsageUpdate {
-                            input_tokens,
-                            output_tokens,
-                        } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            let sid = subs.last().unwrap().clone();
-                            Some(ServerEvent::new(sid, "usage_update").with_data(
-                                serde_json::json!({
-                                    "input_tokens": input_tokens,
-                                    "output_tokens": output_tokens,
-                                }),
-                            ))
-                        }
-                        AgentEvent::BudgetWarning {
-                            session_id,
-                            spent_cents,
-                            budget_cents,
-                            utilization_pct,
-                        } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            Some(
-                                ServerEvent::new(session_id.to_string(), "budget_warning")
-                                    .with_data(serde_json::json!({
-                                        "spent_cents": spent_cents,
-                                        "budget_cents": budget_cents,
-                                        "utilization_pct": utilization_pct,
-                                    })),
-                            )
-                        }
-                        AgentEvent::BudgetExceeded {
-                            session_id,
-                            spent_cents,
-                            budget_cents,
-                        } => {
-                            let subs = event_subs.lock().await;
-                            if subs.is_empty() {
-                                continue;
-                            }
-                            Some(
-                                ServerEvent::new(session_id.to_string(), "budget_exceeded")
-                                    .with_data(serde_json::json!({
-                                        "spent_cents": spent_cents,
-                                        "budget_cents": budget_cents,
-                                    })),
-                            )
-                        }
-                        AgentEvent::HeartbeatFired { timestamp } => Some(
-                            ServerEvent::new("system".to_string(), "heartbeat_fired").with_data(
-                                serde_json::json!({ "timestamp": timestamp.to_rfc3339() }),
-                            ),
-                        ),
-                        AgentEvent::HeartbeatOk {
-                            session_id,
-                            response_chars,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "heartbeat_ok")
-                                .with_data(serde_json::json!({ "response_chars": response_chars })),
-                        ),
-                        AgentEvent::HeartbeatAlert {
-                            session_id,
-                            message,
-                            target_channel,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "heartbeat_alert").with_data(
-                                serde_json::json!({
-                                    "message": message,
-                                    "target_channel": target_channel,
-                                }),
-                            ),
-                        ),
-                        AgentEvent::CronFired { job_id, .. } => Some(
-                            ServerEvent::new("system".to_string(), "cron_fired")
-                                .with_data(serde_json::json!({ "job_name": job_id })),
-                        ),
-                        AgentEvent::CronJobComplete { name, .. } => Some(
-                            ServerEvent::new("system".to_string(), "cron_complete")
-                                .with_data(serde_json::json!({ "job_name": name })),
-                        ),
-                        AgentEvent::GuardianStall { session_id, .. } => {
-                            Some(ServerEvent::new(session_id.to_string(), "guardian_stall"))
-                        }
-                        AgentEvent::GuardianDoomLoop { session_id, .. } => Some(ServerEvent::new(
-                            session_id.to_string(),
-                            "guardian_doom_loop",
-                        )),
-                        AgentEvent::GuardianBudgetAlert { session_id, .. } => Some(
-                            ServerEvent::new(session_id.to_string(), "guardian_budget_alert"),
-                        ),
-                        AgentEvent::GraphGenerated {
-                            session_id,
-                            node_count,
-                            edge_count,
-                            evolution_cycle,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "graph_generated").with_data(
-                                serde_json::json!({
-                                    "node_count": node_count,
-                                    "edge_count": edge_count,
-                                    "evolution_cycle": evolution_cycle,
-                                }),
-                            ),
-                        ),
-                        AgentEvent::NodeComplete {
-                            session_id,
-                            node_id,
-                            succeeded,
-                            elapsed_ms,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "node_complete").with_data(
-                                serde_json::json!({
-                                    "node_id": node_id,
-                                    "succeeded": succeeded,
-                                    "elapsed_ms": elapsed_ms,
-                                }),
-                            ),
-                        ),
-                        AgentEvent::EvolutionTriggered {
-                            session_id,
-                            reason,
-                            cycle,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "evolution_triggered")
-                                .with_data(serde_json::json!({
-                                    "reason": reason,
-                                    "cycle": cycle,
-                                })),
-                        ),
-                        AgentEvent::SemanticFailureCaptured {
-                            session_id,
-                            node_id,
-                            category,
-                            diagnosis,
-                        } => Some(
-                            ServerEvent::new(session_id.to_string(), "semantic_failure").with_data(
-                                serde_json::json!({
-                                    "node_id": node_id,
-                                    "category": category,
-                                    "diagnosis": diagnosis,
-                                }),
-                            ),
-                        ),
-                        AgentEvent::TurnComplete { .. } => None,
-                        AgentEvent::ApprovalResolved { .. } => None,
-                        AgentEvent::GuardianHint { .. }
-                        | AgentEvent::GoalEvaluated { .. }
-                        | AgentEvent::DecisionMade { .. }
-                        | AgentEvent::JudgeVerdict { .. } => None,
-                    };
-
-                if let Some(evt) = server_event {
-                    if let Ok(json) = serde_json::to_string(&evt) {
-                        let mut tx = event_ws_tx.lock().await;
-                        if tx.send(Message::Text(json.into())).await.is_err() {
-                            break;
-                        }
+    let event_floor = replay_floor.clone();
+    let forward_log = event_log.clone();
+    let slow_client = state.config.slow_client;
+    let event_task = tokio::spawn(async move {
+        loop {
+            let frames =
+                match next_frames(&mut event_rx, &forward_log, &event_floor, slow_client).await {
+                    Forward::Frames(frames) => frames,
+                    Forward::TooSlow(missed) => {
+                        let close = CloseFrame {
+                            code: close_code::AGAIN,
+                            reason: format!("client too slow, missed {} events", missed).into(),
+                        };
+                        let _ = event_ws_tx
+                            .lock()
+                            .await
+                            .send(Message::Close(Some(close)))
+                            .await;
+                        break;
+                    }
+                    Forward::Closed => break,
+                };
+            for evt in frames {
+                if let Ok(json) = serde_json::to_string(&evt) {
+                    let mut tx = event_ws_tx.lock().await;
+                    if tx.send(Message::Text(json.into())).await.is_err() {
+                        return;
                     }
                 }
             }
-        });
+        }
+    });
 
     // Create a lane for serial request processing
     let (lane, mut lane_rx) = LaneQueue::new(32);
 
     // Spawn lane processor
-    let lane_runtime = runtime.clone();
-    let lane_store = store.clone();
-    let lane_session_mgr = session_mgr.clone();
-    let lane_subs = subscribed_sessions.clone();
-    let lane_broker = broker.clone();
+    let ctx = ConnectionContext {
+        runtime: state.runtime.clone(),
+        store: state.store.clone(),
+        session_mgr: state.session_mgr.clone(),
+        subscribed: subscribed_sessions.clone(),
+        broker: state.broker.clone(),
+        event_log: event_log.clone(),
+        replay_floor,
+        connect_heads: heads,
+        lanes: state.lanes.clone(),
+        role: role.clone(),
+        key_name,
+    };
     let lane_task = tokio::spawn(async move {
         while let Some(item) = lane_rx.recv().await {
-            let result = process_request(
-                &item.method,
-                &item.params,
-                &lane_runtime,
-                &lane_store,
-                &lane_session_mgr,
-                &lane_subs,
-                &lane_broker,
-            )
-            .await;
+            let result = match check_method_role(&item.method, &role) {
+                Err(denied) => denied,
+                Ok(()) => process_request(&item.method, &item.params, &ctx).await,
+            };
             let _ = item.respond.send(result);
         }
     });
@@ -348,6 +149,16 @@ pub async fn handle_connection(
     while let Some(msg) = ws_rx.next().await {
         let msg = match msg {
             Ok(m) => m,
+            Err(e) if is_oversized(&e) => {
+                let limit = state.config.max_ws_frame_bytes;
+                warn!(limit, "Closing WebSocket: message over max_ws_frame_bytes");
+                let close = CloseFrame {
+                    code: close_code::SIZE,
+                    reason: format!("message exceeds max_ws_frame_bytes ({} bytes)", limit).into(),
+                };
+                let _ = ws_tx.lock().await.send(Message::Close(Some(close))).await;
+                break;
+            }
             Err(e) => {
                 debug!(error = %e, "WebSocket read error");
                 break;
@@ -398,18 +209,440 @@ pub async fn handle_connection(
 
     event_task.abort();
     lane_task.abort();
+    event_log.release_token(&resume_token);
     debug!("Connection closed");
 }
 
+/// What the event task does after one receive from the event log.
+enum Forward {
+    /// Send these frames, in order (possibly none).
+    Frames(Vec<ServerEvent>),
+    /// The client lagged by this many frames and the policy is to drop it.
+    TooSlow(u64),
+    /// The event log shut down.
+    Closed,
+}
+
+/// Receive the next frame for a connection. Frames at or below the
+/// connection's delivered seq for their session are skipped. On lag the
+/// client is either dropped or resynced from the replay buffer, per
+/// `[gateway] slow_client`.
+async fn next_frames(
+    rx: &mut tokio::sync::broadcast::Receiver<ServerEvent>,
+    event_log: &EventLog,
+    delivered: &Mutex<HashMap<String, u64>>,
+    policy: SlowClientPolicy,
+) -> Forward {
+    let frames = match rx.recv().await {
+        Ok(evt) => vec![evt],
+        Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+            warn!(
+                missed,
+                ?policy,
+                "WebSocket client fell behind the event stream"
+            );
+            if policy == SlowClientPolicy::Disconnect {
+                return Forward::TooSlow(missed);
+            }
+            let floors = delivered.lock().await.clone();
+            event_log.resync(&floors, missed)
+        }
+        Err(tokio::sync::broadcast::error::RecvError::Closed) => return Forward::Closed,
+    };
+    let mut delivered = delivered.lock().await;
+    let frames = frames
+        .into_iter()
+        .filter(|evt| {
+            let Some(seq) = evt.seq else {
+                return true;
+            };
+            let floor = delivered.entry(evt.session_id.clone()).or_insert(0);
+            if seq <= *floor {
+                return false;
+            }
+            *floor = seq;
+            true
+        })
+        .collect();
+    Forward::Frames(frames)
+}
+
+/// Whether a read failed because a frame or message went over the size
+/// limits set on the upgrade (`max_ws_frame_bytes`).
+fn is_oversized(error: &axum::Error) -> bool {
+    let source = std::error::Error::source(error);
+    matches!(
+        source.and_then(|e| e.downcast_ref::<tungstenite::Error>()),
+        Some(tungstenite::Error::Capacity(_))
+    )
+}
+
+/// The `calls` of an `approval.respond` to a grouped request: the numbers
+/// (from 1) of the calls to run. `None` when absent, which approves them all.
+pub fn call_numbers(params: &serde_json::Value) -> Option<Vec<usize>> {
+    params["calls"].as_array().map(|calls| {
+        calls
+            .iter()
+            .filter_map(serde_json::Value::as_u64)
+            .map(|n| n as usize)
+            .collect()
+    })
+}
+
+/// Convert an `AgentEvent` into the frame pushed to Web UI clients.
+///
+/// Events without their own session id are attributed to `current`.
+pub fn to_server_event(event: &AgentEvent, current: &str) -> Option<ServerEvent> {
+    match event {
+        AgentEvent::TextDelta(text) => {
+            let sid = current.to_string();
+            Some(ServerEvent::new(sid, "text_delta").with_text(text.clone()))
+        }
+        AgentEvent::ToolStart { name, input } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "tool_start")
+                    .with_tool(name.clone())
+                    .with_data(input.clone()),
+            )
+        }
+        AgentEvent::ToolProgress { name, chunk } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "tool_progress")
+                    .with_tool(name.clone())
+                    .with_text(chunk.clone()),
+            )
+        }
+        AgentEvent::ToolEnd { name, result } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "tool_end")
+                    .with_tool(name.clone())
+                    .with_data(serde_json::json!({
+                        "content": result.content,
+                        "is_error": result.is_error,
+                    })),
+            )
+        }
+        AgentEvent::RunStarted { session_id } => {
+            Some(ServerEvent::new(session_id.to_string(), "run_started"))
+        }
+        AgentEvent::RunComplete {
+            session_id,
+            total_turns,
+            input_tokens,
+            output_tokens,
+            context_tokens,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "run_complete").with_data(serde_json::json!({
+                "total_turns": total_turns,
+                "input_tokens": input_tokens,
+                "output_tokens": output_tokens,
+                "context_tokens": context_tokens,
+            })),
+        ),
+        AgentEvent::TurnLimitReached {
+            session_id,
+            max_turns,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "turn_limit_reached")
+                .with_data(serde_json::json!({ "max_turns": max_turns })),
+        ),
+        AgentEvent::RunError { error } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "run_error").with_data(serde_json::json!({ "error": error })),
+            )
+        }
+        AgentEvent::ApprovalRequested { request } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "approval_requested").with_data(serde_json::json!({
+                    "id": request.id,
+                    "tool_name": request.tool_name,
+                    "tier": request.tier.to_string(),
+                    "input_summary": request.input_summary,
+                    "input_detail": request.input_detail,
+                    "session_id": request.session_id,
+                })),
+            )
+        }
+        AgentEvent::ApprovalTimedOut {
+            request,
+            action,
+            target_channel,
+        } => Some(
+            ServerEvent::new(request.session_id.clone(), "approval_timed_out")
+                .with_tool(request.tool_name.clone())
+                .with_data(serde_json::json!({
+                    "id": request.id,
+                    "action": action.to_string(),
+                    "target_channel": target_channel,
+                })),
+        ),
+        AgentEvent::ToolBlocked { name, tier, reason } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "tool_blocked")
+                    .with_tool(name.clone())
+                    .with_data(serde_json::json!({
+                        "tier": tier.to_string(),
+                        "reason": reason,
+                    })),
+            )
+        }
+        AgentEvent::PromptInjectionDetected {
+            name,
+            label,
+            fragment,
+            in_output,
+        } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "prompt_injection")
+                    .with_tool(name.clone())
+                    .with_data(serde_json::json!({
+                        "label": label,
+                        "fragment": fragment,
+                        "in_output": in_output,
+                    })),
+            )
+        }
+        AgentEvent::UsageUpdate {
+            input_tokens,
+            output_tokens,
+            thinking_tokens,
+        } => {
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "usage_update").with_data(serde_json::json!({
+                    "input_tokens": input_tokens,
+                    "output_tokens": output_tokens,
+                    "thinking_tokens": thinking_tokens,
+                })),
+            )
+        }
+        AgentEvent::ContextNearLimit {
+            session_id,
+            context_tokens,
+            limit_tokens,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "context_near_limit").with_data(
+                serde_json::json!({
+                    "context_tokens": context_tokens,
+                    "limit_tokens": limit_tokens,
+                }),
+            ),
+        ),
+        AgentEvent::BudgetWarning {
+            session_id,
+            spent_cents,
+            budget_cents,
+            utilization_pct,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "budget_warning").with_data(
+                serde_json::json!({
+                    "spent_cents": spent_cents,
+                    "budget_cents": budget_cents,
+                    "utilization_pct": utilization_pct,
+                }),
+            ),
+        ),
+        AgentEvent::BudgetExceeded {
+            session_id,
+            spent_cents,
+            budget_cents,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "budget_exceeded").with_data(
+                serde_json::json!({
+                    "spent_cents": spent_cents,
+                    "budget_cents": budget_cents,
+                }),
+            ),
+        ),
+        AgentEvent::HeartbeatFired { timestamp } => Some(
+            ServerEvent::new("system".to_string(), "heartbeat_fired")
+                .with_data(serde_json::json!({ "timestamp": timestamp.to_rfc3339() })),
+        ),
+        AgentEvent::HeartbeatOk {
+            session_id,
+            response_chars,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "heartbeat_ok")
+                .with_data(serde_json::json!({ "response_chars": response_chars })),
+        ),
+        AgentEvent::HeartbeatAlert {
+            session_id,
+            message,
+            target_channel,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "heartbeat_alert").with_data(
+                serde_json::json!({
+                    "message": message,
+                    "target_channel": target_channel,
+                }),
+            ),
+        ),
+        AgentEvent::CronFired { job_id, .. } => Some(
+            ServerEvent::new("system".to_string(), "cron_fired")
+                .with_data(serde_json::json!({ "job_name": job_id })),
+        ),
+        AgentEvent::CronJobComplete { name, .. } => Some(
+            ServerEvent::new("system".to_string(), "cron_complete")
+                .with_data(serde_json::json!({ "job_name": name })),
+        ),
+        AgentEvent::ChannelConnected { channel } => Some(
+            ServerEvent::new("system".to_string(), "channel_connected")
+                .with_data(serde_json::json!({ "channel": channel })),
+        ),
+        AgentEvent::ChannelDisconnected { channel, reason } => Some(
+            ServerEvent::new("system".to_string(), "channel_disconnected")
+                .with_data(serde_json::json!({ "channel": channel, "reason": reason })),
+        ),
+        AgentEvent::GuardianStall { session_id, .. } => {
+            Some(ServerEvent::new(session_id.to_string(), "guardian_stall"))
+        }
+        AgentEvent::GuardianDoomLoop { session_id, .. } => Some(ServerEvent::new(
+            session_id.to_string(),
+            "guardian_doom_loop",
+        )),
+        AgentEvent::GuardianBudgetAlert { session_id, .. } => Some(ServerEvent::new(
+            session_id.to_string(),
+            "guardian_budget_alert",
+        )),
+        AgentEvent::GuardianRepeatedAnswer {
+            session_id,
+            repeats,
+            action,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "guardian_repeated_answer")
+                .with_data(serde_json::json!({ "repeats": repeats, "action": action.to_string() })),
+        ),
+        AgentEvent::GraphGenerated {
+            session_id,
+            node_count,
+            edge_count,
+            evolution_cycle,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "graph_generated").with_data(
+                serde_json::json!({
+                    "node_count": node_count,
+                    "edge_count": edge_count,
+                    "evolution_cycle": evolution_cycle,
+                }),
+            ),
+        ),
+        AgentEvent::NodeComplete {
+            session_id,
+            node_id,
+            succeeded,
+            elapsed_ms,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "node_complete").with_data(
+                serde_json::json!({
+                    "node_id": node_id,
+                    "succeeded": succeeded,
+                    "elapsed_ms": elapsed_ms,
+                }),
+            ),
+        ),
+        AgentEvent::EvolutionTriggered {
+            session_id,
+            reason,
+            cycle,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "evolution_triggered").with_data(
+                serde_json::json!({
+                    "reason": reason,
+                    "cycle": cycle,
+                }),
+            ),
+        ),
+        AgentEvent::SemanticFailureCaptured {
+            session_id,
+            node_id,
+            category,
+            diagnosis,
+        } => Some(
+            ServerEvent::new(session_id.to_string(), "semantic_failure").with_data(
+                serde_json::json!({
+                    "node_id": node_id,
+                    "category": category,
+                    "diagnosis": diagnosis,
+                }),
+            ),
+        ),
+        AgentEvent::SubAgent {
+            session_id,
+            depth,
+            event,
+        } => {
+            // Shown under the parent run; the nested frame keeps its own kind
+            let inner = to_server_event(event, &session_id.0)?;
+            let sid = current.to_string();
+            Some(
+                ServerEvent::new(sid, "sub_agent").with_data(serde_json::json!({
+                    "session_id": session_id,
+                    "depth": depth,
+                    "event": inner.event,
+                })),
+            )
+        }
+        AgentEvent::TurnComplete { .. } => None,
+        AgentEvent::ApprovalResolved { .. } => None,
+        AgentEvent::GuardianHint { .. }
+        | AgentEvent::GoalEvaluated { .. }
+        | AgentEvent::DecisionMade { .. }
+        | AgentEvent::JudgeVerdict { .. } => None,
+    }
+}
+
+/// Reject methods the connection's role may not call (see `auth::WS_METHOD_ROLES`).
+fn check_method_role(method: &str, role: &ApiKeyRole) -> Result<(), serde_json::Value> {
+    let required = auth::ws_method_role(method);
+    if auth::has_role(role, &required) {
+        Ok(())
+    } else {
+        warn!(method, ?role, "WebSocket method refused for role");
+        Err(serde_json::json!({
+            "error": format!("{:?} role required", required).to_lowercase(),
+            "code": 403,
+        }))
+    }
+}
+
+/// Per-connection handles used by the lane processor.
+struct ConnectionContext {
+    runtime: Arc<AgentRuntime>,
+    store: Arc<dyn SessionStore>,
+    session_mgr: Arc<SessionManager>,
+    subscribed: Arc<Mutex<Vec<String>>>,
+    broker: Arc<ApprovalBroker>,
+    event_log: Arc<EventLog>,
+    replay_floor: Arc<Mutex<HashMap<String, u64>>>,
+    /// Highest seq per session when the connection subscribed; frames up
+    /// to it never reach the connection live.
+    connect_heads: HashMap<String, u64>,
+    lanes: LaneScheduler,
+    role: ApiKeyRole,
+    /// API key the connection authenticated with; the sender of its messages.
+    key_name: String,
+}
+
 async fn process_request(
     method: &str,
     params: &serde_json::Value,
-    runtime: &AgentRuntime,
-    store: &Arc<dyn SessionStore>,
-    session_mgr: &SessionManager,
-    subscribed: &Mutex<Vec<String>>,
-    broker: &ApprovalBroker,
+    ctx: &ConnectionContext,
 ) -> serde_json::Value {
+    let ConnectionContext {
+        runtime,
+        store,
+        session_mgr,
+        subscribed,
+        broker,
+        ..
+    } = ctx;
     match method {
         "agent.send" => {
             let session_id_str = params["session_id"].as_str().unwrap_or("");
@@ -418,9 +651,21 @@ async fn process_request(
             if message.is_empty() {
                 return serde_json::json!({"error": "message is required"});
             }
+            let sampling = match SamplingOverride::new(
+                params["temperature"].as_f64().map(|t| t as f32),
+                params["top_p"].as_f64().map(|p| p as f32),
+            ) {
+                Ok(sampling) => sampling,
+                Err(e) => return serde_json::json!({"error": e.to_string()}),
+            };
 
+            let channel = if session_id_str.is_empty() {
+                "websocket"
+            } else {
+                "webui"
+            };
             let session_id = if session_id_str.is_empty() {
-                let sid = session_mgr.get_or_create("ws:default", "websocket");
+                let sid = session_mgr.get_or_create("ws:default", channel);
                 // Auto-subscribe
                 let mut subs = subscribed.lock().await;
                 if !subs.contains(&sid.to_string()) {
@@ -428,7 +673,7 @@ async fn process_request(
                 }
                 sid
             } else {
-                let sid = session_mgr.get_or_create(session_id_str, "webui");
+                let sid = session_mgr.get_or_create(session_id_str, channel);
                 let mut subs = subscribed.lock().await;
                 if !subs.contains(&sid.to_string()) {
                     subs.push(sid.to_string());
@@ -436,7 +681,18 @@ async fn process_request(
                 sid
             };
 
-            match runtime.run(&session_id, message).await {
+            let lane = Lane::resolve(&ctx.role, params["lane"].as_str());
+            let _permit = ctx.lanes.acquire(lane).await;
+            let source = MessageMetadata {
+                source_channel: Some(channel.to_string()),
+                sender: Some(ctx.key_name.clone()),
+                ..Default::default()
+            };
+            runtime.set_run_sampling(&session_id, sampling);
+            match runtime
+                .run_with_metadata(&session_id, message, source)
+                .await
+            {
                 Ok(response) => serde_json::json!({
                     "session_id": session_id.to_string(),
                     "response": response,
@@ -451,6 +707,19 @@ async fn process_request(
             runtime.cancel_token().cancel();
             serde_json::json!({"cancelled": true})
         }
+        "agent.hint" => {
+            let session_id = params["session_id"].as_str().unwrap_or("");
+            let text = params["text"].as_str().unwrap_or("");
+            if session_id.is_empty() || text.is_empty() {
+                return serde_json::json!({"error": "session_id and text are required"});
+            }
+            // Resolve the key the same way `agent.send` does, so the hint
+            // reaches that session's next turn and no other run.
+            let sid = session_mgr.get_or_create(session_id, "webui");
+            debug!(session_id, "Operator hint queued");
+            runtime.queue_hint(&sid, text.to_string());
+            serde_json::json!({"session_id": sid.to_string(), "queued": true})
+        }
         "session.list" => {
             let keys = session_mgr.list();
             serde_json::json!({"sessions": keys})
@@ -465,21 +734,90 @@ async fn process_request(
 
             match store.load_history(&session_id, limit).await {
                 Ok(messages) => {
-                    let msgs: Vec<serde_json::Value> = messages
-                        .iter()
-                        .map(|m| {
-                            serde_json::json!({
-                                "role": m.role,
-                                "text": m.text(),
-                                "timestamp": m.timestamp,
-                            })
-                        })
-                        .collect();
+                    let msgs: Vec<serde_json::Value> = messages.iter().map(history_entry).collect();
                     serde_json::json!({"messages": msgs})
                 }
                 Err(e) => serde_json::json!({"error": e.to_string()}),
             }
         }
+        "session.subscribe" => {
+            // Watch a session's events without sending to it, and let this
+            // connection's resume token cover it
+            let session_id = params["session_id"].as_str().unwrap_or("");
+            if session_id.is_empty() {
+                return serde_json::json!({"error": "session_id is required"});
+            }
+            let mut subs = subscribed.lock().await;
+            if !subs.iter().any(|s| s == session_id) {
+                subs.push(session_id.to_string());
+            }
+            // Frames after this seq reach the connection live; resuming from
+            // it replays the rest
+            let head = ctx.connect_heads.get(session_id).copied().unwrap_or(0);
+            serde_json::json!({"session_id": session_id, "subscribed": true, "last_seq": head})
+        }
+        "session.resume" => {
+            let token = params["resume_token"].as_str().unwrap_or("");
+            let session_id = params["session_id"].as_str().unwrap_or("");
+            if token.is_empty() || session_id.is_empty() {
+                return serde_json::json!({"error": "resume_token and session_id are required"});
+            }
+            let last_seq = params["last_seq"].as_u64().unwrap_or(0);
+            // Hold the floor while replaying, so the live forwarder neither
+            // resends a replayed frame nor has one it sent replayed again.
+            let mut floors = ctx.replay_floor.lock().await;
+            match ctx.event_log.resume(token, session_id, last_seq).await {
+                Ok(events) => {
+                    let head = ctx.connect_heads.get(session_id).copied().unwrap_or(0);
+                    let floor = floors.entry(session_id.to_string()).or_insert(0);
+                    let events: Vec<ServerEvent> = events
+                        .into_iter()
+                        .filter(|e| e.seq.is_none_or(|seq| seq <= head || seq > *floor))
+                        .collect();
+                    let replayed = events.last().and_then(|e| e.seq).unwrap_or(last_seq);
+                    *floor = (*floor).max(replayed);
+                    let delivered = *floor;
+                    drop(floors);
+                    let mut subs = subscribed.lock().await;
+                    if !subs.iter().any(|s| s == session_id) {
+                        subs.push(session_id.to_string());
+                    }
+                    serde_json::json!({
+                        "session_id": session_id,
+                        "last_seq": delivered,
+                        "events": events,
+                    })
+                }
+                Err(e) => serde_json::json!({"error": e.to_string()}),
+            }
+        }
+        "session.working_dir" => {
+            let key = match params["session_id"].as_str().unwrap_or("") {
+                "" => "ws:default",
+                key => key,
+            };
+            let channel = if key == "ws:default" {
+                "websocket"
+            } else {
+                "webui"
+            };
+            let session_id = session_mgr.get_or_create(key, channel);
+            // The runtime's manager is the one its runs read working dirs from
+            let sessions = runtime.session_manager();
+            if let Some(dir) = params["dir"].as_str().filter(|d| !d.is_empty()) {
+                if let Err(e) = sessions.set_working_dir(&session_id, dir) {
+                    return serde_json::json!({"session_id": key, "error": e.to_string()});
+                }
+            }
+            let working_dir = sessions
+                .working_dir(&session_id)
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default();
+            serde_json::json!({
+                "session_id": key,
+                "working_dir": working_dir.display().to_string(),
+            })
+        }
         "approval.respond" => {
             let request_id = params["request_id"].as_str().unwrap_or("");
             if request_id.is_empty() {
@@ -488,7 +826,10 @@ async fn process_request(
             let approved = params["approved"].as_bool().unwrap_or(false);
             let reason = params["reason"].as_str().unwrap_or("denied").to_string();
             let decision = if approved {
-                ApprovalDecision::Approved
+                match call_numbers(params) {
+                    Some(numbers) => ApprovalDecision::approve_calls(&numbers),
+                    None => ApprovalDecision::approve_with(params["confirm"].as_str()),
+                }
             } else {
                 ApprovalDecision::Denied { reason }
             };
@@ -501,3 +842,291 @@ async fn process_request(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::event::EventBus;
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient};
+    use ryvos_tools::ToolRegistry;
+
+    fn runtime() -> AgentRuntime {
+        runtime_with(test_config())
+    }
+
+    fn runtime_with(config: ryvos_core::config::AppConfig) -> AgentRuntime {
+        AgentRuntime::new(
+            config,
+            Arc::new(MockLlmClient::new()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        )
+    }
+
+    fn context(runtime: Arc<AgentRuntime>, event_log: Arc<EventLog>) -> ConnectionContext {
+        ConnectionContext {
+            runtime,
+            store: Arc::new(InMemorySessionStore::new()),
+            session_mgr: Arc::new(SessionManager::new()),
+            subscribed: Arc::new(Mutex::new(vec!["*".to_string()])),
+            broker: Arc::new(ApprovalBroker::new(Arc::new(EventBus::default()))),
+            event_log,
+            replay_floor: Arc::new(Mutex::new(HashMap::new())),
+            connect_heads: HashMap::new(),
+            lanes: LaneScheduler::new(&Default::default()),
+            role: ApiKeyRole::Admin,
+            key_name: "admin".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn hint_reaches_the_named_session_only() {
+        let llm = MockLlmClient::new()
+            .with_text_response("one")
+            .with_text_response("two");
+        let runtime = Arc::new(AgentRuntime::new(
+            test_config(),
+            Arc::new(llm.clone()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(EventBus::default()),
+        ));
+        let ctx = context(runtime, Arc::new(EventLog::default()));
+        let params = serde_json::json!({"session_id": "s1", "text": "try the other branch"});
+        let resp = process_request("agent.hint", &params, &ctx).await;
+        assert_eq!(resp["queued"], true);
+
+        let send = |session| serde_json::json!({"session_id": session, "message": "hello"});
+        process_request("agent.send", &send("s2"), &ctx).await;
+        let seen: Vec<String> = llm.call_messages(0).iter().map(|m| m.text()).collect();
+        assert!(!seen.iter().any(|t| t.contains("other branch")));
+
+        let reply = process_request("agent.send", &send("s1"), &ctx).await;
+        assert_eq!(reply["session_id"], resp["session_id"]);
+        let last = llm.call_messages(1).last().cloned().unwrap();
+        assert_eq!(last.text(), "try the other branch");
+    }
+
+    #[test]
+    fn hint_requires_operator() {
+        assert!(check_method_role("agent.hint", &ApiKeyRole::Viewer).is_err());
+        assert!(check_method_role("agent.hint", &ApiKeyRole::Operator).is_ok());
+        assert!(check_method_role("agent.hint", &ApiKeyRole::Admin).is_ok());
+    }
+
+    #[test]
+    fn viewer_can_read_but_not_run() {
+        let viewer = ApiKeyRole::Viewer;
+        assert!(check_method_role("session.list", &viewer).is_ok());
+        assert!(check_method_role("session.history", &viewer).is_ok());
+
+        let denied = check_method_role("agent.send", &viewer).unwrap_err();
+        assert_eq!(denied["error"], "operator role required");
+        assert_eq!(denied["code"], 403);
+        assert!(check_method_role("agent.cancel", &viewer).is_err());
+        assert!(check_method_role("approval.respond", &viewer).is_err());
+    }
+
+    #[test]
+    fn sub_agent_events_are_nested_under_the_parent() {
+        let event = AgentEvent::SubAgent {
+            session_id: SessionId::from_string("sub"),
+            depth: 1,
+            event: Box::new(AgentEvent::ToolStart {
+                name: "web_search".into(),
+                input: serde_json::json!({"query": "ryvos"}),
+            }),
+        };
+        let frame = to_server_event(&event, "parent").unwrap();
+        assert_eq!(frame.session_id, "parent");
+        assert_eq!(frame.event.kind, "sub_agent");
+        let data = frame.event.data.unwrap();
+        assert_eq!(data["session_id"], "sub");
+        assert_eq!(data["depth"], 1);
+        assert_eq!(data["event"]["kind"], "tool_start");
+        assert_eq!(data["event"]["tool"], "web_search");
+
+        let silent = AgentEvent::SubAgent {
+            session_id: SessionId::from_string("sub"),
+            depth: 1,
+            event: Box::new(AgentEvent::TurnComplete { turn: 0 }),
+        };
+        assert!(to_server_event(&silent, "parent").is_none());
+    }
+
+    #[tokio::test]
+    async fn resume_replays_missed_events_and_skips_them_live() {
+        let event_log = Arc::new(EventLog::default());
+        let previous = Arc::new(Mutex::new(vec!["*".to_string(), "s1".to_string()]));
+        let token = event_log.issue_token(previous);
+        for text in ["a", "b", "c"] {
+            event_log.record(ServerEvent::new("s1".into(), "text_delta").with_text(text.into()));
+        }
+        event_log.release_token(&token);
+
+        let ctx = context(Arc::new(runtime()), event_log);
+        let params = serde_json::json!({"resume_token": token, "session_id": "s1", "last_seq": 1});
+        let resp = process_request("session.resume", &params, &ctx).await;
+        assert_eq!(resp["last_seq"], 3);
+        let events = resp["events"].as_array().unwrap();
+        let replayed: Vec<_> = events
+            .iter()
+            .map(|e| (&e["seq"], &e["event"]["text"]))
+            .collect();
+        assert_eq!(
+            replayed,
+            vec![(&2.into(), &"b".into()), (&3.into(), &"c".into())]
+        );
+        assert_eq!(ctx.replay_floor.lock().await.get("s1"), Some(&3));
+        assert!(ctx.subscribed.lock().await.contains(&"s1".to_string()));
+
+        let again = process_request("session.resume", &params, &ctx).await;
+        assert_eq!(again["error"], "invalid or expired resume token");
+    }
+
+    #[tokio::test]
+    async fn resume_skips_frames_already_delivered_live() {
+        let event_log = Arc::new(EventLog::default());
+        let previous = Arc::new(Mutex::new(vec!["s1".to_string()]));
+        let token = event_log.issue_token(previous);
+        for text in ["a", "b", "c"] {
+            event_log.record(ServerEvent::new("s1".into(), "text_delta").with_text(text.into()));
+        }
+        event_log.release_token(&token);
+
+        // Connected after "a"; "b" and "c" already went out live
+        let mut ctx = context(Arc::new(runtime()), event_log);
+        ctx.connect_heads.insert("s1".to_string(), 1);
+        ctx.replay_floor.lock().await.insert("s1".to_string(), 3);
+        let params = serde_json::json!({"resume_token": token, "session_id": "s1", "last_seq": 0});
+        let resp = process_request("session.resume", &params, &ctx).await;
+        let texts: Vec<_> = resp["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["event"]["text"].clone())
+            .collect();
+        assert_eq!(texts, vec![serde_json::json!("a")]);
+        assert_eq!(resp["last_seq"], 3);
+        assert_eq!(ctx.replay_floor.lock().await.get("s1"), Some(&3));
+    }
+
+    #[tokio::test]
+    async fn subscribed_session_can_be_resumed_later() {
+        let event_log = Arc::new(EventLog::default());
+        let ctx = context(Arc::new(runtime()), event_log.clone());
+        let token = event_log.issue_token(ctx.subscribed.clone());
+
+        let params = serde_json::json!({"session_id": "s1"});
+        let resp = process_request("session.subscribe", &params, &ctx).await;
+        assert_eq!(resp["subscribed"], true);
+        event_log.record(ServerEvent::new("s1".into(), "text_delta").with_text("a".into()));
+        event_log.release_token(&token);
+
+        let params = serde_json::json!({"resume_token": token, "session_id": "s1", "last_seq": 0});
+        let resp = process_request("session.resume", &params, &ctx).await;
+        assert_eq!(resp["last_seq"], 1);
+        assert!(check_method_role("session.subscribe", &ApiKeyRole::Viewer).is_err());
+        assert!(check_method_role("session.subscribe", &ApiKeyRole::Operator).is_ok());
+    }
+
+    fn delta(event_log: &EventLog, session: &str, text: &str) {
+        event_log.record(ServerEvent::new(session.into(), "text_delta").with_text(text.into()));
+    }
+
+    /// Frames a lagging client receives until `count` text frames arrived.
+    async fn forward(
+        rx: &mut tokio::sync::broadcast::Receiver<ServerEvent>,
+        event_log: &EventLog,
+        delivered: &Mutex<HashMap<String, u64>>,
+        count: usize,
+    ) -> Vec<ServerEvent> {
+        let mut sent = Vec::new();
+        while sent
+            .iter()
+            .filter(|e: &&ServerEvent| e.seq.is_some())
+            .count()
+            < count
+        {
+            match next_frames(rx, event_log, delivered, SlowClientPolicy::Resync).await {
+                Forward::Frames(frames) => sent.extend(frames),
+                _ => panic!("expected frames"),
+            }
+        }
+        sent
+    }
+
+    #[tokio::test]
+    async fn lagging_client_is_resynced_without_gaps() {
+        let event_log = EventLog::default().with_live_buffer(2);
+        delta(&event_log, "s1", "before");
+        let (mut rx, heads) = event_log.subscribe_with_heads();
+        let delivered = Mutex::new(heads);
+
+        for text in ["a", "b", "c", "d", "e"] {
+            delta(&event_log, "s1", text);
+        }
+        let sent = forward(&mut rx, &event_log, &delivered, 5).await;
+
+        assert_eq!(sent[0].session_id, "system");
+        assert_eq!(sent[0].event.kind, "resync");
+        assert_eq!(sent[0].event.data.as_ref().unwrap()["missed"], 3);
+        // Every missed frame once, in order, and nothing from before connecting
+        let texts: Vec<_> = sent[1..]
+            .iter()
+            .map(|e| e.event.text.clone().unwrap())
+            .collect();
+        assert_eq!(texts, vec!["a", "b", "c", "d", "e"]);
+
+        // The frames still buffered live were already replayed
+        for _ in 0..2 {
+            let next = next_frames(&mut rx, &event_log, &delivered, SlowClientPolicy::Resync).await;
+            assert!(matches!(next, Forward::Frames(frames) if frames.is_empty()));
+        }
+        delta(&event_log, "s1", "f");
+        let sent = forward(&mut rx, &event_log, &delivered, 1).await;
+        let texts: Vec<_> = sent.iter().map(|e| e.event.text.clone()).collect();
+        assert_eq!(texts, vec![Some("f".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn lagging_client_is_dropped_under_disconnect_policy() {
+        let event_log = EventLog::default().with_live_buffer(1);
+        let (mut rx, heads) = event_log.subscribe_with_heads();
+        let delivered = Mutex::new(heads);
+        for text in ["a", "b", "c"] {
+            delta(&event_log, "s1", text);
+        }
+        let next = next_frames(
+            &mut rx,
+            &event_log,
+            &delivered,
+            SlowClientPolicy::Disconnect,
+        )
+        .await;
+        assert!(matches!(next, Forward::TooSlow(2)));
+    }
+
+    #[tokio::test]
+    async fn working_dir_is_set_per_session() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let mut config = test_config();
+        config.agent.working_dir_roots = vec![dir.display().to_string()];
+        let runtime = Arc::new(runtime_with(config));
+        let ctx = context(runtime.clone(), Arc::new(EventLog::default()));
+
+        let params = serde_json::json!({"session_id": "s1", "dir": dir.to_str().unwrap()});
+        let resp = process_request("session.working_dir", &params, &ctx).await;
+        assert_eq!(resp["working_dir"], dir.to_str().unwrap());
+        let s1 = ctx.session_mgr.get_or_create("s1", "webui");
+        assert_eq!(runtime.session_manager().working_dir(&s1), Some(dir));
+        let s2 = ctx.session_mgr.get_or_create("s2", "webui");
+        assert_eq!(runtime.session_manager().working_dir(&s2), None);
+
+        let params = serde_json::json!({"session_id": "s1", "dir": "/no/such/dir"});
+        let resp = process_request("session.working_dir", &params, &ctx).await;
+        assert!(resp["error"].as_str().unwrap().contains("not found"));
+        assert!(check_method_role("session.working_dir", &ApiKeyRole::Viewer).is_err());
+    }
+}
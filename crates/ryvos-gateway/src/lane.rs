@@ -1,5 +1,19 @@
+//! Request lanes.
+//!
+//! [`LaneQueue`] serializes the RPC requests of one WebSocket connection.
+//! [`LaneScheduler`] is shared by the whole gateway and decides when agent
+//! runs may start: runs wait in one of three priority [`Lane`]s, and when
+//! the runtime is saturated a queued interactive run starts before any
+//! queued normal or batch run.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
 use tokio::sync::{mpsc, oneshot};
 
+use ryvos_core::config::{ApiKeyRole, LanesConfig};
+
 /// A request queued in a session lane.
 pub struct LaneItem {
     pub method: String,
@@ -35,3 +49,252 @@ impl LaneQueue {
         rx.await.ok()
     }
 }
+
+/// Scheduling priority of an agent run, highest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lane {
+    Interactive,
+    Normal,
+    Batch,
+}
+
+impl Lane {
+    const ALL: [Lane; 3] = [Lane::Interactive, Lane::Normal, Lane::Batch];
+
+    /// Default lane for a caller: admins (including the Web UI's token and
+    /// anonymous single-user mode) are interactive, API keys are normal.
+    pub fn for_role(role: &ApiKeyRole) -> Self {
+        match role {
+            ApiKeyRole::Admin => Lane::Interactive,
+            ApiKeyRole::Operator => Lane::Normal,
+            ApiKeyRole::Viewer => Lane::Batch,
+        }
+    }
+
+    /// Lane for a request: an explicit `lane` field may lower the role's
+    /// lane but never raise it. Unknown names are ignored.
+    pub fn resolve(role: &ApiKeyRole, requested: Option<&str>) -> Self {
+        let default = Self::for_role(role);
+        requested
+            .and_then(|name| serde_json::from_value::<Lane>(name.into()).ok())
+            .map_or(default, |lane| lane.max(default))
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Runs executing and waiting, per lane.
+struct SchedulerState {
+    running: [usize; 3],
+    total: usize,
+    waiting: [VecDeque<oneshot::Sender<LanePermit>>; 3],
+}
+
+struct SchedulerInner {
+    caps: [usize; 3],
+    max_concurrent: usize,
+    state: Mutex<SchedulerState>,
+}
+
+impl SchedulerInner {
+    fn has_room(&self, state: &SchedulerState, lane: Lane) -> bool {
+        state.total < self.max_concurrent && state.running[lane.index()] < self.caps[lane.index()]
+    }
+
+    /// Start waiting runs, highest lane first, while there is room. Returns
+    /// permits whose waiter went away; drop them after releasing the lock.
+    fn dispatch(self: &Arc<Self>, state: &mut SchedulerState) -> Vec<LanePermit> {
+        let mut orphans = Vec::new();
+        for lane in Lane::ALL {
+            while self.has_room(state, lane) {
+                let Some(waiter) = state.waiting[lane.index()].pop_front() else {
+                    break;
+                };
+                state.running[lane.index()] += 1;
+                state.total += 1;
+                let permit = LanePermit {
+                    lane,
+                    scheduler: self.clone(),
+                };
+                if let Err(permit) = waiter.send(permit) {
+                    orphans.push(permit);
+                }
+            }
+        }
+        orphans
+    }
+}
+
+/// Gateway-wide admission control for agent runs, with per-lane caps from
+/// `[gateway.lanes]`.
+#[derive(Clone)]
+pub struct LaneScheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+impl LaneScheduler {
+    pub fn new(config: &LanesConfig) -> Self {
+        Self {
+            inner: Arc::new(SchedulerInner {
+                caps: [config.interactive, config.normal, config.batch].map(|cap| cap.max(1)),
+                max_concurrent: config.max_concurrent.max(1),
+                state: Mutex::new(SchedulerState {
+                    running: [0; 3],
+                    total: 0,
+                    waiting: Default::default(),
+                }),
+            }),
+        }
+    }
+
+    /// Wait until a run in `lane` may start. The run holds its slot until
+    /// the returned permit is dropped.
+    pub async fn acquire(&self, lane: Lane) -> LanePermit {
+        let rx = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.waiting[lane.index()].is_empty() && self.inner.has_room(&state, lane) {
+                state.running[lane.index()] += 1;
+                state.total += 1;
+                return LanePermit {
+                    lane,
+                    scheduler: self.inner.clone(),
+                };
+            }
+            let (tx, rx) = oneshot::channel();
+            state.waiting[lane.index()].push_back(tx);
+            rx
+        };
+        // Waiters only leave the queue through `dispatch`, which sends a permit
+        rx.await.expect("lane scheduler dropped a waiter")
+    }
+}
+
+/// A running slot in a lane; dropping it lets the next waiting run start.
+pub struct LanePermit {
+    lane: Lane,
+    scheduler: Arc<SchedulerInner>,
+}
+
+impl Drop for LanePermit {
+    fn drop(&mut self) {
+        let orphans = {
+            let mut state = self.scheduler.state.lock().unwrap();
+            state.running[self.lane.index()] -= 1;
+            state.total -= 1;
+            self.scheduler.dispatch(&mut state)
+        };
+        drop(orphans);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn scheduler(max_concurrent: usize) -> LaneScheduler {
+        LaneScheduler::new(&LanesConfig {
+            max_concurrent,
+            interactive: 4,
+            normal: 4,
+            batch: 4,
+        })
+    }
+
+    /// Queue a run in `lane` that records its name once it is admitted.
+    fn queue(
+        scheduler: &LaneScheduler,
+        lane: Lane,
+        name: &'static str,
+        order: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> tokio::task::JoinHandle<LanePermit> {
+        let (scheduler, order) = (scheduler.clone(), order.clone());
+        tokio::spawn(async move {
+            let permit = scheduler.acquire(lane).await;
+            order.lock().unwrap().push(name);
+            permit
+        })
+    }
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    #[tokio::test]
+    async fn interactive_run_is_served_ahead_of_queued_batch() {
+        let scheduler = scheduler(1);
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let running = scheduler.acquire(Lane::Batch).await;
+        let batch1 = queue(&scheduler, Lane::Batch, "batch1", &order);
+        let batch2 = queue(&scheduler, Lane::Batch, "batch2", &order);
+        settle().await;
+        let interactive = queue(&scheduler, Lane::Interactive, "interactive", &order);
+        settle().await;
+        assert!(order.lock().unwrap().is_empty(), "runtime is saturated");
+
+        drop(running);
+        drop(interactive.await.unwrap());
+        drop(batch1.await.unwrap());
+        drop(batch2.await.unwrap());
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["interactive", "batch1", "batch2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn lane_cap_holds_back_only_its_own_lane() {
+        let scheduler = LaneScheduler::new(&LanesConfig {
+            max_concurrent: 4,
+            interactive: 4,
+            normal: 4,
+            batch: 1,
+        });
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let batch = scheduler.acquire(Lane::Batch).await;
+        let queued = queue(&scheduler, Lane::Batch, "batch", &order);
+        let normal = queue(&scheduler, Lane::Normal, "normal", &order);
+        settle().await;
+        assert_eq!(*order.lock().unwrap(), vec!["normal"]);
+
+        drop(batch);
+        let _batch = queued.await.unwrap();
+        let _normal = normal.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["normal", "batch"]);
+    }
+
+    #[tokio::test]
+    async fn abandoned_waiter_frees_its_slot() {
+        let scheduler = scheduler(1);
+        let running = scheduler.acquire(Lane::Normal).await;
+        let abandoned = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(Lane::Normal).await }
+        });
+        settle().await;
+        abandoned.abort();
+        let _ = abandoned.await;
+
+        drop(running);
+        let permit = tokio::time::timeout(Duration::from_secs(1), scheduler.acquire(Lane::Batch))
+            .await
+            .expect("slot was leaked");
+        assert_eq!(permit.lane, Lane::Batch);
+    }
+
+    #[test]
+    fn explicit_lane_can_only_lower_priority() {
+        let admin = ApiKeyRole::Admin;
+        let operator = ApiKeyRole::Operator;
+        assert_eq!(Lane::resolve(&admin, None), Lane::Interactive);
+        assert_eq!(Lane::resolve(&admin, Some("batch")), Lane::Batch);
+        assert_eq!(Lane::resolve(&operator, None), Lane::Normal);
+        assert_eq!(Lane::resolve(&operator, Some("interactive")), Lane::Normal);
+        assert_eq!(Lane::resolve(&operator, Some("bogus")), Lane::Normal);
+    }
+}
@@ -4,10 +4,14 @@
 //!
 //! - **38 REST endpoints** for sessions, runs, costs, audit, config,
 //!   approvals, cron, budget, model, integrations, goals, and webhooks.
-//! - **WebSocket** server with real-time event streaming and 5 RPC methods
-//!   (agent.send, agent.cancel, session.list, session.history, approval.respond).
+//! - **WebSocket** server with real-time, resumable event streaming and 7 RPC
+//!   methods (agent.send, agent.cancel, agent.hint, session.list,
+//!   session.history, session.resume, approval.respond).
 //! - **Authentication** with API key roles (Viewer, Operator, Admin) and
-//!   anonymous Admin mode for self-hosted single-user deployments.
+//!   anonymous Admin mode for self-hosted single-user deployments. Each route
+//!   and RPC method declares its minimum role in one table in `auth.rs`.
+//! - **OpenAPI 3** description of the REST API at `GET /openapi.json`, built
+//!   from the same route tables.
 //! - **OAuth 2.0** flow for Gmail, Slack, GitHub, Jira, and Linear.
 //! - **Embedded Web UI** served via `rust_embed` (Svelte 5 SPA, ~376KB).
 
@@ -16,11 +20,16 @@ mod connection;
 mod lane;
 mod middleware;
 pub mod oauth;
+mod openapi;
 mod protocol;
+mod replay;
 mod routes;
 mod server;
 mod state;
 mod static_files;
 
+pub use connection::{call_numbers, to_server_event};
+pub use openapi::openapi_spec;
+pub use protocol::ServerEvent;
 pub use ryvos_core::IntegrationsConfig;
 pub use server::GatewayServer;
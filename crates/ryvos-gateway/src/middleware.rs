@@ -1,13 +1,20 @@
 use std::sync::Arc;
 
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRequestParts, MatchedPath, Request, State};
 use axum::http::request::Parts;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::warn;
+
+use ryvos_core::config::GatewayConfig;
 
 use crate::auth::{self, AuthResult};
 use crate::state::AppState;
 
 /// Extractor that validates authentication via Bearer header or query params.
+///
+/// Reuses the result of `require_role` when the route layer already ran.
 pub struct Authenticated(pub AuthResult);
 
 impl FromRequestParts<Arc<AppState>> for Authenticated {
@@ -17,27 +24,11 @@ impl FromRequestParts<Arc<AppState>> for Authenticated {
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
-        let config = &state.config;
-
-        // Extract Bearer token from Authorization header
-        let bearer = parts
-            .headers
-            .get("authorization")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.strip_prefix("Bearer "))
-            .map(|s| s.to_string());
-
-        // Extract query params
-        let query = parts.uri.query().unwrap_or("");
-        let query_token = auth::extract_token_from_query(query).map(|s| s.to_string());
-        let query_password = auth::extract_password_from_query(query).map(|s| s.to_string());
-
-        let result = auth::validate_auth(
-            config,
-            bearer.as_deref(),
-            query_token.as_deref(),
-            query_password.as_deref(),
-        );
+        let result = parts
+            .extensions
+            .get::<AuthResult>()
+            .cloned()
+            .or_else(|| authenticate(&state.config, &parts.headers, &parts.uri));
 
         async move {
             match result {
@@ -47,3 +38,57 @@ impl FromRequestParts<Arc<AppState>> for Authenticated {
         }
     }
 }
+
+/// Validate the Bearer header or query-string credentials of a request.
+fn authenticate(config: &GatewayConfig, headers: &HeaderMap, uri: &Uri) -> Option<AuthResult> {
+    // Extract Bearer token from Authorization header
+    let bearer = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "));
+
+    // Extract query params
+    let query = uri.query().unwrap_or("");
+    let query_token = auth::extract_token_from_query(query);
+    let query_password = auth::extract_password_from_query(query);
+
+    auth::validate_auth(config, bearer, query_token, query_password)
+}
+
+/// Route layer enforcing the role table in `auth::ROUTE_ROLES`.
+///
+/// Returns 401 when credentials are missing or invalid, and 403 when the
+/// caller's role is too low or the route never declared a required role.
+pub async fn require_role(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    // HEAD is served by GET handlers
+    let method = if req.method() == Method::HEAD {
+        Method::GET
+    } else {
+        req.method().clone()
+    };
+    if auth::is_public_route(method.as_str(), &path) {
+        return Ok(next.run(req).await);
+    }
+    let Some(required) = auth::route_role(method.as_str(), &path) else {
+        warn!(%method, path, "Route has no declared role, refusing");
+        return Err(StatusCode::FORBIDDEN);
+    };
+
+    let result =
+        authenticate(&state.config, req.headers(), req.uri()).ok_or(StatusCode::UNAUTHORIZED)?;
+    if !auth::has_role(&result.role, &required) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    req.extensions_mut().insert(result);
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,253 @@
+//! OpenAPI 3 description of the gateway's HTTP API.
+//!
+//! Paths, methods and required roles come from the route tables in
+//! `auth.rs`, which `middleware::require_role` already forces every route to
+//! appear in, so the document cannot drift from the router. Only the
+//! one-line summaries below are written by hand, and a test checks that each
+//! route has one. The Web UI routes (`/`, `/assets/...`) are left out.
+
+use serde_json::{json, Map, Value};
+
+use ryvos_core::config::ApiKeyRole;
+
+use crate::auth::{PUBLIC_ROUTES, ROUTE_ROLES};
+
+/// Summary of each operation, keyed by method and route pattern.
+const SUMMARIES: &[(&str, &str, &str)] = &[
+    ("GET", "/ws", "WebSocket for events and RPC"),
+    ("GET", "/openapi.json", "This OpenAPI document"),
+    ("GET", "/api/health", "Health check and version"),
+    ("GET", "/api/sessions", "List sessions"),
+    (
+        "GET",
+        "/api/sessions/{id}/history",
+        "Message history of a session",
+    ),
+    (
+        "POST",
+        "/api/sessions/{id}/messages",
+        "Send a message and run the agent",
+    ),
+    ("GET", "/api/metrics", "Gateway and agent metrics"),
+    (
+        "GET",
+        "/api/status",
+        "Model and run, tool and token counters",
+    ),
+    ("GET", "/api/runs", "Recent runs"),
+    ("GET", "/api/costs", "Token usage and cost"),
+    ("GET", "/api/audit", "Audit trail entries"),
+    ("GET", "/api/audit/stats", "Audit trail statistics"),
+    ("GET", "/api/viking/list", "List Viking memory entries"),
+    ("GET", "/api/viking/read", "Read a Viking memory entry"),
+    ("GET", "/api/viking/search", "Search Viking memory"),
+    ("GET", "/api/config", "Read the config file"),
+    ("PUT", "/api/config", "Replace the config file"),
+    ("GET", "/api/channels", "Channel status"),
+    ("GET", "/api/approvals", "Pending approvals"),
+    (
+        "POST",
+        "/api/approvals/{id}/approve",
+        "Approve a pending tool call",
+    ),
+    (
+        "POST",
+        "/api/approvals/{id}/deny",
+        "Deny a pending tool call",
+    ),
+    ("GET", "/api/cron", "List cron jobs"),
+    ("POST", "/api/cron", "Add a cron job"),
+    ("DELETE", "/api/cron/{name}", "Delete a cron job"),
+    ("GET", "/api/budget", "Budget and spend"),
+    ("PUT", "/api/budget", "Update the budget"),
+    ("GET", "/api/model", "Current model"),
+    ("PUT", "/api/model", "Switch the model"),
+    (
+        "GET",
+        "/api/models/available",
+        "Models that can be selected",
+    ),
+    ("GET", "/api/integrations", "List integrations"),
+    ("GET", "/api/integrations/callback", "OAuth callback"),
+    (
+        "POST",
+        "/api/integrations/{app}/connect",
+        "Start an OAuth connection",
+    ),
+    (
+        "DELETE",
+        "/api/integrations/{app}",
+        "Disconnect an integration",
+    ),
+    ("GET", "/api/tools", "List tools with their input schemas"),
+    ("GET", "/api/skills", "List skills"),
+    ("GET", "/api/heartbeat/history", "Heartbeat history"),
+    ("GET", "/api/safety/lessons", "Safety lessons"),
+    ("GET", "/api/decisions", "Recorded decisions"),
+    ("GET", "/api/failures", "Failure journal"),
+    ("POST", "/api/goals/run", "Run a goal"),
+    ("GET", "/api/goals/history", "Goal run history"),
+    ("POST", "/api/hooks/wake", "Wake the agent from a webhook"),
+    (
+        "GET",
+        "/api/whatsapp/webhook",
+        "WhatsApp webhook verification",
+    ),
+    (
+        "POST",
+        "/api/whatsapp/webhook",
+        "WhatsApp incoming messages",
+    ),
+    ("GET", "/v1/models", "OpenAI-compatible model list"),
+    ("POST", "/v1/embeddings", "OpenAI-compatible embeddings"),
+];
+
+/// The OpenAPI document for the gateway, as served at `GET /openapi.json`.
+pub fn openapi_spec() -> Value {
+    let mut paths = Map::new();
+    let secured = ROUTE_ROLES
+        .iter()
+        .map(|(method, path, role)| (*method, *path, Some(role)));
+    let public = PUBLIC_ROUTES
+        .iter()
+        .map(|(method, path)| (*method, *path, None));
+    for (method, path, role) in secured.chain(public) {
+        if path == "/" || path.starts_with("/assets/") {
+            continue;
+        }
+        let item = paths.entry(path.to_string()).or_insert_with(|| json!({}));
+        item[method.to_lowercase()] = operation(method, path, role);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Ryvos Gateway",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "Gateway token or API key",
+                },
+                "token": { "type": "apiKey", "in": "query", "name": "token" },
+            },
+        },
+        "security": [{ "bearer": [] }, { "token": [] }],
+    })
+}
+
+fn operation(method: &str, path: &str, role: Option<&ApiKeyRole>) -> Value {
+    let summary = SUMMARIES
+        .iter()
+        .find(|(m, p, _)| *m == method && *p == path)
+        .map(|(_, _, summary)| *summary)
+        .unwrap_or_default();
+    let mut op = json!({
+        "operationId": operation_id(method, path),
+        "summary": summary,
+        "tags": [tag(path)],
+        "responses": { "200": { "description": "Success" } },
+    });
+
+    let params: Vec<Value> = path
+        .split('/')
+        .filter_map(|seg| seg.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect();
+    if !params.is_empty() {
+        op["parameters"] = Value::Array(params);
+    }
+    if method == "POST" || method == "PUT" {
+        op["requestBody"] = json!({
+            "content": { "application/json": { "schema": { "type": "object" } } },
+        });
+    }
+
+    match role {
+        Some(role) => {
+            let role = json!(role);
+            let forbidden = format!("Needs the {} role or higher", role.as_str().unwrap_or("?"));
+            op["x-ryvos-role"] = role;
+            op["responses"]["401"] = json!({ "description": "Missing or invalid credentials" });
+            op["responses"]["403"] = json!({ "description": forbidden });
+        }
+        None => op["security"] = json!([]),
+    }
+    op
+}
+
+/// `POST /api/sessions/{id}/messages` -> `post_sessions_id_messages`.
+fn operation_id(method: &str, path: &str) -> String {
+    let mut id = method.to_lowercase();
+    for seg in path
+        .split(['/', '.'])
+        .filter(|s| !s.is_empty() && *s != "api")
+    {
+        id.push('_');
+        id.push_str(seg.trim_start_matches('{').trim_end_matches('}'));
+    }
+    id
+}
+
+/// Group by the first path segment under `/api/`.
+fn tag(path: &str) -> &str {
+    let mut segs = path.trim_start_matches('/').split('/');
+    match segs.next() {
+        Some("api") => segs.next().unwrap_or("api"),
+        Some("v1") => "openai",
+        Some("ws") => "websocket",
+        _ => "meta",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_is_json_with_session_and_run_routes() {
+        let text = serde_json::to_string_pretty(&openapi_spec()).unwrap();
+        let spec: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(spec["openapi"], "3.0.3");
+
+        let paths = &spec["paths"];
+        assert!(paths["/api/sessions"]["get"].is_object());
+        let send = &paths["/api/sessions/{id}/messages"]["post"];
+        assert_eq!(send["x-ryvos-role"], "operator");
+        assert_eq!(send["parameters"][0]["name"], "id");
+        assert!(paths["/api/runs"]["get"].is_object());
+        assert!(paths["/api/goals/run"]["post"].is_object());
+        assert!(paths["/v1/embeddings"]["post"].is_object());
+
+        let health = &paths["/api/health"]["get"];
+        assert_eq!(health["security"], json!([]));
+        assert!(paths.get("/").is_none());
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let spec = openapi_spec();
+        let declared = ROUTE_ROLES
+            .iter()
+            .map(|(m, p, _)| (*m, *p))
+            .chain(PUBLIC_ROUTES.iter().copied())
+            .filter(|(_, p)| *p != "/" && !p.starts_with("/assets/"));
+        let mut ids = std::collections::HashSet::new();
+        for (method, path) in declared {
+            let op = &spec["paths"][path][method.to_lowercase()];
+            assert_ne!(op["summary"], "", "{} {} has no summary", method, path);
+            assert!(ids.insert(op["operationId"].to_string()));
+        }
+    }
+}
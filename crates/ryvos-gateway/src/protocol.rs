@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use ryvos_core::types::{ChatMessage, MessageMetadata};
+
 /// A frame sent from the client.
 #[derive(Debug, Deserialize)]
 pub struct ClientFrame {
@@ -25,11 +27,14 @@ pub struct ServerResponse {
 }
 
 /// An event frame pushed to the client.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ServerEvent {
     #[serde(rename = "type")]
     pub frame_type: String,
     pub session_id: String,
+    /// Per-session sequence number, assigned by the gateway event log.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
     pub event: EventPayload,
 }
 
@@ -75,6 +80,7 @@ impl ServerEvent {
         Self {
             frame_type: "event".to_string(),
             session_id,
+            seq: None,
             event: EventPayload {
                 kind: kind.to_string(),
                 text: None,
@@ -99,3 +105,19 @@ impl ServerEvent {
         self
     }
 }
+
+/// A message as the history endpoints return it, with the channel, sender
+/// and attachments it arrived with when those are known.
+pub fn history_entry(msg: &ChatMessage) -> serde_json::Value {
+    let mut entry = serde_json::json!({
+        "role": msg.role,
+        "text": msg.text(),
+        "timestamp": msg.timestamp,
+    });
+    if let Some(source) = msg.metadata.as_ref().and_then(MessageMetadata::source) {
+        entry["source_channel"] = source.source_channel.into();
+        entry["sender"] = source.sender.into();
+        entry["attachments"] = serde_json::to_value(&source.attachments).unwrap_or_default();
+    }
+    entry
+}
@@ -0,0 +1,522 @@
+//! Sequence-numbered event log for resumable WebSocket streams.
+//!
+//! A single recorder task converts every `AgentEvent` into a `ServerEvent`,
+//! stamps it with a per-session sequence number, keeps the most recent
+//! frames per session, and fans them out to live connections.
+//!
+//! Each connection is issued a resume token. When the socket drops, the token
+//! stays valid for a grace period; a reconnecting client calls
+//! `session.resume` with the token and the last `seq` it saw, and receives the
+//! frames it missed, in order.
+//!
+//! Live connections share one broadcast channel. A connection that falls
+//! more than its capacity behind gets `RecvError::Lagged`; instead of losing
+//! those frames it is sent a `resync` notice followed by [`EventLog::resync`],
+//! the retained frames past what it last delivered per session.
+//!
+//! A session's frames are dropped once it has recorded nothing for longer
+//! than the resume-token TTL. If it records again, its seqs carry on above
+//! every seq issued so far, so connections that saw the old frames skip
+//! none of the new ones.
+//!
+//! The recorder itself can lag behind the event bus. The events it skipped
+//! are gone, so it records a `gap` frame in the session of the current run
+//! instead; clients that see one reload that session from history.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{broadcast, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use ryvos_core::event::EventBus;
+use ryvos_core::types::AgentEvent;
+
+use crate::connection;
+use crate::protocol::ServerEvent;
+
+/// Frames retained per session for replay.
+const DEFAULT_REPLAY_CAPACITY: usize = 512;
+
+/// Frames a live connection may fall behind before it lags.
+const DEFAULT_LIVE_BUFFER: usize = 256;
+
+/// Kind of the frame recorded where the recorder lagged and lost events.
+const GAP_KIND: &str = "gap";
+
+/// How long a resume token stays valid after its connection drops.
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(300);
+
+/// Why a resume request was refused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResumeError {
+    /// The token was never issued, was already used, or has expired.
+    InvalidToken,
+    /// The previous connection never subscribed to this session.
+    NotSubscribed,
+    /// Events after `last_seq` have already been evicted; the client must
+    /// reload the session from history.
+    ReplayWindowExceeded { oldest_seq: u64 },
+}
+
+impl fmt::Display for ResumeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResumeError::InvalidToken => write!(f, "invalid or expired resume token"),
+            ResumeError::NotSubscribed => write!(f, "session not subscribed by this token"),
+            ResumeError::ReplayWindowExceeded { oldest_seq } => {
+                write!(
+                    f,
+                    "missed events no longer retained (oldest seq {})",
+                    oldest_seq
+                )
+            }
+        }
+    }
+}
+
+struct SessionLog {
+    /// Seq of the session's first frame since it was (re)created.
+    first_seq: u64,
+    next_seq: u64,
+    events: VecDeque<ServerEvent>,
+    last_recorded: Instant,
+}
+
+impl SessionLog {
+    /// Whether frames after `seq` were evicted. Seqs below `first_seq`
+    /// belong to an earlier, idle-evicted log and were all recorded before it.
+    fn lost_after(&self, seq: u64) -> Option<u64> {
+        let oldest_seq = self
+            .events
+            .front()
+            .and_then(|e| e.seq)
+            .unwrap_or(self.next_seq);
+        (seq.max(self.first_seq - 1) + 1 < oldest_seq).then_some(oldest_seq)
+    }
+}
+
+struct ResumeToken {
+    sessions: Arc<Mutex<Vec<String>>>,
+    /// `None` while the owning connection is still open.
+    released_at: Option<Instant>,
+}
+
+impl ResumeToken {
+    fn is_valid(&self, ttl: Duration) -> bool {
+        !matches!(self.released_at, Some(at) if at.elapsed() >= ttl)
+    }
+}
+
+/// Per-session ring buffer of sequenced frames plus the resume tokens that
+/// may replay them.
+pub struct EventLog {
+    sessions: std::sync::Mutex<HashMap<String, SessionLog>>,
+    tokens: std::sync::Mutex<HashMap<String, ResumeToken>>,
+    live: broadcast::Sender<ServerEvent>,
+    /// Frames recorded so far; no seq is ever higher.
+    recorded: AtomicU64,
+    capacity: usize,
+    token_ttl: Duration,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize, token_ttl: Duration) -> Self {
+        let (live, _) = broadcast::channel(DEFAULT_LIVE_BUFFER);
+        Self {
+            sessions: std::sync::Mutex::new(HashMap::new()),
+            tokens: std::sync::Mutex::new(HashMap::new()),
+            live,
+            recorded: AtomicU64::new(0),
+            capacity: capacity.max(1),
+            token_ttl,
+        }
+    }
+
+    /// Let live connections fall up to `frames` behind before they lag
+    /// (`[gateway] event_buffer`). Call before anyone subscribes.
+    pub fn with_live_buffer(mut self, frames: usize) -> Self {
+        self.live = broadcast::channel(frames.max(1)).0;
+        self
+    }
+
+    /// Subscribe to sequenced frames as they are recorded, and return the
+    /// latest seq per session at that moment: every later frame reaches the
+    /// receiver.
+    pub fn subscribe_with_heads(&self) -> (broadcast::Receiver<ServerEvent>, HashMap<String, u64>) {
+        let sessions = self.sessions.lock().unwrap();
+        let heads = sessions
+            .iter()
+            .map(|(id, log)| (id.clone(), log.next_seq - 1))
+            .collect();
+        (self.live.subscribe(), heads)
+    }
+
+    /// Everything a lagging connection missed: a `resync` notice, then the
+    /// retained frames of each session after the seq in `delivered` (all
+    /// retained frames for sessions not in it), oldest first per session.
+    /// Sessions whose missed frames were already evicted are listed in the
+    /// notice as `lost_sessions`, and those whose replayed frames include a
+    /// recorder `gap` as `gap_sessions`; clients reload both from history.
+    pub fn resync(&self, delivered: &HashMap<String, u64>, missed: u64) -> Vec<ServerEvent> {
+        let mut frames = Vec::new();
+        let mut lost = Vec::new();
+        let mut gaps = Vec::new();
+        {
+            let sessions = self.sessions.lock().unwrap();
+            let mut ids: Vec<&String> = sessions.keys().collect();
+            ids.sort();
+            for id in ids {
+                let log = &sessions[id];
+                let floor = delivered.get(id).copied().unwrap_or(0);
+                if log.lost_after(floor).is_some() {
+                    lost.push(id.clone());
+                }
+                let start = frames.len();
+                frames.extend(
+                    log.events
+                        .iter()
+                        .filter(|e| e.seq.is_some_and(|seq| seq > floor))
+                        .cloned(),
+                );
+                if frames[start..].iter().any(|e| e.event.kind == GAP_KIND) {
+                    gaps.push(id.clone());
+                }
+            }
+        }
+        let notice =
+            ServerEvent::new("system".to_string(), "resync").with_data(serde_json::json!({
+                "missed": missed,
+                "replayed": frames.len(),
+                "lost_sessions": lost,
+                "gap_sessions": gaps,
+            }));
+        std::iter::once(notice).chain(frames).collect()
+    }
+
+    /// Stamp a frame with the next sequence number for its session, retain
+    /// it for replay, and forward it to live connections. Sessions idle
+    /// longer than the token TTL are dropped.
+    pub fn record(&self, mut event: ServerEvent) -> ServerEvent {
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            let now = Instant::now();
+            sessions.retain(|id, log| {
+                *id == event.session_id || now.duration_since(log.last_recorded) < self.token_ttl
+            });
+            let recorded = self.recorded.fetch_add(1, Ordering::Relaxed);
+            let log = sessions
+                .entry(event.session_id.clone())
+                .or_insert_with(|| SessionLog {
+                    first_seq: recorded + 1,
+                    next_seq: recorded + 1,
+                    events: VecDeque::new(),
+                    last_recorded: now,
+                });
+            event.seq = Some(log.next_seq);
+            log.next_seq += 1;
+            log.last_recorded = now;
+            log.events.push_back(event.clone());
+            while log.events.len() > self.capacity {
+                log.events.pop_front();
+            }
+        }
+        let _ = self.live.send(event.clone());
+        event
+    }
+
+    /// Issue a resume token covering the sessions a connection subscribes to.
+    pub fn issue_token(&self, sessions: Arc<Mutex<Vec<String>>>) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, t| t.is_valid(self.token_ttl));
+        tokens.insert(
+            token.clone(),
+            ResumeToken {
+                sessions,
+                released_at: None,
+            },
+        );
+        token
+    }
+
+    /// Start the expiry clock for a token once its connection closes.
+    pub fn release_token(&self, token: &str) {
+        if let Some(t) = self.tokens.lock().unwrap().get_mut(token) {
+            t.released_at = Some(Instant::now());
+        }
+    }
+
+    /// Redeem a released token and return the frames for `session_id` with a
+    /// sequence number greater than `last_seq`, oldest first. Tokens are
+    /// single-use.
+    pub async fn resume(
+        &self,
+        token: &str,
+        session_id: &str,
+        last_seq: u64,
+    ) -> Result<Vec<ServerEvent>, ResumeError> {
+        let sessions = {
+            let tokens = self.tokens.lock().unwrap();
+            match tokens.get(token) {
+                Some(t) if t.is_valid(self.token_ttl) => t.sessions.clone(),
+                _ => return Err(ResumeError::InvalidToken),
+            }
+        };
+        if !sessions.lock().await.iter().any(|s| s == session_id) {
+            return Err(ResumeError::NotSubscribed);
+        }
+
+        let missed = {
+            let sessions = self.sessions.lock().unwrap();
+            let Some(log) = sessions.get(session_id) else {
+                return Ok(vec![]);
+            };
+            if let Some(oldest_seq) = log.lost_after(last_seq) {
+                return Err(ResumeError::ReplayWindowExceeded { oldest_seq });
+            }
+            log.events
+                .iter()
+                .filter(|e| e.seq.is_some_and(|seq| seq > last_seq))
+                .cloned()
+                .collect()
+        };
+        self.tokens.lock().unwrap().remove(token);
+        Ok(missed)
+    }
+
+    /// Record every event published on the bus until shutdown.
+    ///
+    /// Events without a session id (text and tool deltas) are attributed to
+    /// the session of the most recent `RunStarted`.
+    pub async fn run(self: Arc<Self>, event_bus: Arc<EventBus>, shutdown: CancellationToken) {
+        let mut rx = event_bus.subscribe();
+        let mut current = "*".to_string();
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                event = rx.recv() => event,
+            };
+            let event = match event {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // The skipped events cannot be replayed; say so in their
+                    // place so clients know to reload from history
+                    warn!(
+                        skipped,
+                        "Gateway event recorder lagged behind the event bus"
+                    );
+                    self.record(
+                        ServerEvent::new(current.clone(), GAP_KIND)
+                            .with_data(serde_json::json!({ "skipped": skipped })),
+                    );
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if let AgentEvent::RunStarted { session_id } = &event {
+                current = session_id.to_string();
+            }
+            if let Some(frame) = connection::to_server_event(&event, &current) {
+                self.record(frame);
+            }
+        }
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_CAPACITY, DEFAULT_TOKEN_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::types::SessionId;
+
+    fn subscribed(sessions: &[&str]) -> Arc<Mutex<Vec<String>>> {
+        Arc::new(Mutex::new(sessions.iter().map(|s| s.to_string()).collect()))
+    }
+
+    fn delta(log: &EventLog, session: &str, text: &str) {
+        log.record(ServerEvent::new(session.to_string(), "text_delta").with_text(text.into()));
+    }
+
+    fn texts(events: &[ServerEvent]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| e.event.text.clone().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reconnect_replays_exactly_missed_events() {
+        let log = EventLog::default();
+        let token = log.issue_token(subscribed(&["*", "s1"]));
+        let (mut live, _) = log.subscribe_with_heads();
+
+        delta(&log, "s1", "a");
+        delta(&log, "s2", "other");
+        delta(&log, "s1", "b");
+        // The client saw "a" (seq 1) before the socket dropped
+        let seen = live.recv().await.unwrap();
+        assert_eq!(seen.seq, Some(1));
+        log.release_token(&token);
+
+        delta(&log, "s1", "c");
+        delta(&log, "s1", "d");
+
+        let missed = log.resume(&token, "s1", 1).await.unwrap();
+        assert_eq!(texts(&missed), vec!["b", "c", "d"]);
+        let seqs: Vec<_> = missed.iter().map(|e| e.seq.unwrap()).collect();
+        assert_eq!(seqs, vec![2, 3, 4]);
+
+        // Tokens are single-use
+        assert_eq!(
+            log.resume(&token, "s1", 1).await.unwrap_err(),
+            ResumeError::InvalidToken
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_rejects_expired_and_foreign_tokens() {
+        let log = EventLog::new(16, Duration::ZERO);
+        let token = log.issue_token(subscribed(&["s1"]));
+        delta(&log, "s1", "a");
+        assert_eq!(
+            log.resume(&token, "s2", 0).await.unwrap_err(),
+            ResumeError::NotSubscribed
+        );
+
+        log.release_token(&token);
+        assert_eq!(
+            log.resume(&token, "s1", 0).await.unwrap_err(),
+            ResumeError::InvalidToken
+        );
+        assert_eq!(
+            log.resume("unknown", "s1", 0).await.unwrap_err(),
+            ResumeError::InvalidToken
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_is_capped() {
+        let log = EventLog::new(2, DEFAULT_TOKEN_TTL);
+        let token = log.issue_token(subscribed(&["s1"]));
+        for text in ["a", "b", "c", "d"] {
+            delta(&log, "s1", text);
+        }
+        assert_eq!(
+            log.resume(&token, "s1", 1).await.unwrap_err(),
+            ResumeError::ReplayWindowExceeded { oldest_seq: 3 }
+        );
+        let missed = log.resume(&token, "s1", 2).await.unwrap();
+        assert_eq!(texts(&missed), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn resync_replays_past_delivered_and_flags_evicted() {
+        let log = EventLog::new(3, DEFAULT_TOKEN_TTL);
+        for text in ["a", "b", "c", "d"] {
+            delta(&log, "s1", text);
+        }
+        delta(&log, "s2", "x");
+        delta(&log, "s3", "y");
+
+        // New sessions number on from the frames recorded before them
+        let delivered = HashMap::from([("s1".to_string(), 2), ("s3".to_string(), 6)]);
+        let frames = log.resync(&delivered, 4);
+        let notice = frames[0].event.data.clone().unwrap();
+        assert_eq!(frames[0].event.kind, "resync");
+        assert_eq!(notice["missed"], 4);
+        assert_eq!(notice["replayed"], 3);
+        assert_eq!(notice["lost_sessions"], serde_json::json!([]));
+        assert_eq!(texts(&frames[1..]), vec!["c", "d", "x"]);
+
+        // s1 seq 1 was evicted, so a client that saw nothing lost it
+        let frames = log.resync(&HashMap::new(), 6);
+        assert_eq!(
+            frames[0].event.data.as_ref().unwrap()["lost_sessions"],
+            serde_json::json!(["s1"])
+        );
+    }
+
+    #[test]
+    fn idle_sessions_are_evicted_and_resume_above_old_seqs() {
+        // A zero TTL makes every other session idle by the next frame
+        let log = EventLog::new(8, Duration::ZERO);
+        delta(&log, "s1", "a");
+        delta(&log, "s1", "b");
+        delta(&log, "s2", "x");
+        assert_eq!(log.sessions.lock().unwrap().len(), 1);
+        let frames = log.resync(&HashMap::new(), 0);
+        assert_eq!(texts(&frames[1..]), vec!["x"]);
+
+        // A connection that saw s1 up to seq 2 still gets its new frames
+        let seen = HashMap::from([("s1".to_string(), 2)]);
+        let c = log.record(ServerEvent::new("s1".to_string(), "text_delta").with_text("c".into()));
+        assert_eq!(c.seq, Some(4));
+        let frames = log.resync(&seen, 1);
+        assert_eq!(texts(&frames[1..]), vec!["c"]);
+        let notice = frames[0].event.data.clone().unwrap();
+        assert_eq!(notice["lost_sessions"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn recorder_attributes_deltas_to_running_session() {
+        let log = Arc::new(EventLog::default());
+        let bus = Arc::new(EventBus::default());
+        let shutdown = CancellationToken::new();
+        let (mut live, _) = log.subscribe_with_heads();
+        let recorder = tokio::spawn(log.clone().run(bus.clone(), shutdown.clone()));
+        tokio::task::yield_now().await;
+
+        bus.publish(AgentEvent::RunStarted {
+            session_id: SessionId::from_string("s1"),
+        });
+        bus.publish(AgentEvent::TextDelta("hi".into()));
+
+        let started = live.recv().await.unwrap();
+        assert_eq!((started.session_id.as_str(), started.seq), ("s1", Some(1)));
+        let text = live.recv().await.unwrap();
+        assert_eq!((text.session_id.as_str(), text.seq), ("s1", Some(2)));
+
+        shutdown.cancel();
+        recorder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lagging_recorder_records_a_gap() {
+        let log = Arc::new(EventLog::default());
+        let bus = Arc::new(EventBus::new(2));
+        let shutdown = CancellationToken::new();
+        let (mut live, _) = log.subscribe_with_heads();
+        let recorder = tokio::spawn(log.clone().run(bus.clone(), shutdown.clone()));
+        tokio::task::yield_now().await;
+
+        bus.publish(AgentEvent::RunStarted {
+            session_id: SessionId::from_string("s1"),
+        });
+        assert_eq!(live.recv().await.unwrap().seq, Some(1));
+        for text in ["a", "b", "c", "d", "e"] {
+            bus.publish(AgentEvent::TextDelta(text.into()));
+        }
+
+        let gap = live.recv().await.unwrap();
+        assert_eq!((gap.event.kind.as_str(), gap.seq), ("gap", Some(2)));
+        assert_eq!(gap.event.data.unwrap()["skipped"], 3);
+        assert_eq!(live.recv().await.unwrap().event.text.as_deref(), Some("d"));
+
+        let delivered = HashMap::from([("s1".to_string(), 1)]);
+        let notice = log.resync(&delivered, 1)[0].event.data.clone().unwrap();
+        assert_eq!(notice["gap_sessions"], serde_json::json!(["s1"]));
+
+        shutdown.cancel();
+        recorder.await.unwrap();
+    }
+}
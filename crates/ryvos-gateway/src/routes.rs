@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use axum::extract::ws::{WebSocket, WebSocketUpgrade};
@@ -10,11 +10,14 @@ use serde::Deserialize;
 use serde_json::Value;
 use tracing::{debug, info};
 
-use ryvos_core::types::SessionId;
+use ryvos_core::overrides::SamplingOverride;
+use ryvos_core::types::{MessageMetadata, SessionId};
 
 use crate::auth;
 use crate::connection;
+use crate::lane::Lane;
 use crate::middleware::Authenticated;
+use crate::protocol::history_entry;
 use crate::state::AppState;
 
 // GET /api/health — no auth required
@@ -25,23 +28,33 @@ pub async fn health() -> Json<serde_json::Value> {
     }))
 }
 
+// GET /openapi.json — no auth required
+pub async fn openapi() -> Json<serde_json::Value> {
+    Json(crate::openapi::openapi_spec())
+}
+
+/// Sessions with stored history that `/api/sessions` lists, at most.
+const STORED_SESSIONS_LIMIT: usize = 200;
+
 // GET /api/sessions — requires Viewer+
 pub async fn list_sessions(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let keys = state.session_mgr.list();
+    let mut listed: HashSet<String> = keys.iter().cloned().collect();
+    listed.extend(
+        keys.iter()
+            .filter_map(|key| state.session_mgr.session_id(key))
+            .map(|sid| sid.0),
+    );
 
     // If session_meta is available, return rich metadata
-    if let Some(ref meta_store) = state.session_meta {
-        let sessions: Vec<serde_json::Value> = keys
-            .iter()
+    let mut sessions: Vec<serde_json::Value> = if let Some(ref meta_store) = state.session_meta {
+        keys.iter()
             .map(|key| {
                 let meta = meta_store.get(key).ok().flatten();
                 if let Some(m) = meta {
+                    listed.insert(m.session_id.clone());
                     serde_json::json!({
                         "id": key,
                         "session_id": m.session_id,
@@ -54,11 +67,31 @@ pub async fn list_sessions(
                     serde_json::json!({ "id": key })
                 }
             })
-            .collect();
-        return Ok(Json(serde_json::json!({ "sessions": sessions })));
+            .collect()
+    } else {
+        keys.iter().map(|key| serde_json::json!(key)).collect()
+    };
+
+    // The store may be shared with other instances: add the sessions only
+    // it knows about
+    match state.store.list_sessions(STORED_SESSIONS_LIMIT).await {
+        Ok(stored) => sessions.extend(
+            stored
+                .into_iter()
+                .filter(|s| !listed.contains(&s.session_id))
+                .map(|s| {
+                    serde_json::json!({
+                        "id": s.session_id,
+                        "session_id": s.session_id,
+                        "last_active": s.last_active.to_rfc3339(),
+                        "message_count": s.message_count,
+                    })
+                }),
+        ),
+        Err(e) => tracing::warn!(error = %e, "Failed to list stored sessions"),
     }
 
-    Ok(Json(serde_json::json!({ "sessions": keys })))
+    Ok(Json(serde_json::json!({ "sessions": sessions })))
 }
 
 #[derive(Deserialize)]
@@ -73,15 +106,10 @@ fn default_limit() -> usize {
 
 // GET /api/sessions/:id/history?limit=50 — requires Viewer+
 pub async fn session_history(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Query(q): Query<HistoryQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     // Resolve session key → actual session_id via meta store
     let resolved_id = if let Some(ref meta_store) = state.session_meta {
         meta_store
@@ -96,16 +124,7 @@ pub async fn session_history(
     let session_id = SessionId::from_string(&resolved_id);
     match state.store.load_history(&session_id, q.limit).await {
         Ok(messages) => {
-            let msgs: Vec<serde_json::Value> = messages
-                .iter()
-                .map(|m| {
-                    serde_json::json!({
-                        "role": m.role,
-                        "text": m.text(),
-                        "timestamp": m.timestamp,
-                    })
-                })
-                .collect();
+            let msgs: Vec<serde_json::Value> = messages.iter().map(history_entry).collect();
             Ok(Json(serde_json::json!({ "messages": msgs })))
         }
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
@@ -115,25 +134,44 @@ pub async fn session_history(
 #[derive(Deserialize)]
 pub struct SendMessageBody {
     pub message: String,
+    /// Optional priority lane; may only lower the caller's default lane.
+    #[serde(default)]
+    pub lane: Option<String>,
+    /// Sampling temperature for this run only.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling cutoff for this run only.
+    #[serde(default)]
+    pub top_p: Option<f32>,
 }
 
 // POST /api/sessions/:id/messages — requires Operator+
 pub async fn send_message(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
+    Authenticated(auth_result): Authenticated,
     Path(id): Path<String>,
     Json(body): Json<SendMessageBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     if body.message.is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
+    let sampling =
+        SamplingOverride::new(body.temperature, body.top_p).map_err(|_| StatusCode::BAD_REQUEST)?;
 
     let session_id = SessionId::from_string(&id);
-    match state.runtime.run(&session_id, &body.message).await {
+    let lane = Lane::resolve(&auth_result.role, body.lane.as_deref());
+    let _permit = state.lanes.acquire(lane).await;
+    let source = MessageMetadata {
+        source_channel: Some("api".to_string()),
+        sender: Some(auth_result.name),
+        ..Default::default()
+    };
+    state.runtime.set_run_sampling(&session_id, sampling);
+    match state
+        .runtime
+        .run_with_metadata(&session_id, &body.message, source)
+        .await
+    {
         Ok(response) => Ok(Json(serde_json::json!({
             "session_id": session_id.to_string(),
             "response": response,
@@ -175,13 +213,8 @@ fn default_group_by() -> String {
 
 // GET /api/metrics — overview metrics for the dashboard
 pub async fn metrics(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let sessions = state.session_mgr.list();
     let uptime_secs = state.start_time.elapsed().as_secs();
 
@@ -219,16 +252,21 @@ pub async fn metrics(
     })))
 }
 
+// GET /api/status — in-process counters since the gateway started
+pub async fn status(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let model = state.runtime.model_config();
+    let mut body = serde_json::json!(state.runtime.metrics().snapshot());
+    body["model"] = serde_json::json!(model.model_id);
+    body["provider"] = serde_json::json!(model.provider);
+    body["active_sessions"] = serde_json::json!(state.session_mgr.list().len());
+    Json(body)
+}
+
 // GET /api/runs — paginated run history
 pub async fn runs(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<RunsQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let Some(cost_store) = state.cost_store.as_ref() else {
         return Ok(Json(serde_json::json!({
             "runs": [],
@@ -252,14 +290,9 @@ pub async fn runs(
 
 // GET /api/costs — cost summary with breakdown
 pub async fn costs(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<CostsQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let Some(cost_store) = state.cost_store.as_ref() else {
         return Ok(Json(serde_json::json!({
             "summary": { "total_cost_cents": 0, "total_input_tokens": 0, "total_output_tokens": 0, "total_events": 0 },
@@ -348,7 +381,7 @@ pub async fn webhook_wake(
             .and_then(|v| v.strip_prefix("Bearer "))
             .ok_or(StatusCode::UNAUTHORIZED)?;
 
-        if auth_header != expected_token {
+        if !auth::constant_time_eq(expected_token, auth_header) {
             return Err(StatusCode::UNAUTHORIZED);
         }
     }
@@ -368,7 +401,17 @@ pub async fn webhook_wake(
     let channel = body.channel.clone();
     let metadata = body.metadata.clone();
 
-    match state.runtime.run(&session_id, &body.prompt).await {
+    // External triggers are background work
+    let _permit = state.lanes.acquire(Lane::Batch).await;
+    let source = MessageMetadata {
+        source_channel: Some("webhook".to_string()),
+        ..Default::default()
+    };
+    match state
+        .runtime
+        .run_with_metadata(&session_id, &body.prompt, source)
+        .await
+    {
         Ok(response) => {
             // Fire callback if provided
             if let Some(url) = callback_url.clone() {
@@ -458,23 +501,18 @@ pub async fn whatsapp_incoming(
 // GET /ws — WebSocket upgrade, requires auth
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
-    Authenticated(_auth_result): Authenticated,
+    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_ws(socket, state))
+    let limit = state.config.max_ws_frame_bytes;
+    ws.max_message_size(limit)
+        .max_frame_size(limit)
+        .on_upgrade(move |socket| handle_ws(socket, state, auth_result))
 }
 
-async fn handle_ws(socket: WebSocket, state: Arc<AppState>) {
+async fn handle_ws(socket: WebSocket, state: Arc<AppState>, auth_result: auth::AuthResult) {
     info!("WebSocket client connected");
-    connection::handle_connection(
-        socket,
-        state.runtime.clone(),
-        state.event_bus.clone(),
-        state.store.clone(),
-        state.session_mgr.clone(),
-        state.broker.clone(),
-    )
-    .await;
+    connection::handle_connection(socket, state, auth_result).await;
     debug!("WebSocket client disconnected");
 }
 
@@ -496,13 +534,9 @@ fn default_audit_limit() -> usize {
 
 // GET /api/audit — paginated audit entries
 pub async fn audit_entries(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<AuditQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let trail = state.audit_trail.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let entries = if let Some(ref tool) = q.tool {
         trail
@@ -519,12 +553,8 @@ pub async fn audit_entries(
 
 // GET /api/audit/stats — summary stats
 pub async fn audit_stats(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let trail = state.audit_trail.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let total = trail.total_entries().await.unwrap_or(0);
     let tool_breakdown = trail.tool_breakdown().await.unwrap_or_default();
@@ -591,13 +621,9 @@ fn default_viking_search_limit() -> usize {
 
 // GET /api/viking/list — directory listing
 pub async fn viking_list(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<VikingListQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let viking = state.viking_client.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     match viking.list_directory(&q.path).await {
         Ok(entries) => Ok(Json(serde_json::json!(entries))),
@@ -607,13 +633,9 @@ pub async fn viking_list(
 
 // GET /api/viking/read — read a path
 pub async fn viking_read(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<VikingReadQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let viking = state.viking_client.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let level = match q.level.as_str() {
         "L0" => ryvos_memory::viking::ContextLevel::L0,
@@ -628,13 +650,9 @@ pub async fn viking_read(
 
 // GET /api/viking/search — search memories
 pub async fn viking_search(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<VikingSearchQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let viking = state.viking_client.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     match viking
         .search(&q.query, q.directory.as_deref(), q.limit)
@@ -649,12 +667,8 @@ pub async fn viking_search(
 
 // GET /api/config — read current config (Admin only)
 pub async fn get_config(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if auth_result.role != ryvos_core::config::ApiKeyRole::Admin {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     match tokio::fs::read_to_string(path).await {
         Ok(content) => Ok(Json(serde_json::json!({
@@ -667,13 +681,9 @@ pub async fn get_config(
 
 // PUT /api/config — write config (Admin only)
 pub async fn put_config(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if auth_result.role != ryvos_core::config::ApiKeyRole::Admin {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = body["content"].as_str().ok_or(StatusCode::BAD_REQUEST)?;
 
@@ -692,13 +702,8 @@ pub async fn put_config(
 
 // GET /api/channels — list configured channels with status
 pub async fn channels_status(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let mut channels = Vec::new();
 
     // Check each known channel type against configured state
@@ -723,41 +728,46 @@ pub async fn channels_status(
 
 // GET /api/approvals — list pending approvals
 pub async fn list_approvals(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let pending = state.broker.pending_requests().await;
     Ok(Json(serde_json::json!({ "approvals": pending })))
 }
 
-// POST /api/approvals/:id/approve
+#[derive(Deserialize)]
+pub struct ApproveQuery {
+    /// The confirmation word, for a request that needs one typed.
+    pub confirm: Option<String>,
+}
+
+// POST /api/approvals/:id/approve?confirm=CONFIRM
+// A request that needs a typed word stays pending until it is given.
 pub async fn approve_request(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
+    Query(q): Query<ApproveQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let decision = ryvos_core::security::ApprovalDecision::approve_with(q.confirm.as_deref());
+    if let Some(word) = state.broker.confirm_word(&id).await {
+        if !decision.confirms(&word) {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({
+                    "error": format!("this runs a destructive command; approve with ?confirm={}", word),
+                    "confirm_word": word,
+                })),
+            ));
+        }
     }
-    let found = state
-        .broker
-        .respond(&id, ryvos_core::security::ApprovalDecision::Approved)
-        .await;
+    let found = state.broker.respond(&id, decision).await;
     Ok(Json(serde_json::json!({ "approved": found })))
 }
 
 // POST /api/approvals/:id/deny
 pub async fn deny_request(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let found = state
         .broker
         .respond(
@@ -774,12 +784,8 @@ pub async fn deny_request(
 
 // GET /api/cron — list cron jobs from config
 pub async fn list_cron(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = tokio::fs::read_to_string(path)
         .await
@@ -806,13 +812,9 @@ pub struct AddCronBody {
 }
 
 pub async fn add_cron(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Json(body): Json<AddCronBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let mut content = tokio::fs::read_to_string(path)
         .await
@@ -847,13 +849,9 @@ pub async fn add_cron(
 
 // DELETE /api/cron/:name — remove a cron job
 pub async fn delete_cron(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = tokio::fs::read_to_string(path)
         .await
@@ -885,12 +883,8 @@ pub async fn delete_cron(
 
 // GET /api/budget — current budget config
 pub async fn get_budget(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     match &state.budget_config {
         Some(bc) => Ok(Json(serde_json::json!({
             "monthly_budget_cents": bc.monthly_budget_cents,
@@ -911,13 +905,9 @@ pub struct UpdateBudgetBody {
 }
 
 pub async fn put_budget(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Json(body): Json<UpdateBudgetBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = tokio::fs::read_to_string(path)
         .await
@@ -959,12 +949,8 @@ pub async fn put_budget(
 
 // GET /api/model — current model config (api_key redacted)
 pub async fn get_model(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = tokio::fs::read_to_string(path)
         .await
@@ -980,6 +966,7 @@ pub async fn get_model(
     // Redact sensitive fields
     if let Some(table) = model.as_table_mut() {
         table.remove("api_key");
+        table.remove("api_keys");
         table.remove("token");
     }
 
@@ -994,14 +981,9 @@ pub struct ModelsQuery {
 }
 
 pub async fn list_models(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<ModelsQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     // Read current provider from config if not specified
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = tokio::fs::read_to_string(path)
@@ -1068,13 +1050,9 @@ pub struct UpdateModelBody {
 }
 
 pub async fn put_model(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Json(body): Json<UpdateModelBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let path = state.config_path.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     let content = tokio::fs::read_to_string(path)
         .await
@@ -1125,12 +1103,8 @@ fn integration_apps() -> Vec<serde_json::Value> {
 
 // GET /api/integrations
 pub async fn list_integrations(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let integrations = &state.integrations_config;
     let store = &state.integration_store;
     let mut apps = Vec::new();
@@ -1165,13 +1139,9 @@ pub async fn list_integrations(
 
 // POST /api/integrations/:app/connect
 pub async fn connect_integration(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Path(app_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     if app_id == "notion" {
         if let Some(ref notion) = state.integrations_config.notion {
             if let Some(ref store) = state.integration_store {
@@ -1257,13 +1227,9 @@ pub async fn integration_callback(
 
 // DELETE /api/integrations/:app
 pub async fn disconnect_integration(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Path(app_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     if let Some(ref store) = state.integration_store {
         let deleted = store.delete(&app_id).await.unwrap_or(false);
         Ok(Json(
@@ -1290,14 +1256,9 @@ pub struct RunGoalBody {
 /// POST /api/goals/run — Fire a goal via Director orchestration.
 /// Returns immediately with session_id; events stream via WebSocket.
 pub async fn run_goal(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Json(body): Json<RunGoalBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_operator_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     if body.description.is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
@@ -1364,13 +1325,8 @@ pub async fn run_goal(
 
 /// GET /api/goals/history — List past goal/Director runs.
 pub async fn goal_history(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     if let Some(ref cost_store) = state.cost_store {
         let (runs, _total) = cost_store
             .run_history(100, 0)
@@ -1393,17 +1349,22 @@ pub async fn goal_history(
     }
 }
 
+// ── Tools API ───────────────────────────────────────────────────
+
+// GET /api/tools — every registered tool with its tier and input schema
+pub async fn list_tools(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let catalog = state.runtime.tools().read().await.catalog();
+    Ok(Json(serde_json::json!({ "tools": catalog })))
+}
+
 // ── Skills API ──────────────────────────────────────────────────
 
 // GET /api/skills — list installed skills
 pub async fn list_skills(
-    Authenticated(auth_result): Authenticated,
     State(_state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let skills_dir = dirs_home().join(".ryvos/skills");
     let mut skills = Vec::new();
 
@@ -1415,9 +1376,7 @@ pub async fn list_skills(
                     let manifest_path = path.join("skill.toml");
                     if manifest_path.exists() {
                         if let Ok(content) = std::fs::read_to_string(&manifest_path) {
-                            if let Ok(manifest) =
-                                toml::from_str::<toml::Value>(&content)
-                            {
+                            if let Ok(manifest) = toml::from_str::<toml::Value>(&content) {
                                 skills.push(serde_json::json!({
                                     "name": manifest.get("name").and_then(|v| v.as_str()).unwrap_or("unknown"),
                                     "description": manifest.get("description").and_then(|v| v.as_str()).unwrap_or(""),
@@ -1441,14 +1400,9 @@ pub async fn list_skills(
 
 // GET /api/heartbeat/history — recent heartbeat events from audit trail
 pub async fn heartbeat_history(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(q): Query<HistoryQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let trail = state.audit_trail.as_ref().ok_or(StatusCode::NOT_FOUND)?;
 
     // Heartbeat sessions are prefixed with "heartbeat:"
@@ -1478,21 +1432,14 @@ pub async fn heartbeat_history(
 
 // GET /api/safety/lessons?search=&limit=20 — safety lesson browser
 pub async fn safety_lessons(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let limit = params
         .get("limit")
         .and_then(|v| v.parse().ok())
         .unwrap_or(20);
-    let sm = state
-        .safety_memory
-        .as_ref()
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let sm = state.safety_memory.as_ref().ok_or(StatusCode::NOT_FOUND)?;
     if let Some(search) = params.get("search") {
         let lessons = sm
             .search_lessons(search, limit)
@@ -1546,13 +1493,9 @@ pub async fn safety_lessons(
 
 // GET /api/decisions?session_id=&limit=20 — decision audit browser
 pub async fn list_decisions(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let limit = params
         .get("limit")
         .and_then(|v| v.parse().ok())
@@ -1612,13 +1555,9 @@ pub async fn list_decisions(
 
 // GET /api/failures?pattern=&tool=&limit=20 — failure journal browser
 pub async fn list_failures(
-    Authenticated(auth_result): Authenticated,
     State(state): State<Arc<AppState>>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<Value>, StatusCode> {
-    if !auth::has_viewer_access(&auth_result.role) {
-        return Err(StatusCode::FORBIDDEN);
-    }
     let limit = params
         .get("limit")
         .and_then(|v| v.parse().ok())
@@ -1651,6 +1590,85 @@ pub async fn list_failures(
     })))
 }
 
+// ── OpenAI-compatible API ───────────────────────────────────────
+
+// GET /v1/models — the active model and any fallbacks, OpenAI-shaped
+pub async fn openai_models(State(state): State<Arc<AppState>>) -> Json<Value> {
+    let active = state.runtime.model_config();
+    let mut data: Vec<Value> = Vec::new();
+    for model in std::iter::once(&active).chain(state.runtime.fallback_models()) {
+        if data.iter().any(|m| m["id"] == model.model_id.as_str()) {
+            continue;
+        }
+        data.push(serde_json::json!({
+            "id": model.model_id,
+            "object": "model",
+            "created": 0,
+            "owned_by": model.provider,
+        }));
+    }
+    Json(serde_json::json!({ "object": "list", "data": data }))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// `model` and `encoding_format` are accepted for compatibility; the
+/// configured `[embedding]` model always answers, with float vectors.
+#[derive(Deserialize)]
+pub struct EmbeddingsBody {
+    input: EmbeddingInput,
+}
+
+// POST /v1/embeddings — proxied to the [embedding] provider; 404 without one
+pub async fn openai_embeddings(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<EmbeddingsBody>,
+) -> Result<Json<Value>, StatusCode> {
+    let embedder = state.embedder.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let texts = match body.input {
+        EmbeddingInput::One(text) => vec![text],
+        EmbeddingInput::Many(texts) => texts,
+    };
+    if texts.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let vectors = embedder.provider.embed(&texts).await.map_err(|e| {
+        debug!(error = %e, "Embedding provider failed");
+        StatusCode::BAD_GATEWAY
+    })?;
+    if vectors.len() != texts.len() {
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+
+    let tokens: usize = texts
+        .iter()
+        .map(|t| ryvos_agent::intelligence::estimate_tokens(t))
+        .sum();
+    let data: Vec<Value> = vectors
+        .into_iter()
+        .enumerate()
+        .map(|(index, embedding)| {
+            serde_json::json!({
+                "object": "embedding",
+                "index": index,
+                "embedding": embedding,
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({
+        "object": "list",
+        "data": data,
+        "model": embedder.model,
+        "usage": { "prompt_tokens": tokens, "total_tokens": tokens },
+    })))
+}
+
 fn dirs_home() -> std::path::PathBuf {
     std::env::var("HOME")
         .map(std::path::PathBuf::from)
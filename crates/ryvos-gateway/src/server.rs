@@ -1,22 +1,30 @@
 use std::sync::Arc;
 use std::time::Instant;
 
+use axum::extract::DefaultBodyLimit;
+use axum::http::{HeaderName, HeaderValue, Method};
 use axum::routing::{get, post};
 use axum::Router;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
-use tower_http::cors::CorsLayer;
-use tracing::info;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{info, warn};
 
-use ryvos_agent::{AgentRuntime, ApprovalBroker, AuditTrail, FailureJournal, SafetyMemory, SessionManager};
+use ryvos_agent::{
+    AgentRuntime, ApprovalBroker, AuditTrail, FailureJournal, SafetyMemory, SessionManager,
+};
 use ryvos_channels::WhatsAppWebhookHandle;
-use ryvos_core::config::{BudgetConfig, GatewayConfig, IntegrationsConfig};
+use ryvos_core::config::{BudgetConfig, CorsConfig, GatewayConfig, IntegrationsConfig};
 use ryvos_core::event::EventBus;
 use ryvos_core::traits::SessionStore;
+use ryvos_memory::embeddings::EmbeddingProvider;
 use ryvos_memory::{CostStore, IntegrationStore, SessionMetaStore, VikingClient};
 
+use crate::lane::LaneScheduler;
+use crate::middleware;
+use crate::replay::EventLog;
 use crate::routes;
-use crate::state::AppState;
+use crate::state::{AppState, Embedder};
 use crate::static_files;
 
 /// WebSocket + HTTP gateway server built on axum.
@@ -39,6 +47,7 @@ pub struct GatewayServer {
     integrations_config: IntegrationsConfig,
     safety_memory: Option<Arc<SafetyMemory>>,
     failure_journal: Option<Arc<FailureJournal>>,
+    embedder: Option<Embedder>,
 }
 
 impl GatewayServer {
@@ -69,6 +78,7 @@ impl GatewayServer {
             integrations_config: IntegrationsConfig::default(),
             safety_memory: None,
             failure_journal: None,
+            embedder: None,
         }
     }
 
@@ -127,6 +137,11 @@ impl GatewayServer {
         self.failure_journal = Some(journal);
     }
 
+    /// Set the embedding provider served at `/v1/embeddings` as `model`.
+    pub fn set_embedder(&mut self, model: String, provider: Arc<dyn EmbeddingProvider>) {
+        self.embedder = Some(Embedder { model, provider });
+    }
+
     /// Run the gateway server until the cancellation token is triggered.
     pub async fn run(&self, shutdown: CancellationToken) -> anyhow::Result<()> {
         let state = Arc::new(AppState {
@@ -148,89 +163,20 @@ impl GatewayServer {
             integrations_config: self.integrations_config.clone(),
             safety_memory: self.safety_memory.clone(),
             failure_journal: self.failure_journal.clone(),
+            event_log: Arc::new(EventLog::default().with_live_buffer(self.config.event_buffer)),
+            lanes: LaneScheduler::new(&self.config.lanes),
+            embedder: self.embedder.clone(),
         });
 
-        let app = Router::new()
-            // WebSocket
-            .route("/ws", get(routes::ws_handler))
-            // REST API
-            .route("/api/health", get(routes::health))
-            .route("/api/sessions", get(routes::list_sessions))
-            .route("/api/sessions/{id}/history", get(routes::session_history))
-            .route("/api/sessions/{id}/messages", post(routes::send_message))
-            // Monitoring dashboard API
-            .route("/api/metrics", get(routes::metrics))
-            .route("/api/runs", get(routes::runs))
-            .route("/api/costs", get(routes::costs))
-            // Audit trail API
-            .route("/api/audit", get(routes::audit_entries))
-            .route("/api/audit/stats", get(routes::audit_stats))
-            // Viking memory browser API (proxied to Viking server)
-            .route("/api/viking/list", get(routes::viking_list))
-            .route("/api/viking/read", get(routes::viking_read))
-            .route("/api/viking/search", get(routes::viking_search))
-            // Config editor API
-            .route(
-                "/api/config",
-                get(routes::get_config).put(routes::put_config),
-            )
-            // Channel status
-            .route("/api/channels", get(routes::channels_status))
-            // Approvals (already exist logically in WS, now also REST)
-            .route("/api/approvals", get(routes::list_approvals))
-            .route("/api/approvals/{id}/approve", post(routes::approve_request))
-            .route("/api/approvals/{id}/deny", post(routes::deny_request))
-            // Cron management API
-            .route("/api/cron", get(routes::list_cron).post(routes::add_cron))
-            .route(
-                "/api/cron/{name}",
-                axum::routing::delete(routes::delete_cron),
-            )
-            // Budget API
-            .route(
-                "/api/budget",
-                get(routes::get_budget).put(routes::put_budget),
-            )
-            // Model API
-            .route("/api/model", get(routes::get_model).put(routes::put_model))
-            .route("/api/models/available", get(routes::list_models))
-            // Integrations API (OAuth one-click connect)
-            .route("/api/integrations", get(routes::list_integrations))
-            .route(
-                "/api/integrations/callback",
-                get(routes::integration_callback),
-            )
-            .route(
-                "/api/integrations/{app}/connect",
-                post(routes::connect_integration),
-            )
-            .route(
-                "/api/integrations/{app}",
-                axum::routing::delete(routes::disconnect_integration),
-            )
-            // Skills API
-            .route("/api/skills", get(routes::list_skills))
-            // Heartbeat history API
-            .route("/api/heartbeat/history", get(routes::heartbeat_history))
-            // Data audit API
-            .route("/api/safety/lessons", get(routes::safety_lessons))
-            .route("/api/decisions", get(routes::list_decisions))
-            .route("/api/failures", get(routes::list_failures))
-            // Goals / Director API
-            .route("/api/goals/run", post(routes::run_goal))
-            .route("/api/goals/history", get(routes::goal_history))
-            // Webhooks
-            .route("/api/hooks/wake", post(routes::webhook_wake))
-            // WhatsApp Cloud API webhooks
-            .route(
-                "/api/whatsapp/webhook",
-                get(routes::whatsapp_verify).post(routes::whatsapp_incoming),
-            )
-            // Embedded Web UI
-            .route("/", get(static_files::index))
-            .route("/assets/{*path}", get(static_files::static_file))
-            .layer(CorsLayer::permissive())
-            .with_state(state);
+        // Sequence events once for all WebSocket clients
+        tokio::spawn(
+            state
+                .event_log
+                .clone()
+                .run(self.event_bus.clone(), shutdown.clone()),
+        );
+
+        let app = router(state);
 
         let listener = TcpListener::bind(&self.config.bind).await?;
         info!(bind = %self.config.bind, "Gateway listening");
@@ -243,3 +189,748 @@ impl GatewayServer {
         Ok(())
     }
 }
+
+/// Build the gateway router. Every route goes through `require_role`, which
+/// checks it against the role table in `auth.rs`. With `web_ui` enabled,
+/// paths no route matches fall back to the SPA. Request bodies over
+/// `max_body_bytes` are refused with 413.
+pub(crate) fn router(state: Arc<AppState>) -> Router {
+    let cors = state.config.cors.as_ref().map(cors_layer);
+    let body_limit = DefaultBodyLimit::max(state.config.max_body_bytes);
+    let mut app = Router::new()
+        // WebSocket
+        .route("/ws", get(routes::ws_handler))
+        // REST API
+        .route("/api/health", get(routes::health))
+        .route("/openapi.json", get(routes::openapi))
+        .route("/api/sessions", get(routes::list_sessions))
+        .route("/api/sessions/{id}/history", get(routes::session_history))
+        .route("/api/sessions/{id}/messages", post(routes::send_message))
+        // Monitoring dashboard API
+        .route("/api/metrics", get(routes::metrics))
+        .route("/api/status", get(routes::status))
+        .route("/api/runs", get(routes::runs))
+        .route("/api/costs", get(routes::costs))
+        // Audit trail API
+        .route("/api/audit", get(routes::audit_entries))
+        .route("/api/audit/stats", get(routes::audit_stats))
+        // Viking memory browser API (proxied to Viking server)
+        .route("/api/viking/list", get(routes::viking_list))
+        .route("/api/viking/read", get(routes::viking_read))
+        .route("/api/viking/search", get(routes::viking_search))
+        // Config editor API
+        .route(
+            "/api/config",
+            get(routes::get_config).put(routes::put_config),
+        )
+        // Channel status
+        .route("/api/channels", get(routes::channels_status))
+        // Approvals (already exist logically in WS, now also REST)
+        .route("/api/approvals", get(routes::list_approvals))
+        .route("/api/approvals/{id}/approve", post(routes::approve_request))
+        .route("/api/approvals/{id}/deny", post(routes::deny_request))
+        // Cron management API
+        .route("/api/cron", get(routes::list_cron).post(routes::add_cron))
+        .route(
+            "/api/cron/{name}",
+            axum::routing::delete(routes::delete_cron),
+        )
+        // Budget API
+        .route(
+            "/api/budget",
+            get(routes::get_budget).put(routes::put_budget),
+        )
+        // Model API
+        .route("/api/model", get(routes::get_model).put(routes::put_model))
+        .route("/api/models/available", get(routes::list_models))
+        // Integrations API (OAuth one-click connect)
+        .route("/api/integrations", get(routes::list_integrations))
+        .route(
+            "/api/integrations/callback",
+            get(routes::integration_callback),
+        )
+        .route(
+            "/api/integrations/{app}/connect",
+            post(routes::connect_integration),
+        )
+        .route(
+            "/api/integrations/{app}",
+            axum::routing::delete(routes::disconnect_integration),
+        )
+        // Skills API
+        .route("/api/tools", get(routes::list_tools))
+        .route("/api/skills", get(routes::list_skills))
+        // Heartbeat history API
+        .route("/api/heartbeat/history", get(routes::heartbeat_history))
+        // Data audit API
+        .route("/api/safety/lessons", get(routes::safety_lessons))
+        .route("/api/decisions", get(routes::list_decisions))
+        .route("/api/failures", get(routes::list_failures))
+        // Goals / Director API
+        .route("/api/goals/run", post(routes::run_goal))
+        .route("/api/goals/history", get(routes::goal_history))
+        // OpenAI-compatible API
+        .route("/v1/models", get(routes::openai_models))
+        .route("/v1/embeddings", post(routes::openai_embeddings))
+        // Webhooks
+        .route("/api/hooks/wake", post(routes::webhook_wake))
+        // WhatsApp Cloud API webhooks
+        .route(
+            "/api/whatsapp/webhook",
+            get(routes::whatsapp_verify).post(routes::whatsapp_incoming),
+        );
+    if state.config.web_ui {
+        // Embedded Web UI
+        app = app
+            .route("/", get(static_files::index))
+            .route("/assets/{*path}", get(static_files::static_file))
+            .fallback(static_files::spa_fallback);
+    }
+    let app = app
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::require_role,
+        ))
+        .layer(body_limit)
+        .with_state(state);
+
+    match cors {
+        Some(layer) => app.layer(layer),
+        None => app,
+    }
+}
+
+/// Build the CORS layer for `[gateway.cors]`. Only allowlisted origins are
+/// reflected back; preflight `OPTIONS` requests are answered by the layer.
+fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origin = if config.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(config.allowed_origins.iter().filter_map(
+            |o| match HeaderValue::from_str(o) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    warn!(origin = %o, "Ignoring invalid CORS origin");
+                    None
+                }
+            },
+        ))
+    };
+    let methods: Vec<Method> = config
+        .allowed_methods
+        .iter()
+        .filter_map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|h| HeaderName::from_bytes(h.to_lowercase().as_bytes()).ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(origin)
+        .allow_methods(methods)
+        .allow_headers(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use futures::{SinkExt, StreamExt};
+    use ryvos_core::config::{ApiKeyConfig, ApiKeyRole};
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient};
+    use ryvos_tools::ToolRegistry;
+    use tower::ServiceExt;
+
+    fn state() -> Arc<AppState> {
+        state_with_cors(None)
+    }
+
+    fn state_with_cors(cors: Option<CorsConfig>) -> Arc<AppState> {
+        state_with(cors, Arc::new(InMemorySessionStore::new()))
+    }
+
+    fn state_with(cors: Option<CorsConfig>, store: Arc<dyn SessionStore>) -> Arc<AppState> {
+        let event_bus = Arc::new(EventBus::default());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(MockLlmClient::new()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ToolRegistry::new())),
+            store.clone(),
+            event_bus.clone(),
+        );
+        let key = |name: &str, key: &str, role| ApiKeyConfig {
+            name: name.to_string(),
+            key: key.to_string(),
+            role,
+        };
+        Arc::new(AppState {
+            config: GatewayConfig {
+                bind: "127.0.0.1:0".to_string(),
+                token: None,
+                password: None,
+                api_keys: vec![
+                    key("viewer", "rk_view", ApiKeyRole::Viewer),
+                    key("operator", "rk_op", ApiKeyRole::Operator),
+                ],
+                webhooks: None,
+                cors,
+                ..Default::default()
+            },
+            runtime: Arc::new(runtime),
+            event_bus: event_bus.clone(),
+            store,
+            session_mgr: Arc::new(SessionManager::new()),
+            broker: Arc::new(ApprovalBroker::new(event_bus)),
+            whatsapp_handle: None,
+            cost_store: None,
+            budget_config: None,
+            start_time: Instant::now(),
+            audit_trail: None,
+            viking_client: None,
+            config_path: None,
+            session_meta: None,
+            integration_store: None,
+            integrations_config: IntegrationsConfig::default(),
+            safety_memory: None,
+            failure_journal: None,
+            event_log: Arc::new(EventLog::default()),
+            lanes: LaneScheduler::new(&Default::default()),
+            embedder: None,
+        })
+    }
+
+    async fn status(app: Router, method: &str, uri: &str, key: Option<&str>) -> StatusCode {
+        let mut req = Request::builder().method(method).uri(uri);
+        if let Some(key) = key {
+            req = req.header("authorization", format!("Bearer {}", key));
+        }
+        let req = req
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message": ""}"#))
+            .unwrap();
+        app.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn viewer_reads_sessions_but_cannot_start_run() {
+        let app = router(state());
+        let list = status(app.clone(), "GET", "/api/sessions", Some("rk_view")).await;
+        assert_eq!(list, StatusCode::OK);
+
+        let send = "/api/sessions/s1/messages";
+        let goal = "/api/goals/run";
+        assert_eq!(
+            status(app.clone(), "POST", send, Some("rk_view")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(app.clone(), "POST", goal, Some("rk_view")).await,
+            StatusCode::FORBIDDEN
+        );
+        // Operators pass the role check and reach the handler (empty message)
+        assert_eq!(
+            status(app.clone(), "POST", send, Some("rk_op")).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_credentials_are_unauthorized() {
+        let app = router(state());
+        let no_key = status(app.clone(), "GET", "/api/sessions", None).await;
+        assert_eq!(no_key, StatusCode::UNAUTHORIZED);
+        let health = status(app.clone(), "GET", "/api/health", None).await;
+        assert_eq!(health, StatusCode::OK);
+        let spec = status(app, "GET", "/openapi.json", None).await;
+        assert_eq!(spec, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn status_reports_runtime_counters() {
+        let state = state();
+        // The mock LLM has no responses, so the run fails
+        assert!(state
+            .runtime
+            .run(&ryvos_core::types::SessionId::new(), "hi")
+            .await
+            .is_err());
+
+        let req = Request::builder()
+            .uri("/api/status")
+            .header("authorization", "Bearer rk_view")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(state).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["runs"], 1);
+        assert_eq!(body["run_errors"], 1);
+        assert_eq!(body["tool_calls"], 0);
+        assert!(body["input_tokens"].is_u64());
+        assert!(body["model"].is_string());
+    }
+
+    #[tokio::test]
+    async fn sessions_list_includes_stored_sessions() {
+        let store = Arc::new(ryvos_memory::SqliteStore::in_memory().unwrap());
+        let state = state_with(None, store.clone());
+        let live = state.session_mgr.get_or_create("webui:a", "webui");
+        let elsewhere = ryvos_core::types::SessionId::new();
+        for sid in [&live, &elsewhere] {
+            store
+                .append_messages(sid, &[ryvos_core::types::ChatMessage::user("hi")])
+                .await
+                .unwrap();
+        }
+
+        let req = Request::builder()
+            .uri("/api/sessions")
+            .header("authorization", "Bearer rk_view")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(state).oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        // The live session once, by key; the other instance's by its ID
+        let sessions = body["sessions"].as_array().unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0], "webui:a");
+        assert_eq!(sessions[1]["id"], elsewhere.0);
+        assert_eq!(sessions[1]["message_count"], 1);
+    }
+
+    #[tokio::test]
+    async fn destructive_approval_without_the_word_stays_pending() {
+        let state = state();
+        let request = |id: &str| ryvos_core::security::ApprovalRequest {
+            id: id.to_string(),
+            tool_name: "bash".to_string(),
+            tier: ryvos_core::security::SecurityTier::T2,
+            input_summary: "rm -rf build".to_string(),
+            input_detail: None,
+            confirm_word: Some("CONFIRM".to_string()),
+            session_id: "s1".to_string(),
+            timestamp: chrono::Utc::now(),
+            calls: vec![],
+        };
+        let mut rx = state.broker.request(request("a1")).await;
+        let app = router(state.clone());
+        let approve = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("authorization", "Bearer rk_op")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(approve("/api/approvals/a1/approve"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["confirm_word"], "CONFIRM");
+        assert!(rx.try_recv().is_err(), "request should still be pending");
+        assert_eq!(state.broker.pending_requests().await.len(), 1);
+
+        let resp = app
+            .oneshot(approve("/api/approvals/a1/approve?confirm=CONFIRM"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["approved"], true);
+        assert!(rx.try_recv().unwrap().is_approved());
+    }
+
+    #[tokio::test]
+    async fn every_declared_route_is_routed() {
+        // Reaching the auth check (401) means the router has the route
+        let app = router(state());
+        for (method, path, _) in crate::auth::ROUTE_ROLES {
+            let uri = path
+                .replace("{id}", "x")
+                .replace("{name}", "x")
+                .replace("{app}", "x");
+            let resp = status(app.clone(), method, &uri, None).await;
+            assert_eq!(resp, StatusCode::UNAUTHORIZED, "{} {}", method, path);
+        }
+    }
+
+    #[tokio::test]
+    async fn undeclared_route_is_refused() {
+        let state = state();
+        let app = Router::new()
+            .route("/api/undeclared", get(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                middleware::require_role,
+            ))
+            .with_state(state);
+        let resp = status(app, "GET", "/api/undeclared", Some("rk_op")).await;
+        assert_eq!(resp, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn model_endpoint_redacts_every_key() {
+        let path = std::env::temp_dir().join(format!("ryvos_model_{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "[model]\nprovider = \"gemini\"\nmodel_id = \"m\"\n\
+             api_key = \"k-main\"\napi_keys = [\"k-spare\"]\n",
+        )
+        .unwrap();
+        let mut state = Arc::into_inner(state()).unwrap();
+        state.config_path = Some(path.clone());
+
+        let req = Request::builder()
+            .uri("/api/model")
+            .header("authorization", "Bearer rk_view")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(Arc::new(state)).oneshot(req).await.unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("\"model_id\":\"m\""), "{}", body);
+        assert!(
+            !body.contains("k-main") && !body.contains("k-spare"),
+            "{}",
+            body
+        );
+    }
+
+    #[tokio::test]
+    async fn public_path_is_public_for_its_method_only() {
+        let state = state();
+        let app = Router::new()
+            .route(
+                "/api/health",
+                get(|| async { "ok" }).post(|| async { "changed" }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                middleware::require_role,
+            ))
+            .with_state(state);
+        let get = status(app.clone(), "GET", "/api/health", None).await;
+        assert_eq!(get, StatusCode::OK);
+        let post = status(app, "POST", "/api/health", None).await;
+        assert_eq!(post, StatusCode::FORBIDDEN);
+    }
+
+    fn cors_app() -> Router {
+        router(state_with_cors(Some(CorsConfig {
+            allowed_origins: vec!["https://ui.example.com".to_string()],
+            ..Default::default()
+        })))
+    }
+
+    async fn cors_request(app: Router, method: &str, origin: &str) -> axum::response::Response {
+        let mut req = Request::builder()
+            .method(method)
+            .uri("/api/health")
+            .header("origin", origin);
+        if method == "OPTIONS" {
+            req = req
+                .header("access-control-request-method", "GET")
+                .header("access-control-request-headers", "authorization");
+        }
+        app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn cors_reflects_allowed_origin() {
+        let resp = cors_request(cors_app(), "GET", "https://ui.example.com").await;
+        assert_eq!(
+            resp.headers()["access-control-allow-origin"],
+            "https://ui.example.com"
+        );
+
+        let preflight = cors_request(cors_app(), "OPTIONS", "https://ui.example.com").await;
+        assert_eq!(preflight.status(), StatusCode::OK);
+        let headers = preflight.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://ui.example.com"
+        );
+        assert!(headers["access-control-allow-methods"]
+            .to_str()
+            .unwrap()
+            .contains("GET"));
+        assert!(headers["access-control-allow-headers"]
+            .to_str()
+            .unwrap()
+            .contains("authorization"));
+    }
+
+    #[tokio::test]
+    async fn cors_ignores_other_origins() {
+        let resp = cors_request(cors_app(), "GET", "https://evil.example.com").await;
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+
+        let preflight = cors_request(cors_app(), "OPTIONS", "https://evil.example.com").await;
+        assert!(!preflight
+            .headers()
+            .contains_key("access-control-allow-origin"));
+
+        // No [gateway.cors] section: same-origin only
+        let resp = cors_request(router(state()), "GET", "https://ui.example.com").await;
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+    }
+
+    async fn get_page(app: Router, uri: &str, etag: Option<&str>) -> axum::response::Response {
+        let mut req = Request::builder().uri(uri);
+        if let Some(etag) = etag {
+            req = req.header("if-none-match", etag);
+        }
+        app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    fn content_type(resp: &axum::response::Response) -> &str {
+        resp.headers()["content-type"].to_str().unwrap()
+    }
+
+    /// Name of the built asset with this extension, e.g. `index-abc123.js`.
+    fn asset(ext: &str) -> String {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("ui/assets");
+        std::fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .find(|name| name.ends_with(ext))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn assets_have_content_types_and_etags() {
+        let app = router(state());
+        let js = get_page(app.clone(), &format!("/assets/{}", asset(".js")), None).await;
+        assert_eq!(js.status(), StatusCode::OK);
+        assert!(content_type(&js).contains("javascript"));
+        assert_eq!(
+            js.headers()["cache-control"],
+            "public, max-age=31536000, immutable"
+        );
+        let css = get_page(app.clone(), &format!("/assets/{}", asset(".css")), None).await;
+        assert_eq!(content_type(&css), "text/css; charset=utf-8");
+
+        let index = get_page(app.clone(), "/", None).await;
+        assert_eq!(content_type(&index), "text/html; charset=utf-8");
+        assert_eq!(index.headers()["cache-control"], "no-cache");
+        let etag = index.headers()["etag"].to_str().unwrap().to_string();
+        assert!(etag.starts_with('"'));
+
+        let cached = get_page(app.clone(), "/", Some(&etag)).await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(cached.headers()["etag"], etag.as_str());
+        let stale = get_page(app, "/", Some("\"other\"")).await;
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unknown_paths_fall_back_to_index() {
+        let app = router(state());
+        let page = get_page(app.clone(), "/sessions/abc", None).await;
+        assert_eq!(page.status(), StatusCode::OK);
+        assert_eq!(content_type(&page), "text/html; charset=utf-8");
+
+        // API routes and missing assets are never answered with the SPA
+        let health = get_page(app.clone(), "/api/health", None).await;
+        assert_eq!(content_type(&health), "application/json");
+        let api = get_page(app.clone(), "/api/nope", None).await;
+        assert_eq!(api.status(), StatusCode::NOT_FOUND);
+        let missing = get_page(app.clone(), "/assets/missing.js", None).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        let post = status(app, "POST", "/sessions/abc", None).await;
+        assert_eq!(post, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn web_ui_can_be_disabled() {
+        let mut state = state();
+        Arc::get_mut(&mut state).unwrap().config.web_ui = false;
+        let app = router(state);
+        assert_eq!(
+            get_page(app.clone(), "/", None).await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get_page(app, "/sessions/abc", None).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_body_is_rejected() {
+        let mut state = state();
+        Arc::get_mut(&mut state).unwrap().config.max_body_bytes = 64;
+        let app = router(state);
+        let body = format!(r#"{{"message": "{}"}}"#, "x".repeat(100));
+        let req = Request::post("/api/sessions/s1/messages")
+            .header("authorization", "Bearer rk_op")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn oversized_ws_frame_closes_connection() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let mut state = state();
+        Arc::get_mut(&mut state).unwrap().config.max_ws_frame_bytes = 1024;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        let url = format!("ws://{}/ws?token=rk_view", addr);
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let hello = ws.next().await.unwrap().unwrap();
+        assert!(hello.to_text().unwrap().contains("resume_token"));
+
+        ws.send(WsMessage::text("x".repeat(4096))).await.unwrap();
+        let close = loop {
+            match ws.next().await {
+                Some(Ok(WsMessage::Close(frame))) => break frame.unwrap(),
+                Some(Ok(_)) => continue,
+                other => panic!("expected a close frame, got {:?}", other),
+            }
+        };
+        assert_eq!(u16::from(close.code), 1009);
+        assert!(close.reason.contains("max_ws_frame_bytes"));
+    }
+
+    struct FixedEmbedder(usize);
+
+    impl EmbeddingProvider for FixedEmbedder {
+        fn embed(
+            &self,
+            texts: &[String],
+        ) -> futures::future::BoxFuture<'_, Result<Vec<Vec<f32>>, String>> {
+            let vectors = texts.iter().map(|t| vec![t.len() as f32; self.0]).collect();
+            Box::pin(async move { Ok(vectors) })
+        }
+
+        fn dimensions(&self) -> usize {
+            self.0
+        }
+    }
+
+    async fn post_json(app: Router, uri: &str, body: &str) -> (StatusCode, serde_json::Value) {
+        let req = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("authorization", "Bearer rk_op")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let status = resp.status();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn tools_route_serves_the_catalog() {
+        let state = state();
+        *state.runtime.tools().write().await = ToolRegistry::with_builtins();
+
+        let req = Request::builder()
+            .uri("/api/tools")
+            .header("authorization", "Bearer rk_view")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(state).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let tools = body["tools"].as_array().unwrap();
+        let read = tools.iter().find(|t| t["name"] == "read").unwrap();
+        assert_eq!(read["tier"], "t0");
+        assert_eq!(read["input_schema"]["type"], "object");
+        assert!(read["description"].is_string());
+    }
+
+    #[tokio::test]
+    async fn v1_models_lists_the_configured_model() {
+        let req = Request::builder()
+            .uri("/v1/models")
+            .header("authorization", "Bearer rk_view")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(state()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["object"], "list");
+        assert_eq!(body["data"][0]["id"], "test-model");
+        assert_eq!(body["data"][0]["owned_by"], "openai");
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        let anonymous = status(router(state()), "GET", "/v1/models", None).await;
+        assert_eq!(anonymous, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn v1_embeddings_returns_configured_dimension() {
+        let mut state = state();
+        Arc::get_mut(&mut state).unwrap().embedder = Some(Embedder {
+            model: "nomic-embed-text".to_string(),
+            provider: Arc::new(FixedEmbedder(8)),
+        });
+        let app = router(state);
+
+        let body = r#"{"model": "text-embedding-3-small", "input": ["one", "three"]}"#;
+        let (code, resp) = post_json(app.clone(), "/v1/embeddings", body).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(resp["model"], "nomic-embed-text");
+        let data = resp["data"].as_array().unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1]["index"], 1);
+        let vector = data[1]["embedding"].as_array().unwrap();
+        assert_eq!(vector.len(), 8);
+        assert_eq!(vector[0], 5.0);
+
+        let (code, resp) = post_json(app.clone(), "/v1/embeddings", r#"{"input": "hi"}"#).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(resp["data"].as_array().unwrap().len(), 1);
+
+        let (code, _) = post_json(app, "/v1/embeddings", r#"{"input": []}"#).await;
+        assert_eq!(code, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn v1_embeddings_without_embedding_config_is_not_found() {
+        let (code, _) = post_json(router(state()), "/v1/embeddings", r#"{"input": "hi"}"#).await;
+        assert_eq!(code, StatusCode::NOT_FOUND);
+        let viewer = status(router(state()), "POST", "/v1/embeddings", Some("rk_view")).await;
+        assert_eq!(viewer, StatusCode::FORBIDDEN);
+    }
+}
@@ -2,14 +2,20 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 
-use ryvos_agent::{AgentRuntime, ApprovalBroker, AuditTrail, FailureJournal, SafetyMemory, SessionManager};
+use ryvos_agent::{
+    AgentRuntime, ApprovalBroker, AuditTrail, FailureJournal, SafetyMemory, SessionManager,
+};
 use ryvos_channels::WhatsAppWebhookHandle;
 use ryvos_core::config::IntegrationsConfig;
 use ryvos_core::config::{BudgetConfig, GatewayConfig};
 use ryvos_core::event::EventBus;
 use ryvos_core::traits::SessionStore;
+use ryvos_memory::embeddings::EmbeddingProvider;
 use ryvos_memory::{CostStore, IntegrationStore, SessionMetaStore, VikingClient};
 
+use crate::lane::LaneScheduler;
+use crate::replay::EventLog;
+
 /// Shared application state for axum handlers.
 pub struct AppState {
     pub config: GatewayConfig,
@@ -30,4 +36,17 @@ pub struct AppState {
     pub integrations_config: IntegrationsConfig,
     pub safety_memory: Option<Arc<SafetyMemory>>,
     pub failure_journal: Option<Arc<FailureJournal>>,
+    /// Sequenced event frames for live streaming and `session.resume`.
+    pub event_log: Arc<EventLog>,
+    /// Admission control for agent runs (`[gateway.lanes]`).
+    pub lanes: LaneScheduler,
+    /// The `[embedding]` provider behind `POST /v1/embeddings`.
+    pub embedder: Option<Embedder>,
+}
+
+/// An embedding provider and the model name reported for it.
+#[derive(Clone)]
+pub struct Embedder {
+    pub model: String,
+    pub provider: Arc<dyn EmbeddingProvider>,
 }
@@ -1,37 +1,110 @@
+//! Embedded Web UI (Svelte SPA built into `ui/`).
+//!
+//! Assets are served with their content type, a strong `ETag` (the embedded
+//! file's SHA-256) and `Last-Modified`, and answer conditional requests with
+//! `304 Not Modified`. Vite fingerprints everything under `assets/`, so those
+//! are cached as immutable; `index.html` is revalidated on every load. Unknown
+//! non-API paths fall back to `index.html` so client-side routes survive a
+//! reload.
+
 use axum::extract::Path;
-use axum::http::{header, StatusCode};
-use axum::response::IntoResponse;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, TimeZone, Utc};
 use rust_embed::Embed;
 
 #[derive(Embed)]
 #[folder = "ui/"]
 struct UiAssets;
 
-pub async fn index() -> impl IntoResponse {
-    match UiAssets::get("index.html") {
-        Some(content) => (
-            StatusCode::OK,
-            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
-            content.data.to_vec(),
+/// Fingerprinted build output never changes under the same name.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// The SPA shell must be revalidated so new asset names are picked up.
+const REVALIDATE: &str = "no-cache";
+
+pub async fn index(headers: HeaderMap) -> Response {
+    serve("index.html", REVALIDATE, &headers)
+}
+
+pub async fn static_file(Path(path): Path<String>, headers: HeaderMap) -> Response {
+    serve(&format!("assets/{path}"), IMMUTABLE, &headers)
+}
+
+/// Router fallback: `index.html` for page loads of client-side routes, 404
+/// for API paths, missing assets and non-GET requests.
+pub async fn spa_fallback(method: Method, uri: Uri, headers: HeaderMap) -> Response {
+    let path = uri.path();
+    let is_page = method == Method::GET || method == Method::HEAD;
+    let reserved = ["/api/", "/v1/", "/assets/", "/ws"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix));
+    if is_page && !reserved {
+        index(headers).await
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+fn serve(path: &str, cache_control: &'static str, headers: &HeaderMap) -> Response {
+    let Some(content) = UiAssets::get(path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let etag = format!("\"{}\"", hex(&content.metadata.sha256_hash()));
+    let modified = content
+        .metadata
+        .last_modified()
+        .and_then(|secs| Utc.timestamp_opt(secs as i64, 0).single());
+
+    let mut response = if not_modified(headers, &etag, modified) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+        let content_type = if mime.type_() == mime_guess::mime::TEXT {
+            format!("{}; charset=utf-8", mime.essence_str())
+        } else {
+            mime.essence_str().to_string()
+        };
+        (
+            [(header::CONTENT_TYPE, content_type)],
+            content.data.into_owned(),
         )
-            .into_response(),
-        None => StatusCode::NOT_FOUND.into_response(),
+            .into_response()
+    };
+
+    let out = response.headers_mut();
+    out.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        out.insert(header::ETAG, value);
+    }
+    if let Some(value) = modified.and_then(|t| HeaderValue::from_str(&http_date(t)).ok()) {
+        out.insert(header::LAST_MODIFIED, value);
     }
+    response
 }
 
-pub async fn static_file(Path(path): Path<String>) -> impl IntoResponse {
-    match UiAssets::get(&format!("assets/{path}")) {
-        Some(content) => {
-            let mime = mime_guess::from_path(&path)
-                .first_or_octet_stream()
-                .to_string();
-            (
-                StatusCode::OK,
-                [(header::CONTENT_TYPE, mime)],
-                content.data.to_vec(),
-            )
-                .into_response()
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
+/// `If-None-Match` wins over `If-Modified-Since` when both are sent.
+fn not_modified(headers: &HeaderMap, etag: &str, modified: Option<DateTime<Utc>>) -> bool {
+    if let Some(tags) = headers.get(header::IF_NONE_MATCH) {
+        let tags = tags.to_str().unwrap_or("");
+        return tags
+            .split(',')
+            .map(|t| t.trim().trim_start_matches("W/"))
+            .any(|t| t == "*" || t == etag);
     }
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok());
+    matches!((since, modified), (Some(since), Some(modified)) if modified <= since)
+}
+
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
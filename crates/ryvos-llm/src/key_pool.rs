@@ -0,0 +1,214 @@
+//! Rotation across several API keys for one provider.
+//!
+//! [`KeyPoolClient`] wraps a provider client when `[model] api_keys` is set.
+//! Each request starts from the next key in turn, with keys that were
+//! rate-limited or rejected most recently tried last. A key that gets
+//! HTTP 429 or 401 is marked and the request moves on to the next key;
+//! other errors are returned as they are, for [`RetryingClient`](crate::RetryingClient)
+//! to handle.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use tracing::warn;
+
+use ryvos_core::config::ModelConfig;
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::traits::LlmClient;
+use ryvos_core::types::*;
+
+pub struct KeyPoolClient {
+    inner: Box<dyn LlmClient>,
+    next: AtomicUsize,
+    /// When each key last got a 429 or 401.
+    failed_at: Mutex<HashMap<String, Instant>>,
+}
+
+impl KeyPoolClient {
+    pub fn new(inner: Box<dyn LlmClient>) -> Self {
+        Self {
+            inner,
+            next: AtomicUsize::new(0),
+            failed_at: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keys to try for this request, in order.
+    fn candidates(&self, config: &ModelConfig) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for key in config.api_key.iter().chain(&config.api_keys) {
+            if !key.is_empty() && !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        if keys.len() > 1 {
+            let start = self.next.fetch_add(1, Ordering::Relaxed) % keys.len();
+            keys.rotate_left(start);
+            // Stable: keys that never failed keep their round-robin order
+            let failed_at = self.failed_at.lock().unwrap();
+            keys.sort_by_key(|k| failed_at.get(k).copied());
+        }
+        keys
+    }
+}
+
+/// Whether the error means this key is rate-limited or not accepted.
+/// Gemini refuses a bad key with a 400 whose body names `API_KEY_INVALID`.
+fn is_key_error(e: &RyvosError) -> bool {
+    matches!(e, RyvosError::LlmRequest(msg)
+        if msg.starts_with("HTTP 429")
+            || msg.starts_with("HTTP 401")
+            || (msg.starts_with("HTTP 400") && msg.contains("API_KEY_INVALID")))
+}
+
+impl LlmClient for KeyPoolClient {
+    fn chat_stream(
+        &self,
+        config: &ModelConfig,
+        messages: Vec<ChatMessage>,
+        tools: &[ToolDefinition],
+    ) -> BoxFuture<'_, Result<BoxStream<'_, Result<StreamDelta>>>> {
+        let keys = self.candidates(config);
+        let mut config = config.clone();
+        let tools = tools.to_vec();
+
+        Box::pin(async move {
+            if keys.is_empty() {
+                return self.inner.chat_stream(&config, messages, &tools).await;
+            }
+            let mut last_err = None;
+            for (i, key) in keys.iter().enumerate() {
+                config.api_key = Some(key.clone());
+                match self
+                    .inner
+                    .chat_stream(&config, messages.clone(), &tools)
+                    .await
+                {
+                    Err(e) if is_key_error(&e) => {
+                        warn!(
+                            attempt = i + 1,
+                            keys = keys.len(),
+                            error = %e,
+                            "API key rate-limited or rejected, trying the next one"
+                        );
+                        self.failed_at
+                            .lock()
+                            .unwrap()
+                            .insert(key.clone(), Instant::now());
+                        last_err = Some(e);
+                    }
+                    other => return other,
+                }
+            }
+            Err(last_err.unwrap_or_else(|| RyvosError::LlmRequest("No API key worked".into())))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use std::sync::Arc;
+
+    type Seen = Arc<Mutex<Vec<String>>>;
+
+    /// Provider that records the key of each call and answers 429 for
+    /// keys in `limited`.
+    struct KeyRecorder {
+        limited: Vec<&'static str>,
+        seen: Seen,
+    }
+
+    impl LlmClient for KeyRecorder {
+        fn chat_stream(
+            &self,
+            config: &ModelConfig,
+            _messages: Vec<ChatMessage>,
+            _tools: &[ToolDefinition],
+        ) -> BoxFuture<'_, Result<BoxStream<'_, Result<StreamDelta>>>> {
+            let key = config.api_key.clone().unwrap_or_default();
+            self.seen.lock().unwrap().push(key.clone());
+            let limited = self.limited.contains(&key.as_str());
+            Box::pin(async move {
+                if limited {
+                    return Err(RyvosError::LlmRequest(
+                        "HTTP 429 Too Many Requests: slow down".into(),
+                    ));
+                }
+                Ok(Box::pin(stream::empty()) as BoxStream<'_, Result<StreamDelta>>)
+            })
+        }
+    }
+
+    fn pool(limited: Vec<&'static str>) -> (KeyPoolClient, Seen) {
+        let seen = Seen::default();
+        let recorder = KeyRecorder {
+            limited,
+            seen: seen.clone(),
+        };
+        (KeyPoolClient::new(Box::new(recorder)), seen)
+    }
+
+    fn config(keys: &[&str]) -> ModelConfig {
+        serde_json::from_value(serde_json::json!({
+            "model_id": "gpt-4o",
+            "api_key": keys[0],
+            "api_keys": &keys[1..],
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_rotate_across_keys() {
+        let (client, seen) = pool(vec![]);
+        let config = config(&["k1", "k2", "k3"]);
+        for _ in 0..4 {
+            assert!(client.chat_stream(&config, vec![], &[]).await.is_ok());
+        }
+        assert_eq!(*seen.lock().unwrap(), ["k1", "k2", "k3", "k1"]);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_key_is_tried_last() {
+        let (client, seen) = pool(vec!["k1"]);
+        let config = config(&["k1", "k2"]);
+
+        // The 429 moves the same request on to k2
+        assert!(client.chat_stream(&config, vec![], &[]).await.is_ok());
+        assert_eq!(*seen.lock().unwrap(), ["k1", "k2"]);
+
+        // Later requests go to k2 first, even on k1's turn
+        assert!(client.chat_stream(&config, vec![], &[]).await.is_ok());
+        assert!(client.chat_stream(&config, vec![], &[]).await.is_ok());
+        assert_eq!(*seen.lock().unwrap(), ["k1", "k2", "k2", "k2"]);
+    }
+
+    #[test]
+    fn key_errors_are_recognized() {
+        let err = |msg: &str| RyvosError::LlmRequest(msg.to_string());
+        assert!(is_key_error(&err("HTTP 429 Too Many Requests: slow down")));
+        assert!(is_key_error(&err("HTTP 401 Unauthorized: bad key")));
+        let gemini = r#"HTTP 400 Bad Request: {"error": {"code": 400, "message": "API key not valid. Please pass a valid API key.", "status": "INVALID_ARGUMENT", "details": [{"reason": "API_KEY_INVALID"}]}}"#;
+        assert!(is_key_error(&err(gemini)));
+        assert!(!is_key_error(&err(
+            "HTTP 400 Bad Request: {\"error\": {\"message\": \"Invalid JSON payload\"}}"
+        )));
+    }
+
+    #[tokio::test]
+    async fn every_key_limited_returns_the_error() {
+        let (client, seen) = pool(vec!["k1", "k2"]);
+        let err = client
+            .chat_stream(&config(&["k1", "k2"]), vec![], &[])
+            .await
+            .err()
+            .unwrap();
+        assert!(is_key_error(&err));
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+}
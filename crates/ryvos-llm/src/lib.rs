@@ -13,15 +13,22 @@
 //! **Key components:**
 //! - [`create_client`] / [`create_client_with_security`]: Factory functions
 //! - [`RetryingClient`]: Wraps any client with exponential backoff and model fallback
+//! - [`KeyPoolClient`]: Rotates requests across a provider's `api_keys`
+//! - [`RecordingClient`] / [`ReplayClient`]: Record a run's responses and replay them
 //! - [`streaming::SseParser`]: Server-Sent Events parser for HTTP streaming
 
+pub mod key_pool;
 pub mod providers;
+pub mod replay;
 pub mod retry;
 pub mod streaming;
 
+use std::sync::Arc;
+
 use ryvos_core::config::ModelConfig;
 use ryvos_core::traits::LlmClient;
 
+pub use key_pool::KeyPoolClient;
 pub use providers::anthropic::AnthropicClient;
 pub use providers::azure::AzureClient;
 pub use providers::bedrock::BedrockClient;
@@ -30,6 +37,7 @@ pub use providers::cohere::CohereClient;
 pub use providers::copilot::CopilotClient;
 pub use providers::gemini::GeminiClient;
 pub use providers::openai::OpenAiClient;
+pub use replay::{RecordingClient, ReplayClient, Transcript};
 pub use retry::RetryingClient;
 
 /// Create an LLM client based on the provider name.
@@ -45,7 +53,18 @@ pub use retry::RetryingClient;
 /// - `openai` — OpenAI (default fallback)
 /// - 10 preset providers (OpenAI-compatible): ollama, groq, openrouter,
 ///   together, fireworks, cerebras, xai, mistral, perplexity, deepseek
+///
+/// With `api_keys` set, the client is wrapped in a [`KeyPoolClient`].
 pub fn create_client(config: &ModelConfig) -> Box<dyn LlmClient> {
+    let client = create_provider_client(config);
+    if config.api_keys.is_empty() {
+        client
+    } else {
+        Box::new(KeyPoolClient::new(client))
+    }
+}
+
+fn create_provider_client(config: &ModelConfig) -> Box<dyn LlmClient> {
     match config.provider.as_str() {
         "anthropic" | "claude" => Box::new(AnthropicClient::new()),
         "gemini" | "google" => Box::new(GeminiClient::new()),
@@ -79,6 +98,54 @@ pub fn create_client_with_security(
     }
 }
 
+/// Provider names accepted by [`create_client`], including preset aliases.
+const NATIVE_PROVIDERS: &[&str] = &[
+    "anthropic",
+    "claude",
+    "gemini",
+    "google",
+    "azure",
+    "azure-openai",
+    "bedrock",
+    "aws-bedrock",
+    "aws",
+    "cohere",
+    "claude-code",
+    "claude-cli",
+    "claude-sub",
+    "copilot",
+    "github-copilot",
+    "copilot-cli",
+    "openai",
+];
+
+/// Whether `provider` names a native provider or an OpenAI-compatible preset.
+pub fn is_known_provider(provider: &str) -> bool {
+    NATIVE_PROVIDERS.contains(&provider) || providers::presets::get_preset(provider).is_some()
+}
+
+/// Build the client for `model`, wrapped in a [`RetryingClient`] when the
+/// model has a retry policy or `fallbacks` is non-empty.
+///
+/// Preset defaults are expected to be applied to `model` and `fallbacks`
+/// already.
+pub fn build_client(
+    model: &ModelConfig,
+    fallbacks: &[ModelConfig],
+    dangerous_patterns: &[ryvos_core::security::DangerousPattern],
+) -> Arc<dyn LlmClient> {
+    let primary = create_client_with_security(model, dangerous_patterns);
+    if fallbacks.is_empty() && model.retry.is_none() {
+        return Arc::from(primary);
+    }
+    let retry_config = model.retry.clone().unwrap_or_default();
+    let fallbacks = fallbacks
+        .iter()
+        .map(|mc| (mc.clone(), create_client(mc)))
+        .collect();
+    Arc::new(RetryingClient::new(primary, fallbacks, retry_config))
+}
+
 /// Resolve preset defaults into a ModelConfig, filling in base_url and
 /// extra_headers if not already set by the user.
 pub fn apply_preset_defaults(config: &mut ModelConfig) {
@@ -33,6 +33,8 @@ struct AnthropicRequest {
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
     messages: Vec<ApiMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     system: Option<String>,
@@ -41,6 +43,8 @@ struct AnthropicRequest {
     tools: Vec<ApiTool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -49,6 +53,17 @@ struct ThinkingConfig {
     budget_tokens: u32,
 }
 
+/// Smallest thinking budget the Messages API accepts.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Extended thinking request for `level`, or `None` when thinking is off.
+fn thinking_config(level: &ThinkingLevel) -> Option<ThinkingConfig> {
+    level.is_enabled().then(|| ThinkingConfig {
+        r#type: "enabled".to_string(),
+        budget_tokens: level.budget_tokens().max(MIN_THINKING_BUDGET),
+    })
+}
+
 #[derive(Serialize)]
 struct ApiMessage {
     role: String,
@@ -232,6 +247,7 @@ fn parse_sse_to_delta(event: SseEvent) -> Option<Result<StreamDelta>> {
                     deltas.push(StreamDelta::Usage {
                         input_tokens: usage.input_tokens,
                         output_tokens: usage.output_tokens,
+                        thinking_tokens: 0,
                     });
                 }
                 // Return just the message ID; usage tracked separately
@@ -289,6 +305,46 @@ fn parse_sse_to_delta(event: SseEvent) -> Option<Result<StreamDelta>> {
     }
 }
 
+fn build_request(
+    config: &ModelConfig,
+    messages: Vec<ChatMessage>,
+    tools: &[ToolDefinition],
+) -> AnthropicRequest {
+    let (system, api_messages) = convert_messages(messages);
+
+    let api_tools: Vec<ApiTool> = tools
+        .iter()
+        .map(|t| ApiTool {
+            name: t.name.clone(),
+            description: t.description.clone(),
+            input_schema: t.input_schema.clone(),
+        })
+        .collect();
+
+    let thinking = thinking_config(&config.thinking);
+
+    AnthropicRequest {
+        model: config.model_id.clone(),
+        max_tokens: config.max_output_tokens(),
+        // Must NOT send temperature or top_p when thinking is enabled
+        // (Anthropic constraint)
+        temperature: if thinking.is_some() {
+            None
+        } else if config.temperature > 0.0 {
+            Some(config.temperature)
+        } else {
+            None
+        },
+        top_p: config.top_p.filter(|_| thinking.is_none()),
+        messages: api_messages,
+        system,
+        stream: true,
+        tools: api_tools,
+        thinking,
+        stop_sequences: config.stop_sequences.clone(),
+    }
+}
+
 impl LlmClient for AnthropicClient {
     fn chat_stream(
         &self,
@@ -306,45 +362,7 @@ impl LlmClient for AnthropicClient {
                 .ok_or_else(|| RyvosError::Config("Anthropic API key not set".into()))?;
 
             let base_url = config.base_url.as_deref().unwrap_or(ANTHROPIC_API_URL);
-
-            let (system, api_messages) = convert_messages(messages);
-
-            let api_tools: Vec<ApiTool> = tools
-                .iter()
-                .map(|t| ApiTool {
-                    name: t.name.clone(),
-                    description: t.description.clone(),
-                    input_schema: t.input_schema.clone(),
-                })
-                .collect();
-
-            // Build thinking config if enabled
-            let thinking = if config.thinking != ryvos_core::types::ThinkingLevel::Off {
-                Some(ThinkingConfig {
-                    r#type: "enabled".to_string(),
-                    budget_tokens: config.thinking.budget_tokens(),
-                })
-            } else {
-                None
-            };
-
-            let body = AnthropicRequest {
-                model: config.model_id.clone(),
-                max_tokens: config.max_tokens,
-                // Must NOT send temperature when thinking is enabled (Anthropic constraint)
-                temperature: if thinking.is_some() {
-                    None
-                } else if config.temperature > 0.0 {
-                    Some(config.temperature)
-                } else {
-                    None
-                },
-                messages: api_messages,
-                system,
-                stream: true,
-                tools: api_tools,
-                thinking,
-            };
+            let body = build_request(&config, messages, &tools);
 
             let response = self
                 .http
@@ -382,3 +400,58 @@ impl Default for AnthropicClient {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thinking_maps_to_budget_tokens() {
+        assert!(thinking_config(&ThinkingLevel::Off).is_none());
+        assert!(thinking_config(&ThinkingLevel::Custom(0)).is_none());
+        assert_eq!(
+            thinking_config(&ThinkingLevel::Medium)
+                .unwrap()
+                .budget_tokens,
+            10240
+        );
+        let custom = thinking_config(&ThinkingLevel::Custom(4096)).unwrap();
+        assert_eq!(
+            serde_json::to_value(&custom).unwrap(),
+            serde_json::json!({ "type": "enabled", "budget_tokens": 4096 })
+        );
+        // Raised to the API minimum
+        let tiny = thinking_config(&ThinkingLevel::Custom(100)).unwrap();
+        assert_eq!(tiny.budget_tokens, MIN_THINKING_BUDGET);
+    }
+
+    #[test]
+    fn request_carries_stop_sequences() {
+        let config: ModelConfig = serde_json::from_value(serde_json::json!({
+            "model_id": "claude-sonnet-4-20250514",
+            "stop_sequences": ["</plan>"],
+        }))
+        .unwrap();
+        let body = build_request(&config, vec![ChatMessage::user("Plan it")], &[]);
+        let body = serde_json::to_value(body).unwrap();
+        assert_eq!(body["stop_sequences"], serde_json::json!(["</plan>"]));
+    }
+
+    #[test]
+    fn sampling_is_dropped_while_thinking() {
+        let mut config: ModelConfig = serde_json::from_value(serde_json::json!({
+            "model_id": "claude-sonnet-4-20250514",
+            "temperature": 0.9,
+            "top_p": 0.8,
+        }))
+        .unwrap();
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert_eq!(body["top_p"], serde_json::json!(0.8f32));
+        assert_eq!(body["temperature"], serde_json::json!(0.9f32));
+
+        config.thinking = ThinkingLevel::Low;
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert!(body.get("top_p").is_none());
+        assert!(body.get("temperature").is_none());
+    }
+}
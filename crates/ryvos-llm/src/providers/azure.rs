@@ -28,6 +28,39 @@ impl Default for AzureClient {
     }
 }
 
+fn build_request(
+    config: &ModelConfig,
+    messages: Vec<ChatMessage>,
+    tools: &[ToolDefinition],
+) -> serde_json::Value {
+    // Reuse OpenAI message conversion
+    let oai_messages = super::openai::convert_messages(messages);
+    let oai_tools = super::openai::convert_tools(tools);
+
+    let mut body = serde_json::json!({
+        "messages": oai_messages,
+        "max_tokens": config.max_output_tokens(),
+        "stream": true,
+        "tools": oai_tools,
+    });
+    if !config.stop_sequences.is_empty() {
+        body["stop"] = serde_json::json!(config.stop_sequences);
+    }
+    if let Some(seed) = config.seed {
+        body["seed"] = seed.into();
+    }
+    // o-series deployments reject sampling parameters
+    if !super::openai::is_o_series(&config.model_id) {
+        if config.temperature > 0.0 {
+            body["temperature"] = config.temperature.into();
+        }
+        if let Some(top_p) = config.top_p {
+            body["top_p"] = top_p.into();
+        }
+    }
+    body
+}
+
 impl LlmClient for AzureClient {
     fn chat_stream(
         &self,
@@ -57,16 +90,7 @@ impl LlmClient for AzureClient {
                 "https://{resource}.openai.azure.com/openai/deployments/{deployment}/chat/completions?api-version={api_version}"
             );
 
-            // Reuse OpenAI message conversion
-            let oai_messages = super::openai::convert_messages(messages);
-            let oai_tools = super::openai::convert_tools(&tools);
-
-            let body = serde_json::json!({
-                "messages": oai_messages,
-                "max_tokens": config.max_tokens,
-                "stream": true,
-                "tools": oai_tools,
-            });
+            let body = build_request(&config, messages, &tools);
 
             let response = self
                 .http
@@ -98,3 +122,34 @@ impl LlmClient for AzureClient {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_carries_stop_sequences() {
+        let mut config: ModelConfig = serde_json::from_value(serde_json::json!({
+            "provider": "azure",
+            "model_id": "gpt-4o",
+        }))
+        .unwrap();
+        assert!(build_request(&config, vec![], &[]).get("stop").is_none());
+
+        config.stop_sequences = vec!["DONE".to_string()];
+        let body = build_request(&config, vec![], &[]);
+        assert_eq!(body["stop"], serde_json::json!(["DONE"]));
+
+        config.seed = Some(7);
+        assert_eq!(build_request(&config, vec![], &[])["seed"], 7);
+
+        config.top_p = Some(0.5);
+        assert_eq!(build_request(&config, vec![], &[])["top_p"], 0.5);
+
+        config.model_id = "o3-mini".to_string();
+        config.temperature = 1.2;
+        let body = build_request(&config, vec![], &[]);
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("top_p").is_none());
+    }
+}
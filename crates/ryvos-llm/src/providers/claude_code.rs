@@ -382,9 +382,13 @@ mod tests {
             model_id: "claude-sonnet-4".into(),
             api_key: None,
             base_url: None,
-            max_tokens: 8192,
+            max_tokens: None,
             temperature: 0.0,
+            top_p: None,
             thinking: ThinkingLevel::Off,
+            stop_sequences: vec![],
+            seed: None,
+            api_keys: vec![],
             retry: None,
             azure_resource: None,
             azure_deployment: None,
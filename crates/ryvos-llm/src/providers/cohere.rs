@@ -42,9 +42,14 @@ struct CohereRequest {
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    /// Cohere's name for top_p.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p: Option<f32>,
     stream: bool,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tools: Vec<CohereTool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -203,6 +208,17 @@ fn convert_messages(messages: Vec<ChatMessage>) -> Vec<CohereMessage> {
                             tool_results: None,
                         });
                     }
+                    // Text sent with the results follows them as a user message
+                    let text = msg.text();
+                    if !text.is_empty() {
+                        out.push(CohereMessage {
+                            role: "user".to_string(),
+                            content: Some(text),
+                            tool_calls: None,
+                            tool_call_id: None,
+                            tool_results: None,
+                        });
+                    }
                 } else {
                     out.push(CohereMessage {
                         role: "user".to_string(),
@@ -322,6 +338,7 @@ fn parse_cohere_chunk(event: SseEvent) -> Option<Result<StreamDelta>> {
                                 return Some(Ok(StreamDelta::Usage {
                                     input_tokens: tokens.input_tokens,
                                     output_tokens: tokens.output_tokens,
+                                    thinking_tokens: 0,
                                 }));
                             }
                         }
@@ -329,6 +346,7 @@ fn parse_cohere_chunk(event: SseEvent) -> Option<Result<StreamDelta>> {
                             Some("COMPLETE") => Some(StopReason::EndTurn),
                             Some("MAX_TOKENS") => Some(StopReason::MaxTokens),
                             Some("TOOL_CALL") => Some(StopReason::ToolUse),
+                            Some("STOP_SEQUENCE") => Some(StopReason::StopSequence),
                             _ => Some(StopReason::EndTurn),
                         };
                         return stop.map(|s| Ok(StreamDelta::Stop(s)));
@@ -345,6 +363,40 @@ fn parse_cohere_chunk(event: SseEvent) -> Option<Result<StreamDelta>> {
     }
 }
 
+fn build_request(
+    config: &ModelConfig,
+    messages: Vec<ChatMessage>,
+    tools: &[ToolDefinition],
+) -> CohereRequest {
+    let cohere_messages = convert_messages(messages);
+    let cohere_tools: Vec<CohereTool> = tools
+        .iter()
+        .map(|t| CohereTool {
+            r#type: "function".to_string(),
+            function: CohereFnDef {
+                name: t.name.clone(),
+                description: t.description.clone(),
+                parameters: t.input_schema.clone(),
+            },
+        })
+        .collect();
+
+    CohereRequest {
+        model: config.model_id.clone(),
+        messages: cohere_messages,
+        max_tokens: Some(config.max_output_tokens()),
+        temperature: if config.temperature > 0.0 {
+            Some(config.temperature)
+        } else {
+            None
+        },
+        p: config.top_p,
+        stream: true,
+        tools: cohere_tools,
+        stop_sequences: config.stop_sequences.clone(),
+    }
+}
+
 impl LlmClient for CohereClient {
     fn chat_stream(
         &self,
@@ -363,31 +415,7 @@ impl LlmClient for CohereClient {
 
             let base_url = config.base_url.as_deref().unwrap_or(COHERE_API_URL);
 
-            let cohere_messages = convert_messages(messages);
-            let cohere_tools: Vec<CohereTool> = tools
-                .iter()
-                .map(|t| CohereTool {
-                    r#type: "function".to_string(),
-                    function: CohereFnDef {
-                        name: t.name.clone(),
-                        description: t.description.clone(),
-                        parameters: t.input_schema.clone(),
-                    },
-                })
-                .collect();
-
-            let body = CohereRequest {
-                model: config.model_id.clone(),
-                messages: cohere_messages,
-                max_tokens: Some(config.max_tokens),
-                temperature: if config.temperature > 0.0 {
-                    Some(config.temperature)
-                } else {
-                    None
-                },
-                stream: true,
-                tools: cohere_tools,
-            };
+            let body = build_request(&config, messages, &tools);
 
             let response = self
                 .http
@@ -418,3 +446,34 @@ impl LlmClient for CohereClient {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_carries_stop_sequences() {
+        let config: ModelConfig = serde_json::from_value(serde_json::json!({
+            "provider": "cohere",
+            "model_id": "command-r-plus",
+            "stop_sequences": ["\nObservation:"],
+        }))
+        .unwrap();
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert_eq!(
+            body["stop_sequences"],
+            serde_json::json!(["\nObservation:"])
+        );
+    }
+
+    #[test]
+    fn stop_sequence_finish_is_reported() {
+        let event = SseEvent {
+            event_type: None,
+            data: r#"{"type": "message-end", "delta": null, "response": {"finish_reason": "STOP_SEQUENCE"}}"#
+                .to_string(),
+        };
+        let delta = parse_cohere_chunk(event).unwrap().unwrap();
+        assert!(matches!(delta, StreamDelta::Stop(StopReason::StopSequence)));
+    }
+}
@@ -225,6 +225,7 @@ async fn parse_copilot_event(
                     return Some(Ok(StreamDelta::Usage {
                         input_tokens: input,
                         output_tokens: output,
+                        thinking_tokens: 0,
                     }));
                 }
             }
@@ -455,9 +456,13 @@ mod tests {
             model_id: "claude-sonnet-4".into(),
             api_key: None,
             base_url: None,
-            max_tokens: 8192,
+            max_tokens: None,
             temperature: 0.0,
+            top_p: None,
             thinking: ThinkingLevel::Off,
+            stop_sequences: vec![],
+            seed: None,
+            api_keys: vec![],
             retry: None,
             azure_resource: None,
             azure_deployment: None,
@@ -96,6 +96,25 @@ struct GenerationConfig {
     max_output_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking_config: Option<GeminiThinkingConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct GeminiThinkingConfig {
+    thinking_budget: u32,
+}
+
+/// Thinking budget for Gemini 2.5 models, or `None` to keep the model's
+/// default when thinking is off.
+fn thinking_config(level: &ThinkingLevel) -> Option<GeminiThinkingConfig> {
+    level.is_enabled().then(|| GeminiThinkingConfig {
+        thinking_budget: level.budget_tokens(),
+    })
 }
 
 // ── Response types ───────────────────────────────────────────────
@@ -115,12 +134,17 @@ struct GeminiCandidate {
     finish_reason: Option<String>,
 }
 
+/// Sent on every chunk with running totals; only the final chunk's counts
+/// are complete. `candidatesTokenCount` excludes thinking, which Gemini
+/// bills as output and reports in `thoughtsTokenCount`.
 #[derive(Deserialize, Debug)]
 struct GeminiUsage {
     #[serde(default, rename = "promptTokenCount")]
     prompt_token_count: u64,
     #[serde(default, rename = "candidatesTokenCount")]
     candidates_token_count: u64,
+    #[serde(default, rename = "thoughtsTokenCount")]
+    thoughts_token_count: u64,
 }
 
 // ── Conversion ───────────────────────────────────────────────────
@@ -170,10 +194,8 @@ fn convert_messages(messages: Vec<ChatMessage>) -> (Option<GeminiContent>, Vec<G
                 let mut parts = Vec::new();
                 for block in &msg.content {
                     match block {
-                        ContentBlock::Text { text } => {
-                            if !text.is_empty() {
-                                parts.push(GeminiPart::Text { text: text.clone() });
-                            }
+                        ContentBlock::Text { text } if !text.is_empty() => {
+                            parts.push(GeminiPart::Text { text: text.clone() });
                         }
                         ContentBlock::ToolUse { name, input, .. } => {
                             parts.push(GeminiPart::FunctionCall {
@@ -224,42 +246,32 @@ fn convert_messages(messages: Vec<ChatMessage>) -> (Option<GeminiContent>, Vec<G
     (system, contents)
 }
 
-fn parse_gemini_chunk(event: SseEvent) -> Option<Result<StreamDelta>> {
+/// Deltas for one SSE chunk. Usage totals are cumulative, so the chunk's
+/// usage only replaces `usage`; the caller reports the last one with
+/// [`usage_delta`] once the stream ends.
+fn parse_gemini_chunk(
+    event: SseEvent,
+    usage: &mut Option<GeminiUsage>,
+) -> Vec<Result<StreamDelta>> {
     if event.data.trim() == "[DONE]" {
-        return None;
+        return vec![];
     }
 
     let parsed: std::result::Result<GeminiStreamChunk, _> = serde_json::from_str(&event.data);
     match parsed {
         Ok(chunk) => {
-            if let Some(usage) = chunk.usage_metadata {
-                return Some(Ok(StreamDelta::Usage {
-                    input_tokens: usage.prompt_token_count,
-                    output_tokens: usage.candidates_token_count,
-                }));
-            }
-
-            let candidate = chunk.candidates.into_iter().next()?;
-
-            if let Some(reason) = candidate.finish_reason {
-                let stop = match reason.as_str() {
-                    "STOP" => StopReason::EndTurn,
-                    "MAX_TOKENS" => StopReason::MaxTokens,
-                    _ => StopReason::EndTurn,
-                };
-                return Some(Ok(StreamDelta::Stop(stop)));
-            }
+            let mut deltas = Vec::new();
+            let candidate = chunk.candidates.into_iter().next();
+            let finish_reason = candidate.as_ref().and_then(|c| c.finish_reason.clone());
 
-            if let Some(content) = candidate.content {
+            if let Some(content) = candidate.and_then(|c| c.content) {
                 for (i, part) in content.parts.into_iter().enumerate() {
                     match part {
-                        GeminiPart::Text { text } => {
-                            if !text.is_empty() {
-                                return Some(Ok(StreamDelta::TextDelta(text)));
-                            }
+                        GeminiPart::Text { text } if !text.is_empty() => {
+                            deltas.push(Ok(StreamDelta::TextDelta(text)));
                         }
                         GeminiPart::FunctionCall { function_call } => {
-                            return Some(Ok(StreamDelta::ToolUseStart {
+                            deltas.push(Ok(StreamDelta::ToolUseStart {
                                 index: i,
                                 id: format!("call_{}", function_call.name),
                                 name: function_call.name,
@@ -270,15 +282,82 @@ fn parse_gemini_chunk(event: SseEvent) -> Option<Result<StreamDelta>> {
                 }
             }
 
-            None
+            if chunk.usage_metadata.is_some() {
+                *usage = chunk.usage_metadata;
+            }
+
+            if let Some(reason) = finish_reason {
+                let stop = match reason.as_str() {
+                    "STOP" => StopReason::EndTurn,
+                    "MAX_TOKENS" => StopReason::MaxTokens,
+                    _ => StopReason::EndTurn,
+                };
+                deltas.push(Ok(StreamDelta::Stop(stop)));
+            }
+
+            deltas
         }
         Err(e) => {
             warn!(data = %event.data, error = %e, "Failed to parse Gemini SSE chunk");
-            None
+            vec![]
         }
     }
 }
 
+/// The stream's single usage report, from the last totals seen.
+fn usage_delta(usage: Option<GeminiUsage>) -> Vec<Result<StreamDelta>> {
+    usage
+        .map(|usage| {
+            Ok(StreamDelta::Usage {
+                input_tokens: usage.prompt_token_count,
+                output_tokens: usage.candidates_token_count + usage.thoughts_token_count,
+                thinking_tokens: usage.thoughts_token_count,
+            })
+        })
+        .into_iter()
+        .collect()
+}
+
+fn build_request(
+    config: &ModelConfig,
+    messages: Vec<ChatMessage>,
+    tools: &[ToolDefinition],
+) -> GeminiRequest {
+    let (system_instruction, contents) = convert_messages(messages);
+
+    let gemini_tools = if tools.is_empty() {
+        vec![]
+    } else {
+        vec![GeminiToolDecl {
+            function_declarations: tools
+                .iter()
+                .map(|t| GeminiFnDecl {
+                    name: t.name.clone(),
+                    description: t.description.clone(),
+                    parameters: t.input_schema.clone(),
+                })
+                .collect(),
+        }]
+    };
+
+    GeminiRequest {
+        contents,
+        system_instruction,
+        tools: gemini_tools,
+        generation_config: Some(GenerationConfig {
+            max_output_tokens: Some(config.max_output_tokens()),
+            temperature: if config.temperature > 0.0 {
+                Some(config.temperature)
+            } else {
+                None
+            },
+            top_p: config.top_p,
+            thinking_config: thinking_config(&config.thinking),
+            stop_sequences: config.stop_sequences.clone(),
+        }),
+    }
+}
+
 impl LlmClient for GeminiClient {
     fn chat_stream(
         &self,
@@ -300,36 +379,7 @@ impl LlmClient for GeminiClient {
                 config.model_id, api_key
             );
 
-            let (system_instruction, contents) = convert_messages(messages);
-
-            let gemini_tools = if tools.is_empty() {
-                vec![]
-            } else {
-                vec![GeminiToolDecl {
-                    function_declarations: tools
-                        .iter()
-                        .map(|t| GeminiFnDecl {
-                            name: t.name.clone(),
-                            description: t.description.clone(),
-                            parameters: t.input_schema.clone(),
-                        })
-                        .collect(),
-                }]
-            };
-
-            let body = GeminiRequest {
-                contents,
-                system_instruction,
-                tools: gemini_tools,
-                generation_config: Some(GenerationConfig {
-                    max_output_tokens: Some(config.max_tokens),
-                    temperature: if config.temperature > 0.0 {
-                        Some(config.temperature)
-                    } else {
-                        None
-                    },
-                }),
-            };
+            let body = build_request(&config, messages, &tools);
 
             let response = self
                 .http
@@ -352,10 +402,137 @@ impl LlmClient for GeminiClient {
             let byte_stream = response.bytes_stream();
             let sse_stream = SseStream::new(byte_stream);
 
-            let delta_stream =
-                sse_stream.filter_map(|event| async move { parse_gemini_chunk(event) });
+            // `None` marks the end of the stream, where usage is reported
+            let mut usage = None;
+            let delta_stream = sse_stream
+                .map(Some)
+                .chain(futures::stream::once(async { None }))
+                .flat_map(move |event| {
+                    futures::stream::iter(match event {
+                        Some(event) => parse_gemini_chunk(event, &mut usage),
+                        None => usage_delta(usage.take()),
+                    })
+                });
 
             Ok(Box::pin(delta_stream) as BoxStream<'_, Result<StreamDelta>>)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::SseParser;
+
+    /// A thinking-enabled `streamGenerateContent?alt=sse` response: every
+    /// chunk carries running usage totals.
+    const THINKING_STREAM: &str = concat!(
+        r#"data: {"candidates": [{"content": {"parts": [{"text": "The answer"}],"role": "model"},"index": 0}],"usageMetadata": {"promptTokenCount": 12,"totalTokenCount": 260,"thoughtsTokenCount": 245},"modelVersion": "gemini-2.5-flash"}"#,
+        "\r\n\r\n",
+        r#"data: {"candidates": [{"content": {"parts": [{"text": " is 42."}],"role": "model"},"finishReason": "STOP","index": 0}],"usageMetadata": {"promptTokenCount": 12,"candidatesTokenCount": 7,"totalTokenCount": 264,"thoughtsTokenCount": 245},"modelVersion": "gemini-2.5-flash"}"#,
+        "\r\n\r\n",
+    );
+
+    fn parse_stream(raw: &str) -> Vec<StreamDelta> {
+        let mut usage = None;
+        let mut deltas: Vec<_> = SseParser::new()
+            .feed(raw)
+            .into_iter()
+            .flat_map(|event| parse_gemini_chunk(event, &mut usage))
+            .collect();
+        deltas.extend(usage_delta(usage));
+        deltas.into_iter().map(|delta| delta.unwrap()).collect()
+    }
+
+    #[test]
+    fn streamed_usage_includes_thinking_tokens() {
+        let deltas = parse_stream(THINKING_STREAM);
+        let text: String = deltas
+            .iter()
+            .filter_map(|d| match d {
+                StreamDelta::TextDelta(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "The answer is 42.");
+
+        let usage: Vec<_> = deltas
+            .iter()
+            .filter_map(|d| match d {
+                StreamDelta::Usage {
+                    input_tokens,
+                    output_tokens,
+                    thinking_tokens,
+                } => Some((*input_tokens, *output_tokens, *thinking_tokens)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(usage, vec![(12, 252, 245)]);
+        assert!(deltas
+            .iter()
+            .any(|d| matches!(d, StreamDelta::Stop(StopReason::EndTurn))));
+    }
+
+    #[test]
+    fn usage_after_the_final_chunk_is_reported_once() {
+        let trailer = r#"data: {"usageMetadata": {"promptTokenCount": 12,"candidatesTokenCount": 9,"thoughtsTokenCount": 245}}"#;
+        let deltas = parse_stream(&format!("{THINKING_STREAM}{trailer}\n\n"));
+        let usage: Vec<_> = deltas
+            .iter()
+            .filter_map(|d| match d {
+                StreamDelta::Usage { output_tokens, .. } => Some(*output_tokens),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(usage, vec![254]);
+    }
+
+    #[test]
+    fn usage_only_chunk_is_reported() {
+        let raw = r#"data: {"usageMetadata": {"promptTokenCount": 5,"candidatesTokenCount": 3}}"#;
+        let deltas = parse_stream(&format!("{raw}\n\n"));
+        assert!(matches!(
+            deltas.as_slice(),
+            [StreamDelta::Usage {
+                input_tokens: 5,
+                output_tokens: 3,
+                thinking_tokens: 0
+            }]
+        ));
+    }
+
+    #[test]
+    fn thinking_maps_to_thinking_budget() {
+        assert!(thinking_config(&ThinkingLevel::Off).is_none());
+        assert!(thinking_config(&ThinkingLevel::Custom(0)).is_none());
+        let config = GenerationConfig {
+            max_output_tokens: None,
+            temperature: None,
+            top_p: None,
+            thinking_config: thinking_config(&ThinkingLevel::Custom(2048)),
+            stop_sequences: vec![],
+        };
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::json!({ "thinking_config": { "thinking_budget": 2048 } })
+        );
+        let high = thinking_config(&ThinkingLevel::High).unwrap();
+        assert_eq!(high.thinking_budget, 32768);
+    }
+
+    #[test]
+    fn request_carries_stop_sequences() {
+        let config: ModelConfig = serde_json::from_value(serde_json::json!({
+            "provider": "gemini",
+            "model_id": "gemini-2.5-flash",
+            "stop_sequences": ["END"],
+        }))
+        .unwrap();
+        let messages = vec![ChatMessage::user("List three colours, then END")];
+        let body = serde_json::to_value(build_request(&config, messages, &[])).unwrap();
+        assert_eq!(
+            body["generation_config"]["stop_sequences"],
+            serde_json::json!(["END"])
+        );
+    }
+}
@@ -40,11 +40,17 @@ struct ChatRequest {
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
     stream: bool,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tools: Vec<OaiTool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -178,6 +184,16 @@ pub(crate) fn convert_messages(messages: Vec<ChatMessage>) -> Vec<OaiMessage> {
                             tool_call_id: Some(id),
                         });
                     }
+                    // Text sent with the results follows them as a user message
+                    let text = msg.text();
+                    if !text.is_empty() {
+                        oai_msgs.push(OaiMessage {
+                            role: "user".to_string(),
+                            content: Some(serde_json::Value::String(text)),
+                            tool_calls: None,
+                            tool_call_id: None,
+                        });
+                    }
                 } else {
                     oai_msgs.push(OaiMessage {
                         role: "user".to_string(),
@@ -247,6 +263,7 @@ pub(crate) fn parse_chunk(event: SseEvent) -> Vec<Result<StreamDelta>> {
                 deltas.push(Ok(StreamDelta::Usage {
                     input_tokens: usage.prompt_tokens,
                     output_tokens: usage.completion_tokens,
+                    thinking_tokens: 0,
                 }));
                 return deltas;
             }
@@ -315,6 +332,49 @@ pub(crate) fn parse_chunk(event: SseEvent) -> Vec<Result<StreamDelta>> {
     }
 }
 
+/// Whether `model_id` is an o-series reasoning model, which rejects
+/// `temperature` and `top_p`.
+pub(crate) fn is_o_series(model_id: &str) -> bool {
+    model_id.starts_with("o1") || model_id.starts_with("o3") || model_id.starts_with("o4")
+}
+
+fn build_request(
+    config: &ModelConfig,
+    messages: Vec<ChatMessage>,
+    tools: &[ToolDefinition],
+) -> ChatRequest {
+    let oai_messages = convert_messages(messages);
+    let oai_tools = convert_tools(tools);
+
+    // For o-series models, send reasoning_effort instead of temperature
+    let is_o_series = is_o_series(&config.model_id);
+
+    let reasoning_effort = if is_o_series && config.thinking.is_enabled() {
+        Some(config.thinking.reasoning_effort().to_string())
+    } else {
+        None
+    };
+
+    ChatRequest {
+        model: config.model_id.clone(),
+        messages: oai_messages,
+        max_tokens: config.max_output_tokens(),
+        temperature: if is_o_series {
+            None // o-series doesn't support temperature
+        } else if config.temperature > 0.0 {
+            Some(config.temperature)
+        } else {
+            None
+        },
+        top_p: config.top_p.filter(|_| !is_o_series),
+        stream: true,
+        tools: oai_tools,
+        reasoning_effort,
+        stop: config.stop_sequences.clone(),
+        seed: config.seed,
+    }
+}
+
 impl LlmClient for OpenAiClient {
     fn chat_stream(
         &self,
@@ -327,47 +387,7 @@ impl LlmClient for OpenAiClient {
 
         Box::pin(async move {
             let base_url = config.base_url.as_deref().unwrap_or(OPENAI_API_URL);
-
-            let oai_messages = convert_messages(messages);
-            let oai_tools: Vec<OaiTool> = tools
-                .iter()
-                .map(|t| OaiTool {
-                    r#type: "function".to_string(),
-                    function: OaiToolDef {
-                        name: t.name.clone(),
-                        description: t.description.clone(),
-                        parameters: t.input_schema.clone(),
-                    },
-                })
-                .collect();
-
-            // For o-series models, send reasoning_effort instead of temperature
-            let is_o_series = config.model_id.starts_with("o1")
-                || config.model_id.starts_with("o3")
-                || config.model_id.starts_with("o4");
-
-            let reasoning_effort =
-                if is_o_series && config.thinking != ryvos_core::types::ThinkingLevel::Off {
-                    Some(config.thinking.reasoning_effort().to_string())
-                } else {
-                    None
-                };
-
-            let body = ChatRequest {
-                model: config.model_id.clone(),
-                messages: oai_messages,
-                max_tokens: config.max_tokens,
-                temperature: if is_o_series {
-                    None // o-series doesn't support temperature
-                } else if config.temperature > 0.0 {
-                    Some(config.temperature)
-                } else {
-                    None
-                },
-                stream: true,
-                tools: oai_tools,
-                reasoning_effort,
-            };
+            let body = build_request(&config, messages, &tools);
 
             let mut req = self.http.post(base_url).json(&body);
 
@@ -405,3 +425,79 @@ impl LlmClient for OpenAiClient {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_carries_stop_sequences() {
+        let mut config: ModelConfig =
+            serde_json::from_value(serde_json::json!({ "model_id": "gpt-4o" })).unwrap();
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert!(body.get("stop").is_none());
+
+        config.stop_sequences = vec!["</answer>".to_string(), "\n\n###".to_string()];
+        let messages = vec![ChatMessage::user("Answer in tags")];
+        let body = serde_json::to_value(build_request(&config, messages, &[])).unwrap();
+        assert_eq!(body["stop"], serde_json::json!(["</answer>", "\n\n###"]));
+    }
+
+    #[test]
+    fn request_carries_seed() {
+        let mut config: ModelConfig =
+            serde_json::from_value(serde_json::json!({ "model_id": "gpt-4o" })).unwrap();
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert!(body.get("seed").is_none());
+
+        config.seed = Some(42);
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert_eq!(body["seed"], 42);
+    }
+
+    #[test]
+    fn text_beside_tool_results_follows_them() {
+        let msg = ChatMessage {
+            role: Role::User,
+            content: vec![
+                ContentBlock::ToolResult {
+                    tool_use_id: "call_0".to_string(),
+                    content: "ok".to_string(),
+                    is_error: false,
+                },
+                ContentBlock::Text {
+                    text: "One call was dropped.".to_string(),
+                },
+            ],
+            timestamp: None,
+            metadata: None,
+        };
+        let oai = convert_messages(vec![msg]);
+        assert_eq!(oai.len(), 2);
+        assert_eq!(oai[0].role, "tool");
+        assert_eq!(oai[1].role, "user");
+        assert_eq!(
+            oai[1].content,
+            Some(serde_json::Value::String(
+                "One call was dropped.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn o_series_gets_no_sampling_parameters() {
+        let mut config: ModelConfig = serde_json::from_value(serde_json::json!({
+            "model_id": "gpt-4o",
+            "temperature": 1.2,
+            "top_p": 0.5,
+        }))
+        .unwrap();
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert_eq!(body["top_p"], serde_json::json!(0.5f32));
+
+        config.model_id = "o3-mini".to_string();
+        let body = serde_json::to_value(build_request(&config, vec![], &[])).unwrap();
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("top_p").is_none());
+    }
+}
@@ -0,0 +1,271 @@
+//! Recorded transcripts, for runs that behave the same every time.
+//!
+//! [`RecordingClient`] wraps a provider client and keeps the deltas of each
+//! response; [`ReplayClient`] serves those responses back in order without
+//! calling a provider, so an integration test or a re-executed run sees
+//! exactly the stream that was recorded. A transcript is JSONL: an optional
+//! `{"prompt": ...}` header line, then one line per response, each a JSON
+//! array of [`StreamDelta`]s.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use ryvos_core::config::ModelConfig;
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::traits::LlmClient;
+use ryvos_core::types::*;
+
+/// A recorded run: the prompt it was given, if saved, and every response.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transcript {
+    pub prompt: Option<String>,
+    pub responses: Vec<Vec<StreamDelta>>,
+}
+
+/// Header line of a transcript file.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    prompt: String,
+}
+
+impl Transcript {
+    /// Read a transcript written by [`RecordingClient::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        parse_transcript(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// Parse a JSONL transcript. Blank lines are skipped; the first line may be
+/// the `{"prompt": ...}` header.
+pub fn parse_transcript(jsonl: &str) -> Result<Transcript> {
+    let mut transcript = Transcript::default();
+    let lines = jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    for (n, (i, line)) in lines.enumerate() {
+        let bad =
+            |e: serde_json::Error| RyvosError::Config(format!("transcript line {}: {}", i + 1, e));
+        if n == 0 && line.trim_start().starts_with('{') {
+            transcript.prompt = Some(serde_json::from_str::<Header>(line).map_err(bad)?.prompt);
+        } else {
+            transcript
+                .responses
+                .push(serde_json::from_str(line).map_err(bad)?);
+        }
+    }
+    Ok(transcript)
+}
+
+/// Serialize a transcript as JSONL.
+pub fn transcript_jsonl(transcript: &Transcript) -> Result<String> {
+    let mut out = String::new();
+    if let Some(prompt) = &transcript.prompt {
+        out.push_str(&serde_json::to_string(&Header {
+            prompt: prompt.clone(),
+        })?);
+        out.push('\n');
+    }
+    for deltas in &transcript.responses {
+        out.push_str(&serde_json::to_string(deltas)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// ── ReplayClient ────────────────────────────────────────────────
+
+/// Serves recorded responses in order, one per `chat_stream` call.
+pub struct ReplayClient {
+    state: Mutex<ReplayState>,
+}
+
+struct ReplayState {
+    responses: VecDeque<Vec<StreamDelta>>,
+    served: usize,
+}
+
+impl ReplayClient {
+    pub fn new(responses: Vec<Vec<StreamDelta>>) -> Self {
+        Self {
+            state: Mutex::new(ReplayState {
+                responses: responses.into(),
+                served: 0,
+            }),
+        }
+    }
+
+    /// Load the responses of a transcript written by [`RecordingClient::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::new(Transcript::load(path)?.responses))
+    }
+
+    /// Responses not served yet.
+    pub fn remaining(&self) -> usize {
+        self.state.lock().unwrap().responses.len()
+    }
+}
+
+impl LlmClient for ReplayClient {
+    fn chat_stream(
+        &self,
+        _config: &ModelConfig,
+        _messages: Vec<ChatMessage>,
+        _tools: &[ToolDefinition],
+    ) -> BoxFuture<'_, Result<BoxStream<'_, Result<StreamDelta>>>> {
+        let next = {
+            let mut state = self.state.lock().unwrap();
+            match state.responses.pop_front() {
+                Some(deltas) => {
+                    state.served += 1;
+                    Ok(deltas)
+                }
+                None => Err(RyvosError::LlmRequest(format!(
+                    "replay transcript exhausted after {} responses",
+                    state.served
+                ))),
+            }
+        };
+        Box::pin(async move { Ok(stream::iter(next?.into_iter().map(Ok)).boxed()) })
+    }
+}
+
+// ── RecordingClient ─────────────────────────────────────────────
+
+/// Passes requests through to `inner` and records every delta it streams.
+pub struct RecordingClient {
+    inner: Arc<dyn LlmClient>,
+    responses: Arc<Mutex<Vec<Vec<StreamDelta>>>>,
+}
+
+impl RecordingClient {
+    pub fn new(inner: Arc<dyn LlmClient>) -> Self {
+        Self {
+            inner,
+            responses: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// The responses recorded so far, one entry per call.
+    pub fn transcript(&self) -> Vec<Vec<StreamDelta>> {
+        self.responses.lock().unwrap().clone()
+    }
+
+    /// Write the transcript to `path` as JSONL, headed by `prompt` if given.
+    pub fn save(&self, path: &Path, prompt: Option<&str>) -> Result<()> {
+        let transcript = Transcript {
+            prompt: prompt.map(str::to_string),
+            responses: self.transcript(),
+        };
+        std::fs::write(path, transcript_jsonl(&transcript)?)?;
+        Ok(())
+    }
+}
+
+impl LlmClient for RecordingClient {
+    fn chat_stream(
+        &self,
+        config: &ModelConfig,
+        messages: Vec<ChatMessage>,
+        tools: &[ToolDefinition],
+    ) -> BoxFuture<'_, Result<BoxStream<'_, Result<StreamDelta>>>> {
+        let config = config.clone();
+        let tools = tools.to_vec();
+        Box::pin(async move {
+            let stream = self.inner.chat_stream(&config, messages, &tools).await?;
+            let index = {
+                let mut responses = self.responses.lock().unwrap();
+                responses.push(Vec::new());
+                responses.len() - 1
+            };
+            let responses = self.responses.clone();
+            let recorded = stream.inspect(move |delta| {
+                if let Ok(delta) = delta {
+                    responses.lock().unwrap()[index].push(delta.clone());
+                }
+            });
+            Ok(recorded.boxed())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ModelConfig {
+        serde_json::from_value(serde_json::json!({ "model_id": "gpt-4o" })).unwrap()
+    }
+
+    async fn collect(client: &dyn LlmClient) -> Result<Vec<StreamDelta>> {
+        let stream = client
+            .chat_stream(&config(), vec![ChatMessage::user("hi")], &[])
+            .await?;
+        stream.collect::<Vec<_>>().await.into_iter().collect()
+    }
+
+    fn recorded() -> Vec<Vec<StreamDelta>> {
+        vec![
+            vec![
+                StreamDelta::ToolUseStart {
+                    index: 0,
+                    id: "call_1".into(),
+                    name: "read".into(),
+                },
+                StreamDelta::ToolInputDelta {
+                    index: 0,
+                    delta: r#"{"path":"a.txt"}"#.into(),
+                },
+                StreamDelta::Stop(StopReason::ToolUse),
+            ],
+            vec![
+                StreamDelta::TextDelta("Done".into()),
+                StreamDelta::TextDelta(", read it.".into()),
+                StreamDelta::Usage {
+                    input_tokens: 120,
+                    output_tokens: 8,
+                    thinking_tokens: 0,
+                },
+                StreamDelta::Stop(StopReason::EndTurn),
+            ],
+        ]
+    }
+
+    #[tokio::test]
+    async fn replay_reproduces_the_recorded_deltas() {
+        let recorder = RecordingClient::new(Arc::new(ReplayClient::new(recorded())));
+        for _ in 0..2 {
+            collect(&recorder).await.unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+        recorder.save(&path, Some("read a.txt")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+        let transcript = Transcript::load(&path).unwrap();
+        assert_eq!(transcript.prompt.as_deref(), Some("read a.txt"));
+        let replay = ReplayClient::new(transcript.responses);
+        for expected in recorded() {
+            assert_eq!(collect(&replay).await.unwrap(), expected);
+        }
+        assert_eq!(replay.remaining(), 0);
+        let err = collect(&replay).await.unwrap_err();
+        assert!(err.to_string().contains("exhausted after 2"), "{}", err);
+    }
+
+    #[test]
+    fn bad_transcript_lines_are_reported() {
+        let err = parse_transcript("[]\n\n{\"text_delta\": 1}\n").unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+
+        // Only the first line can be the header
+        let transcript = parse_transcript("[]\n").unwrap();
+        assert_eq!(transcript.prompt, None);
+        assert_eq!(transcript.responses.len(), 1);
+    }
+}
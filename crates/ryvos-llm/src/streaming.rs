@@ -24,6 +24,11 @@ impl SseParser {
     /// Feed bytes into the parser and extract complete events.
     pub fn feed(&mut self, chunk: &str) -> Vec<SseEvent> {
         self.buffer.push_str(chunk);
+        // Some servers (Gemini) end lines with CRLF; a `\r` split from its
+        // `\n` stays buffered until the next chunk
+        if self.buffer.contains('\r') {
+            self.buffer = self.buffer.replace("\r\n", "\n");
+        }
         let mut events = Vec::new();
 
         // Split on double newlines (event boundaries)
@@ -143,4 +148,15 @@ mod tests {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].data, "{\"x\":1}");
     }
+
+    #[test]
+    fn test_sse_parser_crlf() {
+        let mut parser = SseParser::new();
+        let events = parser.feed("data: {\"x\":1}\r\n\r");
+        assert_eq!(events.len(), 0);
+        let events = parser.feed("\ndata: {\"x\":2}\r\n\r\n");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data, "{\"x\":1}");
+        assert_eq!(events[1].data, "{\"x\":2}");
+    }
 }
@@ -1,41 +1,53 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
-use http::{HeaderName, HeaderValue};
 use tokio::sync::{broadcast, Mutex};
 use tracing::{debug, info, warn};
 
 use rmcp::model::{
     CallToolRequestParams, GetPromptRequestParams, Prompt, ReadResourceRequestParams, Resource,
-    ResourceContents, SubscribeRequestParams, Tool as McpTool,
+    ResourceContents, SetLevelRequestParams, SubscribeRequestParams, Tool as McpTool,
 };
-use rmcp::service::RunningService;
-use rmcp::transport::streamable_http_client::{
-    StreamableHttpClientTransport, StreamableHttpClientTransportConfig,
-};
-use rmcp::{RoleClient, ServiceExt};
 
-use ryvos_core::config::{McpServerConfig, McpTransport};
+use ryvos_core::config::McpServerConfig;
 use ryvos_core::error::RyvosError;
 
-use crate::handler::{McpEvent, RyvosClientHandler};
-
-type McpConnection = RunningService<RoleClient, RyvosClientHandler>;
+use crate::handler::{McpEvent, McpRoots, McpSampler, RyvosClientHandler};
+use crate::logs::{McpLogBuffer, McpLogEntry, McpLogLevel};
+use crate::transport::{builtin_factories, McpConnection, McpTransportFactory};
 
 /// Manages connections to multiple MCP servers.
 pub struct McpClientManager {
     connections: Mutex<HashMap<String, McpConnection>>,
     server_configs: Mutex<HashMap<String, McpServerConfig>>,
     event_tx: broadcast::Sender<McpEvent>,
+    logs: Arc<McpLogBuffer>,
+    /// Transport factories by the `type` they handle.
+    factories: RwLock<HashMap<String, Arc<dyn McpTransportFactory>>>,
+    /// Directories advertised to every server as roots.
+    roots: McpRoots,
+    /// Runtime answering sampling requests from servers with
+    /// `allow_sampling`.
+    sampling: McpSampler,
 }
 
 impl Default for McpClientManager {
     fn default() -> Self {
         let (event_tx, _) = broadcast::channel(64);
-        Self {
+        let manager = Self {
             connections: Mutex::new(HashMap::new()),
             server_configs: Mutex::new(HashMap::new()),
             event_tx,
+            logs: Arc::new(McpLogBuffer::new()),
+            factories: RwLock::new(HashMap::new()),
+            roots: McpRoots::default(),
+            sampling: McpSampler::default(),
+        };
+        for factory in builtin_factories() {
+            manager.register_transport(factory);
         }
+        manager
     }
 }
 
@@ -44,59 +56,75 @@ impl McpClientManager {
         Self::default()
     }
 
+    /// Register a factory for its transport `type`, replacing any factory
+    /// already registered for it (the built-in ones included).
+    pub fn register_transport(&self, factory: Box<dyn McpTransportFactory>) {
+        self.factories
+            .write()
+            .unwrap()
+            .insert(factory.kind().to_string(), Arc::from(factory));
+    }
+
+    /// Transport types that can be connected, sorted.
+    pub fn transport_kinds(&self) -> Vec<String> {
+        let mut kinds: Vec<String> = self.factories.read().unwrap().keys().cloned().collect();
+        kinds.sort();
+        kinds
+    }
+
     /// Subscribe to MCP events (tools_changed, resources_changed, etc.).
     pub fn subscribe_events(&self) -> broadcast::Receiver<McpEvent> {
         self.event_tx.subscribe()
     }
 
-    /// Connect to an MCP server.
-    pub async fn connect(&self, name: &str, config: &McpServerConfig) -> Result<(), RyvosError> {
-        let handler = RyvosClientHandler::new(name, self.event_tx.clone());
-
-        let client = match &config.transport {
-            McpTransport::Stdio { command, args, env } => {
-                let mut cmd = tokio::process::Command::new(command);
-                cmd.args(args);
-                for (k, v) in env {
-                    cmd.env(k, v);
-                }
+    /// Directories servers are told they may work in, e.g. the workspace
+    /// and `agent.working_dir_roots`. Connected servers are notified that
+    /// the list changed.
+    pub async fn set_roots(&self, roots: Vec<PathBuf>) {
+        *self.roots.write().unwrap() = roots;
+        let conns = self.connections.lock().await;
+        for (name, client) in conns.iter() {
+            if let Err(e) = client.notify_roots_list_changed().await {
+                debug!(server = %name, error = %e, "Failed to notify MCP roots change");
+            }
+        }
+    }
 
-                let transport = rmcp::transport::TokioChildProcess::new(cmd)
-                    .map_err(|e| RyvosError::Mcp(format!("Failed to spawn {}: {}", command, e)))?;
+    /// The directories advertised as roots.
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.read().unwrap().clone()
+    }
 
-                handler.serve(transport).await.map_err(|e| {
-                    RyvosError::Mcp(format!(
-                        "Failed to initialize MCP client for {}: {}",
-                        name, e
-                    ))
-                })?
-            }
-            McpTransport::Sse { url } => {
-                // Build custom headers from config
-                let custom_headers: HashMap<HeaderName, HeaderValue> = config
-                    .headers
-                    .iter()
-                    .filter_map(|(k, v)| {
-                        let name = HeaderName::from_bytes(k.as_bytes()).ok()?;
-                        let value = HeaderValue::from_str(v).ok()?;
-                        Some((name, value))
-                    })
-                    .collect();
-
-                let transport_config = StreamableHttpClientTransportConfig {
-                    uri: url.as_str().into(),
-                    custom_headers,
-                    ..Default::default()
-                };
-                let transport = StreamableHttpClientTransport::from_config(transport_config);
-
-                <RyvosClientHandler as ServiceExt<RoleClient>>::serve(handler, transport)
-                    .await
-                    .map_err(|e| {
-                        RyvosError::Mcp(format!("MCP init for '{}' failed: {}", name, e))
-                    })?
-            }
-        };
+    /// Answer sampling requests from servers with `allow_sampling` on
+    /// `runtime`'s active model, counting the usage against its budget.
+    /// Servers connected before this is set get an error until then.
+    pub fn set_sampling_runtime(&self, runtime: &Arc<ryvos_agent::AgentRuntime>) {
+        *self.sampling.write().unwrap() = Some(Arc::downgrade(runtime));
+    }
+
+    /// Connect to an MCP server.
+    pub async fn connect(&self, name: &str, config: &McpServerConfig) -> Result<(), RyvosError> {
+        let sampling = config.allow_sampling.then(|| self.sampling.clone());
+        let handler = RyvosClientHandler::new(name, self.event_tx.clone(), self.logs.clone())
+            .with_roots(self.roots.clone())
+            .with_sampling(sampling, config.sampling_per_minute);
+
+        let kind = config.transport.kind();
+        let factory = self
+            .factories
+            .read()
+            .unwrap()
+            .get(kind)
+            .cloned()
+            .ok_or_else(|| {
+                RyvosError::Mcp(format!(
+                    "Unknown MCP transport type '{}' for '{}' (registered: {})",
+                    kind,
+                    name,
+                    self.transport_kinds().join(", ")
+                ))
+            })?;
+        let client = factory.connect(name, config, handler).await?;
 
         info!(server = %name, "MCP server connected");
 
@@ -128,7 +156,16 @@ impl McpClientManager {
             }
         }
 
-        self.connect(server_name, &config).await
+        self.connect(server_name, &config).await?;
+
+        // Have the server fetch the roots again for the new session
+        let conns = self.connections.lock().await;
+        if let Some(client) = conns.get(server_name) {
+            if let Err(e) = client.notify_roots_list_changed().await {
+                debug!(server = %server_name, error = %e, "Failed to resend MCP roots");
+            }
+        }
+        Ok(())
     }
 
     /// Check if a server connection is still alive.
@@ -367,6 +404,38 @@ impl McpClientManager {
         Ok(result.messages)
     }
 
+    // ---- Logging ----
+
+    /// Recent log messages from a server at `min_level` or above, oldest
+    /// first. Kept after the server disconnects.
+    pub fn logs(&self, server_name: &str, min_level: McpLogLevel) -> Vec<McpLogEntry> {
+        self.logs.recent(server_name, min_level)
+    }
+
+    /// Ask a server to send log messages at `level` and above.
+    pub async fn set_log_level(
+        &self,
+        server_name: &str,
+        level: McpLogLevel,
+    ) -> Result<(), RyvosError> {
+        let conns = self.connections.lock().await;
+        let client = conns
+            .get(server_name)
+            .ok_or_else(|| RyvosError::Mcp(format!("Server '{}' not connected", server_name)))?;
+
+        let params = SetLevelRequestParams {
+            meta: None,
+            level: level.into(),
+        };
+
+        client.set_level(params).await.map_err(|e| {
+            RyvosError::Mcp(format!(
+                "Failed to set log level on '{}': {}",
+                server_name, e
+            ))
+        })
+    }
+
     // ---- Connection management ----
 
     /// Disconnect from a specific server.
@@ -1,7 +1,12 @@
+use std::collections::VecDeque;
 use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
 
+use futures::StreamExt;
 use tokio::sync::broadcast;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 use rmcp::handler::client::ClientHandler;
 use rmcp::model::*;
@@ -9,6 +14,36 @@ use rmcp::service::{NotificationContext, RequestContext};
 use rmcp::ErrorData as McpError;
 use rmcp::RoleClient;
 
+use ryvos_agent::intelligence::CallUsage;
+use ryvos_agent::AgentRuntime;
+use ryvos_core::types::{ChatMessage, ContentBlock, Role as ChatRole, StopReason, StreamDelta};
+
+use crate::logs::{McpLogBuffer, McpLogLevel};
+
+/// The directories servers are told they may work in, shared by every
+/// connection of a manager.
+pub type McpRoots = Arc<RwLock<Vec<PathBuf>>>;
+
+/// The runtime that answers servers' sampling requests on its active
+/// model, once it is built. Shared by every connection of a manager.
+pub type McpSampler = Arc<RwLock<Option<Weak<AgentRuntime>>>>;
+
+/// `file://` roots for `paths`, named after their last component.
+/// Relative paths are left out, having no `file://` URI.
+pub fn roots_for(paths: &[PathBuf]) -> Vec<Root> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            Some(Root {
+                uri: url::Url::from_directory_path(path).ok()?.to_string(),
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned()),
+            })
+        })
+        .collect()
+}
+
 /// Events emitted by the MCP notification handler.
 #[derive(Debug, Clone)]
 pub enum McpEvent {
@@ -32,19 +67,163 @@ pub enum McpEvent {
     },
 }
 
-/// Custom MCP client handler that processes server notifications
-/// and optionally supports sampling (server-to-client LLM requests).
+/// Custom MCP client handler that processes server notifications,
+/// answers `roots/list`, and optionally supports sampling
+/// (server-to-client LLM requests).
 pub struct RyvosClientHandler {
     server_name: String,
     event_tx: broadcast::Sender<McpEvent>,
+    logs: Arc<McpLogBuffer>,
+    roots: McpRoots,
+    sampling: Option<McpSampler>,
+    sampling_per_minute: u32,
+    /// When recent sampling requests were served, oldest first.
+    sampled_at: Mutex<VecDeque<Instant>>,
 }
 
 impl RyvosClientHandler {
-    pub fn new(server_name: &str, event_tx: broadcast::Sender<McpEvent>) -> Self {
+    pub fn new(
+        server_name: &str,
+        event_tx: broadcast::Sender<McpEvent>,
+        logs: Arc<McpLogBuffer>,
+    ) -> Self {
         Self {
             server_name: server_name.to_string(),
             event_tx,
+            logs,
+            roots: McpRoots::default(),
+            sampling: None,
+            sampling_per_minute: 0,
+            sampled_at: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Advertise and answer `roots/list` with `roots`.
+    pub fn with_roots(mut self, roots: McpRoots) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    /// Answer up to `per_minute` sampling requests a minute with
+    /// `sampler`. Without one, sampling is neither advertised nor served;
+    /// set it only for servers with `allow_sampling`.
+    pub fn with_sampling(mut self, sampler: Option<McpSampler>, per_minute: u32) -> Self {
+        self.sampling = sampler;
+        self.sampling_per_minute = per_minute;
+        self
+    }
+
+    /// The roots `roots/list` answers with.
+    pub fn roots(&self) -> Vec<Root> {
+        roots_for(&self.roots.read().unwrap())
+    }
+
+    /// Count a sampling request against the per-minute limit. False if
+    /// the limit is reached, in which case it is not counted.
+    fn take_sampling_slot(&self) -> bool {
+        let now = Instant::now();
+        let mut sampled_at = self.sampled_at.lock().unwrap();
+        while sampled_at
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= Duration::from_secs(60))
+        {
+            sampled_at.pop_front();
         }
+        if sampled_at.len() >= self.sampling_per_minute as usize {
+            return false;
+        }
+        sampled_at.push_back(now);
+        true
+    }
+
+    /// Run a sampling request on the runtime's active model, with its
+    /// output capped at the model's limit, and count the usage like a
+    /// turn's. Only text content is passed on.
+    async fn sample(
+        &self,
+        runtime: &AgentRuntime,
+        params: CreateMessageRequestParams,
+    ) -> Result<CreateMessageResult, McpError> {
+        let mut messages = Vec::with_capacity(params.messages.len() + 1);
+        if let Some(system) = params.system_prompt.filter(|s| !s.is_empty()) {
+            messages.push(ChatMessage {
+                role: ChatRole::System,
+                content: vec![ContentBlock::Text { text: system }],
+                timestamp: None,
+                metadata: None,
+            });
+        }
+        for message in params.messages {
+            let text: String = message
+                .content
+                .into_vec()
+                .into_iter()
+                .filter_map(|content| match content {
+                    SamplingMessageContent::Text(t) => Some(t.text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            messages.push(match message.role {
+                Role::User => ChatMessage::user(text),
+                Role::Assistant => ChatMessage::assistant_text(text),
+            });
+        }
+
+        let (mut config, llm) = runtime.active_model();
+        config.max_tokens = Some(params.max_tokens.min(config.max_output_tokens()));
+        if let Some(temperature) = params.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(stop) = params.stop_sequences {
+            config.stop_sequences = stop;
+        }
+        let failed =
+            |e: ryvos_core::error::RyvosError| McpError::internal_error(e.to_string(), None);
+        let mut stream = llm
+            .chat_stream(&config, messages, &[])
+            .await
+            .map_err(failed)?;
+        let (mut text, mut stop_reason) = (String::new(), None);
+        let mut usage = CallUsage::default();
+        let mut error = None;
+        while let Some(delta) = stream.next().await {
+            match delta {
+                Ok(StreamDelta::TextDelta(t)) => text.push_str(&t),
+                Ok(StreamDelta::Stop(reason)) => stop_reason = Some(reason),
+                Ok(StreamDelta::Usage {
+                    input_tokens,
+                    output_tokens,
+                    thinking_tokens,
+                }) => {
+                    usage.input_tokens += input_tokens;
+                    usage.output_tokens += output_tokens;
+                    usage.thinking_tokens += thinking_tokens;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        // Billed even when the stream failed partway
+        runtime.record_call_usage(usage);
+        if let Some(e) = error {
+            return Err(failed(e));
+        }
+        Ok(CreateMessageResult {
+            model: config.model_id,
+            stop_reason: Some(
+                match stop_reason {
+                    Some(StopReason::MaxTokens) => CreateMessageResult::STOP_REASON_END_MAX_TOKEN,
+                    Some(StopReason::StopSequence) => CreateMessageResult::STOP_REASON_END_SEQUENCE,
+                    _ => CreateMessageResult::STOP_REASON_END_TURN,
+                }
+                .to_string(),
+            ),
+            message: SamplingMessage::assistant_text(text),
+        })
     }
 }
 
@@ -106,12 +285,16 @@ impl ClientHandler for RyvosClientHandler {
         _ctx: NotificationContext<RoleClient>,
     ) -> impl Future<Output = ()> + Send + '_ {
         async move {
-            let level = format!("{:?}", params.level);
-            let message = params.data.to_string();
+            let level = McpLogLevel::from(params.level);
+            let message = match params.data {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
             debug!(server = %self.server_name, level = %level, "MCP log: {}", message);
+            self.logs.push(&self.server_name, level, message.clone());
             let _ = self.event_tx.send(McpEvent::LogMessage {
                 server: self.server_name.clone(),
-                level,
+                level: level.to_string(),
                 message,
             });
         }
@@ -135,13 +318,49 @@ impl ClientHandler for RyvosClientHandler {
 
     fn create_message(
         &self,
-        _params: CreateMessageRequestParams,
+        params: CreateMessageRequestParams,
         _ctx: RequestContext<RoleClient>,
     ) -> impl Future<Output = Result<CreateMessageResult, McpError>> + Send + '_ {
+        async move {
+            let Some(ref sampler) = self.sampling else {
+                warn!(server = %self.server_name, "Server requested sampling without allow_sampling");
+                return Err(McpError::method_not_found::<CreateMessageRequestMethod>());
+            };
+            let runtime = sampler.read().unwrap().as_ref().and_then(Weak::upgrade);
+            let Some(runtime) = runtime else {
+                return Err(McpError::internal_error(
+                    "sampling is not available until the agent has started",
+                    None,
+                ));
+            };
+            if !self.take_sampling_slot() {
+                warn!(
+                    server = %self.server_name,
+                    per_minute = self.sampling_per_minute,
+                    "MCP sampling request over the rate limit"
+                );
+                return Err(McpError::invalid_request(
+                    format!(
+                        "sampling is limited to {} requests per minute",
+                        self.sampling_per_minute
+                    ),
+                    None,
+                ));
+            }
+            info!(server = %self.server_name, max_tokens = params.max_tokens, "MCP sampling request");
+            self.sample(&runtime, params).await
+        }
+    }
+
+    fn list_roots(
+        &self,
+        _ctx: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<ListRootsResult, McpError>> + Send + '_ {
         async {
-            // Sampling is not yet implemented — return method_not_found
-            warn!(server = %self.server_name, "Server requested sampling (create_message) — not yet supported");
-            Err(McpError::method_not_found::<CreateMessageRequestMethod>())
+            debug!(server = %self.server_name, "MCP roots/list request");
+            Ok(ListRootsResult {
+                roots: self.roots(),
+            })
         }
     }
 
@@ -149,7 +368,16 @@ impl ClientHandler for RyvosClientHandler {
         ClientInfo {
             meta: None,
             protocol_version: Default::default(),
-            capabilities: ClientCapabilities::default(),
+            capabilities: ClientCapabilities {
+                roots: Some(RootsCapabilities {
+                    list_changed: Some(true),
+                }),
+                sampling: self
+                    .sampling
+                    .as_ref()
+                    .map(|_| SamplingCapability::default()),
+                ..Default::default()
+            },
             client_info: Implementation {
                 name: "ryvos".into(),
                 title: None,
@@ -161,3 +389,130 @@ impl ClientHandler for RyvosClientHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::ServiceExt;
+    use ryvos_core::event::EventBus;
+    use ryvos_core::types::AgentEvent;
+    use ryvos_test_utils::MockLlmClient;
+
+    use crate::server::RyvosServerHandler;
+
+    fn handler() -> RyvosClientHandler {
+        let (event_tx, _) = broadcast::channel(1);
+        RyvosClientHandler::new("test", event_tx, Arc::new(McpLogBuffer::new()))
+    }
+
+    #[tokio::test]
+    async fn initialize_advertises_roots_and_serves_roots_list() {
+        let roots = McpRoots::default();
+        *roots.write().unwrap() = vec![
+            PathBuf::from("/srv/project"),
+            PathBuf::from("/srv/my notes"),
+            PathBuf::from("/tmp"),
+        ];
+        let (server_io, client_io) = tokio::io::duplex(8192);
+        let server = tokio::spawn(async move {
+            RyvosServerHandler::new(None, None, std::env::temp_dir())
+                .serve(server_io)
+                .await
+                .unwrap()
+        });
+        let client = handler()
+            .with_roots(roots.clone())
+            .serve(client_io)
+            .await
+            .unwrap();
+        let server = server.await.unwrap();
+
+        let info = server.peer_info().expect("client initialized");
+        assert_eq!(
+            info.capabilities.roots,
+            Some(RootsCapabilities {
+                list_changed: Some(true)
+            })
+        );
+        assert!(info.capabilities.sampling.is_none());
+
+        let listed = server.list_roots().await.unwrap().roots;
+        assert_eq!(listed.len(), 3);
+        assert_eq!(listed[0].uri, "file:///srv/project/");
+        assert_eq!(listed[0].name.as_deref(), Some("project"));
+        assert_eq!(listed[1].uri, "file:///srv/my%20notes/");
+
+        // A later change is seen by the same session
+        roots.write().unwrap().pop();
+        assert_eq!(server.list_roots().await.unwrap().roots.len(), 2);
+        client.cancel().await.ok();
+    }
+
+    fn runtime(llm: &MockLlmClient, event_bus: Arc<EventBus>) -> Arc<AgentRuntime> {
+        let mut config = ryvos_test_utils::test_config();
+        config.model.max_tokens = Some(256);
+        Arc::new(AgentRuntime::new(
+            config,
+            Arc::new(llm.clone()) as Arc<dyn ryvos_core::traits::LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(ryvos_tools::ToolRegistry::new())),
+            Arc::new(ryvos_test_utils::InMemorySessionStore::new()),
+            event_bus,
+        ))
+    }
+
+    fn request(max_tokens: u32) -> CreateMessageRequestParams {
+        serde_json::from_value(serde_json::json!({
+            "messages": [{ "role": "user", "content": { "type": "text", "text": "hi" } }],
+            "maxTokens": max_tokens,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sampling_is_advertised_only_with_a_sampler() {
+        assert!(handler().get_info().capabilities.sampling.is_none());
+
+        let sampling = handler().with_sampling(Some(McpSampler::default()), 10);
+        assert!(sampling.get_info().capabilities.sampling.is_some());
+    }
+
+    #[tokio::test]
+    async fn sampling_is_capped_at_the_model_limit_and_billed() {
+        let llm = MockLlmClient::new().with_text_response("sampled");
+        let event_bus = Arc::new(EventBus::default());
+        let mut events = event_bus.subscribe();
+        let runtime = runtime(&llm, event_bus);
+
+        let result = handler().sample(&runtime, request(100_000)).await.unwrap();
+        assert_eq!(result.message.role, Role::Assistant);
+        assert_eq!(
+            result.stop_reason.as_deref(),
+            Some(CreateMessageResult::STOP_REASON_END_TURN)
+        );
+        assert_eq!(llm.call_config(0).max_tokens, Some(256));
+
+        let billed = loop {
+            if let AgentEvent::UsageUpdate {
+                input_tokens,
+                output_tokens,
+                ..
+            } = events.recv().await.unwrap()
+            {
+                break (input_tokens, output_tokens);
+            }
+        };
+        assert_eq!(billed, (100, 50));
+    }
+
+    #[tokio::test]
+    async fn sampling_is_rate_limited_per_server() {
+        // The limit is checked before a request reaches the model
+        let sampling = handler().with_sampling(Some(McpSampler::default()), 1);
+
+        assert!(sampling.take_sampling_slot());
+        assert!(!sampling.take_sampling_slot());
+        // Another server has its own limit
+        let other = handler().with_sampling(sampling.sampling.clone(), 1);
+        assert!(other.take_sampling_slot());
+    }
+}
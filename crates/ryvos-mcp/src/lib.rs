@@ -4,24 +4,32 @@
 //! `viking_search`, `viking_read`, `viking_write`, `viking_list`,
 //! `memory_get`, `memory_write`, `audit_query`, `audit_stats`, `daily_log_write`.
 //!
-//! **MCP Client**: Connects to external MCP servers (via stdio or SSE transport)
+//! **MCP Client**: Connects to external MCP servers (via stdio, streamable HTTP,
+//! or a custom [`McpTransportFactory`])
 //! and bridges their tools into the Ryvos [`ToolRegistry`]. Tool names are
 //! prefixed as `mcp__{server}__{tool}` to avoid collisions.
 //!
 //! The [`McpClientManager`] handles multiple concurrent server connections
 //! with auto-reconnect, and the [`RyvosClientHandler`] broadcasts tool/resource
-//! change notifications through the event system.
+//! change notifications through the event system. Servers are offered the
+//! manager's roots, and sampling when `allow_sampling` is set.
 
 mod bridge;
 mod client;
 mod handler;
+mod logs;
 mod resource_tool;
 pub mod server;
+mod transport;
 
 pub use bridge::register_mcp_tools;
 pub use client::McpClientManager;
-pub use handler::{McpEvent, RyvosClientHandler};
+pub use handler::{roots_for, McpEvent, McpRoots, McpSampler, RyvosClientHandler};
+pub use logs::{McpLogBuffer, McpLogEntry, McpLogLevel, MAX_LOG_ENTRIES};
 pub use resource_tool::McpReadResourceTool;
+pub use transport::{
+    HttpTransportFactory, McpConnection, McpTransportFactory, StdioTransportFactory,
+};
 
 // Re-export prompt types for consumers that don't depend on rmcp directly
 pub use rmcp::model::{PromptMessage, PromptMessageContent, PromptMessageRole};
@@ -0,0 +1,202 @@
+//! Recent log messages from connected MCP servers.
+//!
+//! Servers send `notifications/message` with a syslog-style level.
+//! [`RyvosClientHandler`](crate::RyvosClientHandler) records each one in the
+//! manager's [`McpLogBuffer`], which keeps the last [`MAX_LOG_ENTRIES`] per
+//! server for `ryvos mcp logs` and `/mcp logs`.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use rmcp::model::LoggingLevel;
+
+/// Messages kept per server; older ones are dropped first.
+pub const MAX_LOG_ENTRIES: usize = 500;
+
+/// MCP log severity, lowest first so levels compare by importance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum McpLogLevel {
+    #[default]
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl McpLogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Notice => "notice",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Critical => "critical",
+            Self::Alert => "alert",
+            Self::Emergency => "emergency",
+        }
+    }
+}
+
+impl fmt::Display for McpLogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for McpLogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "notice" => Ok(Self::Notice),
+            "warning" | "warn" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            "critical" => Ok(Self::Critical),
+            "alert" => Ok(Self::Alert),
+            "emergency" => Ok(Self::Emergency),
+            other => Err(format!(
+                "unknown log level '{}' (expected debug, info, notice, warning, error, critical, alert or emergency)",
+                other
+            )),
+        }
+    }
+}
+
+impl From<LoggingLevel> for McpLogLevel {
+    fn from(level: LoggingLevel) -> Self {
+        match level {
+            LoggingLevel::Debug => Self::Debug,
+            LoggingLevel::Info => Self::Info,
+            LoggingLevel::Notice => Self::Notice,
+            LoggingLevel::Warning => Self::Warning,
+            LoggingLevel::Error => Self::Error,
+            LoggingLevel::Critical => Self::Critical,
+            LoggingLevel::Alert => Self::Alert,
+            LoggingLevel::Emergency => Self::Emergency,
+        }
+    }
+}
+
+impl From<McpLogLevel> for LoggingLevel {
+    fn from(level: McpLogLevel) -> Self {
+        match level {
+            McpLogLevel::Debug => Self::Debug,
+            McpLogLevel::Info => Self::Info,
+            McpLogLevel::Notice => Self::Notice,
+            McpLogLevel::Warning => Self::Warning,
+            McpLogLevel::Error => Self::Error,
+            McpLogLevel::Critical => Self::Critical,
+            McpLogLevel::Alert => Self::Alert,
+            McpLogLevel::Emergency => Self::Emergency,
+        }
+    }
+}
+
+/// One log message from a server.
+#[derive(Debug, Clone)]
+pub struct McpLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: McpLogLevel,
+    pub message: String,
+}
+
+/// Per-server ring of recent log messages.
+#[derive(Debug, Default)]
+pub struct McpLogBuffer {
+    servers: Mutex<HashMap<String, VecDeque<McpLogEntry>>>,
+}
+
+impl McpLogBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a message, dropping the server's oldest once it is full.
+    pub fn push(&self, server: &str, level: McpLogLevel, message: impl Into<String>) {
+        let mut servers = self.servers.lock().unwrap();
+        let entries = servers.entry(server.to_string()).or_default();
+        if entries.len() == MAX_LOG_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back(McpLogEntry {
+            timestamp: Utc::now(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    /// A server's buffered messages at `min_level` or above, oldest first.
+    pub fn recent(&self, server: &str, min_level: McpLogLevel) -> Vec<McpLogEntry> {
+        self.servers
+            .lock()
+            .unwrap()
+            .get(server)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|e| e.level >= min_level)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_filters_by_level_and_server() {
+        let logs = McpLogBuffer::new();
+        logs.push("fs", McpLogLevel::Debug, "scanning /tmp");
+        logs.push("fs", McpLogLevel::Warning, "slow disk");
+        logs.push("git", McpLogLevel::Error, "not a repository");
+        logs.push("fs", McpLogLevel::Error, "permission denied");
+
+        let all: Vec<_> = logs
+            .recent("fs", McpLogLevel::Debug)
+            .into_iter()
+            .map(|e| e.message)
+            .collect();
+        assert_eq!(all, ["scanning /tmp", "slow disk", "permission denied"]);
+
+        let warnings: Vec<_> = logs
+            .recent("fs", "warn".parse().unwrap())
+            .into_iter()
+            .map(|e| e.level)
+            .collect();
+        assert_eq!(warnings, [McpLogLevel::Warning, McpLogLevel::Error]);
+
+        assert!(logs.recent("other", McpLogLevel::Debug).is_empty());
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped_at_capacity() {
+        let logs = McpLogBuffer::new();
+        for i in 0..MAX_LOG_ENTRIES + 3 {
+            logs.push("fs", McpLogLevel::Info, i.to_string());
+        }
+        let entries = logs.recent("fs", McpLogLevel::Debug);
+        assert_eq!(entries.len(), MAX_LOG_ENTRIES);
+        assert_eq!(entries[0].message, "3");
+    }
+
+    #[test]
+    fn levels_parse_case_insensitively() {
+        assert_eq!("ERROR".parse(), Ok(McpLogLevel::Error));
+        assert_eq!("Notice".parse(), Ok(McpLogLevel::Notice));
+        assert!("verbose".parse::<McpLogLevel>().is_err());
+        assert!(McpLogLevel::Critical > McpLogLevel::Warning);
+    }
+}
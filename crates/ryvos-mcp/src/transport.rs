@@ -0,0 +1,213 @@
+//! Transport factories: how [`McpClientManager`](crate::McpClientManager)
+//! opens a connection for each `type` of `[mcp.servers.<name>.transport]`.
+//!
+//! `stdio` and `sse` (alias `http`) are registered by default. Embedders add
+//! their own with [`McpClientManager::register_transport`](crate::McpClientManager::register_transport);
+//! a server configured with that factory's `type` reaches it as
+//! [`McpTransport::Custom`], with the rest of its keys in `options`.
+
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+use http::{HeaderName, HeaderValue};
+use rmcp::service::RunningService;
+use rmcp::transport::streamable_http_client::{
+    StreamableHttpClientTransport, StreamableHttpClientTransportConfig,
+};
+use rmcp::{RoleClient, ServiceExt};
+
+use ryvos_core::config::{McpServerConfig, McpTransport};
+use ryvos_core::error::RyvosError;
+
+use crate::handler::RyvosClientHandler;
+
+/// A live client session with one MCP server.
+pub type McpConnection = RunningService<RoleClient, RyvosClientHandler>;
+
+/// Opens connections for one transport `type`.
+pub trait McpTransportFactory: Send + Sync {
+    /// The `type` this factory handles, e.g. `"stdio"`.
+    fn kind(&self) -> &str;
+
+    /// Connect to server `name` and serve `handler` over the connection.
+    fn connect<'a>(
+        &'a self,
+        name: &'a str,
+        config: &'a McpServerConfig,
+        handler: RyvosClientHandler,
+    ) -> BoxFuture<'a, Result<McpConnection, RyvosError>>;
+}
+
+/// The factories every manager starts with.
+pub(crate) fn builtin_factories() -> Vec<Box<dyn McpTransportFactory>> {
+    vec![
+        Box::new(StdioTransportFactory),
+        Box::new(HttpTransportFactory),
+    ]
+}
+
+fn mismatched(name: &str, config: &McpServerConfig, expected: &str) -> RyvosError {
+    RyvosError::Mcp(format!(
+        "'{}' has an invalid {} transport: {:?}",
+        name, expected, config.transport
+    ))
+}
+
+/// Spawns the server as a child process and talks to it over stdio.
+pub struct StdioTransportFactory;
+
+impl McpTransportFactory for StdioTransportFactory {
+    fn kind(&self) -> &str {
+        "stdio"
+    }
+
+    fn connect<'a>(
+        &'a self,
+        name: &'a str,
+        config: &'a McpServerConfig,
+        handler: RyvosClientHandler,
+    ) -> BoxFuture<'a, Result<McpConnection, RyvosError>> {
+        Box::pin(async move {
+            let McpTransport::Stdio { command, args, env } = &config.transport else {
+                return Err(mismatched(name, config, "stdio"));
+            };
+            let mut cmd = tokio::process::Command::new(command);
+            cmd.args(args);
+            for (k, v) in env {
+                cmd.env(k, v);
+            }
+
+            let transport = rmcp::transport::TokioChildProcess::new(cmd)
+                .map_err(|e| RyvosError::Mcp(format!("Failed to spawn {}: {}", command, e)))?;
+
+            handler.serve(transport).await.map_err(|e| {
+                RyvosError::Mcp(format!(
+                    "Failed to initialize MCP client for {}: {}",
+                    name, e
+                ))
+            })
+        })
+    }
+}
+
+/// Streamable HTTP, with the server config's `headers` on every request.
+pub struct HttpTransportFactory;
+
+impl McpTransportFactory for HttpTransportFactory {
+    fn kind(&self) -> &str {
+        "sse"
+    }
+
+    fn connect<'a>(
+        &'a self,
+        name: &'a str,
+        config: &'a McpServerConfig,
+        handler: RyvosClientHandler,
+    ) -> BoxFuture<'a, Result<McpConnection, RyvosError>> {
+        Box::pin(async move {
+            let McpTransport::Sse { url } = &config.transport else {
+                return Err(mismatched(name, config, "sse"));
+            };
+            // Build custom headers from config
+            let custom_headers: HashMap<HeaderName, HeaderValue> = config
+                .headers
+                .iter()
+                .filter_map(|(k, v)| {
+                    let name = HeaderName::from_bytes(k.as_bytes()).ok()?;
+                    let value = HeaderValue::from_str(v).ok()?;
+                    Some((name, value))
+                })
+                .collect();
+
+            let transport_config = StreamableHttpClientTransportConfig {
+                uri: url.as_str().into(),
+                custom_headers,
+                ..Default::default()
+            };
+            let transport = StreamableHttpClientTransport::from_config(transport_config);
+
+            <RyvosClientHandler as ServiceExt<RoleClient>>::serve(handler, transport)
+                .await
+                .map_err(|e| RyvosError::Mcp(format!("MCP init for '{}' failed: {}", name, e)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::server::RyvosServerHandler;
+    use crate::McpClientManager;
+    use crate::McpLogBuffer;
+
+    /// Connects to an in-process Ryvos MCP server over an in-memory pipe.
+    struct MemoryTransportFactory;
+
+    impl McpTransportFactory for MemoryTransportFactory {
+        fn kind(&self) -> &str {
+            "memory"
+        }
+
+        fn connect<'a>(
+            &'a self,
+            name: &'a str,
+            config: &'a McpServerConfig,
+            handler: RyvosClientHandler,
+        ) -> BoxFuture<'a, Result<McpConnection, RyvosError>> {
+            Box::pin(async move {
+                let McpTransport::Custom { options, .. } = &config.transport else {
+                    return Err(mismatched(name, config, "memory"));
+                };
+                let buffer = options["buffer"].as_u64().unwrap_or(4096) as usize;
+                let (server_io, client_io) = tokio::io::duplex(buffer);
+                tokio::spawn(async move {
+                    let server = RyvosServerHandler::new(None, None, std::env::temp_dir());
+                    if let Ok(running) = server.serve(server_io).await {
+                        let _ = running.waiting().await;
+                    }
+                });
+                handler
+                    .serve(client_io)
+                    .await
+                    .map_err(|e| RyvosError::Mcp(e.to_string()))
+            })
+        }
+    }
+
+    fn server_config(transport: serde_json::Value) -> McpServerConfig {
+        serde_json::from_value(serde_json::json!({ "transport": transport })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn custom_transport_connects_through_the_manager() {
+        let manager = McpClientManager::new();
+        let config = server_config(serde_json::json!({ "type": "memory", "buffer": 8192 }));
+
+        let err = manager.connect("mem", &config).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown MCP transport type 'memory'"));
+        assert!(err.to_string().contains("sse, stdio"), "{}", err);
+
+        manager.register_transport(Box::new(MemoryTransportFactory));
+        manager.connect("mem", &config).await.unwrap();
+        assert!(manager.is_connected("mem").await);
+        let tools = manager.list_tools("mem").await.unwrap();
+        assert!(tools.iter().any(|t| t.name == "viking_search"));
+
+        manager.reconnect("mem").await.unwrap();
+        assert!(manager.is_connected("mem").await);
+        manager.disconnect_all().await;
+    }
+
+    #[tokio::test]
+    async fn builtin_factories_reject_a_mismatched_transport() {
+        let config = server_config(serde_json::json!({ "type": "memory" }));
+        let (event_tx, _) = tokio::sync::broadcast::channel(1);
+        let handler = RyvosClientHandler::new("x", event_tx, Arc::new(McpLogBuffer::new()));
+        let result = StdioTransportFactory.connect("x", &config, handler).await;
+        assert!(matches!(result, Err(ref e) if e.to_string().contains("invalid stdio transport")));
+    }
+}
@@ -1,6 +1,8 @@
 use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 
+use ryvos_core::config::EmbeddingConfig;
+
 /// Trait for embedding providers (OpenAI-compatible APIs).
 pub trait EmbeddingProvider: Send + Sync + 'static {
     /// Embed a batch of texts into vectors.
@@ -28,6 +30,24 @@ impl HttpEmbeddingProvider {
             dims,
         }
     }
+
+    /// Build from `[embedding]`. Without a `base_url`, Ollama uses its local
+    /// OpenAI-compatible endpoint and everything else the OpenAI API.
+    pub fn from_config(config: &EmbeddingConfig) -> Self {
+        let base_url = config
+            .base_url
+            .as_deref()
+            .unwrap_or(match config.provider.as_str() {
+                "ollama" => "http://localhost:11434/v1",
+                _ => "https://api.openai.com/v1",
+            });
+        Self::new(
+            base_url,
+            config.api_key.as_deref(),
+            &config.model,
+            config.dimensions,
+        )
+    }
 }
 
 #[derive(Serialize)]
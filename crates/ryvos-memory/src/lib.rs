@@ -1,10 +1,13 @@
 //! Persistence layer for Ryvos: sessions, memory, costs, and integrations.
 //!
-//! All storage is SQLite-based with no external database dependencies.
+//! All storage is SQLite-based with no external database dependencies,
+//! except that session history can live in Postgres instead (the `postgres`
+//! feature and `[storage] backend = "postgres"`).
 //!
 //! - **VikingStore**: Hierarchical memory with L0/L1/L2 detail levels,
 //!   FTS5 full-text search, and the `viking://` URI protocol.
 //! - **SqliteStore**: Session message persistence with optional embeddings.
+//! - **PostgresStore**: The same session persistence on a shared database.
 //! - **CostStore**: Per-run cost tracking with monthly spend aggregation.
 //! - **SessionMetaStore**: Session metadata (channel, billing, token counts).
 //! - **IntegrationStore**: OAuth token storage for external services.
@@ -14,6 +17,8 @@
 pub mod cost_store;
 pub mod embeddings;
 pub mod integration_store;
+#[cfg(feature = "postgres")]
+pub mod postgres_store;
 pub mod pricing;
 pub mod session_meta;
 pub mod store;
@@ -22,8 +27,10 @@ pub mod viking_store;
 
 pub use cost_store::CostStore;
 pub use integration_store::{IntegrationStore, IntegrationToken};
-pub use pricing::estimate_cost_cents;
+#[cfg(feature = "postgres")]
+pub use postgres_store::PostgresStore;
+pub use pricing::{estimate_cost_cents, model_pricing};
 pub use session_meta::SessionMetaStore;
-pub use store::SqliteStore;
+pub use store::{open_session_store, SqliteStore};
 pub use viking::VikingClient;
 pub use viking_store::VikingStore;
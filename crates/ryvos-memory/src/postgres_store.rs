@@ -0,0 +1,461 @@
+//! Postgres-backed session store, for several instances sharing history.
+//!
+//! Uses the same tables and encoding as [`SqliteStore`](crate::SqliteStore)
+//! (messages as JSON content blocks, RFC 3339 timestamps as text), so a
+//! session reads back identically from either backend. Full-text search
+//! uses a GIN index over `to_tsvector('english', content)`; its `rank` is
+//! `ts_rank`, where higher is better, unlike FTS5's. Connections are
+//! plaintext; TLS is not supported yet. A dropped connection is reopened
+//! on the next call.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio_postgres::{Client, NoTls};
+use tracing::{debug, warn};
+
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::traits::SessionStore;
+use ryvos_core::types::{ChatMessage, SearchResult, SessionId, StoredSession};
+
+use crate::store::{message_from_row, message_row, stored_session};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id BIGSERIAL PRIMARY KEY,
+        session_id TEXT NOT NULL,
+        role TEXT NOT NULL,
+        content TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        metadata TEXT
+    );
+
+    ALTER TABLE messages ADD COLUMN IF NOT EXISTS metadata TEXT;
+
+    CREATE INDEX IF NOT EXISTS idx_messages_session ON messages(session_id, id);
+
+    CREATE INDEX IF NOT EXISTS idx_messages_fts
+        ON messages USING GIN (to_tsvector('english', content));
+
+    CREATE TABLE IF NOT EXISTS session_summaries (
+        session_id TEXT PRIMARY KEY,
+        summary TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS session_scratchpad (
+        session_id TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        PRIMARY KEY (session_id, key)
+    );
+
+    CREATE TABLE IF NOT EXISTS session_settings (
+        session_id TEXT PRIMARY KEY,
+        no_compact BOOLEAN NOT NULL,
+        updated_at TEXT NOT NULL
+    );";
+
+/// The server's message where there is one; `Error`'s own is just "db error".
+fn db_err(e: tokio_postgres::Error) -> RyvosError {
+    match e.as_db_error() {
+        Some(db) => RyvosError::Database(db.to_string()),
+        None => RyvosError::Database(e.to_string()),
+    }
+}
+
+/// Open a connection, driving it on a background task.
+async fn open(url: &str) -> Result<Client> {
+    let (client, connection) = tokio_postgres::connect(url, NoTls).await.map_err(db_err)?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            warn!(error = %e, "Postgres connection closed");
+        }
+    });
+    Ok(client)
+}
+
+/// Postgres-backed session store.
+/// Calls share one connection, one at a time, like `SqliteStore`'s.
+pub struct PostgresStore {
+    url: String,
+    client: Mutex<Client>,
+}
+
+impl PostgresStore {
+    /// Connect to the database at `url` and create the tables if needed.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = open(url).await?;
+        client.batch_execute(SCHEMA).await.map_err(db_err)?;
+        debug!("Postgres store connected");
+        Ok(Self {
+            url: url.to_string(),
+            client: Mutex::new(client),
+        })
+    }
+
+    /// The connection, reopened first if the last one was closed.
+    async fn client(&self) -> Result<MutexGuard<'_, Client>> {
+        let mut client = self.client.lock().await;
+        if client.is_closed() {
+            warn!("Postgres connection lost; reconnecting");
+            *client = open(&self.url).await?;
+        }
+        Ok(client)
+    }
+}
+
+impl SessionStore for PostgresStore {
+    fn append_messages(&self, sid: &SessionId, msgs: &[ChatMessage]) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        let rows: Vec<_> = msgs.iter().map(message_row).collect();
+
+        Box::pin(async move {
+            // All or nothing, so a failed append never leaves half a turn
+            let mut client = self.client().await?;
+            let tx = client.transaction().await.map_err(db_err)?;
+            for (role, content, timestamp, metadata) in &rows {
+                tx.execute(
+                    "INSERT INTO messages (session_id, role, content, timestamp, metadata)
+                     VALUES ($1, $2, $3, $4, $5)",
+                    &[&sid, role, content, timestamp, metadata],
+                )
+                .await
+                .map_err(db_err)?;
+            }
+            tx.commit().await.map_err(db_err)
+        })
+    }
+
+    fn load_history(
+        &self,
+        sid: &SessionId,
+        limit: usize,
+    ) -> BoxFuture<'_, Result<Vec<ChatMessage>>> {
+        let sid = sid.0.clone();
+
+        Box::pin(async move {
+            let rows = self
+                .client()
+                .await?
+                .query(
+                    "SELECT role, content, timestamp, metadata FROM messages
+                     WHERE session_id = $1
+                     ORDER BY id ASC
+                     LIMIT $2",
+                    &[&sid, &(limit as i64)],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(rows
+                .iter()
+                .map(|row| message_from_row(row.get(0), row.get(1), row.get(2), row.get(3)))
+                .collect())
+        })
+    }
+
+    fn search(&self, query: &str, limit: usize) -> BoxFuture<'_, Result<Vec<SearchResult>>> {
+        let query = query.to_string();
+
+        Box::pin(async move {
+            let rows = self
+                .client()
+                .await?
+                .query(
+                    "SELECT session_id, role, content, timestamp,
+                            ts_rank(to_tsvector('english', content), q)::FLOAT8 AS rank
+                     FROM messages, websearch_to_tsquery('english', $1) q
+                     WHERE to_tsvector('english', content) @@ q
+                     ORDER BY rank DESC
+                     LIMIT $2",
+                    &[&query, &(limit as i64)],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(rows
+                .iter()
+                .map(|row| SearchResult {
+                    session_id: row.get(0),
+                    role: row.get(1),
+                    content: row.get(2),
+                    timestamp: DateTime::parse_from_rfc3339(row.get(3))
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                    rank: row.get(4),
+                })
+                .collect())
+        })
+    }
+
+    fn list_sessions(&self, limit: usize) -> BoxFuture<'_, Result<Vec<StoredSession>>> {
+        Box::pin(async move {
+            let rows = self
+                .client()
+                .await?
+                .query(
+                    "SELECT session_id, COUNT(*), MAX(timestamp) FROM messages
+                     GROUP BY session_id
+                     ORDER BY MAX(id) DESC
+                     LIMIT $1",
+                    &[&(limit as i64)],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(rows
+                .iter()
+                .map(|row| stored_session(row.get(0), row.get(1), row.get(2)))
+                .collect())
+        })
+    }
+
+    fn load_summary(&self, sid: &SessionId) -> BoxFuture<'_, Result<Option<String>>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let row = self
+                .client()
+                .await?
+                .query_opt(
+                    "SELECT summary FROM session_summaries WHERE session_id = $1",
+                    &[&sid],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(row.map(|row| row.get(0)))
+        })
+    }
+
+    fn save_summary(&self, sid: &SessionId, summary: &str) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        let summary = summary.to_string();
+        Box::pin(async move {
+            self.client()
+                .await?
+                .execute(
+                    "INSERT INTO session_summaries (session_id, summary, updated_at)
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (session_id) DO UPDATE SET
+                         summary = excluded.summary,
+                         updated_at = excluded.updated_at",
+                    &[&sid, &summary, &Utc::now().to_rfc3339()],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(())
+        })
+    }
+
+    fn load_scratchpad(&self, sid: &SessionId) -> BoxFuture<'_, Result<BTreeMap<String, String>>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let rows = self
+                .client()
+                .await?
+                .query(
+                    "SELECT key, value FROM session_scratchpad WHERE session_id = $1",
+                    &[&sid],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+        })
+    }
+
+    fn set_scratchpad(&self, sid: &SessionId, key: &str, value: &str) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        let key = key.to_string();
+        let value = value.to_string();
+        Box::pin(async move {
+            self.client()
+                .await?
+                .execute(
+                    "INSERT INTO session_scratchpad (session_id, key, value, updated_at)
+                     VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (session_id, key) DO UPDATE SET
+                         value = excluded.value,
+                         updated_at = excluded.updated_at",
+                    &[&sid, &key, &value, &Utc::now().to_rfc3339()],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(())
+        })
+    }
+
+    fn load_no_compact(&self, sid: &SessionId) -> BoxFuture<'_, Result<bool>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let row = self
+                .client()
+                .await?
+                .query_opt(
+                    "SELECT no_compact FROM session_settings WHERE session_id = $1",
+                    &[&sid],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(row.is_some_and(|row| row.get(0)))
+        })
+    }
+
+    fn save_no_compact(&self, sid: &SessionId, no_compact: bool) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            self.client()
+                .await?
+                .execute(
+                    "INSERT INTO session_settings (session_id, no_compact, updated_at)
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (session_id) DO UPDATE SET
+                         no_compact = excluded.no_compact,
+                         updated_at = excluded.updated_at",
+                    &[&sid, &no_compact, &Utc::now().to_rfc3339()],
+                )
+                .await
+                .map_err(db_err)?;
+            Ok(())
+        })
+    }
+}
+
+/// These need a database, so they are ignored by default: set
+/// `RYVOS_TEST_POSTGRES_URL` and pass `--include-ignored` (CI runs them
+/// against a Postgres service).
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SqliteStore;
+    use ryvos_core::types::{Attachment, AttachmentKind, ContentBlock, MessageMetadata, Role};
+
+    async fn connect() -> PostgresStore {
+        let url = std::env::var("RYVOS_TEST_POSTGRES_URL")
+            .expect("RYVOS_TEST_POSTGRES_URL names the test database");
+        PostgresStore::connect(&url).await.unwrap()
+    }
+
+    /// Stamped, since each store would otherwise stamp its own time.
+    fn conversation() -> Vec<ChatMessage> {
+        let sent = Utc::now();
+        let msgs = vec![
+            ChatMessage::user("Which files mention the Postgres backend?").with_metadata(
+                MessageMetadata {
+                    source_channel: Some("slack".to_string()),
+                    sender: Some("U024BE7LH".to_string()),
+                    attachments: vec![Attachment {
+                        kind: AttachmentKind::File,
+                        reference: "https://files.slack.com/notes.txt".to_string(),
+                    }],
+                    ..Default::default()
+                },
+            ),
+            ChatMessage {
+                role: Role::Assistant,
+                content: vec![
+                    ContentBlock::Text {
+                        text: "Let me look.".to_string(),
+                    },
+                    ContentBlock::ToolUse {
+                        id: "call_1".to_string(),
+                        name: "grep".to_string(),
+                        input: serde_json::json!({ "pattern": "postgres" }),
+                    },
+                ],
+                timestamp: None,
+                metadata: None,
+            },
+            ChatMessage::tool_result("call_1", "src/postgres_store.rs", false),
+            ChatMessage::assistant_text("Only src/postgres_store.rs."),
+        ];
+        msgs.into_iter()
+            .map(|msg| ChatMessage {
+                timestamp: Some(sent),
+                ..msg
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a database at RYVOS_TEST_POSTGRES_URL"]
+    async fn history_round_trips_like_sqlite() {
+        let postgres = connect().await;
+        let sqlite = SqliteStore::in_memory().unwrap();
+        let sid = SessionId::new();
+        let msgs = conversation();
+
+        let mut histories = Vec::new();
+        for store in [&postgres as &dyn SessionStore, &sqlite] {
+            store.append_messages(&sid, &msgs[..2]).await.unwrap();
+            store.append_messages(&sid, &msgs[2..]).await.unwrap();
+            let history = store.load_history(&sid, 100).await.unwrap();
+            assert_eq!(store.load_history(&sid, 2).await.unwrap().len(), 2);
+            histories.push(serde_json::to_value(history).unwrap());
+        }
+        assert_eq!(histories[0], histories[1]);
+        assert_eq!(histories[0][0]["metadata"]["source_channel"], "slack");
+        assert_eq!(histories[0].as_array().unwrap().len(), msgs.len());
+        assert!(postgres
+            .load_history(&SessionId::new(), 100)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let found = postgres.search("Postgres backend", 10).await.unwrap();
+        assert!(found.iter().any(|r| r.session_id == sid.0));
+
+        let listed = postgres.list_sessions(1).await.unwrap();
+        assert_eq!(listed[0].session_id, sid.0);
+        assert_eq!(listed[0].message_count, msgs.len() as u64);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a database at RYVOS_TEST_POSTGRES_URL"]
+    async fn summary_and_scratchpad_are_replaced() {
+        let store = connect().await;
+        let sid = SessionId::new();
+        assert_eq!(store.load_summary(&sid).await.unwrap(), None);
+        store.save_summary(&sid, "first").await.unwrap();
+        store.save_summary(&sid, "second").await.unwrap();
+        assert_eq!(
+            store.load_summary(&sid).await.unwrap().as_deref(),
+            Some("second")
+        );
+
+        store.set_scratchpad(&sid, "plan", "draft").await.unwrap();
+        store.set_scratchpad(&sid, "plan", "final").await.unwrap();
+        let entries = store.load_scratchpad(&sid).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries["plan"], "final");
+
+        assert!(!store.load_no_compact(&sid).await.unwrap());
+        store.save_no_compact(&sid, true).await.unwrap();
+        assert!(store.load_no_compact(&sid).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a database at RYVOS_TEST_POSTGRES_URL"]
+    async fn reconnects_after_the_connection_drops() {
+        let store = connect().await;
+        let sid = SessionId::new();
+        let _ = store
+            .client()
+            .await
+            .unwrap()
+            .batch_execute("SELECT pg_terminate_backend(pg_backend_pid())")
+            .await;
+        // The connection task sees the close on its own time
+        for _ in 0..100 {
+            if store.client.lock().await.is_closed() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        store.save_summary(&sid, "after").await.unwrap();
+        assert_eq!(
+            store.load_summary(&sid).await.unwrap().as_deref(),
+            Some("after")
+        );
+    }
+}
@@ -20,11 +20,38 @@ fn default_pricing() -> Vec<(&'static str, u64, u64)> {
     ]
 }
 
+/// Known pricing for a model in cents per million tokens (input, output):
+/// overrides first, then the default table, then a model family named in
+/// the ID. `None` if the model matches none of these.
+pub fn model_pricing(model: &str, overrides: &HashMap<String, ModelPricing>) -> Option<(u64, u64)> {
+    if let Some(pricing) = overrides.get(model) {
+        return Some((pricing.input_cents_per_mtok, pricing.output_cents_per_mtok));
+    }
+    if let Some((_, i, o)) = default_pricing().iter().find(|(m, _, _)| *m == model) {
+        return Some((*i, *o));
+    }
+    // Check partial match (model ID contains a known name)
+    let lower = model.to_lowercase();
+    if lower.contains("opus") {
+        Some((1500, 7500))
+    } else if lower.contains("haiku") {
+        Some((80, 400))
+    } else if lower.contains("sonnet") {
+        Some((300, 1500))
+    } else if lower.contains("gpt-4o-mini") {
+        Some((15, 60))
+    } else if lower.contains("gpt-4o") {
+        Some((250, 1000))
+    } else {
+        None
+    }
+}
+
 /// Estimate cost in cents for a given usage.
 ///
 /// Subscription billing always returns 0 (flat rate).
-/// For API billing, looks up model pricing from overrides first,
-/// then default table, then fallback (300/1500 = Sonnet-class pricing).
+/// For API billing, uses [`model_pricing`], falling back to
+/// Sonnet-class pricing (300/1500) for unknown models.
 pub fn estimate_cost_cents(
     model: &str,
     _provider: &str,
@@ -37,31 +64,7 @@ pub fn estimate_cost_cents(
         return 0;
     }
 
-    let (input_rate, output_rate) = if let Some(pricing) = overrides.get(model) {
-        (pricing.input_cents_per_mtok, pricing.output_cents_per_mtok)
-    } else {
-        match default_pricing().iter().find(|(m, _, _)| *m == model) {
-            Some((_, i, o)) => (*i, *o),
-            None => {
-                // Check partial match (model ID contains a known name)
-                let lower = model.to_lowercase();
-                if lower.contains("opus") {
-                    (1500, 7500)
-                } else if lower.contains("haiku") {
-                    (80, 400)
-                } else if lower.contains("sonnet") {
-                    (300, 1500)
-                } else if lower.contains("gpt-4o-mini") {
-                    (15, 60)
-                } else if lower.contains("gpt-4o") {
-                    (250, 1000)
-                } else {
-                    // Fallback: Sonnet-class pricing
-                    (300, 1500)
-                }
-            }
-        }
-    };
+    let (input_rate, output_rate) = model_pricing(model, overrides).unwrap_or((300, 1500));
 
     // cost = tokens * rate / 1_000_000
     let input_cost = input_tokens * input_rate / 1_000_000;
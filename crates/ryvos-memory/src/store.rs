@@ -1,13 +1,17 @@
 use chrono::{DateTime, Utc};
 use futures::future::BoxFuture;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeMap;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tracing::debug;
 
+use ryvos_core::config::{StorageBackend, StorageConfig};
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::SessionStore;
-use ryvos_core::types::{ChatMessage, SearchResult, SessionId};
+use ryvos_core::types::{
+    ChatMessage, MessageMetadata, Role, SearchResult, SessionId, StoredSession,
+};
 
 use crate::embeddings::cosine_similarity;
 
@@ -39,7 +43,8 @@ impl SqliteStore {
                 session_id TEXT NOT NULL,
                 role TEXT NOT NULL,
                 content TEXT NOT NULL,
-                timestamp TEXT NOT NULL
+                timestamp TEXT NOT NULL,
+                metadata TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_messages_session
@@ -65,10 +70,39 @@ impl SqliteStore {
                 embedding BLOB NOT NULL
             );
 
-            CREATE INDEX IF NOT EXISTS idx_embeddings_msg ON embeddings(message_id);",
+            CREATE INDEX IF NOT EXISTS idx_embeddings_msg ON embeddings(message_id);
+
+            CREATE TABLE IF NOT EXISTS session_summaries (
+                session_id TEXT PRIMARY KEY,
+                summary TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS session_scratchpad (
+                session_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (session_id, key)
+            );
+
+            CREATE TABLE IF NOT EXISTS session_settings (
+                session_id TEXT PRIMARY KEY,
+                no_compact INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
         )
         .map_err(|e| RyvosError::Database(e.to_string()))?;
 
+        // Databases created before messages kept their source lack the column
+        if conn
+            .prepare("SELECT metadata FROM messages LIMIT 0")
+            .is_err()
+        {
+            conn.execute_batch("ALTER TABLE messages ADD COLUMN metadata TEXT")
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+        }
+
         debug!(path = %path.display(), "SQLite store opened");
         Ok(Self {
             conn: Mutex::new(conn),
@@ -85,7 +119,8 @@ impl SqliteStore {
                 session_id TEXT NOT NULL,
                 role TEXT NOT NULL,
                 content TEXT NOT NULL,
-                timestamp TEXT NOT NULL
+                timestamp TEXT NOT NULL,
+                metadata TEXT
             );
 
             CREATE INDEX idx_messages_session ON messages(session_id, id);
@@ -110,7 +145,27 @@ impl SqliteStore {
                 embedding BLOB NOT NULL
             );
 
-            CREATE INDEX IF NOT EXISTS idx_embeddings_msg ON embeddings(message_id);",
+            CREATE INDEX IF NOT EXISTS idx_embeddings_msg ON embeddings(message_id);
+
+            CREATE TABLE IF NOT EXISTS session_summaries (
+                session_id TEXT PRIMARY KEY,
+                summary TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS session_scratchpad (
+                session_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (session_id, key)
+            );
+
+            CREATE TABLE IF NOT EXISTS session_settings (
+                session_id TEXT PRIMARY KEY,
+                no_compact INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
         )
         .map_err(|e| RyvosError::Database(e.to_string()))?;
 
@@ -200,23 +255,92 @@ impl SqliteStore {
     }
 }
 
+/// Open the session store `[storage]` selects: `sessions.db` in
+/// `workspace`, or the Postgres database at `storage.url`.
+pub async fn open_session_store(
+    storage: &StorageConfig,
+    workspace: &Path,
+) -> Result<Arc<dyn SessionStore>> {
+    match storage.backend {
+        StorageBackend::Sqlite => Ok(Arc::new(SqliteStore::open(&workspace.join("sessions.db"))?)),
+        #[cfg(feature = "postgres")]
+        StorageBackend::Postgres => {
+            let url = storage.url.as_deref().ok_or_else(|| {
+                RyvosError::Config("[storage] backend = \"postgres\" needs a url".to_string())
+            })?;
+            Ok(Arc::new(crate::PostgresStore::connect(url).await?))
+        }
+        #[cfg(not(feature = "postgres"))]
+        StorageBackend::Postgres => Err(RyvosError::Config(
+            "[storage] backend = \"postgres\" needs ryvos built with the `postgres` feature"
+                .to_string(),
+        )),
+    }
+}
+
+/// The `(role, content, timestamp, metadata)` columns a message is stored
+/// as. Every [`SessionStore`] backend uses the same encoding, so history
+/// reads back the same whichever one wrote it. Only the message's source
+/// is kept of its metadata (see [`MessageMetadata::source`]).
+pub(crate) type MessageRow = (String, String, String, Option<String>);
+
+/// A `list_sessions` row: the session, its message count and the
+/// timestamp of its latest message.
+pub(crate) fn stored_session(session_id: String, count: i64, last_active: &str) -> StoredSession {
+    StoredSession {
+        session_id,
+        message_count: count as u64,
+        last_active: DateTime::parse_from_rfc3339(last_active)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+    }
+}
+
+pub(crate) fn message_row(msg: &ChatMessage) -> MessageRow {
+    let role = match msg.role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Tool => "tool",
+    };
+    let content = serde_json::to_string(&msg.content).unwrap_or_default();
+    let timestamp = msg.timestamp.unwrap_or_else(Utc::now).to_rfc3339();
+    let metadata = msg
+        .metadata
+        .as_ref()
+        .and_then(MessageMetadata::source)
+        .and_then(|source| serde_json::to_string(&source).ok());
+    (role.to_string(), content, timestamp, metadata)
+}
+
+/// The message stored by [`message_row`].
+pub(crate) fn message_from_row(
+    role: &str,
+    content: &str,
+    timestamp: &str,
+    metadata: Option<&str>,
+) -> ChatMessage {
+    let role = match role {
+        "system" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        "tool" => Role::Tool,
+        _ => Role::User,
+    };
+    ChatMessage {
+        role,
+        content: serde_json::from_str(content).unwrap_or_default(),
+        timestamp: DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc)),
+        metadata: metadata.and_then(|m| serde_json::from_str(m).ok()),
+    }
+}
+
 impl SessionStore for SqliteStore {
     fn append_messages(&self, sid: &SessionId, msgs: &[ChatMessage]) -> BoxFuture<'_, Result<()>> {
         let sid = sid.0.clone();
-        let msgs: Vec<_> = msgs
-            .iter()
-            .map(|m| {
-                let role = match m.role {
-                    ryvos_core::types::Role::System => "system",
-                    ryvos_core::types::Role::User => "user",
-                    ryvos_core::types::Role::Assistant => "assistant",
-                    ryvos_core::types::Role::Tool => "tool",
-                };
-                let content = serde_json::to_string(&m.content).unwrap_or_default();
-                let timestamp = m.timestamp.unwrap_or_else(Utc::now).to_rfc3339();
-                (role.to_string(), content, timestamp)
-            })
-            .collect();
+        let msgs: Vec<_> = msgs.iter().map(message_row).collect();
 
         Box::pin(async move {
             let conn = self
@@ -224,10 +348,11 @@ impl SessionStore for SqliteStore {
                 .lock()
                 .map_err(|e| RyvosError::Database(e.to_string()))?;
 
-            for (role, content, timestamp) in &msgs {
+            for (role, content, timestamp, metadata) in &msgs {
                 conn.execute(
-                    "INSERT INTO messages (session_id, role, content, timestamp) VALUES (?1, ?2, ?3, ?4)",
-                    params![sid, role, content, timestamp],
+                    "INSERT INTO messages (session_id, role, content, timestamp, metadata)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![sid, role, content, timestamp, metadata],
                 )
                 .map_err(|e| RyvosError::Database(e.to_string()))?;
             }
@@ -251,7 +376,7 @@ impl SessionStore for SqliteStore {
 
             let mut stmt = conn
                 .prepare(
-                    "SELECT role, content, timestamp FROM messages
+                    "SELECT role, content, timestamp, metadata FROM messages
                      WHERE session_id = ?1
                      ORDER BY id ASC
                      LIMIT ?2",
@@ -263,34 +388,21 @@ impl SessionStore for SqliteStore {
                     let role: String = row.get(0)?;
                     let content_str: String = row.get(1)?;
                     let ts_str: String = row.get(2)?;
-                    Ok((role, content_str, ts_str))
+                    let metadata: Option<String> = row.get(3)?;
+                    Ok((role, content_str, ts_str, metadata))
                 })
                 .map_err(|e| RyvosError::Database(e.to_string()))?;
 
             let mut messages = Vec::new();
             for row in rows {
-                let (role, content_str, ts_str) =
+                let (role, content_str, ts_str, metadata) =
                     row.map_err(|e| RyvosError::Database(e.to_string()))?;
-
-                let role = match role.as_str() {
-                    "system" => ryvos_core::types::Role::System,
-                    "user" => ryvos_core::types::Role::User,
-                    "assistant" => ryvos_core::types::Role::Assistant,
-                    "tool" => ryvos_core::types::Role::Tool,
-                    _ => ryvos_core::types::Role::User,
-                };
-
-                let content = serde_json::from_str(&content_str).unwrap_or_default();
-                let timestamp = DateTime::parse_from_rfc3339(&ts_str)
-                    .ok()
-                    .map(|dt| dt.with_timezone(&Utc));
-
-                messages.push(ChatMessage {
-                    role,
-                    content,
-                    timestamp,
-                    metadata: None,
-                });
+                messages.push(message_from_row(
+                    &role,
+                    &content_str,
+                    &ts_str,
+                    metadata.as_deref(),
+                ));
             }
 
             Ok(messages)
@@ -341,6 +453,157 @@ impl SessionStore for SqliteStore {
             Ok(results)
         })
     }
+
+    fn list_sessions(&self, limit: usize) -> BoxFuture<'_, Result<Vec<StoredSession>>> {
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+
+            let mut stmt = conn
+                .prepare(
+                    "SELECT session_id, COUNT(*), MAX(timestamp) FROM messages
+                     GROUP BY session_id
+                     ORDER BY MAX(id) DESC
+                     LIMIT ?1",
+                )
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+
+            let rows = stmt
+                .query_map(params![limit as i64], |row| {
+                    let session_id: String = row.get(0)?;
+                    let count: i64 = row.get(1)?;
+                    let last_active: String = row.get(2)?;
+                    Ok(stored_session(session_id, count, &last_active))
+                })
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+
+            let mut sessions = Vec::new();
+            for row in rows {
+                sessions.push(row.map_err(|e| RyvosError::Database(e.to_string()))?);
+            }
+
+            Ok(sessions)
+        })
+    }
+
+    fn load_summary(&self, sid: &SessionId) -> BoxFuture<'_, Result<Option<String>>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            conn.query_row(
+                "SELECT summary FROM session_summaries WHERE session_id = ?1",
+                params![sid],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| RyvosError::Database(e.to_string()))
+        })
+    }
+
+    fn save_summary(&self, sid: &SessionId, summary: &str) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        let summary = summary.to_string();
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            conn.execute(
+                "INSERT INTO session_summaries (session_id, summary, updated_at)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT(session_id) DO UPDATE SET
+                     summary = excluded.summary,
+                     updated_at = excluded.updated_at",
+                params![sid, summary, Utc::now().to_rfc3339()],
+            )
+            .map_err(|e| RyvosError::Database(e.to_string()))?;
+            Ok(())
+        })
+    }
+
+    fn load_scratchpad(&self, sid: &SessionId) -> BoxFuture<'_, Result<BTreeMap<String, String>>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            let mut stmt = conn
+                .prepare("SELECT key, value FROM session_scratchpad WHERE session_id = ?1")
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            let rows = stmt
+                .query_map(params![sid], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            rows.collect::<std::result::Result<_, _>>()
+                .map_err(|e| RyvosError::Database(e.to_string()))
+        })
+    }
+
+    fn set_scratchpad(&self, sid: &SessionId, key: &str, value: &str) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        let key = key.to_string();
+        let value = value.to_string();
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            conn.execute(
+                "INSERT INTO session_scratchpad (session_id, key, value, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(session_id, key) DO UPDATE SET
+                     value = excluded.value,
+                     updated_at = excluded.updated_at",
+                params![sid, key, value, Utc::now().to_rfc3339()],
+            )
+            .map_err(|e| RyvosError::Database(e.to_string()))?;
+            Ok(())
+        })
+    }
+
+    fn load_no_compact(&self, sid: &SessionId) -> BoxFuture<'_, Result<bool>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            let no_compact: Option<bool> = conn
+                .query_row(
+                    "SELECT no_compact FROM session_settings WHERE session_id = ?1",
+                    params![sid],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            Ok(no_compact.unwrap_or(false))
+        })
+    }
+
+    fn save_no_compact(&self, sid: &SessionId, no_compact: bool) -> BoxFuture<'_, Result<()>> {
+        let sid = sid.0.clone();
+        Box::pin(async move {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| RyvosError::Database(e.to_string()))?;
+            conn.execute(
+                "INSERT INTO session_settings (session_id, no_compact, updated_at)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT(session_id) DO UPDATE SET
+                     no_compact = excluded.no_compact,
+                     updated_at = excluded.updated_at",
+                params![sid, no_compact, Utc::now().to_rfc3339()],
+            )
+            .map_err(|e| RyvosError::Database(e.to_string()))?;
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
@@ -378,4 +641,148 @@ mod tests {
         let results = store.search("logging", 10).await.unwrap();
         assert!(!results.is_empty());
     }
+
+    #[tokio::test]
+    async fn sessions_are_listed_latest_first() {
+        let store = SqliteStore::in_memory().unwrap();
+        let (older, newer) = (SessionId::new(), SessionId::new());
+        store
+            .append_messages(&older, &[ChatMessage::user("first")])
+            .await
+            .unwrap();
+        store
+            .append_messages(&newer, &[ChatMessage::user("second")])
+            .await
+            .unwrap();
+        store
+            .append_messages(&older, &[ChatMessage::assistant_text("third")])
+            .await
+            .unwrap();
+
+        let sessions = store.list_sessions(10).await.unwrap();
+        let ids: Vec<_> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, [older.0.as_str(), newer.0.as_str()]);
+        assert_eq!(sessions[0].message_count, 2);
+        assert_eq!(store.list_sessions(1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn message_source_is_stored_without_compaction_flags() {
+        use ryvos_core::types::{Attachment, AttachmentKind};
+
+        let store = SqliteStore::in_memory().unwrap();
+        let sid = SessionId::new();
+        let source = MessageMetadata {
+            source_channel: Some("telegram".to_string()),
+            sender: Some("4242".to_string()),
+            attachments: vec![Attachment {
+                kind: AttachmentKind::Image,
+                reference: "AgACAgQ".to_string(),
+            }],
+            ..Default::default()
+        };
+        let protected = MessageMetadata {
+            protected: true,
+            created_at_turn: Some(3),
+            ..Default::default()
+        };
+        let msgs = vec![
+            ChatMessage::user("What is in this photo?").with_metadata(MessageMetadata {
+                protected: true,
+                ..source.clone()
+            }),
+            ChatMessage::assistant_text("A cat.").with_metadata(protected),
+        ];
+        store.append_messages(&sid, &msgs).await.unwrap();
+
+        let history = store.load_history(&sid, 100).await.unwrap();
+        let stored = history[0].metadata.as_ref().unwrap();
+        assert_eq!(stored.source_channel.as_deref(), Some("telegram"));
+        assert_eq!(stored.sender.as_deref(), Some("4242"));
+        assert_eq!(stored.attachments, source.attachments);
+        assert!(!stored.protected);
+        assert!(history[1].metadata.is_none());
+    }
+
+    #[tokio::test]
+    async fn database_without_metadata_column_is_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE messages (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    session_id TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    timestamp TEXT NOT NULL
+                );
+                INSERT INTO messages (session_id, role, content, timestamp)
+                VALUES ('s1', 'user', '[{\"type\":\"text\",\"text\":\"hi\"}]', '2026-10-16T09:30:00Z');",
+            )
+            .unwrap();
+
+        let store = SqliteStore::open(&path).unwrap();
+        let sid = SessionId::from_string("s1");
+        let history = store.load_history(&sid, 100).await.unwrap();
+        assert_eq!(history[0].text(), "hi");
+        assert!(history[0].metadata.is_none());
+        store
+            .append_messages(&sid, &[ChatMessage::user("again")])
+            .await
+            .unwrap();
+        assert_eq!(store.load_history(&sid, 100).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn session_summary_is_replaced() {
+        let store = SqliteStore::in_memory().unwrap();
+        let sid = SessionId::new();
+        assert_eq!(store.load_summary(&sid).await.unwrap(), None);
+
+        store.save_summary(&sid, "first").await.unwrap();
+        store.save_summary(&sid, "second").await.unwrap();
+        assert_eq!(
+            store.load_summary(&sid).await.unwrap().as_deref(),
+            Some("second")
+        );
+        assert_eq!(store.load_summary(&SessionId::new()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn scratchpad_entries_are_per_session() {
+        let store = SqliteStore::in_memory().unwrap();
+        let sid = SessionId::new();
+        store.set_scratchpad(&sid, "plan", "draft").await.unwrap();
+        store.set_scratchpad(&sid, "plan", "final").await.unwrap();
+        store.set_scratchpad(&sid, "todo", "tests").await.unwrap();
+
+        let entries = store.load_scratchpad(&sid).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["plan"], "final");
+        assert!(store
+            .load_scratchpad(&SessionId::new())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn no_compact_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+        let sid = SessionId::new();
+        {
+            let store = SqliteStore::open(&path).unwrap();
+            assert!(!store.load_no_compact(&sid).await.unwrap());
+            store.save_no_compact(&sid, true).await.unwrap();
+        }
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert!(store.load_no_compact(&sid).await.unwrap());
+        assert!(!store.load_no_compact(&SessionId::new()).await.unwrap());
+        store.save_no_compact(&sid, false).await.unwrap();
+        assert!(!store.load_no_compact(&sid).await.unwrap());
+    }
 }
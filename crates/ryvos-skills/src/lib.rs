@@ -8,19 +8,23 @@
 //!   JSON input is piped to stdin, stdout/stderr captured as the result.
 //! - **SkillManifest**: TOML configuration with `$SKILL_DIR` substitution.
 //! - **Registry**: Remote skill index with SHA-256 verified downloads.
+//! - **Lockfile**: `skills.lock` pinning installed registry releases.
 //!
 //! Skills are loaded from `~/.ryvos/skills/` and registered into the
-//! [`ToolRegistry`] alongside built-in tools.
+//! [`ToolRegistry`] alongside built-in tools. [`run_skill`] executes a single
+//! skill without the agent, for `ryvos skill run`.
 
+pub mod lockfile;
 pub mod manifest;
 pub mod registry;
 pub mod skill_tool;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use tracing::{debug, info, warn};
 
 use ryvos_core::traits::Tool;
+use ryvos_core::types::{SessionId, ToolContext, ToolResult};
 use ryvos_tools::ToolRegistry;
 
 use manifest::{Prerequisites, SkillManifest};
@@ -65,18 +69,10 @@ pub fn load_skills(dir: &Path) -> Vec<SkillTool> {
             continue;
         }
 
-        let content = match std::fs::read_to_string(&manifest_path) {
-            Ok(c) => c,
-            Err(e) => {
-                warn!(path = %manifest_path.display(), error = %e, "Failed to read skill manifest");
-                continue;
-            }
-        };
-
-        let manifest: SkillManifest = match toml::from_str(&content) {
+        let manifest = match read_manifest(&manifest_path) {
             Ok(m) => m,
             Err(e) => {
-                warn!(path = %manifest_path.display(), error = %e, "Failed to parse skill manifest");
+                warn!(path = %manifest_path.display(), error = %e, "Failed to load skill manifest");
                 continue;
             }
         };
@@ -102,6 +98,90 @@ pub fn load_skills(dir: &Path) -> Vec<SkillTool> {
     tools
 }
 
+fn read_manifest(path: &Path) -> std::result::Result<SkillManifest, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    toml::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Load the skill named `name` from `dir`, matching either the manifest name
+/// or the skill's directory name. Unlike [`load_skills`], a skill with unmet
+/// prerequisites is an error carrying the reason.
+pub fn load_skill(dir: &Path, name: &str) -> std::result::Result<SkillTool, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("cannot read skills directory {}: {}", dir.display(), e))?;
+    for skill_dir in entries.flatten().map(|e| e.path()) {
+        let manifest_path = skill_dir.join("skill.toml");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest = match read_manifest(&manifest_path) {
+            Ok(m) => m,
+            Err(e) if skill_dir.file_name() == Some(name.as_ref()) => {
+                return Err(format!("invalid skill.toml: {}", e));
+            }
+            Err(_) => continue,
+        };
+        if manifest.name != name && skill_dir.file_name() != Some(name.as_ref()) {
+            continue;
+        }
+        check_prerequisites(&manifest.prerequisites)
+            .map_err(|reason| format!("prerequisites not met: {}", reason))?;
+        return SkillTool::new(manifest, skill_dir).map_err(|e| e.to_string());
+    }
+    Err(format!("skill '{}' is not installed", name))
+}
+
+/// Build a skill's JSON input from an optional JSON object and `key=value`
+/// pairs, which override keys of the object. Values that parse as JSON
+/// (numbers, booleans, arrays, ...) keep that type; anything else is a string.
+pub fn skill_input(
+    json: Option<&str>,
+    args: &[String],
+) -> std::result::Result<serde_json::Value, String> {
+    let mut input = match json {
+        Some(text) => match serde_json::from_str(text) {
+            Ok(serde_json::Value::Object(map)) => map,
+            Ok(_) => return Err("input must be a JSON object".to_string()),
+            Err(e) => return Err(format!("invalid JSON input: {}", e)),
+        },
+        None => serde_json::Map::new(),
+    };
+    for arg in args {
+        let (key, value) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got '{}'", arg))?;
+        let value = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        input.insert(key.to_string(), value);
+    }
+    Ok(serde_json::Value::Object(input))
+}
+
+/// Run one skill outside the agent loop, in `working_dir`.
+///
+/// Loading and execution failures are `Err`; a skill that ran but exited
+/// non-zero is an `Ok` error result, as the agent would see it.
+pub async fn run_skill(
+    dir: &Path,
+    name: &str,
+    input: serde_json::Value,
+    working_dir: PathBuf,
+) -> std::result::Result<ToolResult, String> {
+    let tool = load_skill(dir, name)?;
+    let ctx = ToolContext {
+        session_id: SessionId::new(),
+        working_dir,
+        store: None,
+        agent_spawner: None,
+        sandbox_config: None,
+        config_path: None,
+        viking_client: None,
+        agent_depth: 0,
+        event_bus: None,
+    };
+    tool.execute(input, ctx).await.map_err(|e| e.to_string())
+}
+
 /// Check that a skill's prerequisites are met.
 /// Returns Ok(()) if all checks pass, or Err with a description of what failed.
 fn check_prerequisites(prereqs: &Prerequisites) -> std::result::Result<(), String> {
@@ -179,6 +259,42 @@ command = "cat"
         assert!(registry.get("echo").is_some());
     }
 
+    #[test]
+    fn catalog_includes_skills_next_to_builtins() {
+        let tmp = tempdir();
+        write_skill(
+            &tmp,
+            "weather",
+            r#"
+name = "weather"
+description = "Fetch current conditions"
+command = "cat"
+"#,
+        );
+
+        let mut registry = ToolRegistry::with_builtins();
+        assert_eq!(load_and_register_skills(&tmp, &mut registry), 1);
+        let catalog = serde_json::to_value(registry.catalog()).unwrap();
+        let entry = |name: &str| {
+            catalog
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|t| t["name"] == name)
+                .cloned()
+                .unwrap()
+        };
+
+        let weather = entry("weather");
+        assert_eq!(weather["description"], "Fetch current conditions");
+        assert_eq!(weather["input_schema"]["type"], "object");
+        assert!(weather["tier"].is_string());
+
+        let read = entry("read");
+        assert_eq!(read["input_schema"]["type"], "object");
+        assert!(read["input_schema"]["properties"].is_object());
+    }
+
     #[test]
     fn skip_invalid_manifest() {
         let tmp = tempdir();
@@ -240,6 +356,67 @@ command = "echo"
         assert_eq!(skills.len(), 1, "Skill without prerequisites should load");
     }
 
+    fn write_skill(root: &Path, dir: &str, manifest: &str) {
+        let skill_dir = root.join(dir);
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("skill.toml"), manifest).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_skill_returns_its_output() {
+        let tmp = tempdir();
+        write_skill(
+            &tmp,
+            "echo-dir",
+            "name = \"echo\"\ndescription = \"Echo input\"\ncommand = \"cat\"\n",
+        );
+
+        let input = skill_input(Some(r#"{"text": "hi"}"#), &["count=3".into()]).unwrap();
+        let result = run_skill(&tmp, "echo", input, tmp.clone()).await.unwrap();
+        assert!(!result.is_error);
+        let echoed: serde_json::Value = serde_json::from_str(&result.content).unwrap();
+        assert_eq!(echoed, serde_json::json!({"text": "hi", "count": 3}));
+
+        // The directory name works too
+        assert!(load_skill(&tmp, "echo-dir").is_ok());
+        let missing = run_skill(&tmp, "nope", serde_json::json!({}), tmp.clone()).await;
+        assert_eq!(missing.unwrap_err(), "skill 'nope' is not installed");
+    }
+
+    #[tokio::test]
+    async fn run_skill_reports_unmet_prerequisites() {
+        let tmp = tempdir();
+        write_skill(
+            &tmp,
+            "needs_env",
+            r#"
+name = "needs_env"
+description = "Needs an env var"
+command = "cat"
+
+[prerequisites]
+required_env = ["_RYVOS_UNSET_TEST_VAR_XYZ"]
+"#,
+        );
+
+        let err = run_skill(&tmp, "needs_env", serde_json::json!({}), tmp.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "prerequisites not met: required env var '_RYVOS_UNSET_TEST_VAR_XYZ' is not set"
+        );
+    }
+
+    #[test]
+    fn skill_input_parses_args() {
+        let input = skill_input(None, &["city=Paris".into(), "days=2".into()]).unwrap();
+        assert_eq!(input, serde_json::json!({"city": "Paris", "days": 2}));
+        assert!(skill_input(Some("[1]"), &[]).is_err());
+        assert!(skill_input(None, &["novalue".into()]).is_err());
+    }
+
     fn tempdir() -> std::path::PathBuf {
         let dir = std::env::temp_dir().join(format!("ryvos_skills_test_{}", uuid::Uuid::new_v4()));
         fs::create_dir_all(&dir).unwrap();
@@ -0,0 +1,357 @@
+//! `skills.lock`: the exact registry skills installed in a skills directory.
+//!
+//! Every `ryvos skill install` records the installed version, tarball URL
+//! and checksum, so `ryvos skill sync` can reinstall the same bytes later
+//! without consulting the registry.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::registry::{self, DownloadProgress, RegistryEntry, RegistryIndex};
+
+/// File name of the lockfile inside the skills directory.
+pub const LOCKFILE_NAME: &str = "skills.lock";
+
+/// File inside an installed skill's directory naming the release it holds.
+pub const RELEASE_FILE: &str = ".release.toml";
+
+/// A skill pinned to an exact registry release.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedSkill {
+    pub name: String,
+    pub version: String,
+    pub tarball_url: String,
+    pub sha256: String,
+}
+
+impl LockedSkill {
+    /// Registry entry that reinstalls exactly this release.
+    pub fn to_entry(&self) -> RegistryEntry {
+        RegistryEntry {
+            name: self.name.clone(),
+            description: String::new(),
+            version: self.version.clone(),
+            author: None,
+            tarball_url: self.tarball_url.clone(),
+            sha256: self.sha256.clone(),
+            tier: "t1".to_string(),
+            tags: vec![],
+        }
+    }
+}
+
+impl From<&RegistryEntry> for LockedSkill {
+    fn from(entry: &RegistryEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            version: entry.version.clone(),
+            tarball_url: entry.tarball_url.clone(),
+            sha256: entry.sha256.clone(),
+        }
+    }
+}
+
+/// Contents of `skills.lock`, kept sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillsLock {
+    #[serde(default, rename = "skill")]
+    pub skills: Vec<LockedSkill>,
+}
+
+impl SkillsLock {
+    /// Path of the lockfile for `skills_dir`.
+    pub fn path(skills_dir: &Path) -> PathBuf {
+        skills_dir.join(LOCKFILE_NAME)
+    }
+
+    /// Load the lockfile for `skills_dir`; a missing file is an empty lock.
+    pub fn load(skills_dir: &Path) -> Result<Self, String> {
+        let path = Self::path(skills_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Write the lockfile for `skills_dir`.
+    pub fn save(&self, skills_dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(skills_dir)
+            .map_err(|e| format!("Failed to create skills dir: {}", e))?;
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize lockfile: {}", e))?;
+        let path = Self::path(skills_dir);
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// The pinned release of `name`, if locked.
+    pub fn get(&self, name: &str) -> Option<&LockedSkill> {
+        self.skills.iter().find(|s| s.name == name)
+    }
+
+    /// Record `entry` as the installed release of its skill.
+    pub fn record(&mut self, entry: &RegistryEntry) {
+        self.remove(&entry.name);
+        self.skills.push(LockedSkill::from(entry));
+        self.skills.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Drop `name` from the lock. Returns whether it was present.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.skills.len();
+        self.skills.retain(|s| s.name != name);
+        self.skills.len() != before
+    }
+}
+
+/// The release installed as `skills_dir/<name>/`, if it was installed
+/// through the lockfile.
+pub fn installed_release(skills_dir: &Path, name: &str) -> Option<LockedSkill> {
+    let content = std::fs::read_to_string(skills_dir.join(name).join(RELEASE_FILE)).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Record `release` as the contents of `skills_dir/<name>/`.
+fn mark_installed(skills_dir: &Path, release: &LockedSkill) -> Result<(), String> {
+    let content = toml::to_string_pretty(release)
+        .map_err(|e| format!("Failed to serialize release: {}", e))?;
+    let path = skills_dir.join(&release.name).join(RELEASE_FILE);
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Split an install spec `name` or `name@version`.
+pub fn parse_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, version)) if !version.is_empty() => (name, Some(version)),
+        Some((name, _)) => (name, None),
+        None => (spec, None),
+    }
+}
+
+/// Find the registry entry for `name`, at exactly `version` when given.
+pub fn resolve<'a>(
+    index: &'a RegistryIndex,
+    name: &str,
+    version: Option<&str>,
+) -> Result<&'a RegistryEntry, String> {
+    let mut candidates = index.skills.iter().filter(|e| e.name == name).peekable();
+    if candidates.peek().is_none() {
+        return Err(format!("Skill '{}' not found in registry", name));
+    }
+    let Some(version) = version else {
+        return Ok(candidates.next().expect("peeked"));
+    };
+    let available: Vec<&str> = candidates.clone().map(|e| e.version.as_str()).collect();
+    candidates.find(|e| e.version == version).ok_or_else(|| {
+        format!(
+            "Skill '{}' has no version {} in the registry (available: {})",
+            name,
+            version,
+            available.join(", ")
+        )
+    })
+}
+
+/// Install the skill named by `spec` (`name` or `name@version`) from
+/// `index` and record it in the lockfile.
+pub async fn install_spec(
+    index: &RegistryIndex,
+    spec: &str,
+    skills_dir: &Path,
+    on_progress: impl FnMut(DownloadProgress),
+) -> Result<LockedSkill, String> {
+    let (name, version) = parse_spec(spec);
+    let entry = resolve(index, name, version)?;
+    let mut lock = SkillsLock::load(skills_dir)?;
+    registry::install_skill_with_progress(entry, skills_dir, on_progress).await?;
+    let release = LockedSkill::from(entry);
+    mark_installed(skills_dir, &release)?;
+    lock.record(entry);
+    lock.save(skills_dir)?;
+    Ok(release)
+}
+
+/// Outcome of [`sync`].
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Locked skills that were missing, or installed at another release,
+    /// and have been reinstalled.
+    pub installed: Vec<String>,
+    /// Locked skills already installed at their pinned release.
+    pub present: Vec<String>,
+    /// Installed skills the lockfile does not mention (left in place).
+    pub unlocked: Vec<String>,
+    /// Locked skills that failed to reinstall, with the error.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Reconcile `skills_dir` with its lockfile: reinstall every locked skill
+/// that is missing or not at its pinned release.
+pub async fn sync(skills_dir: &Path) -> Result<SyncReport, String> {
+    let lock = SkillsLock::load(skills_dir)?;
+    let installed = registry::list_installed(skills_dir);
+    let mut report = SyncReport {
+        unlocked: installed
+            .iter()
+            .filter(|name| lock.get(name).is_none())
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+
+    for locked in &lock.skills {
+        if installed.contains(&locked.name)
+            && installed_release(skills_dir, &locked.name).as_ref() == Some(locked)
+        {
+            report.present.push(locked.name.clone());
+            continue;
+        }
+        info!(name = %locked.name, version = %locked.version, "Reinstalling locked skill");
+        let result = match registry::install_skill(&locked.to_entry(), skills_dir).await {
+            Ok(_) => mark_installed(skills_dir, locked),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => report.installed.push(locked.name.clone()),
+            Err(e) => report.failed.push((locked.name.clone(), e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::tests::{entry_for, make_tarball, serve_tarball};
+
+    fn temp_skills_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ryvos_skills_lock_{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn parse_spec_splits_version() {
+        assert_eq!(parse_spec("git-helper"), ("git-helper", None));
+        assert_eq!(
+            parse_spec("git-helper@1.2.0"),
+            ("git-helper", Some("1.2.0"))
+        );
+        assert_eq!(parse_spec("git-helper@"), ("git-helper", None));
+    }
+
+    #[test]
+    fn lock_roundtrips_sorted() {
+        let dir = temp_skills_dir();
+        let mut lock = SkillsLock::default();
+        lock.record(&entry_for("zeta", "https://example.com/z.tar.gz", b"z"));
+        lock.record(&entry_for("alpha", "https://example.com/a.tar.gz", b"a"));
+        lock.save(&dir).unwrap();
+
+        let loaded = SkillsLock::load(&dir).unwrap();
+        assert_eq!(loaded, lock);
+        let names: Vec<_> = loaded.skills.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "zeta"]);
+        assert!(SkillsLock::load(&temp_skills_dir())
+            .unwrap()
+            .skills
+            .is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn pinned_install_records_version_in_lock() {
+        let old = make_tarball(&[("skill.toml", b"name = \"pinned\"\n".to_vec())]);
+        let new = make_tarball(&[
+            ("skill.toml", b"name = \"pinned\"\n".to_vec()),
+            ("NEW", b"2.0".to_vec()),
+        ]);
+        let (old_url, _) = serve_tarball(old.clone(), None).await;
+        let (new_url, _) = serve_tarball(new.clone(), None).await;
+        let mut latest = entry_for("pinned", &new_url, &new);
+        latest.version = "2.0.0".into();
+        let index = RegistryIndex {
+            version: 1,
+            skills: vec![latest, entry_for("pinned", &old_url, &old)],
+        };
+        let dir = temp_skills_dir();
+
+        let locked = install_spec(&index, "pinned@1.0.0", &dir, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(locked.version, "1.0.0");
+        assert!(!dir.join("pinned/NEW").exists());
+        let lock = SkillsLock::load(&dir).unwrap();
+        let pinned = lock.get("pinned").unwrap();
+        assert_eq!(pinned.version, "1.0.0");
+        assert_eq!(pinned.tarball_url, old_url);
+
+        let err = install_spec(&index, "pinned@9.9.9", &dir, |_| {})
+            .await
+            .unwrap_err();
+        assert!(err.contains("available: 2.0.0, 1.0.0"));
+        assert_eq!(SkillsLock::load(&dir).unwrap(), lock);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn sync_reinstalls_missing_pinned_skill() {
+        let tarball = make_tarball(&[("skill.toml", b"name = \"synced\"\n".to_vec())]);
+        let (url, requests) = serve_tarball(tarball.clone(), None).await;
+        let dir = temp_skills_dir();
+        let mut lock = SkillsLock::default();
+        lock.record(&entry_for("synced", &url, &tarball));
+        lock.save(&dir).unwrap();
+        let manual = dir.join("manual");
+        std::fs::create_dir_all(&manual).unwrap();
+        std::fs::write(manual.join("skill.toml"), "").unwrap();
+
+        let report = sync(&dir).await.unwrap();
+        assert_eq!(report.installed, vec!["synced".to_string()]);
+        assert_eq!(report.unlocked, vec!["manual".to_string()]);
+        assert!(report.failed.is_empty());
+        assert!(dir.join("synced/skill.toml").exists());
+
+        // Already in place: nothing is downloaded again
+        let report = sync(&dir).await.unwrap();
+        assert_eq!(report.present, vec!["synced".to_string()]);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn sync_reinstalls_skill_at_another_version() {
+        let old = make_tarball(&[("skill.toml", b"name = \"bumped\"\n".to_vec())]);
+        let new = make_tarball(&[
+            ("skill.toml", b"name = \"bumped\"\n".to_vec()),
+            ("NEW", b"2.0".to_vec()),
+        ]);
+        let (old_url, _) = serve_tarball(old.clone(), None).await;
+        let (new_url, _) = serve_tarball(new.clone(), None).await;
+        let mut latest = entry_for("bumped", &new_url, &new);
+        latest.version = "2.0.0".into();
+        let index = RegistryIndex {
+            version: 1,
+            skills: vec![latest.clone(), entry_for("bumped", &old_url, &old)],
+        };
+        let dir = temp_skills_dir();
+        install_spec(&index, "bumped@1.0.0", &dir, |_| {})
+            .await
+            .unwrap();
+
+        // The lock moves to 2.0.0, e.g. pulled from another machine
+        let mut lock = SkillsLock::load(&dir).unwrap();
+        lock.record(&latest);
+        lock.save(&dir).unwrap();
+
+        let report = sync(&dir).await.unwrap();
+        assert_eq!(report.installed, vec!["bumped".to_string()]);
+        assert!(dir.join("bumped/NEW").exists());
+        assert_eq!(installed_release(&dir, "bumped").unwrap().version, "2.0.0");
+        let report = sync(&dir).await.unwrap();
+        assert_eq!(report.present, vec!["bumped".to_string()]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
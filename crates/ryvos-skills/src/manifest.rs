@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
 /// Environment prerequisites for a skill.
@@ -14,6 +16,100 @@ pub struct Prerequisites {
     pub required_os: Option<String>,
 }
 
+/// Environment of a skill's command (`[env]`).
+///
+/// The child starts from an empty environment plus [`BASELINE_ENV`]; nothing
+/// else from Ryvos' environment, including API keys, reaches it unless named
+/// in `pass` or in `prerequisites.required_env`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SkillEnv {
+    /// Variables passed through from Ryvos' environment when set.
+    #[serde(default)]
+    pub pass: Vec<String>,
+    /// Variables set to fixed values; these win over passed-through ones.
+    #[serde(default)]
+    pub set: BTreeMap<String, String>,
+}
+
+/// Variables every skill inherits: enough to find binaries, a home and
+/// temp dir, and a locale.
+pub const BASELINE_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_ALL",
+    "TZ",
+    "TMPDIR",
+    // Needed for processes to start on Windows
+    "SYSTEMROOT",
+    "PATHEXT",
+    "COMSPEC",
+];
+
+/// JSON type of a declared skill input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl InputType {
+    /// Name used in JSON Schema and error messages.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InputType::String => "string",
+            InputType::Integer => "integer",
+            InputType::Number => "number",
+            InputType::Boolean => "boolean",
+            InputType::Array => "array",
+            InputType::Object => "object",
+        }
+    }
+
+    /// Whether `value` has this type.
+    pub fn matches(self, value: &serde_json::Value) -> bool {
+        match self {
+            InputType::String => value.is_string(),
+            InputType::Integer => value.is_i64() || value.is_u64(),
+            InputType::Number => value.is_number(),
+            InputType::Boolean => value.is_boolean(),
+            InputType::Array => value.is_array(),
+            InputType::Object => value.is_object(),
+        }
+    }
+}
+
+/// A typed argument declared with `[[inputs]]`.
+///
+/// Every input reaches the command in the stdin JSON; `env` and `flag`
+/// additionally pass it as an environment variable or a command-line flag.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillInput {
+    pub name: String,
+    /// JSON type (default: "string").
+    #[serde(rename = "type", default)]
+    pub kind: InputType,
+    #[serde(default)]
+    pub required: bool,
+    /// Shown to the LLM in the input schema.
+    #[serde(default)]
+    pub description: String,
+    /// Environment variable to export the value as.
+    #[serde(default)]
+    pub env: Option<String>,
+    /// Flag appended to the command as `<flag> <value>`; a boolean input
+    /// appends just the flag when true.
+    #[serde(default)]
+    pub flag: Option<String>,
+}
+
 /// TOML manifest for a drop-in skill.
 ///
 /// Lives at `~/.ryvos/skills/<name>/skill.toml`.
@@ -38,9 +134,15 @@ pub struct SkillManifest {
     pub requires_sandbox: bool,
 
     /// JSON string containing the input schema (OpenAI function format).
+    /// Ignored when `inputs` are declared.
     #[serde(default = "default_schema")]
     pub input_schema_json: String,
 
+    /// Typed inputs; when present they define the schema and are validated
+    /// before the command runs. Without them the input is free-form.
+    #[serde(default)]
+    pub inputs: Vec<SkillInput>,
+
     /// Security tier for this skill (default: "t2").
     #[serde(default = "default_skill_tier")]
     pub tier: String,
@@ -48,6 +150,10 @@ pub struct SkillManifest {
     /// Environment prerequisites (optional, backward-compatible).
     #[serde(default)]
     pub prerequisites: Prerequisites,
+
+    /// Environment variables the command may see beyond the baseline.
+    #[serde(default)]
+    pub env: SkillEnv,
 }
 
 fn default_skill_tier() -> String {
@@ -121,5 +227,49 @@ command = "cat"
             manifest.input_schema_json,
             r#"{"type":"object","properties":{}}"#
         );
+        assert!(manifest.inputs.is_empty());
+        assert!(manifest.env.pass.is_empty());
+    }
+
+    #[test]
+    fn parse_env() {
+        let toml_str = r#"
+name = "weather"
+description = "Weather"
+command = "weather"
+
+[env]
+pass = ["OPENWEATHER_API_KEY"]
+set = { UNITS = "metric" }
+"#;
+        let manifest: SkillManifest = toml::from_str(toml_str).unwrap();
+        assert_eq!(manifest.env.pass, vec!["OPENWEATHER_API_KEY"]);
+        assert_eq!(manifest.env.set["UNITS"], "metric");
+    }
+
+    #[test]
+    fn parse_inputs() {
+        let toml_str = r#"
+name = "weather_lookup"
+description = "Look up weather"
+command = "weather"
+
+[[inputs]]
+name = "city"
+required = true
+env = "CITY"
+
+[[inputs]]
+name = "days"
+type = "integer"
+flag = "--days"
+"#;
+        let manifest: SkillManifest = toml::from_str(toml_str).unwrap();
+        assert_eq!(manifest.inputs.len(), 2);
+        assert_eq!(manifest.inputs[0].kind, InputType::String);
+        assert!(manifest.inputs[0].required);
+        assert_eq!(manifest.inputs[0].env.as_deref(), Some("CITY"));
+        assert_eq!(manifest.inputs[1].kind, InputType::Integer);
+        assert_eq!(manifest.inputs[1].flag.as_deref(), Some("--days"));
     }
 }
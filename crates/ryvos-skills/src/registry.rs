@@ -83,35 +83,52 @@ pub fn search_skills<'a>(index: &'a RegistryIndex, query: &str) -> Vec<&'a Regis
         .collect()
 }
 
+/// Download progress reported while installing a skill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes on disk so far, including any resumed prefix.
+    pub downloaded: u64,
+    /// Full tarball size, when the server reports it.
+    pub total: Option<u64>,
+}
+
 /// Install a skill from the registry to the local skills directory.
 ///
-/// 1. Download the tarball from `entry.tarball_url`
-/// 2. Verify SHA-256 checksum
-/// 3. Extract to `skills_dir/<name>/`
-/// 4. Verify `skill.toml` exists
+/// See [`install_skill_with_progress`].
 pub async fn install_skill(entry: &RegistryEntry, skills_dir: &Path) -> Result<PathBuf, String> {
-    let client = reqwest::Client::new();
-
-    // Download tarball
-    info!(name = %entry.name, url = %entry.tarball_url, "Downloading skill");
-    let response = client
-        .get(&entry.tarball_url)
-        .send()
-        .await
-        .map_err(|e| format!("Download failed: {}", e))?;
+    install_skill_with_progress(entry, skills_dir, |_| {}).await
+}
 
-    if !response.status().is_success() {
-        return Err(format!("Download returned HTTP {}", response.status()));
-    }
+/// Install a skill from the registry, reporting download progress.
+///
+/// 1. Stream the tarball from `entry.tarball_url` into
+///    `skills_dir/.downloads/`, resuming a partial file with an HTTP range
+///    request when the server supports it
+/// 2. Verify SHA-256 checksum
+/// 3. Extract into a temporary directory and verify `skill.toml` exists
+/// 4. Swap the temporary directory into `skills_dir/<name>/`
+///
+/// A failed install leaves any previous version of the skill in place.
+pub async fn install_skill_with_progress(
+    entry: &RegistryEntry,
+    skills_dir: &Path,
+    mut on_progress: impl FnMut(DownloadProgress),
+) -> Result<PathBuf, String> {
+    let downloads = skills_dir.join(".downloads");
+    std::fs::create_dir_all(&downloads)
+        .map_err(|e| format!("Failed to create download dir: {}", e))?;
+    let tarball_path = downloads.join(format!("{}-{}.tar.gz.part", entry.name, entry.version));
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+    info!(name = %entry.name, url = %entry.tarball_url, "Downloading skill");
+    download_resumable(&entry.tarball_url, &tarball_path, &mut on_progress).await?;
 
     // Verify SHA-256
+    let bytes =
+        std::fs::read(&tarball_path).map_err(|e| format!("Failed to read download: {}", e))?;
     let hash = sha256_hex(&bytes);
     if hash != entry.sha256 {
+        // A corrupt partial must not be resumed
+        std::fs::remove_file(&tarball_path).ok();
         return Err(format!(
             "SHA-256 mismatch: expected {}, got {}",
             entry.sha256, hash
@@ -119,48 +136,136 @@ pub async fn install_skill(entry: &RegistryEntry, skills_dir: &Path) -> Result<P
     }
     debug!(name = %entry.name, "SHA-256 verified");
 
-    // Extract to skills_dir/<name>/
+    // Extract next to the final location so the swap is a rename
+    let staging = skills_dir.join(format!(".{}.installing", entry.name));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)
+            .map_err(|e| format!("Failed to clear staging dir: {}", e))?;
+    }
+    std::fs::create_dir_all(&staging).map_err(|e| format!("Failed to create dir: {}", e))?;
+    if let Err(e) = extract_tarball(&tarball_path, &staging).await {
+        std::fs::remove_dir_all(&staging).ok();
+        return Err(e);
+    }
+    std::fs::remove_file(&tarball_path).ok();
+
     let skill_dir = skills_dir.join(&entry.name);
-    if skill_dir.exists() {
-        // Remove existing installation
-        std::fs::remove_dir_all(&skill_dir)
-            .map_err(|e| format!("Failed to remove existing: {}", e))?;
+    swap_into_place(&staging, &skill_dir)?;
+
+    info!(name = %entry.name, path = %skill_dir.display(), "Skill installed");
+    Ok(skill_dir)
+}
+
+/// Stream `url` into `path`, appending to an existing partial file when the
+/// server honours a range request and starting over when it does not.
+async fn download_resumable(
+    url: &str,
+    path: &Path,
+    on_progress: &mut impl FnMut(DownloadProgress),
+) -> Result<(), String> {
+    use std::io::Write;
+
+    let client = reqwest::Client::new();
+    let offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        debug!(offset, "Resuming partial download");
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+        // The partial file already holds the whole tarball
+        on_progress(DownloadProgress {
+            downloaded: offset,
+            total: Some(offset),
+        });
+        return Ok(());
+    }
+    if !status.is_success() {
+        return Err(format!("Download returned HTTP {}", status));
     }
-    std::fs::create_dir_all(&skill_dir).map_err(|e| format!("Failed to create dir: {}", e))?;
 
-    // Extract using tar command (simpler than pulling in tar/flate2 crates here)
-    let tarball_path = skills_dir.join(format!("{}.tar.gz", entry.name));
-    std::fs::write(&tarball_path, &bytes).map_err(|e| format!("Failed to write tarball: {}", e))?;
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|len| len + downloaded);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(path)
+        .map_err(|e| format!("Failed to open download file: {}", e))?;
+    on_progress(DownloadProgress { downloaded, total });
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+        on_progress(DownloadProgress { downloaded, total });
+    }
+    file.flush()
+        .map_err(|e| format!("Failed to write download: {}", e))?;
+    Ok(())
+}
 
+/// Extract a skill tarball into `dest` and check it contains `skill.toml`.
+async fn extract_tarball(tarball: &Path, dest: &Path) -> Result<(), String> {
+    // Extract using tar command (simpler than pulling in tar/flate2 crates here)
     let output = tokio::process::Command::new("tar")
         .args([
             "xzf",
-            &tarball_path.to_string_lossy(),
+            &tarball.to_string_lossy(),
             "-C",
-            &skill_dir.to_string_lossy(),
+            &dest.to_string_lossy(),
             "--strip-components=1",
         ])
         .output()
         .await
         .map_err(|e| format!("tar extract failed: {}", e))?;
 
-    // Clean up tarball
-    std::fs::remove_file(&tarball_path).ok();
-
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(format!("tar extract failed: {}", stderr));
     }
 
-    // Verify skill.toml exists
-    let manifest = skill_dir.join("skill.toml");
-    if !manifest.exists() {
-        std::fs::remove_dir_all(&skill_dir).ok();
+    if !dest.join("skill.toml").exists() {
         return Err("Extracted archive does not contain skill.toml".to_string());
     }
+    Ok(())
+}
 
-    info!(name = %entry.name, path = %skill_dir.display(), "Skill installed");
-    Ok(skill_dir)
+/// Replace `skill_dir` with the extracted `staging` directory, restoring the
+/// previous installation if the rename fails.
+fn swap_into_place(staging: &Path, skill_dir: &Path) -> Result<(), String> {
+    let backup = staging.with_extension("old");
+    if backup.exists() {
+        std::fs::remove_dir_all(&backup).ok();
+    }
+    let had_previous = skill_dir.exists();
+    if had_previous {
+        std::fs::rename(skill_dir, &backup)
+            .map_err(|e| format!("Failed to move existing install aside: {}", e))?;
+    }
+    if let Err(e) = std::fs::rename(staging, skill_dir) {
+        if had_previous {
+            std::fs::rename(&backup, skill_dir).ok();
+        }
+        std::fs::remove_dir_all(staging).ok();
+        return Err(format!("Failed to install skill: {}", e));
+    }
+    if had_previous {
+        std::fs::remove_dir_all(&backup).ok();
+    }
+    Ok(())
 }
 
 /// Remove an installed skill by name.
@@ -201,7 +306,7 @@ fn sha256_hex(data: &[u8]) -> String {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     #[test]
@@ -307,4 +412,190 @@ mod tests {
         assert_eq!(index.skills[0].name, "test-skill");
         assert_eq!(index.skills[0].tier, "t1"); // default
     }
+
+    /// Serve `body` over HTTP/1.1 with range support, recording the `Range`
+    /// header of each request. When `cut_first` is set, the first response
+    /// advertises the full length but closes after that many bytes.
+    pub(crate) async fn serve_tarball(
+        body: Vec<u8>,
+        cut_first: Option<usize>,
+    ) -> (
+        String,
+        std::sync::Arc<std::sync::Mutex<Vec<Option<String>>>>,
+    ) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/skill.tar.gz", listener.local_addr().unwrap());
+        let ranges = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+        tokio::spawn(async move {
+            let mut cut = cut_first;
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = sock.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .map(|r| r.trim_end_matches('-').parse::<usize>().unwrap());
+                seen.lock()
+                    .unwrap()
+                    .push(start.map(|s| format!("bytes={}-", s)));
+
+                let (head, slice) = match start {
+                    Some(s) => (
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n",
+                            s,
+                            body.len() - 1,
+                            body.len(),
+                            body.len() - s
+                        ),
+                        &body[s..],
+                    ),
+                    None => (
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", body.len()),
+                        &body[..],
+                    ),
+                };
+                let slice = match cut.take() {
+                    Some(at) => &slice[..at],
+                    None => slice,
+                };
+                sock.write_all(format!("{}Connection: close\r\n\r\n", head).as_bytes())
+                    .await
+                    .unwrap();
+                sock.write_all(slice).await.unwrap();
+                sock.shutdown().await.ok();
+            }
+        });
+        (url, ranges)
+    }
+
+    /// Build a skill tarball whose top-level directory holds `files`.
+    pub(crate) fn make_tarball(files: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let root = std::env::temp_dir().join(format!("ryvos_tarball_{}", uuid::Uuid::new_v4()));
+        let src = root.join("skill");
+        std::fs::create_dir_all(&src).unwrap();
+        for (name, content) in files {
+            std::fs::write(src.join(name), content).unwrap();
+        }
+        let tarball = root.join("skill.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("czf")
+            .arg(&tarball)
+            .arg("-C")
+            .arg(&root)
+            .arg("skill")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let bytes = std::fs::read(&tarball).unwrap();
+        std::fs::remove_dir_all(&root).ok();
+        bytes
+    }
+
+    /// Incompressible filler so the tarball is large enough to split.
+    fn noise(len: usize) -> Vec<u8> {
+        let mut x: u32 = 0x2545_f491;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    pub(crate) fn entry_for(name: &str, url: &str, tarball: &[u8]) -> RegistryEntry {
+        RegistryEntry {
+            name: name.into(),
+            description: "test".into(),
+            version: "1.0.0".into(),
+            author: None,
+            tarball_url: url.into(),
+            sha256: sha256_hex(tarball),
+            tier: "t1".into(),
+            tags: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn interrupted_download_resumes_from_partial_file() {
+        let tarball = make_tarball(&[
+            ("skill.toml", b"name = \"big\"\n".to_vec()),
+            ("data.bin", noise(64 * 1024)),
+        ]);
+        let (url, ranges) = serve_tarball(tarball.clone(), Some(tarball.len() / 2)).await;
+        let skills_dir =
+            std::env::temp_dir().join(format!("ryvos_skills_{}", uuid::Uuid::new_v4()));
+        let entry = entry_for("big", &url, &tarball);
+
+        assert!(install_skill(&entry, &skills_dir).await.is_err());
+        assert!(!skills_dir.join("big").exists());
+        let partial = skills_dir.join(".downloads/big-1.0.0.tar.gz.part");
+        let partial_len = std::fs::metadata(&partial).unwrap().len();
+        assert!(partial_len > 0 && partial_len < tarball.len() as u64);
+
+        let mut progress = Vec::new();
+        let path = install_skill_with_progress(&entry, &skills_dir, |p| progress.push(p))
+            .await
+            .unwrap();
+        assert!(path.join("skill.toml").exists());
+        assert_eq!(
+            std::fs::read(path.join("data.bin")).unwrap(),
+            noise(64 * 1024)
+        );
+        assert!(!partial.exists());
+
+        assert_eq!(
+            ranges.lock().unwrap().clone(),
+            vec![None, Some(format!("bytes={}-", partial_len))]
+        );
+        let total = Some(tarball.len() as u64);
+        assert_eq!(
+            progress.first(),
+            Some(&DownloadProgress {
+                downloaded: partial_len,
+                total
+            })
+        );
+        assert_eq!(
+            progress.last(),
+            Some(&DownloadProgress {
+                downloaded: tarball.len() as u64,
+                total
+            })
+        );
+        std::fs::remove_dir_all(&skills_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn failed_install_leaves_no_partial_skill_dir() {
+        let tarball = make_tarball(&[("README.md", b"no manifest here".to_vec())]);
+        let (url, _) = serve_tarball(tarball.clone(), None).await;
+        let skills_dir =
+            std::env::temp_dir().join(format!("ryvos_skills_{}", uuid::Uuid::new_v4()));
+        let entry = entry_for("broken", &url, &tarball);
+
+        let err = install_skill(&entry, &skills_dir).await.unwrap_err();
+        assert!(err.contains("skill.toml"));
+        assert!(!skills_dir.join("broken").exists());
+        assert!(!skills_dir.join(".broken.installing").exists());
+
+        // An existing install survives a failed upgrade untouched
+        let existing = skills_dir.join("broken");
+        std::fs::create_dir_all(&existing).unwrap();
+        std::fs::write(existing.join("skill.toml"), "name = \"broken\"\n").unwrap();
+        assert!(install_skill(&entry, &skills_dir).await.is_err());
+        assert_eq!(
+            std::fs::read_to_string(existing.join("skill.toml")).unwrap(),
+            "name = \"broken\"\n"
+        );
+        assert_eq!(list_installed(&skills_dir), vec!["broken".to_string()]);
+        std::fs::remove_dir_all(&skills_dir).ok();
+    }
 }
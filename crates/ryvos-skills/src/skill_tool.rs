@@ -7,7 +7,7 @@ use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
-use crate::manifest::SkillManifest;
+use crate::manifest::{InputType, SkillInput, SkillManifest, BASELINE_ENV};
 
 /// A tool backed by a shell command from a skill manifest.
 pub struct SkillTool {
@@ -18,13 +18,16 @@ pub struct SkillTool {
 
 impl SkillTool {
     pub fn new(manifest: SkillManifest, skill_dir: PathBuf) -> Result<Self> {
-        let schema: serde_json::Value =
+        let schema = if manifest.inputs.is_empty() {
             serde_json::from_str(&manifest.input_schema_json).map_err(|e| {
                 RyvosError::Config(format!(
                     "Invalid input_schema_json for skill '{}': {}",
                     manifest.name, e
                 ))
-            })?;
+            })?
+        } else {
+            inputs_schema(&manifest.inputs)
+        };
 
         Ok(Self {
             manifest,
@@ -32,6 +35,130 @@ impl SkillTool {
             schema,
         })
     }
+
+    /// Check `input` against the declared inputs. Free-form skills accept
+    /// anything.
+    fn validate(&self, input: &serde_json::Value) -> std::result::Result<(), String> {
+        let inputs = &self.manifest.inputs;
+        if inputs.is_empty() {
+            return Ok(());
+        }
+        let Some(given) = input.as_object() else {
+            return Err("input must be a JSON object".to_string());
+        };
+        if let Some(unknown) = given.keys().find(|k| !inputs.iter().any(|i| &i.name == *k)) {
+            return Err(format!("unknown input '{}'", unknown));
+        }
+        for decl in inputs {
+            match given.get(&decl.name).filter(|v| !v.is_null()) {
+                None if decl.required => {
+                    return Err(format!("missing required input '{}'", decl.name));
+                }
+                Some(value) if !decl.kind.matches(value) => {
+                    return Err(format!(
+                        "input '{}' must be of type {}",
+                        decl.name,
+                        decl.kind.as_str()
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The command's whole environment: the baseline, allowlisted and
+    /// required variables from ours, then `[env] set`.
+    fn base_env(&self) -> Vec<(String, String)> {
+        let manifest = &self.manifest;
+        let inherited = BASELINE_ENV
+            .iter()
+            .copied()
+            .chain(manifest.env.pass.iter().map(String::as_str))
+            .chain(
+                manifest
+                    .prerequisites
+                    .required_env
+                    .iter()
+                    .map(String::as_str),
+            )
+            .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)));
+        let set = manifest.env.set.iter().map(|(k, v)| (k.clone(), v.clone()));
+        inherited.chain(set).collect()
+    }
+
+    /// The command line with `$SKILL_DIR` substituted and flag inputs
+    /// appended, plus the env vars to export on top of `base_env`.
+    fn command_for(&self, input: &serde_json::Value) -> (String, Vec<(String, String)>) {
+        let mut command = self
+            .manifest
+            .command
+            .replace("$SKILL_DIR", &self.skill_dir.display().to_string());
+        let mut env = Vec::new();
+        for decl in &self.manifest.inputs {
+            let Some(value) = input.get(&decl.name).filter(|v| !v.is_null()) else {
+                continue;
+            };
+            if let Some(ref var) = decl.env {
+                env.push((var.clone(), plain_value(value)));
+            }
+            if let Some(ref flag) = decl.flag {
+                match (decl.kind, value.as_bool()) {
+                    (InputType::Boolean, Some(true)) => {
+                        command = format!("{} {}", command, shell_quote(flag));
+                    }
+                    (InputType::Boolean, _) => {}
+                    _ => {
+                        command = format!(
+                            "{} {} {}",
+                            command,
+                            shell_quote(flag),
+                            shell_quote(&plain_value(value))
+                        );
+                    }
+                }
+            }
+        }
+        (command, env)
+    }
+}
+
+/// JSON Schema for declared inputs.
+fn inputs_schema(inputs: &[SkillInput]) -> serde_json::Value {
+    let properties: serde_json::Map<String, serde_json::Value> = inputs
+        .iter()
+        .map(|input| {
+            let mut prop = serde_json::json!({ "type": input.kind.as_str() });
+            if !input.description.is_empty() {
+                prop["description"] = input.description.clone().into();
+            }
+            (input.name.clone(), prop)
+        })
+        .collect();
+    let required: Vec<&str> = inputs
+        .iter()
+        .filter(|i| i.required)
+        .map(|i| i.name.as_str())
+        .collect();
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Strings as-is, everything else as JSON text.
+fn plain_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Single-quote `s` for the shell the command runs in.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
 }
 
 impl Tool for SkillTool {
@@ -59,10 +186,16 @@ impl Tool for SkillTool {
         input: serde_json::Value,
         ctx: ToolContext,
     ) -> BoxFuture<'_, Result<ToolResult>> {
-        let command = self
-            .manifest
-            .command
-            .replace("$SKILL_DIR", &self.skill_dir.display().to_string());
+        if let Err(reason) = self.validate(&input) {
+            return Box::pin(async move {
+                Err(RyvosError::ToolValidation(format!(
+                    "{}: {}",
+                    self.manifest.name, reason
+                )))
+            });
+        }
+        let (command, input_env) = self.command_for(&input);
+        let env: Vec<(String, String)> = self.base_env().into_iter().chain(input_env).collect();
         let timeout_secs = self.manifest.timeout_secs;
         let input_bytes = serde_json::to_vec(&input).unwrap_or_default();
         let working_dir = ctx.working_dir.clone();
@@ -82,6 +215,8 @@ impl Tool for SkillTool {
                     c
                 };
                 let mut child = cmd
+                    .env_clear()
+                    .envs(env)
                     .current_dir(&working_dir)
                     .stdin(std::process::Stdio::piped())
                     .stdout(std::process::Stdio::piped())
@@ -156,6 +291,8 @@ mod tests {
             sandbox_config: None,
             config_path: None,
             viking_client: None,
+            agent_depth: 0,
+            event_bus: None,
         }
     }
 
@@ -171,6 +308,8 @@ mod tests {
                 .into(),
             tier: "t2".into(),
             prerequisites: Default::default(),
+            inputs: vec![],
+            env: Default::default(),
         };
         let tool = SkillTool::new(manifest, std::env::temp_dir()).unwrap();
         let input = serde_json::json!({"text": "hello"});
@@ -192,6 +331,8 @@ mod tests {
             input_schema_json: r#"{"type":"object"}"#.into(),
             tier: "t2".into(),
             prerequisites: Default::default(),
+            inputs: vec![],
+            env: Default::default(),
         };
         let tool = SkillTool::new(manifest, std::env::temp_dir()).unwrap();
         let result = tool
@@ -201,4 +342,126 @@ mod tests {
         assert!(result.is_error);
         assert!(result.content.contains("Exit code 42"));
     }
+
+    fn typed_tool(command: &str) -> SkillTool {
+        let manifest: SkillManifest = toml::from_str(&format!(
+            r#"
+name = "weather"
+description = "Weather"
+command = '''{}'''
+
+[[inputs]]
+name = "city"
+required = true
+description = "City name"
+env = "CITY"
+
+[[inputs]]
+name = "days"
+type = "integer"
+flag = "--days"
+
+[[inputs]]
+name = "verbose"
+type = "boolean"
+flag = "-v"
+"#,
+            command
+        ))
+        .unwrap();
+        SkillTool::new(manifest, std::env::temp_dir()).unwrap()
+    }
+
+    #[test]
+    fn declared_inputs_define_the_schema() {
+        let schema = typed_tool("cat").input_schema();
+        assert_eq!(schema["properties"]["city"]["type"], "string");
+        assert_eq!(schema["properties"]["city"]["description"], "City name");
+        assert_eq!(schema["properties"]["days"]["type"], "integer");
+        assert_eq!(schema["required"], serde_json::json!(["city"]));
+        assert_eq!(schema["additionalProperties"], false);
+    }
+
+    #[tokio::test]
+    async fn invalid_inputs_are_rejected_before_running() {
+        let tool = typed_tool("cat");
+        for (input, reason) in [
+            (
+                serde_json::json!({"days": 2}),
+                "missing required input 'city'",
+            ),
+            (
+                serde_json::json!({"city": "Oslo", "days": "two"}),
+                "input 'days' must be of type integer",
+            ),
+            (
+                serde_json::json!({"city": "Oslo", "country": "NO"}),
+                "unknown input 'country'",
+            ),
+        ] {
+            match tool.execute(input, test_ctx()).await {
+                Err(RyvosError::ToolValidation(msg)) => {
+                    assert_eq!(msg, format!("weather: {}", reason))
+                }
+                other => panic!(
+                    "expected a validation error, got {:?}",
+                    other.map(|r| r.content)
+                ),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn inputs_map_to_env_and_flags() {
+        let tool = typed_tool(r#"printf '%s|' "$CITY""#);
+        let input = serde_json::json!({"city": "St. John's", "days": 3, "verbose": true});
+        let result = tool.execute(input, test_ctx()).await.unwrap();
+        assert_eq!(result.content, "St. John's|--days|3|-v|");
+
+        let quiet = serde_json::json!({"city": "Oslo", "verbose": false});
+        let result = tool.execute(quiet, test_ctx()).await.unwrap();
+        assert_eq!(result.content, "Oslo|");
+    }
+
+    #[tokio::test]
+    async fn only_allowlisted_env_reaches_the_skill() {
+        std::env::set_var("_RYVOS_SKILL_TEST_SECRET", "leaked");
+        std::env::set_var("_RYVOS_SKILL_TEST_PASSED", "passed");
+        let manifest: SkillManifest = toml::from_str(
+            r#"
+name = "env_test"
+description = "Print the environment"
+command = "env"
+
+[env]
+pass = ["_RYVOS_SKILL_TEST_PASSED", "_RYVOS_SKILL_TEST_UNSET"]
+set = { UNITS = "metric" }
+"#,
+        )
+        .unwrap();
+        let tool = SkillTool::new(manifest, std::env::temp_dir()).unwrap();
+        let result = tool
+            .execute(serde_json::json!({}), test_ctx())
+            .await
+            .unwrap();
+
+        let vars: Vec<&str> = result
+            .content
+            .lines()
+            .filter_map(|l| l.split_once('=').map(|(k, _)| k))
+            .collect();
+        assert!(result.content.contains("_RYVOS_SKILL_TEST_PASSED=passed"));
+        assert!(result.content.contains("UNITS=metric"));
+        assert!(vars.contains(&"PATH"));
+        assert!(!vars.contains(&"_RYVOS_SKILL_TEST_SECRET"));
+        assert!(!vars.contains(&"_RYVOS_SKILL_TEST_UNSET"));
+        for var in vars {
+            assert!(
+                BASELINE_ENV.contains(&var)
+                    || ["_RYVOS_SKILL_TEST_PASSED", "UNITS", "PWD", "SHLVL", "_"].contains(&var),
+                "unexpected variable {}",
+                var
+            );
+        }
+    }
 }
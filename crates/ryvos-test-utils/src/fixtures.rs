@@ -31,6 +31,8 @@ pub fn test_tool_context() -> ToolContext {
         sandbox_config: None,
         config_path: None,
         viking_client: None,
+        agent_depth: 0,
+        event_bus: None,
     }
 }
 
@@ -44,5 +46,7 @@ pub fn test_tool_context_with_dir(dir: PathBuf) -> ToolContext {
         sandbox_config: None,
         config_path: None,
         viking_client: None,
+        agent_depth: 0,
+        event_bus: None,
     }
 }
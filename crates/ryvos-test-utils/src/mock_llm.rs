@@ -9,12 +9,18 @@ use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::LlmClient;
 use ryvos_core::types::*;
 
+/// A scripted response: the deltas to stream, or a request error message.
+type MockResponse = std::result::Result<Vec<StreamDelta>, String>;
+
 /// A mock LLM client for testing. Returns pre-configured sequences of
-/// `StreamDelta` values, and records every call for assertion.
+/// `StreamDelta` values (or request errors), and records every call for
+/// assertion.
 #[derive(Clone)]
 pub struct MockLlmClient {
-    responses: Arc<Mutex<Vec<Vec<StreamDelta>>>>,
+    responses: Arc<Mutex<Vec<MockResponse>>>,
     calls: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+    tool_names: Arc<Mutex<Vec<Vec<String>>>>,
+    configs: Arc<Mutex<Vec<ModelConfig>>>,
 }
 
 impl MockLlmClient {
@@ -24,13 +30,25 @@ impl MockLlmClient {
         Self {
             responses: Arc::new(Mutex::new(Vec::new())),
             calls: Arc::new(Mutex::new(Vec::new())),
+            tool_names: Arc::new(Mutex::new(Vec::new())),
+            configs: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
     /// Add a response sequence. Each call to `chat_stream` pops the first
     /// response from the queue. If the queue is empty, returns an error.
     pub fn with_response(self, deltas: Vec<StreamDelta>) -> Self {
-        self.responses.lock().unwrap().push(deltas);
+        self.responses.lock().unwrap().push(Ok(deltas));
+        self
+    }
+
+    /// Add a failed request: that call to `chat_stream` returns
+    /// `RyvosError::LlmRequest(message)`, as a provider's HTTP error would.
+    pub fn with_error(self, message: &str) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .push(Err(message.to_string()));
         self
     }
 
@@ -41,6 +59,7 @@ impl MockLlmClient {
             StreamDelta::Usage {
                 input_tokens: 100,
                 output_tokens: 50,
+                thinking_tokens: 0,
             },
             StreamDelta::Stop(StopReason::EndTurn),
         ])
@@ -61,6 +80,7 @@ impl MockLlmClient {
             StreamDelta::Usage {
                 input_tokens: 100,
                 output_tokens: 50,
+                thinking_tokens: 0,
             },
             StreamDelta::Stop(StopReason::ToolUse),
         ])
@@ -75,6 +95,16 @@ impl MockLlmClient {
     pub fn call_messages(&self, n: usize) -> Vec<ChatMessage> {
         self.calls.lock().unwrap()[n].clone()
     }
+
+    /// Names of the tools offered on call N (0-indexed).
+    pub fn call_tool_names(&self, n: usize) -> Vec<String> {
+        self.tool_names.lock().unwrap()[n].clone()
+    }
+
+    /// The model config sent with call N (0-indexed).
+    pub fn call_config(&self, n: usize) -> ModelConfig {
+        self.configs.lock().unwrap()[n].clone()
+    }
 }
 
 impl Default for MockLlmClient {
@@ -86,11 +116,16 @@ impl Default for MockLlmClient {
 impl LlmClient for MockLlmClient {
     fn chat_stream(
         &self,
-        _config: &ModelConfig,
+        config: &ModelConfig,
         messages: Vec<ChatMessage>,
-        _tools: &[ToolDefinition],
+        tools: &[ToolDefinition],
     ) -> BoxFuture<'_, Result<BoxStream<'_, Result<StreamDelta>>>> {
         self.calls.lock().unwrap().push(messages);
+        self.configs.lock().unwrap().push(config.clone());
+        self.tool_names
+            .lock()
+            .unwrap()
+            .push(tools.iter().map(|t| t.name.clone()).collect());
 
         let deltas = {
             let mut responses = self.responses.lock().unwrap();
@@ -103,6 +138,7 @@ impl LlmClient for MockLlmClient {
         };
 
         Box::pin(async move {
+            let deltas = deltas.map_err(RyvosError::LlmRequest)?;
             let stream = stream::iter(deltas.into_iter().map(Ok));
             Ok(stream.boxed())
         })
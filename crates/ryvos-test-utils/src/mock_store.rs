@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Mutex;
 
 use futures::future::BoxFuture;
@@ -10,12 +10,18 @@ use ryvos_core::types::{ChatMessage, SearchResult, SessionId};
 /// An in-memory session store for testing. Stores messages in a HashMap.
 pub struct InMemorySessionStore {
     data: Mutex<HashMap<String, Vec<ChatMessage>>>,
+    summaries: Mutex<HashMap<String, String>>,
+    scratchpads: Mutex<HashMap<String, BTreeMap<String, String>>>,
+    no_compact: Mutex<HashSet<String>>,
 }
 
 impl InMemorySessionStore {
     pub fn new() -> Self {
         Self {
             data: Mutex::new(HashMap::new()),
+            summaries: Mutex::new(HashMap::new()),
+            scratchpads: Mutex::new(HashMap::new()),
+            no_compact: Mutex::new(HashSet::new()),
         }
     }
 
@@ -88,6 +94,55 @@ impl SessionStore for InMemorySessionStore {
         }
         Box::pin(async move { Ok(results) })
     }
+
+    fn load_summary(&self, sid: &SessionId) -> BoxFuture<'_, Result<Option<String>>> {
+        let summary = self.summaries.lock().unwrap().get(&sid.0).cloned();
+        Box::pin(async move { Ok(summary) })
+    }
+
+    fn save_summary(&self, sid: &SessionId, summary: &str) -> BoxFuture<'_, Result<()>> {
+        self.summaries
+            .lock()
+            .unwrap()
+            .insert(sid.0.clone(), summary.to_string());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn load_scratchpad(&self, sid: &SessionId) -> BoxFuture<'_, Result<BTreeMap<String, String>>> {
+        let entries = self
+            .scratchpads
+            .lock()
+            .unwrap()
+            .get(&sid.0)
+            .cloned()
+            .unwrap_or_default();
+        Box::pin(async move { Ok(entries) })
+    }
+
+    fn set_scratchpad(&self, sid: &SessionId, key: &str, value: &str) -> BoxFuture<'_, Result<()>> {
+        self.scratchpads
+            .lock()
+            .unwrap()
+            .entry(sid.0.clone())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn load_no_compact(&self, sid: &SessionId) -> BoxFuture<'_, Result<bool>> {
+        let no_compact = self.no_compact.lock().unwrap().contains(&sid.0);
+        Box::pin(async move { Ok(no_compact) })
+    }
+
+    fn save_no_compact(&self, sid: &SessionId, no_compact: bool) -> BoxFuture<'_, Result<()>> {
+        let mut sessions = self.no_compact.lock().unwrap();
+        if no_compact {
+            sessions.insert(sid.0.clone());
+        } else {
+            sessions.remove(&sid.0);
+        }
+        Box::pin(async { Ok(()) })
+    }
 }
 
 #[cfg(test)]
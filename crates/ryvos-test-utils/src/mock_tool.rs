@@ -8,6 +8,8 @@ use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
 /// A mock tool for testing. Returns a fixed result and records every invocation.
+/// Clones share the record, so a test can keep one after registering another.
+#[derive(Clone)]
 pub struct MockTool {
     tool_name: String,
     tool_description: String,
@@ -1,18 +1,34 @@
 use futures::future::BoxFuture;
 use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
 use tracing::debug;
 
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
-pub struct ApplyPatchTool;
+use super::preview::{self, Change, StagedChanges};
+
+#[derive(Default)]
+pub struct ApplyPatchTool {
+    staged: Arc<StagedChanges>,
+}
+
+impl ApplyPatchTool {
+    /// Stage previews in `staged`, where `apply_change` finds them.
+    pub fn new(staged: Arc<StagedChanges>) -> Self {
+        Self { staged }
+    }
+}
 
 #[derive(Deserialize)]
 struct ApplyPatchInput {
     patch: String,
     #[serde(default)]
     dry_run: Option<bool>,
+    #[serde(default)]
+    preview: bool,
 }
 
 impl Tool for ApplyPatchTool {
@@ -25,7 +41,7 @@ impl Tool for ApplyPatchTool {
     }
 
     fn description(&self) -> &str {
-        "Apply a unified diff patch to files. Supports dry_run mode for validation without writing."
+        "Apply a unified diff patch to files. Supports dry_run mode for validation without writing, or preview mode that returns a change id for apply_change."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -39,6 +55,11 @@ impl Tool for ApplyPatchTool {
                 "dry_run": {
                     "type": "boolean",
                     "description": "If true, validate the patch without applying it (default: false)"
+                },
+                "preview": {
+                    "type": "boolean",
+                    "description": "Validate the patch and return a change id for apply_change without applying it (default: false)",
+                    "default": false
                 }
             },
             "required": ["patch"]
@@ -54,49 +75,31 @@ impl Tool for ApplyPatchTool {
             let params: ApplyPatchInput = serde_json::from_value(input)
                 .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
 
-            let dry_run = params.dry_run.unwrap_or(false);
-
-            debug!(dry_run, "Applying patch");
+            let dry_run = params.dry_run.unwrap_or(false) || params.preview;
 
-            let mut cmd = tokio::process::Command::new("patch");
-            cmd.arg("-p1");
-            if dry_run {
-                cmd.arg("--dry-run");
-            }
-            cmd.current_dir(&ctx.working_dir);
-            cmd.stdin(std::process::Stdio::piped());
-            cmd.stdout(std::process::Stdio::piped());
-            cmd.stderr(std::process::Stdio::piped());
+            debug!(dry_run, preview = params.preview, "Applying patch");
 
-            let mut child = cmd.spawn().map_err(|e| RyvosError::ToolExecution {
-                tool: "apply_patch".to_string(),
-                message: format!("Failed to run patch command: {}", e),
-            })?;
-
-            // Write patch to stdin
-            if let Some(mut stdin) = child.stdin.take() {
-                use tokio::io::AsyncWriteExt;
-                stdin
-                    .write_all(params.patch.as_bytes())
-                    .await
-                    .map_err(|e| RyvosError::ToolExecution {
-                        tool: "apply_patch".to_string(),
-                        message: format!("Failed to write patch to stdin: {}", e),
-                    })?;
-            }
-
-            let output = child
-                .wait_with_output()
-                .await
-                .map_err(|e| RyvosError::ToolExecution {
-                    tool: "apply_patch".to_string(),
-                    message: format!("Failed to wait for patch: {}", e),
-                })?;
+            let output = run_patch(&params.patch, &ctx.working_dir, dry_run).await?;
 
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
 
-            if output.status.success() {
+            if output.status.success() && params.preview {
+                let mut targets = Vec::new();
+                for path in preview::patch_targets(&params.patch, &ctx.working_dir) {
+                    let before = preview::read_existing(&path, "apply_patch").await?;
+                    targets.push((path, before));
+                }
+                let id = self.staged.stage(
+                    &ctx.session_id,
+                    Change::Patch {
+                        patch: params.patch.clone(),
+                        working_dir: ctx.working_dir.clone(),
+                        targets,
+                    },
+                );
+                Ok(preview::preview_result(&id, &params.patch))
+            } else if output.status.success() {
                 let prefix = if dry_run {
                     "Dry run OK"
                 } else {
@@ -120,3 +123,45 @@ impl Tool for ApplyPatchTool {
         })
     }
 }
+
+/// Run `patch -p1` in `working_dir` with `patch` on stdin.
+pub(crate) async fn run_patch(
+    patch: &str,
+    working_dir: &Path,
+    dry_run: bool,
+) -> Result<std::process::Output> {
+    let mut cmd = tokio::process::Command::new("patch");
+    cmd.arg("-p1");
+    if dry_run {
+        cmd.arg("--dry-run");
+    }
+    cmd.current_dir(working_dir);
+    cmd.stdin(std::process::Stdio::piped());
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+
+    let mut child = cmd.spawn().map_err(|e| RyvosError::ToolExecution {
+        tool: "apply_patch".to_string(),
+        message: format!("Failed to run patch command: {}", e),
+    })?;
+
+    // Write patch to stdin
+    if let Some(mut stdin) = child.stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        stdin
+            .write_all(patch.as_bytes())
+            .await
+            .map_err(|e| RyvosError::ToolExecution {
+                tool: "apply_patch".to_string(),
+                message: format!("Failed to write patch to stdin: {}", e),
+            })?;
+    }
+
+    child
+        .wait_with_output()
+        .await
+        .map_err(|e| RyvosError::ToolExecution {
+            tool: "apply_patch".to_string(),
+            message: format!("Failed to wait for patch: {}", e),
+        })
+}
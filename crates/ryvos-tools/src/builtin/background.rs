@@ -0,0 +1,550 @@
+//! Long-running processes the agent starts and comes back to.
+//!
+//! `bash` waits for its command to finish, which rules out starting a dev
+//! server and then testing it. `bg_process start` spawns the command and
+//! returns a handle at once; `status`, `logs` and `stop` take that handle.
+//! Processes belong to the session that started them and run in their own
+//! process group, so `stop` takes down the whole tree. They are killed when
+//! their session ends (`Tool::end_session`) or the tool is dropped with the
+//! registry at shutdown. Exited processes stay listed for `status` and
+//! `logs` until newer ones push them out.
+
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::security::SecurityTier;
+use ryvos_core::traits::Tool;
+use ryvos_core::types::{SessionId, ToolContext, ToolResult};
+
+/// Running processes allowed per session.
+pub const DEFAULT_MAX_PROCESSES: usize = 4;
+
+/// Exited processes kept per session; older ones are dropped.
+const MAX_EXITED: usize = 8;
+
+/// Output lines kept per process; older lines are dropped.
+const MAX_LOG_LINES: usize = 2000;
+
+/// Lines `logs` returns when the call does not say.
+const DEFAULT_TAIL_LINES: usize = 50;
+
+/// How long `stop` waits after SIGTERM before killing outright.
+const STOP_GRACE: Duration = Duration::from_secs(3);
+
+pub struct BackgroundProcessTool {
+    /// Processes by session ID, in start order.
+    sessions: Mutex<HashMap<String, Vec<BackgroundProcess>>>,
+    next_id: AtomicUsize,
+    max_processes: usize,
+}
+
+struct BackgroundProcess {
+    handle: String,
+    command: String,
+    child: Child,
+    started: Instant,
+    log: Arc<std::sync::Mutex<LogTail>>,
+}
+
+impl Drop for BackgroundProcess {
+    fn drop(&mut self) {
+        // `kill_on_drop` only reaches the shell, not what it started
+        #[cfg(unix)]
+        if let (Ok(None), Some(pid)) = (self.child.try_wait(), self.child.id()) {
+            let _ = std::process::Command::new("kill")
+                .args(["-KILL", "--", &format!("-{}", pid)])
+                .status();
+        }
+    }
+}
+
+/// Last `MAX_LOG_LINES` lines of a process's stdout and stderr.
+#[derive(Default)]
+struct LogTail {
+    lines: VecDeque<String>,
+    dropped: usize,
+}
+
+impl LogTail {
+    fn push(&mut self, line: String) {
+        if self.lines.len() == MAX_LOG_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+}
+
+impl BackgroundProcessTool {
+    pub fn new(max_processes: usize) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            next_id: AtomicUsize::new(1),
+            max_processes,
+        }
+    }
+
+    async fn start(&self, command: &str, ctx: &ToolContext) -> Result<ToolResult> {
+        if let Some(ref sandbox) = ctx.sandbox_config {
+            if sandbox.enabled && sandbox.mode == "docker" {
+                return Ok(ToolResult::error(
+                    "bg_process cannot run in the Docker sandbox; use bash instead",
+                ));
+            }
+        }
+
+        let mut sessions = self.sessions.lock().await;
+        let procs = sessions.entry(ctx.session_id.0.clone()).or_default();
+        prune(procs);
+        let running = procs.iter_mut().map(is_running).filter(|r| *r).count();
+        if running >= self.max_processes {
+            return Ok(ToolResult::error(format!(
+                "{} background processes are already running (the limit); stop one first",
+                running
+            )));
+        }
+
+        let mut cmd = Command::new("bash");
+        cmd.arg("-c")
+            .arg(command)
+            .current_dir(&ctx.working_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
+        let mut child = cmd.spawn().map_err(|e| RyvosError::ToolExecution {
+            tool: "bg_process".into(),
+            message: e.to_string(),
+        })?;
+
+        let log = Arc::new(std::sync::Mutex::new(LogTail::default()));
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(capture(stdout, log.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(capture(stderr, log.clone()));
+        }
+
+        let handle = format!("bg{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        let pid = child.id().unwrap_or_default();
+        procs.push(BackgroundProcess {
+            handle: handle.clone(),
+            command: command.to_string(),
+            child,
+            started: Instant::now(),
+            log,
+        });
+        Ok(ToolResult::success(format!(
+            "Started {} (pid {}): {}",
+            handle, pid, command
+        )))
+    }
+
+    async fn status(&self, handle: Option<&str>, ctx: &ToolContext) -> Result<ToolResult> {
+        let mut sessions = self.sessions.lock().await;
+        let procs = sessions.entry(ctx.session_id.0.clone()).or_default();
+        prune(procs);
+        let lines: Vec<String> = procs
+            .iter_mut()
+            .filter(|p| !matches!(handle, Some(h) if p.handle != h))
+            .map(describe)
+            .collect();
+        match (handle, lines.is_empty()) {
+            (Some(h), true) => Ok(unknown_handle(h)),
+            (None, true) => Ok(ToolResult::success("No background processes")),
+            _ => Ok(ToolResult::success(lines.join("\n"))),
+        }
+    }
+
+    async fn logs(&self, handle: &str, tail: usize, ctx: &ToolContext) -> Result<ToolResult> {
+        let sessions = self.sessions.lock().await;
+        let Some(proc) = sessions
+            .get(&ctx.session_id.0)
+            .and_then(|procs| procs.iter().find(|p| p.handle == handle))
+        else {
+            return Ok(unknown_handle(handle));
+        };
+        let log = proc.log.lock().unwrap();
+        if log.lines.is_empty() {
+            return Ok(ToolResult::success(format!("{}: no output yet", handle)));
+        }
+        let skip = log.lines.len().saturating_sub(tail);
+        let shown: Vec<&str> = log.lines.iter().skip(skip).map(String::as_str).collect();
+        let earlier = skip + log.dropped;
+        let mut out = String::new();
+        if earlier > 0 {
+            out.push_str(&format!("... ({} earlier lines)\n", earlier));
+        }
+        out.push_str(&shown.join("\n"));
+        Ok(ToolResult::success(out))
+    }
+
+    async fn stop(&self, handle: &str, ctx: &ToolContext) -> Result<ToolResult> {
+        let mut proc = {
+            let mut sessions = self.sessions.lock().await;
+            let procs = sessions.entry(ctx.session_id.0.clone()).or_default();
+            match procs.iter().position(|p| p.handle == handle) {
+                Some(i) => procs.remove(i),
+                None => return Ok(unknown_handle(handle)),
+            }
+        };
+        let status = terminate(&mut proc.child)
+            .await
+            .map_err(|e| RyvosError::ToolExecution {
+                tool: "bg_process".into(),
+                message: e.to_string(),
+            })?;
+        Ok(ToolResult::success(format!(
+            "Stopped {} ({}): {}",
+            handle, status, proc.command
+        )))
+    }
+}
+
+impl Default for BackgroundProcessTool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PROCESSES)
+    }
+}
+
+async fn capture(stream: impl AsyncRead + Unpin, log: Arc<std::sync::Mutex<LogTail>>) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        log.lock().unwrap().push(line);
+    }
+}
+
+fn is_running(proc: &mut BackgroundProcess) -> bool {
+    matches!(proc.child.try_wait(), Ok(None))
+}
+
+/// Drop the oldest exited processes beyond `MAX_EXITED`.
+fn prune(procs: &mut Vec<BackgroundProcess>) {
+    let exited = procs.iter_mut().map(is_running).filter(|r| !r).count();
+    let mut excess = exited.saturating_sub(MAX_EXITED);
+    procs.retain_mut(|p| {
+        let drop = excess > 0 && !is_running(p);
+        excess -= drop as usize;
+        !drop
+    });
+}
+
+fn describe(proc: &mut BackgroundProcess) -> String {
+    let state = match proc.child.try_wait() {
+        Ok(None) => format!(
+            "running for {}s, pid {}",
+            proc.started.elapsed().as_secs(),
+            proc.child.id().unwrap_or_default()
+        ),
+        Ok(Some(status)) => status.to_string(),
+        Err(e) => format!("unknown ({})", e),
+    };
+    format!("{} [{}]: {}", proc.handle, state, proc.command)
+}
+
+fn unknown_handle(handle: &str) -> ToolResult {
+    ToolResult::error(format!(
+        "No background process '{}' in this session; `status` lists them",
+        handle
+    ))
+}
+
+/// SIGTERM the process group, then kill the group if the process outlives
+/// the grace period.
+async fn terminate(child: &mut Child) -> std::io::Result<std::process::ExitStatus> {
+    if let Some(status) = child.try_wait()? {
+        return Ok(status);
+    }
+    signal_group(child, "-TERM").await;
+    match tokio::time::timeout(STOP_GRACE, child.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            signal_group(child, "-KILL").await;
+            child.kill().await?;
+            child.wait().await
+        }
+    }
+}
+
+/// Send `signal` to the process group a running child leads.
+async fn signal_group(child: &Child, signal: &str) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let group = format!("-{}", pid);
+        let _ = Command::new("kill")
+            .args([signal, "--", &group])
+            .status()
+            .await;
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum BackgroundInput {
+    Start {
+        command: String,
+    },
+    Status {
+        #[serde(default)]
+        handle: Option<String>,
+    },
+    Logs {
+        handle: String,
+        #[serde(default = "default_tail")]
+        lines: usize,
+    },
+    Stop {
+        handle: String,
+    },
+}
+
+fn default_tail() -> usize {
+    DEFAULT_TAIL_LINES
+}
+
+impl Tool for BackgroundProcessTool {
+    fn name(&self) -> &str {
+        "bg_process"
+    }
+
+    fn tier(&self) -> SecurityTier {
+        SecurityTier::T2
+    }
+
+    fn description(&self) -> &str {
+        "Run a long-lived command (dev server, watcher) in the background. `start` returns a \
+         handle at once; use `status`, `logs` (recent output) and `stop` with that handle."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["start", "status", "logs", "stop"]
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Bash command to start (start)"
+                },
+                "handle": {
+                    "type": "string",
+                    "description": "Handle returned by start, e.g. bg1 (status, logs, stop); status without one lists every process"
+                },
+                "lines": {
+                    "type": "integer",
+                    "description": "Output lines to return (logs, default 50)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(
+        &self,
+        input: serde_json::Value,
+        ctx: ToolContext,
+    ) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async move {
+            let input: BackgroundInput = serde_json::from_value(input)
+                .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
+            match input {
+                BackgroundInput::Start { command } => self.start(&command, &ctx).await,
+                BackgroundInput::Status { handle } => self.status(handle.as_deref(), &ctx).await,
+                BackgroundInput::Logs { handle, lines } => self.logs(&handle, lines, &ctx).await,
+                BackgroundInput::Stop { handle } => self.stop(&handle, &ctx).await,
+            }
+        })
+    }
+
+    fn timeout_secs(&self) -> u64 {
+        // `stop` may wait out the grace period
+        STOP_GRACE.as_secs() + 10
+    }
+
+    fn end_session(&self, session_id: &SessionId) -> BoxFuture<'_, ()> {
+        let session = session_id.0.clone();
+        Box::pin(async move {
+            let procs = self.sessions.lock().await.remove(&session);
+            let stops = procs.into_iter().flatten().map(|mut proc| async move {
+                if let Err(e) = terminate(&mut proc.child).await {
+                    warn!(handle = %proc.handle, error = %e, "Failed to stop background process");
+                }
+            });
+            futures::future::join_all(stops).await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_test_utils::test_tool_context_with_dir;
+    use serde_json::json;
+
+    fn ctx() -> ToolContext {
+        test_tool_context_with_dir(std::env::temp_dir())
+    }
+
+    async fn run(tool: &BackgroundProcessTool, input: serde_json::Value) -> ToolResult {
+        tool.execute(input, ctx()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn start_logs_and_stop() {
+        let tool = BackgroundProcessTool::default();
+        let script = "echo ready; echo warn >&2; while true; do sleep 0.1; done";
+        let started = run(&tool, json!({ "action": "start", "command": script })).await;
+        assert!(!started.is_error, "{}", started.content);
+        assert!(started.content.starts_with("Started bg1 (pid "));
+
+        // Output is captured while the process keeps running
+        let mut logs = String::new();
+        for _ in 0..50 {
+            logs = run(&tool, json!({ "action": "logs", "handle": "bg1" }))
+                .await
+                .content;
+            if logs.contains("ready") && logs.contains("warn") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(logs.contains("ready") && logs.contains("warn"), "{}", logs);
+        let status = run(&tool, json!({ "action": "status" })).await;
+        assert!(
+            status.content.starts_with("bg1 [running for"),
+            "{}",
+            status.content
+        );
+
+        let stopped = run(&tool, json!({ "action": "stop", "handle": "bg1" })).await;
+        assert!(
+            stopped.content.starts_with("Stopped bg1 ("),
+            "{}",
+            stopped.content
+        );
+        let gone = run(&tool, json!({ "action": "status", "handle": "bg1" })).await;
+        assert!(gone.is_error);
+    }
+
+    /// Start a shell that backgrounds a `sleep` and reports its pid.
+    async fn start_with_grandchild(tool: &BackgroundProcessTool, ctx: ToolContext) -> u32 {
+        let script = "sleep 300 & echo $!; wait";
+        let input = json!({ "action": "start", "command": script });
+        let started = tool.execute(input, ctx.clone()).await.unwrap();
+        assert!(!started.is_error, "{}", started.content);
+        let handle = started.content["Started ".len()..]
+            .split(' ')
+            .next()
+            .unwrap();
+        for _ in 0..50 {
+            let input = json!({ "action": "logs", "handle": handle });
+            let logs = tool.execute(input, ctx.clone()).await.unwrap().content;
+            if let Ok(pid) = logs.trim().parse() {
+                return pid;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("{} never printed its child's pid", handle);
+    }
+
+    /// Whether a process exists and is not a zombie.
+    #[cfg(target_os = "linux")]
+    async fn alive(pid: u32) -> bool {
+        for _ in 0..50 {
+            match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+                Ok(stat) if !stat.contains(") Z") => {}
+                _ => return false,
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        true
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropping_the_tool_kills_the_process_group() {
+        let tool = BackgroundProcessTool::default();
+        let pid = start_with_grandchild(&tool, ctx()).await;
+        drop(tool);
+        assert!(!alive(pid).await, "sleep {} outlived the tool", pid);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn ending_a_session_stops_only_its_processes() {
+        let tool = BackgroundProcessTool::default();
+        let mut other = ctx();
+        other.session_id = ryvos_core::types::SessionId::from_string("other");
+        let ended = start_with_grandchild(&tool, ctx()).await;
+        let kept = start_with_grandchild(&tool, other.clone()).await;
+
+        tool.end_session(&ctx().session_id).await;
+        assert!(!alive(ended).await);
+        let status = run(&tool, json!({ "action": "status" })).await;
+        assert_eq!(status.content, "No background processes");
+
+        let input = json!({ "action": "status", "handle": "bg2" });
+        let status = tool.execute(input, other.clone()).await.unwrap();
+        assert!(
+            status.content.contains("[running for"),
+            "{}",
+            status.content
+        );
+        tool.end_session(&other.session_id).await;
+        assert!(!alive(kept).await);
+    }
+
+    #[tokio::test]
+    async fn exited_processes_are_pruned() {
+        let tool = BackgroundProcessTool::new(MAX_EXITED + 3);
+        for _ in 0..MAX_EXITED + 3 {
+            let started = run(&tool, json!({ "action": "start", "command": "true" })).await;
+            assert!(!started.is_error, "{}", started.content);
+        }
+        let mut status = String::new();
+        for _ in 0..50 {
+            status = run(&tool, json!({ "action": "status" })).await.content;
+            if !status.contains("running") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(status.lines().count(), MAX_EXITED, "{}", status);
+        assert!(status.starts_with("bg4 ["), "{}", status);
+    }
+
+    #[tokio::test]
+    async fn running_processes_are_limited_per_session() {
+        let tool = BackgroundProcessTool::new(1);
+        let sleep = json!({ "action": "start", "command": "sleep 30" });
+        assert!(!run(&tool, sleep.clone()).await.is_error);
+        let refused = run(&tool, sleep.clone()).await;
+        assert!(refused.is_error);
+        assert!(refused.content.contains("limit"), "{}", refused.content);
+
+        // Other sessions have their own processes and limit
+        let mut other = ctx();
+        other.session_id = ryvos_core::types::SessionId::from_string("other");
+        let started = tool.execute(sleep, other).await.unwrap();
+        assert!(!started.is_error, "{}", started.content);
+        let logs = run(&tool, json!({ "action": "logs", "handle": "bg2" })).await;
+        assert!(logs.is_error);
+
+        run(&tool, json!({ "action": "stop", "handle": "bg1" })).await;
+    }
+}
@@ -8,6 +8,8 @@ use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
+use super::exec::{run_streaming, MAX_CAPTURED_BYTES};
+
 pub struct BashTool;
 
 #[derive(Deserialize)]
@@ -193,38 +195,33 @@ impl Tool for BashTool {
                 }
             }
 
-            // Unsandboxed execution (existing behavior)
+            // Unsandboxed execution, streaming output as it arrives
             let timeout = std::time::Duration::from_secs(params.timeout);
-            let result = tokio::time::timeout(timeout, async {
-                tokio::process::Command::new("bash")
-                    .arg("-c")
-                    .arg(&params.command)
-                    .current_dir(&ctx.working_dir)
-                    .output()
-                    .await
-            })
-            .await;
+            let mut command = tokio::process::Command::new("bash");
+            command
+                .arg("-c")
+                .arg(&params.command)
+                .current_dir(&ctx.working_dir);
+            let result = tokio::time::timeout(timeout, run_streaming(command, "bash", &ctx)).await;
 
             match result {
                 Ok(Ok(output)) => {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-
-                    let mut content = String::new();
-                    if !stdout.is_empty() {
-                        content.push_str(&stdout);
-                    }
-                    if !stderr.is_empty() {
+                    let mut content = output.stdout;
+                    if !output.stderr.is_empty() {
                         if !content.is_empty() {
                             content.push('\n');
                         }
                         content.push_str("STDERR:\n");
-                        content.push_str(&stderr);
+                        content.push_str(&output.stderr);
                     }
 
                     // Truncate if too long
-                    if content.len() > 30000 {
-                        content.truncate(30000);
+                    if output.truncated || content.len() > MAX_CAPTURED_BYTES {
+                        let mut cut = content.len().min(MAX_CAPTURED_BYTES);
+                        while !content.is_char_boundary(cut) {
+                            cut -= 1;
+                        }
+                        content.truncate(cut);
                         content.push_str("\n... (output truncated)");
                     }
 
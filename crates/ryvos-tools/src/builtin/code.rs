@@ -8,6 +8,8 @@ use ryvos_core::security::SecurityTier;
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
+use super::exec::run_streaming;
+
 fn resolve(p: &str, wd: &std::path::Path) -> PathBuf {
     let path = PathBuf::from(p);
     if path.is_absolute() {
@@ -223,20 +225,18 @@ impl Tool for TestRunTool {
             } else {
                 return Ok(ToolResult::error("Cannot detect project type. No Cargo.toml, package.json, pyproject.toml, go.mod, or Makefile found."));
             };
-            let output = tokio::process::Command::new(cmd)
-                .args(args)
-                .current_dir(wd)
-                .output()
+            let mut command = tokio::process::Command::new(cmd);
+            command.args(args).current_dir(wd);
+            let output = run_streaming(command, "test_run", &ctx)
                 .await
                 .map_err(|e| RyvosError::ToolExecution {
                     tool: "test_run".into(),
                     message: e.to_string(),
                 })?;
-            let combined = format!(
-                "{}{}",
-                String::from_utf8_lossy(&output.stdout),
-                String::from_utf8_lossy(&output.stderr)
-            );
+            let mut combined = format!("{}{}", output.stdout, output.stderr);
+            if output.truncated {
+                combined.push_str("\n... (output truncated)");
+            }
             if output.status.success() {
                 Ok(ToolResult::success(format!("Tests passed.\n{}", combined)))
             } else {
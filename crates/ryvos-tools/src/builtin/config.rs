@@ -0,0 +1,356 @@
+//! `config_get` and `config_set`: the agent reading and tuning its own config.
+//!
+//! Both go through the runtime's [`ConfigOverrides`], which only knows the
+//! keys in [`MUTABLE_KEYS`] and refuses security settings and credentials.
+//! A change applies to the current session from its next run on; with
+//! `persist`, it is also written to the config file with `toml_edit`, so
+//! comments and the rest of the file are kept.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::Value;
+use toml_edit::{value, DocumentMut, InlineTable, Item, Table};
+
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::overrides::{ConfigOverrides, MUTABLE_KEYS};
+use ryvos_core::security::SecurityTier;
+use ryvos_core::traits::Tool;
+use ryvos_core::types::{ToolContext, ToolResult};
+
+/// The allowlist with what each key controls, for the input schemas.
+fn key_list() -> String {
+    MUTABLE_KEYS
+        .iter()
+        .map(|(key, what)| format!("{}: {}", key, what))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// ── ConfigGetTool ───────────────────────────────────────────────
+
+pub struct ConfigGetTool {
+    overrides: Arc<ConfigOverrides>,
+}
+
+impl ConfigGetTool {
+    pub fn new(overrides: Arc<ConfigOverrides>) -> Self {
+        Self { overrides }
+    }
+}
+
+#[derive(Deserialize)]
+struct GetInput {
+    #[serde(default)]
+    key: Option<String>,
+}
+
+impl Tool for ConfigGetTool {
+    fn name(&self) -> &str {
+        "config_get"
+    }
+
+    fn tier(&self) -> SecurityTier {
+        SecurityTier::T0
+    }
+
+    fn description(&self) -> &str {
+        "Read your own runtime config for this session. Without a key, lists every key you may change with its current value."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "key": { "type": "string", "description": format!("Key to read ({})", key_list()) }
+            }
+        })
+    }
+
+    fn execute(&self, input: Value, ctx: ToolContext) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async move {
+            let input: GetInput = serde_json::from_value(input)
+                .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
+            let sid = &ctx.session_id;
+            let Some(key) = input.key else {
+                let lines: Vec<String> = MUTABLE_KEYS
+                    .iter()
+                    .map(|(key, _)| {
+                        let current = self.overrides.get(sid, key).unwrap_or(Value::Null);
+                        format!("{} = {}", key, current)
+                    })
+                    .collect();
+                return Ok(ToolResult::success(lines.join("\n")));
+            };
+            Ok(match self.overrides.get(sid, &key) {
+                Ok(current) => ToolResult::success(format!("{} = {}", key, current)),
+                Err(e) => ToolResult::error(e.to_string()),
+            })
+        })
+    }
+}
+
+// ── ConfigSetTool ───────────────────────────────────────────────
+
+pub struct ConfigSetTool {
+    overrides: Arc<ConfigOverrides>,
+    /// Config file `persist` writes to; `None` allows session changes only.
+    config_path: Option<PathBuf>,
+}
+
+impl ConfigSetTool {
+    pub fn new(overrides: Arc<ConfigOverrides>, config_path: Option<PathBuf>) -> Self {
+        Self {
+            overrides,
+            config_path,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SetInput {
+    key: String,
+    value: Value,
+    #[serde(default)]
+    persist: bool,
+}
+
+impl Tool for ConfigSetTool {
+    fn name(&self) -> &str {
+        "config_set"
+    }
+
+    fn tier(&self) -> SecurityTier {
+        SecurityTier::T2
+    }
+
+    fn description(&self) -> &str {
+        "Change your own runtime config for this session, from the next run on. Only a few keys \
+         may be changed; security settings and credentials never can. Set `persist` to also \
+         save the value to the config file."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "key": { "type": "string", "description": format!("Key to set ({})", key_list()) },
+                "value": { "description": "New value, e.g. 40, 0.2, \"high\" or {\"budget_tokens\": 8000}" },
+                "persist": { "type": "boolean", "description": "Also write it to the config file (default: false)" }
+            },
+            "required": ["key", "value"]
+        })
+    }
+
+    fn execute(&self, input: Value, ctx: ToolContext) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async move {
+            let input: SetInput = serde_json::from_value(input)
+                .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
+            let sid = &ctx.session_id;
+            if let Err(e) = self.overrides.set(sid, &input.key, &input.value) {
+                return Ok(ToolResult::error(e.to_string()));
+            }
+            let current = self.overrides.get(sid, &input.key)?;
+            if !input.persist {
+                return Ok(ToolResult::success(format!(
+                    "{} = {} for this session",
+                    input.key, current
+                )));
+            }
+            let Some(ref path) = self.config_path else {
+                return Ok(ToolResult::error(format!(
+                    "{} = {} for this session, but there is no config file to save it to",
+                    input.key, current
+                )));
+            };
+            persist(path, &input.key, &current)?;
+            Ok(ToolResult::success(format!(
+                "{} = {} for this session and saved to {}",
+                input.key,
+                current,
+                path.display()
+            )))
+        })
+    }
+}
+
+/// Write `key` (`section.field`) to the config file at `path`.
+fn persist(path: &Path, key: &str, current: &Value) -> Result<()> {
+    let content = std::fs::read_to_string(path)?;
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e| RyvosError::Config(format!("{}: {}", path.display(), e)))?;
+    let (section, field) = key.split_once('.').unwrap_or(("", key));
+    let item = match current {
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => value(i),
+            None => value(float_for_toml(n.as_f64().unwrap_or_default())),
+        },
+        Value::String(s) => value(s.as_str()),
+        Value::Object(map) => {
+            let mut table = InlineTable::new();
+            for (k, v) in map {
+                let Some(i) = v.as_i64() else {
+                    return Err(RyvosError::Config(format!(
+                        "cannot save {} as `{}` of '{}'",
+                        v, k, key
+                    )));
+                };
+                table.insert(k, i.into());
+            }
+            value(table)
+        }
+        other => {
+            return Err(RyvosError::Config(format!(
+                "cannot save {} for '{}'",
+                other, key
+            )))
+        }
+    };
+    doc.entry(section)
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .ok_or_else(|| RyvosError::Config(format!("`{}` in the config is not a table", section)))?
+        .insert(field, item);
+    std::fs::write(path, doc.to_string())?;
+    Ok(())
+}
+
+/// `n` as written to the config file. A float that is an `f32` widened to
+/// `f64`, as `model.temperature` is, is written as the `f32` prints, so 0.2
+/// is saved as 0.2 rather than 0.20000000298023224.
+fn float_for_toml(n: f64) -> f64 {
+    let narrow = n as f32;
+    if narrow as f64 == n {
+        narrow.to_string().parse().unwrap_or(n)
+    } else {
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::config::AppConfig;
+    use ryvos_test_utils::test_tool_context;
+    use serde_json::json;
+
+    fn overrides() -> Arc<ConfigOverrides> {
+        let config: AppConfig =
+            toml::from_str("[model]\nmodel_id = \"claude-sonnet-4-20250514\"\n").unwrap();
+        Arc::new(ConfigOverrides::new(&config))
+    }
+
+    #[tokio::test]
+    async fn allowlisted_keys_are_read_and_set() {
+        let overrides = overrides();
+        let get = ConfigGetTool::new(overrides.clone());
+        let set = ConfigSetTool::new(overrides.clone(), None);
+        let ctx = test_tool_context();
+
+        let result = get
+            .execute(json!({ "key": "agent.max_turns" }), ctx.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.content, "agent.max_turns = 25");
+
+        let result = set
+            .execute(
+                json!({ "key": "agent.max_turns", "value": 40 }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(overrides.for_session(&ctx.session_id).max_turns, Some(40));
+        let listed = get.execute(json!({}), ctx).await.unwrap();
+        assert!(listed.content.contains("agent.max_turns = 40"));
+        assert!(listed.content.contains("model.thinking = \"off\""));
+    }
+
+    #[tokio::test]
+    async fn credentials_and_security_are_refused() {
+        let overrides = overrides();
+        let get = ConfigGetTool::new(overrides.clone());
+        let set = ConfigSetTool::new(overrides.clone(), None);
+        let ctx = test_tool_context();
+
+        let result = set
+            .execute(
+                json!({ "key": "model.api_key", "value": "sk-x" }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.content.contains("off limits"));
+        let result = get
+            .execute(json!({ "key": "model.api_key" }), ctx.clone())
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        let result = set
+            .execute(
+                json!({ "key": "security.deny_above", "value": "t4" }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert_eq!(overrides.for_session(&ctx.session_id), Default::default());
+    }
+
+    #[tokio::test]
+    async fn persist_keeps_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ryvos.toml");
+        std::fs::write(
+            &path,
+            "# my config\n[model]\nmodel_id = \"claude-sonnet-4-20250514\" # main\n",
+        )
+        .unwrap();
+        let set = ConfigSetTool::new(overrides(), Some(path.clone()));
+        let ctx = test_tool_context();
+
+        for (key, value) in [
+            ("model.thinking", json!({ "budget_tokens": 8000 })),
+            ("agent.max_turns", json!(40)),
+            ("model.temperature", json!(0.2)),
+        ] {
+            let result = set
+                .execute(
+                    json!({ "key": key, "value": value, "persist": true }),
+                    ctx.clone(),
+                )
+                .await
+                .unwrap();
+            assert!(!result.is_error, "{}", result.content);
+        }
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("temperature = 0.2\n"), "{}", written);
+        assert!(written.starts_with("# my config\n"));
+        assert!(written.contains("# main"));
+        let config: AppConfig = toml::from_str(&written).unwrap();
+        assert_eq!(config.agent.max_turns, 40);
+        assert_eq!(
+            config.model.thinking,
+            ryvos_core::types::ThinkingLevel::Custom(8000)
+        );
+    }
+
+    #[test]
+    fn persist_refuses_values_it_cannot_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ryvos.toml");
+        std::fs::write(&path, "[model]\nmodel_id = \"claude-sonnet-4-20250514\"\n").unwrap();
+
+        let nested = json!({ "budget_tokens": 8000, "mode": "deep" });
+        assert!(persist(&path, "model.thinking", &nested).is_err());
+        assert!(persist(&path, "agent.max_turns", &json!(true)).is_err());
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("thinking"));
+    }
+}
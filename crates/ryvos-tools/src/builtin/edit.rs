@@ -1,13 +1,26 @@
 use futures::future::BoxFuture;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::debug;
 
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
-pub struct EditTool;
+use super::preview::{self, Change, StagedChanges};
+
+#[derive(Default)]
+pub struct EditTool {
+    staged: Arc<StagedChanges>,
+}
+
+impl EditTool {
+    /// Stage previews in `staged`, where `apply_change` finds them.
+    pub fn new(staged: Arc<StagedChanges>) -> Self {
+        Self { staged }
+    }
+}
 
 #[derive(Deserialize)]
 struct EditInput {
@@ -16,6 +29,8 @@ struct EditInput {
     new_string: String,
     #[serde(default)]
     replace_all: bool,
+    #[serde(default)]
+    preview: bool,
 }
 
 impl Tool for EditTool {
@@ -28,7 +43,7 @@ impl Tool for EditTool {
     }
 
     fn description(&self) -> &str {
-        "Perform exact string replacements in files. The old_string must be unique in the file unless replace_all is true."
+        "Perform exact string replacements in files. The old_string must be unique in the file unless replace_all is true. With preview: true, returns a diff and a change id for apply_change instead of writing."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -51,6 +66,11 @@ impl Tool for EditTool {
                     "type": "boolean",
                     "description": "Replace all occurrences (default: false)",
                     "default": false
+                },
+                "preview": {
+                    "type": "boolean",
+                    "description": "Return a unified diff and a change id without writing (default: false)",
+                    "default": false
                 }
             },
             "required": ["file_path", "old_string", "new_string"]
@@ -104,6 +124,19 @@ impl Tool for EditTool {
                 content.replacen(&params.old_string, &params.new_string, 1)
             };
 
+            if params.preview {
+                let diff = preview::unified_diff(&path, Some(&content), &new_content);
+                let id = self.staged.stage(
+                    &ctx.session_id,
+                    Change::File {
+                        path,
+                        before: Some(content),
+                        after: new_content,
+                    },
+                );
+                return Ok(preview::preview_result(&id, &diff));
+            }
+
             tokio::fs::write(&path, &new_content)
                 .await
                 .map_err(|e| RyvosError::ToolExecution {
@@ -144,7 +177,7 @@ mod tests {
         std::fs::write(&file_path, "hello world\ngoodbye world\n").unwrap();
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = EditTool;
+        let tool = EditTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "old_string": "hello world",
@@ -165,7 +198,7 @@ mod tests {
         std::fs::write(&file_path, "foo bar foo baz foo\n").unwrap();
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = EditTool;
+        let tool = EditTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "old_string": "foo",
@@ -187,7 +220,7 @@ mod tests {
         std::fs::write(&file_path, "aaa bbb aaa\n").unwrap();
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = EditTool;
+        let tool = EditTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "old_string": "aaa",
@@ -205,7 +238,7 @@ mod tests {
         std::fs::write(&file_path, "some content").unwrap();
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = EditTool;
+        let tool = EditTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "old_string": "nonexistent",
@@ -223,7 +256,7 @@ mod tests {
         std::fs::write(&file_path, "hello").unwrap();
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = EditTool;
+        let tool = EditTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "old_string": "hello",
@@ -0,0 +1,259 @@
+//! Running a command while streaming its output.
+//!
+//! [`run_streaming`] captures stdout and stderr like `Command::output`, but
+//! also publishes what the process prints as `ToolProgress` events while it
+//! runs, so a long build or test run shows output before it finishes.
+
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use ryvos_core::types::{AgentEvent, ToolContext};
+
+/// Bytes kept per stream; output past this is dropped and flagged.
+pub(crate) const MAX_CAPTURED_BYTES: usize = 30000;
+
+/// Minimum time between progress events, so chatty commands are batched.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A progress event is sent early once this much output is pending.
+const MAX_PROGRESS_CHUNK: usize = 4096;
+
+/// A line longer than this is passed on in pieces, so output without
+/// newlines cannot grow the line buffers without bound.
+const MAX_LINE_BYTES: usize = MAX_PROGRESS_CHUNK;
+
+/// Captured output of a finished command.
+pub(crate) struct StreamedOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub status: ExitStatus,
+    /// Some output went past `MAX_CAPTURED_BYTES` and was dropped.
+    pub truncated: bool,
+}
+
+/// Output of one stream, bounded to `MAX_CAPTURED_BYTES`.
+#[derive(Default)]
+struct Capture {
+    text: String,
+    truncated: bool,
+}
+
+impl Capture {
+    fn push(&mut self, chunk: &str) {
+        let room = MAX_CAPTURED_BYTES.saturating_sub(self.text.len());
+        if chunk.len() <= room {
+            self.text.push_str(chunk);
+            return;
+        }
+        let mut cut = room;
+        while !chunk.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        self.text.push_str(&chunk[..cut]);
+        self.truncated = true;
+    }
+}
+
+/// Like `read_until(b'\n', buf)`, but also returns once `buf` holds
+/// `MAX_LINE_BYTES`. Cancel-safe: bytes are moved into `buf` as soon as
+/// they are consumed from `reader`.
+async fn read_line_capped<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(read);
+        }
+        let room = MAX_LINE_BYTES.saturating_sub(buf.len()).max(1);
+        let window = &available[..available.len().min(room)];
+        let (used, done) = match window.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (window.len(), buf.len() + window.len() >= MAX_LINE_BYTES),
+        };
+        buf.extend_from_slice(&window[..used]);
+        reader.consume(used);
+        read += used;
+        if done {
+            return Ok(read);
+        }
+    }
+}
+
+/// Take the text read so far out of `buf`. Unless at end of stream, an
+/// incomplete UTF-8 sequence at the end (a piece of a long line cut
+/// mid-character) stays in `buf` for the next read.
+fn take_text(buf: &mut Vec<u8>, eof: bool) -> String {
+    let keep = match std::str::from_utf8(buf) {
+        Err(e) if !eof && e.error_len().is_none() => buf.len() - e.valid_up_to(),
+        _ => 0,
+    };
+    let tail = buf.split_off(buf.len() - keep);
+    let text = String::from_utf8_lossy(buf).into_owned();
+    *buf = tail;
+    text
+}
+
+/// Run `command` to completion as tool `tool`, publishing its output on
+/// `ctx.event_bus` as it arrives. The child is killed if the returned
+/// future is dropped (e.g. on timeout).
+pub(crate) async fn run_streaming(
+    mut command: Command,
+    tool: &str,
+    ctx: &ToolContext,
+) -> std::io::Result<StreamedOutput> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut stderr = BufReader::new(child.stderr.take().expect("stderr is piped"));
+
+    let (mut out, mut err) = (Capture::default(), Capture::default());
+    let (mut out_line, mut err_line) = (Vec::new(), Vec::new());
+    let (mut out_open, mut err_open) = (true, true);
+    let mut pending = String::new();
+    let mut last_sent: Option<Instant> = None;
+
+    let flush = |pending: &mut String, last_sent: &mut Option<Instant>| {
+        if pending.is_empty() {
+            return;
+        }
+        let chunk = std::mem::take(pending);
+        if let Some(ref bus) = ctx.event_bus {
+            bus.publish(AgentEvent::ToolProgress {
+                name: tool.to_string(),
+                chunk,
+            });
+        }
+        *last_sent = Some(Instant::now());
+    };
+
+    while out_open || err_open {
+        // Partial lines stay in the buffers across iterations, so losing
+        // a read_until race in select! drops nothing
+        tokio::select! {
+            read = read_line_capped(&mut stdout, &mut out_line), if out_open => {
+                if read? == 0 {
+                    out_open = false;
+                }
+                let line = take_text(&mut out_line, !out_open);
+                out.push(&line);
+                pending.push_str(&line);
+            }
+            read = read_line_capped(&mut stderr, &mut err_line), if err_open => {
+                if read? == 0 {
+                    err_open = false;
+                }
+                let line = take_text(&mut err_line, !err_open);
+                err.push(&line);
+                pending.push_str(&line);
+            }
+            _ = tokio::time::sleep(PROGRESS_INTERVAL), if !pending.is_empty() => {}
+        }
+        let due = !matches!(last_sent, Some(t) if t.elapsed() < PROGRESS_INTERVAL);
+        if due || pending.len() >= MAX_PROGRESS_CHUNK {
+            flush(&mut pending, &mut last_sent);
+        }
+    }
+    flush(&mut pending, &mut last_sent);
+
+    let status = child.wait().await?;
+    Ok(StreamedOutput {
+        truncated: out.truncated || err.truncated,
+        stdout: out.text,
+        stderr: err.text,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::event::EventBus;
+    use ryvos_test_utils::test_tool_context_with_dir;
+    use std::sync::Arc;
+
+    fn bash(script: &str) -> Command {
+        let mut command = Command::new("bash");
+        command.arg("-c").arg(script);
+        command
+    }
+
+    #[tokio::test]
+    async fn output_streams_while_command_runs() {
+        let bus = Arc::new(EventBus::default());
+        let mut rx = bus.subscribe();
+        let mut ctx = test_tool_context_with_dir(std::env::temp_dir());
+        ctx.event_bus = Some(bus.clone());
+
+        let script = "for i in 1 2 3; do echo line$i; sleep 0.3; done; echo oops >&2";
+        let output = run_streaming(bash(script), "bash", &ctx).await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, "line1\nline2\nline3\n");
+        assert_eq!(output.stderr, "oops\n");
+
+        let mut chunks = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::ToolProgress { name, chunk } = event {
+                assert_eq!(name, "bash");
+                chunks.push(chunk);
+            }
+        }
+        assert!(chunks.len() >= 3, "expected several chunks: {:?}", chunks);
+        assert_eq!(chunks[0], "line1\n");
+        assert_eq!(chunks.concat(), "line1\nline2\nline3\noops\n");
+    }
+
+    #[tokio::test]
+    async fn captured_output_is_bounded() {
+        let ctx = test_tool_context_with_dir(std::env::temp_dir());
+        let output = run_streaming(bash("yes ryvos | head -c 100000"), "bash", &ctx)
+            .await
+            .unwrap();
+        assert!(output.truncated);
+        assert_eq!(output.stdout.len(), MAX_CAPTURED_BYTES);
+    }
+
+    #[tokio::test]
+    async fn long_line_streams_in_bounded_pieces() {
+        let bus = Arc::new(EventBus::new(1024));
+        let mut rx = bus.subscribe();
+        let mut ctx = test_tool_context_with_dir(std::env::temp_dir());
+        ctx.event_bus = Some(bus.clone());
+
+        // 10000 two-byte characters and no newline at all
+        let script = "printf 'é%.0s' $(seq 10000)";
+        let output = run_streaming(bash(script), "bash", &ctx).await.unwrap();
+        assert_eq!(output.stdout, "é".repeat(10000));
+
+        let mut chunks = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::ToolProgress { chunk, .. } = event {
+                chunks.push(chunk);
+            }
+        }
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|c| c.len() <= MAX_PROGRESS_CHUNK + MAX_LINE_BYTES));
+        assert_eq!(chunks.concat(), output.stdout);
+    }
+
+    #[test]
+    fn cut_character_waits_for_its_remaining_bytes() {
+        let mut buf = "aé".as_bytes()[..2].to_vec();
+        assert_eq!(take_text(&mut buf, false), "a");
+        assert_eq!(buf, vec![0xc3]);
+        buf.push(0xa9);
+        assert_eq!(take_text(&mut buf, false), "é");
+        assert!(buf.is_empty());
+    }
+}
@@ -1,10 +1,13 @@
 pub mod apply_patch;
+pub mod background;
 pub mod bash;
 pub mod browser;
 pub mod code;
+pub mod config;
 pub mod data;
 pub mod database;
 pub mod edit;
+mod exec;
 pub mod filesystem;
 pub mod git;
 pub mod glob;
@@ -18,8 +21,10 @@ pub mod memory_write;
 pub mod network;
 pub mod notification;
 pub mod notion;
+pub mod preview;
 pub mod read;
 pub mod scheduling;
+pub mod scratchpad;
 pub mod sessions;
 pub mod spawn_agent;
 pub mod system;
@@ -0,0 +1,659 @@
+//! Previewed file changes awaiting confirmation.
+//!
+//! `write`, `edit`, and `apply_patch` accept `preview: true`. Instead of
+//! touching the disk they stage the intended change here under a change id
+//! and return it as a unified diff, so the model or an approval step can
+//! look at it first. `apply_change` with that id then makes exactly the
+//! previewed change. A file edited in the meantime is refused rather than
+//! overwritten.
+//!
+//! Staged changes live in a [`StagedChanges`] that the registry shares
+//! between the four tools. They are kept per session, expire after
+//! [`PENDING_TTL`], and are single-use. `apply_change` describes its input
+//! with the staged change's files and diff, so the security gate and the
+//! audit trail see what a call writes.
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::security::SecurityTier;
+use ryvos_core::traits::Tool;
+use ryvos_core::types::{SessionId, ToolContext, ToolResult};
+
+/// How long a previewed change can still be applied.
+const PENDING_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Staged changes kept per session; the session's oldest are dropped first.
+const MAX_PENDING: usize = 64;
+
+/// A change previewed but not yet applied.
+#[derive(Debug, Clone)]
+pub(crate) enum Change {
+    /// Replace a file's content. `before` is `None` when the file did not
+    /// exist at preview time.
+    File {
+        path: PathBuf,
+        before: Option<String>,
+        after: String,
+    },
+    /// A unified diff that passed `patch --dry-run` in `working_dir`, with
+    /// the content of each file it touches at preview time (`None` for a
+    /// file that did not exist).
+    Patch {
+        patch: String,
+        working_dir: PathBuf,
+        targets: Vec<(PathBuf, Option<String>)>,
+    },
+}
+
+impl Change {
+    /// The files the change writes.
+    fn paths(&self) -> Vec<PathBuf> {
+        match self {
+            Change::File { path, .. } => vec![path.clone()],
+            Change::Patch { targets, .. } => targets.iter().map(|(p, _)| p.clone()).collect(),
+        }
+    }
+
+    /// The change as a unified diff.
+    fn diff(&self) -> String {
+        match self {
+            Change::File {
+                path,
+                before,
+                after,
+            } => unified_diff(path, before.as_deref(), after),
+            Change::Patch { patch, .. } => patch.clone(),
+        }
+    }
+}
+
+/// What a pending change will write, for approvals and the audit trail.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedChange {
+    pub paths: Vec<PathBuf>,
+    pub diff: String,
+}
+
+struct Pending {
+    session: String,
+    staged_at: Instant,
+    change: Change,
+}
+
+/// Changes previewed by `write`, `edit` and `apply_patch`, waiting for
+/// `apply_change`.
+#[derive(Default)]
+pub struct StagedChanges {
+    pending: Mutex<HashMap<String, Pending>>,
+    next_id: AtomicU64,
+}
+
+impl StagedChanges {
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<String, Pending>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stage `change` for `session` and return its change id.
+    pub(crate) fn stage(&self, session: &SessionId, change: Change) -> String {
+        let id = format!(
+            "change-{}",
+            self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let mut map = self.pending();
+        map.retain(|_, p| p.staged_at.elapsed() < PENDING_TTL);
+        loop {
+            let mine = map.iter().filter(|(_, p)| p.session == session.0);
+            if mine.clone().count() < MAX_PENDING {
+                break;
+            }
+            let Some(oldest) = mine
+                .min_by_key(|(_, p)| p.staged_at)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            map.remove(&oldest);
+        }
+        map.insert(
+            id.clone(),
+            Pending {
+                session: session.0.clone(),
+                staged_at: Instant::now(),
+                change,
+            },
+        );
+        id
+    }
+
+    /// Remove and return the change `id` staged by `session`, if it is
+    /// still live.
+    fn take(&self, session: &SessionId, id: &str) -> Option<Change> {
+        let mut map = self.pending();
+        let p = map.get(id)?;
+        if p.session != session.0 {
+            return None;
+        }
+        let p = map.remove(id)?;
+        (p.staged_at.elapsed() < PENDING_TTL).then_some(p.change)
+    }
+
+    /// The files and diff of the change `id` staged by `session`, if it is
+    /// still live. Unlike applying it, this leaves it staged.
+    pub fn get(&self, session: &SessionId, id: &str) -> Option<StagedChange> {
+        let map = self.pending();
+        let p = map.get(id)?;
+        (p.session == session.0 && p.staged_at.elapsed() < PENDING_TTL).then(|| StagedChange {
+            paths: p.change.paths(),
+            diff: p.change.diff(),
+        })
+    }
+}
+
+/// The files a unified diff touches, as `patch -p1` in `working_dir`
+/// resolves them: the paths of its `---` and `+++` headers without their
+/// first component, leaving out `/dev/null`.
+pub(crate) fn patch_targets(patch: &str, working_dir: &Path) -> Vec<PathBuf> {
+    let mut targets = Vec::new();
+    for line in patch.lines() {
+        let Some(header) = line
+            .strip_prefix("--- ")
+            .or_else(|| line.strip_prefix("+++ "))
+        else {
+            continue;
+        };
+        // A tab separates the name from an optional timestamp
+        let name = header.split('\t').next().unwrap_or_default().trim();
+        if name == "/dev/null" {
+            continue;
+        }
+        let Some((_, relative)) = name.split_once('/') else {
+            continue;
+        };
+        let path = working_dir.join(relative);
+        if !targets.contains(&path) {
+            targets.push(path);
+        }
+    }
+    targets
+}
+
+/// Unified diff of one file, with `a/` and `b/` headers on its path.
+pub(crate) fn unified_diff(path: &Path, before: Option<&str>, after: &str) -> String {
+    let name = path.display().to_string();
+    let old_header = if before.is_some() {
+        format!("a/{}", name.trim_start_matches('/'))
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_header = format!("b/{}", name.trim_start_matches('/'));
+    similar::TextDiff::from_lines(before.unwrap_or(""), after)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_header, &new_header)
+        .to_string()
+}
+
+/// The tool result returned for a preview: the change id and its diff.
+pub(crate) fn preview_result(id: &str, diff: &str) -> ToolResult {
+    let diff = if diff.is_empty() {
+        "(no changes)\n"
+    } else {
+        diff
+    };
+    ToolResult::success(format!(
+        "Preview of {id} (nothing written). Call apply_change with change_id \"{id}\" to apply it.\n\n{diff}"
+    ))
+}
+
+#[derive(Default)]
+pub struct ApplyChangeTool {
+    staged: Arc<StagedChanges>,
+}
+
+impl ApplyChangeTool {
+    /// Apply the changes previewed into `staged`.
+    pub fn new(staged: Arc<StagedChanges>) -> Self {
+        Self { staged }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApplyChangeInput {
+    change_id: String,
+}
+
+impl Tool for ApplyChangeTool {
+    fn name(&self) -> &str {
+        "apply_change"
+    }
+
+    fn tier(&self) -> SecurityTier {
+        SecurityTier::T1
+    }
+
+    fn description(&self) -> &str {
+        "Apply a change previously previewed with write, edit, or apply_patch (preview: true). \
+         Refuses if the file changed since the preview."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "change_id": {
+                    "type": "string",
+                    "description": "The change id returned by the preview"
+                }
+            },
+            "required": ["change_id"]
+        })
+    }
+
+    /// The input with the staged change's `paths` and `diff`, and its
+    /// `file_path` when it writes one file, for path rules to match.
+    fn describe_input(
+        &self,
+        input: &serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Option<serde_json::Value> {
+        let id = input.get("change_id")?.as_str()?;
+        let staged = self.staged.get(&ctx.session_id, id)?;
+        let mut described = input.clone();
+        let fields = described.as_object_mut()?;
+        if let [path] = &staged.paths[..] {
+            fields.insert("file_path".into(), path.display().to_string().into());
+        }
+        fields.insert(
+            "paths".into(),
+            staged
+                .paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .into(),
+        );
+        fields.insert("diff".into(), staged.diff.into());
+        Some(described)
+    }
+
+    fn execute(
+        &self,
+        input: serde_json::Value,
+        ctx: ToolContext,
+    ) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async move {
+            let params: ApplyChangeInput = serde_json::from_value(input)
+                .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
+
+            let Some(change) = self.staged.take(&ctx.session_id, &params.change_id) else {
+                return Ok(ToolResult::error(format!(
+                    "No pending change {} in this session (unknown, already applied, or expired)",
+                    params.change_id
+                )));
+            };
+            debug!(change_id = %params.change_id, "Applying previewed change");
+
+            match change {
+                Change::File {
+                    path,
+                    before,
+                    after,
+                } => {
+                    let current = read_existing(&path, "apply_change").await?;
+                    if current != before {
+                        return Ok(ToolResult::error(format!(
+                            "{} changed since {} was previewed; preview the change again",
+                            path.display(),
+                            params.change_id
+                        )));
+                    }
+                    write_file(&path, &after, "apply_change").await?;
+                    Ok(ToolResult::success(format!(
+                        "Applied {} to {}",
+                        params.change_id,
+                        path.display()
+                    )))
+                }
+                Change::Patch {
+                    patch,
+                    working_dir,
+                    targets,
+                } => {
+                    for (path, before) in &targets {
+                        if read_existing(path, "apply_change").await? != *before {
+                            return Ok(ToolResult::error(format!(
+                                "{} changed since {} was previewed; preview the change again",
+                                path.display(),
+                                params.change_id
+                            )));
+                        }
+                    }
+                    let output = super::apply_patch::run_patch(&patch, &working_dir, false).await?;
+                    if output.status.success() {
+                        Ok(ToolResult::success(format!(
+                            "Applied {}:\n{}",
+                            params.change_id,
+                            String::from_utf8_lossy(&output.stdout)
+                        )))
+                    } else {
+                        Ok(ToolResult::error(format!(
+                            "Patch for {} failed (exit {}):\n{}\n{}",
+                            params.change_id,
+                            output.status.code().unwrap_or(-1),
+                            String::from_utf8_lossy(&output.stdout),
+                            String::from_utf8_lossy(&output.stderr)
+                        )))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Current content of `path`, or `None` if it does not exist.
+pub(crate) async fn read_existing(path: &Path, tool: &str) -> Result<Option<String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(RyvosError::ToolExecution {
+            tool: tool.to_string(),
+            message: format!("{}: {}", path.display(), e),
+        }),
+    }
+}
+
+/// Write `content` to `path`, creating parent directories.
+pub(crate) async fn write_file(path: &Path, content: &str, tool: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| RyvosError::ToolExecution {
+                tool: tool.to_string(),
+                message: format!("Failed to create directories: {}", e),
+            })?;
+    }
+    tokio::fs::write(path, content)
+        .await
+        .map_err(|e| RyvosError::ToolExecution {
+            tool: tool.to_string(),
+            message: format!("{}: {}", path.display(), e),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::apply_patch::ApplyPatchTool;
+    use crate::builtin::edit::EditTool;
+    use crate::builtin::write::WriteTool;
+    use ryvos_test_utils::test_tool_context_with_dir;
+
+    fn change_id(result: &ToolResult) -> String {
+        let start = result.content.find("change-").expect("change id");
+        result.content[start..]
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn edit_preview_writes_nothing_and_apply_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = Arc::new(StagedChanges::default());
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "alpha\nbeta\ngamma\n").unwrap();
+        let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
+
+        let preview = EditTool::new(staged.clone())
+            .execute(
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "old_string": "beta",
+                    "new_string": "BETA",
+                    "preview": true
+                }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(!preview.is_error);
+        assert!(preview.content.contains("-beta\n+BETA"));
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "alpha\nbeta\ngamma\n"
+        );
+
+        let id = change_id(&preview);
+        let applied = ApplyChangeTool::new(staged.clone())
+            .execute(serde_json::json!({ "change_id": id }), ctx.clone())
+            .await
+            .unwrap();
+        assert!(!applied.is_error, "{}", applied.content);
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "alpha\nBETA\ngamma\n"
+        );
+
+        // Single use
+        let again = ApplyChangeTool::new(staged.clone())
+            .execute(serde_json::json!({ "change_id": id }), ctx)
+            .await
+            .unwrap();
+        assert!(again.is_error);
+    }
+
+    #[tokio::test]
+    async fn write_preview_of_new_file_then_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = Arc::new(StagedChanges::default());
+        let file_path = dir.path().join("sub/new.txt");
+        let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
+
+        let preview = WriteTool::new(staged.clone())
+            .execute(
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "content": "fresh\n",
+                    "preview": true
+                }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(preview.content.contains("--- /dev/null"));
+        assert!(preview.content.contains("+fresh"));
+        assert!(!file_path.exists());
+
+        let applied = ApplyChangeTool::new(staged.clone())
+            .execute(serde_json::json!({ "change_id": change_id(&preview) }), ctx)
+            .await
+            .unwrap();
+        assert!(!applied.is_error, "{}", applied.content);
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "fresh\n");
+    }
+
+    #[tokio::test]
+    async fn apply_refuses_when_file_changed_after_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = Arc::new(StagedChanges::default());
+        let file_path = dir.path().join("racy.txt");
+        std::fs::write(&file_path, "one\n").unwrap();
+        let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
+
+        let preview = WriteTool::new(staged.clone())
+            .execute(
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "content": "two\n",
+                    "preview": true
+                }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        std::fs::write(&file_path, "someone else\n").unwrap();
+
+        let applied = ApplyChangeTool::new(staged.clone())
+            .execute(serde_json::json!({ "change_id": change_id(&preview) }), ctx)
+            .await
+            .unwrap();
+        assert!(applied.is_error);
+        assert!(applied.content.contains("changed since"));
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "someone else\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn changes_are_scoped_to_their_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = Arc::new(StagedChanges::default());
+        let file_path = dir.path().join("mine.txt");
+        let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
+
+        let preview = WriteTool::new(staged.clone())
+            .execute(
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "content": "x",
+                    "preview": true
+                }),
+                ctx,
+            )
+            .await
+            .unwrap();
+
+        let mut other = test_tool_context_with_dir(dir.path().to_path_buf());
+        other.session_id = SessionId::from_string("someone-else");
+        let applied = ApplyChangeTool::new(staged.clone())
+            .execute(
+                serde_json::json!({ "change_id": change_id(&preview) }),
+                other,
+            )
+            .await
+            .unwrap();
+        assert!(applied.is_error);
+        assert!(!file_path.exists());
+    }
+
+    #[tokio::test]
+    async fn patch_preview_validates_then_applies() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = Arc::new(StagedChanges::default());
+        std::fs::write(dir.path().join("hello.txt"), "hello\n").unwrap();
+        let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
+        let patch = "--- a/hello.txt\n+++ b/hello.txt\n@@ -1 +1 @@\n-hello\n+goodbye\n";
+
+        let preview = ApplyPatchTool::new(staged.clone())
+            .execute(
+                serde_json::json!({ "patch": patch, "preview": true }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(!preview.is_error, "{}", preview.content);
+        assert!(preview.content.contains("+goodbye"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("hello.txt")).unwrap(),
+            "hello\n"
+        );
+
+        let id = change_id(&preview);
+        let change = staged.get(&ctx.session_id, &id).unwrap();
+        assert_eq!(change.paths, vec![dir.path().join("hello.txt")]);
+        assert_eq!(change.diff, patch);
+        // The gate sees the call with the file and diff it applies
+        let input = serde_json::json!({ "change_id": id });
+        let described = ApplyChangeTool::new(staged.clone())
+            .describe_input(&input, &ctx)
+            .unwrap();
+        let target = dir.path().join("hello.txt").display().to_string();
+        assert_eq!(described["file_path"], target.as_str());
+        assert_eq!(described["diff"], patch);
+
+        let applied = ApplyChangeTool::new(staged.clone())
+            .execute(serde_json::json!({ "change_id": id }), ctx)
+            .await
+            .unwrap();
+        assert!(!applied.is_error, "{}", applied.content);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("hello.txt")).unwrap(),
+            "goodbye\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn patch_apply_refuses_when_a_target_changed_after_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = Arc::new(StagedChanges::default());
+        let file_path = dir.path().join("hello.txt");
+        std::fs::write(&file_path, "one\ntwo\nthree\nfour\n").unwrap();
+        let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
+        let patch = "--- a/hello.txt\n+++ b/hello.txt\n@@ -4 +4 @@\n-four\n+FOUR\n";
+
+        let preview = ApplyPatchTool::new(staged.clone())
+            .execute(
+                serde_json::json!({ "patch": patch, "preview": true }),
+                ctx.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(!preview.is_error, "{}", preview.content);
+        // Still patchable with fuzz, but not what was previewed
+        std::fs::write(&file_path, "zero\none\ntwo\nthree\nfour\n").unwrap();
+
+        let applied = ApplyChangeTool::new(staged.clone())
+            .execute(serde_json::json!({ "change_id": change_id(&preview) }), ctx)
+            .await
+            .unwrap();
+        assert!(applied.is_error);
+        assert!(applied.content.contains("changed since"));
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "zero\none\ntwo\nthree\nfour\n"
+        );
+    }
+
+    #[test]
+    fn one_session_cannot_evict_another_sessions_changes() {
+        let change = || Change::File {
+            path: PathBuf::from("/tmp/x"),
+            before: None,
+            after: String::new(),
+        };
+        let staged = StagedChanges::default();
+        let (busy, quiet) = (SessionId::new(), SessionId::new());
+        let kept = staged.stage(&quiet, change());
+        let first = staged.stage(&busy, change());
+        for _ in 0..MAX_PENDING {
+            staged.stage(&busy, change());
+        }
+        assert!(staged.get(&quiet, &kept).is_some());
+        assert!(staged.get(&busy, &first).is_none());
+        assert!(staged.get(&busy, &kept).is_none());
+    }
+
+    #[test]
+    fn patch_targets_strip_the_first_component() {
+        let patch = "--- a/src/old.rs\t2024-01-01\n+++ b/src/new.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                     --- /dev/null\n+++ b/added.txt\n@@ -0,0 +1 @@\n+x\n";
+        assert_eq!(
+            patch_targets(patch, Path::new("/w")),
+            vec![
+                PathBuf::from("/w/src/old.rs"),
+                PathBuf::from("/w/src/new.rs"),
+                PathBuf::from("/w/added.txt"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Working notes the agent keeps for itself during a session.
+//!
+//! Entries live in the session store under the current session, outside the
+//! conversation, so a multi-step task can record its plan and intermediate
+//! results without them being pruned. When `[agent.context]
+//! scratchpad_on_compaction` is on, the agent loop re-adds them to the
+//! context after a compaction.
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::security::SecurityTier;
+use ryvos_core::traits::Tool;
+use ryvos_core::types::{ToolContext, ToolResult};
+
+/// Characters of each value `list` shows.
+const LIST_PREVIEW_CHARS: usize = 80;
+
+pub struct ScratchpadTool;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ScratchpadInput {
+    Set { key: String, value: String },
+    Get { key: String },
+    Append { key: String, value: String },
+    List,
+}
+
+impl Tool for ScratchpadTool {
+    fn name(&self) -> &str {
+        "scratchpad"
+    }
+
+    fn tier(&self) -> SecurityTier {
+        SecurityTier::T0
+    }
+
+    fn description(&self) -> &str {
+        "Keep working notes for this session (plans, findings, intermediate results). \
+         `set` and `append` write a key, `get` reads one, `list` shows all keys."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["set", "get", "append", "list"]
+                },
+                "key": { "type": "string", "description": "Entry name (set, get, append)" },
+                "value": { "type": "string", "description": "Text to store (set) or add on a new line (append)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(
+        &self,
+        input: serde_json::Value,
+        ctx: ToolContext,
+    ) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async move {
+            let input: ScratchpadInput = serde_json::from_value(input)
+                .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
+            let store = ctx.store.ok_or_else(|| RyvosError::ToolExecution {
+                tool: "scratchpad".into(),
+                message: "No session store available".into(),
+            })?;
+            let sid = &ctx.session_id;
+
+            match input {
+                ScratchpadInput::Set { key, value } => {
+                    store.set_scratchpad(sid, &key, &value).await?;
+                    Ok(ToolResult::success(format!("Set '{}'", key)))
+                }
+                ScratchpadInput::Get { key } => {
+                    let mut entries = store.load_scratchpad(sid).await?;
+                    Ok(match entries.remove(&key) {
+                        Some(value) => ToolResult::success(value),
+                        None => ToolResult::error(format!("No scratchpad entry '{}'", key)),
+                    })
+                }
+                ScratchpadInput::Append { key, value } => {
+                    let mut entries = store.load_scratchpad(sid).await?;
+                    let combined = match entries.remove(&key) {
+                        Some(existing) if !existing.is_empty() => {
+                            format!("{}\n{}", existing, value)
+                        }
+                        _ => value,
+                    };
+                    store.set_scratchpad(sid, &key, &combined).await?;
+                    Ok(ToolResult::success(format!(
+                        "Appended to '{}' ({} chars)",
+                        key,
+                        combined.chars().count()
+                    )))
+                }
+                ScratchpadInput::List => {
+                    let entries = store.load_scratchpad(sid).await?;
+                    if entries.is_empty() {
+                        return Ok(ToolResult::success("Scratchpad is empty"));
+                    }
+                    let lines: Vec<String> = entries
+                        .iter()
+                        .map(|(key, value)| {
+                            let first = value.lines().next().unwrap_or("");
+                            let mut preview: String =
+                                first.chars().take(LIST_PREVIEW_CHARS).collect();
+                            if preview.len() < value.len() {
+                                preview.push('…');
+                            }
+                            format!("{}: {}", key, preview)
+                        })
+                        .collect();
+                    Ok(ToolResult::success(lines.join("\n")))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ryvos_core::traits::SessionStore;
+    use ryvos_test_utils::{test_tool_context, InMemorySessionStore};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn values_round_trip_through_the_store() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let mut ctx = test_tool_context();
+        ctx.store = Some(store.clone() as Arc<dyn SessionStore>);
+        let run = |input: serde_json::Value| ScratchpadTool.execute(input, ctx.clone());
+
+        run(json!({ "action": "set", "key": "plan", "value": "1. read" }))
+            .await
+            .unwrap();
+        run(json!({ "action": "append", "key": "plan", "value": "2. fix" }))
+            .await
+            .unwrap();
+        run(json!({ "action": "append", "key": "notes", "value": "flaky test" }))
+            .await
+            .unwrap();
+
+        let plan = run(json!({ "action": "get", "key": "plan" }))
+            .await
+            .unwrap();
+        assert_eq!(plan.content, "1. read\n2. fix");
+        let list = run(json!({ "action": "list" })).await.unwrap();
+        assert_eq!(list.content, "notes: flaky test\nplan: 1. read…");
+        let missing = run(json!({ "action": "get", "key": "nope" }))
+            .await
+            .unwrap();
+        assert!(missing.is_error);
+
+        // Stored under the calling session
+        let entries = store.load_scratchpad(&ctx.session_id).await.unwrap();
+        assert_eq!(entries["notes"], "flaky test");
+    }
+}
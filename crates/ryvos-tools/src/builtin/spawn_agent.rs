@@ -5,6 +5,10 @@ use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
+/// Deepest nesting allowed for spawned sub-agents. The top-level agent is
+/// depth 0, so at most this many levels of sub-agents can be stacked.
+pub const MAX_AGENT_DEPTH: u32 = 3;
+
 pub struct SpawnAgentTool;
 
 impl Tool for SpawnAgentTool {
@@ -13,11 +17,11 @@ impl Tool for SpawnAgentTool {
     }
 
     fn tier(&self) -> ryvos_core::security::SecurityTier {
-        ryvos_core::security::SecurityTier::T3
+        ryvos_core::security::SecurityTier::T2
     }
 
     fn description(&self) -> &str {
-        "Spawn a background sub-agent to handle a task in parallel. \
+        "Spawn a sub-agent with restricted permissions to handle a task. \
          Returns the sub-agent's response when complete."
     }
 
@@ -57,10 +61,75 @@ impl Tool for SpawnAgentTool {
                 .ok_or_else(|| RyvosError::ToolValidation("'prompt' must be a string".into()))?
                 .to_string();
 
-            match spawner.spawn(prompt).await {
+            if ctx.agent_depth >= MAX_AGENT_DEPTH {
+                return Ok(ToolResult::error(format!(
+                    "Sub-agent depth limit reached ({} levels)",
+                    MAX_AGENT_DEPTH
+                )));
+            }
+
+            match spawner.spawn_sub_agent(prompt, ctx.agent_depth + 1).await {
                 Ok(result) => Ok(ToolResult::success(result)),
                 Err(e) => Ok(ToolResult::error(format!("Sub-agent failed: {}", e))),
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use ryvos_core::types::AgentSpawner;
+    use ryvos_test_utils::test_tool_context;
+
+    /// Records the depth each sub-agent was spawned at.
+    #[derive(Default)]
+    struct RecordingSpawner {
+        depths: Mutex<Vec<u32>>,
+    }
+
+    impl AgentSpawner for RecordingSpawner {
+        fn spawn(&self, _prompt: String) -> BoxFuture<'_, Result<String>> {
+            Box::pin(async { Ok("unrestricted".into()) })
+        }
+
+        fn spawn_sub_agent(&self, _prompt: String, depth: u32) -> BoxFuture<'_, Result<String>> {
+            self.depths.lock().unwrap().push(depth);
+            Box::pin(async { Ok("restricted".into()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn spawns_one_level_deeper() {
+        let spawner = Arc::new(RecordingSpawner::default());
+        let mut ctx = test_tool_context();
+        ctx.agent_spawner = Some(spawner.clone());
+        ctx.agent_depth = 1;
+
+        let result = SpawnAgentTool
+            .execute(json!({"prompt": "summarize"}), ctx)
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.content, "restricted");
+        assert_eq!(*spawner.depths.lock().unwrap(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn refuses_beyond_depth_limit() {
+        let spawner = Arc::new(RecordingSpawner::default());
+        let mut ctx = test_tool_context();
+        ctx.agent_spawner = Some(spawner.clone());
+        ctx.agent_depth = MAX_AGENT_DEPTH;
+
+        let result = SpawnAgentTool
+            .execute(json!({"prompt": "recurse"}), ctx)
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.content.contains("depth limit"));
+        assert!(spawner.depths.lock().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,9 @@
+use std::sync::Arc;
+
 use futures::future::BoxFuture;
 use serde::Deserialize;
 
+use ryvos_core::clock::{Clock, SystemClock, Zone};
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::security::SecurityTier;
 use ryvos_core::traits::Tool;
@@ -257,3 +260,117 @@ impl Tool for DiskUsageTool {
         })
     }
 }
+
+// ── NowTool ─────────────────────────────────────────────────────
+
+/// Current date and time, in `[agent] timezone` unless the call names one.
+pub struct NowTool {
+    clock: Arc<dyn Clock>,
+    zone: Zone,
+}
+
+impl NowTool {
+    pub fn new(zone: Zone) -> Self {
+        Self::with_clock(zone, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(zone: Zone, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, zone }
+    }
+}
+
+impl Default for NowTool {
+    fn default() -> Self {
+        Self::new(Zone::Local)
+    }
+}
+
+#[derive(Deserialize)]
+struct NowInput {
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+impl Tool for NowTool {
+    fn name(&self) -> &str {
+        "now"
+    }
+    fn tier(&self) -> SecurityTier {
+        SecurityTier::T0
+    }
+    fn description(&self) -> &str {
+        "Get the current date and time, optionally in another timezone or strftime format."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "timezone": { "type": "string", "description": "IANA name (e.g. America/New_York), UTC, or local. Default: the configured timezone" },
+                "format": { "type": "string", "description": "strftime format (e.g. %Y-%m-%d). Default: RFC 3339 with weekday" }
+            }
+        })
+    }
+    fn execute(
+        &self,
+        input: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> BoxFuture<'_, Result<ToolResult>> {
+        Box::pin(async move {
+            let p: NowInput = serde_json::from_value(input)
+                .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
+            let zone = match p.timezone {
+                Some(name) => match name.parse::<Zone>() {
+                    Ok(zone) => zone,
+                    Err(e) => return Ok(ToolResult::error(e)),
+                },
+                None => self.zone,
+            };
+            let now = self.clock.now();
+            let output = match p.format {
+                Some(format) => zone.format(now, &format),
+                None => zone
+                    .format(now, "%Y-%m-%dT%H:%M:%S%:z, %A")
+                    .map(|t| format!("{} ({})", t, zone)),
+            };
+            match output {
+                Some(text) => Ok(ToolResult::success(text)),
+                None => Ok(ToolResult::error("invalid strftime format")),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ryvos_core::clock::FixedClock;
+    use ryvos_test_utils::test_tool_context;
+
+    fn now_tool() -> NowTool {
+        let t = Utc.with_ymd_and_hms(2026, 3, 1, 23, 30, 0).unwrap();
+        NowTool::with_clock("utc".parse().unwrap(), Arc::new(FixedClock(t)))
+    }
+
+    #[tokio::test]
+    async fn now_formats_the_clock_time() {
+        let tool = now_tool();
+        let run = |input| tool.execute(input, test_tool_context());
+
+        let default = run(serde_json::json!({})).await.unwrap();
+        assert_eq!(default.content, "2026-03-01T23:30:00+00:00, Sunday (UTC)");
+
+        let tokyo = serde_json::json!({ "timezone": "Asia/Tokyo", "format": "%Y-%m-%d %H:%M %Z" });
+        let result = run(tokyo).await.unwrap();
+        assert_eq!(result.content, "2026-03-02 08:30 JST");
+
+        let bad_zone = run(serde_json::json!({ "timezone": "Nowhere/Land" }))
+            .await
+            .unwrap();
+        assert!(bad_zone.is_error);
+        let bad_format = run(serde_json::json!({ "format": "%Q" })).await.unwrap();
+        assert!(bad_format.is_error);
+    }
+}
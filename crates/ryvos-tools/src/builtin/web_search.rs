@@ -1,22 +1,383 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
 use futures::future::BoxFuture;
 use serde_json::json;
+use tracing::{debug, warn};
 
+use ryvos_core::config::WebSearchConfig;
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
-pub struct WebSearchTool {
+/// Upper bound on results per search, whatever the model asks for.
+const MAX_RESULTS: usize = 10;
+
+/// A search hit, normalized across providers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// A web search backend.
+pub trait SearchProvider: Send + Sync + 'static {
+    /// Provider name as used in `[web_search] provider`.
+    fn name(&self) -> &str;
+
+    /// Search for `query`, returning at most `max_results` hits.
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>>>;
+}
+
+/// Build the provider selected by `[web_search] provider`.
+pub fn provider_from_config(config: &WebSearchConfig) -> Result<Box<dyn SearchProvider>> {
+    let require_key = || {
+        if config.api_key.is_empty() {
+            Err(RyvosError::Config(format!(
+                "web_search provider '{}' requires an api_key",
+                config.provider
+            )))
+        } else {
+            Ok(config.api_key.clone())
+        }
+    };
+    match config.provider.to_lowercase().as_str() {
+        "tavily" => Ok(Box::new(TavilyProvider::new(&require_key()?))),
+        "brave" => Ok(Box::new(BraveProvider::new(&require_key()?))),
+        "searxng" => {
+            let base_url = config.base_url.as_deref().ok_or_else(|| {
+                RyvosError::Config("web_search provider 'searxng' requires a base_url".into())
+            })?;
+            Ok(Box::new(SearxngProvider::new(base_url)))
+        }
+        other => Err(RyvosError::Config(format!(
+            "unknown web_search provider: {}",
+            other
+        ))),
+    }
+}
+
+/// Send a request and decode the JSON body, mapping failures to tool errors.
+async fn fetch_json(req: reqwest::RequestBuilder) -> Result<serde_json::Value> {
+    let err = |message: String| RyvosError::ToolExecution {
+        tool: "web_search".into(),
+        message,
+    };
+    let resp = req.send().await.map_err(|e| err(e.to_string()))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(err(format!("search API returned {}: {}", status, body)));
+    }
+    resp.json().await.map_err(|e| err(e.to_string()))
+}
+
+/// Collect `title` / `url` / `<snippet_key>` objects from a results array.
+fn collect_results(
+    results: Option<&Vec<serde_json::Value>>,
+    snippet_key: &str,
+    max_results: usize,
+) -> Vec<SearchResult> {
+    results
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|r| {
+                    Some(SearchResult {
+                        title: strip_tags(r["title"].as_str().unwrap_or("")),
+                        url: r["url"].as_str()?.to_string(),
+                        snippet: strip_tags(r[snippet_key].as_str().unwrap_or("")),
+                    })
+                })
+                .take(max_results)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Remove the inline HTML (e.g., `<strong>`) some providers put in snippets.
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+// ── Tavily ──────────────────────────────────────────────────────
+
+pub struct TavilyProvider {
     api_key: String,
     http: reqwest::Client,
 }
 
-impl WebSearchTool {
+impl TavilyProvider {
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn parse(body: &serde_json::Value, max_results: usize) -> Vec<SearchResult> {
+        collect_results(body["results"].as_array(), "content", max_results)
+    }
+}
+
+impl SearchProvider for TavilyProvider {
+    fn name(&self) -> &str {
+        "tavily"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>>> {
+        Box::pin(async move {
+            let req = self
+                .http
+                .post("https://api.tavily.com/search")
+                .json(&json!({
+                    "api_key": self.api_key,
+                    "query": query,
+                    "max_results": max_results,
+                }));
+            Ok(Self::parse(&fetch_json(req).await?, max_results))
+        })
+    }
+}
+
+// ── Brave Search ────────────────────────────────────────────────
+
+pub struct BraveProvider {
+    api_key: String,
+    http: reqwest::Client,
+}
+
+impl BraveProvider {
     pub fn new(api_key: &str) -> Self {
         Self {
             api_key: api_key.to_string(),
             http: reqwest::Client::new(),
         }
     }
+
+    fn parse(body: &serde_json::Value, max_results: usize) -> Vec<SearchResult> {
+        collect_results(
+            body["web"]["results"].as_array(),
+            "description",
+            max_results,
+        )
+    }
+}
+
+impl SearchProvider for BraveProvider {
+    fn name(&self) -> &str {
+        "brave"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>>> {
+        Box::pin(async move {
+            let req = self
+                .http
+                .get("https://api.search.brave.com/res/v1/web/search")
+                .header("X-Subscription-Token", &self.api_key)
+                .header("Accept", "application/json")
+                .query(&[("q", query), ("count", &max_results.to_string())]);
+            Ok(Self::parse(&fetch_json(req).await?, max_results))
+        })
+    }
+}
+
+// ── SearXNG ─────────────────────────────────────────────────────
+
+/// Self-hosted SearXNG instance. Needs no api key, but the instance must
+/// have the `json` output format enabled.
+pub struct SearxngProvider {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl SearxngProvider {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn parse(body: &serde_json::Value, max_results: usize) -> Vec<SearchResult> {
+        collect_results(body["results"].as_array(), "content", max_results)
+    }
+}
+
+impl SearchProvider for SearxngProvider {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>>> {
+        Box::pin(async move {
+            let req = self
+                .http
+                .get(format!("{}/search", self.base_url))
+                .query(&[("q", query), ("format", "json")]);
+            Ok(Self::parse(&fetch_json(req).await?, max_results))
+        })
+    }
+}
+
+// ── Result cache ────────────────────────────────────────────────
+
+struct CacheEntry {
+    results: Vec<SearchResult>,
+    /// How many results were requested when this entry was fetched.
+    max_results: usize,
+    fetched_at: Instant,
+}
+
+/// In-memory TTL cache of search results, keyed by normalized query.
+struct SearchCache {
+    entries: HashMap<String, CacheEntry>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl SearchCache {
+    fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            capacity,
+        }
+    }
+
+    /// Case- and whitespace-insensitive cache key.
+    fn key(query: &str) -> String {
+        query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    /// Cached results covering `max_results`. Expired entries are only
+    /// returned when `allow_stale` is set.
+    fn get(&self, key: &str, max_results: usize, allow_stale: bool) -> Option<Vec<SearchResult>> {
+        let entry = self.entries.get(key)?;
+        // An entry fetched with a smaller limit can still answer if the
+        // backend returned fewer results than that limit
+        let covers = entry.max_results >= max_results || entry.results.len() < entry.max_results;
+        let fresh = entry.fetched_at.elapsed() < self.ttl;
+        if covers && (fresh || allow_stale) {
+            Some(entry.results.iter().take(max_results).cloned().collect())
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, key: String, results: Vec<SearchResult>, max_results: usize) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.fetched_at)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            key,
+            CacheEntry {
+                results,
+                max_results,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+}
+
+// ── WebSearchTool ───────────────────────────────────────────────
+
+pub struct WebSearchTool {
+    provider: Box<dyn SearchProvider>,
+    cache: Mutex<SearchCache>,
+}
+
+impl WebSearchTool {
+    pub fn new(provider: Box<dyn SearchProvider>) -> Self {
+        Self {
+            provider,
+            cache: Mutex::new(SearchCache::new(Duration::from_secs(600), 128)),
+        }
+    }
+
+    /// Build the tool with the provider and cache settings from `[web_search]`.
+    pub fn from_config(config: &WebSearchConfig) -> Result<Self> {
+        Ok(Self::new(provider_from_config(config)?).with_cache(
+            Duration::from_secs(config.cache_ttl_secs),
+            config.cache_max_entries,
+        ))
+    }
+
+    /// Answer repeated queries from cache for `ttl`, keeping at most
+    /// `capacity` queries. A zero TTL or capacity disables caching.
+    pub fn with_cache(mut self, ttl: Duration, capacity: usize) -> Self {
+        let capacity = if ttl.is_zero() { 0 } else { capacity };
+        self.cache = Mutex::new(SearchCache::new(ttl, capacity));
+        self
+    }
+
+    /// Search through the cache. When the backend fails (e.g., it is rate
+    /// limiting us), an expired entry for the same query is served instead.
+    async fn cached_search(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>> {
+        let key = SearchCache::key(query);
+        if let Some(hits) = self.cache.lock().unwrap().get(&key, max_results, false) {
+            debug!(query, "Web search served from cache");
+            return Ok(hits);
+        }
+
+        match self.provider.search(query, max_results).await {
+            Ok(hits) => {
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .insert(key, hits.clone(), max_results);
+                Ok(hits)
+            }
+            Err(e) => match self.cache.lock().unwrap().get(&key, max_results, true) {
+                Some(stale) => {
+                    warn!(query, error = %e, "Web search failed, serving stale cached results");
+                    Ok(stale)
+                }
+                None => Err(e),
+            },
+        }
+    }
 }
 
 impl Tool for WebSearchTool {
@@ -42,7 +403,7 @@ impl Tool for WebSearchTool {
                 },
                 "max_results": {
                     "type": "integer",
-                    "description": "Maximum number of results (default 5)",
+                    "description": "Maximum number of results (default 5, max 10)",
                     "default": 5
                 }
             },
@@ -59,45 +420,19 @@ impl Tool for WebSearchTool {
             let query = input["query"]
                 .as_str()
                 .ok_or_else(|| RyvosError::ToolValidation("'query' must be a string".into()))?;
-            let max = input["max_results"].as_u64().unwrap_or(5);
+            let max = input["max_results"]
+                .as_u64()
+                .map_or(5, |n| (n as usize).clamp(1, MAX_RESULTS));
 
-            let resp = self
-                .http
-                .post("https://api.tavily.com/search")
-                .json(&json!({
-                    "api_key": self.api_key,
-                    "query": query,
-                    "max_results": max,
-                }))
-                .send()
-                .await
-                .map_err(|e| RyvosError::ToolExecution {
-                    tool: "web_search".into(),
-                    message: e.to_string(),
-                })?;
-
-            let body: serde_json::Value =
-                resp.json().await.map_err(|e| RyvosError::ToolExecution {
-                    tool: "web_search".into(),
-                    message: e.to_string(),
-                })?;
-
-            let results = body["results"]
-                .as_array()
-                .map(|arr| {
-                    arr.iter()
-                        .map(|r| {
-                            format!(
-                                "**{}**\n{}\nURL: {}",
-                                r["title"].as_str().unwrap_or(""),
-                                r["content"].as_str().unwrap_or(""),
-                                r["url"].as_str().unwrap_or("")
-                            )
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n\n---\n\n")
-                })
-                .unwrap_or_else(|| "No results found.".into());
+            let hits = self.cached_search(query, max).await?;
+            let results = if hits.is_empty() {
+                "No results found.".to_string()
+            } else {
+                hits.iter()
+                    .map(|r| format!("**{}**\n{}\nURL: {}", r.title, r.snippet, r.url))
+                    .collect::<Vec<_>>()
+                    .join("\n\n---\n\n")
+            };
 
             // Tag as untrusted external data for prompt injection defense
             let tagged = format!(
@@ -111,3 +446,241 @@ impl Tool for WebSearchTool {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use ryvos_test_utils::test_tool_context;
+
+    const TAVILY_FIXTURE: &str = r#"{
+        "query": "rust async",
+        "results": [
+            {"title": "Async Rust", "url": "https://rust-lang.github.io/async-book/", "content": "The async book.", "score": 0.98},
+            {"title": "Tokio", "url": "https://tokio.rs", "content": "An asynchronous runtime.", "score": 0.91},
+            {"title": "No URL", "content": "Dropped."}
+        ]
+    }"#;
+
+    const BRAVE_FIXTURE: &str = r#"{
+        "type": "search",
+        "web": {
+            "type": "search",
+            "results": [
+                {"title": "Tokio - An <strong>async</strong> runtime", "url": "https://tokio.rs", "description": "Build reliable <strong>async</strong> apps.", "age": "2 days ago"},
+                {"title": "async-std", "url": "https://async.rs", "description": "Async version of std."}
+            ]
+        }
+    }"#;
+
+    const SEARXNG_FIXTURE: &str = r#"{
+        "query": "rust async",
+        "number_of_results": 0,
+        "results": [
+            {"title": "Async Rust", "url": "https://rust-lang.github.io/async-book/", "content": "The async book.", "engine": "duckduckgo"},
+            {"title": "Tokio", "url": "https://tokio.rs", "engine": "bing"}
+        ],
+        "answers": [],
+        "suggestions": ["rust async await"]
+    }"#;
+
+    fn fixture(s: &str) -> serde_json::Value {
+        serde_json::from_str(s).unwrap()
+    }
+
+    fn config(provider: &str, api_key: &str, base_url: Option<&str>) -> WebSearchConfig {
+        WebSearchConfig {
+            provider: provider.into(),
+            api_key: api_key.into(),
+            base_url: base_url.map(String::from),
+            cache_ttl_secs: 600,
+            cache_max_entries: 128,
+        }
+    }
+
+    /// Provider that counts backend calls and can be switched to failing.
+    #[derive(Clone, Default)]
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl SearchProvider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn search<'a>(
+            &'a self,
+            query: &'a str,
+            max_results: usize,
+        ) -> BoxFuture<'a, Result<Vec<SearchResult>>> {
+            Box::pin(async move {
+                let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+                if self.failing.load(Ordering::SeqCst) {
+                    return Err(RyvosError::ToolExecution {
+                        tool: "web_search".into(),
+                        message: "search API returned 429 Too Many Requests".into(),
+                    });
+                }
+                Ok((0..max_results)
+                    .map(|i| SearchResult {
+                        title: format!("{} #{} (call {})", query, i, n),
+                        url: format!("https://example.com/{}", i),
+                        snippet: String::new(),
+                    })
+                    .collect())
+            })
+        }
+    }
+
+    fn search(query: &str) -> serde_json::Value {
+        json!({ "query": query, "max_results": 2 })
+    }
+
+    #[test]
+    fn parses_tavily_results() {
+        let results = TavilyProvider::parse(&fixture(TAVILY_FIXTURE), 5);
+        assert_eq!(
+            results,
+            vec![
+                SearchResult {
+                    title: "Async Rust".into(),
+                    url: "https://rust-lang.github.io/async-book/".into(),
+                    snippet: "The async book.".into(),
+                },
+                SearchResult {
+                    title: "Tokio".into(),
+                    url: "https://tokio.rs".into(),
+                    snippet: "An asynchronous runtime.".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_brave_results_without_markup() {
+        let results = BraveProvider::parse(&fixture(BRAVE_FIXTURE), 5);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Tokio - An async runtime");
+        assert_eq!(results[0].url, "https://tokio.rs");
+        assert_eq!(results[0].snippet, "Build reliable async apps.");
+    }
+
+    #[test]
+    fn parses_searxng_results() {
+        let results = SearxngProvider::parse(&fixture(SEARXNG_FIXTURE), 5);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].snippet, "The async book.");
+        assert_eq!(results[1].url, "https://tokio.rs");
+        assert_eq!(results[1].snippet, "");
+    }
+
+    #[test]
+    fn results_are_capped() {
+        assert_eq!(TavilyProvider::parse(&fixture(TAVILY_FIXTURE), 1).len(), 1);
+        assert_eq!(BraveProvider::parse(&fixture(BRAVE_FIXTURE), 1).len(), 1);
+        assert_eq!(
+            SearxngProvider::parse(&fixture(SEARXNG_FIXTURE), 1).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn searxng_needs_no_api_key() {
+        let provider =
+            provider_from_config(&config("searxng", "", Some("http://localhost:8888/"))).unwrap();
+        assert_eq!(provider.name(), "searxng");
+        assert!(provider_from_config(&config("searxng", "", None)).is_err());
+    }
+
+    #[test]
+    fn keyed_providers_require_api_key() {
+        assert!(provider_from_config(&config("brave", "", None)).is_err());
+        assert!(provider_from_config(&config("tavily", "", None)).is_err());
+        let brave = provider_from_config(&config("Brave", "key", None)).unwrap();
+        assert_eq!(brave.name(), "brave");
+        assert!(provider_from_config(&config("bing", "key", None)).is_err());
+    }
+
+    #[tokio::test]
+    async fn repeated_query_within_ttl_is_cached() {
+        let provider = CountingProvider::default();
+        let tool = WebSearchTool::new(Box::new(provider.clone()));
+
+        let first = tool
+            .execute(search("Rust async"), test_tool_context())
+            .await
+            .unwrap();
+        let second = tool
+            .execute(search("  rust   ASYNC "), test_tool_context())
+            .await
+            .unwrap();
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+        assert!(first.content.contains("(call 1)"));
+        assert!(second.content.contains("(call 1)"));
+
+        // A larger result count than was fetched goes back to the backend
+        tool.execute(
+            json!({ "query": "rust async", "max_results": 5 }),
+            test_tool_context(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_requeried() {
+        let provider = CountingProvider::default();
+        let tool =
+            WebSearchTool::new(Box::new(provider.clone())).with_cache(Duration::from_millis(20), 8);
+
+        tool.execute(search("tokio"), test_tool_context())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let result = tool
+            .execute(search("tokio"), test_tool_context())
+            .await
+            .unwrap();
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        assert!(result.content.contains("(call 2)"));
+    }
+
+    #[tokio::test]
+    async fn stale_results_cover_backend_failures() {
+        let provider = CountingProvider::default();
+        let tool =
+            WebSearchTool::new(Box::new(provider.clone())).with_cache(Duration::from_millis(1), 8);
+
+        tool.execute(search("tokio"), test_tool_context())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        provider.failing.store(true, Ordering::SeqCst);
+
+        let result = tool
+            .execute(search("tokio"), test_tool_context())
+            .await
+            .unwrap();
+        assert!(result.content.contains("(call 1)"));
+        assert!(tool
+            .execute(search("uncached"), test_tool_context())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn cache_evicts_oldest_when_full() {
+        let mut cache = SearchCache::new(Duration::from_secs(60), 2);
+        for key in ["a", "b", "c"] {
+            cache.insert(key.into(), vec![], 5);
+        }
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get("a", 5, false).is_none());
+        assert!(cache.get("c", 5, false).is_some());
+    }
+}
@@ -1,18 +1,33 @@
 use futures::future::BoxFuture;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::debug;
 
 use ryvos_core::error::{Result, RyvosError};
 use ryvos_core::traits::Tool;
 use ryvos_core::types::{ToolContext, ToolResult};
 
-pub struct WriteTool;
+use super::preview::{self, Change, StagedChanges};
+
+#[derive(Default)]
+pub struct WriteTool {
+    staged: Arc<StagedChanges>,
+}
+
+impl WriteTool {
+    /// Stage previews in `staged`, where `apply_change` finds them.
+    pub fn new(staged: Arc<StagedChanges>) -> Self {
+        Self { staged }
+    }
+}
 
 #[derive(Deserialize)]
 struct WriteInput {
     file_path: String,
     content: String,
+    #[serde(default)]
+    preview: bool,
 }
 
 impl Tool for WriteTool {
@@ -25,7 +40,7 @@ impl Tool for WriteTool {
     }
 
     fn description(&self) -> &str {
-        "Write content to a file. Creates the file and parent directories if they don't exist. Overwrites existing content."
+        "Write content to a file. Creates the file and parent directories if they don't exist. Overwrites existing content. With preview: true, returns a diff and a change id for apply_change instead of writing."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -39,6 +54,11 @@ impl Tool for WriteTool {
                 "content": {
                     "type": "string",
                     "description": "The content to write to the file"
+                },
+                "preview": {
+                    "type": "boolean",
+                    "description": "Return a unified diff and a change id without writing (default: false)",
+                    "default": false
                 }
             },
             "required": ["file_path", "content"]
@@ -55,24 +75,23 @@ impl Tool for WriteTool {
                 .map_err(|e| RyvosError::ToolValidation(e.to_string()))?;
 
             let path = resolve_path(&params.file_path, &ctx.working_dir);
-            debug!(path = %path.display(), "Writing file");
-
-            // Create parent directories
-            if let Some(parent) = path.parent() {
-                tokio::fs::create_dir_all(parent)
-                    .await
-                    .map_err(|e| RyvosError::ToolExecution {
-                        tool: "write".to_string(),
-                        message: format!("Failed to create directories: {}", e),
-                    })?;
+            debug!(path = %path.display(), preview = params.preview, "Writing file");
+
+            if params.preview {
+                let before = preview::read_existing(&path, "write").await?;
+                let diff = preview::unified_diff(&path, before.as_deref(), &params.content);
+                let id = self.staged.stage(
+                    &ctx.session_id,
+                    Change::File {
+                        path,
+                        before,
+                        after: params.content,
+                    },
+                );
+                return Ok(preview::preview_result(&id, &diff));
             }
 
-            tokio::fs::write(&path, &params.content)
-                .await
-                .map_err(|e| RyvosError::ToolExecution {
-                    tool: "write".to_string(),
-                    message: format!("{}: {}", path.display(), e),
-                })?;
+            preview::write_file(&path, &params.content, "write").await?;
 
             Ok(ToolResult::success(format!(
                 "File written successfully: {}",
@@ -103,7 +122,7 @@ mod tests {
         let file_path = dir.path().join("output.txt");
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = WriteTool;
+        let tool = WriteTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "content": "hello from test"
@@ -123,7 +142,7 @@ mod tests {
         let file_path = dir.path().join("a/b/c/deep.txt");
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = WriteTool;
+        let tool = WriteTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "content": "deep content"
@@ -141,7 +160,7 @@ mod tests {
         std::fs::write(&file_path, "old content").unwrap();
 
         let ctx = test_tool_context_with_dir(dir.path().to_path_buf());
-        let tool = WriteTool;
+        let tool = WriteTool::default();
         let input = serde_json::json!({
             "file_path": file_path.to_str().unwrap(),
             "content": "new content"
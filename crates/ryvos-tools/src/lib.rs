@@ -12,4 +12,4 @@
 pub mod builtin;
 pub mod registry;
 
-pub use registry::ToolRegistry;
+pub use registry::{ToolInfo, ToolRegistry};
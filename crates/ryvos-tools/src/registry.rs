@@ -1,19 +1,43 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use serde::Serialize;
+
 use ryvos_core::error::{Result, RyvosError};
+use ryvos_core::security::SecurityTier;
 use ryvos_core::traits::Tool;
-use ryvos_core::types::{ToolContext, ToolDefinition, ToolResult};
+use ryvos_core::types::{SessionId, ToolContext, ToolDefinition, ToolResult};
+
+/// Source of registry epochs. Shared by all registries so a registry that
+/// replaces another never reuses its epoch.
+static NEXT_EPOCH: AtomicU64 = AtomicU64::new(1);
+
+fn next_epoch() -> u64 {
+    NEXT_EPOCH.fetch_add(1, Ordering::Relaxed)
+}
+
+/// One tool in the catalog served by `ryvos tools --json` and
+/// `GET /api/tools`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub description: String,
+    pub tier: SecurityTier,
+    pub input_schema: serde_json::Value,
+}
 
 /// Registry of available tools.
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    epoch: u64,
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            epoch: next_epoch(),
         }
     }
 
@@ -21,11 +45,22 @@ impl ToolRegistry {
     pub fn register(&mut self, tool: impl Tool) {
         let name = tool.name().to_string();
         self.tools.insert(name, Arc::new(tool));
+        self.epoch = next_epoch();
     }
 
     /// Unregister a tool by name.
     pub fn unregister(&mut self, name: &str) -> bool {
-        self.tools.remove(name).is_some()
+        let removed = self.tools.remove(name).is_some();
+        if removed {
+            self.epoch = next_epoch();
+        }
+        removed
+    }
+
+    /// Changes whenever the set of tools does, so callers can cache
+    /// [`definitions`](Self::definitions) and rebuild only when it moves.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
     }
 
     /// Get a tool by name.
@@ -33,6 +68,17 @@ impl ToolRegistry {
         self.tools.get(name).cloned()
     }
 
+    /// Create a registry holding only the named tools. Unknown names are skipped.
+    pub fn subset(&self, names: &[String]) -> Self {
+        Self {
+            tools: names
+                .iter()
+                .filter_map(|n| self.tools.get(n).map(|t| (n.clone(), t.clone())))
+                .collect(),
+            epoch: next_epoch(),
+        }
+    }
+
     /// List all registered tools.
     pub fn list(&self) -> Vec<&str> {
         self.tools.keys().map(|s| s.as_str()).collect()
@@ -50,6 +96,23 @@ impl ToolRegistry {
             .collect()
     }
 
+    /// Every registered tool with its tier and input schema, sorted by name.
+    /// Includes MCP and skill tools once they are registered.
+    pub fn catalog(&self) -> Vec<ToolInfo> {
+        let mut catalog: Vec<ToolInfo> = self
+            .tools
+            .values()
+            .map(|t| ToolInfo {
+                name: t.name().to_string(),
+                description: t.description().to_string(),
+                tier: t.tier(),
+                input_schema: t.input_schema(),
+            })
+            .collect();
+        catalog.sort_by(|a, b| a.name.cmp(&b.name));
+        catalog
+    }
+
     /// Execute a tool by name.
     pub async fn execute(
         &self,
@@ -72,6 +135,11 @@ impl ToolRegistry {
         }
     }
 
+    /// Tell every tool that a session has ended.
+    pub async fn end_session(&self, session_id: &SessionId) {
+        futures::future::join_all(self.tools.values().map(|t| t.end_session(session_id))).await;
+    }
+
     /// Create a registry with all built-in tools registered.
     pub fn with_builtins() -> Self {
         let mut registry = Self::new();
@@ -79,15 +147,20 @@ impl ToolRegistry {
         // ── Original 12 tools ───────────────────────────────────
         registry.register(crate::builtin::bash::BashTool);
         registry.register(crate::builtin::read::ReadTool);
-        registry.register(crate::builtin::write::WriteTool);
-        registry.register(crate::builtin::edit::EditTool);
+        // Previews from write, edit and apply_patch wait here for apply_change
+        let staged = Arc::new(crate::builtin::preview::StagedChanges::default());
+        registry.register(crate::builtin::write::WriteTool::new(staged.clone()));
+        registry.register(crate::builtin::edit::EditTool::new(staged.clone()));
         registry.register(crate::builtin::memory_search::MemorySearchTool);
         registry.register(crate::builtin::memory_write::MemoryWriteTool);
         registry.register(crate::builtin::spawn_agent::SpawnAgentTool);
         registry.register(crate::builtin::glob::GlobTool);
         registry.register(crate::builtin::grep::GrepTool);
         registry.register(crate::builtin::web_fetch::WebFetchTool);
-        registry.register(crate::builtin::apply_patch::ApplyPatchTool);
+        registry.register(crate::builtin::apply_patch::ApplyPatchTool::new(
+            staged.clone(),
+        ));
+        registry.register(crate::builtin::preview::ApplyChangeTool::new(staged));
 
         // ── Sessions (5) ────────────────────────────────────────
         registry.register(crate::builtin::sessions::SessionListTool);
@@ -96,10 +169,11 @@ impl ToolRegistry {
         registry.register(crate::builtin::sessions::SessionSpawnTool);
         registry.register(crate::builtin::sessions::SessionStatusTool);
 
-        // ── Memory (3) ──────────────────────────────────────────
+        // ── Memory (4) ──────────────────────────────────────────
         registry.register(crate::builtin::memory::MemoryGetTool);
         registry.register(crate::builtin::memory::DailyLogWriteTool);
         registry.register(crate::builtin::memory::MemoryDeleteTool);
+        registry.register(crate::builtin::scratchpad::ScratchpadTool);
 
         // ── File System (9) ─────────────────────────────────────
         registry.register(crate::builtin::filesystem::FileInfoTool);
@@ -132,12 +206,14 @@ impl ToolRegistry {
         registry.register(crate::builtin::network::DnsLookupTool);
         registry.register(crate::builtin::network::NetworkCheckTool);
 
-        // ── System (5) ──────────────────────────────────────────
+        // ── System (7) ──────────────────────────────────────────
         registry.register(crate::builtin::system::ProcessListTool);
         registry.register(crate::builtin::system::ProcessKillTool);
+        registry.register(crate::builtin::background::BackgroundProcessTool::default());
         registry.register(crate::builtin::system::EnvGetTool);
         registry.register(crate::builtin::system::SystemInfoTool);
         registry.register(crate::builtin::system::DiskUsageTool);
+        registry.register(crate::builtin::system::NowTool::default());
 
         // ── Data/Transform (8) ──────────────────────────────────
         registry.register(crate::builtin::data::JsonQueryTool);
@@ -231,6 +307,52 @@ mod tests {
         assert!(!registry.unregister("removable"));
     }
 
+    #[test]
+    fn registry_epoch_moves_only_on_mutation() {
+        let mut registry = ToolRegistry::new();
+        let start = registry.epoch();
+        registry.definitions();
+        registry.get("missing");
+        assert!(!registry.unregister("missing"));
+        assert_eq!(registry.epoch(), start);
+
+        registry.register(MockTool::new("a"));
+        let registered = registry.epoch();
+        assert_ne!(registered, start);
+        assert!(registry.unregister("a"));
+        assert_ne!(registry.epoch(), registered);
+
+        // A fresh registry never reuses an epoch
+        assert_ne!(ToolRegistry::new().epoch(), registry.epoch());
+    }
+
+    #[test]
+    fn catalog_lists_builtins_with_schema_and_tier() {
+        let registry = ToolRegistry::with_builtins();
+        let catalog = registry.catalog();
+        assert_eq!(catalog.len(), registry.list().len());
+        assert!(catalog.windows(2).all(|w| w[0].name < w[1].name));
+
+        let bash = catalog.iter().find(|t| t.name == "bash").unwrap();
+        assert_eq!(bash.tier, SecurityTier::T2);
+        assert!(!bash.description.is_empty());
+        let json = serde_json::to_value(bash).unwrap();
+        assert_eq!(json["tier"], "t2");
+        assert_eq!(
+            json["input_schema"]["properties"]["command"]["type"],
+            "string"
+        );
+    }
+
+    #[test]
+    fn registry_subset_keeps_named_tools() {
+        let mut registry = ToolRegistry::new();
+        registry.register(MockTool::new("alpha"));
+        registry.register(MockTool::new("beta"));
+        let subset = registry.subset(&["beta".into(), "missing".into()]);
+        assert_eq!(subset.list(), vec!["beta"]);
+    }
+
     #[test]
     fn registry_list_returns_all_names() {
         let mut registry = ToolRegistry::new();
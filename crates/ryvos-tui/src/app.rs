@@ -41,7 +41,18 @@ pub struct App {
     pub scroll_offset: usize,
     pub total_input_tokens: u64,
     pub total_output_tokens: u64,
+    /// Size of the conversation after the last run, compaction included.
+    pub context_tokens: u64,
     pub tick_count: usize,
+    /// Follow mode: show another process's session, send nothing to it.
+    pub read_only: bool,
+}
+
+/// The in-process agent the app sends input to. Absent in follow mode.
+pub struct LocalAgent {
+    pub runtime: Arc<AgentRuntime>,
+    pub event_bus: Arc<EventBus>,
+    pub broker: Option<Arc<ApprovalBroker>>,
 }
 
 impl App {
@@ -59,7 +70,39 @@ impl App {
             scroll_offset: 0,
             total_input_tokens: 0,
             total_output_tokens: 0,
+            context_tokens: 0,
             tick_count: 0,
+            read_only: false,
+        }
+    }
+
+    /// An app that watches `session_id` without taking input.
+    pub fn follow(session_id: SessionId) -> Self {
+        Self {
+            read_only: true,
+            ..Self::new(session_id)
+        }
+    }
+
+    /// Show a system note.
+    pub fn notice(&mut self, text: String) {
+        self.messages.push(DisplayMessage {
+            role: MessageRole::System,
+            text,
+        });
+    }
+
+    /// In follow mode, drop actions that would send anything to the agent.
+    pub fn gate_input(&mut self, action: InputAction) -> InputAction {
+        if !self.read_only {
+            return action;
+        }
+        match action {
+            InputAction::Submit(_) | InputAction::Approve(_) | InputAction::Deny(..) => {
+                self.notice("Follow mode is read-only; /quit to exit.".to_string());
+                InputAction::None
+            }
+            other => other,
         }
     }
 
@@ -88,6 +131,18 @@ impl App {
                     text: format!("Running: {}", name),
                 });
             }
+            AgentEvent::ToolProgress { name, chunk } => {
+                // Show the latest output line on the "Running" entry
+                let Some(line) = chunk.lines().rev().find(|l| !l.trim().is_empty()) else {
+                    return;
+                };
+                if let Some(last) = self.messages.last_mut() {
+                    if matches!(last.role, MessageRole::Tool) && last.text.starts_with("Running: ")
+                    {
+                        last.text = format!("Running: {} | {}", name, line.trim());
+                    }
+                }
+            }
             AgentEvent::ToolEnd { name, result } => {
                 self.active_tool = None;
                 let status = if result.is_error { "ERROR" } else { "ok" };
@@ -107,12 +162,14 @@ impl App {
             AgentEvent::RunComplete {
                 input_tokens,
                 output_tokens,
+                context_tokens,
                 ..
             } => {
                 self.is_running = false;
                 self.active_tool = None;
                 self.total_input_tokens += input_tokens;
                 self.total_output_tokens += output_tokens;
+                self.context_tokens = context_tokens;
 
                 // Flush remaining streaming text
                 if !self.streaming_text.is_empty() {
@@ -124,6 +181,23 @@ impl App {
                 }
                 self.scroll_offset = 0;
             }
+            AgentEvent::TurnLimitReached { max_turns, .. } => {
+                // Flush the partial answer so the notice follows it
+                if !self.streaming_text.is_empty() {
+                    let text = std::mem::take(&mut self.streaming_text);
+                    self.messages.push(DisplayMessage {
+                        role: MessageRole::Assistant,
+                        text,
+                    });
+                }
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::System,
+                    text: format!(
+                        "[TURN LIMIT] Stopped after {} turns; the answer may be incomplete",
+                        max_turns
+                    ),
+                });
+            }
             AgentEvent::RunError { error } => {
                 self.is_running = false;
                 self.active_tool = None;
@@ -147,12 +221,32 @@ impl App {
                 });
             }
             AgentEvent::ApprovalResolved { .. } => {}
+            AgentEvent::ApprovalTimedOut {
+                request, action, ..
+            } => {
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::System,
+                    text: format!("[APPROVAL TIMEOUT] {}: {}", request.tool_name, action),
+                });
+            }
             AgentEvent::ToolBlocked { name, tier, reason } => {
                 self.messages.push(DisplayMessage {
                     role: MessageRole::Error,
                     text: format!("[BLOCKED] {} ({}): {}", name, tier, reason),
                 });
             }
+            AgentEvent::PromptInjectionDetected {
+                name,
+                label,
+                in_output,
+                ..
+            } => {
+                let place = if in_output { "output" } else { "input" };
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::System,
+                    text: format!("[INJECTION?] {} {}: {}", name, place, label),
+                });
+            }
             AgentEvent::TurnComplete { .. } => {}
             AgentEvent::CronFired { .. } => {}
             AgentEvent::GuardianStall {
@@ -176,19 +270,38 @@ impl App {
                     text: format!("[GUARDIAN] Doom loop: {} x{}", tool_name, consecutive_calls),
                 });
             }
+            AgentEvent::GuardianRepeatedAnswer {
+                repeats, action, ..
+            } => {
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::System,
+                    text: format!("[GUARDIAN] Repeated answer x{}: {}", repeats, action),
+                });
+            }
             AgentEvent::GuardianBudgetAlert {
                 used_tokens,
                 budget_tokens,
+                used_usd,
+                budget_usd,
                 is_hard_stop,
                 ..
             } => {
                 let kind = if is_hard_stop { "HARD STOP" } else { "warning" };
-                self.messages.push(DisplayMessage {
-                    role: MessageRole::System,
-                    text: format!(
+                let text = match (used_usd, budget_usd) {
+                    (Some(used), Some(budget)) => {
+                        format!(
+                            "[GUARDIAN] Cost budget {}: ${:.2}/${:.2}",
+                            kind, used, budget
+                        )
+                    }
+                    _ => format!(
                         "[GUARDIAN] Budget {}: {}/{} tokens",
                         kind, used_tokens, budget_tokens
                     ),
+                };
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::System,
+                    text,
                 });
             }
             AgentEvent::GoalEvaluated { evaluation, .. } => {
@@ -294,10 +407,40 @@ impl App {
                     ),
                 });
             }
+            AgentEvent::SubAgent { depth, event, .. } => {
+                // Only tool activity; the sub-agent's text comes back as a tool result
+                let indent = "  ".repeat(depth as usize);
+                let text = match *event {
+                    AgentEvent::ToolStart { name, .. } => format!("{}[sub-agent] {}", indent, name),
+                    AgentEvent::ToolEnd { name, result } if result.is_error => {
+                        format!("{}[sub-agent] {}: ERROR", indent, name)
+                    }
+                    _ => return,
+                };
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::Tool,
+                    text,
+                });
+            }
+            AgentEvent::ContextNearLimit {
+                context_tokens,
+                limit_tokens,
+                ..
+            } => {
+                self.messages.push(DisplayMessage {
+                    role: MessageRole::System,
+                    text: format!(
+                        "[CONTEXT] ~{} of {} tokens used and compaction is off",
+                        context_tokens, limit_tokens
+                    ),
+                });
+            }
             AgentEvent::GuardianHint { .. }
             | AgentEvent::UsageUpdate { .. }
             | AgentEvent::DecisionMade { .. }
             | AgentEvent::CronJobComplete { .. }
+            | AgentEvent::ChannelConnected { .. }
+            | AgentEvent::ChannelDisconnected { .. }
             | AgentEvent::BudgetWarning { .. }
             | AgentEvent::BudgetExceeded { .. } => {}
         }
@@ -307,14 +450,11 @@ impl App {
 /// Main app loop.
 pub async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-    runtime: Arc<AgentRuntime>,
-    event_bus: Arc<EventBus>,
-    session_id: SessionId,
-    broker: Option<Arc<ApprovalBroker>>,
+    mut app: App,
+    mut events: EventLoop,
+    agent: Option<LocalAgent>,
 ) -> anyhow::Result<()> {
-    let mut app = App::new(session_id.clone());
-    let agent_rx = event_bus.subscribe();
-    let mut events = EventLoop::new(agent_rx);
+    let broker = agent.as_ref().and_then(|a| a.broker.clone());
 
     loop {
         terminal.draw(|f| ui::draw(f, &app))?;
@@ -323,7 +463,7 @@ pub async fn run_app(
             match event {
                 TuiEvent::Key(key) => {
                     let action = app.input.handle_key(key);
-                    match action {
+                    match app.gate_input(action) {
                         InputAction::Quit => break,
                         InputAction::Clear => {
                             app.messages.clear();
@@ -333,6 +473,9 @@ pub async fn run_app(
                             });
                         }
                         InputAction::Submit(text) => {
+                            let Some(ref agent) = agent else {
+                                continue;
+                            };
                             if app.is_running {
                                 continue;
                             }
@@ -343,9 +486,9 @@ pub async fn run_app(
                             app.scroll_offset = 0;
 
                             // Spawn agent run
-                            let rt = runtime.clone();
-                            let sid = session_id.clone();
-                            let eb = event_bus.clone();
+                            let rt = agent.runtime.clone();
+                            let sid = app.session_id.clone();
+                            let eb = agent.event_bus.clone();
                             tokio::spawn(async move {
                                 if let Err(e) = rt.run(&sid, &text).await {
                                     eb.publish(AgentEvent::RunError {
@@ -433,6 +576,7 @@ pub async fn run_app(
                 TuiEvent::Agent(event) => {
                     app.handle_agent_event(event);
                 }
+                TuiEvent::Notice(text) => app.notice(text),
                 TuiEvent::Tick => {
                     app.tick_count += 1;
                 }
@@ -443,3 +587,42 @@ pub async fn run_app(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn type_line(app: &mut App, line: &str) -> InputAction {
+        for c in line.chars() {
+            app.input
+                .handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        let action = app
+            .input
+            .handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        app.gate_input(action)
+    }
+
+    #[test]
+    fn follow_mode_ignores_input_but_still_quits() {
+        let mut app = App::follow(SessionId::from_string("0123456789"));
+        assert!(matches!(type_line(&mut app, "hello"), InputAction::None));
+        assert!(matches!(
+            type_line(&mut app, "/approve abc"),
+            InputAction::None
+        ));
+        assert!(app.messages.last().unwrap().text.contains("read-only"));
+        assert!(!app
+            .messages
+            .iter()
+            .any(|m| matches!(m.role, MessageRole::User)));
+        assert!(matches!(type_line(&mut app, "/quit"), InputAction::Quit));
+
+        let mut local = App::new(SessionId::from_string("0123456789"));
+        assert!(matches!(
+            type_line(&mut local, "hello"),
+            InputAction::Submit(text) if text == "hello"
+        ));
+    }
+}
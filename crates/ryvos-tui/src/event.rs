@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use crossterm::event::{self, Event as CrosstermEvent};
 use ryvos_core::types::AgentEvent;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 
 /// Events that drive the TUI loop.
 pub enum TuiEvent {
@@ -12,20 +12,38 @@ pub enum TuiEvent {
     Resize(u16, u16),
     /// An agent event from the EventBus.
     Agent(AgentEvent),
+    /// A note for the user, such as the follow connection's state.
+    Notice(String),
     /// Tick timer for animations (spinners, etc.).
     Tick,
 }
 
-/// Merged event loop: crossterm + EventBus + tick timer.
+/// Where agent activity comes from.
+enum AgentSource {
+    /// The in-process EventBus.
+    Bus(broadcast::Receiver<AgentEvent>),
+    /// Events relayed from a gateway in follow mode.
+    Remote(mpsc::Receiver<TuiEvent>),
+}
+
+/// Merged event loop: crossterm + agent events + tick timer.
 pub struct EventLoop {
-    agent_rx: broadcast::Receiver<AgentEvent>,
+    agent: AgentSource,
     tick_interval: Duration,
 }
 
 impl EventLoop {
     pub fn new(agent_rx: broadcast::Receiver<AgentEvent>) -> Self {
         Self {
-            agent_rx,
+            agent: AgentSource::Bus(agent_rx),
+            tick_interval: Duration::from_millis(100),
+        }
+    }
+
+    /// Take agent activity from a follow-mode relay instead of the bus.
+    pub fn remote(rx: mpsc::Receiver<TuiEvent>) -> Self {
+        Self {
+            agent: AgentSource::Remote(rx),
             tick_interval: Duration::from_millis(100),
         }
     }
@@ -43,15 +61,20 @@ impl EventLoop {
             }
         });
 
-        tokio::select! {
-            // Agent events
-            result = self.agent_rx.recv() => {
-                match result {
+        let agent = async {
+            match &mut self.agent {
+                AgentSource::Bus(rx) => match rx.recv().await {
                     Ok(evt) => Some(TuiEvent::Agent(evt)),
                     Err(broadcast::error::RecvError::Lagged(_)) => Some(TuiEvent::Tick),
                     Err(_) => None,
-                }
+                },
+                AgentSource::Remote(rx) => rx.recv().await,
             }
+        };
+
+        tokio::select! {
+            // Agent events
+            result = agent => result,
             // Crossterm events
             result = crossterm_poll => {
                 match result {
@@ -0,0 +1,350 @@
+//! Read-only follow mode: watch a session running in another process.
+//!
+//! `ryvos tui --session <id> --follow` connects to the gateway WebSocket of
+//! a running `ryvos daemon` or `ryvos serve`, subscribes to the session with
+//! `session.subscribe`, and rebuilds [`AgentEvent`]s from its event frames
+//! so the app renders them as if the run were local. A dropped connection
+//! is retried with backoff; on reconnect, `session.resume` replays what was
+//! missed while the gateway still holds it.
+//!
+//! The gateway's token or password travels in the `Authorization` header,
+//! never in the URL, so it stays out of proxy and access logs.
+
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
+
+use ryvos_core::config::GatewayConfig;
+use ryvos_core::types::{AgentEvent, SessionId, ToolResult};
+
+use crate::event::TuiEvent;
+
+/// Longest wait between reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The gateway WebSocket URL for `gw`. A wildcard bind address is reached
+/// through loopback.
+pub(crate) fn gateway_ws_url(gw: &GatewayConfig) -> String {
+    let bind = match gw.bind.rsplit_once(':') {
+        Some(("0.0.0.0", port)) => format!("127.0.0.1:{}", port),
+        Some(("[::]", port)) => format!("[::1]:{}", port),
+        _ => gw.bind.clone(),
+    };
+    format!("ws://{}/ws", bind)
+}
+
+/// The handshake request for `url`, carrying the gateway's token or
+/// password as a Bearer credential.
+pub(crate) fn connect_request(url: &str, gw: &GatewayConfig) -> anyhow::Result<Request> {
+    let mut request = url.into_client_request()?;
+    if let Some(secret) = gw.token.as_ref().or(gw.password.as_ref()) {
+        let value = HeaderValue::from_str(&format!("Bearer {}", secret))?;
+        request.headers_mut().insert("authorization", value);
+    }
+    Ok(request)
+}
+
+/// Stream the session's events into `tx` until the receiver is dropped,
+/// reconnecting whenever the gateway goes away.
+pub(crate) async fn follow_session(
+    gw: GatewayConfig,
+    session_id: SessionId,
+    tx: mpsc::Sender<TuiEvent>,
+) {
+    let url = gateway_ws_url(&gw);
+    let sid = session_id.to_string();
+    // Token and seq to resume from, once the gateway has confirmed a
+    // subscription; until then a (re)connect subscribes afresh.
+    let mut resume: Option<(String, u64)> = None;
+    let mut delay = Duration::from_secs(1);
+
+    loop {
+        let connected = match connect_request(&url, &gw) {
+            Ok(request) => tokio_tungstenite::connect_async(request)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        match connected {
+            Ok((ws, _)) => {
+                delay = Duration::from_secs(1);
+                let (mut ws_tx, mut ws_rx) = ws.split();
+                let mut token = String::new();
+                let mut last_seq = resume.as_ref().map(|(_, seq)| *seq).unwrap_or(0);
+
+                while let Some(Ok(msg)) = ws_rx.next().await {
+                    let Message::Text(text) = msg else { continue };
+                    let Ok(frame) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+
+                    // The first frame hands out this connection's resume token
+                    if frame["event"]["kind"] == "connected" {
+                        token = frame["event"]["data"]["resume_token"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string();
+                        let request = match resume.take() {
+                            Some((old, seq)) => json!({
+                                "type": "request",
+                                "id": "resume",
+                                "method": "session.resume",
+                                "params": {"resume_token": old, "session_id": sid, "last_seq": seq},
+                            }),
+                            None => subscribe_request(&sid),
+                        };
+                        if ws_tx
+                            .send(Message::Text(request.to_string().into()))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                        notice(
+                            &tx,
+                            format!("Following session {} (read-only)", short(&sid)),
+                        )
+                        .await;
+                        continue;
+                    }
+
+                    let frames: Vec<Value> = match frame["type"].as_str() {
+                        Some("event") => vec![frame],
+                        Some("response") if frame["id"] == "subscribe" => {
+                            // Live frames start after the seq the gateway
+                            // held when this connection subscribed
+                            let result = &frame["result"];
+                            if result["subscribed"] == true {
+                                let head = result["last_seq"].as_u64().unwrap_or(0);
+                                last_seq = last_seq.max(head);
+                                resume = Some((token.clone(), last_seq));
+                            }
+                            vec![]
+                        }
+                        Some("response") if frame["id"] == "resume" => {
+                            let result = &frame["result"];
+                            if let Some(seq) = result["last_seq"].as_u64() {
+                                last_seq = last_seq.max(seq);
+                                resume = Some((token.clone(), last_seq));
+                            }
+                            if let Some(error) = result["error"].as_str() {
+                                // Too late to replay: subscribe afresh
+                                notice(&tx, format!("Some events were missed: {}", error)).await;
+                                let request = subscribe_request(&sid);
+                                if ws_tx
+                                    .send(Message::Text(request.to_string().into()))
+                                    .await
+                                    .is_err()
+                                {
+                                    break;
+                                }
+                            }
+                            result["events"].as_array().cloned().unwrap_or_default()
+                        }
+                        _ => vec![],
+                    };
+                    for frame in frames {
+                        if frame["session_id"] != sid.as_str() {
+                            continue;
+                        }
+                        if let Some(seq) = frame["seq"].as_u64() {
+                            last_seq = last_seq.max(seq);
+                            if let Some((_, seq)) = resume.as_mut() {
+                                *seq = last_seq;
+                            }
+                        }
+                        if let Some(event) = frame_to_event(&frame) {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                notice(
+                    &tx,
+                    "Connection to the gateway lost; reconnecting".to_string(),
+                )
+                .await;
+            }
+            Err(e) => {
+                debug!(error = %e, url = %url, "Gateway connection failed");
+                notice(
+                    &tx,
+                    format!(
+                        "Cannot reach the gateway ({}); retrying in {}s",
+                        e,
+                        delay.as_secs()
+                    ),
+                )
+                .await;
+            }
+        }
+        if tx.is_closed() {
+            return;
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+fn subscribe_request(session_id: &str) -> Value {
+    json!({
+        "type": "request",
+        "id": "subscribe",
+        "method": "session.subscribe",
+        "params": {"session_id": session_id},
+    })
+}
+
+async fn notice(tx: &mpsc::Sender<TuiEvent>, text: String) {
+    let _ = tx.send(TuiEvent::Notice(text)).await;
+}
+
+fn short(session_id: &str) -> &str {
+    &session_id[..8.min(session_id.len())]
+}
+
+/// Rebuild what the app shows from one gateway event frame. Kinds the app
+/// has no use for give `None`.
+pub(crate) fn frame_to_event(frame: &Value) -> Option<TuiEvent> {
+    let event = &frame["event"];
+    let text = || event["text"].as_str().unwrap_or_default().to_string();
+    let tool = || event["tool"].as_str().unwrap_or_default().to_string();
+    let data = &event["data"];
+    let count = |key: &str| data[key].as_u64().unwrap_or(0);
+    let session_id = || SessionId::from_string(frame["session_id"].as_str().unwrap_or_default());
+
+    let agent_event = match event["kind"].as_str()? {
+        "run_started" => AgentEvent::RunStarted {
+            session_id: session_id(),
+        },
+        "text_delta" => AgentEvent::TextDelta(text()),
+        "tool_start" => AgentEvent::ToolStart {
+            name: tool(),
+            input: data.clone(),
+        },
+        "tool_progress" => AgentEvent::ToolProgress {
+            name: tool(),
+            chunk: text(),
+        },
+        "tool_end" => AgentEvent::ToolEnd {
+            name: tool(),
+            result: ToolResult {
+                content: data["content"].as_str().unwrap_or_default().to_string(),
+                is_error: data["is_error"].as_bool().unwrap_or(false),
+            },
+        },
+        "run_complete" => AgentEvent::RunComplete {
+            session_id: session_id(),
+            total_turns: count("total_turns") as usize,
+            input_tokens: count("input_tokens"),
+            output_tokens: count("output_tokens"),
+            context_tokens: count("context_tokens"),
+        },
+        "turn_limit_reached" => AgentEvent::TurnLimitReached {
+            session_id: session_id(),
+            max_turns: count("max_turns") as usize,
+        },
+        "run_error" => AgentEvent::RunError {
+            error: data["error"].as_str().unwrap_or_default().to_string(),
+        },
+        "approval_requested" => {
+            return Some(TuiEvent::Notice(format!(
+                "[APPROVAL REQUIRED] {}: \"{}\"",
+                data["tool_name"].as_str().unwrap_or_default(),
+                data["input_summary"].as_str().unwrap_or_default()
+            )));
+        }
+        _ => return None,
+    };
+    Some(TuiEvent::Agent(agent_event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{App, MessageRole};
+
+    fn frame(kind: &str, extra: Value) -> Value {
+        let mut event = json!({ "kind": kind });
+        event
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        json!({ "type": "event", "session_id": "s1", "seq": 1, "event": event })
+    }
+
+    #[test]
+    fn gateway_frames_render_in_the_app() {
+        let frames = [
+            frame("run_started", json!({})),
+            frame("text_delta", json!({ "text": "Checking the logs" })),
+            frame(
+                "tool_start",
+                json!({ "tool": "bash", "data": { "command": "ls" } }),
+            ),
+            frame(
+                "tool_end",
+                json!({ "tool": "bash", "data": { "content": "app.log", "is_error": false } }),
+            ),
+            frame("text_delta", json!({ "text": "All clear." })),
+            frame(
+                "run_complete",
+                json!({ "data": { "total_turns": 2, "input_tokens": 120, "output_tokens": 30, "context_tokens": 90 } }),
+            ),
+            frame("usage_update", json!({})),
+        ];
+
+        let mut app = App::follow(SessionId::from_string("s1-session"));
+        for frame in &frames {
+            match frame_to_event(frame) {
+                Some(TuiEvent::Agent(event)) => app.handle_agent_event(event),
+                Some(TuiEvent::Notice(text)) => app.notice(text),
+                _ => {}
+            }
+        }
+
+        let shown: Vec<&str> = app.messages[1..].iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(
+            shown,
+            [
+                "Checking the logs",
+                "Running: bash",
+                "[bash: ok] app.log",
+                "All clear."
+            ]
+        );
+        assert!(matches!(app.messages[1].role, MessageRole::Assistant));
+        assert!(!app.is_running);
+        assert_eq!(app.total_input_tokens, 120);
+        assert_eq!(app.context_tokens, 90);
+    }
+
+    #[test]
+    fn wildcard_binds_are_reached_over_loopback() {
+        let gw = GatewayConfig {
+            bind: "0.0.0.0:18789".into(),
+            token: Some("t0k".into()),
+            ..Default::default()
+        };
+        assert_eq!(gateway_ws_url(&gw), "ws://127.0.0.1:18789/ws");
+    }
+
+    #[test]
+    fn credentials_travel_in_the_header() {
+        let gw = GatewayConfig {
+            password: Some("hunter2".into()),
+            ..Default::default()
+        };
+        let request = connect_request(&gateway_ws_url(&gw), &gw).unwrap();
+        assert_eq!(request.uri().query(), None);
+        assert_eq!(request.headers()["authorization"], "Bearer hunter2");
+    }
+}
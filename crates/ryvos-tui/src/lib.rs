@@ -6,22 +6,29 @@
 //! - Approval commands (`/approve`, `/deny`) for human-in-the-loop
 //! - Token usage tracking and status bar
 //! - Scroll navigation and multi-line input
+//! - Read-only follow mode for sessions running in a daemon (see `follow`)
 //!
 //! The TUI multiplexes crossterm keyboard events with the agent EventBus,
 //! rendering both user input and background agent activity in real time.
 
 mod app;
 mod event;
+mod follow;
 mod input;
 mod ui;
 
 use std::sync::Arc;
 
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
 use ryvos_agent::approval::ApprovalBroker;
 use ryvos_agent::AgentRuntime;
+use ryvos_core::config::GatewayConfig;
 use ryvos_core::event::EventBus;
 use ryvos_core::types::SessionId;
 
+type Term = Terminal<CrosstermBackend<std::io::Stdout>>;
+
 /// Launch the terminal UI.
 pub async fn run_tui(
     runtime: Arc<AgentRuntime>,
@@ -29,7 +36,44 @@ pub async fn run_tui(
     session_id: SessionId,
     broker: Option<Arc<ApprovalBroker>>,
 ) -> anyhow::Result<()> {
-    // Enter raw mode
+    let events = event::EventLoop::new(event_bus.subscribe());
+    let agent = app::LocalAgent {
+        runtime,
+        event_bus,
+        broker,
+    };
+    let mut terminal = enter_terminal()?;
+    let result = app::run_app(
+        &mut terminal,
+        app::App::new(session_id),
+        events,
+        Some(agent),
+    )
+    .await;
+    leave_terminal(&mut terminal)?;
+    result
+}
+
+/// Launch the terminal UI read-only on a session running elsewhere,
+/// following its events through the WebSocket of the gateway `gw`.
+pub async fn follow_tui(gw: GatewayConfig, session_id: SessionId) -> anyhow::Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::channel(256);
+    let relay = tokio::spawn(follow::follow_session(gw, session_id.clone(), tx));
+
+    let mut terminal = enter_terminal()?;
+    let result = app::run_app(
+        &mut terminal,
+        app::App::follow(session_id),
+        event::EventLoop::remote(rx),
+        None,
+    )
+    .await;
+    leave_terminal(&mut terminal)?;
+    relay.abort();
+    result
+}
+
+fn enter_terminal() -> anyhow::Result<Term> {
     crossterm::terminal::enable_raw_mode()?;
     let mut stdout = std::io::stdout();
     crossterm::execute!(
@@ -37,13 +81,10 @@ pub async fn run_tui(
         crossterm::terminal::EnterAlternateScreen,
         crossterm::event::EnableMouseCapture
     )?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+}
 
-    let backend = ratatui::backend::CrosstermBackend::new(stdout);
-    let mut terminal = ratatui::Terminal::new(backend)?;
-
-    let result = app::run_app(&mut terminal, runtime, event_bus, session_id, broker).await;
-
-    // Restore terminal
+fn leave_terminal(terminal: &mut Term) -> anyhow::Result<()> {
     crossterm::terminal::disable_raw_mode()?;
     crossterm::execute!(
         terminal.backend_mut(),
@@ -51,6 +92,5 @@ pub async fn run_tui(
         crossterm::event::DisableMouseCapture
     )?;
     terminal.show_cursor()?;
-
-    result
+    Ok(())
 }
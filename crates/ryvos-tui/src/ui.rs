@@ -198,10 +198,11 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         format!(" {} Thinking...{}", spinner[idx], tool_info)
     } else {
         format!(
-            " Session: {} | Tokens: {}in/{}out | /quit to exit",
+            " Session: {} | Tokens: {}in/{}out | Context: {} | /quit to exit",
             &app.session_id.to_string()[..8],
             app.total_input_tokens,
-            app.total_output_tokens
+            app.total_output_tokens,
+            app.context_tokens
         )
     };
 
@@ -212,8 +213,13 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_input(f: &mut Frame, app: &App, area: Rect) {
+    let title = if app.read_only {
+        " Following (read-only) "
+    } else {
+        " Input "
+    };
     let input = Paragraph::new(app.input.buffer.as_str())
-        .block(Block::default().borders(Borders::ALL).title(" Input "))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .style(Style::default().fg(Color::White));
 
     f.render_widget(input, area);
@@ -1,6 +1,9 @@
+use std::path::Path;
 use std::str::FromStr;
 
-use ryvos_core::config::AppConfig;
+use dialoguer::Confirm;
+use ryvos_core::config::{AppConfig, GatewayConfig, StorageBackend};
+use ryvos_core::security::env_mask;
 
 struct CheckResult {
     label: String,
@@ -8,15 +11,29 @@ struct CheckResult {
     detail: String,
 }
 
-pub fn run_doctor(config: &AppConfig) {
+impl CheckResult {
+    /// The printed line, with secret env values masked.
+    fn line(&self) -> String {
+        let icon = if self.ok { "[OK]" } else { "[!!]" };
+        format!(
+            "  {} {}: {}",
+            icon,
+            self.label,
+            env_mask().mask(&self.detail)
+        )
+    }
+}
+
+pub async fn run_doctor(config: &AppConfig) {
     let checks = vec![
         check_api_key(config),
         check_workspace(config),
-        check_database(config),
+        check_database(config).await,
         check_channels(config),
         check_cron(config),
         check_mcp(config),
         check_security(config),
+        check_gateway(config),
     ];
 
     // Print results
@@ -24,8 +41,7 @@ pub fn run_doctor(config: &AppConfig) {
     let mut fail_count = 0;
 
     for check in &checks {
-        let icon = if check.ok { "[OK]" } else { "[!!]" };
-        println!("  {} {}: {}", icon, check.label, check.detail);
+        println!("{}", check.line());
         if check.ok {
             ok_count += 1;
         } else {
@@ -41,8 +57,9 @@ fn check_api_key(config: &AppConfig) -> CheckResult {
     let has_key = config
         .model
         .api_key
-        .as_ref()
-        .is_some_and(|k| !k.is_empty() && !k.starts_with("${"));
+        .iter()
+        .chain(&config.model.api_keys)
+        .any(|k| !k.is_empty() && !k.starts_with("${"));
     let provider = &config.model.provider;
     let needs_key = provider != "ollama";
 
@@ -90,8 +107,28 @@ fn check_workspace(config: &AppConfig) -> CheckResult {
     }
 }
 
-fn check_database(config: &AppConfig) -> CheckResult {
+async fn check_database(config: &AppConfig) -> CheckResult {
     let ws = config.workspace_dir();
+    if config.storage.backend == StorageBackend::Postgres {
+        // Connect and read, so a reachable server with the wrong
+        // credentials or database fails here too
+        let probe = async {
+            let store = ryvos_memory::open_session_store(&config.storage, &ws).await?;
+            store.list_sessions(1).await
+        };
+        return match probe.await {
+            Ok(_) => CheckResult {
+                label: "Database".into(),
+                ok: true,
+                detail: "postgres (connected)".into(),
+            },
+            Err(e) => CheckResult {
+                label: "Database".into(),
+                ok: false,
+                detail: format!("postgres: {}", e),
+            },
+        };
+    }
     let db_path = ws.join("sessions.db");
     match ryvos_memory::SqliteStore::open(&db_path) {
         Ok(_) => CheckResult {
@@ -231,3 +268,308 @@ fn check_security(config: &AppConfig) -> CheckResult {
         detail: format!("auto-approve up to {}, {}", auto, deny_str),
     }
 }
+
+fn check_gateway(config: &AppConfig) -> CheckResult {
+    let Some(ref gw) = config.gateway else {
+        return CheckResult {
+            label: "Gateway".into(),
+            ok: true,
+            detail: "Not configured".into(),
+        };
+    };
+    if bind_port(&gw.bind).is_none() {
+        CheckResult {
+            label: "Gateway".into(),
+            ok: false,
+            detail: format!("bind '{}' is not host:port", gw.bind),
+        }
+    } else if !has_auth(gw) {
+        CheckResult {
+            label: "Gateway".into(),
+            ok: false,
+            detail: format!("{} has no token, password or API key", gw.bind),
+        }
+    } else {
+        CheckResult {
+            label: "Gateway".into(),
+            ok: true,
+            detail: gw.bind.clone(),
+        }
+    }
+}
+
+fn has_auth(gw: &GatewayConfig) -> bool {
+    let set = |v: &Option<String>| v.as_ref().is_some_and(|v| !v.is_empty());
+    set(&gw.token) || set(&gw.password) || !gw.api_keys.is_empty()
+}
+
+/// Port of a `host:port` bind address, `None` when it is not one.
+fn bind_port(bind: &str) -> Option<u16> {
+    let (host, port) = bind.rsplit_once(':')?;
+    if host.is_empty() {
+        return None;
+    }
+    port.parse().ok()
+}
+
+fn is_loopback(bind: &str) -> bool {
+    let host = bind.rsplit_once(':').map_or(bind, |(host, _)| host);
+    matches!(host, "127.0.0.1" | "localhost" | "[::1]")
+}
+
+/// Written to the workspace by `run_fix` when it has no `.ryvosignore`.
+const DEFAULT_RYVOSIGNORE: &str = "\
+# Paths Ryvos should leave alone, one gitignore-style pattern per line.
+.env
+.env.*
+*.pem
+*.key
+id_rsa*
+.git/
+node_modules/
+target/
+";
+
+/// Offer to fix what `run_doctor` flags: a missing config, workspace or
+/// `.ryvosignore`, and a gateway without auth or with a bad `bind`. Each change is confirmed
+/// first unless `assume_yes`. Returns how many fixes were applied.
+pub fn run_fix(config_path: &Path, assume_yes: bool) -> anyhow::Result<usize> {
+    let confirm = |prompt: String| -> anyhow::Result<bool> {
+        if assume_yes {
+            println!("  [fix] {}", prompt);
+            return Ok(true);
+        }
+        Ok(Confirm::new()
+            .with_prompt(prompt)
+            .default(true)
+            .interact()?)
+    };
+    let mut applied = 0;
+
+    if !config_path.exists() {
+        let prompt = format!(
+            "No config at {}. Write a minimal one?",
+            config_path.display()
+        );
+        if !confirm(prompt)? {
+            println!("  Skipped the remaining fixes: they need a config.");
+            return Ok(applied);
+        }
+        write_minimal_config(config_path)?;
+        applied += 1;
+    }
+    let config = AppConfig::load(config_path)?;
+
+    let ws = config.workspace_dir();
+    let prompt = format!(
+        "Set up the workspace {} and its memory/ directory?",
+        ws.display()
+    );
+    if !ws.join("memory").is_dir() && confirm(prompt)? {
+        crate::onboard::create_workspace_templates(&ws)?;
+        applied += 1;
+    }
+    let ignore = ws.join(".ryvosignore");
+    let prompt = format!("Write a default {}?", ignore.display());
+    if !ignore.exists() && confirm(prompt)? {
+        std::fs::create_dir_all(&ws)?;
+        std::fs::write(&ignore, DEFAULT_RYVOSIGNORE)?;
+        applied += 1;
+    }
+
+    // Edit the raw file, not the loaded config, so `${VAR}` references
+    // are written back unexpanded
+    let Some(gw) = config.gateway else {
+        return Ok(applied);
+    };
+    let mut table: toml::Table = std::fs::read_to_string(config_path)?.parse()?;
+    let mut edits = toml::Table::new();
+
+    let mut bind = gw.bind.clone();
+    if bind_port(&bind).is_none() {
+        let prompt = format!(
+            "Gateway bind '{}' is not host:port. Use 127.0.0.1:18789?",
+            bind
+        );
+        if confirm(prompt)? {
+            bind = "127.0.0.1:18789".into();
+            edits.insert("bind".into(), bind.clone().into());
+        }
+    }
+    if !has_auth(&gw) {
+        if confirm("The gateway has no token, password or API key. Generate a token?".into())? {
+            let token = format!("{:x}{:x}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+            println!("  Generated token: {}", token);
+            edits.insert("token".into(), token.into());
+        } else if !is_loopback(&bind) {
+            if let Some(port) = bind_port(&bind) {
+                let prompt = format!(
+                    "The gateway accepts anyone on {}. Bind to 127.0.0.1:{} instead?",
+                    bind, port
+                );
+                if confirm(prompt)? {
+                    edits.insert("bind".into(), format!("127.0.0.1:{}", port).into());
+                }
+            }
+        }
+    }
+    if edits.is_empty() {
+        return Ok(applied);
+    }
+
+    // With `include`, [gateway] may live in another file; a [gateway]
+    // table here is merged over it
+    let gateway = table
+        .entry("gateway")
+        .or_insert_with(|| toml::Table::new().into());
+    let Some(gateway) = gateway.as_table_mut() else {
+        anyhow::bail!(
+            "gateway in {} is a {}, not a table; fix it by hand",
+            config_path.display(),
+            gateway.type_str()
+        );
+    };
+    applied += edits.len();
+    gateway.extend(edits);
+    let backup = config_path.with_extension("toml.bak");
+    std::fs::copy(config_path, &backup)?;
+    std::fs::write(config_path, toml::to_string_pretty(&table)?)?;
+    println!(
+        "  Updated {} (previous version in {}; comments are not kept)",
+        config_path.display(),
+        backup.display()
+    );
+    Ok(applied)
+}
+
+/// Write a config that loads: the provider is picked from the API key in
+/// the environment, as when no config exists, and the workspace is the
+/// config's own directory.
+fn write_minimal_config(path: &Path) -> anyhow::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::env::current_dir()?,
+    };
+    std::fs::create_dir_all(&dir)?;
+
+    let model = if std::env::var("ANTHROPIC_API_KEY").is_ok() {
+        "provider = \"anthropic\"\nmodel_id = \"claude-sonnet-4-20250514\"\napi_key = \"${ANTHROPIC_API_KEY}\"\n"
+    } else if std::env::var("OPENAI_API_KEY").is_ok() {
+        "provider = \"openai\"\nmodel_id = \"gpt-4o\"\napi_key = \"${OPENAI_API_KEY}\"\n"
+    } else {
+        "provider = \"ollama\"\nmodel_id = \"llama3.2\"\nbase_url = \"http://localhost:11434/v1/chat/completions\"\n"
+    };
+    let workspace = toml::Value::String(dir.display().to_string());
+    let content = format!(
+        "# Written by `ryvos doctor --fix`; run `ryvos init` for a full setup.\n\n\
+         [agent]\nworkspace = {}\n\n[model]\n{}",
+        workspace, model
+    );
+    std::fs::write(path, content)?;
+    println!("  Wrote {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fix_yes_creates_config_and_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("ryvos").join("config.toml");
+
+        let applied = run_fix(&config_path, true).unwrap();
+        assert_eq!(applied, 3);
+
+        let config = AppConfig::load(&config_path).unwrap();
+        let ws = config.workspace_dir();
+        assert_eq!(ws, dir.path().join("ryvos"));
+        assert!(ws.join("memory").is_dir());
+        assert!(ws.join("BOOT.md").is_file());
+        assert_eq!(
+            std::fs::read_to_string(ws.join(".ryvosignore")).unwrap(),
+            DEFAULT_RYVOSIGNORE
+        );
+        assert!(config.gateway.is_none());
+
+        // A second run has nothing left to do
+        assert_eq!(run_fix(&config_path, true).unwrap(), 0);
+    }
+
+    #[test]
+    fn fix_yes_secures_the_gateway_and_keeps_env_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let original = format!(
+            "[agent]\nworkspace = {}\n\n[model]\nmodel_id = \"m\"\n\
+             api_key = \"${{RYVOS_DOCTOR_TEST_KEY}}\"\n\n[gateway]\nbind = \"0.0.0.0\"\n",
+            toml::Value::String(dir.path().display().to_string())
+        );
+        std::fs::write(&config_path, &original).unwrap();
+        std::fs::create_dir(dir.path().join("memory")).unwrap();
+        std::fs::write(dir.path().join(".ryvosignore"), "").unwrap();
+
+        assert_eq!(run_fix(&config_path, true).unwrap(), 2);
+
+        let raw = std::fs::read_to_string(&config_path).unwrap();
+        assert!(raw.contains("${RYVOS_DOCTOR_TEST_KEY}"));
+        let gw = AppConfig::load(&config_path).unwrap().gateway.unwrap();
+        assert_eq!(gw.bind, "127.0.0.1:18789");
+        assert!(gw.token.is_some_and(|t| !t.is_empty()));
+        assert_eq!(
+            std::fs::read_to_string(config_path.with_extension("toml.bak")).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn fix_yes_secures_a_gateway_from_an_included_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(
+            dir.path().join("gateway.toml"),
+            "[gateway]\nbind = \"0.0.0.0:9000\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &config_path,
+            format!(
+                "include = [\"gateway.toml\"]\n\n[agent]\nworkspace = {}\n\n\
+                 [model]\nmodel_id = \"m\"\n",
+                toml::Value::String(dir.path().display().to_string())
+            ),
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("memory")).unwrap();
+        std::fs::write(dir.path().join(".ryvosignore"), "").unwrap();
+
+        assert_eq!(run_fix(&config_path, true).unwrap(), 1);
+
+        let gw = AppConfig::load(&config_path).unwrap().gateway.unwrap();
+        assert_eq!(gw.bind, "0.0.0.0:9000");
+        assert!(gw.token.is_some_and(|t| !t.is_empty()));
+    }
+
+    #[test]
+    fn check_lines_mask_secret_env_values() {
+        std::env::set_var("RYVOS_DOCTOR_MASK_TOKEN", "tok-doctor-secret");
+        let check = CheckResult {
+            label: "Gateway".into(),
+            ok: false,
+            detail: "token tok-doctor-secret is weak".into(),
+        };
+        assert_eq!(check.line(), "  [!!] Gateway: token *** is weak");
+        std::env::remove_var("RYVOS_DOCTOR_MASK_TOKEN");
+    }
+
+    #[test]
+    fn bind_addresses() {
+        assert_eq!(bind_port("127.0.0.1:18789"), Some(18789));
+        assert_eq!(bind_port("[::1]:80"), Some(80));
+        assert_eq!(bind_port("0.0.0.0"), None);
+        assert_eq!(bind_port(":80"), None);
+        assert!(is_loopback("localhost:80"));
+        assert!(!is_loopback("0.0.0.0:80"));
+    }
+}
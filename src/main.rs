@@ -1,24 +1,33 @@
 mod doctor;
+mod mcp_config;
+mod ndjson;
 mod onboard;
+mod stream_output;
 mod viking_server;
 
 use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use tracing::{error, info, warn};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::EnvFilter;
 
-use ryvos_core::config::{AppConfig, HooksConfig, McpJsonConfig, RetryConfig};
+use ryvos_core::config::{AppConfig, HookPayload, HooksConfig, McpJsonConfig};
 use ryvos_core::event::EventBus;
+use ryvos_core::hooks::HookEvent;
+use ryvos_core::messages;
+use ryvos_core::overrides::SamplingOverride;
 use ryvos_core::security::ApprovalDecision;
 use ryvos_core::types::{AgentEvent, SessionId, ThinkingLevel};
 
+use ryvos_agent::approval_rules::{parse_duration, ArgPattern, RuleAction};
 use ryvos_agent::{AgentRuntime, ApprovalBroker, Guardian, SecurityGate};
-use ryvos_memory::SqliteStore;
 use ryvos_tools::ToolRegistry;
 
+use stream_output::{StreamOptions, StreamWriter};
+
 #[derive(Parser)]
 #[command(name = "ryvos", version, about = "Blazingly fast AI agent runtime")]
 struct Cli {
@@ -26,8 +35,9 @@ struct Cli {
     #[arg(short, long, default_value = "ryvos.toml")]
     config: PathBuf,
 
-    /// Session ID (auto-generated if not provided)
-    #[arg(short, long)]
+    /// Session ID; `last` continues the previous run's session and `new`
+    /// starts a fresh one (default: [agent] default_session, else fresh)
+    #[arg(short, long, global = true)]
     session: Option<String>,
 
     #[command(subcommand)]
@@ -40,16 +50,52 @@ enum Commands {
     Repl,
     /// Run a single prompt and exit
     Run {
+        /// Plain chat: offer the model no tools
+        #[arg(long)]
+        no_tools: bool,
+        /// Print the answer once the run finishes instead of streaming it
+        #[arg(long, alias = "raw")]
+        no_stream: bool,
+        /// Longest a partial line of streamed output is held back
+        #[arg(long, value_name = "MS", default_value_t = 50)]
+        flush_ms: u64,
+        /// Print every agent event to stdout as NDJSON instead of the reply,
+        /// and read approval answers as NDJSON from stdin
+        #[arg(long)]
+        events: bool,
+        /// Sampling temperature for this run only (0.0-2.0)
+        #[arg(long, value_name = "T")]
+        temperature: Option<f32>,
+        /// Nucleus sampling cutoff for this run only (0.0-1.0)
+        #[arg(long, value_name = "P")]
+        top_p: Option<f32>,
+        /// Save the model's responses to FILE, for `ryvos replay`
+        #[arg(long, value_name = "FILE")]
+        record: Option<PathBuf>,
         /// The prompt to send to the agent
         #[arg(trailing_var_arg = true)]
         prompt: Vec<String>,
     },
+    /// Route a task across the agents defined in [orchestrator]
+    Orchestrate {
+        /// The task to hand to the best-matching agent
+        #[arg(trailing_var_arg = true)]
+        task: Vec<String>,
+    },
     /// Show current configuration
     Config,
     /// Launch the terminal UI
-    Tui,
+    Tui {
+        /// Watch --session read-only as it runs in a daemon, through the gateway
+        #[arg(long, requires = "session")]
+        follow: bool,
+    },
     /// Start the WebSocket gateway server
-    Serve,
+    Serve {
+        /// Print the gateway's OpenAPI spec as JSON and exit
+        #[arg(long)]
+        print_openapi: bool,
+    },
     /// Run as a daemon with channel adapters (Telegram, Discord)
     Daemon {
         /// Also start the HTTP/WebSocket gateway
@@ -109,12 +155,49 @@ enum Commands {
         db: Option<PathBuf>,
     },
     /// Run system health checks
-    Doctor,
+    Doctor {
+        /// Offer to fix what the checks flag (missing config or workspace, gateway auth and bind)
+        #[arg(long)]
+        fix: bool,
+        /// Apply every fix without asking
+        #[arg(long, requires = "fix")]
+        yes: bool,
+    },
     /// Show tool health statistics
     Health {
         /// Number of days to look back (default: 7)
         #[arg(long, default_value = "7")]
         days: u64,
+        /// Print the per-tool statistics as a JSON object keyed by tool
+        #[arg(long)]
+        json: bool,
+    },
+    /// List available tools (built-ins, MCP tools and skills)
+    Tools {
+        /// Print each tool's name, description, tier and input schema as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Measure the agent loop's own overhead with a scripted model
+    Bench {
+        /// Scripted runs to time
+        #[arg(long, default_value = "20")]
+        runs: usize,
+        /// Turns per run, the last of which answers
+        #[arg(long, default_value = "5")]
+        turns: usize,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show a run saved with `run --record`, or run it again
+    Replay {
+        /// Transcript written by `run --record`
+        transcript: PathBuf,
+        /// Run the recorded prompt again in a fresh session, with the
+        /// recorded responses standing in for the model. Tools run for real.
+        #[arg(long)]
+        reexecute: bool,
     },
     /// Generate shell completions
     Completions {
@@ -149,14 +232,42 @@ enum Commands {
         #[arg(long, default_value = "20")]
         limit: usize,
     },
-    /// List agent decision audit trail
+    /// List agent decision audit trail with per-option outcome stats
     Decisions {
-        /// Filter by session ID (optional)
+        /// Filter by session ID prefix (optional)
         #[arg(long)]
         session: Option<String>,
         /// Max entries to show (default: 20)
         #[arg(long, default_value = "20")]
         limit: usize,
+        /// Print decisions and stats as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show run log entries written by the JSONL run logger
+    Logs {
+        /// Keep printing new entries as they are written until Ctrl-C
+        #[arg(long)]
+        follow: bool,
+        /// Only entries for this session ID
+        #[arg(long)]
+        session: Option<String>,
+        /// Highest verbosity to show: 1 run summary, 2 per-turn, 3 per-step
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=3))]
+        level: Option<u8>,
+        /// Only entries from this far back (e.g. 30m, 12h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Inspect and manually run cron jobs
+    Cron {
+        #[command(subcommand)]
+        action: CronAction,
+    },
+    /// Trigger heartbeat checks manually
+    Heartbeat {
+        #[command(subcommand)]
+        action: HeartbeatAction,
     },
     /// Search failure patterns in the healing journal
     Failures {
@@ -170,6 +281,31 @@ enum Commands {
         #[arg(long, default_value = "20")]
         limit: usize,
     },
+    /// Always allow matching tool calls without asking (persisted rule)
+    Allow(ApprovalRuleArgs),
+    /// Always deny matching tool calls (persisted rule)
+    Deny(ApprovalRuleArgs),
+}
+
+#[derive(Args)]
+struct ApprovalRuleArgs {
+    /// Tool name, or "*" for every tool
+    #[arg(required_unless_present_any = ["list", "remove"])]
+    tool: Option<String>,
+    /// Glob matched against the call's command or path (e.g. './docs/**')
+    pattern: Option<String>,
+    /// Treat the pattern as a regex over the command or path
+    #[arg(long, requires = "pattern")]
+    regex: bool,
+    /// Expire the rule after this long (e.g. 30m, 12h, 7d)
+    #[arg(long = "for", value_name = "DURATION")]
+    expires: Option<String>,
+    /// List stored rules of this kind
+    #[arg(long, conflicts_with_all = ["tool", "remove"])]
+    list: bool,
+    /// Remove the rule with this id
+    #[arg(long, value_name = "ID", conflicts_with = "tool")]
+    remove: Option<i64>,
 }
 
 #[derive(Subcommand)]
@@ -192,12 +328,44 @@ enum McpAction {
         /// Environment variables (KEY=VALUE)
         #[arg(long)]
         env: Vec<String>,
+        /// Replace a server that already has this name
+        #[arg(long)]
+        force: bool,
     },
     /// Remove an MCP server from config
     Remove {
         /// Server name
         name: String,
     },
+    /// Connect to a server and show the log messages it sends
+    Logs {
+        /// Server name
+        server: String,
+        /// Keep printing new messages until Ctrl-C
+        #[arg(long)]
+        follow: bool,
+        /// Lowest level to show (debug, info, notice, warning, error, critical, alert, emergency)
+        #[arg(long, default_value = "debug")]
+        level: ryvos_mcp::McpLogLevel,
+    },
+}
+
+#[derive(Subcommand)]
+enum CronAction {
+    /// List configured jobs and their next fire times
+    List,
+    /// Run a job now and print its output (channels are not notified)
+    Run {
+        /// Job name from [[cron.jobs]]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HeartbeatAction {
+    /// Run one heartbeat check now, ignoring active hours, and route an
+    /// alert to the configured target channel
+    Run,
 }
 
 #[derive(Subcommand)]
@@ -213,30 +381,47 @@ enum SkillAction {
         /// Search query
         query: String,
     },
-    /// Install a skill from the registry
+    /// Install a skill from the registry and record it in skills.lock
     Install {
-        /// Skill name
+        /// Skill name, optionally pinned as name@version
         name: String,
     },
+    /// Reinstall skills pinned in skills.lock that are missing
+    Sync,
     /// Remove an installed skill
     Remove {
         /// Skill name
         name: String,
     },
+    /// Run a skill directly, without the agent
+    Run {
+        /// Skill name
+        name: String,
+        /// JSON object passed to the skill on stdin
+        input: Option<String>,
+        /// Input field as key=value (repeatable; overrides the JSON input)
+        #[arg(long = "arg", value_name = "KEY=VALUE")]
+        args: Vec<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
+    let cli = Cli::parse();
+
+    // Initialize tracing; `run --events` keeps stdout for its NDJSON lines
+    let writer = match cli.command {
+        Some(Commands::Run { events: true, .. }) => BoxMakeWriter::new(io::stderr),
+        _ => BoxMakeWriter::new(io::stdout),
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("ryvos=info,warn")),
         )
         .with_target(false)
+        .with_writer(writer)
         .init();
 
-    let cli = Cli::parse();
-
     // Handle completions before config loading
     if let Some(Commands::Completions { shell }) = &cli.command {
         let mut cmd = Cli::command();
@@ -244,6 +429,27 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    // Bench needs no config: it scripts its own model and in-memory store
+    if let Some(Commands::Bench { runs, turns, json }) = &cli.command {
+        let report = ryvos_agent::run_bench(*runs, *turns).await?;
+        if *json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("{}", report);
+        }
+        return Ok(());
+    }
+
+    // Showing a transcript needs no config
+    if let Some(Commands::Replay {
+        transcript,
+        reexecute: false,
+    }) = &cli.command
+    {
+        print_transcript(&ryvos_llm::Transcript::load(transcript)?);
+        return Ok(());
+    }
+
     // Handle init before config loading
     if let Some(Commands::Init {
         yes,
@@ -311,9 +517,21 @@ async fn main() -> anyhow::Result<()> {
         return run_mcp_server().await;
     }
 
+    // Print the OpenAPI spec before config loading: it does not depend on it
+    if let Some(Commands::Serve {
+        print_openapi: true,
+    }) = &cli.command
+    {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&ryvos_gateway::openapi_spec())?
+        );
+        return Ok(());
+    }
+
     // Handle MCP CLI subcommands before config loading
     if let Some(Commands::Mcp { action }) = &cli.command {
-        return handle_mcp_cli(action, &cli.config);
+        return handle_mcp_cli(action, &cli.config).await;
     }
 
     // Handle Skill CLI subcommands before config loading
@@ -321,6 +539,21 @@ async fn main() -> anyhow::Result<()> {
         return handle_skill_cli(action).await;
     }
 
+    // Handle doctor --fix before config loading: it can write a missing config
+    if let Some(Commands::Doctor { fix: true, yes }) = &cli.command {
+        let path = if cli.config.exists() || cli.config != *"ryvos.toml" {
+            cli.config.clone()
+        } else {
+            dirs_home()
+                .map(|h| h.join(".ryvos").join("config.toml"))
+                .unwrap_or_else(|| cli.config.clone())
+        };
+        println!("Ryvos Doctor: fixes");
+        let applied = doctor::run_fix(&path, *yes)?;
+        println!("  {} fixes applied", applied);
+        println!();
+    }
+
     // Load config
     let config = if cli.config.exists() {
         AppConfig::load(&cli.config)?
@@ -346,6 +579,19 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    messages::set_locale(messages::Locale::resolve(config.agent.locale.as_deref()));
+    ryvos_core::security::set_env_mask(ryvos_core::security::EnvMask::new(
+        &config.security.masked_env,
+    ));
+
+    // Follow mode only talks to the gateway of a process already running
+    if let (Some(Commands::Tui { follow: true }), Some(session)) = (&cli.command, &cli.session) {
+        let Some(ref gw) = config.gateway else {
+            anyhow::bail!("tui --follow connects through the gateway; add a [gateway] section");
+        };
+        return ryvos_tui::follow_tui(gw.clone(), SessionId::from_string(session)).await;
+    }
+
     // Apply provider preset defaults (base_url, extra headers) for known providers
     let mut config = config;
     ryvos_llm::apply_preset_defaults(&mut config.model);
@@ -353,43 +599,60 @@ async fn main() -> anyhow::Result<()> {
         ryvos_llm::apply_preset_defaults(fb);
     }
 
+    // Diagnose before opening the store: opening fails if the database is down
+    if let Some(Commands::Doctor { .. }) = &cli.command {
+        println!("Ryvos Doctor");
+        println!("============");
+        doctor::run_doctor(&config).await;
+        return Ok(());
+    }
+
     // Set up components
     let workspace = config.workspace_dir();
     std::fs::create_dir_all(&workspace).ok();
 
-    let db_path = workspace.join("sessions.db");
-    let store = Arc::new(SqliteStore::open(&db_path)?);
+    let store = ryvos_memory::open_session_store(&config.storage, &workspace).await?;
     let mut tools = ToolRegistry::with_builtins();
+    tools.register(ryvos_tools::builtin::system::NowTool::new(
+        config.agent.zone(),
+    ));
     let event_bus = Arc::new(EventBus::default());
 
     // Build LLM client with retry and fallback chain
-    // Note: dangerous_patterns are deprecated (no longer block tools), but still passed
-    // to CLI-based providers for informational logging.
-    let primary_llm =
-        ryvos_llm::create_client_with_security(&config.model, &config.security.dangerous_patterns);
-    let llm: Arc<dyn ryvos_core::traits::LlmClient> =
-        if !config.fallback_models.is_empty() || config.model.retry.is_some() {
-            let retry_config = config
-                .model
-                .retry
-                .clone()
-                .unwrap_or_else(RetryConfig::default);
-            let fallbacks: Vec<_> = config
-                .fallback_models
-                .iter()
-                .map(|mc| {
-                    let client = ryvos_llm::create_client(mc);
-                    (mc.clone(), client)
-                })
-                .collect();
-            Arc::new(ryvos_llm::RetryingClient::new(
-                primary_llm,
-                fallbacks,
-                retry_config,
-            ))
-        } else {
-            Arc::from(primary_llm)
-        };
+    // Note: dangerous_patterns block tools in the SecurityGate; CLI-based providers
+    // execute tools themselves, so they only log matches.
+    let llm = ryvos_llm::build_client(
+        &config.model,
+        &config.fallback_models,
+        &config.security.dangerous_patterns,
+    );
+
+    // `run --record` keeps the model's responses; `replay --reexecute`
+    // serves recorded ones back instead of calling the model
+    let recorder = match &cli.command {
+        Some(Commands::Run {
+            record: Some(_), ..
+        }) => Some(Arc::new(ryvos_llm::RecordingClient::new(llm.clone()))),
+        _ => None,
+    };
+    let replay = match &cli.command {
+        Some(Commands::Replay { transcript, .. }) => {
+            let transcript = ryvos_llm::Transcript::load(transcript)?;
+            let client = Arc::new(ryvos_llm::ReplayClient::new(transcript.responses));
+            let Some(prompt) = transcript.prompt else {
+                anyhow::bail!(
+                    "The transcript has no prompt to rerun; record one with `ryvos run --record`"
+                );
+            };
+            Some((prompt, client))
+        }
+        _ => None,
+    };
+    let llm: Arc<dyn ryvos_core::traits::LlmClient> = match (&recorder, &replay) {
+        (Some(recorder), _) => recorder.clone(),
+        (_, Some((_, client))) => client.clone(),
+        _ => llm,
+    };
 
     // Merge .mcp.json project config if present
     let mut mcp_config = config.mcp.clone().unwrap_or_default();
@@ -409,6 +672,7 @@ async fn main() -> anyhow::Result<()> {
     // Connect MCP servers and register bridged tools
     let mcp_manager = if !mcp_config.servers.is_empty() {
         let manager = Arc::new(ryvos_mcp::McpClientManager::new());
+        manager.set_roots(config.working_dir_roots()).await;
         for (name, server_config) in &mcp_config.servers {
             if server_config.auto_connect {
                 match ryvos_mcp::connect_and_register(&manager, name, server_config, &mut tools)
@@ -432,15 +696,28 @@ async fn main() -> anyhow::Result<()> {
 
     // Register web search tool if configured
     if let Some(ref ws_config) = config.web_search {
-        tools.register(ryvos_tools::builtin::web_search::WebSearchTool::new(
-            &ws_config.api_key,
-        ));
-        info!(
-            "Web search tool registered (provider: {})",
-            ws_config.provider
-        );
+        match ryvos_tools::builtin::web_search::WebSearchTool::from_config(ws_config) {
+            Ok(tool) => {
+                tools.register(tool);
+                info!(
+                    "Web search tool registered (provider: {})",
+                    ws_config.provider
+                );
+            }
+            Err(e) => error!(error = %e, "Web search tool not registered"),
+        }
     }
 
+    // Let the agent read and tune a few of its own settings
+    let config_overrides = Arc::new(ryvos_core::overrides::ConfigOverrides::new(&config));
+    tools.register(ryvos_tools::builtin::config::ConfigGetTool::new(
+        config_overrides.clone(),
+    ));
+    tools.register(ryvos_tools::builtin::config::ConfigSetTool::new(
+        config_overrides.clone(),
+        cli.config.exists().then(|| cli.config.clone()),
+    ));
+
     // Load drop-in skills
     let skills_dir = workspace.join("skills");
     let skill_count = ryvos_skills::load_and_register_skills(&skills_dir, &mut tools);
@@ -488,6 +765,15 @@ async fn main() -> anyhow::Result<()> {
             }
         };
 
+    // Persisted allow/deny rules (`ryvos allow` / `ryvos deny`)
+    match ryvos_agent::ApprovalRuleStore::open(&workspace.join("approvals.db")) {
+        Ok(rules) => gate_inner.set_approval_rules(Arc::new(rules)),
+        Err(e) => error!(error = %e, "Failed to open approval rules"),
+    }
+    if let Some(ref hooks) = config.hooks {
+        gate_inner.set_approval_hooks(hooks.on_tool_approval.clone(), hooks.payload);
+    }
+
     let gate = Arc::new(gate_inner);
 
     info!(
@@ -566,9 +852,18 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    let session_mgr = Arc::new(ryvos_agent::SessionManager::new());
-    let mut runtime_inner =
-        AgentRuntime::new_with_gate(config.clone(), llm, gate, store.clone(), event_bus.clone());
+    let session_mgr = Arc::new(
+        ryvos_agent::SessionManager::new().with_working_dir_roots(config.working_dir_roots()),
+    );
+    let mut runtime_inner = AgentRuntime::new_with_gate(
+        config.clone(),
+        llm.clone(),
+        gate,
+        store.clone(),
+        event_bus.clone(),
+    );
+    runtime_inner.set_session_manager(session_mgr.clone());
+    runtime_inner.set_config_overrides(config_overrides);
     if let Some(ref j) = journal {
         runtime_inner.set_journal(j.clone());
     }
@@ -579,23 +874,32 @@ async fn main() -> anyhow::Result<()> {
         runtime_inner.set_safety_memory(sm.clone());
     }
 
-    let session_id = cli
-        .session
-        .map(|s| SessionId::from_string(&s))
-        .unwrap_or_else(SessionId::new);
+    // Runs and the REPL become the session `--session last` continues
+    let enter_session = match cli.command {
+        Some(Commands::Run { .. }) | Some(Commands::Repl) | None => {
+            ryvos_agent::session::enter_cli_session
+        }
+        _ => ryvos_agent::session::resolve_cli_session,
+    };
+    let session_id = enter_session(
+        cli.session.as_deref(),
+        config.agent.default_session.as_deref(),
+        &workspace,
+    );
 
     // Spawn Guardian watchdog if enabled
     if config.agent.guardian.enabled {
-        let (mut guardian, hint_rx) = Guardian::new(
+        let mut guardian = Guardian::with_hint_sender(
             config.agent.guardian.clone(),
             event_bus.clone(),
             runtime_inner.cancel_token(),
+            runtime_inner.hint_sender(),
         );
+        guardian.set_model(config.model.model_id.clone());
         // Wire dollar budget enforcement
         if let (Some(ref cs), Some(ref bc)) = (&cost_store, &config.budget) {
             guardian.set_budget(cs.clone(), bc.clone());
         }
-        runtime_inner.set_guardian_hints(hint_rx);
         tokio::spawn(guardian.run(session_id.clone()));
     }
 
@@ -645,6 +949,9 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let runtime = Arc::new(runtime_inner);
+    if let Some(ref manager) = mcp_manager {
+        manager.set_sampling_runtime(&runtime);
+    }
 
     // Wire agent spawner: give runtime a self-reference for sub-agent tools
     {
@@ -680,7 +987,6 @@ async fn main() -> anyhow::Result<()> {
                     println!("Connected to Viking at {}", ov_config.base_url);
 
                     // Search SQLite FTS5 for all memory entries and bulk-import
-                    use ryvos_core::traits::SessionStore;
                     let results = store.search("*", 10000).await.unwrap_or_default();
 
                     let mut migrated = 0u64;
@@ -726,42 +1032,99 @@ async fn main() -> anyhow::Result<()> {
             }
             return Ok(());
         }
-        Some(Commands::Doctor) => {
-            println!("Ryvos Doctor");
-            println!("============");
-            doctor::run_doctor(&config);
+        Some(Commands::Tools { json }) => {
+            let catalog = tools.read().await.catalog();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&catalog)?);
+            } else {
+                for tool in &catalog {
+                    println!(
+                        "  {:<24} {}  {}",
+                        tool.name,
+                        tool.tier,
+                        tool.description.lines().next().unwrap_or_default()
+                    );
+                }
+            }
+            return Ok(());
+        }
+        Some(Commands::Logs {
+            follow,
+            session,
+            level,
+            since,
+        }) => {
+            let log_dir = config
+                .agent
+                .log
+                .as_ref()
+                .and_then(|l| l.log_dir.as_ref())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| workspace.join("logs"));
+            let since = match since {
+                Some(ref text) => Some(
+                    chrono::Utc::now() - parse_duration(text).map_err(|e| anyhow::anyhow!(e))?,
+                ),
+                None => None,
+            };
+            let filter = ryvos_agent::LogFilter {
+                session,
+                level,
+                since,
+            };
+            let mut tail = ryvos_agent::LogTail::new(log_dir.clone(), filter);
+            let entries = tail.poll()?;
+            if entries.is_empty() && !follow {
+                println!("No run log entries in {}.", log_dir.display());
+            }
+            entries.iter().for_each(print_run_log);
+            if follow {
+                eprintln!("Following {} (Ctrl-C to stop)", log_dir.display());
+                loop {
+                    tokio::select! {
+                        _ = tokio::signal::ctrl_c() => break,
+                        _ = tokio::time::sleep(std::time::Duration::from_millis(500)) => {
+                            tail.poll()?.iter().for_each(print_run_log);
+                        }
+                    }
+                }
+            }
             return Ok(());
         }
-        Some(Commands::Health { days }) => {
+        Some(Commands::Health { days, json }) => {
             let journal_path = workspace.join("healing.db");
             match ryvos_agent::FailureJournal::open(&journal_path) {
                 Ok(journal) => {
                     let since = chrono::Utc::now() - chrono::Duration::days(days as i64);
-                    match journal.tool_health(since) {
-                        Ok(health) => {
+                    match journal.health_report(since) {
+                        Ok(report) if json => {
+                            println!("{}", serde_json::to_string_pretty(&report)?);
+                        }
+                        Ok(report) => {
                             println!("Tool Health (last {} days):", days);
-                            if health.is_empty() {
+                            if report.is_empty() {
                                 println!("  No tool usage recorded yet.");
-                            } else {
-                                let mut entries: Vec<_> = health.into_iter().collect();
-                                entries.sort_by_key(|(name, _)| name.clone());
-                                for (tool, (successes, failures)) in &entries {
-                                    let total = successes + failures;
-                                    let pct = if total > 0 {
-                                        (*successes as f64 / total as f64 * 100.0) as u32
-                                    } else {
-                                        100
-                                    };
-                                    let status = if pct < 90 { " [degraded]" } else { "" };
-                                    println!(
-                                        "  {:<18} {}% success ({}/{}){}",
-                                        format!("{}:", tool),
-                                        pct,
-                                        successes,
-                                        total,
-                                        status
-                                    );
-                                }
+                            }
+                            for (tool, health) in &report {
+                                let status = if health.degraded { " [degraded]" } else { "" };
+                                let timing = health
+                                    .latency
+                                    .map(|l| {
+                                        format!(
+                                            "  p50 {}ms  p95 {}ms  max {}ms",
+                                            l.p50_ms, l.p95_ms, l.max_ms
+                                        )
+                                    })
+                                    .unwrap_or_default();
+                                println!(
+                                    "  {:<18} {}% success ({}/{}){}{}",
+                                    format!("{}:", tool),
+                                    health.pct,
+                                    health.successes,
+                                    health.successes + health.failures,
+                                    timing,
+                                    status
+                                );
                             }
                         }
                         Err(e) => eprintln!("Failed to query tool health: {}", e),
@@ -783,7 +1146,11 @@ async fn main() -> anyhow::Result<()> {
                     match lessons {
                         Ok(lessons) => {
                             let total = sm.count_lessons().await.unwrap_or(0);
-                            println!("Safety Lessons ({} total, showing {}):", total, lessons.len());
+                            println!(
+                                "Safety Lessons ({} total, showing {}):",
+                                total,
+                                lessons.len()
+                            );
                             if lessons.is_empty() {
                                 println!("  No lessons recorded yet.");
                             }
@@ -812,53 +1179,26 @@ async fn main() -> anyhow::Result<()> {
             }
             return Ok(());
         }
-        Some(Commands::Decisions { session, limit }) => {
+        Some(Commands::Decisions {
+            session,
+            limit,
+            json,
+        }) => {
             let journal_path = workspace.join("healing.db");
-            match ryvos_agent::FailureJournal::open(&journal_path) {
-                Ok(journal) => {
-                    match journal.list_decisions(limit, 0) {
-                        Ok(decisions) => {
-                            let filtered: Vec<_> = if let Some(ref sid) = session {
-                                decisions
-                                    .into_iter()
-                                    .filter(|d| d.session_id.starts_with(sid))
-                                    .collect()
-                            } else {
-                                decisions
-                            };
-                            let total = journal.count_decisions().unwrap_or(0);
-                            println!(
-                                "Agent Decisions ({} total, showing {}):",
-                                total,
-                                filtered.len()
-                            );
-                            if filtered.is_empty() {
-                                println!("  No decisions recorded yet.");
-                            }
-                            for d in &filtered {
-                                println!("  ──────────────────────────────────────");
-                                println!("  Decision:  {}", d.description);
-                                println!("  Chosen:    {}", d.chosen_option);
-                                if !d.alternatives.is_empty() {
-                                    let alts: Vec<_> =
-                                        d.alternatives.iter().map(|a| a.name.as_str()).collect();
-                                    println!("  Alternatives: {}", alts.join(", "));
-                                }
-                                println!(
-                                    "  Session:   {}  Turn: {}",
-                                    &d.session_id[..8.min(d.session_id.len())],
-                                    d.turn
-                                );
-                                println!(
-                                    "  Time:      {}",
-                                    d.timestamp.format("%Y-%m-%d %H:%M")
-                                );
-                            }
-                        }
-                        Err(e) => eprintln!("Failed to query decisions: {}", e),
-                    }
-                }
-                Err(e) => eprintln!("Failed to open healing journal: {}", e),
+            let journal = ryvos_agent::FailureJournal::open(&journal_path)
+                .map_err(|e| anyhow::anyhow!("Failed to open healing journal: {}", e))?;
+            let decisions = journal
+                .search_decisions(session.as_deref(), limit)
+                .map_err(|e| anyhow::anyhow!("Failed to query decisions: {}", e))?;
+            let total = journal.count_decisions().unwrap_or(0);
+            if json {
+                let report = ryvos_agent::decision_report::decisions_json(&decisions, total);
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!(
+                    "{}",
+                    ryvos_agent::decision_report::format_decisions(&decisions, total)
+                );
             }
             return Ok(());
         }
@@ -897,10 +1237,7 @@ async fn main() -> anyhow::Result<()> {
                                     &f.session_id[..8.min(f.session_id.len())],
                                     f.turn
                                 );
-                                println!(
-                                    "  Time:    {}",
-                                    f.timestamp.format("%Y-%m-%d %H:%M")
-                                );
+                                println!("  Time:    {}", f.timestamp.format("%Y-%m-%d %H:%M"));
                             }
                         }
                         Err(e) => eprintln!("Failed to query failures: {}", e),
@@ -910,51 +1247,225 @@ async fn main() -> anyhow::Result<()> {
             }
             return Ok(());
         }
+        Some(Commands::Allow(args)) => {
+            return handle_rule_cli(&workspace, RuleAction::Allow, args).await;
+        }
+        Some(Commands::Deny(args)) => {
+            return handle_rule_cli(&workspace, RuleAction::Deny, args).await;
+        }
         Some(Commands::Config) => {
-            println!("{}", toml::to_string_pretty(&config)?);
+            let printed = toml::to_string_pretty(&config)?;
+            println!("{}", ryvos_core::security::env_mask().mask(&printed));
         }
-        Some(Commands::Run { prompt }) => {
-            let text = prompt.join(" ");
+        Some(Commands::Run {
+            no_tools,
+            no_stream,
+            flush_ms,
+            events,
+            temperature,
+            top_p,
+            record,
+            prompt,
+        }) => {
+            runtime.set_no_tools(&session_id, no_tools);
+            runtime.set_run_sampling(&session_id, SamplingOverride::new(temperature, top_p)?);
+            let mut text = prompt.join(" ");
+            if text.is_empty() && events {
+                anyhow::bail!(
+                    "--events reads approval answers from stdin; pass the prompt as arguments"
+                );
+            }
             if text.is_empty() {
                 // Read from stdin
                 let stdin = io::stdin();
-                let input: String = stdin
+                text = stdin
                     .lock()
                     .lines()
                     .map_while(|l| l.ok())
                     .collect::<Vec<_>>()
                     .join("\n");
-                run_once(
-                    &runtime,
-                    &event_bus,
-                    &session_id,
-                    &input,
-                    &config.hooks,
-                    &broker,
-                )
-                .await?;
-            } else {
-                run_once(
-                    &runtime,
-                    &event_bus,
-                    &session_id,
-                    &text,
-                    &config.hooks,
-                    &broker,
-                )
-                .await?;
-            }
-        }
-        Some(Commands::Tui) => {
+            }
+            let output = StreamOptions {
+                raw: no_stream,
+                flush_interval: std::time::Duration::from_millis(flush_ms),
+                events,
+            };
+            let result = run_once(
+                &runtime,
+                &event_bus,
+                &session_id,
+                &text,
+                &config.hooks,
+                &broker,
+                output,
+            )
+            .await;
+            runtime.settle_session_summaries().await;
+            tools.read().await.end_session(&session_id).await;
+            // Saved even if the run failed, to replay the failure
+            if let (Some(path), Some(recorder)) = (record, &recorder) {
+                recorder.save(&path, Some(&text))?;
+                info!(path = %path.display(), "Transcript saved");
+            }
+            result?;
+        }
+        Some(Commands::Replay { .. }) => {
+            let (prompt, client) = replay.expect("transcript loaded for --reexecute");
+            // A fresh session, so no earlier history changes the run
+            let session_id = SessionId::new();
+            let output = StreamOptions {
+                raw: false,
+                flush_interval: std::time::Duration::from_millis(50),
+                events: false,
+            };
+            run_once(
+                &runtime,
+                &event_bus,
+                &session_id,
+                &prompt,
+                &config.hooks,
+                &broker,
+                output,
+            )
+            .await?;
+            runtime.settle_session_summaries().await;
+            tools.read().await.end_session(&session_id).await;
+            let unused = client.remaining();
+            if unused > 0 {
+                eprintln!(
+                    "The run ended with {} recorded response(s) unused, so it did not follow \
+                     the recording; a tool's output likely differed.",
+                    unused
+                );
+            }
+        }
+        Some(Commands::Cron {
+            action: CronAction::List,
+        }) => {
+            let jobs = config
+                .cron
+                .as_ref()
+                .map(|c| c.jobs.as_slice())
+                .unwrap_or(&[]);
+            if jobs.is_empty() {
+                println!("No cron jobs configured. Add [[cron.jobs]] to ryvos.toml.");
+                return Ok(());
+            }
+            let now = chrono::Utc::now();
+            for job in jobs {
+                let next = match ryvos_agent::scheduler::next_fire_after(&job.schedule, now) {
+                    Ok(Some(at)) => at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                    Ok(None) => "never".to_string(),
+                    Err(e) => format!("invalid schedule: {}", e),
+                };
+                let channel = job.channel.as_deref().unwrap_or("all channels");
+                println!(
+                    "{:<20} {:<20} next: {}  channel: {}",
+                    job.name, job.schedule, next, channel
+                );
+            }
+        }
+        Some(Commands::Cron {
+            action: CronAction::Run { name },
+        }) => {
+            let Some(ref cron_config) = config.cron else {
+                anyhow::bail!("No cron jobs configured. Add [[cron.jobs]] to ryvos.toml.");
+            };
+            let scheduler = ryvos_agent::CronScheduler::new(
+                cron_config,
+                runtime.clone(),
+                event_bus.clone(),
+                tokio_util::sync::CancellationToken::new(),
+            );
+            let response = scheduler.run_job(&name).await?;
+            println!("{}", response);
+        }
+        Some(Commands::Heartbeat {
+            action: HeartbeatAction::Run,
+        }) => {
+            let mut heartbeat = ryvos_agent::Heartbeat::new(
+                config.heartbeat.clone().unwrap_or_default(),
+                runtime.clone(),
+                event_bus.clone(),
+                tokio_util::sync::CancellationToken::new(),
+                workspace.clone(),
+            );
+            if let Some(ref trail) = audit_trail {
+                heartbeat.set_audit_trail(trail.clone());
+            }
+            match heartbeat.run_once().await? {
+                ryvos_agent::HeartbeatOutcome::Ok { response } => {
+                    println!("Heartbeat OK (suppressed): {}", response);
+                }
+                ryvos_agent::HeartbeatOutcome::Alert {
+                    message,
+                    target_channel,
+                } => {
+                    let target = target_channel.as_deref().unwrap_or("all channels");
+                    println!("Heartbeat alert (target: {}):\n{}", target, message);
+                    let adapters = notice_adapters(&config, &session_mgr);
+                    let content = ryvos_core::types::MessageContent::Text(format!(
+                        "[Heartbeat Alert] {}",
+                        message
+                    ));
+                    let outcomes = ryvos_channels::route_notice(
+                        &adapters,
+                        &content,
+                        target_channel.as_deref(),
+                    )
+                    .await;
+                    if outcomes.is_empty() {
+                        eprintln!("No configured channel to route the alert to ({})", target);
+                    }
+                    for (channel, result) in outcomes {
+                        match result {
+                            Ok(()) => println!("Routed to {}", channel),
+                            Err(e) => eprintln!("Failed to route to {}: {}", channel, e),
+                        }
+                    }
+                }
+            }
+        }
+        Some(Commands::Orchestrate { task }) => {
+            let Some(ref orchestrator_config) = config.orchestrator else {
+                anyhow::bail!("No agents configured. Add an [orchestrator] section to ryvos.toml.");
+            };
+            let task = task.join(" ");
+            if task.is_empty() {
+                anyhow::bail!("Usage: ryvos orchestrate <task>");
+            }
+            let builder = ryvos_agent::OrchestratorBuilder {
+                config: config.clone(),
+                llm: llm.clone(),
+                tools: tools.clone(),
+                store: store.clone(),
+                event_bus: event_bus.clone(),
+                broker: broker.clone(),
+            };
+            let orchestrator = ryvos_agent::MultiAgentOrchestrator::from_config(
+                orchestrator_config,
+                config.security.to_policy(),
+                builder,
+            );
+            for result in orchestrator.orchestrate(&task).await? {
+                println!("── {} ──", result.agent_id);
+                println!("{}", result.output);
+                if !result.succeeded {
+                    eprintln!("Agent '{}' failed", result.agent_id);
+                }
+            }
+        }
+        Some(Commands::Tui { .. }) => {
             ryvos_tui::run_tui(
                 runtime.clone(),
                 event_bus.clone(),
-                session_id,
+                session_id.clone(),
                 Some(broker.clone()),
             )
             .await?;
+            tools.read().await.end_session(&session_id).await;
         }
-        Some(Commands::Serve) => {
+        Some(Commands::Serve { .. }) => {
             let gateway_config = config.gateway.clone().unwrap_or_default();
             info!(bind = %gateway_config.bind, "Starting WebSocket gateway");
             let mut server = ryvos_gateway::GatewayServer::new(
@@ -987,6 +1498,11 @@ async fn main() -> anyhow::Result<()> {
             );
             server.set_integration_store(integration_store);
             server.set_integrations_config(config.integrations.clone());
+            if let Some(ref embedding) = config.embedding {
+                let provider =
+                    ryvos_memory::embeddings::HttpEmbeddingProvider::from_config(embedding);
+                server.set_embedder(embedding.model.clone(), Arc::new(provider));
+            }
 
             if let Some(ref wa_config) = config.channels.whatsapp {
                 let wa_adapter = ryvos_channels::WhatsAppAdapter::new(
@@ -1149,6 +1665,11 @@ async fn main() -> anyhow::Result<()> {
                 );
                 server.set_integration_store(integration_store);
                 server.set_integrations_config(config.integrations.clone());
+                if let Some(ref embedding) = config.embedding {
+                    let provider =
+                        ryvos_memory::embeddings::HttpEmbeddingProvider::from_config(embedding);
+                    server.set_embedder(embedding.model.clone(), Arc::new(provider));
+                }
 
                 // Wire WhatsApp webhook handle into gateway if configured
                 if let Some(ref wa_config) = config.channels.whatsapp {
@@ -1167,10 +1688,20 @@ async fn main() -> anyhow::Result<()> {
                 });
             }
 
-            let mut dispatcher = ryvos_channels::ChannelDispatcher::new(runtime, event_bus, cancel);
+            let mut dispatcher =
+                ryvos_channels::ChannelDispatcher::new(runtime, event_bus.clone(), cancel);
 
             dispatcher.set_broker(broker.clone());
             dispatcher.set_session_meta(session_meta.clone());
+            dispatcher.set_dedupe_window(std::time::Duration::from_secs(
+                config.channels.dedupe_window_secs,
+            ));
+            dispatcher.set_stream_edit_interval(
+                Some(config.channels.stream_edit_interval_ms)
+                    .filter(|ms| *ms > 0)
+                    .map(std::time::Duration::from_millis),
+            );
+            dispatcher.set_coalesce_queued(config.channels.coalesce_queued_messages);
 
             if let Some(ref hooks_config) = config.hooks {
                 dispatcher.set_hooks(hooks_config.clone());
@@ -1180,6 +1711,7 @@ async fn main() -> anyhow::Result<()> {
                 let mut adapter =
                     ryvos_channels::TelegramAdapter::new(tg_config.clone(), session_mgr.clone());
                 adapter.set_broker(broker.clone());
+                adapter.set_event_bus(event_bus.clone());
                 dispatcher.add_adapter(std::sync::Arc::new(adapter));
             }
 
@@ -1187,6 +1719,7 @@ async fn main() -> anyhow::Result<()> {
                 let mut adapter =
                     ryvos_channels::DiscordAdapter::new(dc_config.clone(), session_mgr.clone());
                 adapter.set_broker(broker.clone());
+                adapter.set_event_bus(event_bus.clone());
                 dispatcher.add_adapter(std::sync::Arc::new(adapter));
             }
 
@@ -1194,6 +1727,7 @@ async fn main() -> anyhow::Result<()> {
                 let mut adapter =
                     ryvos_channels::SlackAdapter::new(slack_config.clone(), session_mgr.clone());
                 adapter.set_broker(broker.clone());
+                adapter.set_event_bus(event_bus.clone());
                 dispatcher.add_adapter(std::sync::Arc::new(adapter));
             }
 
@@ -1208,12 +1742,14 @@ async fn main() -> anyhow::Result<()> {
         }
         Some(Commands::Init { .. }) => unreachable!("handled before config load"),
         Some(Commands::Completions { .. }) => unreachable!("handled before config load"),
+        Some(Commands::Bench { .. }) => unreachable!("handled before config load"),
         Some(Commands::Mcp { .. }) => unreachable!("handled before config load"),
         Some(Commands::Skill { .. }) => unreachable!("handled before config load"),
         Some(Commands::Soul) => unreachable!("handled before config load"),
         Some(Commands::Update { .. }) => unreachable!("handled before config load"),
         Some(Commands::McpServer) => unreachable!("handled before config load"),
         Some(Commands::VikingServer { .. }) => unreachable!("handled before config load"),
+        Some(Commands::Doctor { .. }) => unreachable!("handled before the store opens"),
         Some(Commands::Repl) | None => {
             run_repl(
                 &runtime,
@@ -1236,6 +1772,44 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Print a recorded run: its prompt, then each response's text and the
+/// tool calls it made.
+fn print_transcript(transcript: &ryvos_llm::Transcript) {
+    use ryvos_core::types::StreamDelta;
+
+    if let Some(prompt) = &transcript.prompt {
+        println!("Prompt: {}", prompt);
+    }
+    for (i, deltas) in transcript.responses.iter().enumerate() {
+        println!("\n--- Response {} ---", i + 1);
+        let mut text = String::new();
+        let mut calls: Vec<(String, String)> = Vec::new();
+        for delta in deltas {
+            match delta {
+                StreamDelta::TextDelta(t) => text.push_str(t),
+                StreamDelta::ToolUseStart { index, name, .. } => {
+                    if calls.len() <= *index {
+                        calls.resize(*index + 1, Default::default());
+                    }
+                    calls[*index].0 = name.clone();
+                }
+                StreamDelta::ToolInputDelta { index, delta } => {
+                    if let Some(call) = calls.get_mut(*index) {
+                        call.1.push_str(delta);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !text.is_empty() {
+            println!("{}", text);
+        }
+        for (name, input) in calls {
+            println!("-> {} {}", name, input);
+        }
+    }
+}
+
 async fn run_once(
     runtime: &AgentRuntime,
     event_bus: &EventBus,
@@ -1243,14 +1817,16 @@ async fn run_once(
     input: &str,
     hooks: &Option<HooksConfig>,
     broker: &Arc<ApprovalBroker>,
+    output: StreamOptions,
 ) -> anyhow::Result<()> {
     // Fire on_message hook
     if let Some(hooks) = hooks {
-        ryvos_core::hooks::run_hooks(
-            &hooks.on_message,
-            &[("RYVOS_SESSION", &session_id.0), ("RYVOS_TEXT", input)],
-        )
-        .await;
+        let env = [
+            ("RYVOS_SESSION", session_id.0.as_str()),
+            ("RYVOS_TEXT", input),
+        ];
+        let event = HookEvent::new("message", hooks.payload, &env);
+        ryvos_core::hooks::run_hooks(&hooks.on_message, &event).await;
     }
 
     // Subscribe to events for output
@@ -1261,34 +1837,114 @@ async fn run_once(
         .as_ref()
         .map(|h| h.on_tool_call.clone())
         .unwrap_or_default();
+    let hook_payload = hooks.as_ref().map(|h| h.payload).unwrap_or_default();
     let session_id_str = session_id.0.clone();
     let broker_clone = broker.clone();
 
-    // Spawn event printer
-    let print_handle = tokio::spawn(async move {
-        while let Ok(event) = rx.recv().await {
-            match event {
-                AgentEvent::TextDelta(text) => {
-                    print!("{}", text);
-                    io::stdout().flush().ok();
+    // With --events, approvals are answered on stdin. A plain thread, so a
+    // stdin that never closes does not hold up runtime shutdown.
+    if output.events {
+        let broker = broker.clone();
+        let handle = tokio::runtime::Handle::current();
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if line.trim().is_empty() {
+                    continue;
                 }
-                AgentEvent::ToolStart { name, .. } => {
-                    eprintln!("\n[tool: {}]", name);
-                    if !on_tool_call_cmds.is_empty() {
-                        let cmds = on_tool_call_cmds.clone();
-                        let sid = session_id_str.clone();
-                        let tool = name.clone();
-                        tokio::spawn(async move {
-                            ryvos_core::hooks::run_hooks(
-                                &cmds,
-                                &[("RYVOS_SESSION", &sid), ("RYVOS_TOOL", &tool)],
-                            )
-                            .await;
-                        });
+                match ndjson::parse_approval_answer(&line) {
+                    Ok((request_id, decision)) => {
+                        if !handle.block_on(broker.respond(&request_id, decision)) {
+                            warn!(request_id = %request_id, "No pending approval with this id");
+                        }
                     }
+                    Err(e) => warn!(error = %e, "Ignoring stdin line"),
                 }
-                AgentEvent::ToolEnd { name, result } => {
-                    if result.is_error {
+            }
+        });
+    }
+
+    // Spawn event printer
+    let print_handle = tokio::spawn(async move {
+        let mut reply = StreamWriter::new(io::stdout(), output, std::time::Instant::now());
+        loop {
+            // Write out a held partial line once its interval is up, even
+            // if the stream has paused
+            let deadline = reply.flush_deadline();
+            let held = async {
+                match deadline {
+                    Some(at) => tokio::time::sleep_until(at.into()).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let event = tokio::select! {
+                event = rx.recv() => event,
+                _ = held => {
+                    reply.flush_pending(std::time::Instant::now()).ok();
+                    continue;
+                }
+            };
+            let event = match event {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                    if output.events {
+                        println!("{}", ndjson::lagged_line(missed, &session_id_str));
+                    } else {
+                        warn!(
+                            missed,
+                            "Output fell behind the run; some events were skipped"
+                        );
+                    }
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            if output.events {
+                if let Some(line) = ndjson::event_line(&event, &session_id_str) {
+                    println!("{}", line);
+                }
+                match event {
+                    AgentEvent::ToolStart { name, input } => fire_tool_call_hooks(
+                        &on_tool_call_cmds,
+                        hook_payload,
+                        &session_id_str,
+                        name,
+                        input,
+                    ),
+                    AgentEvent::RunComplete { .. } | AgentEvent::RunError { .. } => break,
+                    _ => {}
+                }
+                continue;
+            }
+            let now = std::time::Instant::now();
+            match &event {
+                AgentEvent::TextDelta(_) => {}
+                AgentEvent::RunComplete { .. } | AgentEvent::RunError { .. } => {
+                    reply.finish(now).ok();
+                }
+                // Keep streamed text ahead of tool lines and prompts
+                _ => {
+                    reply.flush_pending(now).ok();
+                }
+            }
+            match event {
+                AgentEvent::TextDelta(text) => {
+                    reply.push(&text, now).ok();
+                }
+                AgentEvent::ToolStart { name, input } => {
+                    eprintln!("\n[tool: {}]", name);
+                    fire_tool_call_hooks(
+                        &on_tool_call_cmds,
+                        hook_payload,
+                        &session_id_str,
+                        name,
+                        input,
+                    );
+                }
+                AgentEvent::ToolProgress { chunk, .. } => {
+                    eprint!("{}", chunk);
+                }
+                AgentEvent::ToolEnd { name, result } => {
+                    if result.is_error {
                         eprintln!("[{}: ERROR] {}", name, truncate(&result.content, 200));
                     } else {
                         eprintln!("[{}: ok] {}", name, truncate(&result.content, 200));
@@ -1301,18 +1957,33 @@ async fn run_once(
                     );
                     let broker = broker_clone.clone();
                     let req_id = request.id.clone();
+                    let confirm_word = request.confirm_word.clone();
                     tokio::task::spawn_blocking(move || {
-                        let approved = dialoguer::Confirm::new()
-                            .with_prompt("Allow?")
-                            .default(true)
-                            .interact()
-                            .unwrap_or(false);
-                        (approved, broker, req_id)
+                        // A destructive command needs the word typed, not a y/n
+                        let typed = match confirm_word {
+                            Some(ref word) => dialoguer::Input::<String>::new()
+                                .with_prompt(messages::format("approval.confirm_prompt", &[word]))
+                                .allow_empty(true)
+                                .interact_text()
+                                .ok(),
+                            None => None,
+                        };
+                        let approved = match confirm_word {
+                            Some(ref word) => {
+                                typed.as_deref().map(str::trim) == Some(word.as_str())
+                            }
+                            None => dialoguer::Confirm::new()
+                                .with_prompt(messages::t("approval.prompt"))
+                                .default(true)
+                                .interact()
+                                .unwrap_or(false),
+                        };
+                        (approved, typed, broker, req_id)
                     })
                     .await
-                    .map(|(approved, broker, req_id)| {
+                    .map(|(approved, typed, broker, req_id)| {
                         let decision = if approved {
-                            ApprovalDecision::Approved
+                            ApprovalDecision::approve_with(typed.as_deref())
                         } else {
                             ApprovalDecision::Denied {
                                 reason: "denied by user".into(),
@@ -1327,15 +1998,39 @@ async fn run_once(
                 AgentEvent::ToolBlocked { name, tier, reason } => {
                     eprintln!("\n[BLOCKED] {} ({}): {}", name, tier, reason);
                 }
+                AgentEvent::ApprovalTimedOut {
+                    request, action, ..
+                } => {
+                    eprintln!("\n[APPROVAL TIMEOUT] {}: {}", request.tool_name, action);
+                }
+                AgentEvent::PromptInjectionDetected {
+                    name,
+                    label,
+                    fragment,
+                    in_output,
+                } => {
+                    let place = if in_output { "output" } else { "input" };
+                    eprintln!(
+                        "\n[INJECTION?] {} {}: {} on \"{}\"",
+                        name, place, label, fragment
+                    );
+                }
+                AgentEvent::TurnLimitReached { max_turns, .. } => {
+                    eprintln!(
+                        "\n[turn limit: stopped after {} turns; the answer may be incomplete]",
+                        max_turns
+                    );
+                }
                 AgentEvent::RunComplete {
                     total_turns,
                     input_tokens,
                     output_tokens,
+                    context_tokens,
                     ..
                 } => {
                     eprintln!(
-                        "\n[done: {} turns, {}in/{}out tokens]",
-                        total_turns, input_tokens, output_tokens
+                        "\n[done: {} turns, {}in/{}out tokens, {} in context]",
+                        total_turns, input_tokens, output_tokens, context_tokens
                     );
                     break;
                 }
@@ -1361,17 +2056,40 @@ async fn run_once(
                         tool_name, consecutive_calls
                     );
                 }
+                AgentEvent::GuardianRepeatedAnswer {
+                    repeats, action, ..
+                } => {
+                    eprintln!("\n[GUARDIAN] Repeated answer x{}: {}", repeats, action);
+                }
+                AgentEvent::ContextNearLimit {
+                    context_tokens,
+                    limit_tokens,
+                    ..
+                } => {
+                    eprintln!(
+                        "\n[CONTEXT] ~{} of {} tokens used and compaction is off; /nocompact turns it back on",
+                        context_tokens, limit_tokens
+                    );
+                }
                 AgentEvent::GuardianBudgetAlert {
                     used_tokens,
                     budget_tokens,
+                    used_usd,
+                    budget_usd,
                     is_hard_stop,
                     ..
                 } => {
                     let kind = if is_hard_stop { "HARD STOP" } else { "warning" };
-                    eprintln!(
-                        "\n[GUARDIAN] Budget {}: {}/{} tokens",
-                        kind, used_tokens, budget_tokens
-                    );
+                    match (used_usd, budget_usd) {
+                        (Some(used), Some(budget)) => eprintln!(
+                            "\n[GUARDIAN] Cost budget {}: ${:.2}/${:.2}",
+                            kind, used, budget
+                        ),
+                        _ => eprintln!(
+                            "\n[GUARDIAN] Budget {}: {}/{} tokens",
+                            kind, used_tokens, budget_tokens
+                        ),
+                    }
                 }
                 AgentEvent::GoalEvaluated { evaluation, .. } => {
                     let status = if evaluation.passed {
@@ -1400,6 +2118,23 @@ async fn run_once(
                     };
                     eprintln!("\n{}", text);
                 }
+                AgentEvent::SubAgent { depth, event, .. } => {
+                    let indent = "  ".repeat(depth as usize);
+                    match *event {
+                        AgentEvent::ToolStart { name, .. } => {
+                            eprintln!("\n{}[sub-agent tool: {}]", indent, name);
+                        }
+                        AgentEvent::ToolEnd { name, result } if result.is_error => {
+                            eprintln!(
+                                "{}[sub-agent {}: ERROR] {}",
+                                indent,
+                                name,
+                                truncate(&result.content, 200)
+                            );
+                        }
+                        _ => {}
+                    }
+                }
                 AgentEvent::GuardianHint { .. }
                 | AgentEvent::UsageUpdate { .. }
                 | AgentEvent::DecisionMade { .. } => {}
@@ -1420,44 +2155,152 @@ async fn run_once(
 
     // Fire on_response hook
     if let Some(hooks) = hooks {
-        ryvos_core::hooks::run_hooks(&hooks.on_response, &[("RYVOS_SESSION", &session_id.0)]).await;
+        let env = [("RYVOS_SESSION", session_id.0.as_str())];
+        let event = HookEvent::new("response", hooks.payload, &env);
+        ryvos_core::hooks::run_hooks(&hooks.on_response, &event).await;
     }
 
-    println!();
-    print_handle.abort();
+    if output.events {
+        // Let the printer write the terminal event before returning
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(1), print_handle).await;
+    } else {
+        println!();
+        print_handle.abort();
+    }
     Ok(())
 }
 
+/// Run the `on_tool_call` hooks for a tool start in the background.
+fn fire_tool_call_hooks(
+    cmds: &[String],
+    payload: HookPayload,
+    session_id: &str,
+    name: String,
+    input: serde_json::Value,
+) {
+    if cmds.is_empty() {
+        return;
+    }
+    let cmds = cmds.to_vec();
+    let sid = session_id.to_string();
+    tokio::spawn(async move {
+        let env = [
+            ("RYVOS_SESSION", sid.as_str()),
+            ("RYVOS_TOOL", name.as_str()),
+        ];
+        let event = HookEvent::new("tool_call", payload, &env).with("input", input);
+        ryvos_core::hooks::run_hooks(&cmds, &event).await;
+    });
+}
+
+/// REPL commands for `/help`, with the message key of each description.
+const REPL_COMMANDS: &[(&str, &str)] = &[
+    ("/quit", "help.quit"),
+    ("/clear", "help.clear"),
+    ("/session [id|new|last]", "help.session"),
+    ("/status", "help.status"),
+    ("/usage", "help.usage"),
+    ("/tokens [budget]", "help.tokens"),
+    ("/tools", "help.tools"),
+    ("/think [level]", "help.think"),
+    ("/temp [temperature] [top_p]", "help.temp"),
+    ("/model <provider> <model_id>", "help.model"),
+    ("/cd [dir]", "help.cd"),
+    ("/compact", "help.compact"),
+    ("/nocompact", "help.nocompact"),
+    ("/export [markdown|json] [path]", "help.export"),
+    ("/pin <note>", "help.pin"),
+    ("/unpin <n>", "help.unpin"),
+    ("/pins", "help.pins"),
+    ("/notools", "help.notools"),
+    ("/security", "help.security"),
+    ("/approve <id> [CONFIRM]", "help.approve"),
+    ("/deny <id> [reason]", "help.deny"),
+    ("/mcp", "help.mcp"),
+    ("/mcp list", "help.mcp_list"),
+    ("/mcp connect <name>", "help.mcp_connect"),
+    ("/mcp disconnect <name>", "help.mcp_disconnect"),
+    ("/mcp resources [server]", "help.mcp_resources"),
+    ("/mcp prompts [server]", "help.mcp_prompts"),
+    ("/mcp tools [server]", "help.mcp_tools"),
+    ("/mcp logs <server> [level]", "help.mcp_logs"),
+    ("/prompts", "help.prompts"),
+    ("/soul", "help.soul"),
+];
+
+/// Adapters for the configured channels, unstarted, for routing a notice
+/// from a one-shot command. Telegram and WhatsApp reach their allowed users
+/// directly; Discord and Slack only reach channels seen by a running daemon.
+fn notice_adapters(
+    config: &AppConfig,
+    session_mgr: &Arc<ryvos_agent::SessionManager>,
+) -> std::collections::HashMap<String, Arc<dyn ryvos_core::traits::ChannelAdapter>> {
+    let mut adapters: Vec<Arc<dyn ryvos_core::traits::ChannelAdapter>> = Vec::new();
+    if let Some(ref tg) = config.channels.telegram {
+        adapters.push(Arc::new(ryvos_channels::TelegramAdapter::new(
+            tg.clone(),
+            session_mgr.clone(),
+        )));
+    }
+    if let Some(ref dc) = config.channels.discord {
+        adapters.push(Arc::new(ryvos_channels::DiscordAdapter::new(
+            dc.clone(),
+            session_mgr.clone(),
+        )));
+    }
+    if let Some(ref slack) = config.channels.slack {
+        adapters.push(Arc::new(ryvos_channels::SlackAdapter::new(
+            slack.clone(),
+            session_mgr.clone(),
+        )));
+    }
+    if let Some(ref wa) = config.channels.whatsapp {
+        adapters.push(Arc::new(ryvos_channels::WhatsAppAdapter::new(
+            wa.clone(),
+            session_mgr.clone(),
+        )));
+    }
+    adapters
+        .into_iter()
+        .map(|adapter| (adapter.name().to_string(), adapter))
+        .collect()
+}
+
 pub(crate) async fn run_repl(
     runtime: &AgentRuntime,
     event_bus: &EventBus,
-    session_id: &SessionId,
+    initial_session: &SessionId,
     config: &AppConfig,
     tools: &Arc<tokio::sync::RwLock<ToolRegistry>>,
     broker: &Arc<ApprovalBroker>,
     mcp_manager: &Option<Arc<ryvos_mcp::McpClientManager>>,
 ) -> anyhow::Result<()> {
     println!("Ryvos v{}", env!("CARGO_PKG_VERSION"));
-    println!("Session: {}", session_id);
+    println!("Session: {}", initial_session);
     println!(
-        "Security: auto-approve up to {}",
-        config.security.auto_approve_up_to
+        "{}",
+        messages::format("repl.security", &[&config.security.auto_approve_up_to])
     );
-    println!("Type /help for commands, /quit to exit.\n");
+    println!("{}\n", messages::t("repl.hint"));
 
     // Fire on_start hook
     if let Some(ref hooks) = config.hooks {
-        ryvos_core::hooks::run_hooks(&hooks.on_start, &[("RYVOS_SESSION", &session_id.0)]).await;
+        let env = [("RYVOS_SESSION", initial_session.0.as_str())];
+        let event = HookEvent::new("start", hooks.payload, &env);
+        ryvos_core::hooks::run_hooks(&hooks.on_start, &event).await;
     }
 
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut total_input: u64 = 0;
     let mut total_output: u64 = 0;
+    let mut context_tokens: u64 = 0;
     let mut session_thinking = config.model.thinking.clone();
-    let mut _force_compact = false;
+    let mut token_budget: Option<u64> = None;
+    let mut session = initial_session.clone();
 
     loop {
+        let session_id = &session;
         print!("> ");
         stdout.flush()?;
 
@@ -1483,7 +2326,18 @@ pub(crate) async fn run_repl(
                 continue;
             }
             "/session" => {
-                println!("Session ID: {}", session_id);
+                if let Some(requested) = parts.get(1) {
+                    let next = ryvos_agent::session::enter_cli_session(
+                        Some(requested),
+                        None,
+                        &config.workspace_dir(),
+                    );
+                    if next != session {
+                        tools.read().await.end_session(&session).await;
+                        session = next;
+                    }
+                }
+                println!("Session ID: {}", session);
                 continue;
             }
             "/status" => {
@@ -1494,34 +2348,55 @@ pub(crate) async fn run_repl(
                     .into_iter()
                     .map(|s| s.to_string())
                     .collect::<Vec<_>>();
-                println!("Session: {}", session_id);
+                println!("{}", messages::format("status.session", &[session_id]));
+                let model = runtime.model_config();
                 println!(
-                    "Model: {} ({})",
-                    config.model.model_id, config.model.provider
+                    "{}",
+                    messages::format("status.model", &[&model.model_id, &model.provider])
                 );
-                println!("Thinking: {:?}", session_thinking);
-                println!("Tools: {}", tool_list.join(", "));
+                let thinking = format!("{:?}", session_thinking);
+                println!("{}", messages::format("status.thinking", &[&thinking]));
+                let tool_list = tool_list.join(", ");
+                println!("{}", messages::format("status.tools", &[&tool_list]));
                 if let Some(ref mgr) = mcp_manager {
                     let servers = mgr.connected_servers().await;
                     if servers.is_empty() {
-                        println!("MCP: no servers connected");
+                        println!("{}", messages::t("status.mcp_no_servers"));
                     } else {
                         println!(
-                            "MCP: {} server(s) connected ({})",
-                            servers.len(),
-                            servers.join(", ")
+                            "{}",
+                            messages::format(
+                                "status.mcp_connected",
+                                &[&servers.len(), &servers.join(", ")]
+                            )
                         );
                     }
                 } else {
-                    println!("MCP: none configured");
+                    println!("{}", messages::t("status.mcp_not_configured"));
                 }
+                let m = runtime.metrics().snapshot();
+                println!(
+                    "{}",
+                    messages::format(
+                        "status.activity",
+                        &[
+                            &m.runs,
+                            &m.run_errors,
+                            &m.tool_calls,
+                            &m.tool_errors,
+                            &m.input_tokens,
+                            &m.output_tokens
+                        ]
+                    )
+                );
                 continue;
             }
             "/usage" => {
                 println!(
-                    "Session tokens -- Input: {}, Output: {}",
-                    total_input, total_output
+                    "{}",
+                    messages::format("usage.billed", &[&total_input, &total_output])
                 );
+                println!("{}", messages::format("usage.context", &[&context_tokens]));
                 continue;
             }
             "/tools" => {
@@ -1543,20 +2418,204 @@ pub(crate) async fn run_repl(
                     Some("low") => ThinkingLevel::Low,
                     Some("medium") | Some("hard") => ThinkingLevel::Medium,
                     Some("high") => ThinkingLevel::High,
-                    Some(other) => {
+                    Some(other) => match other.parse::<u32>() {
+                        Ok(budget) => ThinkingLevel::Custom(budget),
+                        Err(_) => {
+                            println!(
+                                "Unknown thinking level: {}. Use off/low/medium/high or a token budget.",
+                                other
+                            );
+                            continue;
+                        }
+                    },
+                };
+                println!("Thinking level: {:?}", session_thinking);
+                continue;
+            }
+            "/temp" => {
+                let Some(temperature) = parts.get(1) else {
+                    let model = runtime.model_config();
+                    println!("Temperature: {}", model.temperature);
+                    if let Some(top_p) = model.top_p {
+                        println!("top_p: {}", top_p);
+                    }
+                    println!("Usage: /temp <temperature> [top_p]  (next message only)");
+                    continue;
+                };
+                let parse = |value: Option<&&str>| value.map(|v| v.parse::<f32>()).transpose();
+                let sampling = match (parse(Some(temperature)), parse(parts.get(2))) {
+                    (Ok(t), Ok(p)) => SamplingOverride::new(t, p),
+                    _ => {
+                        println!("Usage: /temp <temperature> [top_p]");
+                        continue;
+                    }
+                };
+                match sampling {
+                    Ok(sampling) => {
+                        runtime.set_run_sampling(session_id, sampling);
+                        let shown: Vec<String> = [
+                            sampling.temperature.map(|t| format!("temperature {}", t)),
+                            sampling.top_p.map(|p| format!("top_p {}", p)),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .collect();
+                        println!("Next message only: {}", shown.join(", "));
+                    }
+                    Err(e) => println!("{}", e),
+                }
+                continue;
+            }
+            "/model" => {
+                let (Some(provider), Some(model_id)) = (parts.get(1), parts.get(2)) else {
+                    let model = runtime.model_config();
+                    println!("Model: {} ({})", model.model_id, model.provider);
+                    println!("Usage: /model <provider> <model_id>");
+                    continue;
+                };
+                let switched = runtime.switch_model(provider, model_id, |model| {
+                    ryvos_llm::build_client(
+                        model,
+                        &config.fallback_models,
+                        &config.security.dangerous_patterns,
+                    )
+                });
+                match switched {
+                    Ok(model) => println!("Model: {} ({})", model.model_id, model.provider),
+                    Err(e) => println!("{}", e),
+                }
+                continue;
+            }
+            "/cd" => {
+                let sessions = runtime.session_manager();
+                let dir = input["/cd".len()..].trim();
+                if !dir.is_empty() {
+                    if let Err(e) = sessions.set_working_dir(session_id, dir) {
+                        println!("{}", e);
+                        continue;
+                    }
+                }
+                let cwd = sessions
+                    .working_dir(session_id)
+                    .or_else(|| std::env::current_dir().ok())
+                    .unwrap_or_default();
+                println!("Working directory: {}", cwd.display());
+                continue;
+            }
+            "/pin" => {
+                let note = input["/pin".len()..].trim();
+                if note.is_empty() {
+                    println!("Usage: /pin <note>  (list with /pins)");
+                } else {
+                    let n = runtime.session_manager().pin(session_id, note);
+                    println!("Pinned #{}: kept in context through every compaction.", n);
+                }
+                continue;
+            }
+            "/unpin" => {
+                match parts.get(1).and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) => match runtime.session_manager().unpin(session_id, n) {
+                        Some(note) => println!("Unpinned #{}: {}", n, note),
+                        None => println!("No pinned note #{} (list with /pins)", n),
+                    },
+                    None => println!("Usage: /unpin <number>"),
+                }
+                continue;
+            }
+            "/pins" => {
+                let pins = runtime.session_manager().pins(session_id);
+                if pins.is_empty() {
+                    println!("No pinned notes. Add one with /pin <note>.");
+                }
+                for (i, note) in pins.iter().enumerate() {
+                    println!("  {}. {}", i + 1, note);
+                }
+                continue;
+            }
+            "/tokens" => {
+                if let Some(arg) = parts.get(1) {
+                    match arg.parse::<u64>() {
+                        Ok(0) => token_budget = None,
+                        Ok(budget) => token_budget = Some(budget),
+                        Err(_) => {
+                            println!("Usage: /tokens [budget]  (0 clears the budget)");
+                            continue;
+                        }
+                    }
+                }
+                let used = total_input + total_output;
+                println!(
+                    "Session tokens -- Input: {}, Output: {}, Total: {}",
+                    total_input, total_output, used
+                );
+                match token_budget {
+                    Some(budget) => {
                         println!(
-                            "Unknown thinking level: {}. Use off/low/medium/high.",
-                            other
+                            "Budget: {} ({}% used, {} remaining)",
+                            budget,
+                            used * 100 / budget,
+                            budget.saturating_sub(used)
                         );
-                        continue;
+                        if let Some(warning) = token_budget_warning(used, budget) {
+                            println!("{}", warning);
+                        }
                     }
+                    None => println!("Budget: none (set with /tokens <budget>)"),
+                }
+                continue;
+            }
+            "/export" => {
+                use ryvos_agent::session_export;
+                let target = session_export::resolve_target(
+                    &parts[1..],
+                    session_id,
+                    &config.workspace_dir(),
+                    chrono::Utc::now(),
+                );
+                let result = match target {
+                    Ok((format, path)) => session_export::export_session(
+                        runtime.store().as_ref(),
+                        session_id,
+                        format,
+                        &path,
+                    )
+                    .await
+                    .map(|n| (n, path)),
+                    Err(e) => Err(e),
                 };
-                println!("Thinking level: {:?}", session_thinking);
+                match result {
+                    Ok((n, path)) => println!("Exported {} messages to {}", n, path.display()),
+                    Err(e) => println!("Export failed: {}", e),
+                }
                 continue;
             }
             "/compact" => {
+                runtime.request_compaction(session_id);
                 println!("Context will be compacted on next message.");
-                _force_compact = true;
+                continue;
+            }
+            "/nocompact" => {
+                let off = !runtime.no_compact(session_id).await.unwrap_or(false);
+                if let Err(e) = runtime.set_no_compact(session_id, off).await {
+                    println!("Could not save the setting: {}", e);
+                }
+                if off {
+                    println!("Compaction off for this session: history is never flushed, summarized or pruned.");
+                    println!(
+                        "Long sessions may hit the model's context limit; /compact still works."
+                    );
+                } else {
+                    println!("Compaction on.");
+                }
+                continue;
+            }
+            "/notools" => {
+                runtime.set_no_tools(session_id, !runtime.no_tools(session_id));
+                if runtime.no_tools(session_id) {
+                    println!("Tools off: replies are plain chat.");
+                } else {
+                    println!("Tools on.");
+                }
                 continue;
             }
             "/security" => {
@@ -1574,6 +2633,15 @@ pub(crate) async fn run_repl(
                     "  Approval timeout: {}s",
                     config.security.approval_timeout_secs
                 );
+                if config.security.safe_mode {
+                    println!("  Safe mode: on (approval above T0, T2+ blocked)");
+                    if !config.security.safe_mode_allow.is_empty() {
+                        println!(
+                            "  Safe mode allow: {}",
+                            config.security.safe_mode_allow.join(", ")
+                        );
+                    }
+                }
                 if !config.security.tool_overrides.is_empty() {
                     println!("  Tool overrides:");
                     for (tool, tier) in &config.security.tool_overrides {
@@ -1593,6 +2661,13 @@ pub(crate) async fn run_repl(
                             req.tier,
                             req.input_summary
                         );
+                        if !req.calls.is_empty() {
+                            let short_id = &req.id[..8];
+                            println!(
+                                "      {}",
+                                messages::format("approval.calls_needed", &[&short_id, &short_id])
+                            );
+                        }
                     }
                 }
                 continue;
@@ -1600,16 +2675,30 @@ pub(crate) async fn run_repl(
             "/approve" => {
                 if let Some(prefix) = parts.get(1) {
                     if let Some(full_id) = broker.find_by_prefix(prefix).await {
-                        if broker.respond(&full_id, ApprovalDecision::Approved).await {
-                            println!("Approved: {}", &full_id[..8]);
+                        let decision =
+                            ApprovalDecision::from_approve_args(parts.get(2..).unwrap_or_default());
+                        if let Some(word) = broker.confirm_word(&full_id).await {
+                            if !decision.confirms(&word) {
+                                println!(
+                                    "{}",
+                                    messages::format("approval.confirm_needed", &[prefix, &word])
+                                );
+                                continue;
+                            }
+                        }
+                        if broker.respond(&full_id, decision).await {
+                            println!(
+                                "{}",
+                                messages::format("approval.approved", &[&&full_id[..8]])
+                            );
                         } else {
-                            println!("Request not found (may have timed out).");
+                            println!("{}", messages::t("approval.not_found"));
                         }
                     } else {
-                        println!("No pending request matching '{}'.", prefix);
+                        println!("{}", messages::format("approval.no_match", &[prefix]));
                     }
                 } else {
-                    println!("Usage: /approve <id-prefix>");
+                    println!("Usage: /approve <id-prefix> [CONFIRM | call numbers]");
                 }
                 continue;
             }
@@ -1625,12 +2714,12 @@ pub(crate) async fn run_repl(
                             .respond(&full_id, ApprovalDecision::Denied { reason })
                             .await
                         {
-                            println!("Denied: {}", &full_id[..8]);
+                            println!("{}", messages::format("approval.denied", &[&&full_id[..8]]));
                         } else {
-                            println!("Request not found (may have timed out).");
+                            println!("{}", messages::t("approval.not_found"));
                         }
                     } else {
-                        println!("No pending request matching '{}'.", prefix);
+                        println!("{}", messages::format("approval.no_match", &[prefix]));
                     }
                 } else {
                     println!("Usage: /deny <id-prefix> [reason]");
@@ -1671,27 +2760,10 @@ pub(crate) async fn run_repl(
                 continue;
             }
             "/help" => {
-                println!("Commands:");
-                println!("  /quit       Exit");
-                println!("  /clear      Reset session context");
-                println!("  /session    Show session ID");
-                println!("  /status     Show agent status");
-                println!("  /usage      Show token usage");
-                println!("  /tools      List available tools");
-                println!("  /think [level]  Set thinking level (off/low/medium/high)");
-                println!("  /compact    Force context compaction");
-                println!("  /security   Show security policy and pending approvals");
-                println!("  /approve <id>   Approve a pending tool call");
-                println!("  /deny <id> [reason]  Deny a pending tool call");
-                println!("  /mcp            Show MCP status");
-                println!("  /mcp list       List configured servers");
-                println!("  /mcp connect <name>   Connect to a server");
-                println!("  /mcp disconnect <name>  Disconnect from a server");
-                println!("  /mcp resources [server]  List MCP resources");
-                println!("  /mcp prompts [server]  List MCP prompts");
-                println!("  /mcp tools [server]  List MCP tools");
-                println!("  /prompts    List all MCP prompts");
-                println!("  /soul       Personalize your agent");
+                println!("{}", messages::t("help.title"));
+                for (command, key) in REPL_COMMANDS {
+                    println!("  {:<11} {}", command, messages::t(key));
+                }
                 continue;
             }
             _ if input.starts_with("/mcp__") => {
@@ -1739,6 +2811,7 @@ pub(crate) async fn run_repl(
                                         &combined,
                                         &config.hooks,
                                         broker,
+                                        StreamOptions::default(),
                                     )
                                     .await?;
                                 }
@@ -1754,10 +2827,7 @@ pub(crate) async fn run_repl(
                 continue;
             }
             _ if input.starts_with('/') => {
-                println!(
-                    "Unknown command: {}. Type /help for available commands.",
-                    parts[0]
-                );
+                println!("{}", messages::format("repl.unknown_command", &[&parts[0]]));
                 continue;
             }
             _ => {}
@@ -1766,37 +2836,72 @@ pub(crate) async fn run_repl(
         // Subscribe to events to track token usage
         let mut rx = event_bus.subscribe();
         let usage_handle = tokio::spawn(async move {
-            let mut inp = 0u64;
-            let mut out = 0u64;
+            let mut usage = None;
             while let Ok(event) = rx.recv().await {
                 match event {
                     AgentEvent::RunComplete {
                         input_tokens,
                         output_tokens,
+                        context_tokens,
                         ..
                     } => {
-                        inp = input_tokens;
-                        out = output_tokens;
+                        usage = Some((input_tokens, output_tokens, context_tokens));
                         break;
                     }
                     AgentEvent::RunError { .. } => break,
                     _ => {}
                 }
             }
-            (inp, out)
+            usage
         });
 
-        run_once(runtime, event_bus, session_id, input, &config.hooks, broker).await?;
+        run_once(
+            runtime,
+            event_bus,
+            session_id,
+            input,
+            &config.hooks,
+            broker,
+            StreamOptions::default(),
+        )
+        .await?;
 
-        if let Ok((inp, out)) = usage_handle.await {
+        if let Ok(Some((inp, out, ctx))) = usage_handle.await {
             total_input += inp;
             total_output += out;
+            context_tokens = ctx;
+            if let Some(budget) = token_budget {
+                if let Some(warning) = token_budget_warning(total_input + total_output, budget) {
+                    println!("{}", warning);
+                }
+            }
         }
     }
 
+    runtime.settle_session_summaries().await;
+    tools.read().await.end_session(&session).await;
     Ok(())
 }
 
+/// Warning shown once session usage reaches 80% of the `/tokens` budget.
+fn token_budget_warning(used: u64, budget: u64) -> Option<String> {
+    if used >= budget {
+        Some(format!(
+            "Warning: token budget exceeded ({} / {}). Consider /compact or /model.",
+            used, budget
+        ))
+    } else if used * 5 >= budget * 4 {
+        Some(format!(
+            "Warning: {}% of the token budget used ({} / {}).",
+            used * 100 / budget,
+            used,
+            budget
+        ))
+    } else {
+        None
+    }
+}
+
 /// Handle /mcp REPL commands.
 async fn handle_mcp_repl(
     args: &[&str],
@@ -1963,15 +3068,36 @@ async fn handle_mcp_repl(
                 }
             }
         }
+        "logs" => {
+            let Some(server) = args.get(1) else {
+                println!("Usage: /mcp logs <server> [level]");
+                return;
+            };
+            let level = match args.get(2).map(|l| l.parse()) {
+                None => ryvos_mcp::McpLogLevel::Debug,
+                Some(Ok(level)) => level,
+                Some(Err(e)) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let entries = mgr.logs(server, level);
+            if entries.is_empty() {
+                println!("No log messages from '{}'.", server);
+            }
+            for entry in entries {
+                print_mcp_log(entry.timestamp, entry.level.as_str(), &entry.message);
+            }
+        }
         _ => {
             println!("Unknown MCP command: {}", subcommand);
-            println!("Usage: /mcp [status|list|connect|disconnect|resources|prompts|tools]");
+            println!("Usage: /mcp [status|list|connect|disconnect|resources|prompts|tools|logs]");
         }
     }
 }
 
 /// Handle `ryvos mcp` CLI subcommands.
-fn handle_mcp_cli(action: &McpAction, config_path: &PathBuf) -> anyhow::Result<()> {
+async fn handle_mcp_cli(action: &McpAction, config_path: &PathBuf) -> anyhow::Result<()> {
     let config_path = if config_path == &PathBuf::from("ryvos.toml") && !config_path.exists() {
         dirs_home()
             .map(|h| h.join(".ryvos").join("config.toml"))
@@ -1999,6 +3125,7 @@ fn handle_mcp_cli(action: &McpAction, config_path: &PathBuf) -> anyhow::Result<(
                                 ryvos_core::config::McpTransport::Sse { url } => {
                                     format!("sse: {}", url)
                                 }
+                                other => other.kind().to_string(),
                             };
                             let auto = if server.auto_connect {
                                 "auto"
@@ -2021,104 +3148,170 @@ fn handle_mcp_cli(action: &McpAction, config_path: &PathBuf) -> anyhow::Result<(
             args,
             url,
             env,
+            force,
         } => {
-            let transport_section = if let Some(cmd) = command {
-                let args_str = if args.is_empty() {
-                    String::new()
-                } else {
-                    format!(
-                        "\nargs = [{}]",
-                        args.iter()
-                            .map(|a| format!("\"{}\"", a))
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    )
-                };
-                let env_str = if env.is_empty() {
-                    String::new()
-                } else {
-                    let pairs: Vec<String> = env
-                        .iter()
-                        .filter_map(|e| {
-                            let (k, v) = e.split_once('=')?;
-                            Some(format!("{} = \"{}\"", k, v))
-                        })
-                        .collect();
-                    if pairs.is_empty() {
-                        String::new()
-                    } else {
-                        format!(
-                            "\n\n[mcp.servers.{}.transport.env]\n{}",
-                            name,
-                            pairs.join("\n")
-                        )
-                    }
-                };
-                format!(
-                    "\n[mcp.servers.{name}]\nauto_connect = true\n\n[mcp.servers.{name}.transport]\ntype = \"stdio\"\ncommand = \"{cmd}\"{args_str}{env_str}\n",
-                )
+            let env = env
+                .iter()
+                .map(|e| {
+                    e.split_once('=')
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .ok_or_else(|| anyhow::anyhow!("--env expects KEY=VALUE, got '{}'", e))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let transport = if let Some(command) = command {
+                mcp_config::ServerTransport::Stdio {
+                    command,
+                    args,
+                    env: &env,
+                }
             } else if let Some(url) = url {
-                format!(
-                    "\n[mcp.servers.{name}]\nauto_connect = true\n\n[mcp.servers.{name}.transport]\ntype = \"sse\"\nurl = \"{url}\"\n",
-                )
+                mcp_config::ServerTransport::Sse { url }
             } else {
                 eprintln!("Error: either --command or --url must be specified");
                 return Ok(());
             };
 
-            // Append to config file
-            if config_path.exists() {
-                let mut content = std::fs::read_to_string(&config_path)?;
-                content.push_str(&transport_section);
-                std::fs::write(&config_path, content)?;
-            } else {
-                // Create minimal config
-                std::fs::create_dir_all(config_path.parent().unwrap_or(&PathBuf::from(".")))?;
-                std::fs::write(&config_path, &transport_section)?;
-            }
+            let mut doc = mcp_config::load(&config_path)?;
+            mcp_config::add_server(&mut doc, name, &transport, *force)?;
+            mcp_config::save(&config_path, &doc)?;
             println!("Added MCP server '{}' to {}", name, config_path.display());
         }
         McpAction::Remove { name } => {
             if config_path.exists() {
-                let content = std::fs::read_to_string(&config_path)?;
-                // Simple removal: filter out lines matching this server section
-                // For a production implementation, use a TOML library for precise editing
-                let section_header = format!("[mcp.servers.{}]", name);
-                let transport_header = format!("[mcp.servers.{}.transport]", name);
-                let env_header = format!("[mcp.servers.{}.transport.env]", name);
-
-                let mut result = Vec::new();
-                let mut skip = false;
-                for line in content.lines() {
-                    let trimmed = line.trim();
-                    if trimmed == section_header
-                        || trimmed == transport_header
-                        || trimmed == env_header
-                    {
-                        skip = true;
-                        continue;
-                    }
-                    if skip && trimmed.starts_with('[') {
-                        skip = false;
-                    }
-                    if !skip {
-                        result.push(line);
-                    }
+                let mut doc = mcp_config::load(&config_path)?;
+                if mcp_config::remove_server(&mut doc, name) {
+                    mcp_config::save(&config_path, &doc)?;
+                    println!(
+                        "Removed MCP server '{}' from {}",
+                        name,
+                        config_path.display()
+                    );
+                } else {
+                    println!(
+                        "No MCP server named '{}' in {}",
+                        name,
+                        config_path.display()
+                    );
                 }
-                std::fs::write(&config_path, result.join("\n"))?;
-                println!(
-                    "Removed MCP server '{}' from {}",
-                    name,
-                    config_path.display()
-                );
             } else {
                 println!("Config file not found: {}", config_path.display());
             }
         }
+        McpAction::Logs {
+            server,
+            follow,
+            level,
+        } => mcp_logs(&config_path, server, *level, *follow).await?,
+    }
+    Ok(())
+}
+
+/// `ryvos mcp logs`: connect to one server on its own and print what it
+/// logs. Only messages sent to this connection are shown; use `/mcp logs`
+/// in the REPL for the servers a running session is connected to.
+async fn mcp_logs(
+    config_path: &std::path::Path,
+    server: &str,
+    level: ryvos_mcp::McpLogLevel,
+    follow: bool,
+) -> anyhow::Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut servers = if config_path.exists() {
+        AppConfig::load(config_path)?
+            .mcp
+            .unwrap_or_default()
+            .servers
+    } else {
+        Default::default()
+    };
+    if let Some(project_mcp) = load_mcp_json() {
+        for (name, entry) in project_mcp.mcp_servers {
+            if let Some(server_config) = entry.to_server_config() {
+                servers.entry(name).or_insert(server_config);
+            }
+        }
+    }
+    let server_config = servers
+        .get(server)
+        .ok_or_else(|| anyhow::anyhow!("No MCP server named '{}'", server))?;
+
+    let manager = ryvos_mcp::McpClientManager::new();
+    let mut events = manager.subscribe_events();
+    manager.connect(server, server_config).await?;
+    // Servers without the logging capability reject this but may log anyway
+    if let Err(e) = manager.set_log_level(server, level).await {
+        warn!(server = %server, error = %e, "Server did not accept a log level");
+    }
+
+    if follow {
+        println!("Following logs from '{}' (Ctrl-C to stop)", server);
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                event = events.recv() => match event {
+                    Ok(ryvos_mcp::McpEvent::LogMessage { level: l, message, .. }) => {
+                        if l.parse::<ryvos_mcp::McpLogLevel>().is_ok_and(|l| l >= level) {
+                            print_mcp_log(chrono::Utc::now(), &l, &message);
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    } else {
+        // Give the server a moment to send its startup messages
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        let entries = manager.logs(server, level);
+        if entries.is_empty() {
+            println!("No log messages from '{}'.", server);
+        }
+        for entry in entries {
+            print_mcp_log(entry.timestamp, entry.level.as_str(), &entry.message);
+        }
     }
+
+    manager.disconnect_all().await;
     Ok(())
 }
 
+fn print_mcp_log(timestamp: chrono::DateTime<chrono::Utc>, level: &str, message: &str) {
+    println!(
+        "{} {:<9} {}",
+        timestamp.with_timezone(&chrono::Local).format("%H:%M:%S"),
+        level,
+        message
+    );
+}
+
+/// One run log entry as `time session event [turn] detail`.
+fn print_run_log(entry: &serde_json::Value) {
+    let time = entry["timestamp"]
+        .as_str()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let session = entry["session_id"].as_str().unwrap_or_default();
+    let mut line = format!(
+        "{} {:<8} {:<24}",
+        time,
+        truncate(session, 8),
+        entry["event_type"].as_str().unwrap_or_default()
+    );
+    if let Some(turn) = entry["turn"].as_u64() {
+        line.push_str(&format!(" turn {}", turn));
+    }
+    if !entry["detail"].is_null() {
+        line.push_str(&format!(" {}", entry["detail"]));
+    }
+    println!("{}", line.trim_end());
+}
+
 /// Handle `ryvos skill` CLI subcommands.
 async fn handle_skill_cli(action: &SkillAction) -> anyhow::Result<()> {
     let home = dirs_home().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
@@ -2182,28 +3375,182 @@ async fn handle_skill_cli(action: &SkillAction) -> anyhow::Result<()> {
             let registry_url = ryvos_core::config::RegistryConfig::default().url;
             match ryvos_skills::registry::fetch_index(&registry_url).await {
                 Ok(index) => {
-                    if let Some(entry) = index.skills.iter().find(|e| e.name == *name) {
-                        match ryvos_skills::registry::install_skill(entry, &skills_dir).await {
-                            Ok(_path) => println!("Installed skill '{}'", name),
-                            Err(e) => eprintln!("Failed to install skill '{}': {}", name, e),
+                    let (skill, _) = ryvos_skills::lockfile::parse_spec(name);
+                    let installed =
+                        ryvos_skills::lockfile::install_spec(&index, name, &skills_dir, |p| {
+                            print_download_progress(skill, p)
+                        })
+                        .await;
+                    match installed {
+                        Ok(locked) => {
+                            println!();
+                            println!("Installed skill '{}' v{}", locked.name, locked.version);
                         }
-                    } else {
-                        eprintln!("Skill '{}' not found in registry", name);
+                        Err(e) => eprintln!("\nFailed to install skill '{}': {}", name, e),
                     }
                 }
                 Err(e) => eprintln!("Failed to fetch registry: {}", e),
             }
         }
+        SkillAction::Sync => match ryvos_skills::lockfile::sync(&skills_dir).await {
+            Ok(report) => {
+                for name in &report.installed {
+                    println!("Reinstalled '{}'", name);
+                }
+                for (name, e) in &report.failed {
+                    eprintln!("Failed to reinstall '{}': {}", name, e);
+                }
+                for name in &report.unlocked {
+                    println!("'{}' is installed but not in skills.lock", name);
+                }
+                println!(
+                    "{} skill(s) up to date, {} reinstalled, {} failed",
+                    report.present.len(),
+                    report.installed.len(),
+                    report.failed.len()
+                );
+            }
+            Err(e) => eprintln!("Failed to sync skills: {}", e),
+        },
         SkillAction::Remove { name } => {
             match ryvos_skills::registry::remove_skill(name, &skills_dir) {
-                Ok(()) => println!("Removed skill '{}'", name),
+                Ok(()) => {
+                    let mut lock =
+                        ryvos_skills::lockfile::SkillsLock::load(&skills_dir).unwrap_or_default();
+                    if lock.remove(name) {
+                        if let Err(e) = lock.save(&skills_dir) {
+                            eprintln!("Failed to update skills.lock: {}", e);
+                        }
+                    }
+                    println!("Removed skill '{}'", name);
+                }
                 Err(e) => eprintln!("Failed to remove skill '{}': {}", name, e),
             }
         }
+        SkillAction::Run { name, input, args } => {
+            let input = ryvos_skills::skill_input(input.as_deref(), args)
+                .map_err(|e| anyhow::anyhow!("Invalid input for skill '{}': {}", name, e))?;
+            let working_dir = std::env::current_dir()?;
+            match ryvos_skills::run_skill(&skills_dir, name, input, working_dir).await {
+                Ok(result) => {
+                    println!("{}", result.content);
+                    if result.is_error {
+                        eprintln!("Skill '{}' failed", name);
+                        std::process::exit(1);
+                    }
+                    eprintln!("Skill '{}' succeeded", name);
+                }
+                Err(e) => {
+                    eprintln!("Cannot run skill '{}': {}", name, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handle `ryvos allow` and `ryvos deny`.
+async fn handle_rule_cli(
+    workspace: &std::path::Path,
+    action: RuleAction,
+    args: ApprovalRuleArgs,
+) -> anyhow::Result<()> {
+    let store = ryvos_agent::ApprovalRuleStore::open(&workspace.join("approvals.db"))
+        .map_err(|e| anyhow::anyhow!("Failed to open approval rules: {}", e))?;
+
+    if args.list {
+        let now = chrono::Utc::now();
+        let rules: Vec<_> = store
+            .list()
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?
+            .into_iter()
+            .filter(|r| r.action == action)
+            .collect();
+        if rules.is_empty() {
+            println!("No {} rules.", action);
+        }
+        for rule in &rules {
+            let pattern = rule
+                .pattern
+                .as_ref()
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| "(any arguments)".to_string());
+            let expiry = match rule.expires_at {
+                Some(t) if t <= now => " [expired]".to_string(),
+                Some(t) => format!(" [until {}]", t.format("%Y-%m-%d %H:%M UTC")),
+                None => String::new(),
+            };
+            println!("  #{:<4} {:<16} {}{}", rule.id, rule.tool, pattern, expiry);
+        }
+        return Ok(());
+    }
+
+    if let Some(id) = args.remove {
+        let rule = store
+            .list()
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?
+            .into_iter()
+            .find(|r| r.id == id && r.action == action);
+        if rule.is_none() || !store.remove(id).await.map_err(|e| anyhow::anyhow!(e))? {
+            anyhow::bail!("No {} rule #{}", action, id);
+        }
+        println!("Removed {} rule #{}", action, id);
+        return Ok(());
+    }
+
+    let tool = args.tool.expect("clap requires a tool");
+    let pattern = args.pattern.map(|p| {
+        if args.regex {
+            ArgPattern::Regex(p)
+        } else {
+            ArgPattern::Glob(p)
+        }
+    });
+    let expires_at = match args.expires {
+        Some(ref text) => {
+            Some(chrono::Utc::now() + parse_duration(text).map_err(|e| anyhow::anyhow!(e))?)
+        }
+        None => None,
+    };
+    let rule = store
+        .add(&tool, pattern, action, expires_at)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    println!(
+        "Added {} rule #{}: {} {}",
+        action,
+        rule.id,
+        rule.tool,
+        rule.pattern
+            .as_ref()
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "(any arguments)".to_string())
+    );
+    if let Some(t) = rule.expires_at {
+        println!("  Expires {}", t.format("%Y-%m-%d %H:%M UTC"));
     }
     Ok(())
 }
 
+/// Redraw the `ryvos skill install` progress line.
+fn print_download_progress(name: &str, progress: ryvos_skills::registry::DownloadProgress) {
+    let kb = progress.downloaded / 1024;
+    match progress.total {
+        Some(total) if total > 0 => print!(
+            "\rDownloading {}: {} / {} KB ({}%)",
+            name,
+            kb,
+            total / 1024,
+            progress.downloaded * 100 / total
+        ),
+        _ => print!("\rDownloading {}: {} KB", name, kb),
+    }
+    io::stdout().flush().ok();
+}
+
 /// Load .mcp.json from the current working directory.
 fn load_mcp_json() -> Option<McpJsonConfig> {
     let path = std::env::current_dir().ok()?.join(".mcp.json");
@@ -2251,9 +3598,13 @@ fn create_env_config() -> anyhow::Result<AppConfig> {
         } else {
             None
         },
-        max_tokens: 8192,
+        max_tokens: None,
         temperature: 0.0,
+        top_p: None,
         thinking: ThinkingLevel::Off,
+        stop_sequences: vec![],
+        seed: None,
+        api_keys: vec![],
         retry: None,
         azure_resource: None,
         azure_deployment: None,
@@ -2291,6 +3642,9 @@ fn create_env_config() -> anyhow::Result<AppConfig> {
         jira: None,
         linear: None,
         integrations: Default::default(),
+        orchestrator: None,
+        tools: Default::default(),
+        storage: Default::default(),
     })
 }
 
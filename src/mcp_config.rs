@@ -0,0 +1,216 @@
+//! Editing `[mcp.servers.<name>]` tables for `ryvos mcp add/remove`.
+//!
+//! The config is parsed with `toml_edit`, so only the server's own table
+//! changes; comments, key order and every other section are written back
+//! exactly as they were read.
+
+use std::path::Path;
+
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+/// Transport of a server being added.
+pub enum ServerTransport<'a> {
+    Stdio {
+        command: &'a str,
+        args: &'a [String],
+        env: &'a [(String, String)],
+    },
+    Sse {
+        url: &'a str,
+    },
+}
+
+/// Add server `name`. An existing server of that name is an error unless
+/// `replace` is set, in which case its whole table is replaced.
+pub fn add_server(
+    doc: &mut DocumentMut,
+    name: &str,
+    transport: &ServerTransport,
+    replace: bool,
+) -> anyhow::Result<()> {
+    let servers = servers_table(doc)?;
+    if servers.contains_key(name) && !replace {
+        anyhow::bail!(
+            "MCP server '{}' already exists (use --force to replace it)",
+            name
+        );
+    }
+
+    let mut transport_table = Table::new();
+    match transport {
+        ServerTransport::Stdio { command, args, env } => {
+            transport_table["type"] = value("stdio");
+            transport_table["command"] = value(*command);
+            if !args.is_empty() {
+                transport_table["args"] = value(args.iter().collect::<Array>());
+            }
+            if !env.is_empty() {
+                let mut env_table = Table::new();
+                for (key, val) in env.iter() {
+                    env_table[key.as_str()] = value(val.as_str());
+                }
+                transport_table["env"] = Item::Table(env_table);
+            }
+        }
+        ServerTransport::Sse { url } => {
+            transport_table["type"] = value("sse");
+            transport_table["url"] = value(*url);
+        }
+    }
+
+    let mut server = Table::new();
+    server["auto_connect"] = value(true);
+    server["transport"] = Item::Table(transport_table);
+    servers.insert(name, Item::Table(server));
+    Ok(())
+}
+
+/// Remove server `name`, with its transport and env tables. Returns
+/// whether it was configured.
+pub fn remove_server(doc: &mut DocumentMut, name: &str) -> bool {
+    doc.get_mut("mcp")
+        .and_then(|mcp| mcp.get_mut("servers"))
+        .and_then(Item::as_table_like_mut)
+        .and_then(|servers| servers.remove(name))
+        .is_some()
+}
+
+/// Read `path` for editing; a missing file is an empty document.
+pub fn load(path: &Path) -> anyhow::Result<DocumentMut> {
+    if !path.exists() {
+        return Ok(DocumentMut::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    content
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+pub fn save(path: &Path, doc: &DocumentMut) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, doc.to_string())?;
+    Ok(())
+}
+
+/// The `mcp.servers` table, created (without headers of its own) if absent.
+fn servers_table(doc: &mut DocumentMut) -> anyhow::Result<&mut Table> {
+    let mcp = doc
+        .entry("mcp")
+        .or_insert_with(implicit_table)
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("`mcp` in the config is not a table"))?;
+    mcp.entry("servers")
+        .or_insert_with(implicit_table)
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("`mcp.servers` in the config is not a table"))
+}
+
+fn implicit_table() -> Item {
+    let mut table = Table::new();
+    table.set_implicit(true);
+    Item::Table(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"# Ryvos config
+[model]
+provider = "anthropic" # main model
+model_id = "claude-sonnet-4-20250514"
+
+[mcp.servers.github]
+auto_connect = false
+
+[mcp.servers.github.transport]
+type = "stdio"
+command = "npx"
+args = ["-y", "@modelcontextprotocol/server-github"]
+
+# Filesystem access for the agent
+[mcp.servers.git]
+transport = { type = "sse", url = "http://localhost:9000/sse" }
+
+[gateway]
+bind = "127.0.0.1:18789"
+"#;
+
+    fn stdio<'a>(args: &'a [String], env: &'a [(String, String)]) -> ServerTransport<'a> {
+        ServerTransport::Stdio {
+            command: "uvx",
+            args,
+            env,
+        }
+    }
+
+    #[test]
+    fn add_then_remove_leaves_config_untouched() {
+        let mut doc: DocumentMut = CONFIG.parse().unwrap();
+        let args = vec!["mcp-server-fetch".to_string()];
+        let env = vec![("TOKEN".to_string(), "x=y".to_string())];
+        add_server(&mut doc, "fetch", &stdio(&args, &env), false).unwrap();
+
+        // New servers sit with the existing ones, ahead of later sections
+        let added = doc.to_string();
+        assert!(added.find("[mcp.servers.fetch]") < added.find("[gateway]"));
+        let config: ryvos_core::config::AppConfig = toml::from_str(&added).unwrap();
+        let servers = &config.mcp.unwrap().servers;
+        assert_eq!(servers.len(), 3);
+        match &servers["fetch"].transport {
+            ryvos_core::config::McpTransport::Stdio { command, args, env } => {
+                assert_eq!(command, "uvx");
+                assert_eq!(args, &["mcp-server-fetch"]);
+                assert_eq!(env["TOKEN"], "x=y");
+            }
+            other => panic!("unexpected transport: {:?}", other),
+        }
+
+        assert!(remove_server(&mut doc, "fetch"));
+        assert_eq!(doc.to_string(), CONFIG);
+        assert!(!remove_server(&mut doc, "fetch"));
+    }
+
+    #[test]
+    fn remove_spares_servers_sharing_a_prefix() {
+        let mut doc: DocumentMut = CONFIG.parse().unwrap();
+        assert!(remove_server(&mut doc, "git"));
+
+        let text = doc.to_string();
+        assert!(text.contains("[mcp.servers.github.transport]"));
+        assert!(text.contains("server-github"));
+        assert!(!text.contains("localhost:9000"));
+        assert!(text.contains("provider = \"anthropic\" # main model"));
+        assert!(text.contains("[gateway]"));
+    }
+
+    #[test]
+    fn existing_name_needs_replace() {
+        let mut doc: DocumentMut = CONFIG.parse().unwrap();
+        let sse = ServerTransport::Sse {
+            url: "https://example.com/sse",
+        };
+        let err = add_server(&mut doc, "github", &sse, false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(doc.to_string(), CONFIG);
+
+        add_server(&mut doc, "github", &sse, true).unwrap();
+        let text = doc.to_string();
+        assert!(text.contains("https://example.com/sse"));
+        assert!(!text.contains("server-github"));
+        assert!(text.contains("[gateway]"));
+    }
+
+    #[test]
+    fn add_to_empty_config() {
+        let mut doc = DocumentMut::new();
+        add_server(&mut doc, "fetch", &stdio(&[], &[]), false).unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "[mcp.servers.fetch]\nauto_connect = true\n\n\
+             [mcp.servers.fetch.transport]\ntype = \"stdio\"\ncommand = \"uvx\"\n"
+        );
+    }
+}
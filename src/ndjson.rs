@@ -0,0 +1,158 @@
+//! Agent events as newline-delimited JSON, for `ryvos run --events`.
+//!
+//! Each line is the same event frame the gateway WebSocket pushes (see
+//! `ryvos_gateway::to_server_event`), without a `seq`. Approval requests are answered by
+//! writing one JSON object per line to stdin with the params of the
+//! `approval.respond` RPC: `{"request_id": "...", "approved": true}`,
+//! optionally with a `reason` for a denial or a `confirm` word for a
+//! destructive command.
+
+use serde_json::{json, Value};
+
+use ryvos_core::security::ApprovalDecision;
+use ryvos_core::types::AgentEvent;
+use ryvos_gateway::{call_numbers, to_server_event, ServerEvent};
+
+/// One NDJSON line (without the newline) for `event`, attributing events
+/// that carry no session of their own to `session_id`. `None` for events
+/// the gateway does not forward.
+pub fn event_line(event: &AgentEvent, session_id: &str) -> Option<String> {
+    let frame = to_server_event(event, session_id)?;
+    serde_json::to_string(&frame).ok()
+}
+
+/// The line written when the printer fell `missed` events behind: a
+/// `resync` notice like the WebSocket's, with nothing replayed and the
+/// session listed as lost.
+pub fn lagged_line(missed: u64, session_id: &str) -> String {
+    let notice = ServerEvent::new("system".to_string(), "resync").with_data(json!({
+        "missed": missed,
+        "replayed": 0,
+        "lost_sessions": [session_id],
+    }));
+    serde_json::to_string(&notice).unwrap_or_default()
+}
+
+/// Parse one stdin line answering an approval request into the request id
+/// and the decision.
+pub fn parse_approval_answer(line: &str) -> Result<(String, ApprovalDecision), String> {
+    let params: Value =
+        serde_json::from_str(line).map_err(|e| format!("not a JSON object: {}", e))?;
+    let request_id = params["request_id"]
+        .as_str()
+        .filter(|id| !id.is_empty())
+        .ok_or("request_id is required")?;
+    let approved = params["approved"]
+        .as_bool()
+        .ok_or("approved must be true or false")?;
+    let decision = if approved {
+        match call_numbers(&params) {
+            Some(numbers) => ApprovalDecision::approve_calls(&numbers),
+            None => ApprovalDecision::approve_with(params["confirm"].as_str()),
+        }
+    } else {
+        ApprovalDecision::Denied {
+            reason: params["reason"].as_str().unwrap_or("denied").to_string(),
+        }
+    };
+    Ok((request_id.to_string(), decision))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use ryvos_agent::AgentRuntime;
+    use ryvos_core::event::EventBus;
+    use ryvos_core::traits::LlmClient;
+    use ryvos_core::types::SessionId;
+    use ryvos_test_utils::{test_config, InMemorySessionStore, MockLlmClient, MockTool};
+    use ryvos_tools::ToolRegistry;
+
+    #[tokio::test]
+    async fn run_emits_one_json_frame_per_line() {
+        let llm = MockLlmClient::new()
+            .with_tool_call("echo", r#"{"text": "hi"}"#)
+            .with_text_response("done");
+        let mut tools = ToolRegistry::new();
+        tools.register(MockTool::new("echo"));
+        let event_bus = Arc::new(EventBus::default());
+        let runtime = AgentRuntime::new(
+            test_config(),
+            Arc::new(llm) as Arc<dyn LlmClient>,
+            Arc::new(tokio::sync::RwLock::new(tools)),
+            Arc::new(InMemorySessionStore::new()),
+            event_bus.clone(),
+        );
+        let mut rx = event_bus.subscribe();
+        let session = SessionId::from_string("ndjson-session");
+        runtime.run(&session, "say hi").await.unwrap();
+
+        let mut out = String::new();
+        while let Ok(event) = rx.try_recv() {
+            if let Some(line) = event_line(&event, "ndjson-session") {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+
+        let frames: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(frames
+            .iter()
+            .all(|f| f["type"] == "event" && f["session_id"] == "ndjson-session"));
+        let kinds: Vec<&str> = frames
+            .iter()
+            .map(|f| f["event"]["kind"].as_str().unwrap())
+            .filter(|k| *k != "text_delta")
+            .collect();
+        assert_eq!(kinds.first(), Some(&"run_started"));
+        assert_eq!(kinds.last(), Some(&"run_complete"));
+        for kind in ["tool_start", "tool_end", "usage_update"] {
+            assert!(kinds.contains(&kind), "missing {} in {:?}", kind, kinds);
+        }
+        let tool_end = frames
+            .iter()
+            .find(|f| f["event"]["kind"] == "tool_end")
+            .unwrap();
+        assert_eq!(tool_end["event"]["tool"], "echo");
+    }
+
+    #[test]
+    fn lag_is_reported_as_a_resync() {
+        let frame: Value = serde_json::from_str(&lagged_line(12, "s1")).unwrap();
+        assert_eq!(frame["type"], "event");
+        assert_eq!(frame["event"]["kind"], "resync");
+        assert_eq!(frame["event"]["data"]["missed"], 12);
+        assert_eq!(frame["event"]["data"]["lost_sessions"][0], "s1");
+    }
+
+    #[test]
+    fn approval_answers_parse() {
+        let (id, decision) =
+            parse_approval_answer(r#"{"request_id":"r1","approved":true}"#).unwrap();
+        assert_eq!(id, "r1");
+        assert!(matches!(decision, ApprovalDecision::Approved));
+
+        let (_, decision) =
+            parse_approval_answer(r#"{"request_id":"r2","approved":false,"reason":"not now"}"#)
+                .unwrap();
+        assert!(matches!(decision, ApprovalDecision::Denied { ref reason } if reason == "not now"));
+
+        let (_, decision) =
+            parse_approval_answer(r#"{"request_id":"r4","approved":true,"confirm":"CONFIRM"}"#)
+                .unwrap();
+        assert!(decision.confirms("CONFIRM"));
+
+        let (_, decision) =
+            parse_approval_answer(r#"{"request_id":"r5","approved":true,"calls":[2]}"#).unwrap();
+        assert!(decision.approves_call(1) && !decision.approves_call(0));
+
+        assert!(parse_approval_answer(r#"{"approved":true}"#).is_err());
+        assert!(parse_approval_answer(r#"{"request_id":"r3"}"#).is_err());
+        assert!(parse_approval_answer("yes").is_err());
+    }
+}
@@ -51,6 +51,7 @@ pub fn configure(mode: &OnboardingMode) -> Result<ChannelsConfig> {
         discord,
         slack,
         whatsapp,
+        ..Default::default()
     })
 }
 
@@ -156,6 +157,7 @@ fn configure_discord() -> Result<DiscordConfig> {
         bot_token,
         dm_policy,
         allowed_users,
+        rich_formatting: false,
     })
 }
 
@@ -195,5 +197,6 @@ fn configure_slack() -> Result<SlackConfig> {
         app_token,
         dm_policy,
         allowed_users,
+        rich_formatting: false,
     })
 }
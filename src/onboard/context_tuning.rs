@@ -41,5 +41,6 @@ pub fn configure() -> Result<ContextConfig> {
         max_safety_lessons: 3,
         safety_filter_by_tools: true,
         protected_ttl: protected_ttl.parse().unwrap_or(20),
+        ..Default::default()
     })
 }
@@ -41,6 +41,7 @@ pub fn configure() -> Result<Option<CronConfig>> {
                 Some(channel)
             },
             goal: None,
+            working_dir: None,
         });
 
         let another = Confirm::new()
@@ -143,6 +143,8 @@ pub fn configure() -> Result<Option<GatewayConfig>> {
         password,
         api_keys,
         webhooks: None,
+        cors: None,
+        ..Default::default()
     }))
 }
 
@@ -38,5 +38,7 @@ pub fn configure() -> Result<GuardianConfig> {
         doom_loop_threshold: doom_loop_threshold.parse().unwrap_or(3),
         stall_timeout_secs: stall_timeout.parse().unwrap_or(120),
         token_warn_pct: 80,
+        cost_budget_usd: None,
+        repeated_answer_action: Default::default(),
     })
 }
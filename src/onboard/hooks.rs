@@ -31,6 +31,8 @@ pub fn configure() -> Result<Option<HooksConfig>> {
         on_tool_error: vec![],
         on_session_start: vec![],
         on_session_end: vec![],
+        on_tool_approval: vec![],
+        payload: Default::default(),
     };
 
     if hooks.is_empty() {
@@ -121,6 +121,7 @@ fn configure_server(template: &McpTemplate) -> Result<McpServerConfig> {
         transport,
         auto_connect: true,
         allow_sampling: false,
+        sampling_per_minute: 10,
         timeout_secs: 120,
         tier_override: None,
         headers: HashMap::new(),
@@ -160,6 +160,8 @@ async fn run_non_interactive(config_path: &Path, options: InitOptions) -> Result
             password: None,
             api_keys: vec![],
             webhooks: None,
+            cors: None,
+            ..Default::default()
         })
     } else {
         None
@@ -178,6 +180,7 @@ async fn run_non_interactive(config_path: &Path, options: InitOptions) -> Result
             bot_token: token,
             dm_policy: DmPolicy::Allowlist,
             allowed_users: vec![],
+            rich_formatting: false,
         });
 
         // Parse --channels flag (e.g., "telegram,discord")
@@ -199,6 +202,7 @@ async fn run_non_interactive(config_path: &Path, options: InitOptions) -> Result
                                 bot_token: token,
                                 dm_policy: DmPolicy::Allowlist,
                                 allowed_users: vec![],
+                                rich_formatting: false,
                             });
                         }
                     }
@@ -212,6 +216,7 @@ async fn run_non_interactive(config_path: &Path, options: InitOptions) -> Result
             discord,
             slack: None,
             whatsapp: None,
+            ..Default::default()
         }
     };
 
@@ -255,9 +260,13 @@ async fn run_non_interactive(config_path: &Path, options: InitOptions) -> Result
         model_id,
         api_key,
         base_url,
-        max_tokens: 8192,
+        max_tokens: None,
         temperature: 0.0,
+        top_p: None,
         thinking: Default::default(),
+        stop_sequences: vec![],
+        seed: None,
+        api_keys: vec![],
         retry: None,
         azure_resource: None,
         azure_deployment: None,
@@ -297,6 +306,9 @@ async fn run_non_interactive(config_path: &Path, options: InitOptions) -> Result
         jira: None,
         linear: None,
         integrations: Default::default(),
+        orchestrator: None,
+        tools: Default::default(),
+        storage: Default::default(),
     };
 
     if let Some(parent) = config_path.parent() {
@@ -725,9 +737,13 @@ async fn run_interactive(config_path: &Path) -> Result<()> {
         model_id: model.model_id,
         api_key: provider.api_key,
         base_url: provider.base_url,
-        max_tokens: 8192,
+        max_tokens: None,
         temperature: 0.0,
+        top_p: None,
         thinking: Default::default(),
+        stop_sequences: vec![],
+        seed: None,
+        api_keys: vec![],
         retry: None,
         azure_resource: None,
         azure_deployment: None,
@@ -771,6 +787,9 @@ async fn run_interactive(config_path: &Path) -> Result<()> {
         jira: None,
         linear: None,
         integrations: Default::default(),
+        orchestrator: None,
+        tools: Default::default(),
+        storage: Default::default(),
     };
 
     if let Some(parent) = config_path.parent() {
@@ -1069,8 +1088,7 @@ async fn launch_app(config: AppConfig, mode: LaunchMode) -> Result<()> {
     let workspace = config.workspace_dir();
     std::fs::create_dir_all(&workspace).ok();
 
-    let db_path = workspace.join("sessions.db");
-    let store = Arc::new(ryvos_memory::SqliteStore::open(&db_path)?);
+    let store = ryvos_memory::open_session_store(&config.storage, &workspace).await?;
     let mut tools = ryvos_tools::ToolRegistry::with_builtins();
     let event_bus = Arc::new(EventBus::default());
     let llm = ryvos_llm::create_client(&config.model);
@@ -69,9 +69,19 @@ pub fn configure() -> Result<SecurityConfig> {
         auto_approve_up_to: auto_approve,
         deny_above,
         approval_timeout_secs: 120,
+        approval_timeout_action: Default::default(),
+        approval_escalation_channel: None,
         tool_overrides: Default::default(),
+        safe_mode: false,
+        safe_mode_allow: vec![],
+        destructive_commands: None,
         dangerous_patterns,
         sub_agent_policy: None,
         pause_before: vec![],
+        rules: vec![],
+        approval_detail: false,
+        group_approvals: false,
+        injection_guard: None,
+        masked_env: vec![],
     })
 }
@@ -35,6 +35,7 @@ fn configure_brave() -> Result<Option<(String, McpServerConfig)>> {
             },
             auto_connect: true,
             allow_sampling: false,
+            sampling_per_minute: 10,
             timeout_secs: 120,
             tier_override: None,
             headers: std::collections::HashMap::new(),
@@ -57,6 +58,7 @@ fn configure_tavily() -> Result<Option<(String, McpServerConfig)>> {
             },
             auto_connect: true,
             allow_sampling: false,
+            sampling_per_minute: 10,
             timeout_secs: 120,
             tier_override: None,
             headers: std::collections::HashMap::new(),
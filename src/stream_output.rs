@@ -0,0 +1,196 @@
+//! Buffered printing of streamed reply text for `ryvos run` and the REPL.
+//!
+//! Writing and flushing every delta costs a syscall per token on fast
+//! streams. [`StreamWriter`] collects deltas and writes them out at a line
+//! break or once the flush interval has passed, and in raw mode holds the
+//! whole reply until the run ends. The caller waits on
+//! [`StreamWriter::flush_deadline`] alongside the event stream, so a
+//! partial line is written on time even when no further delta arrives.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// How `run_once` prints the reply.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamOptions {
+    /// Print the reply only once the run has finished.
+    pub raw: bool,
+    /// Longest a partial line is held back while streaming.
+    pub flush_interval: Duration,
+    /// Print each agent event as an NDJSON line instead of the reply.
+    pub events: bool,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            raw: false,
+            flush_interval: Duration::from_millis(50),
+            events: false,
+        }
+    }
+}
+
+pub struct StreamWriter<W: Write> {
+    out: W,
+    options: StreamOptions,
+    pending: String,
+    last_flush: Instant,
+    /// Last character written to `out`, to end the reply on a newline.
+    last_char: Option<char>,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(out: W, options: StreamOptions, now: Instant) -> Self {
+        Self {
+            out,
+            options,
+            pending: String::new(),
+            last_flush: now,
+            last_char: None,
+        }
+    }
+
+    /// Add a delta, writing out what is buffered if it completes a line or
+    /// the interval has passed.
+    pub fn push(&mut self, text: &str, now: Instant) -> io::Result<()> {
+        self.pending.push_str(text);
+        if self.options.raw {
+            return Ok(());
+        }
+        if text.contains('\n') || now.duration_since(self.last_flush) >= self.options.flush_interval
+        {
+            self.flush(now)?;
+        }
+        Ok(())
+    }
+
+    /// When the partial line held back must be written out, if there is
+    /// one and the reply is streaming.
+    pub fn flush_deadline(&self) -> Option<Instant> {
+        (!self.options.raw && !self.pending.is_empty())
+            .then(|| self.last_flush + self.options.flush_interval)
+    }
+
+    /// Write out what is buffered, unless the whole reply is being held.
+    /// Called before other output (tool lines, prompts) so it stays in order.
+    pub fn flush_pending(&mut self, now: Instant) -> io::Result<()> {
+        if self.options.raw {
+            return Ok(());
+        }
+        self.flush(now)
+    }
+
+    /// Write out everything and end the reply with a newline.
+    pub fn finish(&mut self, now: Instant) -> io::Result<()> {
+        self.flush(now)?;
+        if self.last_char.is_some_and(|c| c != '\n') {
+            self.out.write_all(b"\n")?;
+            self.last_char = Some('\n');
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, now: Instant) -> io::Result<()> {
+        self.last_flush = now;
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.out.write_all(self.pending.as_bytes())?;
+        self.last_char = self.pending.chars().last();
+        self.pending.clear();
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output that records each flush as one chunk.
+    #[derive(Default)]
+    struct Chunks {
+        written: Vec<u8>,
+        flushed: Vec<String>,
+    }
+
+    impl Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            let chunk = String::from_utf8(std::mem::take(&mut self.written)).unwrap();
+            self.flushed.push(chunk);
+            Ok(())
+        }
+    }
+
+    fn ms(start: Instant, n: u64) -> Instant {
+        start + Duration::from_millis(n)
+    }
+
+    #[test]
+    fn deltas_are_coalesced_until_newline_or_interval() {
+        let start = Instant::now();
+        let mut w = StreamWriter::new(Chunks::default(), StreamOptions::default(), start);
+        w.push("Hel", ms(start, 1)).unwrap();
+        w.push("lo", ms(start, 2)).unwrap();
+        w.push(" world\nNext", ms(start, 3)).unwrap();
+        w.push(" line", ms(start, 10)).unwrap();
+        w.push(" goes", ms(start, 60)).unwrap();
+        w.push(" on", ms(start, 70)).unwrap();
+        w.finish(ms(start, 80)).unwrap();
+
+        assert_eq!(
+            w.out.flushed,
+            ["Hello world\nNext", " line goes", " on", "\n"]
+        );
+    }
+
+    #[test]
+    fn raw_mode_writes_once_at_the_end() {
+        let start = Instant::now();
+        let options = StreamOptions {
+            raw: true,
+            ..Default::default()
+        };
+        let mut w = StreamWriter::new(Chunks::default(), options, start);
+        w.push("one\n", ms(start, 1)).unwrap();
+        w.flush_pending(ms(start, 500)).unwrap();
+        w.push("two\n", ms(start, 900)).unwrap();
+        assert!(w.out.flushed.is_empty());
+
+        w.finish(ms(start, 1000)).unwrap();
+        assert_eq!(w.out.flushed, ["one\ntwo\n"]);
+    }
+
+    #[test]
+    fn a_held_partial_line_has_a_deadline() {
+        let start = Instant::now();
+        let mut w = StreamWriter::new(Chunks::default(), StreamOptions::default(), start);
+        assert_eq!(w.flush_deadline(), None);
+        w.push("Hel", ms(start, 10)).unwrap();
+        assert_eq!(w.flush_deadline(), Some(ms(start, 50)));
+        w.push("lo\n", ms(start, 20)).unwrap();
+        assert_eq!(w.flush_deadline(), None);
+
+        let raw = StreamOptions {
+            raw: true,
+            ..Default::default()
+        };
+        let mut w = StreamWriter::new(Chunks::default(), raw, start);
+        w.push("held", ms(start, 1)).unwrap();
+        assert_eq!(w.flush_deadline(), None);
+    }
+
+    #[test]
+    fn finish_without_output_prints_nothing() {
+        let start = Instant::now();
+        let mut w = StreamWriter::new(Chunks::default(), StreamOptions::default(), start);
+        w.finish(start).unwrap();
+        assert!(w.out.flushed.is_empty());
+    }
+}
@@ -58,7 +58,7 @@ on_response = []
     assert_eq!(config.model.provider, "anthropic");
     assert_eq!(config.model.model_id, "claude-sonnet-4-20250514");
     assert_eq!(config.model.api_key, Some("sk-test-key".to_string()));
-    assert_eq!(config.model.max_tokens, 4096);
+    assert_eq!(config.model.max_tokens, Some(4096));
 
     let gw = config.gateway.expect("gateway present");
     assert_eq!(gw.bind, "0.0.0.0:9999");